@@ -0,0 +1,59 @@
+// Closest-hit throughput on the book-cover scene, with spheres stored inline in the list, and
+// so batched into a `SphereSoA`, against the same spheres behind `Arc<dyn Hittable>`.
+// `cargo bench --bench random_scene`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::camera::Camera;
+use rtt::generator::SceneGenerator;
+use rtt::hittable::{Hittable, HittableList, Primitive};
+use rtt::interval::Interval;
+use rtt::vec3::{Point3, Vec3};
+
+const SEED: u64 = 42;
+const RAYS: usize = 200_000;
+const RUNS: usize = 5;
+
+// Fastest of `RUNS` passes over the same rays, in million rays per second.
+fn throughput(world: &HittableList, camera: &Camera) -> f64 {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let start = Instant::now();
+        for i in 0..RAYS {
+            let (s, t) = ((i % 640) as f64 / 640.0, (i / 640 % 360) as f64 / 360.0);
+            let ray = camera.get_ray(s, t, &mut rng).unwrap();
+            black_box(world.hit(&ray, Interval::new(1e-3, f64::INFINITY)));
+        }
+        best = best.min(start.elapsed());
+    }
+    RAYS as f64 / best.as_secs_f64() / 1e6
+}
+
+fn main() {
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        20.0,
+        16.0 / 9.0,
+        0.1,
+        10.0,
+    );
+    let inline = SceneGenerator::new(SEED).generate();
+    let mut boxed = SceneGenerator::new(SEED).generate();
+    for o in boxed.iter_mut() {
+        *o = Primitive::Dyn(o.clone().into_arc());
+    }
+
+    println!("random_scene, {} objects:", inline.len());
+    let (a, b) = (throughput(&boxed, &camera), throughput(&inline, &camera));
+    println!("  Arc<dyn Hittable>  {a:.2} Mrays/s");
+    println!(
+        "  SphereSoA          {b:.2} Mrays/s ({:+.0}%)",
+        100.0 * (b / a - 1.0)
+    );
+}
@@ -0,0 +1,33 @@
+// Compiles the GPU backend's compute shader when the `gpu` feature is on. Needs `glslc` from
+// the Vulkan SDK, or the compiler named by `GLSLC`; without one the build still succeeds and
+// `GpuScene::new` reports that the shader is missing.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=shaders/intersect.comp");
+    println!("cargo:rerun-if-env-changed=GLSLC");
+    if std::env::var_os("CARGO_FEATURE_GPU").is_none() {
+        return;
+    }
+    let out = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"))
+        .join("intersect.spv");
+    let glslc = std::env::var("GLSLC").unwrap_or_else(|_| "glslc".to_string());
+    let status = Command::new(&glslc)
+        .args([
+            "--target-env=vulkan1.2",
+            "-O",
+            "shaders/intersect.comp",
+            "-o",
+        ])
+        .arg(&out)
+        .status();
+    if !matches!(status, Ok(s) if s.success()) {
+        println!(
+            "cargo:warning=could not compile shaders/intersect.comp with {glslc}; \
+             the GPU backend will be unavailable"
+        );
+        std::fs::write(&out, []).expect("write placeholder shader");
+    }
+}
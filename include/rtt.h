@@ -0,0 +1,63 @@
+/* C interface to the rtt renderer. Build with
+ * `cargo rustc --release --lib --features capi --crate-type cdylib` (or `staticlib`)
+ * and link against librtt.{so,dylib,a}. */
+#ifndef RTT_H
+#define RTT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RTT_OK 0
+#define RTT_ERR_NULL (-1)
+#define RTT_ERR_INVALID (-2)
+#define RTT_ERR_BUFFER (-3)
+#define RTT_ERR_RENDER (-4)
+/* Too many materials alive at once to add another. */
+#define RTT_ERR_FULL (-5)
+
+typedef enum RttMaterialKind {
+    RTT_LAMBERTIAN = 0,
+    RTT_METAL = 1,
+    RTT_DIELECTRIC = 2,
+    RTT_DIFFUSE_LIGHT = 3,
+} RttMaterialKind;
+
+/* `kind` is an RttMaterialKind; others are rejected with RTT_ERR_INVALID. `param` is the
+ * fuzz for metals and the index of refraction for dielectrics. */
+typedef struct RttMaterial {
+    uint32_t kind;
+    double color[3];
+    double param;
+} RttMaterial;
+
+typedef struct RttScene RttScene;
+
+/* Called with (scanlines done, total scanlines), possibly from several threads at once. */
+typedef void (*RttProgressFn)(uint32_t done, uint32_t total, void *user_data);
+
+/* Every call checks its arguments: null pointers give RTT_ERR_NULL, and non-finite or out of
+ * range values RTT_ERR_INVALID, leaving the scene unchanged. */
+RttScene *rtt_scene_new(void);
+void rtt_scene_free(RttScene *scene);
+
+int32_t rtt_scene_add_sphere(RttScene *scene, const double center[3], double radius,
+                             const RttMaterial *material);
+
+int32_t rtt_scene_set_camera(RttScene *scene, const double look_from[3],
+                             const double look_at[3], const double vup[3], double vfov,
+                             double aperture, double focus_dist);
+
+/* Renders `width * height` RGBA8 pixels, top row first, into `out` (at least
+ * `width * height * 4` bytes). `progress` may be NULL. */
+int32_t rtt_render(const RttScene *scene, uint32_t width, uint32_t height, uint32_t samples,
+                   uint8_t *out, size_t out_len, RttProgressFn progress, void *user_data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RTT_H */
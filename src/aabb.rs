@@ -0,0 +1,143 @@
+use crate::interval::Interval;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use serde::{Deserialize, Serialize};
+
+// Bound on the relative rounding error of three chained floating point operations.
+const GAMMA_3: f64 = 3.0 * f64::EPSILON * 0.5 / (1.0 - 3.0 * f64::EPSILON * 0.5);
+
+// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    #[inline]
+    pub const fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    #[inline]
+    pub fn surrounding_box(a: Self, b: Self) -> Self {
+        Self {
+            min: Point3::new(
+                a.min.x.min(b.min.x),
+                a.min.y.min(b.min.y),
+                a.min.z.min(b.min.z),
+            ),
+            max: Point3::new(
+                a.max.x.max(b.max.x),
+                a.max.y.max(b.max.y),
+                a.max.z.max(b.max.z),
+            ),
+        }
+    }
+
+    // Inverted box that any union or point expansion replaces.
+    pub const EMPTY: Self = Self {
+        min: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        max: Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+    };
+
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::EMPTY.expand(a).expand(b)
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    #[inline]
+    pub fn expand(self, p: Point3) -> Self {
+        Self::surrounding_box(self, Self::new(p, p))
+    }
+
+    // Grows the box by `delta` on every side, e.g. to give flat primitives some thickness.
+    #[inline]
+    pub fn pad(self, delta: f64) -> Self {
+        let d = Vec3::new(delta, delta, delta);
+        Self::new(self.min - d, self.max + d)
+    }
+
+    #[inline]
+    pub fn centroid(self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    #[inline]
+    pub fn extent(self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let d = self.extent();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    // 0, 1 or 2 for x, y or z.
+    pub fn longest_axis(self) -> usize {
+        let d = self.extent();
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    #[inline]
+    pub fn axis(self, axis: usize) -> (f64, f64) {
+        match axis {
+            0 => (self.min.x, self.max.x),
+            1 => (self.min.y, self.max.y),
+            _ => (self.min.z, self.max.z),
+        }
+    }
+
+    // Slab test. Zero direction components give infinite slab distances, and the NaNs from
+    // 0 * inf are discarded by `f64::max`/`f64::min`, so axis-parallel rays work. The far
+    // distance is padded slightly so rounding never misses a grazing hit.
+    #[inline]
+    pub fn hit(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.entry(r, ray_t).is_some()
+    }
+
+    // Where the ray enters the box within `ray_t`, clamped to `ray_t.min` if it starts
+    // inside; None if it misses.
+    pub fn entry(&self, r: &Ray, ray_t: Interval) -> Option<f64> {
+        let o = r.origin();
+        let d = r.direction();
+        let (mut t_min, mut t_max) = (ray_t.min, ray_t.max);
+        for (o, d, (lo, hi)) in [
+            (o.x, d.x, self.axis(0)),
+            (o.y, d.y, self.axis(1)),
+            (o.z, d.z, self.axis(2)),
+        ] {
+            let inv_d = 1.0 / d;
+            let mut t0 = (lo - o) * inv_d;
+            let mut t1 = (hi - o) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t1 *= 1.0 + 2.0 * GAMMA_3;
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    #[inline]
+    pub fn diagonal(self) -> f64 {
+        (self.max - self.min).length()
+    }
+}
@@ -0,0 +1,748 @@
+use crate::camera::Camera;
+use crate::color::luminance;
+use crate::error::{Error, Result};
+use crate::film::{Film, FilmTile, Pixel};
+use crate::hittable::Hittable;
+use crate::material::MaterialId;
+use crate::render::PathSample;
+use crate::vec3::Color;
+use image::{Rgba, Rgba32FImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// Auxiliary outputs written next to the beauty image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Aov {
+    // Camera-space depth of the primary hit.
+    Depth,
+    // World-space position of the primary hit.
+    Position,
+    // Index of the primary hit object in the scene list.
+    ObjectId,
+    // Index of the primary hit material in scene order.
+    MaterialId,
+    // One beauty contribution per light group, plus the background and ungrouped emitters.
+    LightGroups,
+    // One beauty contribution per `PathClass`, to rebalance direct and indirect light or
+    // reflections in compositing.
+    PathClasses,
+    // Reflectance of the primary hit where the camera ray scattered diffusely.
+    Albedo,
+    // Diffuse light divided by `Albedo`, over the samples `Albedo` covers: the illumination
+    // alone, smooth across textures, for denoisers. The beauty is `Albedo` times `Diffuse`
+    // times coverage, plus `Specular`.
+    Diffuse,
+    // Everything in the beauty that isn't in `Diffuse`: mirrors, glass and emitters seen
+    // directly. Averages over every sample.
+    Specular,
+    // Variance of each pixel's mean radiance per channel, estimated from its samples.
+    Variance,
+    // Standard error of each pixel's mean over the mean itself, by luminance: where the image
+    // is still noisy. Halves with four times the samples.
+    RelativeError,
+    // The beauty without `RenderSettings::clamp`, accumulated from the same samples, to see how
+    // much light clamping takes away.
+    Unclamped,
+}
+
+impl Aov {
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::LightGroups => "light",
+            Aov::PathClasses => "path",
+            Aov::Albedo => "albedo",
+            Aov::Diffuse => "diffuse",
+            Aov::Specular => "specular",
+            Aov::Variance => "variance",
+            Aov::RelativeError => "relative_error",
+            Aov::Unclamped => "unclamped",
+        }
+    }
+
+    #[inline]
+    fn is_id(self) -> bool {
+        matches!(self, Aov::ObjectId | Aov::MaterialId)
+    }
+
+    // Accumulated from the sample's light rather than from the primary hit.
+    #[inline]
+    fn is_lighting(self) -> bool {
+        matches!(self, Aov::Albedo | Aov::Diffuse | Aov::Specular)
+    }
+
+    #[inline]
+    fn is_error(self) -> bool {
+        matches!(self, Aov::Variance | Aov::RelativeError)
+    }
+}
+
+// Stable material IDs: the order in which materials first appear when walking the scene.
+pub struct MaterialIds {
+    ids: HashMap<MaterialId, u32>,
+}
+
+impl MaterialIds {
+    pub fn new(world: &dyn Hittable) -> Self {
+        let mut materials = Vec::new();
+        world.materials(&mut materials);
+
+        let mut ids = HashMap::new();
+        for m in materials {
+            let next = ids.len() as u32;
+            ids.entry(m).or_insert(next);
+        }
+        Self { ids }
+    }
+
+    // Materials not seen during construction share the ID `u32::MAX`.
+    #[inline]
+    pub fn id(&self, material: MaterialId) -> u32 {
+        self.ids.get(&material).copied().unwrap_or(u32::MAX)
+    }
+}
+
+// Light paths told apart by the camera ray's first hit, in the spirit of light path
+// expressions: whether it scattered diffusely (with a density, including volumes), off a
+// mirror-like surface or through one, and whether the light came straight from an emitter or
+// the background after that one bounce. Together with `Emission` they sum to the beauty,
+// before vignetting and fog's in-scattering.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathClass {
+    // Emitters and the background seen directly.
+    Emission,
+    DirectDiffuse,
+    IndirectDiffuse,
+    DirectSpecular,
+    IndirectSpecular,
+    // Light through refracting surfaces, direct and indirect.
+    Transmission,
+}
+
+impl PathClass {
+    pub const ALL: [PathClass; 6] = [
+        PathClass::Emission,
+        PathClass::DirectDiffuse,
+        PathClass::IndirectDiffuse,
+        PathClass::DirectSpecular,
+        PathClass::IndirectSpecular,
+        PathClass::Transmission,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PathClass::Emission => "emission",
+            PathClass::DirectDiffuse => "direct_diffuse",
+            PathClass::IndirectDiffuse => "indirect_diffuse",
+            PathClass::DirectSpecular => "direct_specular",
+            PathClass::IndirectSpecular => "indirect_specular",
+            PathClass::Transmission => "transmission",
+        }
+    }
+}
+
+pub const BACKGROUND_GROUP: &str = "background";
+pub const DEFAULT_LIGHT_GROUP: &str = "emission";
+
+// Always the first two groups, so no emitter may be grouped under either name.
+const RESERVED_GROUPS: usize = 2;
+
+fn is_reserved_group(name: &str) -> bool {
+    name == BACKGROUND_GROUP || name == DEFAULT_LIGHT_GROUP
+}
+
+// Light group names in scene order. The background and ungrouped emitters always get a group.
+// Emitters grouped under a reserved name are left out of every group; scenes reject them.
+pub fn light_group_names(world: &dyn Hittable) -> Vec<Arc<str>> {
+    let mut materials = Vec::new();
+    world.materials(&mut materials);
+
+    let mut names: Vec<Arc<str>> =
+        vec![Arc::from(BACKGROUND_GROUP), Arc::from(DEFAULT_LIGHT_GROUP)];
+    for m in &materials {
+        if let Some(group) = m.get().light_group() {
+            if !is_reserved_group(&group) && !names.contains(&group) {
+                names.push(group);
+            }
+        }
+    }
+    names
+}
+
+// Errors if an emitter in `world` is grouped under the background's or ungrouped emitters' name.
+pub fn check_light_groups(world: &dyn Hittable) -> Result<()> {
+    let mut materials = Vec::new();
+    world.materials(&mut materials);
+    match materials
+        .iter()
+        .filter_map(|m| m.get().light_group())
+        .find(|g| is_reserved_group(g))
+    {
+        Some(group) => Err(Error::Scene(format!(
+            "light group name \"{group}\" is reserved"
+        ))),
+        None => Ok(()),
+    }
+}
+
+// (id, weight) pairs seen by one pixel.
+type IdCoverage = Vec<(u32, f64)>;
+
+// Per-pixel ID coverage, kept sparse since most pixels see one or two IDs.
+pub struct IdFilm {
+    width: u32,
+    height: u32,
+    pixels: Mutex<Vec<IdCoverage>>,
+}
+
+impl IdFilm {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: Mutex::new(vec![Vec::new(); width as usize * height as usize]),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Vec<IdCoverage>>> {
+        self.pixels.lock().map_err(|_| Error::Poisoned("id film"))
+    }
+
+    pub fn clear_pixel(&self, x: u32, y: u32) -> Result<()> {
+        self.lock()?[y as usize * self.width as usize + x as usize].clear();
+        Ok(())
+    }
+
+    pub fn tile(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> IdTile {
+        let x1 = x1.min(self.width);
+        let y1 = y1.min(self.height);
+        IdTile {
+            x0,
+            y0,
+            x1,
+            pixels: vec![Vec::new(); (x1 - x0) as usize * (y1 - y0) as usize],
+        }
+    }
+
+    pub fn merge_tile(&self, tile: IdTile) -> Result<()> {
+        let mut pixels = self.lock()?;
+        let tile_width = tile.x1 - tile.x0;
+        for (i, src) in tile.pixels.into_iter().enumerate() {
+            let x = tile.x0 + i as u32 % tile_width;
+            let y = tile.y0 + i as u32 / tile_width;
+            let dst = &mut pixels[y as usize * self.width as usize + x as usize];
+            for (id, w) in src {
+                add_id(dst, id, w);
+            }
+        }
+        Ok(())
+    }
+
+    // Cryptomatte-style ranks: RGBA = (id0, coverage0, id1, coverage1), most coverage first.
+    pub fn develop(&self, total: &Film) -> Result<Rgba32FImage> {
+        let pixels = self.lock()?;
+        let mut img = Rgba32FImage::new(self.width, self.height);
+        for (i, px) in img.pixels_mut().enumerate() {
+            let x = i as u32 % self.width;
+            let y = i as u32 / self.width;
+            let total_weight = total.pixel(x, y)?.weight_sum;
+            if total_weight <= 0.0 {
+                continue;
+            }
+
+            let mut ranked = pixels[i].clone();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+            let rank = |n: usize| {
+                ranked.get(n).map_or((0.0, 0.0), |&(id, w)| {
+                    (id as f32, (w / total_weight) as f32)
+                })
+            };
+            let (id0, c0) = rank(0);
+            let (id1, c1) = rank(1);
+            *px = Rgba([id0, c0, id1, c1]);
+        }
+        Ok(img)
+    }
+}
+
+pub struct IdTile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    pixels: Vec<IdCoverage>,
+}
+
+impl IdTile {
+    pub fn add_sample(&mut self, x: u32, y: u32, id: u32, weight: f64) {
+        let idx = (y - self.y0) as usize * (self.x1 - self.x0) as usize + (x - self.x0) as usize;
+        add_id(&mut self.pixels[idx], id, weight);
+    }
+}
+
+#[inline]
+fn add_id(ids: &mut IdCoverage, id: u32, weight: f64) {
+    match ids.iter_mut().find(|(i, _)| *i == id) {
+        Some((_, w)) => *w += weight,
+        None => ids.push((id, weight)),
+    }
+}
+
+// Relative error is measured against at least this luminance, so black pixels don't blow up.
+const RELATIVE_ERROR_FLOOR: f64 = 1e-3;
+
+// Variance of a pixel's mean per channel and its relative error, from the mean radiance and
+// mean squared radiance of its samples; None with fewer than two samples.
+fn pixel_error(mean: &Pixel, squares: &Pixel) -> Option<(Color, f64)> {
+    let n = mean.weight_sum;
+    if n < 2.0 {
+        return None;
+    }
+    let m = mean.resolve(0.0);
+    let spread = squares.resolve(0.0) - m * m;
+    let variance = Color::new(
+        spread.r().max(0.0),
+        spread.g().max(0.0),
+        spread.b().max(0.0),
+    ) / (n - 1.0);
+    let relative = luminance(variance).sqrt() / luminance(m).max(RELATIVE_ERROR_FLOOR);
+    Some((variance, relative))
+}
+
+// Pixels without an estimate are left transparent.
+fn develop_error(aov: Aov, mean: &Film, squares: &Film) -> Result<Rgba32FImage> {
+    let mut img = Rgba32FImage::new(mean.width(), mean.height());
+    for (i, px) in img.pixels_mut().enumerate() {
+        let (x, y) = (i as u32 % mean.width(), i as u32 / mean.width());
+        let Some((variance, relative)) = pixel_error(&mean.pixel(x, y)?, &squares.pixel(x, y)?)
+        else {
+            continue;
+        };
+        let value = if aov == Aov::RelativeError {
+            Color::new(relative, relative, relative)
+        } else {
+            variance
+        };
+        *px = Rgba([value.r() as f32, value.g() as f32, value.b() as f32, 1.0]);
+    }
+    Ok(img)
+}
+
+enum AovBuffer {
+    Film(Film),
+    Ids(IdFilm),
+    Groups(Vec<Film>),
+    // One per `PathClass::ALL`.
+    Classes(Vec<Film>),
+    // Sample radiance and its square.
+    Moments(Film, Film),
+}
+
+enum AovTile {
+    Film(FilmTile),
+    Ids(IdTile),
+    Groups(Vec<FilmTile>),
+    Classes(Vec<FilmTile>),
+    Moments(FilmTile, FilmTile),
+}
+
+// One buffer per enabled AOV. Only samples that produce a value are accumulated, so background
+// pixels stay empty and edge pixels average over the covered samples only.
+pub struct AovSet {
+    buffers: Vec<(Aov, AovBuffer)>,
+    material_ids: MaterialIds,
+    group_names: Vec<Arc<str>>,
+}
+
+impl AovSet {
+    pub fn new(aovs: &[Aov], world: &dyn Hittable, width: u32, height: u32) -> Self {
+        let group_names = light_group_names(world);
+        let buffers = aovs
+            .iter()
+            .map(|&a| {
+                let buffer = if a == Aov::LightGroups {
+                    AovBuffer::Groups(
+                        group_names
+                            .iter()
+                            .map(|_| Film::new(width, height))
+                            .collect(),
+                    )
+                } else if a == Aov::PathClasses {
+                    AovBuffer::Classes(
+                        PathClass::ALL
+                            .iter()
+                            .map(|_| Film::new(width, height))
+                            .collect(),
+                    )
+                } else if a.is_id() {
+                    AovBuffer::Ids(IdFilm::new(width, height))
+                } else if a.is_error() {
+                    AovBuffer::Moments(Film::new(width, height), Film::new(width, height))
+                } else {
+                    AovBuffer::Film(Film::new(width, height))
+                };
+                (a, buffer)
+            })
+            .collect();
+
+        Self {
+            buffers,
+            material_ids: MaterialIds::new(world),
+            group_names,
+        }
+    }
+
+    // Roughly what `new` and then `save_multilayer` would allocate for `aovs`, without
+    // allocating it: the buffers, plus each layer developed and copied into the EXR. ID
+    // coverage is counted as one ID per pixel, though edges hold more.
+    pub fn estimate_bytes(aovs: &[Aov], world: &dyn Hittable, width: u32, height: u32) -> usize {
+        let groups = light_group_names(world).len();
+        let per_pixel: usize = aovs
+            .iter()
+            .map(|&a| {
+                let (buffer, layers) = if a == Aov::LightGroups {
+                    (groups * size_of::<Pixel>(), groups)
+                } else if a == Aov::PathClasses {
+                    let n = PathClass::ALL.len();
+                    (n * size_of::<Pixel>(), n)
+                } else if a.is_id() {
+                    (size_of::<IdCoverage>() + size_of::<(u32, f64)>(), 1)
+                } else if a.is_error() {
+                    (2 * size_of::<Pixel>(), 1)
+                } else {
+                    (size_of::<Pixel>(), 1)
+                };
+                buffer + layers * 2 * size_of::<Rgba<f32>>()
+            })
+            .sum();
+        per_pixel * width as usize * height as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    // Enabled AOVs, in the order they were requested.
+    pub fn aovs(&self) -> Vec<Aov> {
+        self.buffers.iter().map(|(a, _)| *a).collect()
+    }
+
+    pub fn tile(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> AovTiles<'_> {
+        AovTiles {
+            tiles: self
+                .buffers
+                .iter()
+                .map(|(a, b)| {
+                    let tile = match b {
+                        AovBuffer::Film(f) => AovTile::Film(f.tile(x0, y0, x1, y1)),
+                        AovBuffer::Ids(f) => AovTile::Ids(f.tile(x0, y0, x1, y1)),
+                        AovBuffer::Groups(g) => {
+                            AovTile::Groups(g.iter().map(|f| f.tile(x0, y0, x1, y1)).collect())
+                        }
+                        AovBuffer::Classes(c) => {
+                            AovTile::Classes(c.iter().map(|f| f.tile(x0, y0, x1, y1)).collect())
+                        }
+                        AovBuffer::Moments(m, s) => {
+                            AovTile::Moments(m.tile(x0, y0, x1, y1), s.tile(x0, y0, x1, y1))
+                        }
+                    };
+                    (*a, tile)
+                })
+                .collect(),
+            material_ids: &self.material_ids,
+            group_names: &self.group_names,
+        }
+    }
+
+    pub fn merge_tile(&self, tiles: AovTiles) -> Result<()> {
+        for ((_, buffer), (_, tile)) in self.buffers.iter().zip(tiles.tiles) {
+            match (buffer, tile) {
+                (AovBuffer::Film(f), AovTile::Film(t)) => f.merge_tile(t)?,
+                (AovBuffer::Ids(f), AovTile::Ids(t)) => f.merge_tile(t)?,
+                (AovBuffer::Groups(g), AovTile::Groups(t))
+                | (AovBuffer::Classes(g), AovTile::Classes(t)) => {
+                    for (f, t) in g.iter().zip(t) {
+                        f.merge_tile(t)?;
+                    }
+                }
+                (AovBuffer::Moments(m, s), AovTile::Moments(tm, ts)) => {
+                    m.merge_tile(tm)?;
+                    s.merge_tile(ts)?;
+                }
+                _ => unreachable!("AOV tile does not match its buffer"),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn film(&self, aov: Aov) -> Option<&Film> {
+        self.buffers.iter().find_map(|(a, b)| match b {
+            AovBuffer::Film(f) if *a == aov => Some(f),
+            _ => None,
+        })
+    }
+
+    // Films for each light group, in scene order.
+    pub fn light_groups(&self) -> impl Iterator<Item = (&str, &Film)> {
+        let films = self.buffers.iter().find_map(|(_, b)| match b {
+            AovBuffer::Groups(g) => Some(g.as_slice()),
+            _ => None,
+        });
+        self.group_names
+            .iter()
+            .map(|name| name.as_ref())
+            .zip(films.unwrap_or(&[]))
+    }
+
+    // Films for each path class, if `PathClasses` is enabled.
+    pub fn path_classes(&self) -> impl Iterator<Item = (PathClass, &Film)> {
+        let films = self.buffers.iter().find_map(|(_, b)| match b {
+            AovBuffer::Classes(c) => Some(c.as_slice()),
+            _ => None,
+        });
+        PathClass::ALL.into_iter().zip(films.unwrap_or(&[]))
+    }
+
+    // Relative error of pixel (x, y), if `Variance` or `RelativeError` is enabled and the pixel
+    // has at least two samples.
+    pub fn relative_error(&self, x: u32, y: u32) -> Result<Option<f64>> {
+        for (_, buffer) in &self.buffers {
+            if let AovBuffer::Moments(m, s) = buffer {
+                return Ok(pixel_error(&m.pixel(x, y)?, &s.pixel(x, y)?).map(|(_, e)| e));
+            }
+        }
+        Ok(None)
+    }
+
+    // Discards what every AOV accumulated for pixel (x, y).
+    pub fn clear_pixel(&self, x: u32, y: u32) -> Result<()> {
+        for (_, buffer) in &self.buffers {
+            match buffer {
+                AovBuffer::Film(f) => f.clear_pixel(x, y)?,
+                AovBuffer::Ids(f) => f.clear_pixel(x, y)?,
+                AovBuffer::Groups(g) | AovBuffer::Classes(g) => {
+                    for f in g {
+                        f.clear_pixel(x, y)?;
+                    }
+                }
+                AovBuffer::Moments(m, s) => {
+                    m.clear_pixel(x, y)?;
+                    s.clear_pixel(x, y)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The image `save` writes for `aov`, or None if it isn't enabled or is split by light
+    // group or path class.
+    pub fn develop(&self, aov: Aov, beauty: &Film) -> Result<Option<Rgba32FImage>> {
+        let Some((_, buffer)) = self.buffers.iter().find(|(a, _)| *a == aov) else {
+            return Ok(None);
+        };
+        Ok(match buffer {
+            AovBuffer::Film(f) => Some(f.develop_coverage(beauty)?),
+            AovBuffer::Ids(f) => Some(f.develop(beauty)?),
+            AovBuffer::Moments(m, s) => Some(develop_error(aov, m, s)?),
+            AovBuffer::Groups(_) | AovBuffer::Classes(_) => None,
+        })
+    }
+
+    // Every image `save` writes, named by file stem: `<name>` per AOV, `light_<group>` per
+    // light group and `path_<class>` per path class.
+    // Alpha holds pixel coverage for value AOVs.
+    pub fn layers(&self, beauty: &Film) -> Result<Vec<(String, Rgba32FImage)>> {
+        let mut layers = Vec::new();
+        for (aov, buffer) in &self.buffers {
+            match buffer {
+                AovBuffer::Film(_) | AovBuffer::Ids(_) | AovBuffer::Moments(..) => {
+                    let img = self.develop(*aov, beauty)?.expect("enabled AOV");
+                    layers.push((aov.name().to_string(), img));
+                }
+                AovBuffer::Groups(g) => {
+                    for (name, f) in self.group_names.iter().zip(g) {
+                        let name = format!("{}_{}", aov.name(), name);
+                        layers.push((name, f.develop_coverage(beauty)?));
+                    }
+                }
+                AovBuffer::Classes(c) => {
+                    for (class, f) in PathClass::ALL.iter().zip(c) {
+                        let name = format!("{}_{}", aov.name(), class.name());
+                        layers.push((name, f.develop_coverage(beauty)?));
+                    }
+                }
+            }
+        }
+        Ok(layers)
+    }
+
+    // Writes `<layer>.exr` into `dir` for each of `layers`.
+    pub fn save(&self, beauty: &Film, dir: &Path) -> Result<()> {
+        for (name, img) in self.layers(beauty)? {
+            let path = dir.join(format!("{name}.exr"));
+            img.save(&path).map_err(Error::image(&path))?;
+        }
+        Ok(())
+    }
+
+    // Writes the beauty and every layer into one EXR: the beauty as plain R, G, B and A, the
+    // rest as `<layer>.R` and so on, which compositors list as separate layers.
+    pub fn save_multilayer(&self, beauty: &Film, splat_scale: f64, path: &Path) -> Result<()> {
+        use exr::prelude::{
+            AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes,
+            SmallVec, Vec2, WritableImage,
+        };
+        let mut layers = vec![(String::new(), beauty.develop_linear(splat_scale)?)];
+        layers.extend(self.layers(beauty)?);
+        let mut channels = Vec::new();
+        for (name, img) in &layers {
+            for (c, suffix) in ["R", "G", "B", "A"].into_iter().enumerate() {
+                let channel = if name.is_empty() {
+                    suffix.to_string()
+                } else {
+                    format!("{name}.{suffix}")
+                };
+                let samples = img.pixels().map(|px| px.0[c]).collect();
+                channels.push(AnyChannel::new(channel.as_str(), FlatSamples::F32(samples)));
+            }
+        }
+        let size = Vec2(beauty.width() as usize, beauty.height() as usize);
+        let layer = Layer::new(
+            size,
+            LayerAttributes::default(),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(SmallVec::from_vec(channels)),
+        );
+        Image::from_layer(layer)
+            .write()
+            .to_file(path)
+            .map_err(|source| Error::Exr {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        tracing::debug!(path = %path.display(), layers = layers.len(), "wrote multilayer EXR");
+        Ok(())
+    }
+}
+
+pub struct AovTiles<'a> {
+    tiles: Vec<(Aov, AovTile)>,
+    material_ids: &'a MaterialIds,
+    group_names: &'a [Arc<str>],
+}
+
+impl AovTiles<'_> {
+    pub fn add_sample(&mut self, x: u32, y: u32, camera: &Camera, sample: &PathSample) {
+        for (aov, tile) in &mut self.tiles {
+            match tile {
+                AovTile::Film(tile) if aov.is_lighting() => add_lighting(tile, *aov, x, y, sample),
+                AovTile::Groups(tiles) => add_light_groups(tiles, self.group_names, x, y, sample),
+                // Like light groups, every sample lands in every class film.
+                AovTile::Classes(tiles) => {
+                    for (tile, c) in tiles.iter_mut().zip(sample.classes) {
+                        tile.add_sample(x, y, c, 1.0);
+                    }
+                }
+                AovTile::Moments(mean, squares) => {
+                    mean.add_sample(x, y, sample.color, 1.0);
+                    squares.add_sample(x, y, sample.color * sample.color, 1.0);
+                }
+                _ => {}
+            }
+        }
+
+        let Some(rec) = &sample.primary else {
+            return;
+        };
+        for (aov, tile) in &mut self.tiles {
+            match tile {
+                AovTile::Groups(_) | AovTile::Classes(_) | AovTile::Moments(..) => {}
+                AovTile::Film(_) if aov.is_lighting() || *aov == Aov::Unclamped => {}
+                AovTile::Film(tile) => {
+                    let value = match aov {
+                        Aov::Depth => {
+                            let d = camera.depth(rec.point);
+                            Color::new(d, d, d)
+                        }
+                        _ => rec.point,
+                    };
+                    tile.add_sample(x, y, value, 1.0);
+                }
+                AovTile::Ids(tile) => {
+                    let id = match aov {
+                        Aov::ObjectId => rec.object_id,
+                        _ => self.material_ids.id(rec.material),
+                    };
+                    tile.add_sample(x, y, id, 1.0);
+                }
+            }
+        }
+    }
+}
+
+impl AovTiles<'_> {
+    // The camera sample's color before clamping, for `Unclamped`.
+    pub fn add_unclamped(&mut self, x: u32, y: u32, color: Color) {
+        for (aov, tile) in &mut self.tiles {
+            if let (Aov::Unclamped, AovTile::Film(tile)) = (*aov, tile) {
+                tile.add_sample(x, y, color, 1.0);
+            }
+        }
+    }
+}
+
+// Splits `sample` into albedo, demodulated diffuse light and the rest, for `aov`.
+fn add_lighting(tile: &mut FilmTile, aov: Aov, x: u32, y: u32, sample: &PathSample) {
+    let diffuse = sample.classes[PathClass::DirectDiffuse as usize]
+        + sample.classes[PathClass::IndirectDiffuse as usize];
+    match (aov, sample.albedo) {
+        (Aov::Specular, _) => tile.add_sample(x, y, sample.color - diffuse, 1.0),
+        (Aov::Albedo, Some(albedo)) => tile.add_sample(x, y, albedo, 1.0),
+        (Aov::Diffuse, Some(albedo)) => {
+            // Channels without reflectance can't carry diffuse light.
+            let divide = |l: f64, a: f64| if a > 0.0 { l / a } else { 0.0 };
+            let irradiance = Color::new(
+                divide(diffuse.r(), albedo.r()),
+                divide(diffuse.g(), albedo.g()),
+                divide(diffuse.b(), albedo.b()),
+            );
+            tile.add_sample(x, y, irradiance, 1.0);
+        }
+        _ => {}
+    }
+}
+
+// Every sample lands in every group film (often as black) so each averages over all samples.
+fn add_light_groups(
+    tiles: &mut [FilmTile],
+    names: &[Arc<str>],
+    x: u32,
+    y: u32,
+    sample: &PathSample,
+) {
+    let mut contributions = vec![Color::default(); tiles.len()];
+    contributions[0] = sample.background;
+    for (group, c) in &sample.emission {
+        // Named groups only ever match past the reserved ones.
+        let i = match group {
+            None => Some(1),
+            Some(name) => names[RESERVED_GROUPS..]
+                .iter()
+                .position(|n| n == name)
+                .map(|i| i + RESERVED_GROUPS),
+        };
+        if let Some(i) = i {
+            contributions[i] += *c;
+        }
+    }
+    for (tile, c) in tiles.iter_mut().zip(contributions) {
+        tile.add_sample(x, y, c, 1.0);
+    }
+}
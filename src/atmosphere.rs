@@ -0,0 +1,127 @@
+// Cheap haze for depth cues and aerial perspective. Camera rays fade towards a fog color by
+// the optical depth of an exponential height fog up to their first hit, integrated in closed
+// form. Light isn't scattered or shadowed inside the fog, so there are no god rays, but it
+// costs nothing per bounce.
+
+use crate::background::SunSky;
+use crate::ray::Ray;
+use crate::vec3::{Color, Vec3};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Atmosphere {
+    // Extinction per unit length at `base_height`.
+    pub density: f64,
+    // Radiance of the haze itself, what distant objects fade to.
+    pub color: Color,
+    // Density falls by a factor of e every `1 / height_falloff` units above `base_height`,
+    // and rises below it. Zero gives uniform fog.
+    #[serde(default)]
+    pub height_falloff: f64,
+    #[serde(default)]
+    pub base_height: f64,
+    // Leaves rays that reach the background alone, for skies that already show the air in
+    // front of them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub surfaces_only: bool,
+}
+
+impl Atmosphere {
+    pub fn new(density: f64, color: Color) -> Self {
+        Self {
+            density,
+            color,
+            height_falloff: 0.0,
+            base_height: 0.0,
+            surfaces_only: false,
+        }
+    }
+
+    pub fn with_height_falloff(mut self, height_falloff: f64, base_height: f64) -> Self {
+        self.height_falloff = height_falloff;
+        self.base_height = base_height;
+        self
+    }
+
+    pub fn with_surfaces_only(mut self, surfaces_only: bool) -> Self {
+        self.surfaces_only = surfaces_only;
+        self
+    }
+
+    // Integrated density along `ray` from its origin to parameter `t`, which may be infinite.
+    pub fn optical_depth(&self, ray: &Ray, t: f64) -> f64 {
+        let speed = ray.direction().length();
+        let distance = t * speed;
+        if self.density <= 0.0 || distance <= 0.0 {
+            return 0.0;
+        }
+        let start =
+            self.density * (-self.height_falloff * (ray.origin().y - self.base_height)).exp();
+        // Rate at which the density decays per unit distance along the ray.
+        let rate = self.height_falloff * ray.direction().y / speed;
+        if rate.abs() < 1e-12 {
+            return start * distance;
+        }
+        start * -(-rate * distance).exp_m1() / rate
+    }
+
+    // Fraction of the light from parameter `t` along `ray` that reaches its origin.
+    #[inline]
+    pub fn transmittance(&self, ray: &Ray, t: f64) -> f64 {
+        (-self.optical_depth(ray, t)).exp()
+    }
+}
+
+// One control for the air of a sunlit scene: Preetham's turbidity, from 2 for a very clear day
+// to about 10 for a hazy one, sets the sky's look, how much the sun dims and reddens through
+// the aerosols on its way down, and the haze over the scene, so a hazy sunset comes out
+// consistent without tuning each.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Haze {
+    pub turbidity: f64,
+}
+
+// Rayleigh extinction of sea-level air at 550 nm, per meter.
+const RAYLEIGH_EXTINCTION: f64 = 1.16e-5;
+// Scale height of aerosols, in meters; they stay much lower than the air itself.
+const AEROSOL_HEIGHT: f64 = 1200.0;
+
+impl Haze {
+    pub fn new(turbidity: f64) -> Self {
+        Self { turbidity }
+    }
+
+    // Optical depth of the aerosols straight up at 550 nm, by Angstrom's formula with
+    // Preetham's fit of its coefficient to turbidity and the usual exponent of 1.3.
+    pub fn aerosol_depth(&self) -> f64 {
+        let beta = (0.04608 * self.turbidity - 0.04586).max(0.0);
+        beta * 0.55f64.powf(-1.3)
+    }
+
+    // `sky` seen through this haze. Its `sun_intensity` is taken as the sun above the
+    // aerosols, dimmed here by the air mass they make up towards the sun.
+    pub fn sky(&self, sky: SunSky) -> SunSky {
+        let beam = (-self.aerosol_depth() * sky.air_mass()).exp();
+        sky.with_turbidity(self.turbidity)
+            .with_intensity(sky.intensity, sky.sun_intensity * beam)
+    }
+
+    // Haze over the scene under `sky`, with scene units `meters_per_unit` long: the air's and
+    // aerosols' extinction at the ground, thinning out with the aerosols' height, glowing
+    // with the mean color of the sky just above the horizon. Only surfaces are fogged, as
+    // the sky shows the rest of the air already.
+    pub fn atmosphere(&self, sky: &SunSky, meters_per_unit: f64) -> Atmosphere {
+        let extinction = RAYLEIGH_EXTINCTION + self.aerosol_depth() / AEROSOL_HEIGHT;
+        let samples = 16;
+        let horizon = (0..samples)
+            .map(|i| {
+                let azimuth = (i as f64 + 0.5) / samples as f64 * std::f64::consts::TAU;
+                sky.sky_radiance(Vec3::new(azimuth.sin(), 0.1, -azimuth.cos()))
+            })
+            .fold(Color::default(), |sum, c| sum + c)
+            / samples as f64;
+        Atmosphere::new(extinction * meters_per_unit, horizon)
+            .with_height_falloff(meters_per_unit / AEROSOL_HEIGHT, 0.0)
+            .with_surfaces_only(true)
+    }
+}
@@ -0,0 +1,439 @@
+// What rays see when they leave the scene: the only light in scenes without emitters. A world
+// carries its background (see `HittableList::set_background`); without one, rays see `SKY`.
+// Most backgrounds are only looked up by rays that escape, so small bright features like the
+// sun are found by chance and are noisy. HDRI maps are also lights: shadow rays are aimed at
+// their bright pixels, see `Background::light`.
+
+use crate::color;
+use crate::error::{Error, Result};
+use crate::light::{Light, LightSample};
+use crate::ray::Ray;
+use crate::scene::BackgroundDesc;
+use crate::vec3::{Color, Point3, Vec3};
+use image::Rgb32FImage;
+use rand::Rng;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+pub trait Background: Send + Sync {
+    fn radiance(&self, ray: &Ray) -> Color;
+
+    // Scene-file form of the background, if it has one.
+    fn to_desc(&self) -> Option<BackgroundDesc> {
+        None
+    }
+
+    // The background as a light infinitely far away, if it can be sampled directly. Worlds
+    // list it with their other lights.
+    fn light(&self) -> Option<&dyn Light> {
+        None
+    }
+}
+
+// The same radiance in every direction.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Constant {
+    pub color: Color,
+}
+
+impl Constant {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for Constant {
+    fn radiance(&self, _ray: &Ray) -> Color {
+        self.color
+    }
+
+    fn to_desc(&self) -> Option<BackgroundDesc> {
+        Some(BackgroundDesc::Constant { color: self.color })
+    }
+}
+
+// Blends from `bottom` straight down to `top` straight up.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub bottom: Color,
+    pub top: Color,
+}
+
+// White to pale blue, the default sky.
+pub const SKY: Gradient = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0));
+
+impl Gradient {
+    pub const fn new(bottom: Color, top: Color) -> Self {
+        Self { bottom, top }
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        SKY
+    }
+}
+
+impl Background for Gradient {
+    fn radiance(&self, ray: &Ray) -> Color {
+        let unit_dir = Vec3::unit_vector(ray.direction());
+        let t = 0.5 * (unit_dir.y + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+
+    fn to_desc(&self) -> Option<BackgroundDesc> {
+        Some(BackgroundDesc::Gradient {
+            bottom: self.bottom,
+            top: self.top,
+        })
+    }
+}
+
+// Equirectangular environment map, e.g. a `.hdr` or `.exr` light probe, with +y up and the
+// image center looking down -z. Also a light: directions are sampled by pixel luminance, so
+// shadow rays find the sun or a bright window however small it is.
+pub struct Hdri {
+    path: PathBuf,
+    image: Rgb32FImage,
+    // None for an all-black map, which has nothing to sample.
+    distribution: Option<PixelDistribution>,
+    pub intensity: f64,
+    // Turns the map about +y, in radians.
+    pub rotation: f64,
+}
+
+impl Hdri {
+    pub fn load(path: &Path) -> Result<Self> {
+        let image = image::open(path).map_err(Error::image(path))?.to_rgb32f();
+        if image.width() == 0 || image.height() == 0 {
+            return Err(Error::Scene(format!("{} is empty", path.display())));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            distribution: PixelDistribution::new(&image),
+            image,
+            intensity: 1.0,
+            rotation: 0.0,
+        })
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f64) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Pixel the unit direction `d` looks up.
+    fn pixel(&self, d: Vec3) -> (u32, u32) {
+        let phi = d.x.atan2(-d.z) - self.rotation;
+        let u = (phi / (2.0 * PI) + 0.5).rem_euclid(1.0);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        let (w, h) = (self.image.width(), self.image.height());
+        (
+            ((u * w as f64) as u32).min(w - 1),
+            ((v * h as f64) as u32).min(h - 1),
+        )
+    }
+
+    fn texel(&self, (x, y): (u32, u32)) -> Color {
+        let [r, g, b] = self.image.get_pixel(x, y).0;
+        self.intensity * Color::new(r as f64, g as f64, b as f64)
+    }
+}
+
+impl Background for Hdri {
+    fn radiance(&self, ray: &Ray) -> Color {
+        self.texel(self.pixel(Vec3::unit_vector(ray.direction())))
+    }
+
+    fn to_desc(&self) -> Option<BackgroundDesc> {
+        Some(BackgroundDesc::Hdri {
+            path: self.path.clone(),
+            intensity: self.intensity,
+            rotation: self.rotation,
+        })
+    }
+
+    fn light(&self) -> Option<&dyn Light> {
+        self.distribution.as_ref().map(|_| self as &dyn Light)
+    }
+}
+
+impl Light for Hdri {
+    fn sample(&self, _origin: Point3, rng: &mut dyn rand::RngCore) -> Option<LightSample> {
+        let distribution = self.distribution.as_ref()?;
+        let (x, y) = distribution.sample(rng);
+        // Uniformly within the pixel, which looks up the same texel throughout.
+        let (w, h) = (self.image.width() as f64, self.image.height() as f64);
+        let u = (x as f64 + rng.random::<f64>()) / w;
+        let theta = PI * (y as f64 + rng.random::<f64>()) / h;
+        let phi = 2.0 * PI * (u - 0.5) + self.rotation;
+        let sin_theta = theta.sin();
+        if sin_theta <= 0.0 {
+            return None;
+        }
+        let direction = Vec3::new(sin_theta * phi.sin(), theta.cos(), -sin_theta * phi.cos());
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            pdf: distribution.probability(x, y) * w * h / (2.0 * PI * PI * sin_theta),
+            radiance: self.texel((x, y)),
+        })
+    }
+
+    fn pdf(&self, _origin: Point3, direction: Vec3) -> Option<(f64, f64)> {
+        let distribution = self.distribution.as_ref()?;
+        let d = Vec3::unit_vector(direction);
+        let (x, y) = self.pixel(d);
+        let probability = distribution.probability(x, y);
+        let sin_theta = (1.0 - d.y * d.y).max(0.0).sqrt();
+        if probability <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+        let (w, h) = (self.image.width() as f64, self.image.height() as f64);
+        Some((
+            f64::INFINITY,
+            probability * w * h / (2.0 * PI * PI * sin_theta),
+        ))
+    }
+}
+
+// Probability of each pixel of an environment map in proportion to its luminance times the
+// solid angle it covers, which shrinks with sin(theta) towards the poles. Sampled by a
+// marginal CDF over rows, then the chosen row's conditional CDF over its pixels.
+struct PixelDistribution {
+    width: usize,
+    // Cumulative row probabilities, ending at 1.
+    marginal: Vec<f64>,
+    // Cumulative probabilities within each row, each ending at 1, row after row.
+    conditional: Vec<f64>,
+}
+
+impl PixelDistribution {
+    fn new(image: &Rgb32FImage) -> Option<Self> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut marginal = Vec::with_capacity(height);
+        let mut conditional = Vec::with_capacity(width * height);
+        let mut total = 0.0;
+        for (y, row) in image.rows().enumerate() {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+            let start = conditional.len();
+            let mut sum = 0.0;
+            for px in row {
+                let [r, g, b] = px.0;
+                let weight = color::luminance(Color::new(r as f64, g as f64, b as f64));
+                sum += if weight.is_finite() {
+                    weight.max(0.0)
+                } else {
+                    0.0
+                };
+                conditional.push(sum);
+            }
+            let cdf = &mut conditional[start..];
+            if sum > 0.0 {
+                cdf.iter_mut().for_each(|c| *c /= sum);
+            } else {
+                // Never chosen; uniform so the CDF stays well formed.
+                let n = cdf.len() as f64;
+                cdf.iter_mut()
+                    .enumerate()
+                    .for_each(|(i, c)| *c = (i + 1) as f64 / n);
+            }
+            cdf[width - 1] = 1.0;
+            total += sum * sin_theta;
+            marginal.push(total);
+        }
+        if !(total > 0.0 && total.is_finite()) {
+            return None;
+        }
+        marginal.iter_mut().for_each(|c| *c /= total);
+        marginal[height - 1] = 1.0;
+        Some(Self {
+            width,
+            marginal,
+            conditional,
+        })
+    }
+
+    fn sample(&self, rng: &mut dyn rand::RngCore) -> (u32, u32) {
+        let pick = |cdf: &[f64], u: f64| cdf.partition_point(|&c| c <= u).min(cdf.len() - 1);
+        let y = pick(&self.marginal, rng.random());
+        let x = pick(self.row(y), rng.random());
+        (x as u32, y as u32)
+    }
+
+    fn row(&self, y: usize) -> &[f64] {
+        &self.conditional[y * self.width..(y + 1) * self.width]
+    }
+
+    fn probability(&self, x: u32, y: u32) -> f64 {
+        let (x, y) = (x as usize, y as usize);
+        let step = |cdf: &[f64], i: usize| cdf[i] - if i == 0 { 0.0 } else { cdf[i - 1] };
+        step(&self.marginal, y) * step(self.row(y), x)
+    }
+}
+
+// Half the angle the sun subtends.
+const SUN_ANGULAR_RADIUS: f64 = 0.0047;
+
+// Clear daylight sky after Preetham, Shirley and Smits, "A Practical Analytic Model for
+// Daylight" (1999), plus the sun's disc. `intensity` is the radiance at the zenith; the sun is
+// `sun_intensity` times brighter, reddened by the air it shines through.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SunSky {
+    // Towards the sun; must point above the horizon.
+    pub sun_direction: Vec3,
+    // Haziness, from 2 for a very clear sky to about 10 for a hazy one.
+    pub turbidity: f64,
+    pub intensity: f64,
+    pub sun_intensity: f64,
+    // Below the horizon, relative to `intensity`.
+    pub ground: Color,
+}
+
+impl SunSky {
+    pub fn new(sun_direction: Vec3) -> Self {
+        Self {
+            sun_direction: Vec3::unit_vector(sun_direction),
+            turbidity: 3.0,
+            intensity: 1.0,
+            sun_intensity: 1000.0,
+            ground: Color::new(0.2, 0.2, 0.2),
+        }
+    }
+
+    // Sun at `elevation` radians above the horizon, `azimuth` radians from -z towards +x.
+    pub fn from_angles(elevation: f64, azimuth: f64) -> Self {
+        let (se, ce) = elevation.sin_cos();
+        let (sa, ca) = azimuth.sin_cos();
+        Self::new(Vec3::new(ce * sa, se, -ce * ca))
+    }
+
+    pub fn with_turbidity(mut self, turbidity: f64) -> Self {
+        self.turbidity = turbidity;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f64, sun_intensity: f64) -> Self {
+        self.intensity = intensity;
+        self.sun_intensity = sun_intensity;
+        self
+    }
+
+    // How many times more air light from the sun passes through than from straight up.
+    pub fn air_mass(&self) -> f64 {
+        1.0 / self.sun_direction.y.max(0.05)
+    }
+
+    // Radiance of the sky along unit direction `d` above the horizon, without the sun's disc.
+    pub(crate) fn sky_radiance(&self, d: Vec3) -> Color {
+        self.intensity * self.sky(d)
+    }
+
+    // Perez distribution coefficients A..E for luminance and the two chromaticities.
+    fn coefficients(&self) -> [[f64; 5]; 3] {
+        let t = self.turbidity;
+        [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ]
+    }
+
+    // Chromaticity x and y at the zenith.
+    fn zenith_chromaticity(&self) -> (f64, f64) {
+        let t = self.turbidity;
+        let s = self.sun_direction.y.clamp(0.0, 1.0).acos();
+        let (s2, s3) = (s * s, s * s * s);
+        let x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let y = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+        (x, y)
+    }
+
+    fn sky(&self, d: Vec3) -> Color {
+        let cos_theta = d.y.max(0.01);
+        let cos_gamma = Vec3::dot(d, self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+        let sun_theta = self.sun_direction.y.clamp(0.0, 1.0).acos();
+        let perez = |[a, b, c, dd, e]: [f64; 5], cos_theta: f64, gamma: f64| {
+            let cos_gamma = gamma.cos();
+            (1.0 + a * (b / cos_theta).exp())
+                * (1.0 + c * (dd * gamma).exp() + e * cos_gamma * cos_gamma)
+        };
+        let [cy, cx, cyy] = self.coefficients();
+        // Each quantity relative to its value at the zenith.
+        let relative = |k: [f64; 5]| perez(k, cos_theta, gamma) / perez(k, 1.0, sun_theta);
+        let (zx, zy) = self.zenith_chromaticity();
+        let (x, y, lum) = (zx * relative(cx), zy * relative(cyy), relative(cy));
+        color::from_xyy(x, y, lum)
+    }
+}
+
+impl Background for SunSky {
+    fn radiance(&self, ray: &Ray) -> Color {
+        let d = Vec3::unit_vector(ray.direction());
+        if d.y < 0.0 {
+            return self.intensity * self.ground;
+        }
+        let mut c = self.sky(d);
+        if Vec3::dot(d, self.sun_direction) >= SUN_ANGULAR_RADIUS.cos() {
+            // Air mass grows towards the horizon, and scatters blue away first.
+            let depth = 0.02 * self.turbidity * self.air_mass();
+            let transmittance =
+                Color::new((-0.5 * depth).exp(), (-depth).exp(), (-2.0 * depth).exp());
+            c += self.sun_intensity * transmittance;
+        }
+        self.intensity * c
+    }
+
+    fn to_desc(&self) -> Option<BackgroundDesc> {
+        Some(BackgroundDesc::SunSky {
+            sun_direction: self.sun_direction,
+            turbidity: self.turbidity,
+            intensity: self.intensity,
+            sun_intensity: self.sun_intensity,
+            ground: self.ground,
+        })
+    }
+}
+
+// Any function of the escaping ray, for backgrounds built in code. These can't be saved in
+// scene files.
+pub struct FnBackground<F>(pub F);
+
+impl<F: Fn(&Ray) -> Color + Send + Sync> Background for FnBackground<F> {
+    fn radiance(&self, ray: &Ray) -> Color {
+        (self.0)(ray)
+    }
+}
@@ -0,0 +1,287 @@
+// Maps baked into a mesh's texture space, for game engines and texture artists: lightmaps,
+// ambient occlusion and curvature. Each texel a triangle covers in UV space is baked at the
+// matching surface point; texels outside every triangle stay empty, with zero alpha, until
+// `pad` grows the charts into them. v runs up, so the texture's top row is v = 1.
+
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::hittable::Hittable;
+use crate::interval::Interval;
+use crate::material::random_cosine_direction;
+use crate::math::Onb;
+use crate::mesh::Mesh;
+use crate::ray::Ray;
+use crate::render::{clamp_sample, lights, sampler, trace_path_with, RenderSettings, T_MIN};
+use crate::vec3::{Color, Point3, Vec3};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+// Where a texel's center lies on the mesh.
+#[derive(Copy, Clone)]
+struct Texel {
+    point: Point3,
+    // The triangle's front, given by its winding.
+    normal: Vec3,
+    tri: [u32; 3],
+    bary: [f64; 3],
+}
+
+// A `width`x`height` lightmap of `mesh` from `samples` hemisphere samples per texel. Texels
+// hold irradiance, incoming radiance integrated over the hemisphere above the surface with
+// the cosine, so a diffuse surface of albedo a reflects a / pi times the texel.
+pub fn bake_irradiance(
+    world: &dyn Hittable,
+    mesh: &Mesh,
+    width: u32,
+    height: u32,
+    samples: u32,
+    settings: &RenderSettings,
+) -> Result<Film> {
+    let lights = lights(world);
+    bake_with(mesh, width, height, |i, texel| {
+        let mut rng = sampler(settings.seed, 0, i as u64);
+        let onb = Onb::from_w(texel.normal);
+        let mut sum = Color::default();
+        for _ in 0..samples {
+            let direction = onb.to_world(random_cosine_direction(&mut rng));
+            let ray = Ray::new(texel.point, direction);
+            let ray_t = Interval::new(T_MIN, f64::INFINITY);
+            let sample = trace_path_with(ray, world, &lights, ray_t, settings, &mut rng);
+            let col = clamp_sample(sample.color, settings.clamp);
+            if col.r().is_finite() && col.g().is_finite() && col.b().is_finite() {
+                sum += col;
+            }
+        }
+        // With cosine-weighted directions, irradiance is pi times the mean radiance.
+        PI * sum / samples.max(1) as f64
+    })
+}
+
+// Ambient occlusion: the cosine-weighted share of `samples` rays per texel that leave the
+// surface without hitting anything within `distance`, 1 where nothing is near.
+pub fn bake_occlusion(
+    world: &dyn Hittable,
+    mesh: &Mesh,
+    width: u32,
+    height: u32,
+    samples: u32,
+    distance: f64,
+    seed: Option<u64>,
+) -> Result<Film> {
+    bake_with(mesh, width, height, |i, texel| {
+        let mut rng = sampler(seed, 0, i as u64);
+        let onb = Onb::from_w(texel.normal);
+        let open = (0..samples)
+            .filter(|_| {
+                let direction = onb.to_world(random_cosine_direction(&mut rng));
+                let ray = Ray::new(texel.point, direction);
+                !world.is_occluded(&ray, Interval::new(T_MIN, distance))
+            })
+            .count();
+        let open = open as f64 / samples.max(1) as f64;
+        Color::new(open, open, open)
+    })
+}
+
+// Mean curvature in grey: 0.5 where flat, brighter where convex and darker where concave,
+// reaching white and black at curvature `range`, that of a sphere of radius 1 / `range`.
+// Estimated per vertex from its neighbours and interpolated across triangles; vertices split
+// along UV seams are joined by position first.
+pub fn bake_curvature(mesh: &Mesh, width: u32, height: u32, range: f64) -> Result<Film> {
+    let curvature = vertex_curvature(mesh);
+    bake_with(mesh, width, height, |_, texel| {
+        let k: f64 = (0..3)
+            .map(|j| texel.bary[j] * curvature[texel.tri[j] as usize])
+            .sum();
+        let grey = 0.5 + 0.5 * (k / range).clamp(-1.0, 1.0);
+        Color::new(grey, grey, grey)
+    })
+}
+
+// Runs `bake` for every covered texel, given its index, in parallel.
+fn bake_with(
+    mesh: &Mesh,
+    width: u32,
+    height: u32,
+    bake: impl Fn(usize, &Texel) -> Color + Sync,
+) -> Result<Film> {
+    if mesh.uvs.is_empty() {
+        return Err(Error::Scene(
+            "baking needs a mesh with texture coordinates".into(),
+        ));
+    }
+    let texels = surface_points(mesh, width, height);
+    let baked: Vec<(usize, Color)> = texels
+        .par_iter()
+        .enumerate()
+        .filter_map(|(i, texel)| Some((i, bake(i, texel.as_ref()?))))
+        .collect();
+    let mut film = Film::new(width, height);
+    for (i, color) in baked {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        film.add_sample(x, y, color, 1.0)?;
+    }
+    Ok(film)
+}
+
+// For each vertex, the mean over its neighbours j of 2 n . (p - p_j) / |p - p_j|^2, with n the
+// area-weighted normal: exactly 1 / r on a sphere of radius r.
+fn vertex_curvature(mesh: &Mesh) -> Vec<f64> {
+    let mut welded = HashMap::new();
+    let ids: Vec<usize> = mesh
+        .positions
+        .iter()
+        .map(|p| {
+            let key = [p.x, p.y, p.z].map(f64::to_bits);
+            let next = welded.len();
+            *welded.entry(key).or_insert(next)
+        })
+        .collect();
+    let mut positions = vec![Point3::default(); welded.len()];
+    for (i, &id) in ids.iter().enumerate() {
+        positions[id] = mesh.positions[i];
+    }
+    let mut normals = vec![Vec3::default(); positions.len()];
+    let mut neighbours = vec![Vec::new(); positions.len()];
+    for tri in &mesh.indices {
+        let [a, b, c] = tri.map(|i| ids[i as usize]);
+        let normal = Vec3::cross(positions[b] - positions[a], positions[c] - positions[a]);
+        for (v, others) in [(a, [b, c]), (b, [c, a]), (c, [a, b])] {
+            normals[v] += normal;
+            for o in others {
+                if !neighbours[v].contains(&o) {
+                    neighbours[v].push(o);
+                }
+            }
+        }
+    }
+    let welded_curvature: Vec<f64> = (0..positions.len())
+        .map(|v| {
+            if normals[v].length_squared() == 0.0 || neighbours[v].is_empty() {
+                return 0.0;
+            }
+            let n = Vec3::unit_vector(normals[v]);
+            let sum: f64 = neighbours[v]
+                .iter()
+                .map(|&j| {
+                    let d = positions[v] - positions[j];
+                    2.0 * Vec3::dot(n, d) / d.length_squared().max(f64::MIN_POSITIVE)
+                })
+                .sum();
+            sum / neighbours[v].len() as f64
+        })
+        .collect();
+    ids.iter().map(|&id| welded_curvature[id]).collect()
+}
+
+// Rasterizes `mesh`'s triangles in UV space: where each texel's center lies on the mesh, for
+// texels inside a triangle. Where triangles overlap the first one wins.
+fn surface_points(mesh: &Mesh, width: u32, height: u32) -> Vec<Option<Texel>> {
+    let mut texels = vec![None; width as usize * height as usize];
+    for &tri in &mesh.indices {
+        let [a, b, c] = mesh.vertices(tri);
+        let normal = Vec3::cross(b - a, c - a);
+        if normal.length_squared() == 0.0 {
+            continue;
+        }
+        let normal = Vec3::unit_vector(normal);
+        // In texel coordinates, y down.
+        let uv = tri.map(|i| {
+            let [u, v] = mesh.uvs[i as usize];
+            (u * width as f64, (1.0 - v) * height as f64)
+        });
+        let area = edge(uv[0], uv[1], uv[2]);
+        if area == 0.0 {
+            continue;
+        }
+        let xs = uv.map(|(x, _)| x);
+        let ys = uv.map(|(_, y)| y);
+        let range = |vs: [f64; 3], n: u32| {
+            let lo = vs.iter().copied().fold(f64::INFINITY, f64::min);
+            let hi = vs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            (lo.floor().max(0.0) as u32)..(hi.ceil().clamp(0.0, n as f64) as u32)
+        };
+        for y in range(ys, height) {
+            for x in range(xs, width) {
+                let center = (x as f64 + 0.5, y as f64 + 0.5);
+                let w0 = edge(uv[1], uv[2], center) / area;
+                let w1 = edge(uv[2], uv[0], center) / area;
+                let w2 = 1.0 - w0 - w1;
+                let texel = &mut texels[(y * width + x) as usize];
+                if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 && texel.is_none() {
+                    *texel = Some(Texel {
+                        point: w0 * a + w1 * b + w2 * c,
+                        normal,
+                        tri,
+                        bary: [w0, w1, w2],
+                    });
+                }
+            }
+        }
+    }
+    texels
+}
+
+// Twice the signed area of the triangle (a, b, p).
+fn edge(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+const NEIGHBOURS: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// Grows the baked texels `texels` steps outwards into the empty ones, each taking the mean of
+// its baked neighbours, so bilinear filtering and mip maps don't pull in black at the seams.
+pub fn pad(lightmap: &Film, texels: u32) -> Result<Film> {
+    let (width, height) = (lightmap.width(), lightmap.height());
+    let mut colors = lightmap.resolve(1.0)?;
+    let mut filled = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| Ok(lightmap.pixel(x, y)?.weight_sum > 0.0))
+        .collect::<Result<Vec<bool>>>()?;
+    for _ in 0..texels {
+        let mut grown = (colors.clone(), filled.clone());
+        for y in 0..height {
+            for x in 0..width {
+                let i = (y * width + x) as usize;
+                if filled[i] {
+                    continue;
+                }
+                let mut sum = Color::default();
+                let mut n = 0;
+                for (dx, dy) in NEIGHBOURS {
+                    let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                    if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                        continue;
+                    }
+                    let j = (ny as u32 * width + nx as u32) as usize;
+                    if filled[j] {
+                        sum += colors[j];
+                        n += 1;
+                    }
+                }
+                if n > 0 {
+                    grown.0[i] = sum / n as f64;
+                    grown.1[i] = true;
+                }
+            }
+        }
+        (colors, filled) = grown;
+    }
+    let mut out = Film::new(width, height);
+    for (i, (&color, &filled)) in colors.iter().zip(&filled).enumerate() {
+        if filled {
+            out.add_sample(i as u32 % width, i as u32 / width, color, 1.0)?;
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,216 @@
+// Bloom and diffraction glare over the HDR film: light brighter than a threshold spills into
+// its surroundings, as it does through real lenses, so the sun and bright emitters read as
+// bright rather than as flat clipped white. Applied to a copy of the film before it is
+// developed, so rendering can carry on accumulating into the original.
+
+use crate::color::luminance;
+use crate::error::Result;
+use crate::film::Film;
+use crate::vec3::Color;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// How much longer the red and shorter the blue glare streaks are than green, for the color
+// fringes diffraction gives them.
+const STREAK_CHANNEL_LENGTHS: [f64; 3] = [1.0, 0.85, 0.7];
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bloom {
+    // Luminance above which light blooms; 1.0 is display white.
+    pub threshold: f64,
+    // Standard deviation of the glow, as a fraction of the image width.
+    pub radius: f64,
+    // Fraction of the light above the threshold added back as glow.
+    pub intensity: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glare: Option<Glare>,
+}
+
+// Star-shaped streaks from the aperture's edges, over the same light as the bloom.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Glare {
+    // Spikes around each highlight, e.g. 6 for a six-bladed aperture.
+    pub streaks: u32,
+    // Distance over which a streak fades to e^-4, as a fraction of the image width.
+    pub length: f64,
+    // Fraction of the light above the threshold spread over the streaks.
+    pub intensity: f64,
+    // Angle of the first streak from the image's x axis, in radians.
+    #[serde(default)]
+    pub rotation: f64,
+}
+
+impl Bloom {
+    pub fn new(threshold: f64, radius: f64, intensity: f64) -> Self {
+        Self {
+            threshold,
+            radius,
+            intensity,
+            glare: None,
+        }
+    }
+
+    pub fn with_glare(mut self, glare: Glare) -> Self {
+        self.glare = Some(glare);
+        self
+    }
+
+    // A copy of `film` with the glow added, to develop with the same `splat_scale`, which must
+    // be positive.
+    pub fn apply(&self, film: &Film, splat_scale: f64) -> Result<Film> {
+        let (width, height) = (film.width() as usize, film.height() as usize);
+        if width == 0 || height == 0 {
+            return film.snapshot();
+        }
+        let bright: Vec<Color> = film
+            .resolve(splat_scale)?
+            .into_iter()
+            .map(|c| self.above_threshold(c))
+            .collect();
+        let mut glow = vec![Color::default(); width * height];
+        if self.intensity > 0.0 {
+            let sigma = self.radius * width as f64;
+            for (g, b) in glow.iter_mut().zip(blur(&bright, width, height, sigma)) {
+                *g += self.intensity * b;
+            }
+        }
+        if let Some(glare) = &self.glare {
+            for (g, s) in glow.iter_mut().zip(glare.streaks(&bright, width, height)) {
+                *g += s;
+            }
+        }
+
+        let out = film.snapshot()?;
+        let splats: Vec<(f64, f64, Color)> = glow
+            .into_iter()
+            .enumerate()
+            .filter(|(_, c)| *c != Color::default())
+            .map(|(i, c)| {
+                let (x, y) = (i % width, i / width);
+                (x as f64 + 0.5, y as f64 + 0.5, c / splat_scale)
+            })
+            .collect();
+        out.add_splats(&splats)?;
+        Ok(out)
+    }
+
+    // The part of `c` over the threshold, keeping its hue.
+    fn above_threshold(&self, c: Color) -> Color {
+        let l = luminance(c);
+        if !l.is_finite() || l <= self.threshold || l <= 0.0 {
+            return Color::default();
+        }
+        c * ((l - self.threshold) / l)
+    }
+}
+
+impl Glare {
+    // Streaks from every pixel of `bright`, scaled by `intensity`.
+    fn streaks(&self, bright: &[Color], width: usize, height: usize) -> Vec<Color> {
+        let length = self.length * width as f64;
+        if self.streaks == 0 || length <= 0.0 || self.intensity <= 0.0 {
+            return vec![Color::default(); width * height];
+        }
+        let steps = (length * STREAK_CHANNEL_LENGTHS[0]).ceil() as usize;
+        // Per-channel falloff along a streak, each summing to 1 / streaks.
+        let falloff: Vec<[f64; 3]> = (1..=steps)
+            .map(|s| STREAK_CHANNEL_LENGTHS.map(|k| (-4.0 * s as f64 / (length * k)).exp()))
+            .collect();
+        let totals: [f64; 3] = std::array::from_fn(|c| {
+            falloff.iter().map(|f| f[c]).sum::<f64>() * self.streaks as f64
+        });
+        let directions: Vec<(f64, f64)> = (0..self.streaks)
+            .map(|k| {
+                let angle = self.rotation + std::f64::consts::TAU * k as f64 / self.streaks as f64;
+                (angle.cos(), -angle.sin())
+            })
+            .collect();
+
+        (0..height)
+            .into_par_iter()
+            .fold(
+                || vec![Color::default(); width * height],
+                |mut out, y| {
+                    for x in 0..width {
+                        let c = bright[y * width + x];
+                        if c == Color::default() {
+                            continue;
+                        }
+                        let c = self.intensity * c;
+                        for &(dx, dy) in &directions {
+                            for (s, f) in falloff.iter().enumerate() {
+                                let px = x as f64 + 0.5 + dx * (s + 1) as f64;
+                                let py = y as f64 + 0.5 + dy * (s + 1) as f64;
+                                if px < 0.0 || py < 0.0 || px >= width as f64 || py >= height as f64
+                                {
+                                    break;
+                                }
+                                out[py as usize * width + px as usize] += Color::new(
+                                    c.r() * f[0] / totals[0],
+                                    c.g() * f[1] / totals[1],
+                                    c.b() * f[2] / totals[2],
+                                );
+                            }
+                        }
+                    }
+                    out
+                },
+            )
+            .reduce(
+                || vec![Color::default(); width * height],
+                |mut a, b| {
+                    for (a, b) in a.iter_mut().zip(b) {
+                        *a += b;
+                    }
+                    a
+                },
+            )
+    }
+}
+
+// Separable Gaussian blur with standard deviation `sigma` pixels. Taps falling outside the
+// image are dropped and the rest renormalized, so edges don't darken.
+fn blur(image: &[Color], width: usize, height: usize, sigma: f64) -> Vec<Color> {
+    if sigma < 0.5 {
+        return image.to_vec();
+    }
+    let reach = (3.0 * sigma).ceil() as usize;
+    let kernel: Vec<f64> = (0..=2 * reach)
+        .map(|i| (-0.5 * ((i as f64 - reach as f64) / sigma).powi(2)).exp())
+        .collect();
+    let rows = blur_rows(image, width, &kernel);
+    let columns = blur_rows(&transpose(&rows, width, height), height, &kernel);
+    transpose(&columns, height, width)
+}
+
+// Convolves each `width` long row with `kernel`, centred.
+fn blur_rows(image: &[Color], width: usize, kernel: &[f64]) -> Vec<Color> {
+    let reach = kernel.len() / 2;
+    let mut out = vec![Color::default(); image.len()];
+    out.par_chunks_mut(width)
+        .zip(image.par_chunks(width))
+        .for_each(|(out, row)| {
+            for (x, px) in out.iter_mut().enumerate() {
+                let start = x.saturating_sub(reach);
+                let end = (x + reach + 1).min(width);
+                let (mut sum, mut weight) = (Color::default(), 0.0);
+                for (p, c) in row.iter().enumerate().take(end).skip(start) {
+                    let w = kernel[p + reach - x];
+                    sum += w * *c;
+                    weight += w;
+                }
+                *px = sum / weight;
+            }
+        });
+    out
+}
+
+fn transpose(image: &[Color], width: usize, height: usize) -> Vec<Color> {
+    let mut out = vec![Color::default(); image.len()];
+    for y in 0..height {
+        for x in 0..width {
+            out[x * height + y] = image[y * width + x];
+        }
+    }
+    out
+}
@@ -1,6 +1,153 @@
-use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::hittable::Hittable;
+use crate::interval::Interval;
+use crate::lens::RealisticCamera;
+use crate::ray::{Ray, RayDifferential, RayKind};
+use crate::render::T_MIN;
+use crate::stereo::{OdsCamera, StereoCamera};
 use crate::vec3::{Point3, Vec3};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// Optional lens imperfections. All zero means an ideal thin lens.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LensEffects {
+    // Radial distortion coefficient: < 0 barrel, > 0 pincushion.
+    pub distortion: f64,
+    // Relative magnification difference between channels (R grows, B shrinks).
+    pub chromatic_aberration: f64,
+    // 0 = none, 1 = full cos^4 natural vignetting.
+    pub vignetting: f64,
+}
+
+// Relative shutter transmission over the open interval.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ShutterCurve {
+    #[default]
+    Box,
+    // Linear open/close ramps, each covering `ramp` of the interval (0.5 = triangle).
+    Trapezoid {
+        ramp: f64,
+    },
+    // Piecewise-constant weights over equal subdivisions of the interval.
+    Tabulated(Vec<f64>),
+}
+
+impl ShutterCurve {
+    // Maps a uniform sample in [0, 1) to a normalized time in [0, 1) distributed by the curve.
+    pub fn sample(&self, xi: f64) -> f64 {
+        match self {
+            ShutterCurve::Box => xi,
+            ShutterCurve::Trapezoid { ramp } => {
+                let r = ramp.clamp(0.0, 0.5);
+                if r == 0.0 {
+                    return xi;
+                }
+                // Area of each ramp is r/2, the plateau 1 - 2r, total 1 - r.
+                let total = 1.0 - r;
+                let a = xi * total;
+                if a < 0.5 * r {
+                    (2.0 * a * r).sqrt()
+                } else if a < total - 0.5 * r {
+                    a + 0.5 * r
+                } else {
+                    1.0 - (2.0 * (total - a) * r).sqrt()
+                }
+            }
+            ShutterCurve::Tabulated(weights) => {
+                let total: f64 = weights.iter().map(|w| w.max(0.0)).sum();
+                if total <= 0.0 {
+                    return xi;
+                }
+                let n = weights.len() as f64;
+                let mut target = xi * total;
+                for (i, w) in weights.iter().enumerate() {
+                    let w = w.max(0.0);
+                    if target < w {
+                        return (i as f64 + target / w) / n;
+                    }
+                    target -= w;
+                }
+                xi
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Shutter {
+    pub open: f64,
+    pub close: f64,
+    pub curve: ShutterCurve,
+    // Rolling shutter readout time from the top to the bottom scanline; 0 = global shutter.
+    pub readout: f64,
+}
+
+impl Shutter {
+    pub fn new(open: f64, close: f64) -> Self {
+        Self {
+            open,
+            close,
+            ..Default::default()
+        }
+    }
+
+    // Sample time for screen row `t` (0 = bottom, 1 = top).
+    pub fn sample_time(&self, t: f64, rng: &mut dyn rand::RngCore) -> f64 {
+        // Rows are read out in turn however short the exposure.
+        let offset = (1.0 - t).clamp(0.0, 1.0) * self.readout;
+        if self.close <= self.open {
+            return self.open + offset;
+        }
+        self.open + offset + self.curve.sample(rng.random::<f64>()) * (self.close - self.open)
+    }
+}
+
+// Depths along the viewing axis between which a camera's blur stays within a tolerance; see
+// `Camera::focus_limits`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FocusLimits {
+    pub near: f64,
+    // Infinite once the aperture is no wider than the tolerated blur.
+    pub far: f64,
+}
+
+impl FocusLimits {
+    #[inline]
+    pub fn contains(&self, depth: f64) -> bool {
+        (self.near..=self.far).contains(&depth)
+    }
+}
+
+// A point as seen by the camera; see `Camera::project`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraSample {
+    // Film coordinates, as taken by `Camera::get_ray`.
+    pub s: f64,
+    pub t: f64,
+    // Unit direction from the point towards the camera.
+    pub direction: Vec3,
+    pub distance: f64,
+    // Pinhole importance over squared distance. A light path's throughput times the BSDF and
+    // cosine towards the camera, times this, is what it adds to its pixel, once splats are
+    // divided by the light paths traced per pixel.
+    pub importance: f64,
+}
+
+// How a camera turns film points into rays. The thin lens is the camera's own; the others
+// trace their own rays, keeping the camera's lens effects, shutter and clipping.
+#[derive(Clone, Default)]
+pub enum Projection {
+    #[default]
+    ThinLens,
+    // Rays the lens blocks come back as None, and add black to their pixel.
+    Realistic(Arc<RealisticCamera>),
+    // One eye on each half of the film.
+    Stereo(Arc<StereoCamera>),
+    // A panorama per eye; the camera's framing is ignored.
+    Ods(Arc<OdsCamera>),
+}
 
 pub struct Camera {
     origin: Point3,
@@ -9,7 +156,14 @@ pub struct Camera {
     vertical: Vec3,
     u: Vec3,
     v: Vec3,
+    w: Vec3,
     lens_radius: f64,
+    aspect_ratio: f64,
+    lens: LensEffects,
+    shutter: Shutter,
+    near: f64,
+    far: f64,
+    projection: Projection,
 }
 
 impl Camera {
@@ -43,21 +197,302 @@ impl Camera {
             vertical,
             u,
             v,
+            w,
             lens_radius: aperture * 0.5,
+            aspect_ratio,
+            lens: LensEffects::default(),
+            shutter: Shutter::default(),
+            near: 0.0,
+            far: f64::INFINITY,
+            projection: Projection::ThinLens,
+        }
+    }
+
+    // Near and far clipping distances, measured along the viewing axis.
+    pub fn with_clipping(mut self, near: f64, far: f64) -> Self {
+        self.near = near;
+        self.far = far;
+        self
+    }
+
+    // Ray parameter range for a camera ray, honoring the near and far planes.
+    pub fn clip_range(&self, ray: &Ray, ray_t: Interval) -> Interval {
+        let depth_per_t = -Vec3::dot(ray.direction(), self.w);
+        if depth_per_t <= 0.0 {
+            return ray_t;
+        }
+        // Lens offsets move the origin slightly off the camera center; measure from there.
+        let origin_depth = self.depth(ray.origin());
+        let near = (self.near - origin_depth) / depth_per_t;
+        let far = (self.far - origin_depth) / depth_per_t;
+        Interval::intersect(ray_t, Interval::new(near, far))
+    }
+
+    // Distance along the viewing axis at which the camera is in focus.
+    pub fn focus_dist(&self) -> f64 {
+        -Vec3::dot(self.lower_left_corner - self.origin, self.w)
+    }
+
+    pub fn with_focus_dist(mut self, focus_dist: f64) -> Self {
+        // The film plane sits at the focus distance; the field of view stays.
+        let scale = focus_dist / self.focus_dist();
+        self.lower_left_corner = self.origin + (self.lower_left_corner - self.origin) * scale;
+        self.horizontal *= scale;
+        self.vertical *= scale;
+        self
+    }
+
+    // Focuses on what film point (s, t) sees, (0.5, 0.5) being the center; see
+    // `focus_at`. The focus stays put if nothing is there.
+    pub fn with_autofocus(self, world: &dyn Hittable, s: f64, t: f64) -> Self {
+        match self.focus_at(world, s, t) {
+            Some(focus_dist) => self.with_focus_dist(focus_dist),
+            None => self,
+        }
+    }
+
+    // Depth of what film point (s, t) sees: the median over a small patch of pinhole rays
+    // around it, so a point on an edge or in a gap doesn't focus on the background. None if
+    // every ray misses.
+    pub fn focus_at(&self, world: &dyn Hittable, s: f64, t: f64) -> Option<f64> {
+        // A 5x5 grid spanning 2% of the film's height.
+        const STEP: f64 = 0.005;
+        let mut depths: Vec<f64> = (-2..=2)
+            .flat_map(|i| (-2..=2).map(move |j| (i, j)))
+            .filter_map(|(i, j)| {
+                let s = s + i as f64 * STEP / self.aspect_ratio;
+                let t = t + j as f64 * STEP;
+                let ray = self.pinhole_ray(s, t);
+                let ray_t = self.clip_range(&ray, Interval::new(T_MIN, f64::INFINITY));
+                world.hit(&ray, ray_t).map(|rec| self.depth(rec.point))
+            })
+            .collect();
+        if depths.is_empty() {
+            return None;
         }
+        depths.sort_by(f64::total_cmp);
+        Some(depths[depths.len() / 2])
     }
 
-    pub fn get_ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk(rng);
-        let offset = self.u * rd.x + self.v * rd.y;
+    // The depth of field of an `image_height` pixel tall render: where a point blurs over at
+    // most `max_blur` pixels. A point at depth z spreads over aperture * |z - focus| / z at
+    // the focus distance.
+    pub fn focus_limits(&self, image_height: u32, max_blur: f64) -> FocusLimits {
+        let aperture = 2.0 * self.lens_radius;
+        let focus = self.focus_dist();
+        let blur = max_blur * self.vertical.length() / image_height as f64;
+        FocusLimits {
+            near: aperture * focus / (aperture + blur),
+            far: if aperture > blur {
+                aperture * focus / (aperture - blur)
+            } else {
+                f64::INFINITY
+            },
+        }
+    }
+
+    // The ray through film point (s, t) from the center of the lens, at the shutter's opening.
+    // Always the thin lens's, whatever the projection.
+    pub fn pinhole_ray(&self, s: f64, t: f64) -> Ray {
+        let (s, t) = self.distort(s, t, 1.0);
+        Ray::with_time(
+            self.origin,
+            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin,
+            self.shutter.open,
+        )
+        .with_kind(RayKind::Camera)
+    }
+
+    pub fn with_shutter(mut self, shutter: Shutter) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn with_lens_effects(mut self, lens: LensEffects) -> Self {
+        self.lens = lens;
+        self
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    #[inline]
+    pub fn is_thin_lens(&self) -> bool {
+        matches!(self.projection, Projection::ThinLens)
+    }
+
+    #[inline]
+    pub fn has_chromatic_aberration(&self) -> bool {
+        self.lens.chromatic_aberration != 0.0
+    }
+
+    // None if the projection blocks the ray; see `Projection`.
+    pub fn get_ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        let (s, t) = self.distort(s, t, 1.0);
+        self.ideal_ray(s, t, rng)
+    }
+
+    // Like `get_ray`, with differentials toward the rays `ds` and `dt` further across the film,
+    // usually one pixel, through the same point on the lens. Only the thin lens has them.
+    pub fn get_ray_differential(
+        &self,
+        s: f64,
+        t: f64,
+        ds: f64,
+        dt: f64,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<Ray> {
+        let ray = self.get_ray(s, t, rng)?;
+        if !self.is_thin_lens() {
+            return Some(ray);
+        }
+        let direction = |s, t| {
+            let (s, t) = self.distort(s, t, 1.0);
+            self.lower_left_corner + s * self.horizontal + t * self.vertical - ray.origin()
+        };
+        Some(ray.with_differential(Some(RayDifferential {
+            rx_origin: ray.origin(),
+            rx_direction: direction(s + ds, t),
+            ry_origin: ray.origin(),
+            ry_direction: direction(s, t + dt),
+        })))
+    }
+
+    // Ray for a single color channel (0 = R, 1 = G, 2 = B) under chromatic aberration.
+    pub fn get_ray_for_channel(
+        &self,
+        s: f64,
+        t: f64,
+        channel: usize,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<Ray> {
+        let scale = 1.0 + (1.0 - channel as f64) * self.lens.chromatic_aberration;
+        let (s, t) = self.distort(s, t, scale);
+        self.ideal_ray(s, t, rng)
+    }
+
+    // Shutter time for a ray not tied to a screen row, e.g. one traced from a light.
+    pub fn sample_time(&self, rng: &mut dyn rand::RngCore) -> f64 {
+        self.shutter.sample_time(0.5, rng)
+    }
+
+    // Where `p` lands on the film of a pinhole at the camera's center, for light tracing; None
+    // if it is off the film or clipped, or the camera isn't a thin lens. Aperture and lens
+    // effects are ignored.
+    pub fn project(&self, p: Point3) -> Option<CameraSample> {
+        if !self.is_thin_lens() {
+            return None;
+        }
+        let to_camera = self.origin - p;
+        let distance = to_camera.length();
+        let cos_theta = Vec3::dot(to_camera, self.w) / distance;
+        if distance == 0.0 || cos_theta <= 0.0 {
+            return None;
+        }
+        let ray = Ray::new(self.origin, p - self.origin);
+        if !self
+            .clip_range(&ray, Interval::new(0.0, f64::INFINITY))
+            .contains(1.0)
+        {
+            return None;
+        }
+        // Scale the ray to meet the film plane, which is `focus` along the viewing axis.
+        let focus = self.focus_dist();
+        let on_film = self.origin - to_camera * (focus / (distance * cos_theta));
+        let offset = on_film - self.lower_left_corner;
+        let s = Vec3::dot(offset, self.horizontal) / self.horizontal.length_squared();
+        let t = Vec3::dot(offset, self.vertical) / self.vertical.length_squared();
+        if !((0.0..1.0).contains(&s) && (0.0..1.0).contains(&t)) {
+            return None;
+        }
+        // Film area one unit in front of the pinhole.
+        let area = self.horizontal.length() * self.vertical.length() / (focus * focus);
+        Some(CameraSample {
+            s,
+            t,
+            direction: to_camera / distance,
+            distance,
+            importance: 1.0 / (area * cos_theta.powi(3) * distance * distance),
+        })
+    }
+
+    // Distance of `p` from the camera along the viewing axis.
+    #[inline]
+    pub fn depth(&self, p: Point3) -> f64 {
+        -Vec3::dot(p - self.origin, self.w)
+    }
 
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+    // Radiometric falloff weight for the given screen position.
+    pub fn vignetting(&self, s: f64, t: f64) -> f64 {
+        if self.lens.vignetting == 0.0 {
+            return 1.0;
+        }
+        let dir = self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin;
+        let cos_theta = -Vec3::dot(Vec3::unit_vector(dir), self.w);
+        let falloff = cos_theta.powi(4);
+        1.0 - self.lens.vignetting * (1.0 - falloff)
+    }
+
+    fn distort(&self, s: f64, t: f64, scale: f64) -> (f64, f64) {
+        if self.lens.distortion == 0.0 && scale == 1.0 {
+            return (s, t);
+        }
+        // Centered coordinates, normalized so the half-diagonal has radius 1.
+        let norm = (self.aspect_ratio * self.aspect_ratio + 1.0).sqrt();
+        let x = (2.0 * s - 1.0) * self.aspect_ratio / norm;
+        let y = (2.0 * t - 1.0) / norm;
+        let r2 = x * x + y * y;
+        let k = scale * (1.0 + self.lens.distortion * r2);
+        (0.5 + (s - 0.5) * k, 0.5 + (t - 0.5) * k)
+    }
+
+    fn ideal_ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        let ray = match &self.projection {
+            Projection::ThinLens => {
+                let rd = self.lens_radius * random_in_unit_disk(rng);
+                let offset = self.u * rd.x + self.v * rd.y;
+                let direction = self.lower_left_corner + s * self.horizontal + t * self.vertical
+                    - self.origin
+                    - offset;
+                Ray::new(self.origin + offset, direction)
+            }
+            Projection::Realistic(lens) => lens.get_ray(s, t, rng)?,
+            Projection::Stereo(stereo) => stereo.get_ray(s, t, rng)?,
+            Projection::Ods(ods) => ods.get_ray(s, t, rng),
+        };
+        Some(
+            Ray::with_time(
+                ray.origin(),
+                ray.direction(),
+                self.shutter.sample_time(t, rng),
+            )
+            .with_kind(RayKind::Camera),
         )
     }
 }
 
+// Camera position (look_from, look_at) that frames `bbox` when looking along `view_dir`.
+// `margin` > 1 leaves space around the scene's bounding sphere.
+pub fn auto_frame(
+    bbox: &Aabb,
+    view_dir: Vec3,
+    vertical_fov_degrees: f64,
+    aspect_ratio: f64,
+    margin: f64,
+) -> (Point3, Point3) {
+    let look_at = bbox.centroid();
+    let radius = 0.5 * bbox.diagonal();
+
+    let half_v = 0.5 * vertical_fov_degrees.to_radians();
+    let half_h = (aspect_ratio * half_v.tan()).atan();
+    let half_fov = half_v.min(half_h);
+
+    let dist = margin * radius / half_fov.sin();
+    (look_at - dist * Vec3::unit_vector(view_dir), look_at)
+}
+
 #[inline]
 fn random_in_unit_disk(rng: &mut dyn rand::RngCore) -> Vec3 {
     loop {
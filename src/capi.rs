@@ -0,0 +1,275 @@
+// Minimal C ABI for embedding the renderer; see `include/rtt.h` for the C declarations.
+// Every entry point checks its pointers and reports failure through a status code rather
+// than unwinding across the FFI boundary.
+
+use crate::aov::AovSet;
+use crate::film::Film;
+use crate::hittable::{HittableList, Sphere};
+use crate::material::MaterialRef;
+use crate::render::render_image;
+use crate::scene::{CameraDesc, MaterialDesc};
+use crate::vec3::{Point3, Vec3};
+use std::ffi::c_void;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+pub const RTT_OK: i32 = 0;
+pub const RTT_ERR_NULL: i32 = -1;
+pub const RTT_ERR_INVALID: i32 = -2;
+pub const RTT_ERR_BUFFER: i32 = -3;
+pub const RTT_ERR_RENDER: i32 = -4;
+pub const RTT_ERR_FULL: i32 = -5;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RttMaterialKind {
+    Lambertian = 0,
+    Metal = 1,
+    Dielectric = 2,
+    DiffuseLight = 3,
+}
+
+impl TryFrom<u32> for RttMaterialKind {
+    type Error = ();
+
+    fn try_from(kind: u32) -> Result<Self, ()> {
+        Ok(match kind {
+            0 => Self::Lambertian,
+            1 => Self::Metal,
+            2 => Self::Dielectric,
+            3 => Self::DiffuseLight,
+            _ => return Err(()),
+        })
+    }
+}
+
+// `kind` is an `RttMaterialKind`, held as a plain integer since C may store any value there.
+// `param` is the fuzz for metals and the index of refraction for dielectrics.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct RttMaterial {
+    pub kind: u32,
+    pub color: [f64; 3],
+    pub param: f64,
+}
+
+impl RttMaterial {
+    // None for an unknown kind, a color that isn't finite and non-negative, or a parameter
+    // out of range.
+    fn to_desc(self) -> Option<MaterialDesc> {
+        let color = Vec3::from(self.color);
+        if !self.color.iter().all(|c| c.is_finite() && *c >= 0.0) {
+            return None;
+        }
+        let kind = RttMaterialKind::try_from(self.kind).ok()?;
+        Some(match kind {
+            RttMaterialKind::Lambertian => MaterialDesc::Lambertian { albedo: color },
+            RttMaterialKind::Metal if (0.0..=1.0).contains(&self.param) => MaterialDesc::Metal {
+                albedo: color,
+                fuzz: self.param,
+                measured: None,
+            },
+            RttMaterialKind::Dielectric if self.param.is_finite() && self.param > 0.0 => {
+                MaterialDesc::Dielectric { ior: self.param }
+            }
+            RttMaterialKind::DiffuseLight => MaterialDesc::DiffuseLight {
+                emit: color,
+                group: None,
+            },
+            _ => return None,
+        })
+    }
+}
+
+// Called with (scanlines done, total scanlines), possibly from several threads at once.
+pub type RttProgressFn = Option<extern "C" fn(u32, u32, *mut c_void)>;
+
+pub struct RttScene {
+    world: HittableList,
+    camera: CameraDesc,
+}
+
+// The caller promises its progress callback and user data are safe to use from any thread.
+struct Progress {
+    callback: RttProgressFn,
+    user_data: *mut c_void,
+}
+
+unsafe impl Sync for Progress {}
+
+impl Progress {
+    fn report(&self, done: u32, total: u32) {
+        if let Some(callback) = self.callback {
+            callback(done, total, self.user_data);
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rtt_scene_new() -> *mut RttScene {
+    let scene = RttScene {
+        world: HittableList::new(),
+        camera: CameraDesc {
+            look_from: Point3::new(0.0, 0.0, 1.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            vfov: 90.0,
+            ..Default::default()
+        },
+    };
+    Box::into_raw(Box::new(scene))
+}
+
+/// # Safety
+/// `scene` must be null or a pointer returned by `rtt_scene_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rtt_scene_free(scene: *mut RttScene) {
+    if !scene.is_null() {
+        drop(Box::from_raw(scene));
+    }
+}
+
+/// # Safety
+/// `scene` must come from `rtt_scene_new`; `center` must point to 3 doubles and `material`
+/// to a valid `RttMaterial`.
+#[no_mangle]
+pub unsafe extern "C" fn rtt_scene_add_sphere(
+    scene: *mut RttScene,
+    center: *const f64,
+    radius: f64,
+    material: *const RttMaterial,
+) -> i32 {
+    let (Some(scene), Some(material)) = (scene.as_mut(), material.as_ref()) else {
+        return RTT_ERR_NULL;
+    };
+    let Some(center) = read_vec3(center) else {
+        return RTT_ERR_NULL;
+    };
+    if !is_finite(center) || !radius.is_finite() || radius <= 0.0 {
+        return RTT_ERR_INVALID;
+    }
+    let Some(material) = material.to_desc() else {
+        return RTT_ERR_INVALID;
+    };
+    let Ok(material) = MaterialRef::new(material.build()) else {
+        return RTT_ERR_FULL;
+    };
+    scene
+        .world
+        .add(Arc::new(Sphere::new(center, radius, material)));
+    RTT_OK
+}
+
+/// # Safety
+/// `scene` must come from `rtt_scene_new`; `look_from`, `look_at` and `vup` must each point
+/// to 3 doubles.
+#[no_mangle]
+pub unsafe extern "C" fn rtt_scene_set_camera(
+    scene: *mut RttScene,
+    look_from: *const f64,
+    look_at: *const f64,
+    vup: *const f64,
+    vfov: f64,
+    aperture: f64,
+    focus_dist: f64,
+) -> i32 {
+    let Some(scene) = scene.as_mut() else {
+        return RTT_ERR_NULL;
+    };
+    let (Some(look_from), Some(look_at), Some(vup)) =
+        (read_vec3(look_from), read_vec3(look_at), read_vec3(vup))
+    else {
+        return RTT_ERR_NULL;
+    };
+    let forward = look_at - look_from;
+    let valid = [look_from, look_at, vup].into_iter().all(is_finite)
+        && vfov > 0.0
+        && vfov < 180.0
+        && aperture.is_finite()
+        && aperture >= 0.0
+        && focus_dist.is_finite()
+        && focus_dist > 0.0
+        && forward.length_squared() > 0.0
+        && Vec3::cross(forward, vup).length_squared() > 0.0;
+    if !valid {
+        return RTT_ERR_INVALID;
+    }
+    scene.camera = CameraDesc {
+        look_from,
+        look_at,
+        vup,
+        vfov,
+        aperture,
+        focus_dist,
+        ..scene.camera.clone()
+    };
+    RTT_OK
+}
+
+/// Renders into `out`, `width * height` RGBA8 pixels stored top row first.
+///
+/// # Safety
+/// `scene` must come from `rtt_scene_new` and `out` must point to `out_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rtt_render(
+    scene: *const RttScene,
+    width: u32,
+    height: u32,
+    samples: u32,
+    out: *mut u8,
+    out_len: usize,
+    progress: RttProgressFn,
+    user_data: *mut c_void,
+) -> i32 {
+    let Some(scene) = scene.as_ref() else {
+        return RTT_ERR_NULL;
+    };
+    if out.is_null() {
+        return RTT_ERR_NULL;
+    }
+    if width == 0 || height == 0 || samples == 0 {
+        return RTT_ERR_INVALID;
+    }
+    let needed = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4));
+    if needed.is_none_or(|needed| out_len < needed) {
+        return RTT_ERR_BUFFER;
+    }
+    let out = std::slice::from_raw_parts_mut(out, out_len);
+    let progress = Progress {
+        callback: progress,
+        user_data,
+    };
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let camera = scene
+            .camera
+            .build_in(width as f64 / height as f64, &scene.world);
+        let film = Film::new(width, height);
+        let aovs = AovSet::new(&[], &scene.world, width, height);
+        render_image(&scene.world, &camera, &film, &aovs, samples, &|p| {
+            progress.report(p.done, p.total)
+        })?;
+        film.develop(1.0)
+    }));
+
+    match result {
+        Ok(Ok(img)) => {
+            out[..img.as_raw().len()].copy_from_slice(img.as_raw());
+            RTT_OK
+        }
+        _ => RTT_ERR_RENDER,
+    }
+}
+
+fn is_finite(v: Vec3) -> bool {
+    v.x.is_finite() && v.y.is_finite() && v.z.is_finite()
+}
+
+unsafe fn read_vec3(p: *const f64) -> Option<Vec3> {
+    if p.is_null() {
+        return None;
+    }
+    let v = std::slice::from_raw_parts(p, 3);
+    Some(Vec3::new(v[0], v[1], v[2]))
+}
@@ -0,0 +1,325 @@
+// Color helpers for scene files and lights. `Color` values are linear RGB with sRGB primaries;
+// hex codes, like those from a color picker, are sRGB-encoded and converted on the way in.
+// Scene files accept any of the forms `deserialize` understands wherever a color goes.
+
+use crate::error::{Error, Result};
+use crate::vec3::{Color, Vec3};
+use serde::{Deserialize, Deserializer};
+use std::sync::OnceLock;
+
+pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+pub const GREY: Color = Color::new(0.5, 0.5, 0.5);
+pub const RED: Color = Color::new(1.0, 0.0, 0.0);
+pub const GREEN: Color = Color::new(0.0, 1.0, 0.0);
+pub const BLUE: Color = Color::new(0.0, 0.0, 1.0);
+
+// Range of `from_kelvin`; temperatures outside it are clamped.
+pub const MIN_KELVIN: f64 = 1667.0;
+pub const MAX_KELVIN: f64 = 25000.0;
+
+// sRGB transfer function, one channel at a time.
+#[inline]
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[inline]
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn from_srgb(c: Color) -> Color {
+    Color::new(
+        srgb_to_linear(c.x),
+        srgb_to_linear(c.y),
+        srgb_to_linear(c.z),
+    )
+}
+
+pub fn to_srgb(c: Color) -> Color {
+    Color::new(
+        linear_to_srgb(c.x),
+        linear_to_srgb(c.y),
+        linear_to_srgb(c.z),
+    )
+}
+
+// `#rrggbb` or `#rgb`, with or without the `#`.
+pub fn from_hex(hex: &str) -> Result<Color> {
+    let invalid = || Error::Scene(format!("invalid hex color {hex:?}"));
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    if !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).map_err(|_| invalid());
+    let [r, g, b] = match digits.len() {
+        6 => [
+            channel(&digits[0..2])?,
+            channel(&digits[2..4])?,
+            channel(&digits[4..6])?,
+        ],
+        3 => [
+            channel(&digits[0..1])? * 17,
+            channel(&digits[1..2])? * 17,
+            channel(&digits[2..3])? * 17,
+        ],
+        _ => return Err(invalid()),
+    };
+    Ok(from_srgb(Color::new(r as f64, g as f64, b as f64) / 255.0))
+}
+
+// `#rrggbb` for a linear color, clamped to [0, 1].
+pub fn to_hex(c: Color) -> String {
+    let byte = |v: f64| (255.0 * linear_to_srgb(v.clamp(0.0, 1.0))).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", byte(c.x), byte(c.y), byte(c.z))
+}
+
+// Linear sRGB from CIE XYZ, unclamped: colors outside the sRGB gamut, like most spectral
+// colors, get negative channels.
+pub fn from_xyz(xyz: Vec3) -> Color {
+    Color::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+// CIE XYZ from linear sRGB; the inverse of `from_xyz`.
+pub fn to_xyz(c: Color) -> Vec3 {
+    Vec3::new(
+        0.4123956 * c.x + 0.3575834 * c.y + 0.1804926 * c.z,
+        0.2125862 * c.x + 0.7151703 * c.y + 0.0722005 * c.z,
+        0.0192972 * c.x + 0.1191839 * c.y + 0.9504971 * c.z,
+    )
+}
+
+// Bradford chromatic adaptation of `c`, seen under a light whose white is `from`, to how it
+// looks under `to`, both as XYZ. Only the whites' chromaticities matter.
+pub fn adapt(c: Color, from: Vec3, to: Vec3) -> Color {
+    const BRADFORD: [[f64; 3]; 3] = [
+        [0.8951, 0.2664, -0.1614],
+        [-0.7502, 1.7135, 0.0367],
+        [0.0389, -0.0685, 1.0296],
+    ];
+    const BRADFORD_INVERSE: [[f64; 3]; 3] = [
+        [0.9869929, -0.1470543, 0.1599627],
+        [0.4323053, 0.5183603, 0.0492912],
+        [-0.0085287, 0.0400428, 0.9684867],
+    ];
+    let mul = |m: &[[f64; 3]; 3], v: Vec3| {
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    };
+    let (from, to) = (from / from.y, to / to.y);
+    let (lms_from, lms_to) = (mul(&BRADFORD, from), mul(&BRADFORD, to));
+    let lms = mul(&BRADFORD, to_xyz(c));
+    let scaled = Vec3::new(
+        lms.x * lms_to.x / lms_from.x,
+        lms.y * lms_to.y / lms_from.y,
+        lms.z * lms_to.z / lms_from.z,
+    );
+    from_xyz(mul(&BRADFORD_INVERSE, scaled))
+}
+
+// Linear sRGB from CIE xyY, clamped to the gamut.
+pub fn from_xyy(x: f64, y: f64, lum: f64) -> Color {
+    if y <= 0.0 {
+        return BLACK;
+    }
+    let c = from_xyz(Vec3::new(lum * x / y, lum, lum * (1.0 - x - y) / y));
+    Color::new(c.x.max(0.0), c.y.max(0.0), c.z.max(0.0))
+}
+
+// Relative luminance of a linear color.
+#[inline]
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+// Color of light at a correlated color temperature, e.g. 2700 K for a tungsten bulb or
+// 6500 K for overcast daylight, with the brightest channel scaled to 1. Follows the Planckian
+// locus using the cubic fit of Kim et al. (2002).
+pub fn from_kelvin(kelvin: f64) -> Color {
+    let (x, y) = kelvin_xy(kelvin);
+    let c = from_xyy(x, y, 1.0);
+    c / c.x.max(c.y).max(c.z)
+}
+
+// CIE xy chromaticity of `from_kelvin`'s light, `tint` Δuv off the Planckian locus: positive
+// is greener, negative more magenta.
+pub fn kelvin_xy_tinted(kelvin: f64, tint: f64) -> (f64, f64) {
+    let uv = |(x, y): (f64, f64)| {
+        let d = -2.0 * x + 12.0 * y + 3.0;
+        (4.0 * x / d, 6.0 * y / d)
+    };
+    let (u, v) = uv(kelvin_xy(kelvin));
+    // The locus runs from red to blue as the temperature rises; green is to its left.
+    let t = kelvin.clamp(MIN_KELVIN, MAX_KELVIN);
+    let (u0, v0) = uv(kelvin_xy(t * 0.99));
+    let (u1, v1) = uv(kelvin_xy(t * 1.01));
+    let (du, dv) = (u1 - u0, v1 - v0);
+    let length = du.hypot(dv);
+    let (nu, nv) = (dv / length, -du / length);
+    let (u, v) = (u + tint * nu, v + tint * nv);
+    let d = 2.0 * u - 8.0 * v + 4.0;
+    (3.0 * u / d, 2.0 * v / d)
+}
+
+// CIE xy chromaticity on the Planckian locus.
+fn kelvin_xy(kelvin: f64) -> (f64, f64) {
+    let t = kelvin.clamp(MIN_KELVIN, MAX_KELVIN);
+    let (t1, t2, t3) = (1e3 / t, 1e6 / (t * t), 1e9 / (t * t * t));
+    let x = if t <= 4000.0 {
+        -0.2661239 * t3 - 0.2343589 * t2 + 0.8776956 * t1 + 0.179910
+    } else {
+        -3.0258469 * t3 + 2.1070379 * t2 + 0.2226347 * t1 + 0.240390
+    };
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if t <= 2222.0 {
+        -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+    };
+    (x, y)
+}
+
+// Wavelengths the CIE observer responds to, in nanometres.
+pub const VISIBLE: (f64, f64) = (360.0, 830.0);
+
+// CIE 1931 2-degree color matching functions at `nm`, as XYZ. Uses the multi-lobe Gaussian fit
+// of Wyman, Sloan and Shirley (2013), within about 1% of the tabulated curves.
+pub fn cie_xyz(nm: f64) -> Vec3 {
+    let g = |mu: f64, below: f64, above: f64| {
+        let t = (nm - mu) / if nm < mu { below } else { above };
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+// Spacing of the wavelengths spectra are integrated at, in nanometres, as in the CIE tables.
+pub const SPECTRAL_STEP: f64 = 5.0;
+
+fn wavelengths() -> impl Iterator<Item = f64> {
+    let (lo, hi) = VISIBLE;
+    (0..=((hi - lo) / SPECTRAL_STEP) as u32).map(move |i| lo + i as f64 * SPECTRAL_STEP)
+}
+
+// XYZ of a spectrum given as radiance per nanometre, integrated over `VISIBLE`.
+pub fn spectrum_to_xyz(spectrum: impl Fn(f64) -> f64) -> Vec3 {
+    wavelengths().fold(Vec3::default(), |sum, nm| {
+        sum + SPECTRAL_STEP * spectrum(nm) * cie_xyz(nm)
+    })
+}
+
+// Linear sRGB weight of a single wavelength, for spectral rendering: averaging
+// `radiance(nm) * wavelength_to_rgb(nm)` over wavelengths drawn uniformly from `VISIBLE`
+// converges to the spectrum's color, with a flat spectrum of 1 at luminance 1. Often has
+// negative channels, which the averaging cancels.
+pub fn wavelength_to_rgb(nm: f64) -> Color {
+    static SCALE: OnceLock<f64> = OnceLock::new();
+    let (lo, hi) = VISIBLE;
+    if !(lo..=hi).contains(&nm) {
+        return BLACK;
+    }
+    let scale = SCALE.get_or_init(|| (hi - lo) / spectrum_to_xyz(|_| 1.0).y);
+    *scale * from_xyz(cie_xyz(nm))
+}
+
+// Spectral radiance of a blackbody at `kelvin`, per steradian, square metre and nanometre.
+pub fn planck(nm: f64, kelvin: f64) -> f64 {
+    // 2hc^2 in W m^2 / sr, and hc / k in metre Kelvin.
+    const C1: f64 = 1.191_042_97e-16;
+    const C2: f64 = 1.438_776_9e-2;
+    if kelvin <= 0.0 {
+        return 0.0;
+    }
+    let lambda = nm * 1e-9;
+    1e-9 * C1 / (lambda.powi(5) * (C2 / (lambda * kelvin)).exp_m1())
+}
+
+// Color of a blackbody at `kelvin`, integrated against the CIE observer. White balanced so
+// 6500 K is white, then scaled so the brightest channel is 1: only the hue depends on
+// temperature. Black at or below 0 K.
+pub fn blackbody(kelvin: f64) -> Color {
+    // RGB weight of each wavelength, divided by the color at 6500 K. Volumes look this up at
+    // every collision, so only Planck's law is evaluated per call.
+    static WEIGHTS: OnceLock<Vec<(f64, Color)>> = OnceLock::new();
+    if kelvin <= 0.0 {
+        return BLACK;
+    }
+    let weights = WEIGHTS.get_or_init(|| {
+        let white = from_xyz(spectrum_to_xyz(|nm| planck(nm, 6500.0)));
+        wavelengths()
+            .map(|nm| {
+                let c = SPECTRAL_STEP * from_xyz(cie_xyz(nm));
+                (nm, Color::new(c.x / white.x, c.y / white.y, c.z / white.z))
+            })
+            .collect()
+    });
+    let c = weights.iter().fold(Color::default(), |sum, &(nm, w)| {
+        sum + planck(nm, kelvin) * w
+    });
+    let c = Color::new(c.x.max(0.0), c.y.max(0.0), c.z.max(0.0));
+    let max = c.x.max(c.y).max(c.z);
+    if !(max > 0.0 && max.is_finite()) {
+        return BLACK;
+    }
+    c / max
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorSpec {
+    // Derived, so also takes `[r, g, b]`.
+    Components(Color),
+    Hex(String),
+    Kelvin {
+        kelvin: f64,
+        #[serde(default = "one")]
+        intensity: f64,
+    },
+    Blackbody {
+        blackbody: f64,
+        #[serde(default = "one")]
+        intensity: f64,
+    },
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+// For `#[serde(deserialize_with)]` on colors in scene files. Accepts `{"x": r, "y": g, "z": b}`
+// as colors are saved, `[r, g, b]`, a hex string, `{"kelvin": k, "intensity": i}`, or
+// `{"blackbody": k, "intensity": i}` for the exact blackbody hue.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Color, D::Error> {
+    Ok(match ColorSpec::deserialize(deserializer)? {
+        ColorSpec::Components(c) => c,
+        ColorSpec::Hex(s) => from_hex(&s).map_err(serde::de::Error::custom)?,
+        ColorSpec::Kelvin { kelvin, intensity } => intensity * from_kelvin(kelvin),
+        ColorSpec::Blackbody {
+            blackbody: kelvin,
+            intensity,
+        } => intensity * blackbody(kelvin),
+    })
+}
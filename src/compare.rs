@@ -0,0 +1,129 @@
+// Side-by-side and difference views of two renders of the same frame, e.g. before and after an
+// optimization that shouldn't change the image. Monte Carlo noise differs between any two
+// renders, so small differences everywhere are expected; structure in the heatmap is not.
+
+use crate::color::luminance;
+use crate::error::{Error, Result};
+use crate::vec3::Color;
+use image::{Rgb, RgbImage, Rgba, RgbaImage};
+use serde::Serialize;
+
+// Width of the line between the halves of a split image, in pixels.
+const DIVIDER: u32 = 2;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub struct DiffStats {
+    // Over all channels of all pixels.
+    pub mean_abs: f64,
+    pub rmse: f64,
+    pub max_abs: f64,
+    // Peak signal to noise ratio in dB against display white; infinite for identical images.
+    pub psnr: f64,
+}
+
+impl DiffStats {
+    pub fn new(a: &[Color], b: &[Color]) -> Result<Self> {
+        same_size(a.len(), b.len())?;
+        if a.is_empty() {
+            return Ok(Self::default());
+        }
+        let (mut sum, mut squares, mut max) = (0.0, 0.0, 0.0f64);
+        for (a, b) in a.iter().zip(b) {
+            for d in [a.r() - b.r(), a.g() - b.g(), a.b() - b.b()] {
+                let d = d.abs();
+                if !d.is_finite() {
+                    continue;
+                }
+                sum += d;
+                squares += d * d;
+                max = max.max(d);
+            }
+        }
+        let n = 3.0 * a.len() as f64;
+        let mse = squares / n;
+        Ok(Self {
+            mean_abs: sum / n,
+            rmse: mse.sqrt(),
+            max_abs: max,
+            psnr: -10.0 * mse.log10(),
+        })
+    }
+}
+
+fn same_size(a: usize, b: usize) -> Result<()> {
+    if a != b {
+        return Err(Error::Scene(format!(
+            "can't compare images of {a} and {b} pixels"
+        )));
+    }
+    Ok(())
+}
+
+// Left half of `a` beside right half of `b`, with a white line between them.
+pub fn split(a: &RgbaImage, b: &RgbaImage) -> Result<RgbaImage> {
+    if a.dimensions() != b.dimensions() {
+        return Err(Error::Scene(format!(
+            "can't compare a {:?} image with a {:?} one",
+            a.dimensions(),
+            b.dimensions()
+        )));
+    }
+    let middle = a.width() / 2;
+    let divider = middle.saturating_sub(DIVIDER / 2)..middle + DIVIDER.div_ceil(2);
+    Ok(RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        if divider.contains(&x) {
+            Rgba([255, 255, 255, 255])
+        } else if x < middle {
+            *a.get_pixel(x, y)
+        } else {
+            *b.get_pixel(x, y)
+        }
+    }))
+}
+
+// Absolute luminance difference of each pixel as a heat color, `scale` being the difference
+// shown at full heat. Without one it is the 99th percentile difference, so a few fireflies
+// don't wash out the rest.
+pub fn heatmap(
+    a: &[Color],
+    b: &[Color],
+    width: u32,
+    height: u32,
+    scale: Option<f64>,
+) -> Result<RgbImage> {
+    same_size(a.len(), b.len())?;
+    same_size(a.len(), width as usize * height as usize)?;
+    let diffs: Vec<f64> = a
+        .iter()
+        .zip(b)
+        .map(|(&a, &b)| (luminance(a) - luminance(b)).abs())
+        .map(|d| if d.is_finite() { d } else { f64::INFINITY })
+        .collect();
+    let scale = scale.unwrap_or_else(|| {
+        let mut sorted: Vec<f64> = diffs.iter().copied().filter(|d| d.is_finite()).collect();
+        sorted.sort_by(f64::total_cmp);
+        sorted.get(sorted.len() * 99 / 100).copied().unwrap_or(0.0)
+    });
+    Ok(RgbImage::from_fn(width, height, |x, y| {
+        let d = diffs[(y * width + x) as usize];
+        heat_color(if scale > 0.0 { d / scale } else { d.signum() })
+    }))
+}
+
+// Black through blue, red and yellow to white as `t` goes from 0 to 1 and over.
+pub fn heat_color(t: f64) -> Rgb<u8> {
+    const STOPS: [[f64; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.1, 0.1, 0.8],
+        [0.9, 0.1, 0.1],
+        [1.0, 0.9, 0.1],
+        [1.0, 1.0, 1.0],
+    ];
+    let t = if t.is_nan() { 1.0 } else { t.clamp(0.0, 1.0) };
+    let f = t * (STOPS.len() - 1) as f64;
+    let i = (f as usize).min(STOPS.len() - 2);
+    let w = f - i as f64;
+    Rgb(std::array::from_fn(|c| {
+        ((STOPS[i][c] * (1.0 - w) + STOPS[i + 1][c] * w) * 255.0).round() as u8
+    }))
+}
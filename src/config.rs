@@ -0,0 +1,87 @@
+// User-level defaults for the command line, read from `rtt.toml` in the user's config
+// directory, so settings used on every render needn't be repeated. Flags given on the command
+// line take precedence over anything set here.
+//
+//     threads = 8
+//     output_dir = "~/renders"
+//     format = "tif"
+//     preset = "preview"
+//     memory_budget = 16384
+
+use crate::error::{Error, Result};
+use crate::render::Preset;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Output formats `Film::save` writes, by extension.
+pub const FORMATS: [&str; 4] = ["png", "tif", "tiff", "dng"];
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Render threads; all cores when unset.
+    pub threads: Option<usize>,
+    // Where output images go; the working directory when unset. A leading `~` is the home
+    // directory.
+    pub output_dir: Option<PathBuf>,
+    // Extension of the main output image, one of `FORMATS`.
+    pub format: Option<String>,
+    pub preset: Option<Preset>,
+    // MiB a render may need before it is refused; see `MemoryEstimate`.
+    pub memory_budget: Option<usize>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let config: Config = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        if let Some(format) = &config.format {
+            if !FORMATS.contains(&format.as_str()) {
+                return Err(format!(
+                    "unknown format {format:?}, expected one of {}",
+                    FORMATS.join(", ")
+                ));
+            }
+        }
+        if config.threads == Some(0) {
+            return Err("threads must be at least 1".into());
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::Scene(format!("{}: {e}", path.display())))
+    }
+
+    // `$XDG_CONFIG_HOME/rtt.toml`, or `~/.config/rtt.toml`.
+    pub fn user_path() -> Option<PathBuf> {
+        let dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => home_dir()?.join(".config"),
+        };
+        Some(dir.join("rtt.toml"))
+    }
+
+    // The user's config, or the defaults when there is none.
+    pub fn user() -> Result<Self> {
+        match Self::user_path() {
+            Some(path) if path.is_file() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    // `output_dir` with `~` expanded.
+    pub fn output_dir(&self) -> Option<PathBuf> {
+        let dir = self.output_dir.as_ref()?;
+        match (dir.strip_prefix("~"), home_dir()) {
+            (Ok(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(dir.clone()),
+        }
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|h| !h.is_empty())
+        .map(PathBuf::from)
+}
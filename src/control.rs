@@ -0,0 +1,79 @@
+// Stopping and pausing a render from another thread, e.g. a GUI's stop button or a service
+// dropping a request. Render threads check in between pixels: a cancelled render merges what
+// it has into the film and returns `Error::Cancelled`, and a paused one waits until resumed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+// Cloned handles control the same render.
+#[derive(Clone, Default)]
+pub struct RenderControl {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    // Mirrors `paused` so running renders can check without locking.
+    pausing: AtomicBool,
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl RenderControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Stops the render, waking it if paused. Renders started afterwards stop at once until
+    // `reset`.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+        self.set_paused(false);
+    }
+
+    // Clears a cancel, so renders started afterwards run; a cancelled `Renderer` can then
+    // carry on with its next pass.
+    pub fn reset(&self) {
+        self.inner.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn pause(&self) {
+        self.set_paused(true);
+    }
+
+    pub fn resume(&self) {
+        self.set_paused(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.pausing.load(Ordering::Relaxed)
+    }
+
+    fn set_paused(&self, paused: bool) {
+        let mut guard = self.inner.paused.lock().unwrap_or_else(|e| e.into_inner());
+        *guard = paused;
+        self.inner.pausing.store(paused, Ordering::Relaxed);
+        if !paused {
+            self.inner.resumed.notify_all();
+        }
+    }
+
+    // Waits out a pause; false once the render should stop.
+    pub(crate) fn proceed(&self) -> bool {
+        if self.is_paused() {
+            let guard = self.inner.paused.lock().unwrap_or_else(|e| e.into_inner());
+            drop(
+                self.inner
+                    .resumed
+                    .wait_while(guard, |paused| *paused)
+                    .unwrap_or_else(|e| e.into_inner()),
+            );
+        }
+        !self.is_cancelled()
+    }
+}
@@ -0,0 +1,137 @@
+// Linear, unclipped output for photographic workflows: 32-bit float TIFF, and DNG so raw
+// developers treat the render like a camera file, with exposure and white balance still open.
+// Both hold the film's linear sRGB-primaries values as they are; the DNG's color matrix tells
+// the developer what those primaries are.
+
+use crate::error::{Error, Result};
+use image::Rgba32FImage;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tiff::encoder::colortype::{RGB32Float, RGBA32Float};
+use tiff::encoder::{Rational, SRational, TiffEncoder, TiffValue};
+use tiff::tags::{Tag, Type};
+use tiff::TiffError;
+
+// DNG tags, which `tiff` doesn't name.
+const DNG_VERSION: u16 = 50706;
+const DNG_BACKWARD_VERSION: u16 = 50707;
+const UNIQUE_CAMERA_MODEL: u16 = 50708;
+const COLOR_MATRIX_1: u16 = 50721;
+const AS_SHOT_NEUTRAL: u16 = 50728;
+const CALIBRATION_ILLUMINANT_1: u16 = 50778;
+// PhotometricInterpretation for demosaiced camera data.
+const LINEAR_RAW: u16 = 34892;
+// CalibrationIlluminant for D65, the sRGB white point.
+const D65: u16 = 21;
+// ExtraSamples for premultiplied alpha.
+const ASSOCIATED_ALPHA: u16 = 1;
+
+// Linear sRGB from CIE XYZ under D65, as `color::from_xyz`, in ten-thousandths.
+const XYZ_TO_SRGB: [i32; 9] = [32406, -15372, -4986, -9689, 18758, 415, 557, -2040, 10570];
+
+// Writes `image`, linear with premultiplied alpha as from `Film::develop_linear`, as a
+// 32-bit float RGBA TIFF.
+pub fn save_tiff(image: &Rgba32FImage, path: &Path) -> Result<()> {
+    let err = tiff_error(path);
+    // Failing to create the file is reported with its path, like any other write error.
+    let file = File::create(path).map_err(|e| err(e.into()))?;
+    let mut tiff = TiffEncoder::new(BufWriter::new(file)).map_err(err)?;
+    let mut encoder = tiff
+        .new_image::<RGBA32Float>(image.width(), image.height())
+        .map_err(err)?;
+    encoder
+        .encoder()
+        .write_tag(Tag::ExtraSamples, ASSOCIATED_ALPHA)
+        .map_err(err)?;
+    encoder.write_data(image.as_raw()).map_err(err)?;
+    tracing::debug!(path = %path.display(), "wrote float TIFF");
+    Ok(())
+}
+
+// Writes `image`'s color as a linear DNG: float samples of an already demosaiced "camera" whose
+// native space is linear sRGB, white balanced for D65. Alpha is dropped.
+pub fn save_dng(image: &Rgba32FImage, path: &Path) -> Result<()> {
+    let err = tiff_error(path);
+    let rgb: Vec<f32> = image
+        .pixels()
+        .flat_map(|px| [px.0[0], px.0[1], px.0[2]])
+        .collect();
+    let file = File::create(path).map_err(|e| err(e.into()))?;
+    let mut tiff = TiffEncoder::new(BufWriter::new(file)).map_err(err)?;
+    let mut encoder = tiff
+        .new_image::<RGB32Float>(image.width(), image.height())
+        .map_err(err)?;
+    let dir = encoder.encoder();
+    dir.write_tag(Tag::NewSubfileType, 0u32).map_err(err)?;
+    dir.write_tag(Tag::PhotometricInterpretation, LINEAR_RAW)
+        .map_err(err)?;
+    // Float samples need DNG 1.4.
+    dir.write_tag(Tag::Unknown(DNG_VERSION), &[1u8, 4, 0, 0][..])
+        .map_err(err)?;
+    dir.write_tag(Tag::Unknown(DNG_BACKWARD_VERSION), &[1u8, 4, 0, 0][..])
+        .map_err(err)?;
+    dir.write_tag(Tag::Unknown(UNIQUE_CAMERA_MODEL), "rtt")
+        .map_err(err)?;
+    let matrix = XYZ_TO_SRGB.map(|n| SRational { n, d: 10000 });
+    dir.write_tag(Tag::Unknown(COLOR_MATRIX_1), SRationals(&matrix))
+        .map_err(err)?;
+    dir.write_tag(Tag::Unknown(CALIBRATION_ILLUMINANT_1), D65)
+        .map_err(err)?;
+    let neutral: [_; 3] = std::array::from_fn(|_| Rational { n: 1, d: 1 });
+    dir.write_tag(Tag::Unknown(AS_SHOT_NEUTRAL), Rationals(&neutral))
+        .map_err(err)?;
+    encoder.write_data(&rgb).map_err(err)?;
+    tracing::debug!(path = %path.display(), "wrote DNG");
+    Ok(())
+}
+
+fn tiff_error(path: &Path) -> impl Fn(TiffError) -> Error + Copy + '_ {
+    move |source| Error::Tiff {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+// `tiff` only writes rationals one at a time; these write arrays of them.
+struct Rationals<'a>(&'a [Rational]);
+struct SRationals<'a>(&'a [SRational]);
+
+impl TiffValue for Rationals<'_> {
+    const BYTE_LEN: u8 = 8;
+    const FIELD_TYPE: Type = Type::RATIONAL;
+
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(
+            self.0
+                .iter()
+                .flat_map(|r| [r.n.to_ne_bytes(), r.d.to_ne_bytes()])
+                .flatten()
+                .collect(),
+        )
+    }
+}
+
+impl TiffValue for SRationals<'_> {
+    const BYTE_LEN: u8 = 8;
+    const FIELD_TYPE: Type = Type::SRATIONAL;
+
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(
+            self.0
+                .iter()
+                .flat_map(|r| [r.n.to_ne_bytes(), r.d.to_ne_bytes()])
+                .flatten()
+                .collect(),
+        )
+    }
+}
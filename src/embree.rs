@@ -0,0 +1,276 @@
+// Triangle meshes traced through Intel Embree: Embree builds the BVH and finds the closest
+// triangle, and the hit is handed back to Rust for shading like any other. Only the handful
+// of Embree 4 C entry points this needs are declared here, so building needs just the
+// library (`libembree4`) and no bindings generator. The distance is recomputed in double
+// precision on the triangle Embree found, so bounces don't pick up its single-precision error.
+
+use crate::aabb::Aabb;
+use crate::error::{Error, Result};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::MaterialId;
+use crate::mesh::{hit_triangle, Mesh};
+use crate::ray::Ray;
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::stats::SceneStats;
+use crate::vec3::Vec3;
+use std::ffi::{c_char, c_void};
+use std::sync::OnceLock;
+
+mod sys {
+    use std::ffi::{c_char, c_void};
+
+    pub type RTCDevice = *mut c_void;
+    pub type RTCScene = *mut c_void;
+    pub type RTCGeometry = *mut c_void;
+
+    pub const RTC_GEOMETRY_TYPE_TRIANGLE: u32 = 0;
+    pub const RTC_BUFFER_TYPE_INDEX: u32 = 0;
+    pub const RTC_BUFFER_TYPE_VERTEX: u32 = 1;
+    pub const RTC_FORMAT_UINT3: u32 = 0x5003;
+    pub const RTC_FORMAT_FLOAT3: u32 = 0x9003;
+    pub const RTC_INVALID_GEOMETRY_ID: u32 = u32::MAX;
+
+    #[repr(C, align(16))]
+    pub struct RTCRay {
+        pub org: [f32; 3],
+        pub tnear: f32,
+        pub dir: [f32; 3],
+        pub time: f32,
+        pub tfar: f32,
+        pub mask: u32,
+        pub id: u32,
+        pub flags: u32,
+    }
+
+    #[repr(C, align(16))]
+    pub struct RTCHit {
+        pub ng: [f32; 3],
+        pub u: f32,
+        pub v: f32,
+        pub prim_id: u32,
+        pub geom_id: u32,
+        pub inst_id: [u32; 1],
+        // Only written by builds with instance arrays (4.3 on), but reserved either way.
+        pub inst_prim_id: [u32; 1],
+    }
+
+    #[repr(C, align(16))]
+    pub struct RTCRayHit {
+        pub ray: RTCRay,
+        pub hit: RTCHit,
+    }
+
+    #[link(name = "embree4")]
+    extern "C" {
+        pub fn rtcNewDevice(config: *const c_char) -> RTCDevice;
+        pub fn rtcGetDeviceError(device: RTCDevice) -> u32;
+        pub fn rtcNewScene(device: RTCDevice) -> RTCScene;
+        pub fn rtcCommitScene(scene: RTCScene);
+        pub fn rtcReleaseScene(scene: RTCScene);
+        pub fn rtcNewGeometry(device: RTCDevice, kind: u32) -> RTCGeometry;
+        pub fn rtcSetNewGeometryBuffer(
+            geometry: RTCGeometry,
+            kind: u32,
+            slot: u32,
+            format: u32,
+            byte_stride: usize,
+            item_count: usize,
+        ) -> *mut c_void;
+        pub fn rtcCommitGeometry(geometry: RTCGeometry);
+        pub fn rtcAttachGeometry(scene: RTCScene, geometry: RTCGeometry) -> u32;
+        pub fn rtcReleaseGeometry(geometry: RTCGeometry);
+        pub fn rtcIntersect1(scene: RTCScene, rayhit: *mut RTCRayHit, args: *mut c_void);
+        pub fn rtcOccluded1(scene: RTCScene, ray: *mut RTCRay, args: *mut c_void);
+    }
+}
+
+// One device for the whole process, as Embree recommends, never released.
+struct Device(sys::RTCDevice);
+
+// Embree devices may be used from any thread.
+unsafe impl Send for Device {}
+unsafe impl Sync for Device {}
+
+impl Device {
+    fn shared() -> Result<&'static Device> {
+        static DEVICE: OnceLock<Device> = OnceLock::new();
+        let device = DEVICE.get_or_init(|| {
+            // SAFETY: a null configuration string selects the defaults.
+            Device(unsafe { sys::rtcNewDevice(std::ptr::null::<c_char>()) })
+        });
+        if device.0.is_null() {
+            // SAFETY: with a null device this reports why the last device creation failed.
+            let code = unsafe { sys::rtcGetDeviceError(std::ptr::null_mut()) };
+            return Err(Error::Scene(format!(
+                "could not create an Embree device (error {code})"
+            )));
+        }
+        Ok(device)
+    }
+}
+
+// A committed scene holding one triangle mesh.
+struct Scene(sys::RTCScene);
+
+// Committed Embree scenes are immutable and safe to trace from many threads at once.
+unsafe impl Send for Scene {}
+unsafe impl Sync for Scene {}
+
+impl Drop for Scene {
+    fn drop(&mut self) {
+        // SAFETY: the scene was created by `rtcNewScene` and is released once.
+        unsafe { sys::rtcReleaseScene(self.0) }
+    }
+}
+
+// A `Mesh` whose rays Embree traces. Saves, reports stats and bounds like the mesh it wraps.
+pub struct EmbreeMesh {
+    mesh: Mesh,
+    scene: Scene,
+}
+
+impl EmbreeMesh {
+    pub fn new(mesh: Mesh) -> Result<Self> {
+        let device = Device::shared()?;
+        let failed = |what: &str| {
+            // SAFETY: `device` is a live device.
+            let code = unsafe { sys::rtcGetDeviceError(device.0) };
+            Error::Scene(format!("Embree could not {what} (error {code})"))
+        };
+
+        // SAFETY: buffers are sized by Embree for exactly the items written into them, and
+        // every handle is checked before use and released once.
+        unsafe {
+            let scene = sys::rtcNewScene(device.0);
+            if scene.is_null() {
+                return Err(failed("create a scene"));
+            }
+            let scene = Scene(scene);
+            if mesh.indices.is_empty() {
+                sys::rtcCommitScene(scene.0);
+                return Ok(Self { mesh, scene });
+            }
+            let geometry = sys::rtcNewGeometry(device.0, sys::RTC_GEOMETRY_TYPE_TRIANGLE);
+            if geometry.is_null() {
+                return Err(failed("create a mesh"));
+            }
+            let vertices = sys::rtcSetNewGeometryBuffer(
+                geometry,
+                sys::RTC_BUFFER_TYPE_VERTEX,
+                0,
+                sys::RTC_FORMAT_FLOAT3,
+                3 * std::mem::size_of::<f32>(),
+                mesh.positions.len(),
+            ) as *mut [f32; 3];
+            let indices = sys::rtcSetNewGeometryBuffer(
+                geometry,
+                sys::RTC_BUFFER_TYPE_INDEX,
+                0,
+                sys::RTC_FORMAT_UINT3,
+                3 * std::mem::size_of::<u32>(),
+                mesh.indices.len(),
+            ) as *mut [u32; 3];
+            if vertices.is_null() || indices.is_null() {
+                sys::rtcReleaseGeometry(geometry);
+                return Err(failed("allocate mesh buffers"));
+            }
+            for (i, p) in mesh.positions.iter().enumerate() {
+                vertices.add(i).write([p.x as f32, p.y as f32, p.z as f32]);
+            }
+            std::ptr::copy_nonoverlapping(mesh.indices.as_ptr(), indices, mesh.indices.len());
+            sys::rtcCommitGeometry(geometry);
+            sys::rtcAttachGeometry(scene.0, geometry);
+            // The scene keeps its own reference.
+            sys::rtcReleaseGeometry(geometry);
+            sys::rtcCommitScene(scene.0);
+            Ok(Self { mesh, scene })
+        }
+    }
+}
+
+fn embree_ray(r: &Ray, ray_t: Interval) -> sys::RTCRay {
+    let (o, d) = (r.origin(), r.direction());
+    sys::RTCRay {
+        org: [o.x as f32, o.y as f32, o.z as f32],
+        tnear: ray_t.min as f32,
+        dir: [d.x as f32, d.y as f32, d.z as f32],
+        time: r.time() as f32,
+        tfar: ray_t.max as f32,
+        mask: u32::MAX,
+        id: 0,
+        flags: 0,
+    }
+}
+
+impl Hittable for EmbreeMesh {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if self.mesh.indices.is_empty() {
+            return None;
+        }
+        let mut rayhit = sys::RTCRayHit {
+            ray: embree_ray(r, ray_t),
+            hit: sys::RTCHit {
+                ng: [0.0; 3],
+                u: 0.0,
+                v: 0.0,
+                prim_id: sys::RTC_INVALID_GEOMETRY_ID,
+                geom_id: sys::RTC_INVALID_GEOMETRY_ID,
+                inst_id: [sys::RTC_INVALID_GEOMETRY_ID],
+                inst_prim_id: [sys::RTC_INVALID_GEOMETRY_ID],
+            },
+        };
+        // SAFETY: the scene is committed and `rayhit` is a properly aligned ray and hit.
+        unsafe { sys::rtcIntersect1(self.scene.0, &mut rayhit, std::ptr::null_mut::<c_void>()) };
+        if rayhit.hit.geom_id == sys::RTC_INVALID_GEOMETRY_ID {
+            return None;
+        }
+
+        let indices = self.mesh.indices[rayhit.hit.prim_id as usize];
+        let tri = self.mesh.vertices(indices);
+        let (t, outward_normal, bary) = hit_triangle(tri, r, ray_t).unwrap_or_else(|| {
+            // Right on an edge, where single and double precision disagree.
+            let [a, b, c] = tri;
+            let n = Vec3::cross(b - a, c - a);
+            let hit = &rayhit.hit;
+            (
+                rayhit.ray.tfar as f64,
+                Vec3::unit_vector(n),
+                [hit.u as f64, hit.v as f64],
+            )
+        });
+        Some(self.mesh.record(r, t, outward_normal, indices, bary))
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        if self.mesh.indices.is_empty() {
+            return false;
+        }
+        let mut ray = embree_ray(r, ray_t);
+        // SAFETY: the scene is committed and `ray` is a properly aligned ray.
+        unsafe { sys::rtcOccluded1(self.scene.0, &mut ray, std::ptr::null_mut::<c_void>()) };
+        // Embree marks an occluded ray by setting its far distance to minus infinity.
+        ray.tfar == f32::NEG_INFINITY
+    }
+
+    // Rare enough queries to leave to the Rust mesh.
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        self.mesh.hit_all_into(r, ray_t, out);
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.mesh.bounding_box(time0, time1)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        self.mesh.materials(out);
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        self.mesh.stats(stats);
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        self.mesh.to_desc(materials)
+    }
+}
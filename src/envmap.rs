@@ -0,0 +1,237 @@
+// Baking the scene's surroundings into environment maps and skyboxes for game engines and other
+// renderers: six 90 degree renders from one point, kept as separate faces, laid out as a
+// horizontal cross, or resampled into an equirectangular panorama that `Hdri` reads back.
+//
+// Faces are upright as seen from inside the cube, +y up on the four sides. The cross is
+//
+//          +y
+//     -x   -z   +x   +z
+//          -y
+//
+// so it folds into the cube, with the front face the way cameras look by default.
+
+use crate::aov::AovSet;
+use crate::camera::Camera;
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::hittable::Hittable;
+use crate::render::{render_image_with, RenderSettings};
+use crate::vec3::{Color, Point3, Vec3};
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PosX,
+        CubeFace::NegX,
+        CubeFace::PosY,
+        CubeFace::NegY,
+        CubeFace::PosZ,
+        CubeFace::NegZ,
+    ];
+
+    // File name suffix, as most engines expect.
+    pub fn name(self) -> &'static str {
+        match self {
+            CubeFace::PosX => "px",
+            CubeFace::NegX => "nx",
+            CubeFace::PosY => "py",
+            CubeFace::NegY => "ny",
+            CubeFace::PosZ => "pz",
+            CubeFace::NegZ => "nz",
+        }
+    }
+
+    // The direction the face looks and the one up its image. The top face has -z at its
+    // bottom edge and the bottom face at its top edge, where they meet the front.
+    pub fn basis(self) -> (Vec3, Vec3) {
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        match self {
+            CubeFace::PosX => (Vec3::new(1.0, 0.0, 0.0), y),
+            CubeFace::NegX => (Vec3::new(-1.0, 0.0, 0.0), y),
+            CubeFace::PosY => (y, Vec3::new(0.0, 0.0, 1.0)),
+            CubeFace::NegY => (-y, Vec3::new(0.0, 0.0, -1.0)),
+            CubeFace::PosZ => (Vec3::new(0.0, 0.0, 1.0), y),
+            CubeFace::NegZ => (Vec3::new(0.0, 0.0, -1.0), y),
+        }
+    }
+
+    pub fn camera(self, center: Point3) -> Camera {
+        let (forward, up) = self.basis();
+        Camera::new(center, center + forward, up, 90.0, 1.0, 0.0, 1.0)
+    }
+
+    // Column and row in the cross.
+    fn cross_cell(self) -> (u32, u32) {
+        match self {
+            CubeFace::NegX => (0, 1),
+            CubeFace::NegZ => (1, 1),
+            CubeFace::PosX => (2, 1),
+            CubeFace::PosZ => (3, 1),
+            CubeFace::PosY => (1, 0),
+            CubeFace::NegY => (1, 2),
+        }
+    }
+
+    // Where the unit direction `d` lands on the face, as film coordinates with t up.
+    fn film_coords(self, d: Vec3) -> (f64, f64) {
+        let (forward, up) = self.basis();
+        let right = Vec3::cross(forward, up);
+        let p = d / Vec3::dot(d, forward);
+        (
+            0.5 * (Vec3::dot(p, right) + 1.0),
+            0.5 * (Vec3::dot(p, up) + 1.0),
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EnvLayout {
+    #[default]
+    Cross,
+    // Six images, `<name>_px` to `<name>_nz`.
+    Faces,
+    // Twice as wide as tall, mapped like `Hdri`.
+    Equirect,
+}
+
+impl EnvLayout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cross" => Some(EnvLayout::Cross),
+            "faces" => Some(EnvLayout::Faces),
+            "equirect" => Some(EnvLayout::Equirect),
+            _ => None,
+        }
+    }
+}
+
+pub struct Cubemap {
+    size: u32,
+    // In `CubeFace::ALL` order.
+    faces: Vec<Film>,
+}
+
+impl Cubemap {
+    // Renders the faces `size` pixels square from `center`.
+    pub fn render(
+        world: &dyn Hittable,
+        center: Point3,
+        size: u32,
+        samples: u32,
+        settings: &RenderSettings,
+    ) -> Result<Self> {
+        if size == 0 {
+            return Err(Error::Scene("cubemap faces need at least one pixel".into()));
+        }
+        let mut faces = Vec::with_capacity(6);
+        for face in CubeFace::ALL {
+            let film = Film::new(size, size);
+            let aovs = AovSet::new(&[], world, size, size);
+            let camera = face.camera(center);
+            render_image_with(world, &camera, &film, &aovs, samples, settings, &|_| {})?;
+            tracing::debug!(face = face.name(), "cubemap face rendered");
+            faces.push(film);
+        }
+        Ok(Self { size, faces })
+    }
+
+    #[inline]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn face(&self, face: CubeFace) -> &Film {
+        let i = CubeFace::ALL.iter().position(|&f| f == face).unwrap_or(0);
+        &self.faces[i]
+    }
+
+    // The faces laid out as a 4x3 cross; the empty corners are transparent.
+    pub fn cross(&self) -> Result<Film> {
+        let mut out = Film::new(4 * self.size, 3 * self.size);
+        for face in CubeFace::ALL {
+            let (col, row) = face.cross_cell();
+            let film = self.face(face);
+            for y in 0..self.size {
+                for x in 0..self.size {
+                    let p = film.pixel(x, y)?;
+                    let (ox, oy) = (col * self.size + x, row * self.size + y);
+                    out.add_sample_alpha(ox, oy, p.resolve(1.0), p.alpha(), 1.0)?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    // A `width` by `width / 2` panorama, each pixel filtered bilinearly from the face its
+    // direction falls on.
+    pub fn equirect(&self, width: u32) -> Result<Film> {
+        let height = (width / 2).max(1);
+        let faces = self
+            .faces
+            .iter()
+            .map(|f| {
+                let colors = f.resolve(1.0)?;
+                let alphas = (0..self.size * self.size)
+                    .map(|i| Ok(f.pixel(i % self.size, i / self.size)?.alpha()))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((colors, alphas))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut out = Film::new(width, height);
+        for y in 0..height {
+            let theta = (y as f64 + 0.5) / height as f64 * PI;
+            for x in 0..width {
+                let phi = ((x as f64 + 0.5) / width as f64 - 0.5) * 2.0 * PI;
+                let d = Vec3::new(
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                    -theta.sin() * phi.cos(),
+                );
+                let (i, face) = CubeFace::ALL
+                    .into_iter()
+                    .enumerate()
+                    .max_by(|(_, a), (_, b)| {
+                        Vec3::dot(d, a.basis().0).total_cmp(&Vec3::dot(d, b.basis().0))
+                    })
+                    .unwrap_or((0, CubeFace::PosX));
+                let (s, t) = face.film_coords(d);
+                let (colors, alphas) = &faces[i];
+                let (color, alpha) = self.bilinear(colors, alphas, s, t);
+                out.add_sample_alpha(x, y, color, alpha, 1.0)?;
+            }
+        }
+        Ok(out)
+    }
+
+    fn bilinear(&self, colors: &[Color], alphas: &[f64], s: f64, t: f64) -> (Color, f64) {
+        let n = self.size as f64;
+        let fx = (s * n - 0.5).clamp(0.0, n - 1.0);
+        let fy = ((1.0 - t) * n - 0.5).clamp(0.0, n - 1.0);
+        let (x0, y0) = (fx.floor() as u32, fy.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(self.size - 1), (y0 + 1).min(self.size - 1));
+        let (ax, ay) = (fx - x0 as f64, fy - y0 as f64);
+        let mut color = Color::default();
+        let mut alpha = 0.0;
+        for (x, y, w) in [
+            (x0, y0, (1.0 - ax) * (1.0 - ay)),
+            (x1, y0, ax * (1.0 - ay)),
+            (x0, y1, (1.0 - ax) * ay),
+            (x1, y1, ax * ay),
+        ] {
+            let i = (y * self.size + x) as usize;
+            color += w * colors[i];
+            alpha += w * alphas[i];
+        }
+        (color, alpha)
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to write {}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
+
+    #[error("failed to write {}: {source}", path.display())]
+    Exr {
+        path: PathBuf,
+        #[source]
+        source: exr::error::Error,
+    },
+
+    #[error("failed to write {}: {source}", path.display())]
+    Tiff {
+        path: PathBuf,
+        #[source]
+        source: tiff::TiffError,
+    },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid scene file: {0}")]
+    Json(#[from] serde_json::Error),
+
+    // Well-formed scene file that doesn't describe a valid scene, e.g. a dangling material index.
+    #[error("invalid scene: {0}")]
+    Scene(String),
+
+    #[error("video encoding failed: {0}")]
+    Encoder(String),
+
+    #[error("GPU backend failed: {0}")]
+    Gpu(String),
+
+    #[error("watching for changes failed: {0}")]
+    Watch(String),
+
+    #[error("over the memory budget: {0}")]
+    Memory(String),
+
+    // Pixel coordinates past the edge of a film.
+    #[error("pixel ({x}, {y}) is outside the {width}x{height} film")]
+    OutOfBounds {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+
+    // Stopped through a `RenderControl`.
+    #[error("render cancelled")]
+    Cancelled,
+
+    // A render thread panicked while holding a buffer lock, so its contents can't be trusted.
+    #[error("{0} lock poisoned by a panicked render thread")]
+    Poisoned(&'static str),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // For `map_err` on image writes, keeping the path in the message.
+    pub(crate) fn image(path: &Path) -> impl FnOnce(image::ImageError) -> Self + '_ {
+        move |source| Error::Image {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Physical units for light and exposure, so a scene lit by a 800 lm lamp and shot at ISO 100,
+// 1/60 s, f/2.8 comes out as bright as a photo would. Area lights may give their power in
+// lumens or watts instead of a radiance, converted with the scene's declared size of a unit
+// (`SceneDesc::meters_per_unit`); radiance is then luminance in cd/m², and a camera's
+// `PhysicalExposure` scales it for display. Without either, values stay as they are.
+
+use crate::color::luminance;
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::vec3::Color;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+// Luminous efficacy of 555 nm light, which renderers taking watts conventionally assume.
+pub const LUMENS_PER_WATT: f64 = 683.0;
+
+// Exposure triangle of a camera. The shutter here only sets brightness; motion blur comes from
+// `Shutter`.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhysicalExposure {
+    pub iso: f64,
+    // Seconds.
+    pub shutter: f64,
+    pub f_number: f64,
+}
+
+impl PhysicalExposure {
+    pub fn new(iso: f64, shutter: f64, f_number: f64) -> Result<Self> {
+        let exposure = Self {
+            iso,
+            shutter,
+            f_number,
+        };
+        exposure.check()?;
+        Ok(exposure)
+    }
+
+    // Scene files aren't checked on load.
+    fn check(&self) -> Result<()> {
+        let Self {
+            iso,
+            shutter,
+            f_number,
+        } = *self;
+        if !(iso > 0.0 && shutter > 0.0 && f_number > 0.0) {
+            return Err(Error::Scene(format!(
+                "exposure needs a positive ISO, shutter time and f-number, got ISO {iso}, \
+                 {shutter} s, f/{f_number}"
+            )));
+        }
+        Ok(())
+    }
+
+    // Exposure value at ISO 100: 0 for 1 s at f/1, one more per stop less light.
+    pub fn ev100(&self) -> f64 {
+        (self.f_number * self.f_number / self.shutter * 100.0 / self.iso).log2()
+    }
+
+    // What scene luminance is multiplied by for the film, by the saturation-based ISO
+    // standard: the luminance that just clips is 1.2 * 2^EV100 cd/m² (78 / (100 * 0.65)).
+    pub fn scale(&self) -> f64 {
+        1.0 / (1.2 * self.ev100().exp2())
+    }
+
+    // A copy of `film` exposed, to develop with the same `splat_scale`. Before bloom and the
+    // look, which expect display-referred brightness.
+    pub fn apply(&self, film: &Film, splat_scale: f64) -> Result<Film> {
+        self.check()?;
+        let scale = self.scale();
+        let colors: Vec<Color> = film
+            .resolve(splat_scale)?
+            .into_iter()
+            .map(|c| scale * c)
+            .collect();
+        film.with_colors(&colors, splat_scale)
+    }
+}
+
+// Light output of an emitter, instead of its radiance.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LightPower {
+    Lumens(f64),
+    // Radiant watts, at `LUMENS_PER_WATT`.
+    Watts(f64),
+}
+
+impl LightPower {
+    pub fn lumens(self) -> f64 {
+        match self {
+            LightPower::Lumens(lm) => lm,
+            LightPower::Watts(w) => w * LUMENS_PER_WATT,
+        }
+    }
+
+    // Radiance of a diffuse emitter of `color` and `area` square meters putting out this power
+    // from one side, or from both: its color scaled to luminance power / (pi * area).
+    pub fn radiance(self, color: Color, area: f64, two_sided: bool) -> Color {
+        let sides = if two_sided { 2.0 } else { 1.0 };
+        let l = luminance(color);
+        if l <= 0.0 || area <= 0.0 {
+            return Color::default();
+        }
+        color * (self.lumens() / (PI * area * sides) / l)
+    }
+}
@@ -0,0 +1,352 @@
+use crate::dng;
+use crate::error::{Error, Result};
+use crate::vec3::Color;
+use image::{DynamicImage, Rgba, Rgba32FImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pixel {
+    pub color_sum: Color,
+    pub alpha_sum: f64,
+    pub weight_sum: f64,
+    pub splat: Color,
+}
+
+impl Pixel {
+    #[inline]
+    pub fn add_sample(&mut self, color: Color, alpha: f64, weight: f64) {
+        self.color_sum += weight * color;
+        self.alpha_sum += weight * alpha;
+        self.weight_sum += weight;
+    }
+
+    #[inline]
+    pub fn merge(&mut self, other: &Pixel) {
+        self.color_sum += other.color_sum;
+        self.alpha_sum += other.alpha_sum;
+        self.weight_sum += other.weight_sum;
+        self.splat += other.splat;
+    }
+
+    // False once a NaN or infinite sample has poisoned the sums.
+    #[inline]
+    pub fn is_finite(&self) -> bool {
+        let c = self.color_sum + self.splat;
+        [c.r(), c.g(), c.b(), self.alpha_sum]
+            .iter()
+            .all(|v| v.is_finite())
+    }
+
+    // Coverage of the pixel; empty pixels count as transparent.
+    #[inline]
+    pub fn alpha(&self) -> f64 {
+        if self.weight_sum > 0.0 {
+            (self.alpha_sum / self.weight_sum).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    #[inline]
+    pub fn resolve(&self, splat_scale: f64) -> Color {
+        let mut col = if self.weight_sum > 0.0 {
+            self.color_sum / self.weight_sum
+        } else {
+            Color::default()
+        };
+        col += splat_scale * self.splat;
+        col
+    }
+}
+
+// Image-space accumulation buffer. (0, 0) is the top-left pixel.
+pub struct Film {
+    width: u32,
+    height: u32,
+    pixels: Mutex<Vec<Pixel>>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: Mutex::new(vec![Pixel::default(); width as usize * height as usize]),
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> Result<usize> {
+        if x >= self.width || y >= self.height {
+            return Err(Error::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(y as usize * self.width as usize + x as usize)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Vec<Pixel>>> {
+        self.pixels.lock().map_err(|_| Error::Poisoned("film"))
+    }
+
+    pub fn add_sample(&mut self, x: u32, y: u32, color: Color, weight: f64) -> Result<()> {
+        self.add_sample_alpha(x, y, color, 1.0, weight)
+    }
+
+    pub fn add_sample_alpha(
+        &mut self,
+        x: u32,
+        y: u32,
+        color: Color,
+        alpha: f64,
+        weight: f64,
+    ) -> Result<()> {
+        let idx = self.index(x, y)?;
+        let pixels = self.pixels.get_mut().map_err(|_| Error::Poisoned("film"))?;
+        pixels[idx].add_sample(color, alpha, weight);
+        Ok(())
+    }
+
+    // Unweighted contribution, e.g. from light tracing. Scaled by `splat_scale` on develop.
+    pub fn add_splat(&self, x: f64, y: f64, color: Color) -> Result<()> {
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return Ok(());
+        }
+        let idx = self.index(x as u32, y as u32)?;
+        self.lock()?[idx].splat += color;
+        Ok(())
+    }
+
+    // `add_splat` for many contributions under one lock.
+    pub fn add_splats(&self, splats: &[(f64, f64, Color)]) -> Result<()> {
+        let mut pixels = self.lock()?;
+        for &(x, y, color) in splats {
+            if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+                continue;
+            }
+            pixels[self.index(x as u32, y as u32)?].splat += color;
+        }
+        Ok(())
+    }
+
+    // Clamped to the film, so a rectangle partly or wholly past its edge gives a smaller or
+    // empty tile.
+    pub fn tile(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> FilmTile {
+        let x1 = x1.min(self.width);
+        let y1 = y1.min(self.height);
+        let x0 = x0.min(x1);
+        let y0 = y0.min(y1);
+        FilmTile {
+            x0,
+            y0,
+            x1,
+            y1,
+            pixels: vec![Pixel::default(); (x1 - x0) as usize * (y1 - y0) as usize],
+        }
+    }
+
+    pub fn merge_tile(&self, tile: FilmTile) -> Result<()> {
+        let mut pixels = self.lock()?;
+        let tile_width = (tile.x1 - tile.x0) as usize;
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                let src =
+                    &tile.pixels[(y - tile.y0) as usize * tile_width + (x - tile.x0) as usize];
+                pixels[self.index(x, y)?].merge(src);
+            }
+        }
+        Ok(())
+    }
+
+    // Copy of the current accumulation, safe to develop while rendering continues.
+    pub fn snapshot(&self) -> Result<Film> {
+        Ok(Film {
+            width: self.width,
+            height: self.height,
+            pixels: Mutex::new(self.lock()?.clone()),
+        })
+    }
+
+    // Copy that resolves to `colors` at `splat_scale`, which must be positive, keeping each
+    // pixel's alpha and sample weights. For post effects; the difference goes into the splats.
+    pub fn with_colors(&self, colors: &[Color], splat_scale: f64) -> Result<Film> {
+        let out = self.snapshot()?;
+        for (p, &c) in out.lock()?.iter_mut().zip(colors) {
+            p.splat += (c - p.resolve(splat_scale)) / splat_scale;
+        }
+        Ok(out)
+    }
+
+    // Discards everything accumulated so far.
+    pub fn clear(&self) -> Result<()> {
+        self.lock()?.fill(Pixel::default());
+        Ok(())
+    }
+
+    // Discards what pixel (x, y) accumulated, e.g. before re-rendering it.
+    pub fn clear_pixel(&self, x: u32, y: u32) -> Result<()> {
+        let idx = self.index(x, y)?;
+        self.lock()?[idx] = Pixel::default();
+        Ok(())
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Result<Pixel> {
+        Ok(self.lock()?[self.index(x, y)?])
+    }
+
+    pub fn resolve(&self, splat_scale: f64) -> Result<Vec<Color>> {
+        let pixels = self.lock()?;
+        Ok(pixels.iter().map(|p| p.resolve(splat_scale)).collect())
+    }
+
+    // Colors are premultiplied by alpha (held-out samples are black), so unpremultiply for PNG.
+    pub fn develop(&self, splat_scale: f64) -> Result<RgbaImage> {
+        self.develop_region((0, 0, self.width, self.height), splat_scale)
+    }
+
+    // Like `develop`, for the rectangle (x0, y0, x1, y1) only, e.g. a tile that just finished.
+    pub fn develop_region(
+        &self,
+        (x0, y0, x1, y1): (u32, u32, u32, u32),
+        splat_scale: f64,
+    ) -> Result<RgbaImage> {
+        let x1 = x1.min(self.width);
+        let y1 = y1.min(self.height);
+        let pixels = self.lock()?;
+        let mut img = RgbaImage::new(x1.saturating_sub(x0), y1.saturating_sub(y0));
+        for (x, y, px) in img.enumerate_pixels_mut() {
+            let p = &pixels[self.index(x0 + x, y0 + y)?];
+            let alpha = p.alpha();
+            let col = p.resolve(splat_scale);
+            *px = if alpha > 0.0 && alpha < 1.0 {
+                to_rgba(col / alpha, alpha)
+            } else {
+                to_rgba(col, alpha)
+            };
+        }
+        Ok(img)
+    }
+
+    // Develops and writes the image, format chosen by the file extension. `.tif`, `.tiff`,
+    // `.dng`, `.exr` and `.hdr` get the linear float image; see `dng`. `.hdr` drops alpha.
+    pub fn save(&self, path: &Path, splat_scale: f64) -> Result<()> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("tif" | "tiff") => {
+                return dng::save_tiff(&self.develop_linear(splat_scale)?, path)
+            }
+            Some("dng") => return dng::save_dng(&self.develop_linear(splat_scale)?, path),
+            Some("exr") => {
+                return self
+                    .develop_linear(splat_scale)?
+                    .save(path)
+                    .map_err(Error::image(path))
+            }
+            Some("hdr") => {
+                let img = DynamicImage::ImageRgba32F(self.develop_linear(splat_scale)?);
+                return img.to_rgb32f().save(path).map_err(Error::image(path));
+            }
+            _ => {}
+        }
+        self.develop(splat_scale)?
+            .save(path)
+            .map_err(Error::image(path))?;
+        tracing::debug!(path = %path.display(), "wrote film");
+        Ok(())
+    }
+
+    // Linear float image with premultiplied alpha, e.g. for EXR.
+    pub fn develop_linear(&self, splat_scale: f64) -> Result<Rgba32FImage> {
+        let pixels = self.lock()?;
+        let mut img = Rgba32FImage::new(self.width, self.height);
+        for (px, p) in img.pixels_mut().zip(pixels.iter()) {
+            let col = p.resolve(splat_scale);
+            *px = Rgba([
+                col.r() as f32,
+                col.g() as f32,
+                col.b() as f32,
+                p.alpha() as f32,
+            ]);
+        }
+        Ok(img)
+    }
+
+    // Linear float image; alpha is the fraction of `total`'s sample weight that landed here.
+    pub fn develop_coverage(&self, total: &Film) -> Result<Rgba32FImage> {
+        let pixels = self.lock()?;
+        let totals = total.lock()?;
+        let mut img = Rgba32FImage::new(self.width, self.height);
+        for ((px, p), t) in img.pixels_mut().zip(pixels.iter()).zip(totals.iter()) {
+            let col = p.resolve(0.0);
+            let coverage = if t.weight_sum > 0.0 {
+                (p.weight_sum / t.weight_sum).min(1.0)
+            } else {
+                0.0
+            };
+            *px = Rgba([
+                col.r() as f32,
+                col.g() as f32,
+                col.b() as f32,
+                coverage as f32,
+            ]);
+        }
+        Ok(img)
+    }
+}
+
+pub struct FilmTile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+    pixels: Vec<Pixel>,
+}
+
+impl FilmTile {
+    #[inline]
+    pub fn bounds(&self) -> (u32, u32, u32, u32) {
+        (self.x0, self.y0, self.x1, self.y1)
+    }
+
+    pub fn add_sample(&mut self, x: u32, y: u32, color: Color, weight: f64) {
+        self.add_sample_alpha(x, y, color, 1.0, weight);
+    }
+
+    pub fn add_sample_alpha(&mut self, x: u32, y: u32, color: Color, alpha: f64, weight: f64) {
+        let idx = (y - self.y0) as usize * (self.x1 - self.x0) as usize + (x - self.x0) as usize;
+        self.pixels[idx].add_sample(color, alpha, weight);
+    }
+}
+
+#[inline]
+pub fn clamp_u8(x: f64) -> u8 {
+    let x = x.clamp(0.0, 0.999);
+    (255.99 * x) as u8
+}
+
+// Post pipeline: gamma correction, then quantize.
+#[inline]
+pub fn to_rgba(col: Color, alpha: f64) -> Rgba<u8> {
+    let col = Color::new(col.r().sqrt(), col.g().sqrt(), col.b().sqrt());
+    Rgba([
+        clamp_u8(col.r()),
+        clamp_u8(col.g()),
+        clamp_u8(col.b()),
+        (255.0 * alpha.clamp(0.0, 1.0)).round() as u8,
+    ])
+}
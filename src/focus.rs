@@ -0,0 +1,105 @@
+// A quick look at what a camera keeps in focus, for setting aperture and focus distance
+// without full renders. Each pixel's center is traced once, from the middle of the lens, and
+// colored by where its depth falls against the camera's `FocusLimits`, shaded by how squarely
+// the surface faces the camera so shapes stay readable.
+
+use crate::camera::{Camera, FocusLimits};
+use crate::error::{Error, Result};
+use crate::hittable::Hittable;
+use crate::interval::Interval;
+use crate::render::T_MIN;
+use crate::vec3::Vec3;
+use image::{Rgb, RgbImage};
+use rayon::prelude::*;
+use std::path::Path;
+
+pub const NEAR_COLOR: Rgb<u8> = Rgb([70, 120, 255]);
+pub const IN_FOCUS_COLOR: Rgb<u8> = Rgb([70, 220, 70]);
+pub const FAR_COLOR: Rgb<u8> = Rgb([255, 90, 60]);
+
+// How much of the view is in front of, within and behind the depth of field, as fractions
+// of the pixels. The background counts as infinitely far.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FocusCoverage {
+    pub near: f64,
+    pub in_focus: f64,
+    pub far: f64,
+}
+
+// The view of a `width`x`height` render in which `max_blur` pixels of blur count as sharp.
+pub fn focus_view(
+    world: &dyn Hittable,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    max_blur: f64,
+) -> (RgbImage, FocusCoverage) {
+    let limits = camera.focus_limits(height, max_blur);
+    let pixels: Vec<(Zone, f64)> = (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let s = (x as f64 + 0.5) / width as f64;
+            let t = (height as f64 - y as f64 - 0.5) / height as f64;
+            let ray = camera.pinhole_ray(s, t);
+            let ray_t = camera.clip_range(&ray, Interval::new(T_MIN, f64::INFINITY));
+            match world.hit(&ray, ray_t) {
+                Some(rec) => {
+                    let facing = Vec3::dot(rec.normal, Vec3::unit_vector(ray.direction())).abs();
+                    (
+                        Zone::new(&limits, camera.depth(rec.point)),
+                        0.3 + 0.7 * facing,
+                    )
+                }
+                None => (Zone::new(&limits, f64::INFINITY), 0.2),
+            }
+        })
+        .collect();
+
+    let mut coverage = FocusCoverage::default();
+    let share = 1.0 / pixels.len().max(1) as f64;
+    let mut img = RgbImage::new(width, height);
+    for (i, &(zone, shade)) in pixels.iter().enumerate() {
+        let (count, Rgb(c)) = match zone {
+            Zone::Near => (&mut coverage.near, NEAR_COLOR),
+            Zone::InFocus => (&mut coverage.in_focus, IN_FOCUS_COLOR),
+            Zone::Far => (&mut coverage.far, FAR_COLOR),
+        };
+        *count += share;
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        img.put_pixel(x, y, Rgb(c.map(|v| (v as f64 * shade).round() as u8)));
+    }
+    (img, coverage)
+}
+
+#[derive(Copy, Clone)]
+enum Zone {
+    Near,
+    InFocus,
+    Far,
+}
+
+impl Zone {
+    fn new(limits: &FocusLimits, depth: f64) -> Self {
+        if depth < limits.near {
+            Zone::Near
+        } else if limits.contains(depth) {
+            Zone::InFocus
+        } else {
+            Zone::Far
+        }
+    }
+}
+
+pub fn save_focus_view(
+    world: &dyn Hittable,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    max_blur: f64,
+    path: &Path,
+) -> Result<FocusCoverage> {
+    let (img, coverage) = focus_view(world, camera, width, height, max_blur);
+    img.save(path).map_err(Error::image(path))?;
+    Ok(coverage)
+}
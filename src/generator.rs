@@ -0,0 +1,186 @@
+// Reproducible random scenes in the style of the book cover: small spheres with random
+// materials scattered on a huge ground sphere around three large feature spheres. The same
+// seed and settings always give the same scene, so stress tests and benchmarks can pin one.
+
+use crate::hittable::{HittableList, Sphere};
+use crate::material::{Dielectric, Lambertian, Material, Metal};
+use crate::scatter::Scatter;
+use crate::vec3::{Point3, Vec3};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+// Fraction of small spheres being diffuse, metal or glass.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MaterialWeights {
+    pub diffuse: f64,
+    pub metal: f64,
+    pub glass: f64,
+}
+
+impl Default for MaterialWeights {
+    fn default() -> Self {
+        Self {
+            diffuse: 0.8,
+            metal: 0.15,
+            glass: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneGenerator {
+    seed: u64,
+    count: usize,
+    radius: (f64, f64),
+    weights: MaterialWeights,
+    feature_spheres: bool,
+}
+
+impl SceneGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            count: 400,
+            radius: (0.2, 0.2),
+            weights: MaterialWeights::default(),
+            feature_spheres: true,
+        }
+    }
+
+    // Number of small spheres; the ground area grows to fit them.
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    // Small sphere radii are drawn uniformly from [min, max].
+    pub fn with_radius(mut self, min: f64, max: f64) -> Self {
+        self.radius = (min.min(max), min.max(max));
+        self
+    }
+
+    // Relative weights; they needn't sum to one.
+    pub fn with_weights(mut self, weights: MaterialWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    // Whether to add the large glass, diffuse and metal spheres at the centre.
+    pub fn with_feature_spheres(mut self, feature_spheres: bool) -> Self {
+        self.feature_spheres = feature_spheres;
+        self
+    }
+
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn generate(&self) -> HittableList {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut world = HittableList::new();
+
+        let ground_mat: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        world.add(Sphere::new(
+            Point3::new(0.0, -1000.0, 0.0),
+            1000.0,
+            ground_mat,
+        ));
+
+        // Same gap between neighbours as the original 0.2-radius spheres on a 0.9 spacing.
+        let (r_min, r_max) = self.radius;
+        let spacing = 2.0 * r_max + 0.5;
+        let features = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(-4.0, 0.0, 0.0),
+            Point3::new(4.0, 0.0, 0.0),
+        ];
+        let clearance = 1.0 + r_max + 0.2;
+
+        // Poisson-disk sets fill a bit over half the hexagonal packing density; leave margin
+        // for that and for the clearing around the feature spheres, then trim to `count`.
+        let cell_area = spacing * spacing * 3f64.sqrt() / 2.0;
+        let mut area = self.count as f64 * cell_area / 0.5;
+        if self.feature_spheres {
+            area += 3.0 * std::f64::consts::PI * clearance * clearance;
+        }
+        let half = 0.5 * area.sqrt();
+        let feature_spheres = self.feature_spheres;
+        let mut centers = Scatter::new(spacing)
+            .with_density(move |p| {
+                let clear = !feature_spheres
+                    || features
+                        .iter()
+                        .all(|&c| (Point3::new(p.x, 0.0, p.z) - c).length() >= clearance);
+                if clear {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .plane(
+                Point3::new(-half, 0.0, -half),
+                Point3::new(half, 0.0, half),
+                &mut rng,
+            );
+        // Points come out in growth order, so shuffle before trimming to keep the spread even.
+        centers.shuffle(&mut rng);
+        centers.truncate(self.count);
+
+        let MaterialWeights {
+            diffuse,
+            metal,
+            glass,
+        } = self.weights;
+        let total = (diffuse + metal + glass).max(f64::MIN_POSITIVE);
+        for base in centers {
+            let radius = if r_max > r_min {
+                rng.random_range(r_min..=r_max)
+            } else {
+                r_min
+            };
+            let center = base + Vec3::new(0.0, radius, 0.0);
+            let choose_mat = rng.random::<f64>() * total;
+            let mat: Arc<dyn Material> = if choose_mat < diffuse {
+                let albedo = Vec3::new(
+                    rng.random::<f64>() * rng.random::<f64>(),
+                    rng.random::<f64>() * rng.random::<f64>(),
+                    rng.random::<f64>() * rng.random::<f64>(),
+                );
+                Arc::new(Lambertian::new(albedo))
+            } else if choose_mat < diffuse + metal {
+                let albedo = Vec3::new(
+                    0.5 * (1.0 + rng.random::<f64>()),
+                    0.5 * (1.0 + rng.random::<f64>()),
+                    0.5 * (1.0 + rng.random::<f64>()),
+                );
+                Arc::new(Metal::new(albedo, 0.1))
+            } else {
+                Arc::new(Dielectric::new(1.5))
+            };
+            world.add(Sphere::new(center, radius, mat));
+        }
+
+        if self.feature_spheres {
+            world.add(Sphere::new(
+                Point3::new(0.0, 1.0, 0.0),
+                1.0,
+                Arc::new(Dielectric::new(1.5)),
+            ));
+            world.add(Sphere::new(
+                Point3::new(-4.0, 1.0, 0.0),
+                1.0,
+                Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1))),
+            ));
+            world.add(Sphere::new(
+                Point3::new(4.0, 1.0, 0.0),
+                1.0,
+                Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0)),
+            ));
+        }
+
+        world
+    }
+}
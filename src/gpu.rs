@@ -0,0 +1,904 @@
+// Hardware ray tracing on Vulkan GPUs with ray queries (RTX, RDNA 2 on, Arc). Triangle meshes
+// go into acceleration structures the GPU builds, and each call to `GpuScene::intersect` sends
+// a batch of rays through a compute shader (`shaders/intersect.comp`) that finds their closest
+// hits. Hits come back as `HitRecord`s to shade on the CPU as usual; added to a world, a
+// `GpuScene` has `render_image_with` find each row's camera ray hits in one dispatch. Memory
+// is host-visible where the CPU touches it and device-local otherwise; the distance is
+// recomputed in double precision on the triangle found, as for Embree.
+
+use crate::aabb::Aabb;
+use crate::error::{Error, Result};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::MaterialId;
+use crate::mesh::{hit_triangle, Mesh};
+use crate::ray::Ray;
+use crate::stats::SceneStats;
+use crate::vec3::Vec3;
+use ash::vk;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Threads per workgroup, as in the shader.
+const WORKGROUP_SIZE: u32 = 64;
+
+// SPIR-V compiled by the build script; empty if no shader compiler was found.
+static SHADER: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/intersect.spv"));
+
+// Laid out as `Ray` in the shader.
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+struct GpuRay {
+    origin: [f32; 3],
+    t_min: f32,
+    direction: [f32; 3],
+    t_max: f32,
+}
+
+// Laid out as `Hit` in the shader; a negative `t` is a miss.
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+struct GpuHit {
+    t: f32,
+    instance: u32,
+    primitive: u32,
+    pad: u32,
+}
+
+fn failed(what: &'static str) -> impl FnOnce(vk::Result) -> Error {
+    move |e| Error::Gpu(format!("could not {what}: {e}"))
+}
+
+struct Buffer {
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    address: vk::DeviceAddress,
+}
+
+struct AccelerationStructure {
+    handle: vk::AccelerationStructureKHR,
+    buffer: Buffer,
+    address: vk::DeviceAddress,
+}
+
+// Instance, device and the queue everything is submitted to.
+struct Context {
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    device: ash::Device,
+    acceleration: ash::khr::acceleration_structure::Device,
+    memory: vk::PhysicalDeviceMemoryProperties,
+    scratch_alignment: u64,
+    queue: vk::Queue,
+    pool: vk::CommandPool,
+}
+
+impl Context {
+    fn new() -> Result<Self> {
+        // SAFETY: every handle is created from the one before it and destroyed in `drop`.
+        unsafe {
+            let entry = ash::Entry::load()
+                .map_err(|e| Error::Gpu(format!("could not load Vulkan: {e}")))?;
+            let app = vk::ApplicationInfo::default()
+                .application_name(c"rtt")
+                .api_version(vk::API_VERSION_1_2);
+            let instance = entry
+                .create_instance(
+                    &vk::InstanceCreateInfo::default().application_info(&app),
+                    None,
+                )
+                .map_err(failed("create a Vulkan instance"))?;
+            match Self::with_instance(entry, instance.clone()) {
+                Ok(context) => Ok(context),
+                Err(e) => {
+                    instance.destroy_instance(None);
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    unsafe fn with_instance(entry: ash::Entry, instance: ash::Instance) -> Result<Self> {
+        let extensions = [
+            ash::khr::acceleration_structure::NAME,
+            ash::khr::ray_query::NAME,
+            ash::khr::deferred_host_operations::NAME,
+        ];
+        // Discrete GPUs first, then anything else with ray queries and a compute queue.
+        let mut candidates = Vec::new();
+        for physical in instance
+            .enumerate_physical_devices()
+            .map_err(failed("list GPUs"))?
+        {
+            let supported = instance
+                .enumerate_device_extension_properties(physical)
+                .unwrap_or_default();
+            let has = |name: &std::ffi::CStr| {
+                supported
+                    .iter()
+                    .any(|p| p.extension_name_as_c_str() == Ok(name))
+            };
+            if !extensions.iter().all(|e| has(e)) {
+                continue;
+            }
+            let family = instance
+                .get_physical_device_queue_family_properties(physical)
+                .iter()
+                .position(|f| f.queue_flags.contains(vk::QueueFlags::COMPUTE));
+            if let Some(family) = family {
+                let discrete = instance
+                    .get_physical_device_properties(physical)
+                    .device_type
+                    == vk::PhysicalDeviceType::DISCRETE_GPU;
+                candidates.push((!discrete, physical, family as u32));
+            }
+        }
+        candidates.sort_by_key(|&(integrated, ..)| integrated);
+        let Some(&(_, physical, family)) = candidates.first() else {
+            return Err(Error::Gpu("no GPU supports ray queries".to_string()));
+        };
+
+        let mut acceleration_properties =
+            vk::PhysicalDeviceAccelerationStructurePropertiesKHR::default();
+        let mut properties =
+            vk::PhysicalDeviceProperties2::default().push_next(&mut acceleration_properties);
+        instance.get_physical_device_properties2(physical, &mut properties);
+        let scratch_alignment =
+            (acceleration_properties.min_acceleration_structure_scratch_offset_alignment as u64)
+                .max(1);
+
+        let mut vulkan12 =
+            vk::PhysicalDeviceVulkan12Features::default().buffer_device_address(true);
+        let mut acceleration_features =
+            vk::PhysicalDeviceAccelerationStructureFeaturesKHR::default()
+                .acceleration_structure(true);
+        let mut ray_query = vk::PhysicalDeviceRayQueryFeaturesKHR::default().ray_query(true);
+        let priorities = [1.0];
+        let queues = [vk::DeviceQueueCreateInfo::default()
+            .queue_family_index(family)
+            .queue_priorities(&priorities)];
+        let extension_names = extensions.map(|e| e.as_ptr());
+        let info = vk::DeviceCreateInfo::default()
+            .queue_create_infos(&queues)
+            .enabled_extension_names(&extension_names)
+            .push_next(&mut vulkan12)
+            .push_next(&mut acceleration_features)
+            .push_next(&mut ray_query);
+        let device = instance
+            .create_device(physical, &info, None)
+            .map_err(failed("open the GPU"))?;
+        let pool = match device.create_command_pool(
+            &vk::CommandPoolCreateInfo::default()
+                .queue_family_index(family)
+                .flags(vk::CommandPoolCreateFlags::TRANSIENT),
+            None,
+        ) {
+            Ok(pool) => pool,
+            Err(e) => {
+                device.destroy_device(None);
+                return Err(failed("create a command pool")(e));
+            }
+        };
+        Ok(Self {
+            acceleration: ash::khr::acceleration_structure::Device::new(&instance, &device),
+            memory: instance.get_physical_device_memory_properties(physical),
+            scratch_alignment,
+            queue: device.get_device_queue(family, 0),
+            pool,
+            device,
+            instance,
+            _entry: entry,
+        })
+    }
+
+    // A buffer in memory with at least `flags`, addressable from shaders if `usage` says so.
+    unsafe fn buffer(
+        &self,
+        size: u64,
+        usage: vk::BufferUsageFlags,
+        flags: vk::MemoryPropertyFlags,
+    ) -> Result<Buffer> {
+        let buffer = self
+            .device
+            .create_buffer(
+                &vk::BufferCreateInfo::default()
+                    .size(size.max(16))
+                    .usage(usage)
+                    .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                None,
+            )
+            .map_err(failed("create a buffer"))?;
+        let requirements = self.device.get_buffer_memory_requirements(buffer);
+        let Some(memory_type) = (0..self.memory.memory_type_count).find(|&i| {
+            requirements.memory_type_bits & (1 << i) != 0
+                && self.memory.memory_types[i as usize]
+                    .property_flags
+                    .contains(flags)
+        }) else {
+            self.device.destroy_buffer(buffer, None);
+            return Err(Error::Gpu(format!("no GPU memory is {flags:?}")));
+        };
+        let addressable = usage.contains(vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS);
+        let mut allocate_flags =
+            vk::MemoryAllocateFlagsInfo::default().flags(vk::MemoryAllocateFlags::DEVICE_ADDRESS);
+        let mut info = vk::MemoryAllocateInfo::default()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type);
+        if addressable {
+            info = info.push_next(&mut allocate_flags);
+        }
+        let memory = match self.device.allocate_memory(&info, None) {
+            Ok(memory) => memory,
+            Err(e) => {
+                self.device.destroy_buffer(buffer, None);
+                return Err(failed("allocate GPU memory")(e));
+            }
+        };
+        let buffer = Buffer {
+            buffer,
+            memory,
+            address: 0,
+        };
+        if let Err(e) = self.device.bind_buffer_memory(buffer.buffer, memory, 0) {
+            self.destroy(&buffer);
+            return Err(failed("bind GPU memory")(e));
+        }
+        let address = if addressable {
+            self.device.get_buffer_device_address(
+                &vk::BufferDeviceAddressInfo::default().buffer(buffer.buffer),
+            )
+        } else {
+            0
+        };
+        Ok(Buffer { address, ..buffer })
+    }
+
+    // A host-visible buffer holding `data`.
+    unsafe fn upload<T: Copy>(&self, data: &[T], usage: vk::BufferUsageFlags) -> Result<Buffer> {
+        let size = std::mem::size_of_val(data) as u64;
+        let buffer = self.buffer(
+            size,
+            usage,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        match self.device.map_memory(
+            buffer.memory,
+            0,
+            vk::WHOLE_SIZE,
+            vk::MemoryMapFlags::empty(),
+        ) {
+            Ok(p) => {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), p as *mut T, data.len());
+                self.device.unmap_memory(buffer.memory);
+                Ok(buffer)
+            }
+            Err(e) => {
+                self.destroy(&buffer);
+                Err(failed("map GPU memory")(e))
+            }
+        }
+    }
+
+    // The first `count` items of a host-visible buffer.
+    unsafe fn download<T: Copy + Default>(&self, buffer: &Buffer, count: usize) -> Result<Vec<T>> {
+        let p = self
+            .device
+            .map_memory(
+                buffer.memory,
+                0,
+                vk::WHOLE_SIZE,
+                vk::MemoryMapFlags::empty(),
+            )
+            .map_err(failed("map GPU memory"))?;
+        let mut out = vec![T::default(); count];
+        std::ptr::copy_nonoverlapping(p as *const T, out.as_mut_ptr(), count);
+        self.device.unmap_memory(buffer.memory);
+        Ok(out)
+    }
+
+    unsafe fn destroy(&self, buffer: &Buffer) {
+        self.device.destroy_buffer(buffer.buffer, None);
+        self.device.free_memory(buffer.memory, None);
+    }
+
+    // Records commands with `record`, submits them and waits until they finish. Earlier
+    // submissions' acceleration structure builds and shader writes are visible to them.
+    unsafe fn submit(&self, record: impl FnOnce(vk::CommandBuffer)) -> Result<()> {
+        let cmd = self
+            .device
+            .allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::default()
+                    .command_pool(self.pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(1),
+            )
+            .map_err(failed("allocate a command buffer"))?[0];
+        let cmds = [cmd];
+        let result = (|| {
+            self.device
+                .begin_command_buffer(
+                    cmd,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .map_err(failed("record commands"))?;
+            let barrier = [vk::MemoryBarrier::default()
+                .src_access_mask(
+                    vk::AccessFlags::ACCELERATION_STRUCTURE_WRITE_KHR
+                        | vk::AccessFlags::SHADER_WRITE,
+                )
+                .dst_access_mask(
+                    vk::AccessFlags::ACCELERATION_STRUCTURE_READ_KHR | vk::AccessFlags::SHADER_READ,
+                )];
+            self.device.cmd_pipeline_barrier(
+                cmd,
+                vk::PipelineStageFlags::ACCELERATION_STRUCTURE_BUILD_KHR
+                    | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::ACCELERATION_STRUCTURE_BUILD_KHR
+                    | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &barrier,
+                &[],
+                &[],
+            );
+            record(cmd);
+            self.device
+                .end_command_buffer(cmd)
+                .map_err(failed("record commands"))?;
+            let fence = self
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+                .map_err(failed("create a fence"))?;
+            let submitted = self
+                .device
+                .queue_submit(
+                    self.queue,
+                    &[vk::SubmitInfo::default().command_buffers(&cmds)],
+                    fence,
+                )
+                .and_then(|()| self.device.wait_for_fences(&[fence], true, u64::MAX));
+            self.device.destroy_fence(fence, None);
+            submitted.map_err(failed("run commands on the GPU"))
+        })();
+        self.device.free_command_buffers(self.pool, &cmds);
+        result
+    }
+
+    // Builds an acceleration structure of `ty` over `geometry` with `primitives` triangles or
+    // instances.
+    unsafe fn build(
+        &self,
+        ty: vk::AccelerationStructureTypeKHR,
+        geometry: vk::AccelerationStructureGeometryKHR,
+        primitives: u32,
+    ) -> Result<AccelerationStructure> {
+        let geometries = [geometry];
+        let info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
+            .ty(ty)
+            .flags(vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE)
+            .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
+            .geometries(&geometries);
+        let mut sizes = vk::AccelerationStructureBuildSizesInfoKHR::default();
+        self.acceleration.get_acceleration_structure_build_sizes(
+            vk::AccelerationStructureBuildTypeKHR::DEVICE,
+            &info,
+            &[primitives],
+            &mut sizes,
+        );
+        let device_local = vk::MemoryPropertyFlags::DEVICE_LOCAL;
+        let buffer = self.buffer(
+            sizes.acceleration_structure_size,
+            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR
+                | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            device_local,
+        )?;
+        let handle = match self.acceleration.create_acceleration_structure(
+            &vk::AccelerationStructureCreateInfoKHR::default()
+                .buffer(buffer.buffer)
+                .size(sizes.acceleration_structure_size)
+                .ty(ty),
+            None,
+        ) {
+            Ok(handle) => handle,
+            Err(e) => {
+                self.destroy(&buffer);
+                return Err(failed("create an acceleration structure")(e));
+            }
+        };
+        let structure = AccelerationStructure {
+            handle,
+            buffer,
+            address: 0,
+        };
+        let built = self
+            .buffer(
+                sizes.build_scratch_size + self.scratch_alignment,
+                vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                device_local,
+            )
+            .and_then(|scratch| {
+                let info = info.dst_acceleration_structure(handle).scratch_data(
+                    vk::DeviceOrHostAddressKHR {
+                        device_address: scratch.address.next_multiple_of(self.scratch_alignment),
+                    },
+                );
+                let ranges = [vk::AccelerationStructureBuildRangeInfoKHR::default()
+                    .primitive_count(primitives)];
+                let result = self.submit(|cmd| {
+                    self.acceleration
+                        .cmd_build_acceleration_structures(cmd, &[info], &[&ranges])
+                });
+                self.destroy(&scratch);
+                result
+            });
+        if let Err(e) = built {
+            self.destroy_structure(&structure);
+            return Err(e);
+        }
+        let address = self.acceleration.get_acceleration_structure_device_address(
+            &vk::AccelerationStructureDeviceAddressInfoKHR::default()
+                .acceleration_structure(handle),
+        );
+        Ok(AccelerationStructure {
+            address,
+            ..structure
+        })
+    }
+
+    unsafe fn destroy_structure(&self, structure: &AccelerationStructure) {
+        self.acceleration
+            .destroy_acceleration_structure(structure.handle, None);
+        self.destroy(&structure.buffer);
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        // SAFETY: nothing created from the device outlives `GpuScene::drop`, which runs first.
+        unsafe {
+            let _ = self.device.device_wait_idle();
+            self.device.destroy_command_pool(self.pool, None);
+            self.device.destroy_device(None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+// The compute pipeline running `shaders/intersect.comp`, with its one descriptor set.
+struct Pipeline {
+    set_layout: vk::DescriptorSetLayout,
+    layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+    pool: vk::DescriptorPool,
+    set: vk::DescriptorSet,
+}
+
+impl Pipeline {
+    unsafe fn new(context: &Context) -> Result<Self> {
+        if SHADER.is_empty() {
+            return Err(Error::Gpu(
+                "built without its shader; install glslc and rebuild".to_string(),
+            ));
+        }
+        let code = ash::util::read_spv(&mut std::io::Cursor::new(SHADER))?;
+        let device = &context.device;
+        let bindings = [
+            (0, vk::DescriptorType::ACCELERATION_STRUCTURE_KHR),
+            (1, vk::DescriptorType::STORAGE_BUFFER),
+            (2, vk::DescriptorType::STORAGE_BUFFER),
+        ]
+        .map(|(binding, ty)| {
+            vk::DescriptorSetLayoutBinding::default()
+                .binding(binding)
+                .descriptor_type(ty)
+                .descriptor_count(1)
+                .stage_flags(vk::ShaderStageFlags::COMPUTE)
+        });
+        let mut pipeline = Self {
+            set_layout: device
+                .create_descriptor_set_layout(
+                    &vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings),
+                    None,
+                )
+                .map_err(failed("create a descriptor set layout"))?,
+            layout: vk::PipelineLayout::null(),
+            pipeline: vk::Pipeline::null(),
+            pool: vk::DescriptorPool::null(),
+            set: vk::DescriptorSet::null(),
+        };
+        if let Err(e) = pipeline.create(context, &code) {
+            pipeline.destroy(context);
+            return Err(e);
+        }
+        Ok(pipeline)
+    }
+
+    unsafe fn create(&mut self, context: &Context, code: &[u32]) -> Result<()> {
+        let device = &context.device;
+        let set_layouts = [self.set_layout];
+        let push_constants = [vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .size(std::mem::size_of::<u32>() as u32)];
+        self.layout = device
+            .create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::default()
+                    .set_layouts(&set_layouts)
+                    .push_constant_ranges(&push_constants),
+                None,
+            )
+            .map_err(failed("create a pipeline layout"))?;
+        let module = device
+            .create_shader_module(&vk::ShaderModuleCreateInfo::default().code(code), None)
+            .map_err(failed("load the shader"))?;
+        let stage = vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(module)
+            .name(c"main");
+        let created = device.create_compute_pipelines(
+            vk::PipelineCache::null(),
+            &[vk::ComputePipelineCreateInfo::default()
+                .stage(stage)
+                .layout(self.layout)],
+            None,
+        );
+        device.destroy_shader_module(module, None);
+        self.pipeline = created.map_err(|(_, e)| failed("create the pipeline")(e))?[0];
+
+        let sizes = [
+            vk::DescriptorPoolSize::default()
+                .ty(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
+                .descriptor_count(1),
+            vk::DescriptorPoolSize::default()
+                .ty(vk::DescriptorType::STORAGE_BUFFER)
+                .descriptor_count(2),
+        ];
+        self.pool = device
+            .create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::default()
+                    .max_sets(1)
+                    .pool_sizes(&sizes),
+                None,
+            )
+            .map_err(failed("create a descriptor pool"))?;
+        self.set = device
+            .allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::default()
+                    .descriptor_pool(self.pool)
+                    .set_layouts(&set_layouts),
+            )
+            .map_err(failed("allocate a descriptor set"))?[0];
+        Ok(())
+    }
+
+    // Null handles are ignored by Vulkan, so this also cleans up after a partial `create`.
+    unsafe fn destroy(&self, context: &Context) {
+        let device = &context.device;
+        device.destroy_descriptor_pool(self.pool, None);
+        device.destroy_pipeline(self.pipeline, None);
+        device.destroy_pipeline_layout(self.layout, None);
+        device.destroy_descriptor_set_layout(self.set_layout, None);
+    }
+}
+
+// Triangle meshes in GPU acceleration structures, intersected a batch of rays at a time.
+pub struct GpuScene {
+    meshes: Vec<Mesh>,
+    // One per mesh with triangles, and the top level over them; None for an empty scene.
+    bottom: Vec<AccelerationStructure>,
+    top: Option<AccelerationStructure>,
+    pipeline: Pipeline,
+    // Held while recording and submitting, as the command pool and descriptor set are shared.
+    submitting: Mutex<()>,
+    // Set once a batch has failed on the GPU, after which every batch is traced on the CPU.
+    failed: AtomicBool,
+    context: Context,
+}
+
+// Vulkan handles may be used from any thread; `submitting` serializes the ones that must be
+// externally synchronized.
+unsafe impl Send for GpuScene {}
+unsafe impl Sync for GpuScene {}
+
+impl GpuScene {
+    // Fails if there is no Vulkan GPU with ray query support.
+    pub fn new(meshes: Vec<Mesh>) -> Result<Self> {
+        let context = Context::new()?;
+        // SAFETY: everything is created on `context`'s device and destroyed in `drop`.
+        unsafe {
+            let pipeline = Pipeline::new(&context)?;
+            let mut scene = Self {
+                meshes,
+                bottom: Vec::new(),
+                top: None,
+                pipeline,
+                submitting: Mutex::new(()),
+                failed: AtomicBool::new(false),
+                context,
+            };
+            scene.build()?;
+            Ok(scene)
+        }
+    }
+
+    unsafe fn build(&mut self) -> Result<()> {
+        let context = &self.context;
+        let input = vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR
+            | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS;
+        let mut instances = Vec::new();
+        for (index, mesh) in self.meshes.iter().enumerate() {
+            if mesh.indices.is_empty() {
+                continue;
+            }
+            let positions: Vec<[f32; 3]> = mesh
+                .positions
+                .iter()
+                .map(|p| [p.x as f32, p.y as f32, p.z as f32])
+                .collect();
+            let vertices = context.upload(&positions, input)?;
+            let indices = match context.upload(&mesh.indices, input) {
+                Ok(indices) => indices,
+                Err(e) => {
+                    context.destroy(&vertices);
+                    return Err(e);
+                }
+            };
+            let triangles = vk::AccelerationStructureGeometryTrianglesDataKHR::default()
+                .vertex_format(vk::Format::R32G32B32_SFLOAT)
+                .vertex_data(vk::DeviceOrHostAddressConstKHR {
+                    device_address: vertices.address,
+                })
+                .vertex_stride(std::mem::size_of::<[f32; 3]>() as u64)
+                .max_vertex(positions.len() as u32 - 1)
+                .index_type(vk::IndexType::UINT32)
+                .index_data(vk::DeviceOrHostAddressConstKHR {
+                    device_address: indices.address,
+                });
+            let geometry = vk::AccelerationStructureGeometryKHR::default()
+                .geometry_type(vk::GeometryTypeKHR::TRIANGLES)
+                .geometry(vk::AccelerationStructureGeometryDataKHR { triangles })
+                .flags(vk::GeometryFlagsKHR::OPAQUE);
+            let built = context.build(
+                vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+                geometry,
+                mesh.indices.len() as u32,
+            );
+            context.destroy(&vertices);
+            context.destroy(&indices);
+            let structure = built?;
+            instances.push(vk::AccelerationStructureInstanceKHR {
+                transform: vk::TransformMatrixKHR {
+                    matrix: [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+                },
+                // The mesh's index, which hits report back.
+                instance_custom_index_and_mask: vk::Packed24_8::new(index as u32, 0xff),
+                instance_shader_binding_table_record_offset_and_flags: vk::Packed24_8::new(
+                    0,
+                    vk::GeometryInstanceFlagsKHR::TRIANGLE_FACING_CULL_DISABLE.as_raw() as u8,
+                ),
+                acceleration_structure_reference: vk::AccelerationStructureReferenceKHR {
+                    device_handle: structure.address,
+                },
+            });
+            self.bottom.push(structure);
+        }
+        if instances.is_empty() {
+            return Ok(());
+        }
+
+        let buffer = context.upload(&instances, input)?;
+        let geometry = vk::AccelerationStructureGeometryKHR::default()
+            .geometry_type(vk::GeometryTypeKHR::INSTANCES)
+            .geometry(vk::AccelerationStructureGeometryDataKHR {
+                instances: vk::AccelerationStructureGeometryInstancesDataKHR::default().data(
+                    vk::DeviceOrHostAddressConstKHR {
+                        device_address: buffer.address,
+                    },
+                ),
+            });
+        let built = context.build(
+            vk::AccelerationStructureTypeKHR::TOP_LEVEL,
+            geometry,
+            instances.len() as u32,
+        );
+        context.destroy(&buffer);
+        let top = built?;
+
+        let structures = [top.handle];
+        let mut write = vk::WriteDescriptorSetAccelerationStructureKHR::default()
+            .acceleration_structures(&structures);
+        let mut descriptor = vk::WriteDescriptorSet::default()
+            .dst_set(self.pipeline.set)
+            .dst_binding(0)
+            .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
+            .push_next(&mut write);
+        descriptor.descriptor_count = 1;
+        context.device.update_descriptor_sets(&[descriptor], &[]);
+        self.top = Some(top);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+
+    // The closest hit of each ray within its interval, as `Mesh::hit` would find it.
+    pub fn intersect(&self, rays: &[(Ray, Interval)]) -> Result<Vec<Option<HitRecord>>> {
+        if self.top.is_none() || rays.is_empty() {
+            return Ok(vec![None; rays.len()]);
+        }
+        let gpu_rays: Vec<GpuRay> = rays
+            .iter()
+            .map(|(r, ray_t)| {
+                let (o, d) = (r.origin(), r.direction());
+                GpuRay {
+                    origin: [o.x as f32, o.y as f32, o.z as f32],
+                    t_min: ray_t.min as f32,
+                    direction: [d.x as f32, d.y as f32, d.z as f32],
+                    t_max: ray_t.max as f32,
+                }
+            })
+            .collect();
+        let hits = self.dispatch(&gpu_rays)?;
+        Ok(rays
+            .iter()
+            .zip(hits)
+            .map(|((r, ray_t), hit)| self.record(r, *ray_t, hit))
+            .collect())
+    }
+
+    fn dispatch(&self, rays: &[GpuRay]) -> Result<Vec<GpuHit>> {
+        let context = &self.context;
+        let storage = vk::BufferUsageFlags::STORAGE_BUFFER;
+        let _lock = self
+            .submitting
+            .lock()
+            .map_err(|_| Error::Poisoned("GPU queue"))?;
+        // SAFETY: the buffers outlive the submission, which is waited for, and the lock keeps
+        // other batches from touching the descriptor set or command pool meanwhile.
+        unsafe {
+            let ray_buffer = context.upload(rays, storage)?;
+            let hits = vec![GpuHit::default(); rays.len()];
+            let hit_buffer = match context.upload(&hits, storage) {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    context.destroy(&ray_buffer);
+                    return Err(e);
+                }
+            };
+            let ray_info = [vk::DescriptorBufferInfo::default()
+                .buffer(ray_buffer.buffer)
+                .range(vk::WHOLE_SIZE)];
+            let hit_info = [vk::DescriptorBufferInfo::default()
+                .buffer(hit_buffer.buffer)
+                .range(vk::WHOLE_SIZE)];
+            let writes = [(1, &ray_info), (2, &hit_info)].map(|(binding, info)| {
+                vk::WriteDescriptorSet::default()
+                    .dst_set(self.pipeline.set)
+                    .dst_binding(binding)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .buffer_info(info)
+            });
+            context.device.update_descriptor_sets(&writes, &[]);
+            let count = rays.len() as u32;
+            let result = context
+                .submit(|cmd| {
+                    let device = &context.device;
+                    device.cmd_bind_pipeline(
+                        cmd,
+                        vk::PipelineBindPoint::COMPUTE,
+                        self.pipeline.pipeline,
+                    );
+                    device.cmd_bind_descriptor_sets(
+                        cmd,
+                        vk::PipelineBindPoint::COMPUTE,
+                        self.pipeline.layout,
+                        0,
+                        &[self.pipeline.set],
+                        &[],
+                    );
+                    device.cmd_push_constants(
+                        cmd,
+                        self.pipeline.layout,
+                        vk::ShaderStageFlags::COMPUTE,
+                        0,
+                        &count.to_ne_bytes(),
+                    );
+                    device.cmd_dispatch(cmd, count.div_ceil(WORKGROUP_SIZE), 1, 1);
+                })
+                .and_then(|()| context.download(&hit_buffer, rays.len()));
+            context.destroy(&ray_buffer);
+            context.destroy(&hit_buffer);
+            result
+        }
+    }
+
+    fn record(&self, r: &Ray, ray_t: Interval, hit: GpuHit) -> Option<HitRecord> {
+        if hit.t < 0.0 {
+            return None;
+        }
+        let mesh = self.meshes.get(hit.instance as usize)?;
+        let indices = *mesh.indices.get(hit.primitive as usize)?;
+        let tri = mesh.vertices(indices);
+        let (t, outward_normal, bary) = hit_triangle(tri, r, ray_t).unwrap_or_else(|| {
+            // Right on an edge, where single and double precision disagree.
+            let [a, b, c] = tri;
+            let n = Vec3::unit_vector(Vec3::cross(b - a, c - a));
+            (hit.t as f64, n, [0.0, 0.0])
+        });
+        Some(mesh.record(r, t, outward_normal, indices, bary))
+    }
+}
+
+// Rays traced one at a time, such as shadow rays and bounces, go through the meshes' own BVHs
+// on the CPU; only whole batches of camera rays are worth the round trip to the GPU.
+impl Hittable for GpuScene {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut closest = None;
+        let mut ray_t = ray_t;
+        for mesh in &self.meshes {
+            if let Some(rec) = mesh.hit(r, ray_t) {
+                ray_t = ray_t.with_max(rec.t);
+                closest = Some(rec);
+            }
+        }
+        closest
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.meshes.iter().any(|m| m.is_occluded(r, ray_t))
+    }
+
+    fn hit_batch(&self, rays: &[(Ray, Interval)]) -> Vec<Option<HitRecord>> {
+        if !self.failed.load(Ordering::Relaxed) {
+            match self.intersect(rays) {
+                Ok(hits) => return hits,
+                Err(e) => {
+                    tracing::warn!(error = %e, "GPU batch failed, tracing on the CPU");
+                    self.failed.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        rays.iter().map(|(r, ray_t)| self.hit(r, *ray_t)).collect()
+    }
+
+    fn prefers_batches(&self) -> bool {
+        !self.failed.load(Ordering::Relaxed)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let mut boxes = self.meshes.iter().map(|m| m.bounding_box(time0, time1));
+        let first = boxes.next()??;
+        boxes.try_fold(first, |acc, b| Some(Aabb::surrounding_box(acc, b?)))
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        for mesh in &self.meshes {
+            mesh.materials(out);
+        }
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        for mesh in &self.meshes {
+            mesh.stats(stats);
+        }
+    }
+}
+
+impl Drop for GpuScene {
+    fn drop(&mut self) {
+        // SAFETY: no submission is in flight once `&mut self` is held; the context is dropped
+        // after this, with the device.
+        unsafe {
+            let context = &self.context;
+            let _ = context.device.device_wait_idle();
+            if let Some(top) = &self.top {
+                context.destroy_structure(top);
+            }
+            for structure in &self.bottom {
+                context.destroy_structure(structure);
+            }
+            self.pipeline.destroy(context);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// Path guiding: learns, per region of the scene, which directions bring in the most light and
+// steers diffuse bounces towards them, so paths find light that reaches a surface only through
+// glass or a small opening. Space is split by a binary tree over the scene bounds that refines
+// where paths land, and each region holds a histogram over directions in equal-area bins of
+// cos(theta) and phi. Paths add the radiance they find to the histograms while a pass renders;
+// `refine` then turns them into the distributions the next pass samples, and splits regions
+// that saw many paths. Bounces draw from a mixture of the guide and the material, weighted by
+// the mixture's density, so estimates stay unbiased however poor the guide is.
+
+use crate::aabb::Aabb;
+use crate::color::luminance;
+use crate::hittable::Hittable;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+const THETA_BINS: usize = 8;
+const PHI_BINS: usize = 16;
+const BINS: usize = THETA_BINS * PHI_BINS;
+// Share of each learned distribution spread evenly, so no direction is ruled out.
+const UNIFORM_FRACTION: f64 = 0.1;
+// A region that gathers more paths than this in one pass is split in two.
+const SPLIT_PATHS: u32 = 4000;
+const MAX_DEPTH: u32 = 36;
+
+// Share of guided bounces that sample the guide rather than the material.
+pub const GUIDE_FRACTION: f64 = 0.5;
+
+// Learned density over the sphere of directions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectionalDistribution {
+    cdf: [f32; BINS],
+}
+
+impl DirectionalDistribution {
+    // Proportional to `weights` per bin, or None if they are all zero.
+    fn from_weights(weights: &[f64; BINS]) -> Option<Self> {
+        let total: f64 = weights.iter().sum();
+        if !(total > 0.0 && total.is_finite()) {
+            return None;
+        }
+        let mut cdf = [0.0; BINS];
+        let mut sum = 0.0;
+        for (c, w) in cdf.iter_mut().zip(weights) {
+            sum += (1.0 - UNIFORM_FRACTION) * w / total + UNIFORM_FRACTION / BINS as f64;
+            *c = sum as f32;
+        }
+        cdf[BINS - 1] = 1.0;
+        Some(Self { cdf })
+    }
+
+    fn probability(&self, bin: usize) -> f64 {
+        let below = if bin == 0 { 0.0 } else { self.cdf[bin - 1] };
+        (self.cdf[bin] - below) as f64
+    }
+
+    // Density per unit solid angle.
+    pub fn pdf(&self, direction: Vec3) -> f64 {
+        self.probability(bin(direction)) * BINS as f64 / (4.0 * PI)
+    }
+
+    // Unit direction: a bin by its probability, then uniformly within it.
+    pub fn sample(&self, rng: &mut dyn rand::RngCore) -> Vec3 {
+        let u = rng.random::<f32>();
+        let bin = self.cdf.partition_point(|&c| c <= u).min(BINS - 1);
+        let (theta_bin, phi_bin) = (bin / PHI_BINS, bin % PHI_BINS);
+        let z = -1.0 + 2.0 * (theta_bin as f64 + rng.random::<f64>()) / THETA_BINS as f64;
+        let phi = 2.0 * PI * (phi_bin as f64 + rng.random::<f64>()) / PHI_BINS as f64 - PI;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+}
+
+fn bin(direction: Vec3) -> usize {
+    let d = Vec3::unit_vector(direction);
+    let theta_bin = ((d.z + 1.0) * 0.5 * THETA_BINS as f64) as usize;
+    let phi_bin = ((d.y.atan2(d.x) + PI) / (2.0 * PI) * PHI_BINS as f64) as usize;
+    theta_bin.min(THETA_BINS - 1) * PHI_BINS + phi_bin.min(PHI_BINS - 1)
+}
+
+#[inline]
+fn coordinate(p: Point3, axis: usize) -> f64 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z,
+    }
+}
+
+struct Region {
+    depth: u32,
+    // Sums of radiance over density per bin, as f32 bits so threads can add without locking.
+    training: Vec<AtomicU32>,
+    paths: AtomicU32,
+    distribution: Option<DirectionalDistribution>,
+}
+
+impl Region {
+    fn new(depth: u32, distribution: Option<DirectionalDistribution>) -> Self {
+        Self {
+            depth,
+            training: (0..BINS).map(|_| AtomicU32::new(0)).collect(),
+            paths: AtomicU32::new(0),
+            distribution,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Node {
+    Leaf(usize),
+    // Halved along `axis`; the lower half is node `children`, the upper `children + 1`.
+    Split { axis: usize, children: usize },
+}
+
+pub struct PathGuide {
+    bounds: Aabb,
+    nodes: Vec<Node>,
+    regions: Vec<Region>,
+}
+
+impl PathGuide {
+    pub fn new(bounds: Aabb) -> Self {
+        Self {
+            bounds,
+            nodes: vec![Node::Leaf(0)],
+            regions: vec![Region::new(0, None)],
+        }
+    }
+
+    // A guide over everything in `world`, or None if it is unbounded.
+    pub fn for_world(world: &dyn Hittable) -> Option<Self> {
+        let bounds = world.bounding_box(0.0, 1.0)?;
+        (!bounds.is_empty()).then(|| Self::new(bounds.pad(1e-3)))
+    }
+
+    #[inline]
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+
+    fn region(&self, p: Point3) -> Option<&Region> {
+        let mut bounds = self.bounds;
+        if !(0..3).all(|a| {
+            let (lo, hi) = bounds.axis(a);
+            (lo..=hi).contains(&coordinate(p, a))
+        }) {
+            return None;
+        }
+        let mut node = 0;
+        loop {
+            match self.nodes[node] {
+                Node::Leaf(region) => return Some(&self.regions[region]),
+                Node::Split { axis, children } => {
+                    let (lo, hi) = bounds.axis(axis);
+                    let mid = 0.5 * (lo + hi);
+                    let upper = coordinate(p, axis) >= mid;
+                    let (min, max) = (&mut bounds.min, &mut bounds.max);
+                    let (bound, value) = if upper { (min, mid) } else { (max, mid) };
+                    match axis {
+                        0 => bound.x = value,
+                        1 => bound.y = value,
+                        _ => bound.z = value,
+                    }
+                    node = children + upper as usize;
+                }
+            }
+        }
+    }
+
+    // What the last pass learned about light arriving at `p`, if anything.
+    pub fn distribution(&self, p: Point3) -> Option<&DirectionalDistribution> {
+        self.region(p)?.distribution.as_ref()
+    }
+
+    // Records a path that left `p` along `direction`, sampled with density `pdf`, and gathered
+    // `radiance` from there on while its throughput was `throughput`.
+    pub fn learn(&self, p: Point3, direction: Vec3, pdf: f64, throughput: Color, radiance: Color) {
+        let Some(region) = self.region(p) else {
+            return;
+        };
+        region.paths.fetch_add(1, Ordering::Relaxed);
+        let incident = luminance(radiance) / (luminance(throughput) * pdf);
+        if !(incident > 0.0 && incident.is_finite()) {
+            return;
+        }
+        let _ = region.training[bin(direction)].fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |bits| Some((f32::from_bits(bits) + incident as f32).to_bits()),
+        );
+    }
+
+    // Replaces each region's distribution with what it learned since the last call, keeping
+    // the old one where nothing was learned, and splits busy regions.
+    pub fn refine(&mut self) {
+        for node in 0..self.nodes.len() {
+            let Node::Leaf(index) = self.nodes[node] else {
+                continue;
+            };
+            let region = &mut self.regions[index];
+            let weights: [f64; BINS] = std::array::from_fn(|i| {
+                f32::from_bits(std::mem::take(region.training[i].get_mut())) as f64
+            });
+            if let Some(distribution) = DirectionalDistribution::from_weights(&weights) {
+                region.distribution = Some(distribution);
+            }
+            let paths = std::mem::take(region.paths.get_mut());
+            if paths > SPLIT_PATHS && region.depth < MAX_DEPTH {
+                region.depth += 1;
+                let sibling = Region::new(region.depth, region.distribution.clone());
+                let axis = (region.depth - 1) as usize % 3;
+                self.nodes[node] = Node::Split {
+                    axis,
+                    children: self.nodes.len(),
+                };
+                self.nodes.push(Node::Leaf(index));
+                self.nodes.push(Node::Leaf(self.regions.len()));
+                self.regions.push(sibling);
+            }
+        }
+    }
+}
@@ -1,104 +1,1065 @@
-use crate::material::Material;
-use crate::ray::Ray;
-use crate::vec3::{Point3, Vec3};
-use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::background::Background;
+use crate::interval::Interval;
+use crate::light::Light;
+use crate::material::{Material, MaterialId, MaterialRef};
+use crate::ray::{Ray, RayKind};
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::soa::SphereSoA;
+use crate::stats::{short_type_name, SceneStats};
+use crate::vec3::{Color, Point3, Vec3};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub t: f64,
     pub point: Point3,
+    // Always faces against the incoming ray; see `front_face`.
     pub normal: Vec3,
-    pub material: Arc<dyn Material>,
+    // True when the ray hit the outside of the surface.
+    pub front_face: bool,
+    pub material: MaterialId,
+    // Index of the hit object in the top-level scene list.
+    pub object_id: u32,
+    // Set for holdout objects, which occlude but render as transparent black.
+    pub holdout: bool,
+    // Surface coordinates for texture lookups: longitude and latitude on spheres, the mesh's
+    // texture coordinates, or barycentrics on triangles without any.
+    pub u: f64,
+    pub v: f64,
 }
 
 pub trait Hittable: Send + Sync {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord>;
+
+    // Appends every intersection within `ray_t` to `out`, in any order. The default steps
+    // from one `hit` to the next, which suits objects a ray crosses only a few times.
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        let mut ray_t = ray_t;
+        while let Some(rec) = self.hit(r, ray_t) {
+            ray_t.min = rec.t;
+            out.push(rec);
+        }
+    }
+
+    // Whether anything at all is hit within `ray_t`, for shadow rays. Objects that can stop
+    // at the first intersection found, without building a record, override this.
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.hit(r, ray_t).is_some()
+    }
+
+    // Share of light getting through along `r` within `ray_t`: white if nothing is in the way,
+    // black if something opaque is, and tinted through filters such as thin glass; see
+    // `Material::filter`. Only lists look past hits.
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        if self.is_occluded(r, ray_t) {
+            Color::default()
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
+
+    // Every intersection within `ray_t`, nearest first: for CSG, shadow rays through
+    // transparent surfaces, and tracking which media a ray is inside.
+    fn hit_all(&self, r: &Ray, ray_t: Interval) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        self.hit_all_into(r, ray_t, &mut hits);
+        hits.sort_by(|a, b| a.t.total_cmp(&b.t));
+        hits
+    }
+
+    // The closest hit of each ray within its interval, as `hit` would find it. Objects that
+    // intersect many rays at once faster than one at a time, such as `GpuScene`, override
+    // this and `prefers_batches`.
+    fn hit_batch(&self, rays: &[(Ray, Interval)]) -> Vec<Option<HitRecord>> {
+        rays.iter().map(|(r, ray_t)| self.hit(r, *ray_t)).collect()
+    }
+
+    // Whether renderers should find camera ray hits through `hit_batch`, a row at a time.
+    fn prefers_batches(&self) -> bool {
+        false
+    }
+
+    // Box enclosing the object over the shutter interval, if it is bounded.
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        None
+    }
+
+    // Appends every material referenced by this object, in a stable order.
+    fn materials(&self, _out: &mut Vec<MaterialId>) {}
+
+    // Appends the lights in this object that can be sampled directly.
+    fn lights<'a>(&'a self, _out: &mut Vec<&'a dyn Light>) {}
+
+    // What rays leaving the scene see, if this object sets it; only worlds do.
+    fn background(&self) -> Option<&dyn Background> {
+        None
+    }
+
+    // Records this object in the scene report. Containers override this to recurse.
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.add_primitive(short_type_name(self), std::mem::size_of_val(self));
+    }
+
+    // Serializable description for scene export, registering materials in `materials`.
+    // None if this object, or anything it contains, can't be saved.
+    fn to_desc(&self, _materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        None
+    }
+}
+
+// Whether `r` passes straight through the cutout surface at `rec`, with probability one minus
+// its material's opacity there. Decided by hashing the ray and the distance, so it is random
+// across rays but the same every time one ray is tested: closest-hit and shadow queries agree.
+pub fn passes_through(r: &Ray, rec: &HitRecord) -> bool {
+    let opacity = rec.material.get().opacity(rec);
+    if opacity >= 1.0 {
+        return false;
+    }
+    let (o, d) = (r.origin(), r.direction());
+    let mut h = 0u64;
+    for x in [o.x, o.y, o.z, d.x, d.y, d.z, rec.t] {
+        // splitmix64 finalizer.
+        h = (h ^ x.to_bits()).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+    }
+    (h >> 11) as f64 / (1u64 << 53) as f64 >= opacity
 }
 
+// Fewest spheres for which a list batches them into a `SphereSoA`.
+pub const SOA_MIN_SPHERES: usize = 16;
+
+#[derive(Default)]
 pub struct HittableList {
-    pub objects: Vec<Arc<dyn Hittable>>,
+    // Private so that every change goes through a method that starts the caches over.
+    objects: Vec<Primitive>,
+    pub background: Option<Arc<dyn Background>>,
+    // Built on the first hit after a change.
+    batched: OnceLock<Option<Batched>>,
+    // Whether any object has cutout materials.
+    cutouts: OnceLock<bool>,
+    // The same for filter materials.
+    filters: OnceLock<bool>,
+}
+
+// The list's spheres batched for intersection, and everything else.
+struct Batched {
+    spheres: SphereSoA,
+    others: Vec<u32>,
 }
 
 impl HittableList {
     pub fn new() -> Self {
         Self {
             objects: Vec::new(),
+            background: None,
+            batched: OnceLock::new(),
+            cutouts: OnceLock::new(),
+            filters: OnceLock::new(),
         }
     }
 
-    pub fn add(&mut self, object: Arc<dyn Hittable>) {
-        self.objects.push(object);
+    // Whether hits need testing for cutouts; see `passes_through`.
+    fn has_cutouts(&self) -> bool {
+        self.any_material(&self.cutouts, |m| m.has_cutouts())
     }
-}
 
-impl Hittable for HittableList {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    // Whether shadow rays need to look past hits; see `Material::filter`.
+    fn has_filters(&self) -> bool {
+        self.any_material(&self.filters, |m| m.is_filter())
+    }
+
+    // Whether any material in the list passes `test`, cached in `cell` until the list changes.
+    fn any_material(&self, cell: &OnceLock<bool>, test: fn(&dyn Material) -> bool) -> bool {
+        *cell.get_or_init(|| {
+            let mut materials = Vec::new();
+            self.materials(&mut materials);
+            materials.iter().any(|m| test(&*m.get()))
+        })
+    }
+
+    fn closest(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if let Some(batched) = self.batched() {
+            let mut hit_rec = batched.spheres.hit(r, ray_t);
+            let mut closest_so_far = hit_rec.as_ref().map_or(ray_t.max, |h| h.t);
+            for &i in &batched.others {
+                if let Some(mut rec) =
+                    self.objects[i as usize].hit(r, ray_t.with_max(closest_so_far))
+                {
+                    closest_so_far = rec.t;
+                    rec.object_id = i;
+                    hit_rec = Some(rec);
+                }
+            }
+            return hit_rec;
+        }
+
         let mut hit_rec: Option<HitRecord> = None;
-        let mut closest_so_far = t_max;
+        let mut closest_so_far = ray_t.max;
 
-        for obj in &self.objects {
-            if let Some(rec) = obj.hit(r, t_min, closest_so_far) {
+        for (i, obj) in self.objects.iter().enumerate() {
+            if let Some(mut rec) = obj.hit(r, ray_t.with_max(closest_so_far)) {
                 closest_so_far = rec.t;
+                rec.object_id = i as u32;
                 hit_rec = Some(rec);
             }
         }
 
         hit_rec
     }
+
+    fn batched(&self) -> Option<&Batched> {
+        let batched = self.batched.get_or_init(|| {
+            let count = self
+                .objects
+                .iter()
+                .filter(|o| matches!(o, Primitive::Sphere(_)))
+                .count();
+            if count < SOA_MIN_SPHERES {
+                return None;
+            }
+            let mut batched = Batched {
+                spheres: SphereSoA::new(),
+                others: Vec::new(),
+            };
+            for (i, obj) in self.objects.iter().enumerate() {
+                match obj {
+                    Primitive::Sphere(s) => batched.spheres.push(s, i as u32),
+                    _ => batched.others.push(i as u32),
+                }
+            }
+            Some(batched)
+        });
+        batched.as_ref()
+    }
+
+    // Drops everything cached about the objects, before they change.
+    fn changed(&mut self) {
+        self.batched = OnceLock::new();
+        self.cutouts = OnceLock::new();
+        self.filters = OnceLock::new();
+    }
+
+    pub fn set_background(&mut self, background: Arc<dyn Background>) {
+        self.background = Some(background);
+    }
+
+    pub fn add(&mut self, object: impl Into<Primitive>) {
+        self.push(object.into());
+    }
+
+    pub fn push(&mut self, object: Primitive) {
+        self.changed();
+        self.objects.push(object);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    // The object whose hits have `object_id` `i`.
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&Primitive> {
+        self.objects.get(i)
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut Primitive> {
+        self.changed();
+        self.objects.get_mut(i)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Primitive> {
+        self.objects.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Primitive> {
+        self.changed();
+        self.objects.iter_mut()
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if !self.has_cutouts() {
+            return self.closest(r, ray_t);
+        }
+        // Step past surfaces the ray passes through.
+        let mut ray_t = ray_t;
+        loop {
+            let rec = self.closest(r, ray_t)?;
+            if !passes_through(r, &rec) {
+                return Some(rec);
+            }
+            ray_t.min = rec.t;
+        }
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        if self.has_cutouts() {
+            return self.hit(r, ray_t).is_some();
+        }
+        match self.batched() {
+            Some(batched) => {
+                batched.spheres.is_occluded(r, ray_t)
+                    || batched
+                        .others
+                        .iter()
+                        .any(|&i| self.objects[i as usize].is_occluded(r, ray_t))
+            }
+            None => self.objects.iter().any(|o| o.is_occluded(r, ray_t)),
+        }
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        if !self.has_filters() {
+            return if self.is_occluded(r, ray_t) {
+                Color::default()
+            } else {
+                Color::new(1.0, 1.0, 1.0)
+            };
+        }
+        let mut ray_t = ray_t;
+        let mut transmittance = Color::new(1.0, 1.0, 1.0);
+        while let Some(rec) = self.hit(r, ray_t) {
+            transmittance *= rec.material.get().transmittance(r, &rec);
+            if transmittance == Color::default() {
+                break;
+            }
+            ray_t.min = rec.t;
+        }
+        transmittance
+    }
+
+    // Objects that batch take all the rays at once, each looking only nearer than the closest
+    // hit so far; the rest are tested a ray at a time. Cutouts are stepped past ray by ray.
+    fn hit_batch(&self, rays: &[(Ray, Interval)]) -> Vec<Option<HitRecord>> {
+        if !self.prefers_batches() || self.has_cutouts() {
+            return rays.iter().map(|(r, ray_t)| self.hit(r, *ray_t)).collect();
+        }
+        let mut hits: Vec<Option<HitRecord>> = vec![None; rays.len()];
+        let (batching, single): (Vec<_>, Vec<_>) = self
+            .objects
+            .iter()
+            .enumerate()
+            .partition(|(_, o)| o.prefers_batches());
+        for ((r, ray_t), hit) in rays.iter().zip(&mut hits) {
+            for &(i, obj) in &single {
+                let closest = hit.as_ref().map_or(ray_t.max, |h| h.t);
+                if let Some(mut rec) = obj.hit(r, ray_t.with_max(closest)) {
+                    rec.object_id = i as u32;
+                    *hit = Some(rec);
+                }
+            }
+        }
+        for (i, obj) in batching {
+            let nearer: Vec<(Ray, Interval)> = rays
+                .iter()
+                .zip(&hits)
+                .map(|(&(r, ray_t), hit)| {
+                    (r, ray_t.with_max(hit.as_ref().map_or(ray_t.max, |h| h.t)))
+                })
+                .collect();
+            for (hit, found) in hits.iter_mut().zip(obj.hit_batch(&nearer)) {
+                if let Some(mut rec) = found {
+                    rec.object_id = i as u32;
+                    *hit = Some(rec);
+                }
+            }
+        }
+        hits
+    }
+
+    fn prefers_batches(&self) -> bool {
+        self.objects.iter().any(|o| o.prefers_batches())
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        let start = out.len();
+        for (i, obj) in self.objects.iter().enumerate() {
+            let first = out.len();
+            obj.hit_all_into(r, ray_t, out);
+            for rec in &mut out[first..] {
+                rec.object_id = i as u32;
+            }
+        }
+        if self.has_cutouts() {
+            let mut k = start;
+            for j in start..out.len() {
+                if !passes_through(r, &out[j]) {
+                    out.swap(k, j);
+                    k += 1;
+                }
+            }
+            out.truncate(k);
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let mut boxes = self.objects.iter().map(|o| o.bounding_box(time0, time1));
+        let first = boxes.next()??;
+        boxes.try_fold(first, |acc, b| Some(Aabb::surrounding_box(acc, b?)))
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        for obj in &self.objects {
+            obj.materials(out);
+        }
+    }
+
+    fn lights<'a>(&'a self, out: &mut Vec<&'a dyn Light>) {
+        for obj in &self.objects {
+            obj.lights(out);
+        }
+        out.extend(self.background.as_deref().and_then(|b| b.light()));
+    }
+
+    fn background(&self) -> Option<&dyn Background> {
+        self.background.as_deref()
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.geometry_bytes += std::mem::size_of_val(self)
+            + self.objects.capacity() * std::mem::size_of::<Primitive>();
+        for obj in &self.objects {
+            obj.stats(stats);
+        }
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        let objects = self
+            .objects
+            .iter()
+            .map(|o| o.to_desc(materials))
+            .collect::<Option<_>>()?;
+        Some(ObjectDesc::List { objects })
+    }
 }
 
+#[derive(Clone)]
 pub struct Sphere {
     pub center: Point3,
     pub radius: f64,
-    pub material: Arc<dyn Material>,
+    pub material: MaterialRef,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
+    pub fn new(center: Point3, radius: f64, material: impl Into<MaterialRef>) -> Self {
         Self {
             center,
             radius,
-            material,
+            material: material.into(),
         }
     }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = r.origin() - self.center;
-        let a = Vec3::dot(r.direction(), r.direction());
-        let half_b = Vec3::dot(oc, r.direction());
-        let c = Vec3::dot(oc, oc) - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-
-        if discriminant > 0.0 {
-            let sqrtd = discriminant.sqrt();
-
-            let mut root = (-half_b - sqrtd) / a;
-            if root > t_min && root < t_max {
-                let p = r.at(root);
-                let normal = (p - self.center) / self.radius;
-                return Some(HitRecord {
-                    t: root,
-                    point: p,
-                    normal,
-                    material: Arc::clone(&self.material),
-                });
-            }
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        hit_sphere(self.center, self.radius, self.material.id(), r, ray_t)
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        sphere_occludes(self.center, self.radius, r, ray_t)
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        sphere_hits(self.center, self.radius, self.material.id(), r, ray_t, out);
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(sphere_box(self.center, self.radius))
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::Sphere {
+            center: self.center,
+            radius: self.radius,
+            material: materials.index(self.material.id())?,
+        })
+    }
+}
+
+// Orients an outward normal against the ray, reporting which side was hit.
+#[inline]
+pub fn face_normal(r: &Ray, outward_normal: Vec3) -> (bool, Vec3) {
+    let front_face = Vec3::dot(r.direction(), outward_normal) < 0.0;
+    if front_face {
+        (true, outward_normal)
+    } else {
+        (false, -outward_normal)
+    }
+}
+
+#[inline]
+pub(crate) fn sphere_box(center: Point3, radius: f64) -> Aabb {
+    let r = Vec3::new(radius.abs(), radius.abs(), radius.abs());
+    Aabb::new(center - r, center + r)
+}
+
+fn hit_sphere(
+    center: Point3,
+    radius: f64,
+    material: MaterialId,
+    r: &Ray,
+    ray_t: Interval,
+) -> Option<HitRecord> {
+    let oc = r.origin() - center;
+    let a = Vec3::dot(r.direction(), r.direction());
+    let half_b = Vec3::dot(oc, r.direction());
+    let c = Vec3::dot(oc, oc) - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+
+    if discriminant > 0.0 {
+        let sqrtd = discriminant.sqrt();
+
+        let mut root = (-half_b - sqrtd) / a;
+        if ray_t.surrounds(root) {
+            return Some(sphere_record(center, radius, material, r, root));
+        }
+
+        root = (-half_b + sqrtd) / a;
+        if ray_t.surrounds(root) {
+            return Some(sphere_record(center, radius, material, r, root));
+        }
+    }
+
+    None
+}
+
+// Whether either crossing of a sphere is within `ray_t`.
+#[inline]
+fn sphere_occludes(center: Point3, radius: f64, r: &Ray, ray_t: Interval) -> bool {
+    let oc = r.origin() - center;
+    let a = Vec3::dot(r.direction(), r.direction());
+    let half_b = Vec3::dot(oc, r.direction());
+    let c = Vec3::dot(oc, oc) - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant <= 0.0 {
+        return false;
+    }
+    let sqrtd = discriminant.sqrt();
+    ray_t.surrounds((-half_b - sqrtd) / a) || ray_t.surrounds((-half_b + sqrtd) / a)
+}
+
+// Both crossings of a sphere, where within `ray_t`.
+pub(crate) fn sphere_hits(
+    center: Point3,
+    radius: f64,
+    material: MaterialId,
+    r: &Ray,
+    ray_t: Interval,
+    out: &mut Vec<HitRecord>,
+) {
+    let oc = r.origin() - center;
+    let a = Vec3::dot(r.direction(), r.direction());
+    let half_b = Vec3::dot(oc, r.direction());
+    let c = Vec3::dot(oc, oc) - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant <= 0.0 {
+        return;
+    }
+    let sqrtd = discriminant.sqrt();
+    for root in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+        if ray_t.surrounds(root) {
+            out.push(sphere_record(center, radius, material, r, root));
+        }
+    }
+}
+
+// The hit at `t` on a sphere the ray is known to cross there.
+#[inline]
+pub(crate) fn sphere_record(
+    center: Point3,
+    radius: f64,
+    material: MaterialId,
+    r: &Ray,
+    t: f64,
+) -> HitRecord {
+    let p = r.at(t);
+    let outward_normal = (p - center) / radius;
+    let (front_face, normal) = face_normal(r, outward_normal);
+    let (u, v) = sphere_uv(outward_normal);
+    HitRecord {
+        t,
+        point: p,
+        normal,
+        front_face,
+        material,
+        object_id: 0,
+        holdout: false,
+        u,
+        v,
+    }
+}
+
+// Longitude and latitude of a point on the unit sphere, each in [0, 1]: u goes round from -x,
+// v up from -y.
+#[inline]
+fn sphere_uv(p: Vec3) -> (f64, f64) {
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = f64::atan2(-p.z, p.x) + std::f64::consts::PI;
+    (
+        phi / (2.0 * std::f64::consts::PI),
+        theta / std::f64::consts::PI,
+    )
+}
+
+#[derive(Clone)]
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub material: MaterialRef,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: impl Into<MaterialRef>,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material: material.into(),
+        }
+    }
+
+    #[inline]
+    pub fn center(&self, time: f64) -> Point3 {
+        if self.time1 == self.time0 {
+            return self.center0;
+        }
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        hit_sphere(
+            self.center(r.time()),
+            self.radius,
+            self.material.id(),
+            r,
+            ray_t,
+        )
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        sphere_occludes(self.center(r.time()), self.radius, r, ray_t)
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        let center = self.center(r.time());
+        sphere_hits(center, self.radius, self.material.id(), r, ray_t, out);
+    }
+
+    // The center moves in a straight line, so the ends of the interval bound the sweep.
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        Some(Aabb::surrounding_box(
+            sphere_box(self.center(time0), self.radius),
+            sphere_box(self.center(time1), self.radius),
+        ))
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::MovingSphere {
+            center0: self.center0,
+            center1: self.center1,
+            time0: self.time0,
+            time1: self.time1,
+            radius: self.radius,
+            material: materials.index(self.material.id())?,
+        })
+    }
+}
+
+// An object held by value in a `HittableList`, so the shapes random scenes are made of are
+// traced without a pointer chase or virtual call. Anything else goes through `Dyn`.
+#[derive(Clone)]
+pub enum Primitive {
+    Sphere(Sphere),
+    MovingSphere(MovingSphere),
+    Dyn(Arc<dyn Hittable>),
+}
+
+impl Primitive {
+    // The object as a trait object, e.g. to wrap it in a `Holdout`.
+    pub fn into_arc(self) -> Arc<dyn Hittable> {
+        match self {
+            Primitive::Sphere(s) => Arc::new(s),
+            Primitive::MovingSphere(s) => Arc::new(s),
+            Primitive::Dyn(o) => o,
+        }
+    }
+
+    // Short type name of the object, e.g. for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Primitive::Sphere(s) => short_type_name(s),
+            Primitive::MovingSphere(s) => short_type_name(s),
+            Primitive::Dyn(o) => short_type_name(&**o),
+        }
+    }
+}
+
+impl From<Sphere> for Primitive {
+    fn from(sphere: Sphere) -> Self {
+        Primitive::Sphere(sphere)
+    }
+}
+
+impl From<MovingSphere> for Primitive {
+    fn from(sphere: MovingSphere) -> Self {
+        Primitive::MovingSphere(sphere)
+    }
+}
+
+impl From<Arc<dyn Hittable>> for Primitive {
+    fn from(object: Arc<dyn Hittable>) -> Self {
+        Primitive::Dyn(object)
+    }
+}
+
+impl<T: Hittable + 'static> From<Arc<T>> for Primitive {
+    fn from(object: Arc<T>) -> Self {
+        Primitive::Dyn(object)
+    }
+}
+
+impl Hittable for Primitive {
+    #[inline]
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        match self {
+            Primitive::Sphere(s) => s.hit(r, ray_t),
+            Primitive::MovingSphere(s) => s.hit(r, ray_t),
+            Primitive::Dyn(o) => o.hit(r, ray_t),
+        }
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        match self {
+            Primitive::Sphere(s) => s.is_occluded(r, ray_t),
+            Primitive::MovingSphere(s) => s.is_occluded(r, ray_t),
+            Primitive::Dyn(o) => o.is_occluded(r, ray_t),
+        }
+    }
+
+    fn hit_batch(&self, rays: &[(Ray, Interval)]) -> Vec<Option<HitRecord>> {
+        match self {
+            Primitive::Dyn(o) => o.hit_batch(rays),
+            _ => rays.iter().map(|(r, ray_t)| self.hit(r, *ray_t)).collect(),
+        }
+    }
+
+    fn prefers_batches(&self) -> bool {
+        matches!(self, Primitive::Dyn(o) if o.prefers_batches())
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        match self {
+            Primitive::Sphere(s) => s.hit_all_into(r, ray_t, out),
+            Primitive::MovingSphere(s) => s.hit_all_into(r, ray_t, out),
+            Primitive::Dyn(o) => o.hit_all_into(r, ray_t, out),
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        match self {
+            Primitive::Sphere(s) => s.bounding_box(time0, time1),
+            Primitive::MovingSphere(s) => s.bounding_box(time0, time1),
+            Primitive::Dyn(o) => o.bounding_box(time0, time1),
+        }
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        match self {
+            Primitive::Sphere(s) => s.materials(out),
+            Primitive::MovingSphere(s) => s.materials(out),
+            Primitive::Dyn(o) => o.materials(out),
+        }
+    }
+
+    fn lights<'a>(&'a self, out: &mut Vec<&'a dyn Light>) {
+        if let Primitive::Dyn(o) = self {
+            o.lights(out);
+        }
+    }
+
+    fn background(&self) -> Option<&dyn Background> {
+        match self {
+            Primitive::Dyn(o) => o.background(),
+            _ => None,
+        }
+    }
+
+    // Inline shapes take no memory beyond their slot, which the list already counts.
+    fn stats(&self, stats: &mut SceneStats) {
+        match self {
+            Primitive::Dyn(o) => o.stats(stats),
+            _ => stats.add_primitive(self.type_name(), 0),
+        }
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        match self {
+            Primitive::Sphere(s) => s.to_desc(materials),
+            Primitive::MovingSphere(s) => s.to_desc(materials),
+            Primitive::Dyn(o) => o.to_desc(materials),
+        }
+    }
+}
+
+// Marks the wrapped object as a holdout matte.
+pub struct Holdout {
+    pub object: Arc<dyn Hittable>,
+}
+
+impl Holdout {
+    pub fn new(object: Arc<dyn Hittable>) -> Self {
+        Self { object }
+    }
+}
+
+impl Hittable for Holdout {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut rec = self.object.hit(r, ray_t)?;
+        rec.holdout = true;
+        Some(rec)
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.object.is_occluded(r, ray_t)
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        let start = out.len();
+        self.object.hit_all_into(r, ray_t, out);
+        for rec in &mut out[start..] {
+            rec.holdout = true;
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.object.bounding_box(time0, time1)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        self.object.materials(out);
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.geometry_bytes += std::mem::size_of_val(self);
+        self.object.stats(stats);
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::Holdout {
+            object: Box::new(self.object.to_desc(materials)?),
+        })
+    }
+}
+
+// Which kinds of ray see an object. Hiding one from the camera but not from indirect rays
+// makes a bounce card: it lights the scene and shows in reflections, but not directly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Visibility {
+    pub camera: bool,
+    // Whether it casts shadows from `Light`s.
+    pub shadow: bool,
+    // Rays after a bounce: reflections, refractions and indirect light.
+    pub indirect: bool,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self {
+            camera: true,
+            shadow: true,
+            indirect: true,
+        }
+    }
+}
+
+impl Visibility {
+    pub fn with_camera(mut self, camera: bool) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    pub fn with_indirect(mut self, indirect: bool) -> Self {
+        self.indirect = indirect;
+        self
+    }
+
+    #[inline]
+    pub fn sees(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera,
+            RayKind::Shadow => self.shadow,
+            RayKind::Indirect => self.indirect,
+        }
+    }
+}
+
+// An object only the kinds of ray in `visibility` hit; others pass through it.
+pub struct Masked {
+    pub object: Arc<dyn Hittable>,
+    pub visibility: Visibility,
+}
+
+impl Masked {
+    pub fn new(object: Arc<dyn Hittable>, visibility: Visibility) -> Self {
+        Self { object, visibility }
+    }
+}
+
+impl Hittable for Masked {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if !self.visibility.sees(r.kind()) {
+            return None;
+        }
+        self.object.hit(r, ray_t)
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.visibility.sees(r.kind()) && self.object.is_occluded(r, ray_t)
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        if self.visibility.sees(r.kind()) {
+            self.object.hit_all_into(r, ray_t, out);
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.object.bounding_box(time0, time1)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        self.object.materials(out);
+    }
+
+    // Hidden lights still light the scene.
+    fn lights<'a>(&'a self, out: &mut Vec<&'a dyn Light>) {
+        self.object.lights(out);
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.geometry_bytes += std::mem::size_of_val(self);
+        self.object.stats(stats);
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::Masked {
+            object: Box::new(self.object.to_desc(materials)?),
+            visibility: self.visibility,
+        })
+    }
+}
+
+// Half-space removed by a clipping plane: points with dot(p - point, normal) > 0 are cut away.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClipPlane {
+    pub point: Point3,
+    pub normal: Vec3,
+}
 
-            root = (-half_b + sqrtd) / a;
-            if root > t_min && root < t_max {
-                let p = r.at(root);
-                let normal = (p - self.center) / self.radius;
-                return Some(HitRecord {
-                    t: root,
-                    point: p,
-                    normal,
-                    material: Arc::clone(&self.material),
-                });
+impl ClipPlane {
+    pub fn new(point: Point3, normal: Vec3) -> Self {
+        Self { point, normal }
+    }
+
+    #[inline]
+    pub fn clips(&self, p: Point3) -> bool {
+        Vec3::dot(p - self.point, self.normal) > 0.0
+    }
+}
+
+// Cutaway view of the wrapped object: hits in any clipped half-space are skipped, so rays
+// see through to whatever lies behind.
+pub struct Clipped {
+    pub object: Arc<dyn Hittable>,
+    pub planes: Vec<ClipPlane>,
+}
+
+impl Clipped {
+    pub fn new(object: Arc<dyn Hittable>, planes: Vec<ClipPlane>) -> Self {
+        Self { object, planes }
+    }
+}
+
+impl Hittable for Clipped {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut ray_t = ray_t;
+        loop {
+            let rec = self.object.hit(r, ray_t)?;
+            if !self.planes.iter().any(|p| p.clips(rec.point)) {
+                return Some(rec);
             }
+            ray_t.min = rec.t;
         }
+    }
 
-        None
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        let mut hits = Vec::new();
+        self.object.hit_all_into(r, ray_t, &mut hits);
+        out.extend(
+            hits.into_iter()
+                .filter(|rec| !self.planes.iter().any(|p| p.clips(rec.point))),
+        );
+    }
+
+    // Conservative: clipping is ignored.
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.object.bounding_box(time0, time1)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        self.object.materials(out);
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.geometry_bytes +=
+            std::mem::size_of_val(self) + self.planes.capacity() * std::mem::size_of::<ClipPlane>();
+        self.object.stats(stats);
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::Clipped {
+            object: Box::new(self.object.to_desc(materials)?),
+            planes: self.planes.clone(),
+        })
     }
 }
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+// Closed range of ray parameters (or any scalar). `min > max` is empty.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Interval {
+    pub const EMPTY: Self = Self {
+        min: f64::INFINITY,
+        max: f64::NEG_INFINITY,
+    };
+
+    pub const UNIVERSE: Self = Self {
+        min: f64::NEG_INFINITY,
+        max: f64::INFINITY,
+    };
+
+    #[inline]
+    pub const fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+
+    #[inline]
+    pub fn size(self) -> f64 {
+        self.max - self.min
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.min > self.max
+    }
+
+    // min <= x <= max
+    #[inline]
+    pub fn contains(self, x: f64) -> bool {
+        self.min <= x && x <= self.max
+    }
+
+    // min < x < max
+    #[inline]
+    pub fn surrounds(self, x: f64) -> bool {
+        self.min < x && x < self.max
+    }
+
+    #[inline]
+    pub fn clamp(self, x: f64) -> f64 {
+        x.max(self.min).min(self.max)
+    }
+
+    // Grows the interval by `delta` in total, half on each side.
+    #[inline]
+    pub fn expand(self, delta: f64) -> Self {
+        let padding = 0.5 * delta;
+        Self::new(self.min - padding, self.max + padding)
+    }
+
+    // Smallest interval containing both.
+    #[inline]
+    pub fn union(a: Self, b: Self) -> Self {
+        Self::new(a.min.min(b.min), a.max.max(b.max))
+    }
+
+    #[inline]
+    pub fn intersect(a: Self, b: Self) -> Self {
+        Self::new(a.min.max(b.min), a.max.min(b.max))
+    }
+
+    #[inline]
+    pub fn with_min(self, min: f64) -> Self {
+        Self::new(min, self.max)
+    }
+
+    #[inline]
+    pub fn with_max(self, max: f64) -> Self {
+        Self::new(self.min, max)
+    }
+}
@@ -0,0 +1,315 @@
+use crate::error::{Error, Result};
+use crate::material::refract;
+use crate::ray::{Ray, RayKind};
+use crate::vec3::{Point3, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Lens tables are in millimetres; the scene is assumed to be in metres.
+const MM_TO_SCENE: f64 = 0.001;
+
+// One refracting surface of a lens system, listed front (scene side) to back (film side).
+// A curvature radius of zero marks the aperture stop.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LensElement {
+    pub curvature_radius: f64,
+    // Distance along the axis to the next element (or to the film for the last one).
+    pub thickness: f64,
+    // IOR of the medium behind this surface; zero for the aperture stop.
+    pub eta: f64,
+    pub aperture_radius: f64,
+}
+
+impl LensElement {
+    pub const fn new(
+        curvature_radius: f64,
+        thickness: f64,
+        eta: f64,
+        aperture_diameter: f64,
+    ) -> Self {
+        Self {
+            curvature_radius,
+            thickness,
+            eta,
+            aperture_radius: aperture_diameter * 0.5,
+        }
+    }
+
+    #[inline]
+    fn is_stop(&self) -> bool {
+        self.curvature_radius == 0.0
+    }
+}
+
+// The 50mm double Gauss from PBRT's lens collection.
+pub fn double_gauss_50mm() -> Vec<LensElement> {
+    vec![
+        LensElement::new(29.475, 3.76, 1.67, 25.2),
+        LensElement::new(84.83, 0.12, 1.0, 25.2),
+        LensElement::new(19.275, 4.025, 1.67, 23.0),
+        LensElement::new(40.77, 3.275, 1.699, 23.0),
+        LensElement::new(12.75, 5.705, 1.0, 18.0),
+        LensElement::new(0.0, 4.5, 0.0, 17.1),
+        LensElement::new(-14.495, 1.18, 1.603, 17.0),
+        LensElement::new(40.77, 6.065, 1.658, 20.0),
+        LensElement::new(-20.385, 0.19, 1.0, 20.0),
+        LensElement::new(437.065, 3.22, 1.717, 20.0),
+        LensElement::new(-39.73, 0.0, 1.0, 20.0),
+    ]
+}
+
+// Camera that traces rays through a lens table, following PBRT's RealisticCamera.
+// Lens space has the film at z = 0 and the scene towards -z.
+pub struct RealisticCamera {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    elements: Vec<LensElement>,
+    film_width: f64,
+    film_height: f64,
+}
+
+impl RealisticCamera {
+    // Errors if the lens table is empty or not a physical lens, or can't focus at `focus_dist`.
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        vup: Vec3,
+        elements: Vec<LensElement>,
+        film_diagonal_mm: f64,
+        aspect_ratio: f64,
+        focus_dist: f64,
+    ) -> Result<Self> {
+        if elements.is_empty() {
+            return Err(Error::Scene(
+                "a lens needs at least one element".to_string(),
+            ));
+        }
+        for (i, e) in elements.iter().enumerate() {
+            let valid = e.curvature_radius.is_finite()
+                && e.thickness >= 0.0
+                && e.thickness.is_finite()
+                && e.aperture_radius > 0.0
+                && e.aperture_radius.is_finite()
+                && if e.is_stop() {
+                    e.eta == 0.0
+                } else {
+                    e.eta > 0.0 && e.eta.is_finite()
+                };
+            if !valid {
+                return Err(Error::Scene(format!("lens element {i} is invalid: {e:?}")));
+            }
+        }
+        let positive = |v: f64| v > 0.0 && v.is_finite();
+        if !(positive(film_diagonal_mm) && positive(aspect_ratio) && positive(focus_dist)) {
+            return Err(Error::Scene(format!(
+                "a lens needs a positive film diagonal, aspect ratio and focus distance, not \
+                 {film_diagonal_mm}, {aspect_ratio} and {focus_dist}"
+            )));
+        }
+
+        let w = Vec3::unit_vector(look_from - look_at);
+        let u = Vec3::unit_vector(Vec3::cross(vup, w));
+        let v = Vec3::cross(w, u);
+
+        let film_height = film_diagonal_mm / (aspect_ratio * aspect_ratio + 1.0).sqrt();
+        let film_width = aspect_ratio * film_height;
+
+        let mut camera = Self {
+            origin: look_from,
+            u,
+            v,
+            w,
+            elements,
+            film_width,
+            film_height,
+        };
+
+        let back = camera
+            .focus_thick_lens(focus_dist / MM_TO_SCENE)
+            .ok_or_else(|| Error::Scene(format!("the lens can't focus at {focus_dist}")))?;
+        if let Some(rear) = camera.elements.last_mut() {
+            rear.thickness = back;
+        }
+        Ok(camera)
+    }
+
+    // Overrides the aperture stop diameter (in mm), clamped to the stop's physical size.
+    pub fn with_aperture_diameter(mut self, diameter: f64) -> Self {
+        if let Some(stop) = self.elements.iter_mut().find(|e| e.is_stop()) {
+            stop.aperture_radius = (diameter * 0.5).min(stop.aperture_radius);
+        }
+        self
+    }
+
+    // Returns None when the ray is blocked inside the lens system.
+    pub fn get_ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        // The lens inverts the image, so the film is mirrored relative to the screen.
+        let p_film = Point3::new(
+            -(s - 0.5) * self.film_width,
+            -(t - 0.5) * self.film_height,
+            0.0,
+        );
+
+        let rear = self.elements.last()?;
+        let p_rear = loop {
+            let x = rng.random_range(-1.0..1.0);
+            let y = rng.random_range(-1.0..1.0);
+            if x * x + y * y < 1.0 {
+                break Point3::new(
+                    x * rear.aperture_radius,
+                    y * rear.aperture_radius,
+                    -self.rear_z(),
+                );
+            }
+        };
+
+        let r = self.trace_from_film(Ray::new(p_film, p_rear - p_film))?;
+
+        let o = MM_TO_SCENE * r.origin();
+        let d = r.direction();
+        Some(
+            Ray::new(
+                self.origin + o.x * self.u + o.y * self.v + o.z * self.w,
+                Vec3::unit_vector(d.x * self.u + d.y * self.v + d.z * self.w),
+            )
+            .with_kind(RayKind::Camera),
+        )
+    }
+
+    #[inline]
+    fn rear_z(&self) -> f64 {
+        self.elements.last().map_or(0.0, |e| e.thickness)
+    }
+
+    #[inline]
+    fn front_z(&self) -> f64 {
+        self.elements.iter().map(|e| e.thickness).sum()
+    }
+
+    fn trace_from_film(&self, ray: Ray) -> Option<Ray> {
+        let mut r = ray;
+        let mut element_z = 0.0;
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            element_z -= element.thickness;
+            let (t, n) = intersect_element(element, element_z, &r)?;
+            let p = r.at(t);
+            if p.x * p.x + p.y * p.y > element.aperture_radius * element.aperture_radius {
+                return None;
+            }
+            let d = if element.is_stop() {
+                r.direction()
+            } else {
+                let eta_i = element.eta;
+                let eta_t = match i {
+                    0 => 1.0,
+                    _ if self.elements[i - 1].eta == 0.0 => 1.0,
+                    _ => self.elements[i - 1].eta,
+                };
+                refract(r.direction(), n, eta_i / eta_t)?
+            };
+            r = Ray::new(p, d);
+        }
+        Some(r)
+    }
+
+    fn trace_from_scene(&self, ray: Ray) -> Option<Ray> {
+        let mut r = ray;
+        let mut element_z = -self.front_z();
+        for (i, element) in self.elements.iter().enumerate() {
+            let (t, n) = intersect_element(element, element_z, &r)?;
+            let p = r.at(t);
+            if p.x * p.x + p.y * p.y > element.aperture_radius * element.aperture_radius {
+                return None;
+            }
+            let d = if element.is_stop() {
+                r.direction()
+            } else {
+                let eta_i = match i {
+                    0 => 1.0,
+                    _ if self.elements[i - 1].eta == 0.0 => 1.0,
+                    _ => self.elements[i - 1].eta,
+                };
+                let eta_t = if element.eta != 0.0 { element.eta } else { 1.0 };
+                refract(r.direction(), n, eta_i / eta_t)?
+            };
+            r = Ray::new(p, d);
+            element_z += element.thickness;
+        }
+        Some(r)
+    }
+
+    // Principal plane and focal point (as distances along the axis) of a traced paraxial ray.
+    fn cardinal_points(r_in: Ray, r_out: Ray) -> (f64, f64) {
+        let tf = -r_out.origin().x / r_out.direction().x;
+        let fz = r_out.at(tf).z;
+        let tp = (r_in.origin().x - r_out.origin().x) / r_out.direction().x;
+        let pz = r_out.at(tp).z;
+        (pz, fz)
+    }
+
+    // Film distance that brings `focus_dist` (mm) into focus, using a thick lens approximation.
+    fn focus_thick_lens(&self, focus_dist: f64) -> Option<f64> {
+        let x = 0.001
+            * (self.film_width * self.film_width + self.film_height * self.film_height).sqrt();
+
+        let r_scene = Ray::new(
+            Point3::new(x, 0.0, -self.front_z() - 1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let r_film = self.trace_from_scene(r_scene)?;
+        let (pz0, fz0) = Self::cardinal_points(r_scene, r_film);
+
+        let r_film = Ray::new(
+            Point3::new(x, 0.0, -self.rear_z() + 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        );
+        let r_scene = self.trace_from_film(r_film)?;
+        let (pz1, _fz1) = Self::cardinal_points(r_film, r_scene);
+
+        let f = fz0 - pz0;
+        let z = -focus_dist;
+        let c = (pz1 - z - pz0) * (pz1 - z - 4.0 * f - pz0);
+        if c <= 0.0 {
+            return None;
+        }
+        let delta = 0.5 * (pz1 - z + pz0 - c.sqrt());
+        Some(self.rear_z() + delta)
+    }
+}
+
+// Hit distance and a normal facing against the ray for one lens surface.
+fn intersect_element(element: &LensElement, element_z: f64, ray: &Ray) -> Option<(f64, Vec3)> {
+    let d = ray.direction();
+    if element.is_stop() {
+        if d.z == 0.0 {
+            return None;
+        }
+        let t = (element_z - ray.origin().z) / d.z;
+        return (t >= 0.0).then_some((t, Vec3::new(0.0, 0.0, -d.z.signum())));
+    }
+
+    let radius = element.curvature_radius;
+    let o = ray.origin() - Vec3::new(0.0, 0.0, element_z + radius);
+    let a = Vec3::dot(d, d);
+    let b = 2.0 * Vec3::dot(d, o);
+    let c = Vec3::dot(o, o) - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrtd = discriminant.sqrt();
+    let t0 = (-b - sqrtd) / (2.0 * a);
+    let t1 = (-b + sqrtd) / (2.0 * a);
+
+    let use_closer = (d.z > 0.0) ^ (radius < 0.0);
+    let t = if use_closer { t0.min(t1) } else { t0.max(t1) };
+    if t < 0.0 {
+        return None;
+    }
+
+    let n = Vec3::unit_vector(o + t * d);
+    let n = if Vec3::dot(n, d) > 0.0 { -n } else { n };
+    Some((t, n))
+}
@@ -1,5 +1,69 @@
+pub mod aabb;
+pub mod aov;
+pub mod atmosphere;
+pub mod background;
+pub mod bake;
+pub mod bloom;
 pub mod camera;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod color;
+pub mod compare;
+pub mod config;
+pub mod control;
+pub mod dng;
+#[cfg(feature = "embree")]
+pub mod embree;
+pub mod envmap;
+pub mod error;
+pub mod exposure;
+pub mod film;
+pub mod focus;
+pub mod generator;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod guiding;
 pub mod hittable;
+pub mod interval;
+pub mod lens;
+pub mod light;
+pub mod lighttrace;
+pub mod lookdev;
 pub mod material;
+pub mod math;
+pub mod memory;
+pub mod mesh;
+pub mod paged;
+pub mod pathdump;
+pub mod polarization;
+pub mod post;
+pub mod procgen;
 pub mod ray;
+pub mod reload;
+pub mod render;
+pub mod report;
+pub mod reproject;
+pub mod restir;
+pub mod scatter;
+pub mod scene;
+#[cfg(feature = "script")]
+pub mod script;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod sheet;
+pub mod soa;
+pub mod stats;
+pub mod stereo;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod sun;
+pub mod texture;
+#[cfg(feature = "vdb")]
+pub mod vdb;
 pub mod vec3;
+pub mod video;
+pub mod volume;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use error::{Error, Result};
@@ -0,0 +1,571 @@
+// Lights that can be sampled directly. Emissive geometry is only found when a path happens to
+// hit it; a `Light` can also be aimed at from every diffuse bounce (next-event estimation),
+// which is what makes soft shadows converge predictably. See `RenderSettings` for the
+// shadow-sample count and multiple importance sampling controls.
+
+use crate::aabb::Aabb;
+use crate::hittable::{face_normal, HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::{random_cosine_direction, DiffuseLight, Material, MaterialId, MaterialRef};
+use crate::math::Onb;
+use crate::ray::Ray;
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::texture::ImageTexture;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// A point on a light as seen from some origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightSample {
+    // Unit direction from the origin towards the point.
+    pub direction: Vec3,
+    pub distance: f64,
+    // Density per unit solid angle at the origin.
+    pub pdf: f64,
+    // Radiance leaving the light towards the origin.
+    pub radiance: Color,
+}
+
+// A ray leaving a light, for tracing paths from the lights.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EmissionSample {
+    pub ray: Ray,
+    // Emitted radiance times cosine over the density of picking the ray's origin and
+    // direction: the power the ray carries.
+    pub weight: Color,
+}
+
+pub trait Light: Send + Sync {
+    fn sample(&self, origin: Point3, rng: &mut dyn rand::RngCore) -> Option<LightSample>;
+
+    // A ray leaving the light, for light tracing; None for lights that can't start one, like
+    // the environment.
+    fn sample_emission(&self, _rng: &mut dyn rand::RngCore) -> Option<EmissionSample> {
+        None
+    }
+
+    // Distance along `direction` (in its units) and solid-angle density with which `sample`
+    // picks that direction from `origin`; None if the light isn't there or doesn't face it.
+    fn pdf(&self, origin: Point3, direction: Vec3) -> Option<(f64, f64)>;
+
+    // Name of the light group direct lighting from this light is reported under, if any.
+    fn light_group(&self) -> Option<Arc<str>> {
+        None
+    }
+}
+
+// Rectangular area light spanned by edges `u` and `v` from `corner`. It emits on the side
+// `u x v` points to, or on both sides if two-sided; the dark side absorbs.
+pub struct QuadLight {
+    pub corner: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub two_sided: bool,
+    light: Arc<DiffuseLight>,
+    material: MaterialRef,
+    back: MaterialRef,
+    normal: Vec3,
+    area: f64,
+}
+
+impl QuadLight {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, emit: Color) -> Self {
+        Self::from_light(corner, u, v, DiffuseLight::new(emit))
+    }
+
+    fn from_light(corner: Point3, u: Vec3, v: Vec3, light: DiffuseLight) -> Self {
+        let n = Vec3::cross(u, v);
+        let light = Arc::new(light);
+        Self {
+            corner,
+            u,
+            v,
+            two_sided: false,
+            material: MaterialRef::from(light.clone()),
+            light,
+            back: MaterialRef::from(Arc::new(DiffuseLight::new(Color::default()))),
+            normal: Vec3::unit_vector(n),
+            area: n.length(),
+        }
+    }
+
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    pub fn with_group(self, group: &str) -> Self {
+        let light = DiffuseLight::new(self.emit()).with_group(group);
+        Self::from_light(self.corner, self.u, self.v, light).with_two_sided(self.two_sided)
+    }
+
+    #[inline]
+    pub fn emit(&self) -> Color {
+        self.light.emit
+    }
+
+    #[inline]
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+
+    // Whether light leaves towards `origin`.
+    #[inline]
+    fn faces(&self, origin: Point3) -> bool {
+        self.two_sided || Vec3::dot(origin - self.corner, self.normal) > 0.0
+    }
+
+    // Ray parameter of the hit, if the ray crosses the quad within `ray_t`.
+    fn intersect(&self, r: &Ray, ray_t: Interval) -> Option<f64> {
+        let denom = Vec3::dot(self.normal, r.direction());
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = Vec3::dot(self.corner - r.origin(), self.normal) / denom;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+        let (alpha, beta) = self.coordinates(r.at(t));
+        ((0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta)).then_some(t)
+    }
+
+    // Position of a point in the quad's plane along `u` and `v`, 0 to 1 across the quad.
+    fn coordinates(&self, p: Point3) -> (f64, f64) {
+        let planar = p - self.corner;
+        let n = Vec3::cross(self.u, self.v);
+        let w = n / Vec3::dot(n, n);
+        (
+            Vec3::dot(w, Vec3::cross(planar, self.v)),
+            Vec3::dot(w, Vec3::cross(self.u, planar)),
+        )
+    }
+}
+
+impl Hittable for QuadLight {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let t = self.intersect(r, ray_t)?;
+        let (front_face, normal) = face_normal(r, self.normal);
+        let material = if front_face || self.two_sided {
+            self.material.id()
+        } else {
+            self.back.id()
+        };
+        let (u, v) = self.coordinates(r.at(t));
+        Some(HitRecord {
+            t,
+            point: r.at(t),
+            normal,
+            front_face,
+            material,
+            object_id: 0,
+            holdout: false,
+            u,
+            v,
+        })
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        let c = self.corner;
+        Some(
+            Aabb::from_points(c, c + self.u + self.v)
+                .expand(c + self.u)
+                .expand(c + self.v)
+                .pad(1e-4),
+        )
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    fn lights<'a>(&'a self, out: &mut Vec<&'a dyn Light>) {
+        out.push(self);
+    }
+
+    fn to_desc(&self, _materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::QuadLight {
+            corner: self.corner,
+            u: self.u,
+            v: self.v,
+            emit: self.light.emit,
+            two_sided: self.two_sided,
+            group: self.light.group.as_deref().map(str::to_string),
+            power: None,
+        })
+    }
+}
+
+impl Light for QuadLight {
+    fn sample(&self, origin: Point3, rng: &mut dyn rand::RngCore) -> Option<LightSample> {
+        if !self.faces(origin) || self.area <= 0.0 {
+            return None;
+        }
+        let p = self.corner + rng.random::<f64>() * self.u + rng.random::<f64>() * self.v;
+        let to_light = p - origin;
+        let distance = to_light.length();
+        let direction = to_light / distance;
+        let cosine = Vec3::dot(direction, self.normal).abs();
+        if cosine < 1e-9 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            pdf: distance * distance / (cosine * self.area),
+            radiance: self.light.emit,
+        })
+    }
+
+    // Uniform over the area, cosine-weighted about the normal on an emitting side.
+    fn sample_emission(&self, rng: &mut dyn rand::RngCore) -> Option<EmissionSample> {
+        if self.area <= 0.0 {
+            return None;
+        }
+        let p = self.corner + rng.random::<f64>() * self.u + rng.random::<f64>() * self.v;
+        let (normal, sides) = if self.two_sided && rng.random::<bool>() {
+            (-self.normal, 2.0)
+        } else if self.two_sided {
+            (self.normal, 2.0)
+        } else {
+            (self.normal, 1.0)
+        };
+        let direction = Onb::from_w(normal).to_world(random_cosine_direction(rng));
+        Some(EmissionSample {
+            ray: Ray::new(p, direction),
+            weight: (sides * PI * self.area) * self.light.emit,
+        })
+    }
+
+    fn pdf(&self, origin: Point3, direction: Vec3) -> Option<(f64, f64)> {
+        if !self.faces(origin) {
+            return None;
+        }
+        let r = Ray::new(origin, direction);
+        let t = self.intersect(&r, Interval::new(0.0, f64::INFINITY))?;
+        let distance = t * direction.length();
+        let cosine = Vec3::dot(Vec3::unit_vector(direction), self.normal).abs();
+        Some((t, distance * distance / (cosine * self.area)))
+    }
+
+    fn light_group(&self) -> Option<Arc<str>> {
+        self.light.group.clone()
+    }
+}
+
+// Disk-shaped spot light of `radius` at `position`, shining a cone of half-angle `angle` along
+// its axis. Towards the edge of the cone the beam fades out over the outer `softness` share of
+// the angle, and an optional gobo image is projected across it like a slide, its top towards
+// the light's `up`. Hits on the lit face carry where in the beam the ray left as (u, v), -1 to
+// 1 across the cone, which is what the emission is looked up by.
+pub struct SpotLight {
+    pub position: Point3,
+    pub radius: f64,
+    beam: Arc<Beam>,
+    material: MaterialRef,
+    back: MaterialRef,
+}
+
+#[derive(Clone)]
+struct Beam {
+    emit: Color,
+    // Unit axis, and the gobo's right and up as seen looking down the beam.
+    axis: Vec3,
+    right: Vec3,
+    up: Vec3,
+    // Radians, below 90 degrees.
+    angle: f64,
+    softness: f64,
+    gobo: Option<ImageTexture>,
+    group: Option<Arc<str>>,
+}
+
+impl SpotLight {
+    // `angle` in degrees, at most 89.
+    pub fn new(position: Point3, direction: Vec3, radius: f64, angle: f64, emit: Color) -> Self {
+        let beam = Beam {
+            emit,
+            axis: Vec3::unit_vector(direction),
+            right: Vec3::default(),
+            up: Vec3::default(),
+            angle: angle.clamp(0.01, 89.0).to_radians(),
+            softness: 0.0,
+            gobo: None,
+            group: None,
+        };
+        Self::from_beam(position, radius, beam).with_up(Vec3::new(0.0, 1.0, 0.0))
+    }
+
+    fn from_beam(position: Point3, radius: f64, beam: Beam) -> Self {
+        let beam = Arc::new(beam);
+        Self {
+            position,
+            radius,
+            material: MaterialRef::from(beam.clone()),
+            beam,
+            back: MaterialRef::from(Arc::new(DiffuseLight::new(Color::default()))),
+        }
+    }
+
+    fn with_beam(self, change: impl FnOnce(&mut Beam)) -> Self {
+        let mut beam = (*self.beam).clone();
+        change(&mut beam);
+        Self::from_beam(self.position, self.radius, beam)
+    }
+
+    // Which way the top of the gobo points; along the axis, the world's z is used instead.
+    pub fn with_up(self, up: Vec3) -> Self {
+        self.with_beam(|b| {
+            let mut right = Vec3::cross(b.axis, up);
+            if right.length_squared() < 1e-12 {
+                right = Vec3::cross(b.axis, Vec3::new(0.0, 0.0, 1.0));
+            }
+            b.right = Vec3::unit_vector(right);
+            b.up = Vec3::cross(b.right, b.axis);
+        })
+    }
+
+    // Share of the cone, 0 for a hard edge to 1 for fading all the way from the center.
+    pub fn with_softness(self, softness: f64) -> Self {
+        self.with_beam(|b| b.softness = softness.clamp(0.0, 1.0))
+    }
+
+    // Image the beam's color is multiplied by, spread over the cone.
+    pub fn with_gobo(self, gobo: ImageTexture) -> Self {
+        self.with_beam(|b| b.gobo = Some(gobo))
+    }
+
+    pub fn with_group(self, group: &str) -> Self {
+        self.with_beam(|b| b.group = Some(Arc::from(group)))
+    }
+
+    #[inline]
+    pub fn emit(&self) -> Color {
+        self.beam.emit
+    }
+
+    #[inline]
+    pub fn direction(&self) -> Vec3 {
+        self.beam.axis
+    }
+
+    // Half-angle of the cone in degrees.
+    #[inline]
+    pub fn angle(&self) -> f64 {
+        self.beam.angle.to_degrees()
+    }
+
+    #[inline]
+    pub fn softness(&self) -> f64 {
+        self.beam.softness
+    }
+
+    // Radiance leaving the light along `direction`.
+    pub fn radiance(&self, direction: Vec3) -> Color {
+        let (x, y) = self.beam.coordinates(direction);
+        self.beam.profile(x, y)
+    }
+
+    #[inline]
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn random_point(&self, rng: &mut dyn rand::RngCore) -> Point3 {
+        let r = self.radius * rng.random::<f64>().sqrt();
+        let phi = 2.0 * PI * rng.random::<f64>();
+        self.position + r * (phi.cos() * self.beam.right + phi.sin() * self.beam.up)
+    }
+
+    #[inline]
+    fn faces(&self, origin: Point3) -> bool {
+        Vec3::dot(origin - self.position, self.beam.axis) > 0.0
+    }
+
+    fn intersect(&self, r: &Ray, ray_t: Interval) -> Option<f64> {
+        let denom = Vec3::dot(self.beam.axis, r.direction());
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = Vec3::dot(self.position - r.origin(), self.beam.axis) / denom;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+        ((r.at(t) - self.position).length_squared() <= self.radius * self.radius).then_some(t)
+    }
+}
+
+impl Beam {
+    // Where light leaving along `direction` crosses the gobo, -1 to 1 across the cone; infinite
+    // behind the light.
+    fn coordinates(&self, direction: Vec3) -> (f64, f64) {
+        let along = Vec3::dot(direction, self.axis);
+        if along <= 0.0 {
+            return (f64::INFINITY, f64::INFINITY);
+        }
+        let scale = along * self.angle.tan();
+        (
+            Vec3::dot(direction, self.right) / scale,
+            Vec3::dot(direction, self.up) / scale,
+        )
+    }
+
+    fn profile(&self, x: f64, y: f64) -> Color {
+        let r = x.hypot(y);
+        if r.is_nan() || r > 1.0 {
+            return Color::default();
+        }
+        let theta = (r * self.angle.tan()).atan();
+        let inner = self.angle * (1.0 - self.softness);
+        let falloff = if theta <= inner {
+            1.0
+        } else {
+            let s = (self.angle - theta) / (self.angle - inner);
+            s * s * (3.0 - 2.0 * s)
+        };
+        let pattern = match &self.gobo {
+            Some(gobo) => gobo.value(0.5 * (x + 1.0), 0.5 * (y + 1.0)),
+            None => Color::new(1.0, 1.0, 1.0),
+        };
+        falloff * self.emit * pattern
+    }
+}
+
+impl Material for Beam {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _rec: &HitRecord,
+        _rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.profile(rec.u, rec.v)
+    }
+
+    fn light_group(&self) -> Option<Arc<str>> {
+        self.group.clone()
+    }
+
+    fn textures(&self, out: &mut Vec<ImageTexture>) {
+        out.extend(self.gobo.clone());
+    }
+}
+
+impl Hittable for SpotLight {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let t = self.intersect(r, ray_t)?;
+        let (front_face, normal) = face_normal(r, self.beam.axis);
+        let (material, (u, v)) = if front_face {
+            let leaving = -Vec3::unit_vector(r.direction());
+            (self.material.id(), self.beam.coordinates(leaving))
+        } else {
+            (self.back.id(), (0.0, 0.0))
+        };
+        Some(HitRecord {
+            t,
+            point: r.at(t),
+            normal,
+            front_face,
+            material,
+            object_id: 0,
+            holdout: false,
+            u,
+            v,
+        })
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        let (right, up) = (self.radius * self.beam.right, self.radius * self.beam.up);
+        let p = self.position;
+        Some(
+            Aabb::from_points(p - right - up, p + right + up)
+                .expand(p + right - up)
+                .expand(p - right + up)
+                .pad(1e-4),
+        )
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    fn lights<'a>(&'a self, out: &mut Vec<&'a dyn Light>) {
+        out.push(self);
+    }
+
+    fn to_desc(&self, _materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        let beam = &self.beam;
+        Some(ObjectDesc::SpotLight {
+            position: self.position,
+            direction: beam.axis,
+            up: Some(beam.up),
+            radius: self.radius,
+            angle: self.angle(),
+            softness: beam.softness,
+            emit: beam.emit,
+            gobo: beam.gobo.as_ref().map(|g| g.path().to_path_buf()),
+            group: beam.group.as_deref().map(str::to_string),
+        })
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, origin: Point3, rng: &mut dyn rand::RngCore) -> Option<LightSample> {
+        if !self.faces(origin) || self.radius <= 0.0 {
+            return None;
+        }
+        let to_light = self.random_point(rng) - origin;
+        let distance = to_light.length();
+        let direction = to_light / distance;
+        let cosine = -Vec3::dot(direction, self.beam.axis);
+        let radiance = self.radiance(-direction);
+        if cosine < 1e-9 || radiance == Color::default() {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            pdf: distance * distance / (cosine * self.area()),
+            radiance,
+        })
+    }
+
+    // Uniform over the disk and over the solid angle of the cone.
+    fn sample_emission(&self, rng: &mut dyn rand::RngCore) -> Option<EmissionSample> {
+        if self.radius <= 0.0 {
+            return None;
+        }
+        let p = self.random_point(rng);
+        let cos_max = self.beam.angle.cos();
+        let cos_theta = 1.0 - rng.random::<f64>() * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.random::<f64>();
+        let beam = &self.beam;
+        let direction =
+            sin_theta * (phi.cos() * beam.right + phi.sin() * beam.up) + cos_theta * beam.axis;
+        let solid_angle = 2.0 * PI * (1.0 - cos_max);
+        Some(EmissionSample {
+            ray: Ray::new(p, direction),
+            weight: (cos_theta * self.area() * solid_angle) * self.radiance(direction),
+        })
+    }
+
+    fn pdf(&self, origin: Point3, direction: Vec3) -> Option<(f64, f64)> {
+        if !self.faces(origin) {
+            return None;
+        }
+        let r = Ray::new(origin, direction);
+        let t = self.intersect(&r, Interval::new(0.0, f64::INFINITY))?;
+        let distance = t * direction.length();
+        let cosine = Vec3::dot(Vec3::unit_vector(direction), self.beam.axis).abs();
+        Some((t, distance * distance / (cosine * self.area())))
+    }
+
+    fn light_group(&self) -> Option<Arc<str>> {
+        self.beam.group.clone()
+    }
+}
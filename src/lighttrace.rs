@@ -0,0 +1,120 @@
+// Light tracing: paths start at the lights, and every diffuse vertex is connected to the camera
+// and splatted onto the film. It finds caustics, light focused by mirrors and glass onto matte
+// receivers, which camera paths only reach by chance. It misses whatever the camera sees in a
+// mirror or through glass, and lights seen directly, so it suits caustic passes rather than
+// whole images. Only `Light`s start paths, and the camera is treated as a pinhole.
+
+use crate::camera::Camera;
+use crate::error::Result;
+use crate::film::Film;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::light::Light;
+use crate::ray::{Ray, RayKind};
+use crate::render::{hit_filtered, lights, sampler, RenderSettings, T_MIN};
+use crate::vec3::Color;
+use rand::Rng;
+use rayon::prelude::*;
+use tracing::info_span;
+
+// Traces `paths_per_pixel` times the film's pixel count light paths and splats what they send
+// to the camera. Develop the film with a splat scale of one over the paths per pixel traced
+// so far.
+pub fn render_light_paths(
+    world: &dyn Hittable,
+    camera: &Camera,
+    film: &Film,
+    paths_per_pixel: u32,
+    settings: &RenderSettings,
+) -> Result<()> {
+    let lights = lights(world);
+    if lights.is_empty() {
+        return Ok(());
+    }
+    let (width, height) = (film.width(), film.height());
+    let _span = info_span!(
+        "light tracing",
+        paths = paths_per_pixel as u64 * width as u64 * height as u64
+    )
+    .entered();
+    (0..height).into_par_iter().try_for_each(|row| {
+        let mut rng = sampler(settings.seed, 0, row as u64);
+        let mut splats = Vec::new();
+        for _ in 0..paths_per_pixel as u64 * width as u64 {
+            trace(world, camera, &lights, settings, &mut splats, &mut rng);
+        }
+        // Film y runs down from the top.
+        for (x, y, _) in &mut splats {
+            (*x, *y) = (*x * width as f64, (1.0 - *y) * height as f64);
+        }
+        film.add_splats(&splats)
+    })
+}
+
+// One light path, pushing (s, t, color) for each diffuse vertex the camera sees.
+fn trace(
+    world: &dyn Hittable,
+    camera: &Camera,
+    lights: &[&dyn Light],
+    settings: &RenderSettings,
+    splats: &mut Vec<(f64, f64, Color)>,
+    rng: &mut dyn rand::RngCore,
+) {
+    let light = lights[rng.random_range(0..lights.len())];
+    let Some(emission) = light.sample_emission(rng) else {
+        return;
+    };
+    let time = camera.sample_time(rng);
+    let mut ray = Ray::with_time(emission.ray.origin(), emission.ray.direction(), time);
+    let mut throughput = lights.len() as f64 * emission.weight;
+    for _ in 0..settings.bounces.total {
+        let ray_t = Interval::new(T_MIN, f64::INFINITY);
+        let Some(rec) = hit_filtered(world, &ray, ray_t, &mut throughput, rng) else {
+            break;
+        };
+        if rec.holdout {
+            break;
+        }
+        let Some((attenuation, continued)) = rec.material.get().scatter(&ray, &rec, rng) else {
+            break;
+        };
+        if rec.material.get().scattering_pdf(&ray, &rec, &continued) > 0.0 {
+            if let Some((s, t, c)) = connect(world, camera, &ray, &rec, attenuation) {
+                splats.push((s, t, throughput * c));
+            }
+        }
+        throughput *= attenuation;
+        if throughput == Color::default() {
+            break;
+        }
+        ray = continued;
+    }
+}
+
+// What the hit `rec` on `ray` sends to the camera per unit throughput, with its film
+// coordinates, if the camera sees it.
+fn connect(
+    world: &dyn Hittable,
+    camera: &Camera,
+    ray: &Ray,
+    rec: &HitRecord,
+    attenuation: Color,
+) -> Option<(f64, f64, Color)> {
+    let seen = camera.project(rec.point)?;
+    // It stands in for a camera ray, so it sees what the camera sees.
+    let to_camera =
+        Ray::with_time(rec.point, seen.direction, ray.time()).with_kind(RayKind::Camera);
+    if rec.material.get().scattering_pdf(ray, rec, &to_camera) <= 0.0 {
+        return None;
+    }
+    let visible = world.transmittance(&to_camera, Interval::new(T_MIN, seen.distance - T_MIN));
+    if visible == Color::default() {
+        return None;
+    }
+    // BSDF times cosine, as `scatter`'s attenuation is for its own sampling density.
+    let reflected = rec
+        .material
+        .get()
+        .scattering(ray, rec, attenuation, &to_camera);
+    Some((seen.s, seen.t, visible * reflected * seen.importance))
+}
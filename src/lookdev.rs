@@ -0,0 +1,91 @@
+// A fixed look-development scene for judging materials: a ball of the material on a grey
+// checkerboard under one soft key light and a neutral sky. Lighting, camera and floor never
+// change, so renders of different materials, or of one material before and after an edit,
+// compare directly.
+
+use crate::scene::{BackgroundDesc, CameraDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use crate::vec3::{Color, Point3, Vec3};
+
+// Checkerboard squares a side, each a ball radius wide.
+pub const TILES: usize = 8;
+
+// The previewed material is material 0 of the scene.
+pub fn material_ball(material: MaterialDesc) -> SceneDesc {
+    let grey = |v: f64| MaterialDesc::Lambertian {
+        albedo: Color::new(v, v, v),
+    };
+    let (light, dark) = checkerboard();
+    SceneDesc {
+        camera: CameraDesc {
+            look_from: Point3::new(0.0, 2.5, 7.5),
+            look_at: Point3::new(0.0, 0.9, 0.0),
+            vfov: 30.0,
+            focus_dist: 7.5,
+            ..Default::default()
+        },
+        materials: vec![material, grey(0.7), grey(0.2)],
+        objects: vec![
+            ObjectDesc::Sphere {
+                center: Point3::new(0.0, 1.0, 0.0),
+                radius: 1.0,
+                material: 0,
+            },
+            floor(light, 1),
+            floor(dark, 2),
+            // Up and to the left of the camera, facing down.
+            ObjectDesc::QuadLight {
+                corner: Point3::new(-4.0, 5.0, 1.0),
+                u: Vec3::new(2.0, 0.0, 0.0),
+                v: Vec3::new(0.0, 0.0, 2.0),
+                emit: Color::new(8.0, 8.0, 8.0),
+                two_sided: false,
+                group: None,
+                power: None,
+            },
+        ],
+        background: Some(BackgroundDesc::Gradient {
+            bottom: Color::new(0.2, 0.2, 0.2),
+            top: Color::new(0.6, 0.6, 0.6),
+        }),
+        meters_per_unit: None,
+    }
+}
+
+// Corners of the light and dark squares of the floor, centred under the ball at y = 0.
+fn checkerboard() -> (Vec<Point3>, Vec<Point3>) {
+    let (mut light, mut dark) = (Vec::new(), Vec::new());
+    let half = TILES as f64 / 2.0;
+    for i in 0..TILES {
+        for j in 0..TILES {
+            let (x, z) = (i as f64 - half, j as f64 - half);
+            let square = [
+                Point3::new(x, 0.0, z),
+                Point3::new(x + 1.0, 0.0, z),
+                Point3::new(x + 1.0, 0.0, z + 1.0),
+                Point3::new(x, 0.0, z + 1.0),
+            ];
+            if (i + j) % 2 == 0 {
+                &mut light
+            } else {
+                &mut dark
+            }
+            .extend(square);
+        }
+    }
+    (light, dark)
+}
+
+fn floor(corners: Vec<Point3>, material: usize) -> ObjectDesc {
+    let indices = (0..corners.len() as u32 / 4)
+        .flat_map(|s| [[4 * s, 4 * s + 1, 4 * s + 2], [4 * s, 4 * s + 2, 4 * s + 3]])
+        .collect();
+    ObjectDesc::Mesh {
+        positions: corners,
+        indices,
+        uvs: Vec::new(),
+        obj: None,
+        material,
+        target_triangles: None,
+        lod: false,
+    }
+}
@@ -1,209 +1,1329 @@
-mod camera;
-mod hittable;
-mod material;
-mod ray;
-mod vec3;
-
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 
-use image::{Rgba, RgbaImage};
-use rand::Rng;
-use rayon::prelude::*;
-
-use rtt::camera::Camera;
-use rtt::hittable::{Hittable, HittableList, Sphere};
-use rtt::material::{Dielectric, Lambertian, Material, Metal};
-use rtt::ray::Ray;
-use rtt::vec3::{Color, Point3, Vec3};
+use tracing::{error, info, info_span, warn, Level};
+use tracing_subscriber::EnvFilter;
 
-const WHITE: Color = Color {
-    x: 1.0,
-    y: 1.0,
-    z: 1.0,
-};
-const BLACK: Color = Color {
-    x: 0.0,
-    y: 0.0,
-    z: 0.0,
-};
-const BLUE: Color = Color {
-    x: 0.5,
-    y: 0.7,
-    z: 1.0,
+use rtt::aov::{Aov, AovSet};
+use rtt::atmosphere::{Atmosphere, Haze};
+use rtt::background::{Hdri, SunSky};
+use rtt::bake::{self, bake_curvature, bake_irradiance, bake_occlusion};
+use rtt::bloom::{Bloom, Glare};
+use rtt::camera::{Camera, Projection};
+use rtt::compare::{heatmap, split, DiffStats};
+use rtt::config::{Config, FORMATS};
+use rtt::envmap::{CubeFace, Cubemap, EnvLayout};
+use rtt::exposure::PhysicalExposure;
+use rtt::film::Film;
+use rtt::focus::save_focus_view;
+use rtt::generator::SceneGenerator;
+use rtt::hittable::HittableList;
+use rtt::lighttrace::render_light_paths;
+use rtt::lookdev::material_ball;
+use rtt::memory::MemoryEstimate;
+use rtt::mesh::{self, Mesh};
+use rtt::paged::{self, GeometryCache};
+use rtt::pathdump;
+use rtt::polarization::render_polarized;
+use rtt::post::{Grain, Look, Lut, Vignette, WhiteBalance};
+#[cfg(feature = "watch")]
+use rtt::reload::SceneCache;
+use rtt::render::{
+    noisy_pixels, render_image_with, repair_pixels, Preset, RenderSettings, SeedMode,
 };
+use rtt::report::save_exposure_report;
+use rtt::restir::Restir;
+use rtt::scene::{BackgroundDesc, CameraDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use rtt::sheet::ContactSheet;
+use rtt::stats::SceneStats;
+use rtt::stereo::{OdsCamera, StereoCamera, StereoLayout};
+use rtt::sun::{SolarPosition, SunTime};
+use rtt::texture::{TextureCache, TextureFormat};
+use rtt::vec3::{Color, Point3};
+use rtt::video::{VideoEncoder, VideoSettings};
+#[cfg(feature = "watch")]
+use rtt::watch::SceneWatcher;
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+const SAMPLES: u32 = 10;
+
+// Each -v raises and each -q lowers the log level from the default of INFO. RUST_LOG, when
+// set, takes precedence.
+fn init_logging() {
+    let verbosity = std::env::args()
+        .skip(1)
+        .fold(0i32, |v, arg| match arg.as_str() {
+            "-v" | "--verbose" => v + 1,
+            "-vv" => v + 2,
+            "-q" | "--quiet" => v - 1,
+            "-qq" => v - 2,
+            _ => v,
+        });
+    let level = match verbosity {
+        i32::MIN..=-2 => Level::ERROR,
+        -1 => Level::WARN,
+        0 => Level::INFO,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(level.as_str().to_lowercase()));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+// Value following `--name` on the command line, if present.
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    args.find(|a| a == name)?;
+    args.next()
+}
 
-fn clamp_u8(x: f64) -> u8 {
-    let x = x.clamp(0.0, 0.999);
-    (255.99 * x) as u8
+// Value following `--name`, parsed, if present.
+fn parse_flag<T: std::str::FromStr>(name: &str) -> rtt::Result<Option<T>> {
+    arg_value(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| rtt::Error::Scene(format!("bad {name} {value:?}")))
+        })
+        .transpose()
 }
 
-fn ray_color(ray: Ray, world: &HittableList, depth: i32, rng: &mut dyn rand::RngCore) -> Color {
-    if depth >= 50 {
-        return BLACK;
+// Comma-separated numbers given for `flag`.
+fn parse_float_list(flag: &str, value: &str) -> rtt::Result<Vec<f64>> {
+    value
+        .split(',')
+        .map(|v| v.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| rtt::Error::Scene(format!("bad {flag} {value:?}")))
+}
+
+// Finishing look from `--white-balance <kelvin>[,<tint>]` or `--white-balance <r>,<g>,<b>`,
+// `--vignette <strength>`, `--grain <amount>,<size>`, seeded by `--seed`, and
+// `--lut <file.cube>`; see `post`.
+fn look() -> rtt::Result<Look> {
+    let mut look = Look::default();
+    if let Some(white) = arg_value("--white-balance") {
+        look.white_balance = Some(match parse_float_list("--white-balance", &white)?[..] {
+            [kelvin] => WhiteBalance::from_temperature(kelvin, 0.0),
+            [kelvin, tint] => WhiteBalance::from_temperature(kelvin, tint),
+            [r, g, b] => WhiteBalance::new(Color::new(r, g, b)),
+            _ => {
+                return Err(rtt::Error::Scene(format!(
+                    "bad --white-balance {white:?}, expected kelvin[,tint] or r,g,b"
+                )))
+            }
+        });
+    }
+    if let Some(strength) = arg_value("--vignette") {
+        let [strength] = parse_floats::<1>("--vignette", &strength)?;
+        look.vignette = Some(Vignette::new(strength));
     }
+    if let Some(grain) = arg_value("--grain") {
+        let [amount, size] = parse_floats::<2>("--grain", &grain)?;
+        let seed = arg_value("--seed")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        look.grain = Some(Grain::new(amount, size, seed));
+    }
+    if let Some(path) = arg_value("--lut") {
+        look.lut = Some(Arc::new(Lut::load(Path::new(&path))?));
+    }
+    Ok(look)
+}
+
+// `N` comma-separated numbers given for `flag`.
+fn parse_floats<const N: usize>(flag: &str, value: &str) -> rtt::Result<[f64; N]> {
+    parse_float_list(flag, value)?
+        .try_into()
+        .map_err(|_| rtt::Error::Scene(format!("{flag} takes {N} comma-separated numbers")))
+}
+
+// Every value given for a flag that may repeat.
+fn arg_values(name: &str) -> Vec<String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    args.windows(2)
+        .filter(|w| w[0] == name)
+        .map(|w| w[1].clone())
+        .collect()
+}
 
-    if let Some(rec) = world.hit(&ray, 0.001, f64::INFINITY) {
-        if let Some((attenuation, scattered)) = rec.material.scatter(&ray, &rec, rng) {
-            return attenuation * ray_color(scattered, world, depth + 1, rng);
-        } else {
-            return BLACK;
+// `--stereo <ipd>` renders a side-by-side stereo pair with the eyes `ipd` scene units apart,
+// and `--ods <ipd>` an omnidirectional stereo panorama, left eye on top, stretched to the
+// image. See `stereo`.
+fn projection(desc: &CameraDesc, focus_dist: f64, aspect_ratio: f64) -> rtt::Result<Projection> {
+    let ipd = |flag| -> rtt::Result<Option<f64>> {
+        let Some(value) = arg_value(flag) else {
+            return Ok(None);
+        };
+        match parse_floats::<1>(flag, &value)? {
+            [ipd] if ipd >= 0.0 && ipd.is_finite() => Ok(Some(ipd)),
+            _ => Err(rtt::Error::Scene(format!("bad {flag} {value:?}"))),
         }
+    };
+    Ok(match (ipd("--stereo")?, ipd("--ods")?) {
+        (Some(_), Some(_)) => {
+            return Err(rtt::Error::Scene(
+                "--stereo and --ods can't be used together".into(),
+            ))
+        }
+        (Some(ipd), None) => Projection::Stereo(Arc::new(StereoCamera::new(
+            desc.look_from,
+            desc.look_at,
+            desc.vup,
+            desc.vfov,
+            aspect_ratio,
+            desc.aperture,
+            focus_dist,
+            ipd,
+            StereoLayout::SideBySide,
+        ))),
+        (None, Some(ipd)) => Projection::Ods(Arc::new(OdsCamera::new(
+            desc.look_from,
+            desc.look_at,
+            desc.vup,
+            ipd,
+            StereoLayout::TopBottom,
+        ))),
+        (None, None) => Projection::ThinLens,
+    })
+}
+
+fn default_camera() -> CameraDesc {
+    CameraDesc {
+        look_from: Point3::new(13.0, 2.0, 3.0),
+        look_at: Point3::new(0.0, 0.0, 0.0),
+        vfov: 20.0,
+        aperture: 0.1,
+        focus_dist: 10.0,
+        ..Default::default()
     }
+}
 
-    let unit_dir = Vec3::unit_vector(ray.direction());
-    let t = 0.5 * (unit_dir.y + 1.0);
-    (1.0 - t) * WHITE + t * BLUE
+// `rtt serve [--addr host:port]` runs the HTTP render service instead of a one-off render.
+// `--max-pixels <n>` caps each job's width times height, `--max-samples <n>` its samples per
+// pixel, and `--memory-budget <MiB>` (or the config's `memory_budget`) what it may need;
+// larger jobs are refused, as are new ones while `--max-jobs <n>` render. Scenes may only
+// refer to files under `--scene-root <dir>`.
+#[cfg(feature = "serve")]
+fn serve() -> rtt::Result<()> {
+    const MIB: usize = 1 << 20;
+    let config = config()?;
+    let addr = arg_value("--addr").unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let defaults = rtt::serve::ServeLimits::default();
+    let limits = rtt::serve::ServeLimits {
+        max_pixels: arg_value("--max-pixels")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.max_pixels),
+        max_samples: arg_value("--max-samples")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.max_samples),
+        max_jobs: arg_value("--max-jobs")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.max_jobs),
+        scene_root: arg_value("--scene-root").map(PathBuf::from),
+        memory_budget: arg_value("--memory-budget")
+            .and_then(|s| s.parse::<usize>().ok())
+            .or(config.memory_budget)
+            .map(|mib| mib * MIB),
+        ..defaults
+    };
+    rtt::serve::serve(&addr, limits)
 }
 
-fn random_scene() -> HittableList {
-    let mut rng = rand::rng();
-    let mut world = HittableList::new();
+#[cfg(not(feature = "serve"))]
+fn serve() -> rtt::Result<()> {
+    error!("this build has no HTTP service; rebuild with `--features serve`");
+    std::process::exit(2);
+}
 
-    let ground_mat: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        ground_mat,
-    )));
+// `rtt stream [--addr host:port] [--passes n]` renders progressively to websocket clients.
+#[cfg(feature = "stream")]
+fn stream() -> rtt::Result<()> {
+    let addr = arg_value("--addr").unwrap_or_else(|| "127.0.0.1:9001".to_string());
+    let passes = arg_value("--passes")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SAMPLES);
+    let aspect_ratio = WIDTH as f64 / HEIGHT as f64;
+    let (world, camera, _) = build_scene(aspect_ratio)?;
+    let camera = camera.build_in(aspect_ratio, &world);
+    rtt::stream::stream(&addr, &world, &camera, WIDTH, HEIGHT, passes)
+}
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat: f64 = rng.random::<f64>();
-            let center = Point3::new(
-                a as f64 + 0.9 * rng.random::<f64>(),
-                0.2,
-                b as f64 + 0.9 * rng.random::<f64>(),
-            );
+#[cfg(not(feature = "stream"))]
+fn stream() -> rtt::Result<()> {
+    error!("this build has no websocket streaming; rebuild with `--features stream`");
+    std::process::exit(2);
+}
 
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = Vec3::new(
-                        rng.random::<f64>() * rng.random::<f64>(),
-                        rng.random::<f64>() * rng.random::<f64>(),
-                        rng.random::<f64>() * rng.random::<f64>(),
-                    );
-                    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(albedo));
-                    world.add(Arc::new(Sphere::new(center, 0.2, mat)));
-                } else if choose_mat < 0.95 {
-                    // metal
-                    let albedo = Vec3::new(
-                        0.5 * (1.0 + rng.random::<f64>()),
-                        0.5 * (1.0 + rng.random::<f64>()),
-                        0.5 * (1.0 + rng.random::<f64>()),
-                    );
-                    let fuzz = 0.1;
-                    let mat: Arc<dyn Material> = Arc::new(Metal::new(albedo, fuzz));
-                    world.add(Arc::new(Sphere::new(center, 0.2, mat)));
-                } else {
-                    // glass
-                    let mat: Arc<dyn Material> = Arc::new(Dielectric::new(1.5));
-                    world.add(Arc::new(Sphere::new(center, 0.2, mat)));
-                }
-            }
+// `rtt watch <scene.json>` renders the scene at preview quality and half size, then again each
+// time the file is saved, for look development. Image viewers that reload changed files show
+// the latest `output.png`. `--preset` picks another quality.
+#[cfg(feature = "watch")]
+fn watch() -> rtt::Result<()> {
+    let Some(path) = std::env::args().nth(2) else {
+        error!("usage: rtt watch <scene.json> [--preset name]");
+        std::process::exit(2);
+    };
+    let config = config()?;
+    let preset = preset(&config)?.unwrap_or(Preset::Preview);
+    let out_path = out_dir(&config)?.join("output.png");
+    #[cfg_attr(not(feature = "script"), allow(unused_mut))]
+    let mut watcher = SceneWatcher::new(Path::new(&path))?;
+    // Objects the edit didn't touch are kept from the last build.
+    let mut cache = SceneCache::new();
+    loop {
+        // Material scripts are reused with their objects, so edits to them are picked up here.
+        #[cfg(feature = "script")]
+        rtt::script::reload_changed();
+        // A half-written or mistyped scene shouldn't end the session.
+        if let Err(e) = render_preview(Path::new(&path), &mut cache, preset, &out_path) {
+            tracing::warn!("{e}");
         }
+        #[cfg(feature = "script")]
+        for script in rtt::script::loaded() {
+            watcher.add(&script)?;
+        }
+        info!(path, "waiting for changes");
+        watcher.wait()?;
     }
+}
+
+#[cfg(feature = "watch")]
+fn render_preview(
+    path: &Path,
+    cache: &mut SceneCache,
+    preset: Preset,
+    out_path: &Path,
+) -> rtt::Result<()> {
+    let (num_x, num_y) = (WIDTH / 2, HEIGHT / 2);
+    let start = Instant::now();
+    let (world, camera) = cache.build(&SceneDesc::load(path)?, num_x as f64 / num_y as f64)?;
+    let stats = cache.stats();
+    info!(
+        reused = stats.objects_reused,
+        built = stats.objects_built,
+        "scene reloaded"
+    );
+    let film = render_preset(&world, &camera, num_x, num_y, preset)?;
+    film.save(out_path, 1.0)?;
+    info!(
+        path = %out_path.display(),
+        elapsed_s = start.elapsed().as_secs_f64(),
+        "preview saved"
+    );
+    Ok(())
+}
 
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0.0, 1.0, 0.0),
-        1.0,
-        Arc::new(Dielectric::new(1.5)),
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(-4.0, 1.0, 0.0),
-        1.0,
-        Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1))),
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0)),
-    )));
+#[cfg(not(feature = "watch"))]
+fn watch() -> rtt::Result<()> {
+    error!("this build can't watch files; rebuild with `--features watch`");
+    std::process::exit(2);
+}
 
-    world
+// Beauty only, at `preset`'s quality, for the quick renders of the subcommands.
+fn render_preset(
+    world: &HittableList,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    preset: Preset,
+) -> rtt::Result<Film> {
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(&[], world, width, height);
+    render_image_with(
+        world,
+        camera,
+        &film,
+        &aovs,
+        preset.samples(),
+        &preset.settings(),
+        &|_| {},
+    )?;
+    Ok(film)
 }
 
-fn main() {
-    let num_x: u32 = 1920;
-    let num_y: u32 = 1080;
-    let num_samples: u32 = 10;
-    let aspect_ratio = num_x as f64 / num_y as f64;
+// `rtt compare <a.json> [<b.json>] [--presets a,b]` renders two variants of a frame at half
+// size: two scenes at one preset, or one scene at two presets. It writes `compare_split.png`,
+// the left of A beside the right of B, `compare_diff.png`, a heatmap of their difference, and
+// the difference statistics to `compare.json`.
+fn compare() -> rtt::Result<()> {
+    let scenes: Vec<String> = std::env::args()
+        .skip(2)
+        .take_while(|a| !a.starts_with("--"))
+        .collect();
+    let config = config()?;
+    let presets = match arg_value("--presets") {
+        Some(names) => names
+            .split(',')
+            .map(|name| {
+                Preset::from_name(name.trim())
+                    .ok_or_else(|| rtt::Error::Scene(format!("unknown preset {name:?}")))
+            })
+            .collect::<rtt::Result<Vec<_>>>()?,
+        None => vec![preset(&config)?.unwrap_or(Preset::Preview)],
+    };
+    let variants: Vec<(&String, Preset)> = match (&scenes[..], &presets[..]) {
+        ([a, b], [p]) => vec![(a, *p), (b, *p)],
+        ([a], [p, q]) => vec![(a, *p), (a, *q)],
+        ([a, b], [p, q]) => vec![(a, *p), (b, *q)],
+        _ => {
+            error!("usage: rtt compare <a.json> <b.json> | rtt compare <scene.json> --presets a,b");
+            std::process::exit(2);
+        }
+    };
 
-    let world = random_scene();
-
-    let look_from = Point3::new(13.0, 2.0, 3.0);
-    let look_at = Point3::new(0.0, 0.0, 0.0);
-    let vup = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-
-    let camera = Camera::new(
-        look_from,
-        look_at,
-        vup,
-        20.0,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
+    let (num_x, num_y) = (WIDTH / 2, HEIGHT / 2);
+    let mut films = Vec::new();
+    for (path, preset) in variants {
+        let (world, camera) =
+            SceneDesc::load(Path::new(path))?.build(num_x as f64 / num_y as f64)?;
+        let start = Instant::now();
+        films.push(render_preset(&world, &camera, num_x, num_y, preset)?);
+        info!(
+            path,
+            preset = preset.name(),
+            elapsed_s = start.elapsed().as_secs_f64(),
+            "variant rendered"
+        );
+    }
+    let (a, b) = (&films[0], &films[1]);
+    let (colors_a, colors_b) = (a.resolve(1.0)?, b.resolve(1.0)?);
+    let stats = DiffStats::new(&colors_a, &colors_b)?;
+    let out_dir = out_dir(&config)?;
+    let image_error = |path: PathBuf| move |source| rtt::Error::Image { path, source };
+    let path = out_dir.join("compare_split.png");
+    split(&a.develop(1.0)?, &b.develop(1.0)?)?
+        .save(&path)
+        .map_err(image_error(path.clone()))?;
+    let path = out_dir.join("compare_diff.png");
+    heatmap(&colors_a, &colors_b, num_x, num_y, None)?
+        .save(&path)
+        .map_err(image_error(path.clone()))?;
+    std::fs::write(
+        out_dir.join("compare.json"),
+        serde_json::to_string_pretty(&stats)?,
+    )?;
+    info!(
+        mean_abs = stats.mean_abs,
+        rmse = stats.rmse,
+        max_abs = stats.max_abs,
+        psnr = stats.psnr,
+        "difference"
     );
+    Ok(())
+}
 
-    let img = Mutex::new(RgbaImage::new(num_x, num_y));
+// `rtt sheet <scene.json>... [--angles n] [--thumb-width px] [--columns n]` renders small
+// thumbnails of each scene, from `--angles` evenly spaced orbits around its look-at point when
+// given, into a labelled contact sheet, `sheet.png`. Quality is `--preset`, draft by default.
+fn sheet() -> rtt::Result<()> {
+    let paths: Vec<String> = std::env::args()
+        .skip(2)
+        .take_while(|a| !a.starts_with("--"))
+        .collect();
+    if paths.is_empty() {
+        error!("usage: rtt sheet <scene.json>... [--angles n] [--thumb-width px] [--columns n]");
+        std::process::exit(2);
+    }
+    let config = config()?;
+    let preset = preset(&config)?.unwrap_or(Preset::Draft);
+    let angles: u32 = arg_value("--angles")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1)
+        .max(1);
+    let thumb_width: u32 = arg_value("--thumb-width")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(320);
+    let thumb_height = (thumb_width * HEIGHT / WIDTH).max(1);
+    let aspect_ratio = thumb_width as f64 / thumb_height as f64;
 
-    let start = Instant::now();
+    let mut cells = Vec::new();
+    for path in &paths {
+        let desc = SceneDesc::load(Path::new(path))?;
+        let (world, _) = desc.build(aspect_ratio)?;
+        let name = Path::new(path)
+            .file_stem()
+            .map_or_else(|| path.clone(), |s| s.to_string_lossy().into_owned());
+        for k in 0..angles {
+            let degrees = 360 * k / angles;
+            let camera = desc
+                .camera
+                .orbit((degrees as f64).to_radians())
+                .build_in(aspect_ratio, &world);
+            let film = render_preset(&world, &camera, thumb_width, thumb_height, preset)?;
+            let label = if angles > 1 {
+                format!("{name} {degrees}°")
+            } else {
+                name.clone()
+            };
+            info!(label, "thumbnail rendered");
+            cells.push((label, film.develop(1.0)?));
+        }
+    }
+    let columns = arg_value("--columns")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| (cells.len() as f64).sqrt().ceil() as u32);
+    let out_path = out_dir(&config)?.join("sheet.png");
+    ContactSheet::new(columns)
+        .compose(&cells)
+        .save(&out_path)
+        .map_err(|source| rtt::Error::Image {
+            path: out_path.clone(),
+            source,
+        })?;
+    info!(path = %out_path.display(), "contact sheet saved");
+    Ok(())
+}
 
-    (0..num_y).into_par_iter().for_each(|j| {
-        let mut rng = rand::rng();
-        let row = num_y - 1 - j;
+// `rtt material <material.json> [--size px]` renders one material description on the fixed
+// look-development scene, see `lookdev`, into `material.png`. Quality is `--preset`, preview by
+// default.
+fn material() -> rtt::Result<()> {
+    let Some(path) = std::env::args().nth(2) else {
+        error!("usage: rtt material <material.json> [--size px]");
+        std::process::exit(2);
+    };
+    let config = config()?;
+    let preset = preset(&config)?.unwrap_or(Preset::Preview);
+    let size: u32 = arg_value("--size")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(512);
+    let material: MaterialDesc = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    let (world, camera) = material_ball(material).build(1.0)?;
+    let film = render_preset(&world, &camera, size, size, preset)?;
+    let out_path = out_dir(&config)?.join("material.png");
+    film.save(&out_path, 1.0)?;
+    info!(path = %out_path.display(), "material preview saved");
+    Ok(())
+}
+
+// `rtt bake <scene.json> <object> [--map lightmap|occlusion|curvature] [--size px]
+// [--padding texels]` bakes a map of mesh number `object` of the scene, `--size` texels square
+// (512 by default), grown `--padding` texels (4 by default) past its UV charts; see `bake`.
+// The lightmap, the irradiance reaching the mesh, goes to `lightmap.exr`; ambient occlusion
+// within `--ao-distance` (a quarter of the mesh's size by default) to `occlusion.png`; and
+// curvature, saturating at that of a sphere of `--curvature-radius` (a tenth of the mesh's
+// size by default), to `curvature.png`. The mesh needs inline triangles with texture
+// coordinates. Quality is `--preset`, preview by default.
+fn bake() -> rtt::Result<()> {
+    let args: Vec<String> = std::env::args().skip(2).take(2).collect();
+    let [path, object] = &args[..] else {
+        error!(
+            "usage: rtt bake <scene.json> <object> [--map lightmap|occlusion|curvature] \
+             [--size px] [--padding texels]"
+        );
+        std::process::exit(2);
+    };
+    let map = arg_value("--map").unwrap_or_else(|| "lightmap".to_string());
+    let config = config()?;
+    let preset = preset(&config)?.unwrap_or(Preset::Preview);
+    let size: u32 = arg_value("--size")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(512);
+    let padding: u32 = arg_value("--padding")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(4);
+    let desc = SceneDesc::load(Path::new(path))?;
+    let object = object
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| desc.objects.get(i))
+        .ok_or_else(|| rtt::Error::Scene(format!("the scene has no object {object:?}")))?;
+    let ObjectDesc::Mesh {
+        positions,
+        indices,
+        uvs,
+        obj: None,
+        material,
+        ..
+    } = object
+    else {
+        return Err(rtt::Error::Scene(
+            "only meshes with inline triangles can be baked".into(),
+        ));
+    };
+    let material = desc
+        .materials
+        .get(*material)
+        .ok_or_else(|| rtt::Error::Scene(format!("no material {material}")))?;
+    let mesh =
+        Mesh::new(positions.clone(), indices.clone(), material.build())?.with_uvs(uvs.clone())?;
+    let extent = mesh::bounds(positions).map_or(1.0, |b| b.diagonal());
+    let length = |flag: &str, default: f64| {
+        arg_value(flag)
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|&d| d > 0.0)
+            .unwrap_or(default)
+    };
+    let (world, _) = desc.build(1.0)?;
+    let start = Instant::now();
+    let (baked, name) = match map.as_str() {
+        "lightmap" => (
+            bake_irradiance(
+                &world,
+                &mesh,
+                size,
+                size,
+                preset.samples(),
+                &preset.settings(),
+            )?,
+            "lightmap.exr",
+        ),
+        "occlusion" => (
+            bake_occlusion(
+                &world,
+                &mesh,
+                size,
+                size,
+                preset.samples(),
+                length("--ao-distance", 0.25 * extent),
+                preset.settings().seed,
+            )?,
+            "occlusion.png",
+        ),
+        "curvature" => {
+            let radius = length("--curvature-radius", 0.1 * extent);
+            (
+                bake_curvature(&mesh, size, size, 1.0 / radius)?,
+                "curvature.png",
+            )
+        }
+        _ => {
+            return Err(rtt::Error::Scene(format!(
+                "unknown --map {map:?}, expected lightmap, occlusion or curvature"
+            )))
+        }
+    };
+    let baked = bake::pad(&baked, padding)?;
+    let out_path = out_dir(&config)?.join(name);
+    baked.save(&out_path, 1.0)?;
+    info!(
+        path = %out_path.display(),
+        elapsed_s = start.elapsed().as_secs_f64(),
+        "{map} saved"
+    );
+    Ok(())
+}
 
-        let mut row_pixels: Vec<Rgba<u8>> = Vec::with_capacity(num_x as usize);
+// `rtt page <mesh.obj> <out.geom> [--chunk-triangles n]` converts a mesh into the chunked
+// file a `PagedMesh` streams from.
+fn page() -> rtt::Result<()> {
+    let args: Vec<String> = std::env::args().skip(2).take(2).collect();
+    let [input, output] = &args[..] else {
+        error!("usage: rtt page <mesh.obj> <out.geom> [--chunk-triangles n]");
+        std::process::exit(2);
+    };
+    let chunk_triangles = arg_value("--chunk-triangles")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(paged::CHUNK_TRIANGLES);
+    let (positions, indices) = mesh::load_obj(Path::new(input))?;
+    paged::write(&positions, &indices, Path::new(output), chunk_triangles)?;
+    info!(triangles = indices.len(), output, "paged mesh written");
+    Ok(())
+}
 
-        for i in 0..num_x {
-            let mut col = Color::new(0.0, 0.0, 0.0);
+fn main() {
+    init_logging();
+    let result = match std::env::args().nth(1).as_deref() {
+        Some("serve") => serve(),
+        Some("stream") => stream(),
+        Some("page") => page(),
+        Some("bake") => bake(),
+        Some("sheet") => sheet(),
+        Some("material") => material(),
+        Some("compare") => compare(),
+        Some("watch") => watch(),
+        _ => run(),
+    };
+    if let Err(e) = result {
+        error!("{e}");
+        std::process::exit(1);
+    }
+}
 
-            for _s in 0..num_samples {
-                let u = (i as f64 + rng.random::<f64>()) / num_x as f64;
-                let v = (j as f64 + rng.random::<f64>()) / num_y as f64;
-                let r = camera.get_ray(u, v, &mut rng);
-                col += ray_color(r, &world, 0, &mut rng);
+// The world, its camera, and haze to render it with, if any.
+fn build_scene(aspect_ratio: f64) -> rtt::Result<(HittableList, CameraDesc, Option<Atmosphere>)> {
+    // `--geometry-budget <MB>` caps how much of the scene's paged meshes stays in memory.
+    if let Some(mb) = arg_value("--geometry-budget").and_then(|s| s.parse::<usize>().ok()) {
+        GeometryCache::shared().set_budget(mb << 20);
+    }
+    // `--texture-format f16|block` keeps decoded textures smaller than full precision.
+    let format = match arg_value("--texture-format").as_deref() {
+        Some("f16") => TextureFormat::F16,
+        Some("block") => TextureFormat::Block,
+        _ => TextureFormat::F32,
+    };
+    TextureCache::shared().set_format(format)?;
+    // `--scene <file.json>` renders a saved scene instead of a random one.
+    let mut meters_per_unit = 1.0;
+    let (mut world, mut camera) = info_span!("scene_build").in_scope(|| -> rtt::Result<_> {
+        let start = Instant::now();
+        let (world, camera) = match arg_value("--scene") {
+            Some(path) => {
+                let desc = SceneDesc::load(Path::new(&path))?;
+                meters_per_unit = desc.meters_per_unit.unwrap_or(1.0);
+                (desc.build_lod(aspect_ratio, HEIGHT)?.0, desc.camera)
+            }
+            None => {
+                // `--seed <n>` makes the random scene reproducible.
+                let seed = match arg_value("--seed") {
+                    Some(seed) => seed
+                        .parse()
+                        .map_err(|_| rtt::Error::Scene(format!("invalid seed {seed:?}")))?,
+                    None => rand::random(),
+                };
+                info!(seed, "generating random scene");
+                let world = SceneGenerator::new(seed).generate();
+                // `--export <file.json>` saves the generated scene so it can be re-rendered.
+                if let Some(path) = arg_value("--export") {
+                    SceneDesc::from_world(&world, default_camera())?.save(Path::new(&path))?;
+                    info!(path, "scene exported");
+                }
+                (world, default_camera())
             }
+        };
+        info!(
+            objects = world.len(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "scene built"
+        );
+        Ok((world, camera))
+    })?;
+    // `--hdri <file>` lights the scene with an environment map; `--sun <elevation degrees>`
+    // with a daylight sky. Either replaces the scene's own background.
+    if let Some(path) = arg_value("--hdri") {
+        world.set_background(Arc::new(Hdri::load(Path::new(&path))?));
+    } else if let Some(elevation) = arg_value("--sun") {
+        let elevation: f64 = elevation
+            .parse()
+            .map_err(|_| rtt::Error::Scene(format!("invalid sun elevation {elevation:?}")))?;
+        if elevation <= 0.0 {
+            return Err(rtt::Error::Scene(
+                "the sun must be above the horizon".into(),
+            ));
+        }
+        world.set_background(Arc::new(SunSky::from_angles(elevation.to_radians(), 0.0)));
+    } else if let Some(time) = arg_value("--sun-at") {
+        // `--sun-at <2026-06-21T14:30+02:00>` places the sun as seen from `--location
+        // <latitude>,<longitude>` (degrees north and east) at that time, for shadow studies;
+        // `--north <degrees>` turns true north from -z towards +x. See `sun`.
+        let location = arg_value("--location").ok_or_else(|| {
+            rtt::Error::Scene("--sun-at needs --location <latitude>,<longitude>".into())
+        })?;
+        let [latitude, longitude] = parse_floats::<2>("--location", &location)?;
+        let north = match arg_value("--north") {
+            Some(north) => parse_floats::<1>("--north", &north)?[0],
+            None => 0.0,
+        };
+        let sun = SolarPosition::at(&SunTime::parse(&time)?, latitude, longitude);
+        info!(
+            elevation = sun.elevation.to_degrees(),
+            azimuth = sun.azimuth.to_degrees(),
+            "sun position"
+        );
+        world.set_background(Arc::new(sun.sky(north.to_radians())?));
+    }
+    // `--haze <turbidity>` sets how hazy the air of a sun and sky is, from 2 for a clear day to
+    // 10: the sky's look, the sun's brightness through it and the haze over the scene
+    // together; see `Haze`.
+    let mut atmosphere = None;
+    if let Some(turbidity) = arg_value("--haze") {
+        let [turbidity] = parse_floats::<1>("--haze", &turbidity)?;
+        if !(1.0..=20.0).contains(&turbidity) {
+            return Err(rtt::Error::Scene(format!(
+                "--haze takes a turbidity from 1 to 20, got {turbidity}"
+            )));
+        }
+        let Some(BackgroundDesc::SunSky {
+            sun_direction,
+            turbidity: _,
+            intensity,
+            sun_intensity,
+            ground,
+        }) = world.background.as_ref().and_then(|b| b.to_desc())
+        else {
+            return Err(rtt::Error::Scene(
+                "--haze needs a sun and sky, from the scene, --sun or --sun-at".into(),
+            ));
+        };
+        let sky = SunSky {
+            sun_direction,
+            turbidity,
+            intensity,
+            sun_intensity,
+            ground,
+        };
+        let haze = Haze::new(turbidity);
+        let sky = haze.sky(sky);
+        atmosphere = Some(haze.atmosphere(&sky, meters_per_unit));
+        world.set_background(Arc::new(sky));
+    }
+    info!("scene statistics:\n{}", SceneStats::new(&world));
+    // `--autofocus [<s>,<t>]` focuses on what the center of the frame, or film point (s, t)
+    // from the bottom left, sees instead of at the scene's focus distance.
+    if std::env::args().any(|a| a == "--autofocus") {
+        let point = arg_value("--autofocus")
+            .and_then(|p| parse_float_list("--autofocus", &p).ok())
+            .and_then(|p| match p[..] {
+                [s, t] => Some((s, t)),
+                _ => None,
+            });
+        camera.autofocus = Some(point.unwrap_or((0.5, 0.5)));
+    }
+    // `--exposure <iso>,<shutter seconds>,<f-number>` develops the render like a camera would,
+    // for scenes lit in physical units; see `PhysicalExposure`.
+    if let Some(exposure) = arg_value("--exposure") {
+        let [iso, shutter, f_number] = parse_floats::<3>("--exposure", &exposure)?;
+        camera.exposure = Some(PhysicalExposure::new(iso, shutter, f_number)?);
+    }
+    Ok((world, camera, atmosphere))
+}
 
-            col /= num_samples as f64;
+// Defaults come from `~/.config/rtt.toml`, or `--config <file.toml>`, unless `--no-config`;
+// see `Config`. Flags override them. `--threads <n>` caps the render threads.
+fn config() -> rtt::Result<Config> {
+    let config = if std::env::args().any(|a| a == "--no-config") {
+        Config::default()
+    } else if let Some(path) = arg_value("--config") {
+        Config::load(Path::new(&path))?
+    } else {
+        Config::user()?
+    };
+    let threads = arg_value("--threads")
+        .and_then(|s| s.parse().ok())
+        .or(config.threads);
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| rtt::Error::Scene(format!("can't use {threads} threads: {e}")))?;
+    }
+    Ok(config)
+}
 
-            // gamma correction
-            let col = Vec3::new(col.r().sqrt(), col.g().sqrt(), col.b().sqrt());
+// `--preset draft|preview|final` picks samples, bounces, regularization and repair together;
+// other flags override single settings of it.
+fn preset(config: &Config) -> rtt::Result<Option<Preset>> {
+    match arg_value("--preset") {
+        Some(name) => Ok(Some(Preset::from_name(&name).ok_or_else(|| {
+            rtt::Error::Scene(format!(
+                "unknown preset {name:?}, expected draft, preview or final"
+            ))
+        })?)),
+        None => Ok(config.preset),
+    }
+}
+
+// `--output-dir <dir>` picks where images go, creating it if need be.
+fn out_dir(config: &Config) -> rtt::Result<PathBuf> {
+    let dir = match arg_value("--output-dir").map(PathBuf::from) {
+        Some(dir) => dir,
+        None => match config.output_dir() {
+            Some(dir) => dir,
+            None => std::env::current_dir()?,
+        },
+    };
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
 
-            let ir = clamp_u8(col.r());
-            let ig = clamp_u8(col.g());
-            let ib = clamp_u8(col.b());
+// Estimates what a `width`x`height` render with `aovs` needs before allocating it. Over
+// `--memory-budget <MiB>` (or the config's `memory_budget`) the render is refused; over
+// `--warn-memory <MiB>` it only warns.
+fn check_memory(
+    config: &Config,
+    world: &HittableList,
+    width: u32,
+    height: u32,
+    aovs: &[Aov],
+) -> rtt::Result<()> {
+    const MIB: usize = 1 << 20;
+    let estimate = MemoryEstimate::new(world, width, height, aovs);
+    info!("estimated memory: {estimate}");
+    let budget = arg_value("--memory-budget")
+        .and_then(|s| s.parse::<usize>().ok())
+        .or(config.memory_budget);
+    if let Some(mib) = budget {
+        estimate.check(mib * MIB)?;
+    }
+    if let Some(mib) = arg_value("--warn-memory").and_then(|s| s.parse::<usize>().ok()) {
+        if let Err(e) = estimate.check(mib * MIB) {
+            warn!("{e}");
+        }
+    }
+    Ok(())
+}
+
+fn run() -> rtt::Result<()> {
+    let config = config()?;
+    let preset = preset(&config)?;
+    // `--format png|tif|dng` picks what the main image is written as.
+    let format = arg_value("--format")
+        .or_else(|| config.format.clone())
+        .unwrap_or_else(|| "png".to_string());
+    if !FORMATS.contains(&format.as_str()) {
+        return Err(rtt::Error::Scene(format!(
+            "unknown --format {format:?}, expected one of {}",
+            FORMATS.join(", ")
+        )));
+    }
+    let num_samples = preset.map_or(SAMPLES, Preset::samples);
+    let (num_x, num_y) = (WIDTH, HEIGHT);
+    let aspect_ratio = num_x as f64 / num_y as f64;
+
+    let (world, camera, haze) = build_scene(aspect_ratio)?;
+    let exposure = camera.exposure;
+    if let Some(exposure) = exposure {
+        info!(ev100 = exposure.ev100(), "physical exposure");
+    }
 
-            row_pixels.push(Rgba([ir, ig, ib, 255]));
+    // `--shadow-samples <n>` sets shadow rays per diffuse bounce towards area lights;
+    // `--no-mis` counts lights through shadow rays only; `--guiding` learns where light comes
+    // from and steers bounces there; `--fog <density>` adds a pale haze that thins out with
+    // height; `--restir [candidates]` resamples direct light for scenes with many lights.
+    let mut render_settings = preset.map_or_else(RenderSettings::default, Preset::settings);
+    if let Some(haze) = haze {
+        render_settings = render_settings.with_atmosphere(haze);
+    }
+    if let Some(n) = parse_flag("--shadow-samples")? {
+        render_settings = render_settings.with_shadow_samples(n);
+    }
+    if std::env::args().any(|a| a == "--no-mis") {
+        render_settings = render_settings.with_mis(false);
+    }
+    if std::env::args().any(|a| a == "--guiding") {
+        render_settings = render_settings.with_guiding(true);
+    }
+    if std::env::args().any(|a| a == "--restir") {
+        let mut restir = Restir::default();
+        if let Some(n) = arg_value("--restir").and_then(|s| s.parse().ok()) {
+            restir = restir.with_candidates(n);
         }
+        render_settings = render_settings.with_restir(restir);
+    }
+    // `--max-bounces`, `--diffuse-bounces`, `--glossy-bounces` and `--transmission-bounces`
+    // cap how far paths go in all and per kind of bounce.
+    let mut bounces = render_settings.bounces;
+    for (flag, limit) in [
+        ("--max-bounces", &mut bounces.total),
+        ("--diffuse-bounces", &mut bounces.diffuse),
+        ("--glossy-bounces", &mut bounces.glossy),
+        ("--transmission-bounces", &mut bounces.transmission),
+    ] {
+        if let Some(n) = parse_flag(flag)? {
+            *limit = n;
+        }
+    }
+    render_settings = render_settings.with_bounces(bounces);
+    // `--regularize <degrees>` blurs mirrors and glass seen after a diffuse bounce by at least
+    // that much, trading caustic fireflies for slight blur.
+    if let Some(degrees) = parse_flag::<f64>("--regularize")? {
+        if !(0.0..=90.0).contains(&degrees) {
+            return Err(rtt::Error::Scene(format!(
+                "--regularize takes an angle from 0 to 90 degrees, got {degrees}"
+            )));
+        }
+        render_settings = render_settings.with_regularization(degrees.to_radians());
+    }
+    // `--clamp <luminance>` scales samples brighter than that down to it, removing fireflies
+    // at the cost of some light. Light tracing isn't clamped.
+    if let Some(max) = parse_flag::<f64>("--clamp")? {
+        if max.is_nan() || max <= 0.0 {
+            return Err(rtt::Error::Scene(format!(
+                "--clamp takes a positive luminance, got {max}"
+            )));
+        }
+        render_settings = render_settings.with_clamp(max);
+    }
+    if let Some(density) = parse_flag::<f64>("--fog")? {
+        if !(density >= 0.0 && density.is_finite()) {
+            return Err(rtt::Error::Scene(format!(
+                "--fog takes a finite density of at least 0, got {density}"
+            )));
+        }
+        let haze = Atmosphere::new(density, Color::new(0.75, 0.8, 0.85));
+        render_settings = render_settings.with_atmosphere(haze.with_height_falloff(0.2, 0.0));
+    }
 
-        {
-            let mut img = img.lock().unwrap();
-            for (i, px) in row_pixels.into_iter().enumerate() {
-                img.put_pixel(i as u32, row, px);
+    // `--sample-seed <n>` seeds the samplers so reruns give the same noise, and `--seed-mode
+    // fixed|decorrelated` picks whether turntable frames share one noise pattern or each get
+    // their own, the default; see `SeedMode`.
+    let seed_mode = match arg_value("--seed-mode") {
+        Some(name) => Some(SeedMode::from_name(&name).ok_or_else(|| {
+            rtt::Error::Scene(format!(
+                "unknown --seed-mode {name:?}, expected fixed or decorrelated"
+            ))
+        })?),
+        None => None,
+    };
+    let seed: Option<u64> = parse_flag("--sample-seed")?.or(seed_mode.map(|_| 0));
+    let seed_mode = seed_mode.unwrap_or(SeedMode::Decorrelated);
+    let frame_settings = |frame: u32| match seed {
+        Some(seed) => render_settings.with_seed(seed_mode.seed(seed, frame as u64)),
+        None => render_settings,
+    };
+    let frames: u32 = parse_flag("--frames")?.unwrap_or(48);
+    if frames == 0 {
+        return Err(rtt::Error::Scene("--frames takes at least 1 frame".into()));
+    }
+    // `--frame <n>` renders just frame n of the turntable, as a still.
+    let frame: Option<u32> = parse_flag("--frame")?;
+    if let Some(frame) = frame.filter(|&frame| frame >= frames) {
+        return Err(rtt::Error::Scene(format!(
+            "--frame {frame} is past the last of {frames} frames"
+        )));
+    }
+
+    let look = look()?;
+    // `--video <file.mp4|file.webm>` renders a turntable around the look-at point instead of a
+    // still, with `--frames`, `--fps` and `--bitrate`.
+    if let Some(path) = arg_value("--video") {
+        let settings = VideoSettings {
+            fps: arg_value("--fps")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(24.0),
+            bitrate: arg_value("--bitrate"),
+            ..Default::default()
+        };
+        check_memory(&config, &world, num_x, num_y, &[])?;
+        let mut video = VideoEncoder::new(Path::new(&path), num_x, num_y, &settings)?;
+        for frame in 0..frames {
+            let angle = std::f64::consts::TAU * frame as f64 / frames as f64;
+            let camera = camera.orbit(angle).build_in(aspect_ratio, &world);
+            let film = Film::new(num_x, num_y);
+            let aovs = AovSet::new(&[], &world, num_x, num_y);
+            let start = Instant::now();
+            render_image_with(
+                &world,
+                &camera,
+                &film,
+                &aovs,
+                num_samples,
+                &frame_settings(frame),
+                &|_| {},
+            )?;
+            info!(
+                frame,
+                frames,
+                elapsed_s = start.elapsed().as_secs_f64(),
+                "frame finished"
+            );
+            let film = match exposure {
+                Some(exposure) => exposure.apply(&film, 1.0)?,
+                None => film,
+            };
+            let film = if look.is_empty() {
+                film
+            } else {
+                look.apply(&film, 1.0, frame as u64)?
+            };
+            video.push_frame(&film.develop(1.0)?)?;
+        }
+        video.finish()?;
+        info!(path, "video saved");
+        return Ok(());
+    }
+    // `--envmap <file>` bakes what surrounds the camera position instead of a still: six
+    // `--env-size` pixel square faces (half the image height by default), laid out by
+    // `--env-layout cross|faces|equirect`; see `envmap`. `.exr` and `.hdr` keep the full range.
+    if let Some(path) = arg_value("--envmap") {
+        let layout = match arg_value("--env-layout") {
+            Some(name) => EnvLayout::from_name(&name).ok_or_else(|| {
+                rtt::Error::Scene(format!(
+                    "unknown --env-layout {name:?}, expected cross, faces or equirect"
+                ))
+            })?,
+            None => EnvLayout::default(),
+        };
+        let size: u32 = parse_flag("--env-size")?.unwrap_or(num_y / 2);
+        // The cross layout is four faces wide.
+        if size == 0 || size > u32::MAX / 4 {
+            return Err(rtt::Error::Scene(format!(
+                "--env-size takes a face size from 1 to {} pixels, got {size}",
+                u32::MAX / 4
+            )));
+        }
+        check_memory(&config, &world, 4 * size, 3 * size, &[])?;
+        let start = Instant::now();
+        let cubemap = Cubemap::render(
+            &world,
+            camera.look_from,
+            size,
+            num_samples,
+            &frame_settings(0),
+        )?;
+        info!(
+            elapsed_s = start.elapsed().as_secs_f64(),
+            "cubemap rendered"
+        );
+        let path = PathBuf::from(path);
+        match layout {
+            EnvLayout::Cross => cubemap.cross()?.save(&path, 1.0)?,
+            EnvLayout::Equirect => cubemap.equirect(4 * size)?.save(&path, 1.0)?,
+            EnvLayout::Faces => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let extension = path.extension().unwrap_or_default().to_string_lossy();
+                for face in CubeFace::ALL {
+                    let name = format!("{stem}_{}.{extension}", face.name());
+                    cubemap.face(face).save(&path.with_file_name(name), 1.0)?;
+                }
             }
         }
+        info!(path = %path.display(), "environment map saved");
+        return Ok(());
+    }
+    let autofocus = camera.autofocus.is_some();
+    let desc = match frame {
+        Some(frame) => camera.orbit(std::f64::consts::TAU * frame as f64 / frames as f64),
+        None => camera,
+    };
+    let camera = desc.build_in(aspect_ratio, &world);
+    let projection = projection(&desc, camera.focus_dist(), aspect_ratio)?;
+    let camera = camera.with_projection(projection);
+    if autofocus {
+        info!(focus_dist = camera.focus_dist(), "autofocused");
+    }
 
-        println!("Scanline {} of {}", num_y - j, num_y);
-    });
+    // `--focus-view [<pixels>]` writes `focus.png` instead of rendering: what is in front of
+    // (blue), within (green) and behind (red) the depth of field, blur up to the given pixels
+    // (1 by default) counting as sharp.
+    if std::env::args().any(|a| a == "--focus-view") {
+        // The blur is optional, so a following flag isn't one.
+        let max_blur = match arg_value("--focus-view").filter(|s| !s.starts_with("--")) {
+            Some(_) => parse_flag::<f64>("--focus-view")?.unwrap_or(1.0),
+            None => 1.0,
+        };
+        if !max_blur.is_finite() || max_blur <= 0.0 {
+            return Err(rtt::Error::Scene(format!(
+                "--focus-view needs a positive blur, got {max_blur}"
+            )));
+        }
+        let limits = camera.focus_limits(num_y, max_blur);
+        let path = out_dir(&config)?.join("focus.png");
+        let coverage = save_focus_view(&world, &camera, num_x, num_y, max_blur, &path)?;
+        info!(
+            focus_dist = camera.focus_dist(),
+            near = limits.near,
+            far = limits.far,
+            in_focus = coverage.in_focus,
+            path = %path.display(),
+            "focus view saved"
+        );
+        return Ok(());
+    }
+    let render_settings = frame_settings(frame.unwrap_or(0));
 
-    let elapsed = start.elapsed();
-    println!(
-        "Render finished in {:.2} minutes",
-        elapsed.as_secs_f64() / 60.0
-    );
+    // `--dump-paths <file.json|file.obj> --pixel <x>,<y> [--pixel ...]` records the paths
+    // through the given pixels instead of rendering, `--dump-samples` per pixel.
+    if let Some(path) = arg_value("--dump-paths") {
+        let samples: u32 = parse_flag("--dump-samples")?.unwrap_or(16);
+        if samples == 0 {
+            return Err(rtt::Error::Scene(
+                "--dump-samples takes at least 1 sample".into(),
+            ));
+        }
+        let mut rng = rand::rng();
+        let mut paths = Vec::new();
+        for pixel in arg_values("--pixel") {
+            let Some((x, y)) = pixel
+                .split_once(',')
+                .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+            else {
+                return Err(rtt::Error::Scene(format!(
+                    "bad --pixel {pixel:?}, expected x,y"
+                )));
+            };
+            paths.extend(pathdump::record_pixel(
+                &world,
+                &camera,
+                (num_x, num_y),
+                (x, y),
+                samples,
+                &render_settings,
+                &mut rng,
+            )?);
+        }
+        pathdump::save(&paths, Path::new(&path))?;
+        info!(path, count = paths.len(), "paths saved");
+        return Ok(());
+    }
 
-    let out_path = std::env::current_dir()
-        .unwrap_or_else(|_| std::path::PathBuf::from("."))
-        .join("output.png");
+    // `--polarization` traces Stokes vectors through glass and metal instead, writing the
+    // intensity as usual and the degree of polarization to `polarization.png`.
+    if std::env::args().any(|a| a == "--polarization") {
+        let dir = out_dir(&config)?;
+        let start = Instant::now();
+        let stokes =
+            render_polarized(&world, &camera, num_x, num_y, num_samples, &render_settings)?;
+        info!(elapsed_s = start.elapsed().as_secs_f64(), "render finished");
+        stokes
+            .intensity()?
+            .save(&dir.join(format!("output.{format}")), 1.0)?;
+        let path = dir.join("polarization.png");
+        stokes.save_degree_view(&path)?;
+        info!(path = %path.display(), "polarization saved");
+        return Ok(());
+    }
 
-    {
-        let img = img.into_inner().expect("image mutex poisoned");
-        img.save(&out_path).expect("failed to save image");
+    // `--variance` also writes per-pixel variance and relative error, to see where the image
+    // is still noisy. `--repair <max relative error>` then renders pixels noisier than that,
+    // and any with NaNs, again with `--repair-samples` more samples.
+    let mut aov_list = vec![Aov::Depth];
+    let repair: Option<f64> = parse_flag("--repair")?;
+    if let Some(max) = repair.filter(|max| max.is_nan() || *max < 0.0) {
+        return Err(rtt::Error::Scene(format!(
+            "--repair takes a relative error of at least 0, got {max}"
+        )));
+    }
+    let repair = repair.or_else(|| preset.and_then(Preset::repair));
+    // Read before rendering, so a bad count doesn't cost the first pass.
+    let repair_samples: u32 = parse_flag("--repair-samples")?.unwrap_or(num_samples);
+    if repair_samples == 0 {
+        return Err(rtt::Error::Scene(
+            "--repair-samples takes at least 1 sample".into(),
+        ));
+    }
+    if std::env::args().any(|a| a == "--variance") {
+        aov_list.extend([Aov::Variance, Aov::RelativeError]);
+    } else if repair.is_some() {
+        aov_list.push(Aov::RelativeError);
+    }
+    // `--path-classes` splits the beauty into direct and indirect diffuse and specular light,
+    // transmission and directly seen emission.
+    if std::env::args().any(|a| a == "--path-classes") {
+        aov_list.push(Aov::PathClasses);
+    }
+    // `--denoise-aovs` writes albedo, albedo-divided diffuse light and the specular rest, for
+    // external denoisers.
+    if std::env::args().any(|a| a == "--denoise-aovs") {
+        aov_list.extend([Aov::Albedo, Aov::Diffuse, Aov::Specular]);
+    }
+    // `--unclamped` also accumulates the image without `--clamp` from the same samples, writes
+    // it to `unclamped.exr` and logs how far the two differ: the bias clamping introduces.
+    if std::env::args().any(|a| a == "--unclamped") {
+        aov_list.push(Aov::Unclamped);
+    }
+    check_memory(&config, &world, num_x, num_y, &aov_list)?;
+    let film = Film::new(num_x, num_y);
+    let aovs = AovSet::new(&aov_list, &world, num_x, num_y);
+
+    // `--light-tracing` traces the samples from the lights instead, for caustic passes; see
+    // `lighttrace`.
+    let light_tracing = std::env::args().any(|a| a == "--light-tracing");
+    let start = Instant::now();
+    if light_tracing {
+        render_light_paths(&world, &camera, &film, num_samples, &render_settings)?;
+    } else {
+        render_image_with(
+            &world,
+            &camera,
+            &film,
+            &aovs,
+            num_samples,
+            &render_settings,
+            &|_| {},
+        )?;
+    }
+    info!(elapsed_s = start.elapsed().as_secs_f64(), "render finished");
+
+    if let Some(max_relative_error) = repair.filter(|_| !light_tracing) {
+        let pixels = noisy_pixels(&film, &aovs, max_relative_error)?;
+        let start = Instant::now();
+        repair_pixels(
+            &world,
+            &camera,
+            &film,
+            &aovs,
+            &pixels,
+            repair_samples,
+            &render_settings,
+        )?;
+        info!(
+            pixels = pixels.len(),
+            elapsed_s = start.elapsed().as_secs_f64(),
+            "repair finished"
+        );
+    }
+
+    if let Some(unclamped) = aovs.film(Aov::Unclamped).filter(|_| !light_tracing) {
+        let stats = DiffStats::new(&film.resolve(1.0)?, &unclamped.resolve(1.0)?)?;
+        info!(
+            mean_abs = stats.mean_abs,
+            rmse = stats.rmse,
+            max_abs = stats.max_abs,
+            psnr = stats.psnr,
+            "clamping bias"
+        );
+    }
+
+    let out_dir = out_dir(&config)?;
+    let out_path = out_dir.join(format!("output.{format}"));
+
+    let splat_scale = if light_tracing {
+        1.0 / num_samples as f64
+    } else {
+        1.0
+    };
+    let film = match exposure {
+        Some(exposure) => exposure.apply(&film, splat_scale)?,
+        None => film,
+    };
+    // `--bloom <threshold>,<radius>,<intensity>` spreads light over the threshold into a glow
+    // `radius` image widths wide, and `--glare <streaks>,<length>,<intensity>` adds streaks
+    // around it; see `Bloom`. Only the saved images get them.
+    let film = match arg_value("--bloom") {
+        Some(bloom) => {
+            let [threshold, radius, intensity] = parse_floats::<3>("--bloom", &bloom)?;
+            let mut bloom = Bloom::new(threshold, radius, intensity);
+            if let Some(glare) = arg_value("--glare") {
+                let [streaks, length, intensity] = parse_floats::<3>("--glare", &glare)?;
+                bloom = bloom.with_glare(Glare {
+                    streaks: streaks as u32,
+                    length,
+                    intensity,
+                    rotation: 0.0,
+                });
+            }
+            bloom.apply(&film, splat_scale)?
+        }
+        None => film,
+    };
+    let film = if look.is_empty() {
+        film
+    } else {
+        look.apply(&film, splat_scale, frame.unwrap_or(0) as u64)?
+    };
+    film.save(&out_path, splat_scale)?;
+    // `--raw <file.tif|file.dng>` also writes the linear, unclipped render for raw developers.
+    if let Some(path) = arg_value("--raw") {
+        film.save(Path::new(&path), splat_scale)?;
+    }
+    aovs.save(&film, &out_dir)?;
+    // `--report` writes `report.png`, a false-color exposure view over a luminance histogram,
+    // and its statistics to `report.json`.
+    if std::env::args().any(|a| a == "--report") {
+        let stats = save_exposure_report(&film, splat_scale, &out_dir)?;
+        info!(
+            min = stats.min,
+            max = stats.max,
+            mean = stats.mean,
+            clipped = stats.clipped,
+            crushed = stats.crushed,
+            exposure_to_mid_grey = stats.exposure_to_mid_grey,
+            "exposure"
+        );
+    }
+    // With more than one AOV, everything also goes into one multilayer EXR for compositing.
+    if aovs.aovs().len() > 1 {
+        aovs.save_multilayer(&film, splat_scale, &out_dir.join("output.exr"))?;
     }
 
-    println!("Image saved to: {}", out_path.display());
+    info!(path = %out_path.display(), "image saved");
+    Ok(())
 }
@@ -1,7 +1,15 @@
+use crate::color;
+use crate::error::{Error, Result};
 use crate::hittable::HitRecord;
+use crate::math::Onb;
 use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::scene::MaterialDesc;
+use crate::texture::ImageTexture;
+use crate::vec3::{Color, Point3, Vec3};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak};
 
 pub trait Material: Send + Sync {
     fn scatter(
@@ -10,6 +18,329 @@ pub trait Material: Send + Sync {
         rec: &HitRecord,
         rng: &mut dyn rand::RngCore,
     ) -> Option<(Vec3, Ray)>;
+
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::default()
+    }
+
+    // Density, per unit solid angle, with which `scatter` picks `scattered`. Zero for
+    // delta (specular) lobes.
+    fn scattering_pdf(&self, _ray_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    // BRDF times cosine towards `scattered`, given the `attenuation` `scatter` returned. By
+    // default `attenuation * scattering_pdf`, right for materials whose attenuation is the same
+    // whichever way their sampled lobe sends the ray; those mixing lobes of different colors
+    // work it out themselves.
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color,
+        scattered: &Ray,
+    ) -> Color {
+        attenuation * self.scattering_pdf(ray_in, rec, scattered)
+    }
+
+    // Name of the light group emitted radiance is reported under, if any.
+    fn light_group(&self) -> Option<Arc<str>> {
+        None
+    }
+
+    // Chance that a ray hitting at `rec` stops there rather than passing straight through,
+    // for cutouts such as leaves on cards; see `Cutout`.
+    fn opacity(&self, _rec: &HitRecord) -> f64 {
+        1.0
+    }
+
+    // Whether `opacity` is ever below 1, so hits on this material need testing at all.
+    fn has_cutouts(&self) -> bool {
+        false
+    }
+
+    // For surfaces rays pass straight through unbent, like thin glass: the tint `r` picks up
+    // going on through, or None if it stops here instead, chosen at random. Passing through
+    // isn't a bounce; the ray goes on from where it started.
+    fn filter(&self, _r: &Ray, _rec: &HitRecord, _rng: &mut dyn rand::RngCore) -> Option<Color> {
+        None
+    }
+
+    // What `filter` passes on average, for shadow rays to see through it.
+    fn transmittance(&self, _r: &Ray, _rec: &HitRecord) -> Color {
+        Color::default()
+    }
+
+    // Whether `filter` ever passes rays, so shadow rays need to look past hits at all.
+    fn is_filter(&self) -> bool {
+        false
+    }
+
+    // Appends the image textures this material samples, for memory estimates.
+    fn textures(&self, _out: &mut Vec<ImageTexture>) {}
+
+    // Serializable description for scene export; None if this material can't be saved.
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        None
+    }
+
+    // For path regularization: this material with its mirror-like lobes spread over cones
+    // of at least `angle` radians, sampled with a density so shadow rays can find lights
+    // through them. None for materials without such lobes.
+    fn regularized(&self, _angle: f64) -> Option<Arc<dyn Material>> {
+        None
+    }
+}
+
+// Id of a material in the registry. Hits carry one instead of an `Arc`, so recording a hit
+// copies eight bytes rather than touching a reference count; shading looks the material up
+// with `get`. Ids stay valid while some `MaterialRef` to the material is alive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialId {
+    index: u32,
+    // Bumped each time the slot is freed, so an old id can't reach the slot's next material.
+    generation: u32,
+}
+
+impl MaterialId {
+    // Panics if every `MaterialRef` to the material has been dropped.
+    #[inline]
+    pub fn get(self) -> Arc<dyn Material> {
+        MaterialRegistry::global()
+            .get(self)
+            .expect("material used after every object holding it was dropped")
+    }
+
+    // The same material with its mirror-like lobes regularized, built once per `angle`.
+    pub fn regularized(self, angle: f64) -> Option<MaterialId> {
+        MaterialRegistry::global().regularized(self, angle)
+    }
+}
+
+// A registered material, kept in the registry for as long as any clone of this lives. Objects
+// hold one and stamp its `id` on their hits.
+#[derive(Clone)]
+pub struct MaterialRef {
+    id: MaterialId,
+    material: Arc<dyn Material>,
+}
+
+impl MaterialRef {
+    // Fails only when billions of materials are alive at once.
+    pub fn new(material: Arc<dyn Material>) -> Result<Self> {
+        MaterialRegistry::global().register(material)
+    }
+
+    #[inline]
+    pub fn id(&self) -> MaterialId {
+        self.id
+    }
+}
+
+impl std::ops::Deref for MaterialRef {
+    type Target = dyn Material;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &*self.material
+    }
+}
+
+impl std::fmt::Debug for MaterialRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MaterialRef").field(&self.id).finish()
+    }
+}
+
+// For building objects directly; panics where `MaterialRef::new` would fail.
+impl From<Arc<dyn Material>> for MaterialRef {
+    fn from(material: Arc<dyn Material>) -> Self {
+        MaterialRef::new(material).unwrap_or_else(|e| panic!("{e}"))
+    }
+}
+
+impl<T: Material + 'static> From<Arc<T>> for MaterialRef {
+    fn from(material: Arc<T>) -> Self {
+        MaterialRef::from(material as Arc<dyn Material>)
+    }
+}
+
+// Segment `k` holds the next `FIRST_SEGMENT << k` slots, so slots never move and lookups only
+// lock the slot they read.
+const FIRST_SEGMENT: usize = 64;
+const SEGMENTS: usize = 26;
+
+#[derive(Default)]
+struct Slot {
+    generation: u32,
+    // Weak, so the registry never keeps a material alive; the `MaterialRef`s do.
+    material: Option<Weak<dyn Material>>,
+}
+
+type Segment = Box<[RwLock<Slot>]>;
+
+#[derive(Default)]
+struct Registrations {
+    // Ids by the address of the registered `Arc`, so sharing one registers it once.
+    ids: HashMap<usize, MaterialId>,
+    // Slots handed out so far, and those since freed for reuse.
+    len: usize,
+    free: Vec<u32>,
+    // Dead materials are swept out once this many slots are in use.
+    sweep_at: usize,
+}
+
+// Every material hits refer to. Slots of materials whose last `MaterialRef` has been dropped
+// are swept for reuse whenever the slots in use double, so building scene after scene in one
+// process doesn't grow it.
+pub struct MaterialRegistry {
+    segments: [OnceLock<Segment>; SEGMENTS],
+    registrations: Mutex<Registrations>,
+    // Keyed by the original material, holding the regularized one alive alongside it.
+    regularized: RwLock<HashMap<(MaterialId, u64), Option<MaterialRef>>>,
+}
+
+impl MaterialRegistry {
+    pub fn global() -> &'static MaterialRegistry {
+        static GLOBAL: OnceLock<MaterialRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(|| MaterialRegistry {
+            segments: [const { OnceLock::new() }; SEGMENTS],
+            registrations: Mutex::default(),
+            regularized: RwLock::default(),
+        })
+    }
+
+    #[inline]
+    fn locate(index: usize) -> (usize, usize) {
+        let k = (index / FIRST_SEGMENT + 1).ilog2() as usize;
+        (k, index - FIRST_SEGMENT * ((1 << k) - 1))
+    }
+
+    #[inline]
+    fn slot(&self, index: u32) -> Option<&RwLock<Slot>> {
+        let (k, i) = Self::locate(index as usize);
+        self.segments.get(k)?.get().map(|s| &s[i])
+    }
+
+    pub fn register(&self, material: Arc<dyn Material>) -> Result<MaterialRef> {
+        let key = Arc::as_ptr(&material) as *const () as usize;
+        // A panic while holding the lock leaves the table consistent, so carry on.
+        let mut registrations = self.registrations.lock().unwrap_or_else(|e| e.into_inner());
+        // Slots hold a weak reference until swept, which keeps the address from being reused,
+        // so a match is this very `Arc`.
+        if let Some(&id) = registrations.ids.get(&key) {
+            return Ok(MaterialRef { id, material });
+        }
+        if registrations.free.is_empty() && self.in_use(&registrations) >= registrations.sweep_at {
+            self.sweep(&mut registrations);
+        }
+        let index = match registrations.free.pop() {
+            Some(index) => index,
+            None => {
+                let index = registrations.len;
+                let (k, _) = Self::locate(index);
+                if k >= SEGMENTS {
+                    return Err(Error::Scene(format!("more than {index} materials in use")));
+                }
+                self.segments[k]
+                    .get_or_init(|| (0..FIRST_SEGMENT << k).map(|_| RwLock::default()).collect());
+                registrations.len += 1;
+                index as u32
+            }
+        };
+        let mut slot = self
+            .slot(index)
+            .unwrap()
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        slot.material = Some(Arc::downgrade(&material));
+        let id = MaterialId {
+            index,
+            generation: slot.generation,
+        };
+        registrations.ids.insert(key, id);
+        Ok(MaterialRef { id, material })
+    }
+
+    // None once every `MaterialRef` to the material has been dropped.
+    #[inline]
+    pub fn get(&self, id: MaterialId) -> Option<Arc<dyn Material>> {
+        let slot = self
+            .slot(id.index)?
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.material.as_ref()?.upgrade()
+    }
+
+    // Slots in use, including any whose materials have died since the last sweep.
+    pub fn len(&self) -> usize {
+        self.in_use(&self.registrations.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn in_use(&self, registrations: &Registrations) -> usize {
+        registrations.len - registrations.free.len()
+    }
+
+    fn free(&self, registrations: &mut Registrations, id: MaterialId) {
+        let mut slot = self
+            .slot(id.index)
+            .unwrap()
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.material = None;
+        registrations.free.push(id.index);
+    }
+
+    fn sweep(&self, registrations: &mut Registrations) {
+        // Regularized materials die with their originals.
+        self.regularized
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|&(id, _), _| self.get(id).is_some());
+        let dead: Vec<(usize, MaterialId)> = registrations
+            .ids
+            .iter()
+            .filter(|&(_, &id)| self.get(id).is_none())
+            .map(|(&key, &id)| (key, id))
+            .collect();
+        for (key, id) in dead {
+            registrations.ids.remove(&key);
+            self.free(registrations, id);
+        }
+        registrations.sweep_at = FIRST_SEGMENT.max(2 * self.in_use(registrations));
+    }
+
+    fn regularized(&self, id: MaterialId, angle: f64) -> Option<MaterialId> {
+        let key = (id, angle.to_bits());
+        if let Some(found) = self
+            .regularized
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+        {
+            return found.as_ref().map(MaterialRef::id);
+        }
+        // Leave the material as it is if the registry is full.
+        let built = self
+            .get(id)?
+            .regularized(angle)
+            .and_then(|m| self.register(m).ok());
+        let mut table = self.regularized.write().unwrap_or_else(|e| e.into_inner());
+        // Another thread may have built it meanwhile; keep theirs.
+        table
+            .entry(key)
+            .or_insert(built)
+            .as_ref()
+            .map(MaterialRef::id)
+    }
 }
 
 #[inline]
@@ -26,6 +357,61 @@ pub fn random_in_unit_sphere(rng: &mut dyn rand::RngCore) -> Vec3 {
     }
 }
 
+// Unit direction uniformly within `cos_max` of the unit vector `axis`.
+fn sample_cone(axis: Vec3, cos_max: f64, rng: &mut dyn rand::RngCore) -> Vec3 {
+    let cos_theta = 1.0 - rng.random::<f64>() * (1.0 - cos_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * rng.random::<f64>();
+    Onb::from_w(axis).local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+// Density of `sample_cone` at the unit vector `direction`.
+fn cone_pdf(axis: Vec3, cos_max: f64, direction: Vec3) -> f64 {
+    if Vec3::dot(axis, direction) >= cos_max {
+        1.0 / (2.0 * std::f64::consts::PI * (1.0 - cos_max))
+    } else {
+        0.0
+    }
+}
+
+// `sample_cone`, with samples on the wrong side of the surface with normal `n` mirrored back
+// across it, so a cone that dips through the surface loses nothing. `above` is the side
+// wanted.
+fn sample_folded_cone(
+    axis: Vec3,
+    cos_max: f64,
+    n: Vec3,
+    above: bool,
+    rng: &mut dyn rand::RngCore,
+) -> Vec3 {
+    let d = sample_cone(axis, cos_max, rng);
+    if (Vec3::dot(d, n) > 0.0) == above {
+        d
+    } else {
+        reflect(d, n)
+    }
+}
+
+// Density of `sample_folded_cone` at a unit `direction` on the wanted side.
+fn folded_cone_pdf(axis: Vec3, cos_max: f64, n: Vec3, direction: Vec3) -> f64 {
+    cone_pdf(axis, cos_max, direction) + cone_pdf(axis, cos_max, reflect(direction, n))
+}
+
+// Direction in the local frame (z up) distributed by cos(theta) / pi over the hemisphere.
+#[inline]
+pub fn random_cosine_direction(rng: &mut dyn rand::RngCore) -> Vec3 {
+    let r1 = rng.random::<f64>();
+    let r2 = rng.random::<f64>();
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let r = r2.sqrt();
+    Vec3::new(phi.cos() * r, phi.sin() * r, (1.0 - r2).sqrt())
+}
+
+#[inline]
+pub fn cosine_pdf(cos_theta: f64) -> f64 {
+    (cos_theta / std::f64::consts::PI).max(0.0)
+}
+
 #[inline]
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
     v - 2.0 * Vec3::dot(v, n) * n
@@ -50,6 +436,28 @@ pub fn schlick(cosine: f64, ref_idx: f64) -> f64 {
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
 
+// Unpolarized Fresnel reflectance of a smooth dielectric interface, where `eta` = n_t / n_i and
+// `cos_theta_i` is measured against the normal on the incident side. Returns 1 under total
+// internal reflection.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta_i.clamp(-1.0, 1.0);
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, 1.0 / eta)
+    } else {
+        (cos_i, eta)
+    };
+
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
 pub struct Lambertian {
     pub albedo: Vec3,
 }
@@ -64,20 +472,93 @@ impl Material for Lambertian {
     #[inline]
     fn scatter(
         &self,
-        _ray_in: &Ray,
+        ray_in: &Ray,
         rec: &HitRecord,
         rng: &mut dyn rand::RngCore,
     ) -> Option<(Vec3, Ray)> {
-        let target = rec.point + rec.normal + random_in_unit_sphere(rng);
-        let scattered = Ray::new(rec.point, target - rec.point);
+        // Cosine-weighted sampling cancels the cosine term of the BRDF, leaving the albedo.
+        let uvw = Onb::from_w(rec.normal);
+        let direction = uvw.to_world(random_cosine_direction(rng));
+        let scattered = Ray::with_time(rec.point, direction, ray_in.time());
         let attenuation = self.albedo;
         Some((attenuation, scattered))
     }
+
+    #[inline]
+    fn scattering_pdf(&self, _ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        cosine_pdf(Vec3::dot(
+            rec.normal,
+            Vec3::unit_vector(scattered.direction()),
+        ))
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Lambertian {
+            albedo: self.albedo,
+        })
+    }
+}
+
+// Metals whose complex index of refraction has been measured, for `Metal::with_measured`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeasuredMetal {
+    Gold,
+    Silver,
+    Copper,
+    #[serde(alias = "aluminium")]
+    Aluminum,
+}
+
+impl MeasuredMetal {
+    // Index n + ik for the red, green and blue primaries, from measurements at about 650, 550
+    // and 450 nm.
+    pub fn ior(self) -> (Color, Color) {
+        let (n, k) = match self {
+            MeasuredMetal::Gold => ([0.18299, 0.42108, 1.37340], [3.42420, 2.34590, 1.77040]),
+            MeasuredMetal::Silver => ([0.15943, 0.14512, 0.13547], [3.92910, 3.19000, 2.38080]),
+            MeasuredMetal::Copper => ([0.27105, 0.67693, 1.31640], [3.60920, 2.62480, 2.29210]),
+            MeasuredMetal::Aluminum => ([1.34560, 0.96521, 0.61722], [7.47460, 6.39950, 5.30310]),
+        };
+        (Color::from(n), Color::from(k))
+    }
+
+    // Reflectance for light arriving at `cos_theta` to the normal.
+    pub fn reflectance(self, cos_theta: f64) -> Color {
+        let (n, k) = self.ior();
+        Color::new(
+            fresnel_conductor(cos_theta, n.r(), k.r()),
+            fresnel_conductor(cos_theta, n.g(), k.g()),
+            fresnel_conductor(cos_theta, n.b(), k.b()),
+        )
+    }
+}
+
+// Unpolarized Fresnel reflectance of a conductor of complex index `eta` + i`k`, for light
+// arriving from air at `cos_theta_i` to the normal.
+pub fn fresnel_conductor(cos_theta_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta_i.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let (eta2, k2) = (eta * eta, k * k);
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos2.sqrt() * a;
+    let r_s = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let r_p = r_s * (t3 - t4) / (t3 + t4);
+    0.5 * (r_s + r_p)
 }
 
+// Reflects with the albedo, or with a measured metal's Fresnel reflectance, which the albedo
+// then tints: darker head on and brightening to white at grazing angles.
+#[derive(Copy, Clone)]
 pub struct Metal {
     albedo: Vec3,
     fuzz: f64,
+    measured: Option<MeasuredMetal>,
 }
 
 impl Metal {
@@ -89,8 +570,30 @@ impl Metal {
             } else {
                 1.0
             },
+            measured: None,
         }
     }
+
+    pub fn with_measured(mut self, metal: MeasuredMetal) -> Self {
+        self.measured = Some(metal);
+        self
+    }
+
+    // Of light coming in along `ray_in` and leaving along `scattered`, about the microfacet
+    // between them.
+    fn reflectance(&self, ray_in: &Ray, scattered: &Ray) -> Color {
+        let Some(metal) = self.measured else {
+            return self.albedo;
+        };
+        let out = Vec3::unit_vector(scattered.direction());
+        let half = out - Vec3::unit_vector(ray_in.direction());
+        let cos_theta = if half.length_squared() > 0.0 {
+            Vec3::dot(out, Vec3::unit_vector(half))
+        } else {
+            0.0
+        };
+        self.albedo * metal.reflectance(cos_theta)
+    }
 }
 
 impl Material for Metal {
@@ -102,17 +605,76 @@ impl Material for Metal {
         rng: &mut dyn rand::RngCore,
     ) -> Option<(Vec3, Ray)> {
         let reflected = reflect(Vec3::unit_vector(ray_in.direction()), rec.normal);
-        let scattered = Ray::new(
+        let mut scattered = Ray::with_time(
             rec.point,
             reflected + self.fuzz * random_in_unit_sphere(rng),
+            ray_in.time(),
         );
-        let attenuation = self.albedo;
+        // Fuzz decorrelates neighbouring rays; only a perfect mirror keeps the differential.
+        if self.fuzz == 0.0 {
+            scattered =
+                scattered
+                    .with_differential(ray_in.specular_differential(rec.t, rec.normal, |d| {
+                        Some(reflect(d, rec.normal))
+                    }));
+        }
         if Vec3::dot(scattered.direction(), rec.normal) > 0.0 {
-            Some((attenuation, scattered))
+            Some((self.reflectance(ray_in, &scattered), scattered))
         } else {
             None
         }
     }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Metal {
+            albedo: self.albedo,
+            fuzz: self.fuzz,
+            measured: self.measured,
+        })
+    }
+
+    fn regularized(&self, angle: f64) -> Option<Arc<dyn Material>> {
+        // Fuzz offsets reflections by up to about asin(fuzz).
+        let angle = angle.max(self.fuzz.asin()).min(std::f64::consts::FRAC_PI_2);
+        Some(Arc::new(RegularizedMetal {
+            metal: *self,
+            cos_max: angle.cos(),
+        }))
+    }
+}
+
+// `Metal` reflecting uniformly within a cone around the mirror direction.
+struct RegularizedMetal {
+    // The original, for its reflectance and export.
+    metal: Metal,
+    cos_max: f64,
+}
+
+impl Material for RegularizedMetal {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        let axis = reflect(Vec3::unit_vector(ray_in.direction()), rec.normal);
+        let direction = sample_folded_cone(axis, self.cos_max, rec.normal, true, rng);
+        let scattered = Ray::with_time(rec.point, direction, ray_in.time());
+        Some((self.metal.reflectance(ray_in, &scattered), scattered))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let direction = Vec3::unit_vector(scattered.direction());
+        if Vec3::dot(direction, rec.normal) <= 0.0 {
+            return 0.0;
+        }
+        let axis = reflect(Vec3::unit_vector(ray_in.direction()), rec.normal);
+        folded_cone_pdf(axis, self.cos_max, rec.normal, direction)
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        self.metal.to_desc()
+    }
 }
 
 pub struct Dielectric {
@@ -135,33 +697,794 @@ impl Material for Dielectric {
     ) -> Option<(Vec3, Ray)> {
         let attenuation = Vec3::new(1.0, 1.0, 1.0);
 
-        let reflected = reflect(ray_in.direction(), rec.normal);
+        // IOR ratio n_t / n_i across the interface the ray is crossing.
+        let eta = if rec.front_face {
+            self.ref_idx
+        } else {
+            1.0 / self.ref_idx
+        };
 
-        let (outward_normal, ni_over_nt, cosine) = if Vec3::dot(ray_in.direction(), rec.normal)
-            > 0.0
-        {
-            let outward_normal = -rec.normal;
-            let ni_over_nt = self.ref_idx;
-            let cosine = self.ref_idx * Vec3::dot(ray_in.direction(), rec.normal)
-                / ray_in.direction().length();
-            (outward_normal, ni_over_nt, cosine)
+        let unit_dir = Vec3::unit_vector(ray_in.direction());
+        let cos_theta = (-Vec3::dot(unit_dir, rec.normal)).min(1.0);
+        let reflect_prob = fresnel_dielectric(cos_theta, eta);
+
+        let refracted = (rng.random::<f64>() >= reflect_prob)
+            .then(|| refract(unit_dir, rec.normal, 1.0 / eta))
+            .flatten();
+        let direction = refracted.unwrap_or_else(|| reflect(unit_dir, rec.normal));
+        let diff = ray_in.specular_differential(rec.t, rec.normal, |d| match refracted {
+            Some(_) => refract(d, rec.normal, 1.0 / eta),
+            None => Some(reflect(d, rec.normal)),
+        });
+
+        Some((
+            attenuation,
+            Ray::with_time(rec.point, direction, ray_in.time()).with_differential(diff),
+        ))
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Dielectric { ior: self.ref_idx })
+    }
+
+    fn regularized(&self, angle: f64) -> Option<Arc<dyn Material>> {
+        Some(Arc::new(RegularizedDielectric {
+            ref_idx: self.ref_idx,
+            cos_max: angle.min(std::f64::consts::FRAC_PI_2).cos(),
+        }))
+    }
+}
+
+// `Dielectric` reflecting and refracting uniformly within cones around the mirror and
+// refracted directions, chosen between by Fresnel reflectance.
+struct RegularizedDielectric {
+    ref_idx: f64,
+    cos_max: f64,
+}
+
+impl RegularizedDielectric {
+    // Reflected and refracted axes with the probability of reflecting; no refracted axis
+    // under total internal reflection.
+    fn lobes(&self, ray_in: &Ray, rec: &HitRecord) -> (Vec3, Option<Vec3>, f64) {
+        let eta = if rec.front_face {
+            self.ref_idx
+        } else {
+            1.0 / self.ref_idx
+        };
+        let unit_dir = Vec3::unit_vector(ray_in.direction());
+        let cos_theta = (-Vec3::dot(unit_dir, rec.normal)).min(1.0);
+        let reflected = reflect(unit_dir, rec.normal);
+        match refract(unit_dir, rec.normal, 1.0 / eta) {
+            Some(refracted) => (
+                reflected,
+                Some(Vec3::unit_vector(refracted)),
+                fresnel_dielectric(cos_theta, eta),
+            ),
+            None => (reflected, None, 1.0),
+        }
+    }
+}
+
+impl Material for RegularizedDielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        let (reflected, refracted, reflect_prob) = self.lobes(ray_in, rec);
+        let (axis, above) = match refracted {
+            Some(t) if rng.random::<f64>() >= reflect_prob => (t, false),
+            _ => (reflected, true),
+        };
+        let direction = sample_folded_cone(axis, self.cos_max, rec.normal, above, rng);
+        Some((
+            Vec3::new(1.0, 1.0, 1.0),
+            Ray::with_time(rec.point, direction, ray_in.time()),
+        ))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let direction = Vec3::unit_vector(scattered.direction());
+        let (reflected, refracted, reflect_prob) = self.lobes(ray_in, rec);
+        if Vec3::dot(direction, rec.normal) > 0.0 {
+            reflect_prob * folded_cone_pdf(reflected, self.cos_max, rec.normal, direction)
+        } else {
+            refracted.map_or(0.0, |t| {
+                (1.0 - reflect_prob) * folded_cone_pdf(t, self.cos_max, rec.normal, direction)
+            })
+        }
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Dielectric::new(self.ref_idx).to_desc()
+    }
+}
+
+// A pane of glass too thin to model both sides of, e.g. a window or a soap bubble: light
+// either reflects off it or passes straight through, without the sideways shift or the second
+// refraction a solid would give. Reflectance includes the light bouncing back and forth
+// inside the pane, so a single surface of it looks as bright as two would.
+//
+// Light passing through picks up the `tint`, as through tinted glazing. It does so without a
+// bounce, as a filter, so shadow rays see through panes too and windows are cheap to light a
+// room through; hits that stop reflect.
+pub struct ThinDielectric {
+    pub ref_idx: f64,
+    pub tint: Color,
+}
+
+impl ThinDielectric {
+    pub fn new(ref_idx: f64) -> Self {
+        Self {
+            ref_idx,
+            tint: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    // Of both faces together, for light arriving at `cos_theta` to the normal from either side.
+    pub fn reflectance(&self, cos_theta: f64) -> f64 {
+        let r = fresnel_dielectric(cos_theta.abs(), self.ref_idx);
+        if r < 1.0 {
+            2.0 * r / (1.0 + r)
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Material for ThinDielectric {
+    #[inline]
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        _rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        let direction = reflect(Vec3::unit_vector(ray_in.direction()), rec.normal);
+        let diff =
+            ray_in.specular_differential(rec.t, rec.normal, |d| Some(reflect(d, rec.normal)));
+        Some((
+            Vec3::new(1.0, 1.0, 1.0),
+            Ray::with_time(rec.point, direction, ray_in.time()).with_differential(diff),
+        ))
+    }
+
+    fn filter(&self, r: &Ray, rec: &HitRecord, rng: &mut dyn rand::RngCore) -> Option<Color> {
+        let cos_theta = Vec3::dot(Vec3::unit_vector(r.direction()), rec.normal);
+        (rng.random::<f64>() >= self.reflectance(cos_theta)).then_some(self.tint)
+    }
+
+    fn transmittance(&self, r: &Ray, rec: &HitRecord) -> Color {
+        let cos_theta = Vec3::dot(Vec3::unit_vector(r.direction()), rec.normal);
+        (1.0 - self.reflectance(cos_theta)) * self.tint
+    }
+
+    fn is_filter(&self) -> bool {
+        true
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::ThinDielectric {
+            ior: self.ref_idx,
+            tint: self.tint,
+        })
+    }
+}
+
+// A diffuse base under a clear dielectric coat, like plastic or varnished wood. The coat
+// reflects by Fresnel, smoothly or, with `roughness`, over a cone; the base gets what the coat
+// lets in and lets out what the coat passes back. Light the coat reflects back down is
+// diffused again rather than lost, so a white base reflects everything.
+#[derive(Copy, Clone)]
+pub struct Plastic {
+    pub albedo: Color,
+    pub ior: f64,
+    pub roughness: f64,
+    // Cosine of the coat's spread, 1 for a mirror.
+    cos_max: f64,
+    // Diffuse reflectance of the coat from outside.
+    fdr: f64,
+    // Base albedo after light bounces between base and coat, per lobe sample.
+    base: Color,
+}
+
+impl Plastic {
+    pub fn new(albedo: Color, ior: f64) -> Self {
+        // Twice the integral of F(mu) mu over mu in [0, 1].
+        const STEPS: usize = 1024;
+        let fdr = (0..STEPS)
+            .map(|i| {
+                let mu = (i as f64 + 0.5) / STEPS as f64;
+                2.0 * fresnel_dielectric(mu, ior) * mu / STEPS as f64
+            })
+            .sum::<f64>();
+        // From inside, much more is reflected back down: what leaves shrinks by eta^2.
+        let fdr_inside = 1.0 - (1.0 - fdr) / (ior * ior);
+        let base = |a: f64| a * (1.0 - fdr) / (ior * ior * (1.0 - a * fdr_inside));
+        Self {
+            albedo,
+            ior,
+            roughness: 0.0,
+            cos_max: 1.0,
+            fdr,
+            base: Color::new(base(albedo.r()), base(albedo.g()), base(albedo.b())),
+        }
+    }
+
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self.cos_max = (1.0 - self.roughness * self.roughness).sqrt();
+        self
+    }
+
+    fn is_smooth(&self) -> bool {
+        self.cos_max >= 1.0
+    }
+
+    // Mirror direction and the coat's reflectance for light along `ray_in`.
+    fn coat(&self, ray_in: &Ray, rec: &HitRecord) -> (Vec3, f64) {
+        let unit_dir = Vec3::unit_vector(ray_in.direction());
+        let cos_theta = (-Vec3::dot(unit_dir, rec.normal)).clamp(0.0, 1.0);
+        (
+            reflect(unit_dir, rec.normal),
+            fresnel_dielectric(cos_theta, self.ior),
+        )
+    }
+
+    // Density of diffuse samples leaving at `cos_theta`: cosine weighted, less what the coat
+    // reflects back on the way out.
+    fn diffuse_pdf(&self, cos_theta: f64) -> f64 {
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+        let through = 1.0 - fresnel_dielectric(cos_theta, self.ior);
+        cosine_pdf(cos_theta) * through / (1.0 - self.fdr)
+    }
+
+    // A direction with density `diffuse_pdf`, by rejection from cosine-weighted ones.
+    fn sample_diffuse(&self, rec: &HitRecord, rng: &mut dyn rand::RngCore) -> Vec3 {
+        let uvw = Onb::from_w(rec.normal);
+        let head_on = 1.0 - fresnel_dielectric(1.0, self.ior);
+        loop {
+            let local = random_cosine_direction(rng);
+            let through = 1.0 - fresnel_dielectric(local.z, self.ior);
+            if rng.random::<f64>() * head_on < through {
+                return uvw.to_world(local);
+            }
+        }
+    }
+
+    // Rough coats only: the density of either lobe and BRDF times cosine towards `direction`.
+    fn rough(&self, ray_in: &Ray, rec: &HitRecord, direction: Vec3) -> (f64, Color) {
+        let (mirror, f) = self.coat(ray_in, rec);
+        let coat = folded_cone_pdf(mirror, self.cos_max, rec.normal, direction);
+        let diffuse = self.diffuse_pdf(Vec3::dot(direction, rec.normal));
+        (
+            f * coat + (1.0 - f) * diffuse,
+            Color::new(f, f, f) * coat + (1.0 - f) * diffuse * self.base,
+        )
+    }
+}
+
+impl Material for Plastic {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        let (mirror, f) = self.coat(ray_in, rec);
+        let on_coat = rng.random::<f64>() < f;
+        if self.is_smooth() {
+            // Each lobe in proportion to how much it reflects, so the coat's is white.
+            return Some(if on_coat {
+                let diff = ray_in
+                    .specular_differential(rec.t, rec.normal, |d| Some(reflect(d, rec.normal)));
+                (
+                    Color::new(1.0, 1.0, 1.0),
+                    Ray::with_time(rec.point, mirror, ray_in.time()).with_differential(diff),
+                )
+            } else {
+                let direction = self.sample_diffuse(rec, rng);
+                (
+                    self.base,
+                    Ray::with_time(rec.point, direction, ray_in.time()),
+                )
+            });
+        }
+        let direction = if on_coat {
+            sample_folded_cone(mirror, self.cos_max, rec.normal, true, rng)
         } else {
-            let outward_normal = rec.normal;
-            let ni_over_nt = 1.0 / self.ref_idx;
-            let cosine = -Vec3::dot(ray_in.direction(), rec.normal) / ray_in.direction().length();
-            (outward_normal, ni_over_nt, cosine)
+            self.sample_diffuse(rec, rng)
+        };
+        let (pdf, value) = self.rough(ray_in, rec, Vec3::unit_vector(direction));
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some((
+            value / pdf,
+            Ray::with_time(rec.point, direction, ray_in.time()),
+        ))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let direction = Vec3::unit_vector(scattered.direction());
+        if !self.is_smooth() {
+            return self.rough(ray_in, rec, direction).0;
+        }
+        // The mirror direction is the coat's; anywhere else it's the diffuse lobe's, given
+        // that one was picked.
+        let (mirror, _) = self.coat(ray_in, rec);
+        if Vec3::dot(direction, mirror) > 1.0 - 1e-12 {
+            return 0.0;
+        }
+        self.diffuse_pdf(Vec3::dot(direction, rec.normal))
+    }
+
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color,
+        scattered: &Ray,
+    ) -> Color {
+        if self.is_smooth() {
+            return attenuation * self.scattering_pdf(ray_in, rec, scattered);
+        }
+        let direction = Vec3::unit_vector(scattered.direction());
+        self.rough(ray_in, rec, direction).1
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Plastic {
+            albedo: self.albedo,
+            ior: self.ior,
+            roughness: self.roughness,
+        })
+    }
+
+    fn regularized(&self, angle: f64) -> Option<Arc<dyn Material>> {
+        // Spread the coat; `roughness` stays as it was, for export.
+        let cos_max = angle.min(std::f64::consts::FRAC_PI_2).cos();
+        Some(Arc::new(Plastic {
+            cos_max: self.cos_max.min(cos_max),
+            ..*self
+        }))
+    }
+}
+
+// Flakes spread what they reflect over a cone this wide, in radians, so shadow rays can find
+// the lights they glint with.
+const FLAKE_GLOSS: f64 = 0.1;
+
+// Car paint: a pigment under a clearcoat, as `Plastic`, with metal flakes in the pigment. The
+// surface is cut into cells `flake_size` across, and each holds a flake with chance
+// `flake_density`, tilted up to `flake_spread` radians from the surface at random; where
+// there's one, light getting past the coat glints off it in `flake_color` instead. Cells are
+// fixed in space, so a flake catches the light from one viewpoint and not the next: sparkle.
+#[derive(Copy, Clone)]
+pub struct CarPaint {
+    pub color: Color,
+    pub ior: f64,
+    pub flake_color: Color,
+    pub flake_density: f64,
+    pub flake_size: f64,
+    pub flake_spread: f64,
+    paint: Plastic,
+}
+
+impl CarPaint {
+    pub fn new(color: Color) -> Self {
+        let ior = 1.5;
+        Self {
+            color,
+            ior,
+            flake_color: Color::new(0.9, 0.9, 0.9),
+            flake_density: 0.3,
+            flake_size: 0.005,
+            flake_spread: 0.3,
+            paint: Plastic::new(color, ior),
+        }
+    }
+
+    pub fn with_ior(mut self, ior: f64) -> Self {
+        self.ior = ior;
+        self.paint = Plastic::new(self.color, ior);
+        self
+    }
+
+    pub fn with_flakes(mut self, color: Color, density: f64) -> Self {
+        self.flake_color = color;
+        self.flake_density = density.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_flake_size(mut self, size: f64) -> Self {
+        self.flake_size = size;
+        self
+    }
+
+    pub fn with_flake_spread(mut self, angle: f64) -> Self {
+        self.flake_spread = angle.clamp(0.0, std::f64::consts::FRAC_PI_2);
+        self
+    }
+
+    // Normal of the flake at `point` on a surface with normal `n`, if there is one there.
+    pub fn flake(&self, point: Point3, n: Vec3) -> Option<Vec3> {
+        if self.flake_size <= 0.0 {
+            return None;
+        }
+        let cell = point / self.flake_size;
+        let mut h = 0u64;
+        for x in [cell.x, cell.y, cell.z] {
+            // splitmix64 finalizer.
+            h = (h ^ (x.floor() as i64 as u64)).wrapping_add(0x9e37_79b9_7f4a_7c15);
+            h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            h ^= h >> 31;
+        }
+        let mut uniform = || {
+            h = h.wrapping_mul(0x5851_f42d_4c95_7f2d).wrapping_add(1);
+            (h >> 11) as f64 / (1u64 << 53) as f64
         };
+        if uniform() >= self.flake_density {
+            return None;
+        }
+        // Uniform over the cap of tilts within the spread.
+        let cos_theta = 1.0 - uniform() * (1.0 - self.flake_spread.cos());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * uniform();
+        Some(Onb::from_w(n).local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+    }
+
+    // Where a flake at `rec` sends light from `ray_in`, if one is there and faces it; without,
+    // the hit is plain paint.
+    fn glint(&self, ray_in: &Ray, rec: &HitRecord) -> Option<Vec3> {
+        let m = self.flake(rec.point, rec.normal)?;
+        let axis = reflect(Vec3::unit_vector(ray_in.direction()), m);
+        (Vec3::dot(axis, rec.normal) > 0.0).then_some(axis)
+    }
 
-        let reflect_prob = match refract(ray_in.direction(), outward_normal, ni_over_nt) {
-            Some(_) => schlick(cosine, self.ref_idx).min(1.0).max(0.0),
-            None => 1.0,
+    // What a flake reflects towards `direction`, less what the coat keeps in on the way out.
+    fn flake_reflectance(&self, rec: &HitRecord, direction: Vec3) -> Color {
+        let cos_theta = Vec3::dot(direction, rec.normal);
+        if cos_theta <= 0.0 {
+            return Color::default();
+        }
+        (1.0 - fresnel_dielectric(cos_theta, self.ior)) * self.flake_color
+    }
+}
+
+impl Material for CarPaint {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        let Some(axis) = self.glint(ray_in, rec) else {
+            return self.paint.scatter(ray_in, rec, rng);
         };
+        let (mirror, f) = self.paint.coat(ray_in, rec);
+        if rng.random::<f64>() < f {
+            let diff =
+                ray_in.specular_differential(rec.t, rec.normal, |d| Some(reflect(d, rec.normal)));
+            return Some((
+                Color::new(1.0, 1.0, 1.0),
+                Ray::with_time(rec.point, mirror, ray_in.time()).with_differential(diff),
+            ));
+        }
+        let direction = sample_folded_cone(axis, FLAKE_GLOSS.cos(), rec.normal, true, rng);
+        Some((
+            self.flake_reflectance(rec, Vec3::unit_vector(direction)),
+            Ray::with_time(rec.point, direction, ray_in.time()),
+        ))
+    }
 
-        if rng.random::<f64>() < reflect_prob {
-            Some((attenuation, Ray::new(rec.point, reflected)))
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let Some(axis) = self.glint(ray_in, rec) else {
+            return self.paint.scattering_pdf(ray_in, rec, scattered);
+        };
+        // As for `Plastic`, the coat's mirror has no density and the flake's is given that
+        // it was picked.
+        let direction = Vec3::unit_vector(scattered.direction());
+        let (mirror, _) = self.paint.coat(ray_in, rec);
+        if Vec3::dot(direction, mirror) > 1.0 - 1e-12 || Vec3::dot(direction, rec.normal) <= 0.0 {
+            return 0.0;
+        }
+        folded_cone_pdf(axis, FLAKE_GLOSS.cos(), rec.normal, direction)
+    }
+
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color,
+        scattered: &Ray,
+    ) -> Color {
+        if self.glint(ray_in, rec).is_none() {
+            return self.paint.scattering(ray_in, rec, attenuation, scattered);
+        }
+        let direction = Vec3::unit_vector(scattered.direction());
+        self.flake_reflectance(rec, direction) * self.scattering_pdf(ray_in, rec, scattered)
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::CarPaint {
+            color: self.color,
+            ior: self.ior,
+            flake_color: self.flake_color,
+            flake_density: self.flake_density,
+            flake_size: self.flake_size,
+            flake_spread: self.flake_spread,
+        })
+    }
+}
+
+// Points tabulating the sheen's directional albedo, from grazing to head on.
+const SHEEN_TABLE: usize = 32;
+
+// Cloth such as velvet: a diffuse `albedo` under a fuzz of fibres that catch light at grazing
+// angles, Estevez and Kulla's "Charlie" sheen with Neubelt and Pettineo's visibility term.
+// `roughness` from 0 to 1 widens the sheen from a thin rim to a soft haze. The base only gets
+// what the sheen doesn't reflect, so together they never reflect more than arrives.
+#[derive(Clone)]
+pub struct Velvet {
+    pub albedo: Color,
+    pub sheen: Color,
+    pub roughness: f64,
+    // Sheen reflectance for light along `i / (SHEEN_TABLE - 1)` from the surface, for white
+    // sheen.
+    table: [f64; SHEEN_TABLE],
+}
+
+impl Velvet {
+    pub fn new(albedo: Color, roughness: f64) -> Self {
+        let mut velvet = Self {
+            albedo,
+            sheen: Color::new(1.0, 1.0, 1.0),
+            roughness: roughness.clamp(0.07, 1.0),
+            table: [0.0; SHEEN_TABLE],
+        };
+        // Midpoint rule over the hemisphere; the lobe is smooth, so a coarse grid does.
+        const STEPS: usize = 48;
+        velvet.table = std::array::from_fn(|i| {
+            let cos_v = (i as f64 / (SHEEN_TABLE - 1) as f64).max(1e-3);
+            let v = Vec3::new((1.0 - cos_v * cos_v).sqrt(), 0.0, cos_v);
+            let mut sum = 0.0;
+            for a in 0..STEPS {
+                let cos_l = (a as f64 + 0.5) / STEPS as f64;
+                let sin_l = (1.0 - cos_l * cos_l).sqrt();
+                for b in 0..STEPS {
+                    let phi = std::f64::consts::PI * (b as f64 + 0.5) / STEPS as f64;
+                    let l = Vec3::new(sin_l * phi.cos(), sin_l * phi.sin(), cos_l);
+                    sum += velvet.sheen_brdf(v, l, Vec3::new(0.0, 0.0, 1.0)) * cos_l;
+                }
+            }
+            // Over cos_l in [0, 1] and, by symmetry, twice phi in [0, pi].
+            sum * 2.0 * std::f64::consts::PI / (STEPS * STEPS) as f64
+        });
+        velvet
+    }
+
+    pub fn with_sheen(mut self, sheen: Color) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    // White sheen's BRDF between unit `v` and `l` about the normal `n`.
+    fn sheen_brdf(&self, v: Vec3, l: Vec3, n: Vec3) -> f64 {
+        let (cos_v, cos_l) = (Vec3::dot(v, n), Vec3::dot(l, n));
+        if cos_v <= 0.0 || cos_l <= 0.0 {
+            return 0.0;
+        }
+        let half = v + l;
+        let cos_h = if half.length_squared() > 0.0 {
+            Vec3::dot(Vec3::unit_vector(half), n)
         } else {
-            let refracted = refract(ray_in.direction(), outward_normal, ni_over_nt).unwrap();
-            Some((attenuation, Ray::new(rec.point, refracted)))
+            0.0
+        };
+        let sin2_h = (1.0 - cos_h * cos_h).max(0.0);
+        let inv_r = 1.0 / self.roughness;
+        let d = (2.0 + inv_r) * sin2_h.powf(0.5 * inv_r) / (2.0 * std::f64::consts::PI);
+        let visibility = 1.0 / (4.0 * (cos_l + cos_v - cos_l * cos_v));
+        d * visibility
+    }
+
+    // How much white sheen reflects of light arriving `cos_theta` from the normal.
+    pub fn sheen_albedo(&self, cos_theta: f64) -> f64 {
+        let x = cos_theta.clamp(0.0, 1.0) * (SHEEN_TABLE - 1) as f64;
+        let i = (x as usize).min(SHEEN_TABLE - 2);
+        let t = x - i as f64;
+        self.table[i] * (1.0 - t) + self.table[i + 1] * t
+    }
+
+    // BRDF times cosine from `ray_in` towards `direction`.
+    fn reflected(&self, ray_in: &Ray, rec: &HitRecord, direction: Vec3) -> Color {
+        let v = -Vec3::unit_vector(ray_in.direction());
+        let l = Vec3::unit_vector(direction);
+        let cos_l = Vec3::dot(l, rec.normal);
+        if cos_l <= 0.0 {
+            return Color::default();
         }
+        let strongest = self.sheen.r().max(self.sheen.g()).max(self.sheen.b());
+        let left = 1.0 - strongest * self.sheen_albedo(Vec3::dot(v, rec.normal));
+        let sheen = self.sheen * self.sheen_brdf(v, l, rec.normal);
+        (sheen + self.albedo * (left / std::f64::consts::PI)) * cos_l
+    }
+}
+
+impl Material for Velvet {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        let direction = Onb::from_w(rec.normal).to_world(random_cosine_direction(rng));
+        let pdf = cosine_pdf(Vec3::dot(Vec3::unit_vector(direction), rec.normal));
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some((
+            self.reflected(ray_in, rec, direction) / pdf,
+            Ray::with_time(rec.point, direction, ray_in.time()),
+        ))
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        cosine_pdf(Vec3::dot(
+            rec.normal,
+            Vec3::unit_vector(scattered.direction()),
+        ))
+    }
+
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        _attenuation: Color,
+        scattered: &Ray,
+    ) -> Color {
+        self.reflected(ray_in, rec, scattered.direction())
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Velvet {
+            albedo: self.albedo,
+            sheen: self.sheen,
+            roughness: self.roughness,
+        })
+    }
+}
+
+pub struct DiffuseLight {
+    pub emit: Color,
+    pub group: Option<Arc<str>>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self { emit, group: None }
+    }
+
+    // Glows with the color of a blackbody at `kelvin`, brightest channel `intensity`.
+    pub fn blackbody(kelvin: f64, intensity: f64) -> Self {
+        Self::new(intensity * color::blackbody(kelvin))
+    }
+
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = Some(Arc::from(group));
+        self
+    }
+}
+
+impl Material for DiffuseLight {
+    #[inline]
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _rec: &HitRecord,
+        _rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    #[inline]
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        self.emit
+    }
+
+    fn light_group(&self) -> Option<Arc<str>> {
+        self.group.clone()
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::DiffuseLight {
+            emit: self.emit,
+            group: self.group.as_deref().map(str::to_string),
+        })
+    }
+}
+
+// `material` with holes cut by an opacity texture, e.g. a leaf on a card: rays pass through
+// with probability one minus the texture's mean channel value at the hit's (u, v).
+pub struct Cutout {
+    material: Arc<dyn Material>,
+    opacity: ImageTexture,
+}
+
+impl Cutout {
+    pub fn new(material: Arc<dyn Material>, opacity: ImageTexture) -> Self {
+        Self { material, opacity }
+    }
+}
+
+impl Material for Cutout {
+    #[inline]
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        self.material.scatter(ray_in, rec, rng)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.material.emitted(rec)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.material.scattering_pdf(ray_in, rec, scattered)
+    }
+
+    fn scattering(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color,
+        scattered: &Ray,
+    ) -> Color {
+        self.material
+            .scattering(ray_in, rec, attenuation, scattered)
+    }
+
+    fn light_group(&self) -> Option<Arc<str>> {
+        self.material.light_group()
+    }
+
+    fn opacity(&self, rec: &HitRecord) -> f64 {
+        let c = self.opacity.value(rec.u, rec.v);
+        ((c.r() + c.g() + c.b()) / 3.0).clamp(0.0, 1.0) * self.material.opacity(rec)
+    }
+
+    fn has_cutouts(&self) -> bool {
+        true
+    }
+
+    fn textures(&self, out: &mut Vec<ImageTexture>) {
+        out.push(self.opacity.clone());
+        self.material.textures(out);
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Cutout {
+            material: Box::new(self.material.to_desc()?),
+            opacity: self.opacity.path().to_path_buf(),
+        })
+    }
+
+    fn regularized(&self, angle: f64) -> Option<Arc<dyn Material>> {
+        Some(Arc::new(Cutout::new(
+            self.material.regularized(angle)?,
+            self.opacity.clone(),
+        )))
     }
 }
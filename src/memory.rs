@@ -0,0 +1,80 @@
+// What a render will need in memory, estimated before its films are allocated, so a frame too
+// large for the machine fails up front instead of partway through. The scene is measured, as
+// it is already loaded, and textures not yet decoded are sized from their headers; the films
+// and AOVs are sized from the resolution. Paged geometry counts only what is resident; its
+// cache has its own budget.
+
+use crate::aabb::Aabb;
+use crate::aov::{Aov, AovSet};
+use crate::error::{Error, Result};
+use crate::film::Pixel;
+use crate::hittable::Hittable;
+use crate::stats::{format_bytes, SceneStats};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryEstimate {
+    // The beauty film, a copy for post effects and the developed 8-bit image.
+    pub film_bytes: usize,
+    pub aov_bytes: usize,
+    // Geometry, materials and textures.
+    pub scene_bytes: usize,
+    // Acceleration structures that report their shape.
+    pub bvh_bytes: usize,
+}
+
+impl MemoryEstimate {
+    pub fn new(world: &dyn Hittable, width: u32, height: u32, aovs: &[Aov]) -> Self {
+        let pixels = width as usize * height as usize;
+        let stats = SceneStats::new(world);
+        Self {
+            film_bytes: pixels * (2 * size_of::<Pixel>() + 4),
+            aov_bytes: AovSet::estimate_bytes(aovs, world, width, height),
+            scene_bytes: stats.memory_bytes(),
+            // A box and two child or primitive indices per node.
+            bvh_bytes: stats.bvh.map_or(0, |b| b.nodes * (size_of::<Aabb>() + 8)),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.film_bytes + self.aov_bytes + self.scene_bytes + self.bvh_bytes
+    }
+
+    // An error naming the largest part if the render needs more than `budget` bytes.
+    pub fn check(&self, budget: usize) -> Result<()> {
+        if self.total() <= budget {
+            return Ok(());
+        }
+        let parts = [
+            ("films", self.film_bytes),
+            ("AOVs", self.aov_bytes),
+            ("the scene", self.scene_bytes),
+            ("the BVH", self.bvh_bytes),
+        ];
+        let (largest, bytes) = parts
+            .into_iter()
+            .max_by_key(|&(_, b)| b)
+            .unwrap_or_default();
+        Err(Error::Memory(format!(
+            "the render needs about {} but the budget is {}; {largest} take {}",
+            format_bytes(self.total()),
+            format_bytes(budget),
+            format_bytes(bytes)
+        )))
+    }
+}
+
+impl fmt::Display for MemoryEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} (films {}, AOVs {}, scene {}, BVH {})",
+            format_bytes(self.total()),
+            format_bytes(self.film_bytes),
+            format_bytes(self.aov_bytes),
+            format_bytes(self.scene_bytes),
+            format_bytes(self.bvh_bytes)
+        )
+    }
+}
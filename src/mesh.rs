@@ -0,0 +1,695 @@
+// Indexed triangle meshes, traced through a BVH over their triangles, with quadric-error
+// decimation for previewing heavy geometry and a level-of-detail heuristic based on how large a
+// mesh appears on screen. Paged meshes use the same BVH over and within their chunks.
+
+use crate::aabb::Aabb;
+use crate::error::{Error, Result};
+use crate::hittable::{face_normal, HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::{MaterialId, MaterialRef};
+use crate::ray::Ray;
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::stats::SceneStats;
+use crate::vec3::{Point3, Vec3};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+// Vertex positions and counter-clockwise (seen from outside) triangles indexing them.
+pub type Geometry = (Vec<Point3>, Vec<[u32; 3]>);
+
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub indices: Vec<[u32; 3]>,
+    // Texture coordinates per position; empty if the mesh has none.
+    pub uvs: Vec<[f64; 2]>,
+    pub material: MaterialRef,
+    bbox: Aabb,
+    // Over the triangles, stored as indices into `indices` in leaf order.
+    bvh: Bvh,
+    order: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        indices: Vec<[u32; 3]>,
+        material: impl Into<MaterialRef>,
+    ) -> Result<Self> {
+        if let Some(&i) = indices
+            .iter()
+            .flatten()
+            .find(|&&i| i as usize >= positions.len())
+        {
+            return Err(Error::Scene(format!(
+                "mesh index {i} out of range ({} vertices)",
+                positions.len()
+            )));
+        }
+        let bbox = bounds(&positions).unwrap_or_default();
+        let triangle_bounds: Vec<Aabb> = indices
+            .iter()
+            .map(|tri| triangle_bounds(&tri.map(|i| positions[i as usize])))
+            .collect();
+        let (bvh, order) = Bvh::build(&triangle_bounds, LEAF_TRIANGLES);
+        Ok(Self {
+            positions,
+            indices,
+            uvs: Vec::new(),
+            material: material.into(),
+            bbox,
+            bvh,
+            order,
+        })
+    }
+
+    // Texture coordinates, one per position, for `HitRecord::u` and `v`.
+    pub fn with_uvs(mut self, uvs: Vec<[f64; 2]>) -> Result<Self> {
+        if uvs.len() != self.positions.len() {
+            return Err(Error::Scene(format!(
+                "{} texture coordinates for {} vertices",
+                uvs.len(),
+                self.positions.len()
+            )));
+        }
+        self.uvs = uvs;
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    #[inline]
+    pub(crate) fn vertices(&self, tri: [u32; 3]) -> [Point3; 3] {
+        tri.map(|i| self.positions[i as usize])
+    }
+
+    // The triangles in a BVH leaf's `range`.
+    fn leaf(&self, range: Range<usize>) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.order[range].iter().map(|&i| self.indices[i as usize])
+    }
+
+    // The hit at `t` on triangle `tri`, at barycentrics `bary` of its second and third
+    // vertices.
+    pub(crate) fn record(
+        &self,
+        r: &Ray,
+        t: f64,
+        outward_normal: Vec3,
+        tri: [u32; 3],
+        [b1, b2]: [f64; 2],
+    ) -> HitRecord {
+        let (front_face, normal) = face_normal(r, outward_normal);
+        let [u, v] = if self.uvs.is_empty() {
+            [b1, b2]
+        } else {
+            let [a, b, c] = tri.map(|i| self.uvs[i as usize]);
+            let b0 = 1.0 - b1 - b2;
+            [0, 1].map(|k| b0 * a[k] + b1 * b[k] + b2 * c[k])
+        };
+        HitRecord {
+            t,
+            point: r.at(t),
+            normal,
+            front_face,
+            material: self.material.id(),
+            object_id: 0,
+            holdout: false,
+            u,
+            v,
+        }
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut closest: Option<([u32; 3], TriangleHit)> = None;
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            let mut found = None;
+            for tri in self.leaf(range) {
+                let t_max = found.map_or(ray_t.max, |(_, (t, _, _))| t);
+                if let Some(hit) = hit_triangle(self.vertices(tri), r, ray_t.with_max(t_max)) {
+                    found = Some((tri, hit));
+                }
+            }
+            if found.is_some() {
+                closest = found;
+            }
+            found.map(|(_, (t, _, _))| t)
+        });
+        let (tri, (t, outward_normal, bary)) = closest?;
+        Some(self.record(r, t, outward_normal, tri, bary))
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        let mut occluded = false;
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            occluded = self
+                .leaf(range)
+                .any(|tri| hit_triangle(self.vertices(tri), r, ray_t).is_some());
+            // An empty interval ends the traversal.
+            occluded.then_some(f64::NEG_INFINITY)
+        });
+        occluded
+    }
+
+    // One traversal, rather than one per hit.
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            for tri in self.leaf(range) {
+                if let Some((t, n, bary)) = hit_triangle(self.vertices(tri), r, ray_t) {
+                    out.push(self.record(r, t, n, tri, bary));
+                }
+            }
+            None
+        });
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.add_primitive(
+            "Mesh",
+            std::mem::size_of_val(self)
+                + self.positions.capacity() * std::mem::size_of::<Point3>()
+                + self.indices.capacity() * std::mem::size_of::<[u32; 3]>()
+                + self.order.capacity() * std::mem::size_of::<u32>()
+                + self.bvh.bytes(),
+        );
+        stats.triangles += self.triangle_count();
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::Mesh {
+            positions: self.positions.clone(),
+            indices: self.indices.clone(),
+            uvs: self.uvs.clone(),
+            obj: None,
+            material: materials.index(self.material.id())?,
+            target_triangles: None,
+            lod: false,
+        })
+    }
+}
+
+// Distance, unit normal given by the winding order, and barycentrics of the second and third
+// vertices.
+pub(crate) type TriangleHit = (f64, Vec3, [f64; 2]);
+
+// Möller-Trumbore.
+pub(crate) fn hit_triangle(
+    [a, b, c]: [Point3; 3],
+    r: &Ray,
+    ray_t: Interval,
+) -> Option<TriangleHit> {
+    let e1 = b - a;
+    let e2 = c - a;
+    let p = Vec3::cross(r.direction(), e2);
+    let det = Vec3::dot(e1, p);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = r.origin() - a;
+    let u = Vec3::dot(s, p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = Vec3::cross(s, e1);
+    let v = Vec3::dot(r.direction(), q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = Vec3::dot(e2, q) * inv_det;
+    if !ray_t.surrounds(t) {
+        return None;
+    }
+    Some((t, Vec3::unit_vector(Vec3::cross(e1, e2)), [u, v]))
+}
+
+// Triangles per leaf of a mesh's BVH.
+pub(crate) const LEAF_TRIANGLES: usize = 4;
+
+// Padded so triangles lying in an axis plane still have some thickness.
+pub(crate) fn triangle_bounds(tri: &[Point3; 3]) -> Aabb {
+    Aabb::from_points(tri[0], tri[1]).expand(tri[2]).pad(1e-4)
+}
+
+#[inline]
+fn coordinate(p: Point3, axis: usize) -> f64 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Node {
+    Leaf { start: u32, count: u32 },
+    // The left child follows its parent; the right one is at `right`.
+    Inner { right: u32 },
+}
+
+// Median splits over the centroids of the items, stored reordered so every leaf is a range.
+pub(crate) struct Bvh {
+    nodes: Vec<(Aabb, Node)>,
+}
+
+impl Bvh {
+    // Builds over `bounds`, returning the tree and the order items are stored in.
+    pub(crate) fn build(bounds: &[Aabb], leaf_size: usize) -> (Self, Vec<u32>) {
+        let mut order: Vec<u32> = (0..bounds.len() as u32).collect();
+        let mut bvh = Bvh { nodes: Vec::new() };
+        if !bounds.is_empty() {
+            bvh.split(bounds, &mut order, 0, leaf_size);
+        }
+        (bvh, order)
+    }
+
+    fn split(&mut self, bounds: &[Aabb], order: &mut [u32], start: usize, leaf_size: usize) {
+        let node_bounds = order.iter().fold(Aabb::EMPTY, |b, &i| {
+            Aabb::surrounding_box(b, bounds[i as usize])
+        });
+        let index = self.nodes.len();
+        if order.len() <= leaf_size {
+            self.nodes.push((
+                node_bounds,
+                Node::Leaf {
+                    start: start as u32,
+                    count: order.len() as u32,
+                },
+            ));
+            return;
+        }
+        let centroids = order
+            .iter()
+            .fold(Aabb::EMPTY, |b, &i| b.expand(bounds[i as usize].centroid()));
+        let axis = centroids.longest_axis();
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |&a, &b| {
+            let ca = coordinate(bounds[a as usize].centroid(), axis);
+            let cb = coordinate(bounds[b as usize].centroid(), axis);
+            ca.total_cmp(&cb)
+        });
+        self.nodes.push((node_bounds, Node::Inner { right: 0 }));
+        let (left, right) = order.split_at_mut(mid);
+        self.split(bounds, left, start, leaf_size);
+        let right_index = self.nodes.len() as u32;
+        self.split(bounds, right, start + mid, leaf_size);
+        self.nodes[index].1 = Node::Inner { right: right_index };
+    }
+
+    // Calls `visit` with each leaf's item range the ray may reach, in the order the ray enters
+    // their bounds; it returns the distance of a hit found there, which then bounds the rest of
+    // the search. Children are ordered by where the ray enters them rather than by the split
+    // axis, which is wrong where siblings' bounds overlap.
+    pub(crate) fn traverse(
+        &self,
+        r: &Ray,
+        mut ray_t: Interval,
+        mut visit: impl FnMut(Range<usize>, Interval) -> Option<f64>,
+    ) -> Option<f64> {
+        let entry = |index: u32, ray_t: Interval| {
+            let (bounds, _) = self.nodes.get(index as usize)?;
+            Some((bounds.entry(r, ray_t)?, index))
+        };
+        let mut closest = None;
+        let mut stack: Vec<(f64, u32)> = entry(0, ray_t).into_iter().collect();
+        while let Some((t_enter, index)) = stack.pop() {
+            // A hit found since this node was pushed may be nearer than the node itself.
+            if t_enter > ray_t.max {
+                continue;
+            }
+            match self.nodes[index as usize].1 {
+                Node::Leaf { start, count } => {
+                    let range = start as usize..(start + count) as usize;
+                    if let Some(t) = visit(range, ray_t) {
+                        closest = Some(t);
+                        ray_t = ray_t.with_max(t);
+                    }
+                }
+                Node::Inner { right } => {
+                    let (near, far) = match (entry(index + 1, ray_t), entry(right, ray_t)) {
+                        (Some(a), Some(b)) if b.0 < a.0 => (Some(b), Some(a)),
+                        (a, b) => (a, b),
+                    };
+                    // The far child goes on the stack first, so the near one is searched first.
+                    stack.extend(far.into_iter().chain(near));
+                }
+            }
+        }
+        closest
+    }
+
+    // Each leaf's bounds and item range, in storage order.
+    pub(crate) fn leaves(&self) -> impl Iterator<Item = (Aabb, Range<usize>)> + '_ {
+        self.nodes.iter().filter_map(|&(bounds, node)| match node {
+            Node::Leaf { start, count } => Some((bounds, start as usize..(start + count) as usize)),
+            Node::Inner { .. } => None,
+        })
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<(Aabb, Node)>()
+    }
+}
+
+// Box around `positions`, padded so flat meshes still have some thickness.
+pub fn bounds(positions: &[Point3]) -> Option<Aabb> {
+    if positions.is_empty() {
+        return None;
+    }
+    Some(
+        positions
+            .iter()
+            .fold(Aabb::EMPTY, |b, &p| b.expand(p))
+            .pad(1e-4),
+    )
+}
+
+// Reads vertices and faces from a Wavefront OBJ file. Polygons are fan-triangulated; normals,
+// texture coordinates, groups and materials are ignored.
+pub fn load_obj(path: &Path) -> Result<Geometry> {
+    let text = std::fs::read_to_string(path)?;
+    let bad =
+        |line: usize, what: &str| Error::Scene(format!("{}:{}: {what}", path.display(), line + 1));
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("v") => {
+                let xyz: Vec<f64> = fields
+                    .take(3)
+                    .map(str::parse)
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(|_| bad(n, "invalid vertex"))?;
+                let [x, y, z] = xyz[..] else {
+                    return Err(bad(n, "vertex needs three coordinates"));
+                };
+                positions.push(Point3::new(x, y, z));
+            }
+            Some("f") => {
+                // Indices are 1-based, negative ones count back from the latest vertex, and
+                // anything after a slash is a texture or normal index.
+                let face = fields
+                    .map(|f| {
+                        let i: i64 = f.split('/').next()?.parse().ok()?;
+                        let i = if i < 0 {
+                            positions.len() as i64 + i
+                        } else {
+                            i - 1
+                        };
+                        u32::try_from(i).ok()
+                    })
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(|| bad(n, "invalid face"))?;
+                if face.len() < 3 {
+                    return Err(bad(n, "face needs at least three vertices"));
+                }
+                for k in 1..face.len() - 1 {
+                    indices.push([face[0], face[k], face[k + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((positions, indices))
+}
+
+// Symmetric 4x4 error quadric, upper triangle stored row by row.
+#[derive(Copy, Clone, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    // Squared distance to the plane through `p` with unit normal `n`.
+    fn plane(n: Vec3, p: Point3) -> Self {
+        let (a, b, c) = (n.x, n.y, n.z);
+        let d = -Vec3::dot(n, p);
+        Quadric([
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ])
+    }
+
+    fn add(self, o: Quadric) -> Quadric {
+        let mut q = self;
+        for (x, y) in q.0.iter_mut().zip(o.0) {
+            *x += y;
+        }
+        q
+    }
+
+    fn error(&self, v: Point3) -> f64 {
+        let [a, b, c, d, e, f, g, h, i, j] = self.0;
+        let (x, y, z) = (v.x, v.y, v.z);
+        a * x * x
+            + 2.0 * b * x * y
+            + 2.0 * c * x * z
+            + 2.0 * d * x
+            + e * y * y
+            + 2.0 * f * y * z
+            + 2.0 * g * y
+            + h * z * z
+            + 2.0 * i * z
+            + j
+    }
+
+    // Position minimizing the error, if the quadric isn't degenerate.
+    fn optimum(&self) -> Option<Point3> {
+        let [a, b, c, d, e, f, g, h, i, _] = self.0;
+        let det = a * (e * h - f * f) - b * (b * h - f * c) + c * (b * f - e * c);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        // Cramer's rule on the gradient = 0 system.
+        let x = -(d * (e * h - f * f) - b * (g * h - f * i) + c * (g * f - e * i)) / det;
+        let y = -(a * (g * h - i * f) - d * (b * h - f * c) + c * (b * i - g * c)) / det;
+        let z = -(a * (e * i - f * g) - b * (b * i - g * c) + d * (b * f - e * c)) / det;
+        Some(Point3::new(x, y, z))
+    }
+}
+
+struct Collapse {
+    cost: f64,
+    a: usize,
+    b: usize,
+    target: Point3,
+    // Vertex generations when this was queued; stale entries are skipped.
+    stamps: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed so the max-heap pops the cheapest collapse first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+// Garland-Heckbert edge-collapse decimation down to about `target` triangles. Collapses that
+// would flip a neighbouring triangle are rejected, so the result may stay above `target`.
+pub fn simplify(positions: &[Point3], indices: &[[u32; 3]], target: usize) -> Geometry {
+    if indices.len() <= target {
+        return (positions.to_vec(), indices.to_vec());
+    }
+
+    let mut pos = positions.to_vec();
+    let mut tris: Vec<[usize; 3]> = indices.iter().map(|t| t.map(|i| i as usize)).collect();
+    let mut alive = vec![true; tris.len()];
+    let mut quadrics = vec![Quadric::default(); pos.len()];
+    let mut vertex_tris: Vec<Vec<usize>> = vec![Vec::new(); pos.len()];
+    let mut removed = vec![false; pos.len()];
+    let mut stamps = vec![0u32; pos.len()];
+
+    for (t, tri) in tris.iter().enumerate() {
+        let [a, b, c] = tri.map(|i| pos[i]);
+        let n = Vec3::cross(b - a, c - a);
+        let len = n.length();
+        if len > 0.0 {
+            // Weighting by area keeps large faces from being eroded by tiny ones.
+            let q = Quadric::plane(n / len, a);
+            let q = Quadric(q.0.map(|x| x * len * 0.5));
+            for &v in tri {
+                quadrics[v] = quadrics[v].add(q);
+            }
+        }
+        for &v in tri {
+            vertex_tris[v].push(t);
+        }
+    }
+
+    let plan = |pos: &[Point3], quadrics: &[Quadric], stamps: &[u32], a: usize, b: usize| {
+        let q = quadrics[a].add(quadrics[b]);
+        let mid = 0.5 * (pos[a] + pos[b]);
+        let target = q
+            .optimum()
+            .filter(|p| (*p - mid).length() <= 2.0 * (pos[a] - pos[b]).length())
+            .unwrap_or_else(|| {
+                [pos[a], pos[b], mid]
+                    .into_iter()
+                    .min_by(|x, y| q.error(*x).total_cmp(&q.error(*y)))
+                    .unwrap_or(mid)
+            });
+        Collapse {
+            cost: q.error(target),
+            a,
+            b,
+            target,
+            stamps: (stamps[a], stamps[b]),
+        }
+    };
+
+    let mut heap = BinaryHeap::new();
+    let mut edges = HashSet::new();
+    for tri in &tris {
+        for k in 0..3 {
+            let (a, b) = (tri[k], tri[(k + 1) % 3]);
+            if edges.insert((a.min(b), a.max(b))) {
+                heap.push(plan(&pos, &quadrics, &stamps, a, b));
+            }
+        }
+    }
+
+    let mut live = tris.len();
+    while live > target {
+        let Some(c) = heap.pop() else { break };
+        let (a, b) = (c.a, c.b);
+        if removed[a] || removed[b] || (stamps[a], stamps[b]) != c.stamps {
+            continue;
+        }
+
+        // Triangles that lose an edge, and those that merely move.
+        let shared: Vec<usize> = vertex_tris[a]
+            .iter()
+            .copied()
+            .filter(|&t| alive[t] && tris[t].contains(&b))
+            .collect();
+        let flips = vertex_tris[a]
+            .iter()
+            .chain(&vertex_tris[b])
+            .filter(|&&t| alive[t] && !shared.contains(&t))
+            .any(|&t| {
+                let before = tri_normal(&pos, tris[t]);
+                let moved = tris[t].map(|v| if v == a || v == b { c.target } else { pos[v] });
+                let after = Vec3::cross(moved[1] - moved[0], moved[2] - moved[0]);
+                Vec3::dot(before, after) <= 0.0
+            });
+        if flips {
+            continue;
+        }
+
+        pos[a] = c.target;
+        quadrics[a] = quadrics[a].add(quadrics[b]);
+        removed[b] = true;
+        stamps[a] += 1;
+        for t in shared {
+            alive[t] = false;
+            live -= 1;
+        }
+        let moved = std::mem::take(&mut vertex_tris[b]);
+        for &t in &moved {
+            for v in tris[t].iter_mut() {
+                if *v == b {
+                    *v = a;
+                }
+            }
+        }
+        vertex_tris[a].extend(moved);
+        vertex_tris[a].retain(|&t| alive[t]);
+        vertex_tris[a].sort_unstable();
+        vertex_tris[a].dedup();
+
+        let neighbours: HashSet<usize> = vertex_tris[a]
+            .iter()
+            .flat_map(|&t| tris[t])
+            .filter(|&v| v != a)
+            .collect();
+        for n in neighbours {
+            heap.push(plan(&pos, &quadrics, &stamps, a, n));
+        }
+    }
+
+    // Compact the surviving vertices.
+    let mut remap = vec![u32::MAX; pos.len()];
+    let mut out_pos = Vec::new();
+    let mut out_tris = Vec::with_capacity(live);
+    for (t, tri) in tris.iter().enumerate() {
+        if !alive[t] {
+            continue;
+        }
+        out_tris.push(tri.map(|v| {
+            if remap[v] == u32::MAX {
+                remap[v] = out_pos.len() as u32;
+                out_pos.push(pos[v]);
+            }
+            remap[v]
+        }));
+    }
+    (out_pos, out_tris)
+}
+
+#[inline]
+fn tri_normal(pos: &[Point3], [a, b, c]: [usize; 3]) -> Vec3 {
+    Vec3::cross(pos[b] - pos[a], pos[c] - pos[a])
+}
+
+// What the camera sees, for picking mesh detail from projected size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LodView {
+    pub eye: Point3,
+    pub vfov: f64,
+    pub image_height: u32,
+}
+
+impl LodView {
+    // Approximate on-screen diameter of `bounds`, in pixels.
+    pub fn projected_pixels(&self, bounds: Aabb) -> f64 {
+        let distance = (bounds.centroid() - self.eye).length();
+        let radius = 0.5 * bounds.diagonal();
+        if distance <= radius {
+            return f64::INFINITY;
+        }
+        let half_height = (self.vfov.to_radians() / 2.0).tan();
+        (radius / distance) / half_height * self.image_height as f64
+    }
+
+    // Triangle budget for a mesh covering `bounds`: about one triangle per two pixels of
+    // projected area, so detail finer than a pixel isn't kept.
+    pub fn triangles(&self, bounds: Aabb) -> usize {
+        let d = self.projected_pixels(bounds);
+        let area = std::f64::consts::FRAC_PI_4 * d * d;
+        (area / 2.0).max(1.0).min(usize::MAX as f64) as usize
+    }
+}
@@ -0,0 +1,471 @@
+// Out-of-core meshes, for scenes too big to hold in memory. `write` splits a mesh into
+// spatially compact chunks of triangles and saves them to a geometry file; a `PagedMesh` keeps
+// only the chunks' bounds in memory, loads a chunk when a ray first reaches it, and builds a
+// small BVH over its triangles then. Loaded chunks share one `GeometryCache`, which drops the
+// least recently used once its memory budget is exceeded, like the texture cache. Rendering
+// from a cache that can't hold the working set is slow, since chunks are read again and again,
+// but it finishes.
+
+use crate::aabb::Aabb;
+use crate::error::{Error, Result};
+use crate::hittable::{face_normal, HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::{MaterialId, MaterialRef};
+use crate::mesh::{hit_triangle, triangle_bounds, Bvh, TriangleHit, LEAF_TRIANGLES};
+use crate::ray::Ray;
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::stats::SceneStats;
+use crate::texture::CacheStats;
+use crate::vec3::{Point3, Vec3};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::{debug, warn};
+
+const MAGIC: &[u8; 8] = b"RTTGEOM1";
+// Triangles per chunk `write` aims for by default.
+pub const CHUNK_TRIANGLES: usize = 4096;
+// Budget of the shared cache until `set_budget` says otherwise.
+pub const DEFAULT_BUDGET: usize = 1 << 30;
+const TRIANGLE_BYTES: u64 = 9 * 8;
+
+type Triangle = [Point3; 3];
+
+// Saves `indices` over `positions` to `path` in chunks of about `chunk_triangles`, as a
+// `PagedMesh` reads them.
+pub fn write(
+    positions: &[Point3],
+    indices: &[[u32; 3]],
+    path: &Path,
+    chunk_triangles: usize,
+) -> Result<()> {
+    let mut triangles = Vec::with_capacity(indices.len());
+    for tri in indices {
+        let mut vertices = [Point3::default(); 3];
+        for (v, &i) in vertices.iter_mut().zip(tri) {
+            *v = *positions.get(i as usize).ok_or_else(|| {
+                Error::Scene(format!(
+                    "mesh index {i} out of range ({} vertices)",
+                    positions.len()
+                ))
+            })?;
+        }
+        triangles.push(vertices);
+    }
+    let bounds: Vec<Aabb> = triangles.iter().map(triangle_bounds).collect();
+    let (chunks, order) = Bvh::build(&bounds, chunk_triangles.max(1));
+    let leaves: Vec<(Aabb, Range<usize>)> = chunks.leaves().collect();
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&(leaves.len() as u64).to_le_bytes())?;
+    let header = MAGIC.len() as u64 + 8 + leaves.len() as u64 * (6 * 8 + 2 * 8);
+    let mut offset = header;
+    for (b, range) in &leaves {
+        for v in [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z] {
+            out.write_all(&v.to_le_bytes())?;
+        }
+        out.write_all(&offset.to_le_bytes())?;
+        out.write_all(&(range.len() as u64).to_le_bytes())?;
+        offset += range.len() as u64 * TRIANGLE_BYTES;
+    }
+    for (_, range) in leaves {
+        for &i in &order[range] {
+            for p in triangles[i as usize] {
+                for v in [p.x, p.y, p.z] {
+                    out.write_all(&v.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn read_f64(r: &mut impl Read) -> Result<f64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn read_u64(r: &mut impl Read) -> Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// Where a chunk's triangles are in the file.
+struct ChunkInfo {
+    bounds: Aabb,
+    offset: u64,
+    triangles: usize,
+}
+
+// A resident chunk: its triangles in BVH order and the BVH over them.
+struct Chunk {
+    triangles: Vec<Triangle>,
+    bvh: Bvh,
+}
+
+impl Chunk {
+    fn read(path: &Path, info: &ChunkInfo) -> Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        file.seek(SeekFrom::Start(info.offset))?;
+        let mut triangles = Vec::with_capacity(info.triangles);
+        for _ in 0..info.triangles {
+            let mut tri = [Point3::default(); 3];
+            for p in &mut tri {
+                *p = Point3::new(
+                    read_f64(&mut file)?,
+                    read_f64(&mut file)?,
+                    read_f64(&mut file)?,
+                );
+            }
+            triangles.push(tri);
+        }
+        let bounds: Vec<Aabb> = triangles.iter().map(triangle_bounds).collect();
+        let (bvh, order) = Bvh::build(&bounds, LEAF_TRIANGLES);
+        let triangles = order.iter().map(|&i| triangles[i as usize]).collect();
+        Ok(Self { triangles, bvh })
+    }
+
+    fn bytes(&self) -> usize {
+        self.triangles.capacity() * std::mem::size_of::<Triangle>() + self.bvh.bytes()
+    }
+
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<TriangleHit> {
+        let mut closest = None;
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            let mut found = None;
+            for tri in &self.triangles[range] {
+                let t_max = found.map_or(ray_t.max, |(t, _, _)| t);
+                if let Some(hit) = hit_triangle(*tri, r, ray_t.with_max(t_max)) {
+                    found = Some(hit);
+                }
+            }
+            if found.is_some() {
+                closest = found;
+            }
+            found.map(|(t, _, _)| t)
+        });
+        closest
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        let mut occluded = false;
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            occluded = self.triangles[range]
+                .iter()
+                .any(|tri| hit_triangle(*tri, r, ray_t).is_some());
+            // An empty interval ends the traversal.
+            occluded.then_some(f64::NEG_INFINITY)
+        });
+        occluded
+    }
+
+    // Every triangle hit within `ray_t`; the traversal never narrows the interval.
+    fn hit_all(&self, r: &Ray, ray_t: Interval, hits: &mut Vec<TriangleHit>) {
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            hits.extend(
+                self.triangles[range]
+                    .iter()
+                    .filter_map(|tri| hit_triangle(*tri, r, ray_t)),
+            );
+            None
+        });
+    }
+}
+
+struct Entry {
+    chunk: Option<Arc<Chunk>>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<(PathBuf, usize), Entry>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+// Chunks of paged meshes resident in memory, shared between meshes.
+pub struct GeometryCache {
+    budget: AtomicUsize,
+    state: Mutex<CacheState>,
+}
+
+impl GeometryCache {
+    // `budget` is in bytes of triangles and BVH nodes. A chunk larger than the budget is
+    // still loaded, it just evicts everything else.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget: AtomicUsize::new(budget),
+            state: Mutex::default(),
+        }
+    }
+
+    // The cache scene files' paged meshes load into.
+    pub fn shared() -> Arc<GeometryCache> {
+        static SHARED: OnceLock<Arc<GeometryCache>> = OnceLock::new();
+        Arc::clone(SHARED.get_or_init(|| Arc::new(GeometryCache::new(DEFAULT_BUDGET))))
+    }
+
+    #[inline]
+    pub fn budget(&self) -> usize {
+        self.budget.load(Ordering::Relaxed)
+    }
+
+    // Takes effect as chunks are next loaded.
+    pub fn set_budget(&self, budget: usize) {
+        self.budget.store(budget, Ordering::Relaxed);
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, CacheState>> {
+        self.state
+            .lock()
+            .map_err(|_| Error::Poisoned("geometry cache"))
+    }
+
+    fn get(&self, path: &Path, index: usize, info: &ChunkInfo) -> Result<Arc<Chunk>> {
+        let key = (path.to_path_buf(), index);
+        {
+            let mut state = self.lock()?;
+            state.clock += 1;
+            let now = state.clock;
+            if let Some(chunk) = state.entries.get_mut(&key).and_then(|e| {
+                e.last_used = now;
+                e.chunk.clone()
+            }) {
+                state.stats.hits += 1;
+                return Ok(chunk);
+            }
+        }
+
+        // Read without holding the lock so resident chunks stay available meanwhile.
+        let chunk = Arc::new(Chunk::read(path, info)?);
+        let size = chunk.bytes();
+
+        let mut state = self.lock()?;
+        let now = state.clock;
+        // Another thread may have loaded it in the meantime; keep theirs.
+        if let Some(existing) = state.entries.get(&key).and_then(|e| e.chunk.clone()) {
+            return Ok(existing);
+        }
+        state.entries.insert(
+            key.clone(),
+            Entry {
+                chunk: Some(Arc::clone(&chunk)),
+                last_used: now,
+            },
+        );
+        state.stats.loads += 1;
+        state.stats.bytes += size;
+        debug!(path = %path.display(), chunk = index, bytes = size, "loaded geometry chunk");
+        self.evict(&mut state, &key);
+        Ok(chunk)
+    }
+
+    // Drops least recently used chunks, except `keep`, until within budget.
+    fn evict(&self, state: &mut CacheState, keep: &(PathBuf, usize)) {
+        let budget = self.budget();
+        while state.stats.bytes > budget {
+            let victim = state
+                .entries
+                .iter()
+                .filter(|(k, e)| e.chunk.is_some() && *k != keep)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            let Some(victim) = victim else { break };
+            if let Some(chunk) = state.entries.get_mut(&victim).and_then(|e| e.chunk.take()) {
+                state.stats.bytes -= chunk.bytes();
+                state.stats.evictions += 1;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        Ok(self.lock()?.stats)
+    }
+}
+
+// A mesh read from a file saved by `write`, a chunk at a time as rays reach it.
+pub struct PagedMesh {
+    path: PathBuf,
+    chunks: Vec<ChunkInfo>,
+    bvh: Bvh,
+    bbox: Aabb,
+    material: MaterialRef,
+    cache: Arc<GeometryCache>,
+}
+
+impl PagedMesh {
+    // Reads only the chunk table; triangles are loaded on demand into `cache`.
+    pub fn open(
+        path: impl AsRef<Path>,
+        material: impl Into<MaterialRef>,
+        cache: Arc<GeometryCache>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut file = BufReader::new(File::open(&path)?);
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::Scene(format!(
+                "{} is not a paged geometry file",
+                path.display()
+            )));
+        }
+        let count = read_u64(&mut file)?;
+        let mut chunks = Vec::new();
+        for _ in 0..count {
+            let mut v = [0.0; 6];
+            for x in &mut v {
+                *x = read_f64(&mut file)?;
+            }
+            chunks.push(ChunkInfo {
+                bounds: Aabb::new(Point3::new(v[0], v[1], v[2]), Point3::new(v[3], v[4], v[5])),
+                offset: read_u64(&mut file)?,
+                triangles: read_u64(&mut file)? as usize,
+            });
+        }
+        let bounds: Vec<Aabb> = chunks.iter().map(|c| c.bounds).collect();
+        let (bvh, order) = Bvh::build(&bounds, 1);
+        let mut chunks: Vec<Option<ChunkInfo>> = chunks.into_iter().map(Some).collect();
+        let chunks: Vec<ChunkInfo> = order
+            .iter()
+            .filter_map(|&i| chunks[i as usize].take())
+            .collect();
+        let bbox = bounds
+            .iter()
+            .fold(Aabb::EMPTY, |b, &c| Aabb::surrounding_box(b, c));
+        Ok(Self {
+            path,
+            chunks,
+            bvh,
+            bbox,
+            material: material.into(),
+            cache,
+        })
+    }
+
+    #[inline]
+    pub fn triangle_count(&self) -> usize {
+        self.chunks.iter().map(|c| c.triangles).sum()
+    }
+
+    #[inline]
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    // The chunk's triangles, loading them if they aren't resident.
+    fn chunk(&self, index: usize) -> Option<Arc<Chunk>> {
+        match self.cache.get(&self.path, index, &self.chunks[index]) {
+            Ok(chunk) => Some(chunk),
+            Err(e) => {
+                // Shows as a hole rather than aborting the render.
+                warn!(path = %self.path.display(), chunk = index, "geometry unavailable: {e}");
+                None
+            }
+        }
+    }
+
+    // Chunks keep no texture coordinates, so hits report barycentrics.
+    fn record(&self, r: &Ray, t: f64, outward_normal: Vec3, [u, v]: [f64; 2]) -> HitRecord {
+        let (front_face, normal) = face_normal(r, outward_normal);
+        HitRecord {
+            t,
+            point: r.at(t),
+            normal,
+            front_face,
+            material: self.material.id(),
+            object_id: 0,
+            holdout: false,
+            u,
+            v,
+        }
+    }
+}
+
+impl Hittable for PagedMesh {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let mut closest = None;
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            let mut found = None;
+            for index in range {
+                let Some(chunk) = self.chunk(index) else {
+                    continue;
+                };
+                let t_max = found.map_or(ray_t.max, |(t, _, _)| t);
+                if let Some(hit) = chunk.hit(r, ray_t.with_max(t_max)) {
+                    found = Some(hit);
+                }
+            }
+            if found.is_some() {
+                closest = found;
+            }
+            found.map(|(t, _, _)| t)
+        });
+        let (t, outward_normal, bary) = closest?;
+        Some(self.record(r, t, outward_normal, bary))
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        let mut occluded = false;
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            occluded = range
+                .filter_map(|index| self.chunk(index))
+                .any(|chunk| chunk.is_occluded(r, ray_t));
+            // An empty interval ends the traversal.
+            occluded.then_some(f64::NEG_INFINITY)
+        });
+        occluded
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        let mut hits = Vec::new();
+        self.bvh.traverse(r, ray_t, |range, ray_t| {
+            for index in range {
+                if let Some(chunk) = self.chunk(index) {
+                    chunk.hit_all(r, ray_t, &mut hits);
+                }
+            }
+            None
+        });
+        out.extend(
+            hits.into_iter()
+                .map(|(t, n, bary)| self.record(r, t, n, bary)),
+        );
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        (!self.chunks.is_empty()).then_some(self.bbox)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    // Counts what stays resident: the chunk table, not the triangles.
+    fn stats(&self, stats: &mut SceneStats) {
+        stats.add_primitive(
+            "PagedMesh",
+            std::mem::size_of_val(self)
+                + self.chunks.capacity() * std::mem::size_of::<ChunkInfo>()
+                + self.bvh.bytes(),
+        );
+        stats.triangles += self.triangle_count();
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        Some(ObjectDesc::PagedMesh {
+            path: self.path.clone(),
+            material: materials.index(self.material.id())?,
+        })
+    }
+}
@@ -0,0 +1,135 @@
+// Path dumps for debugging: every vertex of the paths traced through chosen pixels, with what
+// was hit, how the path continued and what it gathered there, written as JSON to inspect or as
+// OBJ polylines to load next to the scene in a 3D viewer. Fireflies, light leaks and wrong
+// normals are much easier to pin down from the actual paths than from the pixels they end in.
+
+use crate::camera::Camera;
+use crate::error::{Error, Result};
+use crate::hittable::Hittable;
+use crate::interval::Interval;
+use crate::render::{lights, trace_path_recorded, RenderSettings, T_MIN};
+use crate::scene::MaterialDesc;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::Rng;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// How far past the last vertex an escaped path is drawn in OBJ dumps.
+const ESCAPE_LENGTH: f64 = 1.0;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PathVertex {
+    pub point: Point3,
+    // Faces the incoming ray.
+    pub normal: Vec3,
+    pub front_face: bool,
+    pub object_id: u32,
+    // None for materials that can't be described, and for holdouts.
+    pub material: Option<MaterialDesc>,
+    // Path throughput arriving here.
+    pub throughput: Color,
+    // Light emitted here and gathered by shadow rays from here, weighted by throughput and,
+    // for emission, by multiple importance sampling.
+    pub emitted: Color,
+    pub direct: Color,
+    // Where the path went next, with the material's attenuation and the density the direction
+    // was chosen with; None where it ended. The density is None for specular bounces.
+    pub scattered: Option<Vec3>,
+    pub attenuation: Option<Color>,
+    pub pdf: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RecordedPath {
+    // Film coordinates, with y down, and which of the pixel's samples this is.
+    pub pixel: [u32; 2],
+    pub sample: u32,
+    pub origin: Point3,
+    pub vertices: Vec<PathVertex>,
+    // Direction the path left the scene in, if it reached the background.
+    pub escaped: Option<Vec3>,
+    pub color: Color,
+}
+
+// `samples` paths through pixel (x, y) of a `width` by `height` film, jittered within the
+// pixel as a render would.
+pub fn record_pixel(
+    world: &dyn Hittable,
+    camera: &Camera,
+    (width, height): (u32, u32),
+    (x, y): (u32, u32),
+    samples: u32,
+    settings: &RenderSettings,
+    rng: &mut dyn rand::RngCore,
+) -> Result<Vec<RecordedPath>> {
+    if x >= width || y >= height {
+        return Err(Error::Scene(format!(
+            "pixel ({x}, {y}) is outside the {width}x{height} film"
+        )));
+    }
+    let lights = lights(world);
+    let full = Interval::new(T_MIN, f64::INFINITY);
+    let j = height - 1 - y;
+    Ok((0..samples)
+        .map(|sample| {
+            let u = (x as f64 + rng.random::<f64>()) / width as f64;
+            let v = (j as f64 + rng.random::<f64>()) / height as f64;
+            // A ray the lens blocks leaves an empty, black path.
+            let mut path = match camera.get_ray(u, v, rng) {
+                Some(ray) => {
+                    let ray_t = camera.clip_range(&ray, full);
+                    let traced = trace_path_recorded(ray, world, &lights, ray_t, settings, rng);
+                    let mut path = traced.path.unwrap_or_default();
+                    path.color = traced.color;
+                    path
+                }
+                None => RecordedPath::default(),
+            };
+            path.pixel = [x, y];
+            path.sample = sample;
+            path
+        })
+        .collect())
+}
+
+// Paths as pretty-printed JSON, or as OBJ polylines if `path` ends in `.obj`.
+pub fn save(paths: &[RecordedPath], path: &Path) -> Result<()> {
+    let is_obj = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("obj"));
+    let contents = if is_obj {
+        to_obj(paths)
+    } else {
+        serde_json::to_string_pretty(paths)?
+    };
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+// One named polyline per path from the camera through its vertices, ending a short way along
+// the escape direction if it reached the background.
+pub fn to_obj(paths: &[RecordedPath]) -> String {
+    let mut out = String::new();
+    let mut count = 0;
+    for path in paths {
+        let mut points = vec![path.origin];
+        points.extend(path.vertices.iter().map(|v| v.point));
+        if let Some(dir) = path.escaped {
+            let last = *points.last().expect("origin is always present");
+            points.push(last + ESCAPE_LENGTH * Vec3::unit_vector(dir));
+        }
+        let [x, y] = path.pixel;
+        let _ = writeln!(out, "o path_{x}_{y}_{}", path.sample);
+        for p in &points {
+            let _ = writeln!(out, "v {} {} {}", p.x, p.y, p.z);
+        }
+        out.push('l');
+        for i in 0..points.len() {
+            let _ = write!(out, " {}", count + i + 1);
+        }
+        out.push('\n');
+        count += points.len();
+    }
+    out
+}
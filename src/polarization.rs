@@ -0,0 +1,440 @@
+// Polarized light transport, opt in, for studying glare off glass, water and metal. Camera
+// paths carry a Mueller matrix per color channel through their run of mirror-like bounces,
+// from Fresnel's equations at each dielectric or metal surface, and end where the ordinary
+// path tracer takes over: light reaching the first rough surface, light or the sky is taken
+// as unpolarized. The Stokes vector that arrives is then that radiance times the first column
+// of the accumulated matrix. Intensity matches an ordinary render up to the second such
+// bounce; from there it differs as in reality, by how much the polarization one surface
+// leaves changes what the next reflects.
+//
+// Stokes vectors are in the camera's frame, S1 positive for light polarized along the image's
+// horizontal. Metals get complex indices from their albedo, after Gulbrandsen's "Artist
+// Friendly Metallic Fresnel" with the edge tint equal to it.
+
+use crate::camera::Camera;
+use crate::color::luminance;
+use crate::compare::heat_color;
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::light::Light;
+use crate::ray::Ray;
+use crate::render::{lights, sampler, trace_path_with, RenderSettings, T_MIN};
+use crate::scene::MaterialDesc;
+use crate::vec3::{Color, Vec3};
+use image::RgbImage;
+use rand::Rng;
+use rayon::prelude::*;
+use std::ops::{Add, Div, Mul, Sub};
+use std::path::Path;
+
+pub type Stokes = [f64; 4];
+
+// Acts on Stokes vectors; identity by default.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mueller(pub [[f64; 4]; 4]);
+
+impl Default for Mueller {
+    fn default() -> Self {
+        Self::scaled(1.0)
+    }
+}
+
+impl Mueller {
+    fn scaled(s: f64) -> Self {
+        Self(std::array::from_fn(|i| {
+            std::array::from_fn(|j| if i == j { s } else { 0.0 })
+        }))
+    }
+
+    // Turns the reference frame by `angle` radians about the direction of travel.
+    pub fn rotator(angle: f64) -> Self {
+        let (s, c) = (2.0 * angle).sin_cos();
+        Self([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, c, s, 0.0],
+            [0.0, -s, c, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Reflection at `cos_i` off a surface of relative index `eta`, real for dielectrics; in
+    // the frame with x perpendicular to the plane of incidence.
+    pub fn dielectric_reflection(cos_i: f64, eta: f64) -> Self {
+        let (rs, rp) = amplitudes(cos_i, Complex::new(eta, 0.0)).0;
+        Self::from_amplitudes(rs, rp, 1.0)
+    }
+
+    pub fn dielectric_transmission(cos_i: f64, eta: f64) -> Self {
+        let ((_, _), (ts, tp), cos_t) = amplitudes(cos_i, Complex::new(eta, 0.0));
+        // Beam cross-section and impedance change; no light gets through past the critical
+        // angle.
+        let factor = if cos_t.im == 0.0 {
+            eta * cos_t.re / cos_i.max(1e-12)
+        } else {
+            0.0
+        };
+        Self::from_amplitudes(ts, tp, factor)
+    }
+
+    // Reflection off a conductor of complex index n + ik.
+    pub fn conductor_reflection(cos_i: f64, n: f64, k: f64) -> Self {
+        let (rs, rp) = amplitudes(cos_i, Complex::new(n, k)).0;
+        Self::from_amplitudes(rs, rp, 1.0)
+    }
+
+    fn from_amplitudes(s: Complex, p: Complex, factor: f64) -> Self {
+        let (a, b) = (s.norm_sqr() * factor, p.norm_sqr() * factor);
+        let cross = s * p.conj();
+        let (re, im) = (cross.re * factor, cross.im * factor);
+        Self([
+            [0.5 * (a + b), 0.5 * (a - b), 0.0, 0.0],
+            [0.5 * (a - b), 0.5 * (a + b), 0.0, 0.0],
+            [0.0, 0.0, re, im],
+            [0.0, 0.0, -im, re],
+        ])
+    }
+
+    pub fn apply(&self, s: Stokes) -> Stokes {
+        std::array::from_fn(|i| (0..4).map(|j| self.0[i][j] * s[j]).sum())
+    }
+
+    fn first_column(&self) -> Stokes {
+        std::array::from_fn(|i| self.0[i][0])
+    }
+}
+
+impl Mul for Mueller {
+    type Output = Mueller;
+
+    fn mul(self, o: Mueller) -> Mueller {
+        Mueller(std::array::from_fn(|i| {
+            std::array::from_fn(|j| (0..4).map(|k| self.0[i][k] * o.0[k][j]).sum())
+        }))
+    }
+}
+
+impl Mul<f64> for Mueller {
+    type Output = Mueller;
+
+    fn mul(self, s: f64) -> Mueller {
+        Mueller(self.0.map(|row| row.map(|v| v * s)))
+    }
+}
+
+// Degree of polarization of `s`, 0 for unpolarized light to 1 for fully polarized.
+pub fn degree_of_polarization(s: Stokes) -> f64 {
+    if s[0] <= 0.0 {
+        return 0.0;
+    }
+    ((s[1] * s[1] + s[2] * s[2] + s[3] * s[3]).sqrt() / s[0]).min(1.0)
+}
+
+// Gulbrandsen's index for a metal of normal reflectance `r`, edge tint `r` as well.
+fn metal_index(r: f64) -> (f64, f64) {
+    let r = r.clamp(0.0, 0.99);
+    let sr = r.sqrt();
+    let n = r * (1.0 - r) / (1.0 + r) + (1.0 - r) * (1.0 + sr) / (1.0 - sr);
+    let k2 = (r * (n + 1.0) * (n + 1.0) - (n - 1.0) * (n - 1.0)) / (1.0 - r);
+    (n, k2.max(0.0).sqrt())
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    // Principal root, with a non-negative real part.
+    fn sqrt(self) -> Self {
+        let r = self.norm_sqr().sqrt();
+        let re = (0.5 * (r + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (r - self.re)).max(0.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, o: Complex) -> Complex {
+        Complex::new(self.re + o.re, self.im + o.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, o: Complex) -> Complex {
+        Complex::new(self.re - o.re, self.im - o.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, o: Complex) -> Complex {
+        Complex::new(
+            self.re * o.re - self.im * o.im,
+            self.re * o.im + self.im * o.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, o: Complex) -> Complex {
+        let d = o.norm_sqr();
+        let n = self * o.conj();
+        Complex::new(n.re / d, n.im / d)
+    }
+}
+
+// Fresnel amplitudes (r_s, r_p) and (t_s, t_p) at `cos_i` into relative index `eta`, and
+// eta cos_t / eta, the cosine of the refracted angle, complex past the critical angle.
+#[allow(clippy::type_complexity)]
+fn amplitudes(cos_i: f64, eta: Complex) -> ((Complex, Complex), (Complex, Complex), Complex) {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let ci = Complex::new(cos_i, 0.0);
+    let sin2 = Complex::new(1.0 - cos_i * cos_i, 0.0);
+    let eta2 = eta * eta;
+    // eta cos_t.
+    let root = (eta2 - sin2).sqrt();
+    let rs = (ci - root) / (ci + root);
+    let rp = (eta2 * ci - root) / (eta2 * ci + root);
+    let two = Complex::new(2.0 * cos_i, 0.0);
+    let ts = two / (ci + root);
+    let tp = two * eta / (eta2 * ci + root);
+    ((rs, rp), (ts, tp), root / eta)
+}
+
+// Mean Stokes vectors per pixel and color channel.
+pub struct StokesImage {
+    width: u32,
+    height: u32,
+    // Per pixel, per channel.
+    pixels: Vec<[Stokes; 3]>,
+}
+
+impl StokesImage {
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Red, green and blue Stokes vectors at (x, y), top left (0, 0).
+    pub fn stokes(&self, x: u32, y: u32) -> [Stokes; 3] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    // Of the pixel's luminance.
+    pub fn degree_of_polarization(&self, x: u32, y: u32) -> f64 {
+        let [r, g, b] = self.stokes(x, y);
+        degree_of_polarization(std::array::from_fn(|i| {
+            luminance(Color::new(r[i], g[i], b[i]))
+        }))
+    }
+
+    // The ordinary image, S0.
+    pub fn intensity(&self) -> Result<Film> {
+        let mut film = Film::new(self.width, self.height);
+        for (i, [r, g, b]) in self.pixels.iter().enumerate() {
+            let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+            film.add_sample(x, y, Color::new(r[0], g[0], b[0]), 1.0)?;
+        }
+        Ok(film)
+    }
+
+    // Degree of polarization as heat, black where light is unpolarized to white where fully.
+    pub fn degree_view(&self) -> RgbImage {
+        RgbImage::from_fn(self.width, self.height, |x, y| {
+            heat_color(self.degree_of_polarization(x, y))
+        })
+    }
+
+    pub fn save_degree_view(&self, path: &Path) -> Result<()> {
+        self.degree_view().save(path).map_err(Error::image(path))
+    }
+}
+
+// Renders `samples` polarized paths per pixel.
+pub fn render_polarized(
+    world: &dyn Hittable,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    samples: u32,
+    settings: &RenderSettings,
+) -> Result<StokesImage> {
+    let lights = lights(world);
+    let pixels = (0..width * height)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let mut rng = sampler(settings.seed, 0, i as u64);
+            let mut sum = [[0.0; 4]; 3];
+            for _ in 0..samples {
+                let s = (x as f64 + rng.random::<f64>()) / width as f64;
+                let t = ((height - 1 - y) as f64 + rng.random::<f64>()) / height as f64;
+                // A ray the lens blocks adds nothing.
+                let Some(ray) = camera.get_ray(s, t, &mut rng) else {
+                    continue;
+                };
+                // The image's horizontal, as the first ray's reference frame.
+                let across = camera.pinhole_ray(s + 1e-3, t).direction()
+                    - camera.pinhole_ray(s, t).direction();
+                let ray_t = camera.clip_range(&ray, Interval::new(T_MIN, f64::INFINITY));
+                let stokes =
+                    trace_polarized(ray, across, ray_t, world, &lights, settings, &mut rng);
+                for (c, s) in sum.iter_mut().zip(stokes) {
+                    for (a, b) in c.iter_mut().zip(s) {
+                        if b.is_finite() {
+                            *a += b;
+                        }
+                    }
+                }
+            }
+            sum.map(|c| c.map(|v| v / samples.max(1) as f64))
+        })
+        .collect();
+    Ok(StokesImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+// Follows `ray` through mirror-like bounces, then hands the rest of the path to the ordinary
+// tracer. `across` is the reference direction for the camera's Stokes frame.
+fn trace_polarized(
+    mut ray: Ray,
+    across: Vec3,
+    mut ray_t: Interval,
+    world: &dyn Hittable,
+    lights: &[&dyn Light],
+    settings: &RenderSettings,
+    rng: &mut dyn rand::RngCore,
+) -> [Stokes; 3] {
+    let mut reference = perpendicular(across, ray.direction());
+    let mut m = [Mueller::default(); 3];
+    for _ in 0..settings.bounces.total {
+        let Some(rec) = world.hit(&ray, ray_t) else {
+            break;
+        };
+        let Some(interaction) = Interaction::new(&rec) else {
+            break;
+        };
+        let Some((attenuation, scattered)) = rec.material.get().scatter(&ray, &rec, rng) else {
+            return [[0.0; 4]; 3];
+        };
+        let d = Vec3::unit_vector(ray.direction());
+        let out = Vec3::unit_vector(scattered.direction());
+        let reflected = Vec3::dot(out, rec.normal) > 0.0;
+        // Metals reflect about the microfacet the fuzz picked.
+        let normal = match interaction {
+            Interaction::Metal(..) if (out - d).length_squared() > 1e-12 => {
+                Vec3::unit_vector(out - d)
+            }
+            _ => rec.normal,
+        };
+        let cos_i = Vec3::dot(d, normal).abs();
+        let s_axis = perpendicular(Vec3::cross(d, normal), d);
+        let s_axis = if s_axis.length_squared() > 0.0 {
+            s_axis
+        } else {
+            reference
+        };
+        let angle =
+            Vec3::dot(Vec3::cross(reference, s_axis), d).atan2(Vec3::dot(reference, s_axis));
+        let rotate = Mueller::rotator(angle);
+        let channels = [attenuation.r(), attenuation.g(), attenuation.b()];
+        for (c, m) in m.iter_mut().enumerate() {
+            let fresnel = match interaction {
+                Interaction::Dielectric(eta) if reflected => {
+                    Mueller::dielectric_reflection(cos_i, eta)
+                }
+                Interaction::Dielectric(eta) => Mueller::dielectric_transmission(cos_i, eta),
+                Interaction::Metal(n, k) => Mueller::conductor_reflection(cos_i, n[c], k[c]),
+            };
+            // Scaled to the material's attenuation, since it already chose between
+            // reflection and refraction by their unpolarized share.
+            let norm = fresnel.0[0][0];
+            let fresnel = if norm > 0.0 {
+                fresnel * (channels[c] / norm)
+            } else {
+                Mueller::scaled(0.0)
+            };
+            *m = *m * rotate * fresnel;
+        }
+        reference = s_axis;
+        ray = scattered;
+        ray_t = Interval::new(T_MIN, f64::INFINITY);
+    }
+    let radiance = trace_path_with(ray, world, lights, ray_t, settings, rng).color;
+    let radiance = [radiance.r(), radiance.g(), radiance.b()];
+    std::array::from_fn(|c| m[c].first_column().map(|v| v * radiance[c]))
+}
+
+// Surfaces that polarize what they reflect or transmit.
+#[derive(Copy, Clone)]
+enum Interaction {
+    // Relative index across the surface in the ray's direction.
+    Dielectric(f64),
+    // Complex index n + ik per channel.
+    Metal([f64; 3], [f64; 3]),
+}
+
+impl Interaction {
+    fn new(rec: &HitRecord) -> Option<Self> {
+        match rec.material.get().to_desc()? {
+            MaterialDesc::Dielectric { ior } => Some(Interaction::Dielectric(if rec.front_face {
+                ior
+            } else {
+                1.0 / ior
+            })),
+            MaterialDesc::Metal {
+                measured: Some(metal),
+                ..
+            } => {
+                let (n, k) = metal.ior();
+                Some(Interaction::Metal(
+                    [n.r(), n.g(), n.b()],
+                    [k.r(), k.g(), k.b()],
+                ))
+            }
+            MaterialDesc::Metal { albedo, .. } => {
+                let [(nr, kr), (ng, kg), (nb, kb)] =
+                    [albedo.r(), albedo.g(), albedo.b()].map(metal_index);
+                Some(Interaction::Metal([nr, ng, nb], [kr, kg, kb]))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Unit `v` with its component along `d` removed; zero if nothing is left.
+fn perpendicular(v: Vec3, d: Vec3) -> Vec3 {
+    let d = Vec3::unit_vector(d);
+    let p = v - Vec3::dot(v, d) * d;
+    if p.length_squared() < 1e-24 {
+        Vec3::default()
+    } else {
+        Vec3::unit_vector(p)
+    }
+}
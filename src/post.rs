@@ -0,0 +1,277 @@
+// Finishing looks applied to a copy of the film before it is developed: white balance, lens
+// vignetting, film grain and a 3D color lookup table. White balance and vignetting work on the
+// linear image; the LUT and grain work on display values, as `film::to_rgba` encodes them, so
+// their result is clipped to display white.
+
+use crate::color::{adapt, from_xyz, kelvin_xy_tinted, to_xyz, WHITE};
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::vec3::{Color, Vec3};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+// Chromatic adaptation that makes light of color `white` neutral, as a camera's white balance
+// does, keeping its luminance. Scenes mixing warm lamps with a blue sky look right under one
+// or the other; this picks which.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WhiteBalance {
+    // Linear color of the light to neutralize, e.g. picked from a grey card in the render.
+    pub white: Color,
+}
+
+// Darkening towards the corners.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vignette {
+    // Fraction of the light lost in the corners.
+    pub strength: f64,
+    // Exponent of the distance from the centre, in half diagonals; higher keeps more of the
+    // frame untouched.
+    pub falloff: f64,
+}
+
+// Monochrome grain, strongest in the midtones. The same seed and frame give the same grain.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Grain {
+    // Standard deviation of the grain in display values, at mid grey.
+    pub amount: f64,
+    // Size of a grain, in pixels.
+    pub size: f64,
+    pub seed: u64,
+}
+
+// A 3D lookup table over display values, as read from a `.cube` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    size: usize,
+    domain: (Color, Color),
+    // Red fastest, then green, then blue, as `.cube` files list them.
+    table: Vec<Color>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Look {
+    pub white_balance: Option<WhiteBalance>,
+    pub vignette: Option<Vignette>,
+    pub grain: Option<Grain>,
+    pub lut: Option<Arc<Lut>>,
+}
+
+impl Look {
+    pub fn is_empty(&self) -> bool {
+        self.white_balance.is_none()
+            && self.vignette.is_none()
+            && self.grain.is_none()
+            && self.lut.is_none()
+    }
+
+    // A copy of `film` with the look applied, to develop with the same `splat_scale`, which must
+    // be positive. `frame` picks the grain, so animations get fresh grain every frame.
+    pub fn apply(&self, film: &Film, splat_scale: f64, frame: u64) -> Result<Film> {
+        let (width, height) = (film.width(), film.height());
+        let mut colors = film.resolve(splat_scale)?;
+        if let Some(balance) = &self.white_balance {
+            for c in &mut colors {
+                *c = balance.apply(*c);
+            }
+        }
+        if let Some(vignette) = &self.vignette {
+            for (i, c) in colors.iter_mut().enumerate() {
+                let (x, y) = (i as u32 % width, i as u32 / width);
+                *c *= vignette.factor(x, y, width, height);
+            }
+        }
+        if self.lut.is_some() || self.grain.is_some() {
+            for (i, c) in colors.iter_mut().enumerate() {
+                let (x, y) = (i as u32 % width, i as u32 / width);
+                // Display values, as `film::to_rgba` encodes them.
+                let mut v = Color::new(
+                    c.r().clamp(0.0, 1.0).sqrt(),
+                    c.g().clamp(0.0, 1.0).sqrt(),
+                    c.b().clamp(0.0, 1.0).sqrt(),
+                );
+                if let Some(lut) = &self.lut {
+                    v = lut.lookup(v);
+                }
+                if let Some(grain) = &self.grain {
+                    v = grain.apply(v, x, y, frame);
+                }
+                let v = Color::new(
+                    v.r().clamp(0.0, 1.0),
+                    v.g().clamp(0.0, 1.0),
+                    v.b().clamp(0.0, 1.0),
+                );
+                *c = v * v;
+            }
+        }
+        film.with_colors(&colors, splat_scale)
+    }
+}
+
+impl WhiteBalance {
+    pub fn new(white: Color) -> Self {
+        Self { white }
+    }
+
+    // For light of correlated color temperature `kelvin`, `tint` Δuv off the Planckian locus,
+    // positive for greener light; e.g. 3200 K balances for tungsten. 6500 K with no tint is
+    // about neutral already.
+    pub fn from_temperature(kelvin: f64, tint: f64) -> Self {
+        let (x, y) = kelvin_xy_tinted(kelvin, tint);
+        // Unclamped, since very warm or tinted lights fall outside the sRGB gamut.
+        Self::new(from_xyz(Vec3::new(x / y, 1.0, (1.0 - x - y) / y)))
+    }
+
+    pub fn apply(&self, c: Color) -> Color {
+        adapt(c, to_xyz(self.white), to_xyz(WHITE))
+    }
+}
+
+impl Vignette {
+    pub fn new(strength: f64) -> Self {
+        Self {
+            strength,
+            falloff: 2.0,
+        }
+    }
+
+    fn factor(&self, x: u32, y: u32, width: u32, height: u32) -> f64 {
+        let (w, h) = (width as f64, height as f64);
+        let dx = x as f64 + 0.5 - 0.5 * w;
+        let dy = y as f64 + 0.5 - 0.5 * h;
+        let r = (dx * dx + dy * dy).sqrt() / (0.5 * w.hypot(h));
+        1.0 - self.strength.clamp(0.0, 1.0) * r.powf(self.falloff)
+    }
+}
+
+impl Grain {
+    pub fn new(amount: f64, size: f64, seed: u64) -> Self {
+        Self { amount, size, seed }
+    }
+
+    fn apply(&self, v: Color, x: u32, y: u32, frame: u64) -> Color {
+        let size = self.size.max(1.0);
+        let noise = value_noise(
+            (x as f64 + 0.5) / size,
+            (y as f64 + 0.5) / size,
+            self.seed ^ frame.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+        );
+        // Midtones get the most; black and white stay clean.
+        let luma = (v.r() + v.g() + v.b()) / 3.0;
+        let offset = self.amount * noise * 4.0 * luma * (1.0 - luma);
+        v + Color::new(offset, offset, offset)
+    }
+}
+
+// Smoothly interpolated lattice noise with unit standard deviation at lattice points.
+fn value_noise(x: f64, y: f64, seed: u64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (sx, sy) = (fx * fx * (3.0 - 2.0 * fx), fy * fy * (3.0 - 2.0 * fy));
+    let at = |i: f64, j: f64| lattice(i as i64, j as i64, seed);
+    let top = at(x0, y0) * (1.0 - sx) + at(x0 + 1.0, y0) * sx;
+    let bottom = at(x0, y0 + 1.0) * (1.0 - sx) + at(x0 + 1.0, y0 + 1.0) * sx;
+    top * (1.0 - sy) + bottom * sy
+}
+
+// Roughly normal, zero mean, unit variance value for lattice point (i, j).
+fn lattice(i: i64, j: i64, seed: u64) -> f64 {
+    let mut h = seed ^ (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    h ^= (j as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    // Sum of four uniforms from splitmix64 rounds, centred and scaled.
+    let mut sum = 0.0;
+    for _ in 0..4 {
+        h = h.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = h;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        sum += (z >> 11) as f64 / (1u64 << 53) as f64;
+    }
+    (sum - 2.0) * 3.0f64.sqrt()
+}
+
+impl Lut {
+    // `table` has `size`^3 entries, red fastest.
+    pub fn new(size: usize, table: Vec<Color>) -> Result<Self> {
+        if size < 2 || table.len() != size * size * size {
+            return Err(Error::Scene(format!(
+                "a {size}^3 LUT needs {} entries, not {}",
+                size * size * size,
+                table.len()
+            )));
+        }
+        Ok(Self {
+            size,
+            domain: (Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)),
+            table,
+        })
+    }
+
+    // Reads an Adobe/Resolve `.cube` 3D LUT.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::Scene(format!("{}: {e}", path.display())))
+    }
+
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut size = None;
+        let mut domain = (Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let mut table = Vec::new();
+        let triple = |rest: &[&str]| -> std::result::Result<Color, String> {
+            let v: Vec<f64> = rest
+                .iter()
+                .map(|s| s.parse().map_err(|_| format!("bad number {s:?}")))
+                .collect::<std::result::Result<_, _>>()?;
+            match v[..] {
+                [r, g, b] => Ok(Color::new(r, g, b)),
+                _ => Err(format!("expected three numbers, got {}", v.len())),
+            }
+        };
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first().copied() {
+                None => {}
+                Some(w) if w.starts_with('#') => {}
+                Some("TITLE") => {}
+                Some("LUT_3D_SIZE") => {
+                    let n = words.get(1).and_then(|n| n.parse().ok());
+                    size = Some(n.ok_or_else(|| format!("bad LUT_3D_SIZE line {line:?}"))?);
+                }
+                Some("LUT_1D_SIZE") => return Err("1D LUTs aren't supported".into()),
+                Some("DOMAIN_MIN") => domain.0 = triple(&words[1..])?,
+                Some("DOMAIN_MAX") => domain.1 = triple(&words[1..])?,
+                Some(_) => table.push(triple(&words)?),
+            }
+        }
+        let size = size.ok_or("no LUT_3D_SIZE")?;
+        let mut lut = Self::new(size, table).map_err(|e| e.to_string())?;
+        lut.domain = domain;
+        Ok(lut)
+    }
+
+    // Trilinear lookup; inputs outside the domain are clamped to it.
+    pub fn lookup(&self, c: Color) -> Color {
+        let n = self.size - 1;
+        let (lo, hi) = self.domain;
+        let coord = |v: f64, lo: f64, hi: f64| {
+            let t = if hi > lo { (v - lo) / (hi - lo) } else { 0.0 };
+            let f = t.clamp(0.0, 1.0) * n as f64;
+            let i = (f.floor() as usize).min(n - 1);
+            (i, f - i as f64)
+        };
+        let (ri, rf) = coord(c.r(), lo.r(), hi.r());
+        let (gi, gf) = coord(c.g(), lo.g(), hi.g());
+        let (bi, bf) = coord(c.b(), lo.b(), hi.b());
+        let at = |r: usize, g: usize, b: usize| self.table[(b * self.size + g) * self.size + r];
+        let mut out = Color::default();
+        for (db, wb) in [(0, 1.0 - bf), (1, bf)] {
+            for (dg, wg) in [(0, 1.0 - gf), (1, gf)] {
+                for (dr, wr) in [(0, 1.0 - rf), (1, rf)] {
+                    out += (wr * wg * wb) * at(ri + dr, gi + dg, bi + db);
+                }
+            }
+        }
+        out
+    }
+}
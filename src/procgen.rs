@@ -0,0 +1,292 @@
+// Parametric mesh generators, so demo scenes and tests don't need model files. Every shape is
+// closed unless noted, wound counter-clockwise seen from outside, and built around the origin
+// unless it takes explicit bounds; place it with `transform`.
+
+use crate::math::Mat4;
+use crate::mesh::Geometry;
+use crate::vec3::{Point3, Vec3};
+use rand::Rng;
+use std::collections::HashMap;
+use std::f64::consts::{PI, TAU};
+
+// Latitude-longitude sphere with `2 * (rings - 1) * segments` triangles.
+pub fn uv_sphere(radius: f64, rings: u32, segments: u32) -> Geometry {
+    let rings = rings.max(2);
+    let segments = segments.max(3);
+    let mut positions = vec![
+        Point3::new(0.0, radius, 0.0),
+        Point3::new(0.0, -radius, 0.0),
+    ];
+    for r in 1..rings {
+        let theta = PI * r as f64 / rings as f64;
+        for s in 0..segments {
+            let phi = TAU * s as f64 / segments as f64;
+            positions.push(
+                radius
+                    * Point3::new(
+                        theta.sin() * phi.cos(),
+                        theta.cos(),
+                        theta.sin() * phi.sin(),
+                    ),
+            );
+        }
+    }
+    let ring = |r: u32, s: u32| 2 + (r - 1) * segments + s % segments;
+    let mut indices = Vec::new();
+    for s in 0..segments {
+        indices.push([0, ring(1, s + 1), ring(1, s)]);
+        indices.push([1, ring(rings - 1, s), ring(rings - 1, s + 1)]);
+        for r in 1..rings - 1 {
+            quad(
+                &mut indices,
+                [
+                    ring(r, s),
+                    ring(r, s + 1),
+                    ring(r + 1, s + 1),
+                    ring(r + 1, s),
+                ],
+            );
+        }
+    }
+    (positions, indices)
+}
+
+// Subdivided icosahedron with `20 * 4^subdivisions` nearly equal triangles.
+pub fn icosphere(radius: f64, subdivisions: u32) -> Geometry {
+    let t = (1.0 + 5f64.sqrt()) / 2.0;
+    let mut positions: Vec<Point3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .into_iter()
+    .map(|p| radius * Vec3::unit_vector(Vec3::from(p)))
+    .collect();
+    let mut indices: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Shared edges get one midpoint, so the result stays watertight.
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Point3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let m = 0.5 * (positions[a as usize] + positions[b as usize]);
+                positions.push(radius * Vec3::unit_vector(m));
+                positions.len() as u32 - 1
+            })
+        };
+        let mut next = Vec::with_capacity(indices.len() * 4);
+        for [a, b, c] in indices {
+            let ab = midpoint(a, b, &mut positions);
+            let bc = midpoint(b, c, &mut positions);
+            let ca = midpoint(c, a, &mut positions);
+            next.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        indices = next;
+    }
+    (positions, indices)
+}
+
+// Open `width` by `depth` grid in the XZ plane, facing +y, with `nx` by `nz` cells.
+pub fn plane_grid(width: f64, depth: f64, nx: u32, nz: u32) -> Geometry {
+    let nx = nx.max(1);
+    let nz = nz.max(1);
+    let mut positions = Vec::new();
+    for j in 0..=nz {
+        for i in 0..=nx {
+            positions.push(Point3::new(
+                width * (i as f64 / nx as f64 - 0.5),
+                0.0,
+                depth * (j as f64 / nz as f64 - 0.5),
+            ));
+        }
+    }
+    let at = |i: u32, j: u32| j * (nx + 1) + i;
+    let mut indices = Vec::new();
+    for j in 0..nz {
+        for i in 0..nx {
+            quad(
+                &mut indices,
+                [at(i, j), at(i, j + 1), at(i + 1, j + 1), at(i + 1, j)],
+            );
+        }
+    }
+    (positions, indices)
+}
+
+// Axis-aligned box between `min` and `max`. Faces don't share vertices, keeping edges sharp.
+pub fn cuboid(min: Point3, max: Point3) -> Geometry {
+    let d = max - min;
+    let (dx, dy, dz) = (
+        Vec3::new(d.x, 0.0, 0.0),
+        Vec3::new(0.0, d.y, 0.0),
+        Vec3::new(0.0, 0.0, d.z),
+    );
+    let faces = [
+        (Point3::new(max.x, min.y, min.z), dy, dz),
+        (min, dz, dy),
+        (Point3::new(min.x, max.y, min.z), dz, dx),
+        (min, dx, dz),
+        (Point3::new(min.x, min.y, max.z), dx, dy),
+        (min, dy, dx),
+    ];
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    // Each face spans u then v, so u x v points outward.
+    for (origin, u, v) in faces {
+        let base = positions.len() as u32;
+        positions.extend([origin, origin + u, origin + u + v, origin + v]);
+        quad(&mut indices, [base, base + 1, base + 2, base + 3]);
+    }
+    (positions, indices)
+}
+
+// Torus around the y axis: `major` from the axis to the tube centre, `minor` the tube radius.
+pub fn torus(major: f64, minor: f64, rings: u32, segments: u32) -> Geometry {
+    let rings = rings.max(3);
+    let segments = segments.max(3);
+    let mut positions = Vec::new();
+    for i in 0..rings {
+        let phi = TAU * i as f64 / rings as f64;
+        for j in 0..segments {
+            let theta = TAU * j as f64 / segments as f64;
+            let r = major + minor * theta.cos();
+            positions.push(Point3::new(
+                r * phi.cos(),
+                minor * theta.sin(),
+                r * phi.sin(),
+            ));
+        }
+    }
+    let at = |i: u32, j: u32| (i % rings) * segments + j % segments;
+    let mut indices = Vec::new();
+    for i in 0..rings {
+        for j in 0..segments {
+            quad(
+                &mut indices,
+                [at(i, j), at(i, j + 1), at(i + 1, j + 1), at(i + 1, j)],
+            );
+        }
+    }
+    (positions, indices)
+}
+
+// Cylinder along +y from the origin. Without caps the ends are left open.
+pub fn cylinder(radius: f64, height: f64, segments: u32, capped: bool) -> Geometry {
+    let segments = segments.max(3);
+    let mut positions = Vec::new();
+    for k in 0..2 {
+        for s in 0..segments {
+            let phi = TAU * s as f64 / segments as f64;
+            positions.push(Point3::new(
+                radius * phi.cos(),
+                k as f64 * height,
+                radius * phi.sin(),
+            ));
+        }
+    }
+    let at = |k: u32, s: u32| k * segments + s % segments;
+    let mut indices = Vec::new();
+    for s in 0..segments {
+        quad(
+            &mut indices,
+            [at(0, s), at(1, s), at(1, s + 1), at(0, s + 1)],
+        );
+    }
+    if capped {
+        let bottom = positions.len() as u32;
+        positions.push(Point3::new(0.0, 0.0, 0.0));
+        positions.push(Point3::new(0.0, height, 0.0));
+        for s in 0..segments {
+            indices.push([bottom, at(0, s), at(0, s + 1)]);
+            indices.push([bottom + 1, at(1, s + 1), at(1, s)]);
+        }
+    }
+    (positions, indices)
+}
+
+// Two triangles for the quad a-b-c-d, where (b - a) x (d - a) is the outward side.
+#[inline]
+fn quad(indices: &mut Vec<[u32; 3]>, [a, b, c, d]: [u32; 4]) {
+    indices.push([a, b, c]);
+    indices.push([a, c, d]);
+}
+
+// Moves `geometry` by `m`, flipping the winding if `m` mirrors it.
+pub fn transform(geometry: &mut Geometry, m: &Mat4) {
+    let (positions, indices) = geometry;
+    for p in positions.iter_mut() {
+        *p = m.transform_point(*p);
+    }
+    if m.determinant() < 0.0 {
+        for tri in indices.iter_mut() {
+            tri.swap(1, 2);
+        }
+    }
+}
+
+// `count` points spread uniformly by area over the surface, each with its unit normal, e.g.
+// for placing rocks on terrain. Empty if the geometry has no area.
+pub fn scatter_on_surface(
+    (positions, indices): &Geometry,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Vec<(Point3, Vec3)> {
+    let corners = |tri: &[u32; 3]| tri.map(|i| positions[i as usize]);
+    let mut cumulative = Vec::with_capacity(indices.len());
+    let mut total = 0.0;
+    for tri in indices {
+        let [a, b, c] = corners(tri);
+        total += 0.5 * Vec3::cross(b - a, c - a).length();
+        cumulative.push(total);
+    }
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    (0..count)
+        .map(|_| {
+            let pick = rng.random::<f64>() * total;
+            let t = cumulative
+                .partition_point(|&area| area <= pick)
+                .min(indices.len() - 1);
+            let [a, b, c] = corners(&indices[t]);
+            // Folding the unit square onto the triangle keeps the density uniform.
+            let (mut u, mut v) = (rng.random::<f64>(), rng.random::<f64>());
+            if u + v > 1.0 {
+                (u, v) = (1.0 - u, 1.0 - v);
+            }
+            let normal = Vec3::unit_vector(Vec3::cross(b - a, c - a));
+            (a + u * (b - a) + v * (c - a), normal)
+        })
+        .collect()
+}
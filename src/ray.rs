@@ -1,9 +1,35 @@
 use crate::vec3::{Point3, Vec3};
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    tm: f64,
+    #[serde(skip)]
+    diff: Option<RayDifferential>,
+    #[serde(skip)]
+    kind: RayKind,
+}
+
+// What a ray is traced for, so objects can hide from some kinds of ray; see `Visibility`.
+// Rays are indirect unless marked otherwise, as bounces are.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RayKind {
+    Camera,
+    Shadow,
+    #[default]
+    Indirect,
+}
+
+// Rays through the neighbouring pixels in x and y, carried along with a camera ray so a hit can
+// tell how much of the surface one pixel covers.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RayDifferential {
+    pub rx_origin: Point3,
+    pub rx_direction: Vec3,
+    pub ry_origin: Point3,
+    pub ry_direction: Vec3,
 }
 
 impl Ray {
@@ -12,9 +38,35 @@ impl Ray {
         Self {
             orig: origin,
             dir: direction,
+            tm: 0.0,
+            diff: None,
+            kind: RayKind::Indirect,
         }
     }
 
+    #[inline]
+    pub const fn with_time(origin: Point3, direction: Vec3, time: f64) -> Self {
+        Self {
+            orig: origin,
+            dir: direction,
+            tm: time,
+            diff: None,
+            kind: RayKind::Indirect,
+        }
+    }
+
+    #[inline]
+    pub const fn with_differential(mut self, diff: Option<RayDifferential>) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    #[inline]
+    pub const fn with_kind(mut self, kind: RayKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     #[inline]
     pub const fn origin(self) -> Point3 {
         self.orig
@@ -25,8 +77,69 @@ impl Ray {
         self.dir
     }
 
+    #[inline]
+    pub const fn time(self) -> f64 {
+        self.tm
+    }
+
+    #[inline]
+    pub const fn differential(self) -> Option<RayDifferential> {
+        self.diff
+    }
+
+    #[inline]
+    pub const fn kind(self) -> RayKind {
+        self.kind
+    }
+
     #[inline]
     pub fn at(self, t: f64) -> Point3 {
         self.orig + t * self.dir
     }
+
+    // Offsets from the hit at `t` to where the neighbouring rays meet the tangent plane with
+    // `normal` there, or None without differentials or when they run parallel to the plane.
+    pub fn surface_offsets(self, t: f64, normal: Vec3) -> Option<(Vec3, Vec3)> {
+        let diff = self.diff?;
+        let p = self.at(t);
+        let plane = |o: Point3, d: Vec3| {
+            let denom = Vec3::dot(normal, d);
+            if denom == 0.0 {
+                return None;
+            }
+            let t = Vec3::dot(normal, p - o) / denom;
+            t.is_finite().then(|| o + t * d - p)
+        };
+        Some((
+            plane(diff.rx_origin, diff.rx_direction)?,
+            plane(diff.ry_origin, diff.ry_direction)?,
+        ))
+    }
+
+    // Width of the surface one pixel covers at the hit at `t`, for choosing a texture filter
+    // or a level of detail; None if unknown.
+    pub fn footprint(self, t: f64, normal: Vec3) -> Option<f64> {
+        let (dpdx, dpdy) = self.surface_offsets(t, normal)?;
+        Some(dpdx.length().max(dpdy.length()))
+    }
+
+    // Differential for a ray leaving the hit at `t` in direction `mapped(dir)`, each
+    // neighbouring ray starting where it met the tangent plane and bent by the same `mapped`.
+    // For mirror reflection and refraction, which keep neighbouring rays coherent.
+    pub fn specular_differential(
+        self,
+        t: f64,
+        normal: Vec3,
+        mapped: impl Fn(Vec3) -> Option<Vec3>,
+    ) -> Option<RayDifferential> {
+        let diff = self.diff?;
+        let (dpdx, dpdy) = self.surface_offsets(t, normal)?;
+        let p = self.at(t);
+        Some(RayDifferential {
+            rx_origin: p + dpdx,
+            rx_direction: mapped(Vec3::unit_vector(diff.rx_direction))?,
+            ry_origin: p + dpdy,
+            ry_direction: mapped(Vec3::unit_vector(diff.ry_direction))?,
+        })
+    }
 }
@@ -0,0 +1,126 @@
+// Rebuilding a scene after an edit, reusing what the edit didn't touch. Built objects are kept
+// by their description and the materials they use, so changing one material rebuilds only the
+// objects using it, and loading meshes, opening paged geometry or reading HDRIs happens again
+// only for objects and backgrounds that changed. Files the scene refers to are assumed not to
+// have changed themselves.
+
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::error::Result;
+use crate::hittable::{HittableList, Primitive};
+use crate::material::{MaterialId, MaterialRef};
+use crate::scene::{BackgroundDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// What the last `SceneCache::build` reused and rebuilt.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReloadStats {
+    pub objects_reused: usize,
+    pub objects_built: usize,
+    pub materials_reused: usize,
+    pub materials_built: usize,
+    pub background_reused: bool,
+}
+
+#[derive(Default)]
+pub struct SceneCache {
+    materials: Vec<(MaterialDesc, MaterialRef)>,
+    // Built objects by `object_key`; identical objects share a key.
+    objects: HashMap<String, Vec<Primitive>>,
+    background: Option<(BackgroundDesc, Arc<dyn Background>)>,
+    stats: ReloadStats,
+}
+
+impl SceneCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Like `SceneDesc::build`. If it fails the cache keeps what the last successful build
+    // left, so a broken edit followed by a fix still reuses everything.
+    pub fn build(&mut self, desc: &SceneDesc, aspect_ratio: f64) -> Result<(HittableList, Camera)> {
+        let mut stats = ReloadStats::default();
+        let materials: Vec<(MaterialDesc, MaterialRef)> = desc
+            .materials
+            .iter()
+            .map(|m| match self.materials.iter().find(|(old, _)| old == m) {
+                Some((_, material)) => {
+                    stats.materials_reused += 1;
+                    Ok((m.clone(), material.clone()))
+                }
+                None => {
+                    stats.materials_built += 1;
+                    Ok((m.clone(), MaterialRef::new(m.build())?))
+                }
+            })
+            .collect::<Result<_>>()?;
+        let ids: Vec<MaterialRef> = materials.iter().map(|(_, m)| m.clone()).collect();
+
+        let background = match &desc.background {
+            Some(b) => match &self.background {
+                Some((old, built)) if old == b => {
+                    stats.background_reused = true;
+                    Some((b.clone(), built.clone()))
+                }
+                _ => Some((b.clone(), b.build()?)),
+            },
+            None => None,
+        };
+        let mut old = std::mem::take(&mut self.objects);
+        let mut objects: HashMap<String, Vec<Primitive>> = HashMap::new();
+        let mut world = HittableList::new();
+        let built = (|| -> Result<()> {
+            for object in desc.objects_in_units()?.iter() {
+                let key = object_key(object, &ids)?;
+                let primitive = match old.get_mut(&key).and_then(Vec::pop) {
+                    Some(p) => {
+                        stats.objects_reused += 1;
+                        p
+                    }
+                    None => {
+                        stats.objects_built += 1;
+                        object.build_with(&ids, None)?
+                    }
+                };
+                objects.entry(key).or_default().push(primitive.clone());
+                world.add(primitive);
+            }
+            Ok(())
+        })();
+        if let Err(e) = built {
+            // Put back what this attempt took out.
+            for (key, mut primitives) in objects {
+                old.entry(key).or_default().append(&mut primitives);
+            }
+            self.objects = old;
+            return Err(e);
+        }
+
+        if let Some((_, b)) = &background {
+            world.set_background(b.clone());
+        }
+
+        self.materials = materials;
+        self.objects = objects;
+        self.background = background;
+        self.stats = stats;
+        let camera = desc.camera.build_in(aspect_ratio, &world);
+        Ok((world, camera))
+    }
+
+    pub fn stats(&self) -> ReloadStats {
+        self.stats
+    }
+}
+
+// The object's description with the ids of the materials it uses: equal keys build equal
+// objects.
+fn object_key(object: &ObjectDesc, materials: &[MaterialRef]) -> Result<String> {
+    let ids: Vec<Option<MaterialId>> = object
+        .material_indices()
+        .into_iter()
+        .map(|i| materials.get(i).map(MaterialRef::id))
+        .collect();
+    Ok(format!("{}{ids:?}", serde_json::to_string(object)?))
+}
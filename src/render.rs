@@ -0,0 +1,1538 @@
+use crate::aov::{Aov, AovSet, AovTiles, PathClass};
+use crate::atmosphere::Atmosphere;
+use crate::background::{Background, SKY};
+use crate::camera::Camera;
+use crate::color::luminance;
+use crate::control::RenderControl;
+use crate::error::{Error, Result};
+use crate::film::{Film, FilmTile};
+use crate::guiding::{DirectionalDistribution, PathGuide, GUIDE_FRACTION};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::light::Light;
+use crate::material::MaterialId;
+use crate::pathdump::{PathVertex, RecordedPath};
+use crate::ray::{Ray, RayKind};
+use crate::reproject::reproject;
+use crate::restir::{self, Reservoir, Restir};
+use crate::scene::MaterialDesc;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, info_span, warn};
+
+pub use crate::color::BLACK;
+
+pub const MAX_DEPTH: i32 = 50;
+
+// Radiance along a camera ray plus the primary hit and per-light contributions, for AOVs.
+pub struct PathSample {
+    pub color: Color,
+    // 0 when the camera ray hit a holdout object.
+    pub alpha: f64,
+    pub primary: Option<HitRecord>,
+    // Background radiance reaching the camera, weighted by path throughput.
+    pub background: Color,
+    // Emitted radiance reaching the camera, tagged with the emitter's light group.
+    pub emission: Vec<(Option<Arc<str>>, Color)>,
+    // Every vertex of the path, when traced by `trace_path_recorded`.
+    pub path: Option<RecordedPath>,
+    // Radiance reaching the camera split by `PathClass`, indexed by `PathClass as usize`.
+    pub classes: [Color; PathClass::ALL.len()],
+    // Attenuation of the camera ray's first bounce, if it scattered diffusely: the surface
+    // color that `Aov::Diffuse` divides out.
+    pub albedo: Option<Color>,
+}
+
+impl PathSample {
+    // What a path that saw nothing brings back, as does a camera ray blocked inside the lens.
+    fn black() -> Self {
+        Self {
+            color: BLACK,
+            alpha: 1.0,
+            primary: None,
+            background: BLACK,
+            emission: Vec::new(),
+            path: None,
+            classes: [BLACK; PathClass::ALL.len()],
+            albedo: None,
+        }
+    }
+}
+
+// How the camera ray's first hit scattered, which decides a path's `PathClass`.
+#[derive(Copy, Clone)]
+enum Lobe {
+    Diffuse,
+    Specular,
+    Transmission,
+}
+
+impl Lobe {
+    // How a material sent `continued` on from `rec`, given the density it was sampled with.
+    fn of(rec: &HitRecord, continued: &Ray, scattering_pdf: f64) -> Lobe {
+        if scattering_pdf > 0.0 {
+            Lobe::Diffuse
+        } else if Vec3::dot(continued.direction(), rec.normal) < 0.0 {
+            Lobe::Transmission
+        } else {
+            Lobe::Specular
+        }
+    }
+
+    // Class of light that took `events` scattering events to reach the camera, the first of
+    // them into `lobe`.
+    fn class(lobe: Option<Lobe>, events: i32) -> PathClass {
+        match (lobe, events) {
+            (_, ..=0) | (None, _) => PathClass::Emission,
+            (Some(Lobe::Diffuse), 1) => PathClass::DirectDiffuse,
+            (Some(Lobe::Diffuse), _) => PathClass::IndirectDiffuse,
+            (Some(Lobe::Specular), 1) => PathClass::DirectSpecular,
+            (Some(Lobe::Specular), _) => PathClass::IndirectSpecular,
+            (Some(Lobe::Transmission), _) => PathClass::Transmission,
+        }
+    }
+}
+
+pub const T_MIN: f64 = 0.001;
+
+// Most bounces a path takes in all and of each kind before it ends. Lower limits render faster
+// but lose light that needs many bounces: interreflections, or glass seen through glass. Light
+// arriving at the last diffuse bounce by shadow ray still counts, so a diffuse limit of zero
+// gives direct lighting only.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BounceLimits {
+    pub total: u32,
+    // Bounces sampled with a density: matte surfaces and volumes.
+    pub diffuse: u32,
+    // Mirror-like reflections, including fuzzy metals.
+    pub glossy: u32,
+    // Refractions into and out of glass.
+    pub transmission: u32,
+}
+
+impl Default for BounceLimits {
+    fn default() -> Self {
+        Self {
+            total: MAX_DEPTH as u32,
+            diffuse: MAX_DEPTH as u32,
+            glossy: MAX_DEPTH as u32,
+            transmission: MAX_DEPTH as u32,
+        }
+    }
+}
+
+impl BounceLimits {
+    pub fn with_total(mut self, total: u32) -> Self {
+        self.total = total;
+        self
+    }
+
+    pub fn with_diffuse(mut self, diffuse: u32) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    pub fn with_glossy(mut self, glossy: u32) -> Self {
+        self.glossy = glossy;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: u32) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    fn limit(&self, lobe: Lobe) -> u32 {
+        match lobe {
+            Lobe::Diffuse => self.diffuse,
+            Lobe::Specular => self.glossy,
+            Lobe::Transmission => self.transmission,
+        }
+    }
+}
+
+// Sampling controls that trade render time for noise.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderSettings {
+    // Shadow rays towards `Light`s from each diffuse bounce. Zero leaves lights to be found
+    // by chance, like emissive geometry.
+    pub shadow_samples: u32,
+    // Weights light and material sampling by the power heuristic. Without it, lights are only
+    // counted through shadow rays, which is noisier for glossy surfaces and large lights.
+    pub mis: bool,
+    // Learns where light comes from while rendering and steers diffuse bounces there; see
+    // `PathGuide`. Helps most where light reaches surfaces through glass.
+    pub guiding: bool,
+    // Haze over camera rays; see `Atmosphere`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub atmosphere: Option<Atmosphere>,
+    // Resamples light samples at camera ray hits, shared between neighbouring pixels; see
+    // `Restir`. Helps most with many lights. Ignored with chromatic aberration or a
+    // camera other than a thin lens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restir: Option<Restir>,
+    pub bounces: BounceLimits,
+    // Path regularization: after a path's first diffuse bounce, mirrors and glass spread their
+    // reflections over cones of at least this half-angle, in radians, so light reaching a
+    // diffuse surface only through them (caustics seen in a mirror) is found by shadow rays
+    // instead of showing as fireflies. Blurs such light slightly, so it is biased. Regularized
+    // bounces count as diffuse for the bounce limits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regularization: Option<f64>,
+    // Largest luminance a camera sample may add to the film; brighter samples are scaled down
+    // to it, keeping their hue. Removes fireflies at the cost of darkening bright, rarely found
+    // light, so it is biased; `Aov::Unclamped` keeps the unclamped image to compare against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clamp: Option<f64>,
+    // Seeds the samplers, so the same settings give the same noise, e.g. for temporal
+    // denoisers; see `SeedMode` for animations. Unseeded renders differ from run to run.
+    // Heterogeneous volumes still draw their own random numbers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            shadow_samples: 1,
+            mis: true,
+            guiding: false,
+            atmosphere: None,
+            restir: None,
+            bounces: BounceLimits::default(),
+            regularization: None,
+            clamp: None,
+            seed: None,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn with_shadow_samples(mut self, shadow_samples: u32) -> Self {
+        self.shadow_samples = shadow_samples;
+        self
+    }
+
+    pub fn with_mis(mut self, mis: bool) -> Self {
+        self.mis = mis;
+        self
+    }
+
+    pub fn with_guiding(mut self, guiding: bool) -> Self {
+        self.guiding = guiding;
+        self
+    }
+
+    pub fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere);
+        self
+    }
+
+    pub fn with_restir(mut self, restir: Restir) -> Self {
+        self.restir = Some(restir);
+        self
+    }
+
+    pub fn with_bounces(mut self, bounces: BounceLimits) -> Self {
+        self.bounces = bounces;
+        self
+    }
+
+    pub fn with_regularization(mut self, angle: f64) -> Self {
+        self.regularization = Some(angle);
+        self
+    }
+
+    pub fn with_clamp(mut self, max_luminance: f64) -> Self {
+        self.clamp = Some(max_luminance);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+// How an animation's sampler seeds change from frame to frame. `Fixed` keeps one noise pattern
+// that stays put while the image moves, which temporal denoisers handle best; `Decorrelated`
+// gives every frame fresh noise, which averages out when frames are blended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedMode {
+    Fixed,
+    Decorrelated,
+}
+
+impl SeedMode {
+    pub const ALL: [SeedMode; 2] = [SeedMode::Fixed, SeedMode::Decorrelated];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SeedMode::Fixed => "fixed",
+            SeedMode::Decorrelated => "decorrelated",
+        }
+    }
+
+    // The seed for `frame` of an animation seeded with `seed`.
+    pub fn seed(self, seed: u64, frame: u64) -> u64 {
+        match self {
+            SeedMode::Fixed => seed,
+            SeedMode::Decorrelated => mix(seed ^ mix(frame)),
+        }
+    }
+}
+
+// splitmix64's finalizer: nearby inputs give unrelated outputs.
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Random numbers for one task of a render: seeded from `seed`, the samples per pixel already
+// taken (`pass`) and the task's `index`, or from the thread's generator when unseeded.
+pub(crate) fn sampler(seed: Option<u64>, pass: u64, index: u64) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(mix(mix(seed ^ mix(pass)) ^ index)),
+        None => StdRng::from_rng(&mut rand::rng()),
+    }
+}
+
+// Named bundles of quality settings, from quick looks to finished frames. None clamps samples,
+// which would bias even final frames, and there is no denoiser or pixel filter to set;
+// regularization tames the worst fireflies and noisy pixel repair cleans up what is left.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Draft,
+    Preview,
+    Final,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Draft, Preset::Preview, Preset::Final];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Draft => "draft",
+            Preset::Preview => "preview",
+            Preset::Final => "final",
+        }
+    }
+
+    // Samples per pixel.
+    pub fn samples(self) -> u32 {
+        match self {
+            Preset::Draft => 4,
+            Preset::Preview => 32,
+            Preset::Final => 256,
+        }
+    }
+
+    pub fn settings(self) -> RenderSettings {
+        let settings = RenderSettings::default();
+        match self {
+            Preset::Draft => settings
+                .with_bounces(BounceLimits::default().with_total(4).with_diffuse(2))
+                .with_regularization(10f64.to_radians()),
+            Preset::Preview => settings
+                .with_bounces(BounceLimits::default().with_total(8).with_diffuse(4))
+                .with_regularization(3f64.to_radians()),
+            // All of `MAX_DEPTH`.
+            Preset::Final => settings.with_shadow_samples(2),
+        }
+    }
+
+    // Largest relative error left unrepaired, for `repair_pixels`.
+    pub fn repair(self) -> Option<f64> {
+        match self {
+            Preset::Draft | Preset::Preview => None,
+            Preset::Final => Some(0.05),
+        }
+    }
+}
+
+pub fn ray_color(ray: Ray, world: &dyn Hittable, depth: i32, rng: &mut dyn rand::RngCore) -> Color {
+    let lights = lights(world);
+    let settings = RenderSettings::default();
+    let ray_t = Interval::new(T_MIN, f64::INFINITY);
+    Integrator::new(world, &lights, &settings, None)
+        .trace(ray, depth, ray_t, None, rng)
+        .color
+}
+
+pub fn trace_path(ray: Ray, world: &dyn Hittable, rng: &mut dyn rand::RngCore) -> PathSample {
+    trace_path_range(ray, world, Interval::new(T_MIN, f64::INFINITY), rng)
+}
+
+// Like `trace_path`, but the camera ray only considers hits within `ray_t`, e.g. the near
+// and far clipping planes.
+pub fn trace_path_range(
+    ray: Ray,
+    world: &dyn Hittable,
+    ray_t: Interval,
+    rng: &mut dyn rand::RngCore,
+) -> PathSample {
+    let lights = lights(world);
+    trace_path_with(ray, world, &lights, ray_t, &RenderSettings::default(), rng)
+}
+
+// Like `trace_path_range` with explicit settings and the world's lights, gathered once by
+// `lights` rather than per path.
+pub fn trace_path_with(
+    ray: Ray,
+    world: &dyn Hittable,
+    lights: &[&dyn Light],
+    ray_t: Interval,
+    settings: &RenderSettings,
+    rng: &mut dyn rand::RngCore,
+) -> PathSample {
+    Integrator::new(world, lights, settings, None).trace(ray, 0, ray_t, None, rng)
+}
+
+// Like `trace_path_with`, steering diffuse bounces by `guide` and teaching it what the path
+// finds.
+pub fn trace_path_guided(
+    ray: Ray,
+    world: &dyn Hittable,
+    lights: &[&dyn Light],
+    guide: &PathGuide,
+    ray_t: Interval,
+    settings: &RenderSettings,
+    rng: &mut dyn rand::RngCore,
+) -> PathSample {
+    Integrator::new(world, lights, settings, Some(guide)).trace(ray, 0, ray_t, None, rng)
+}
+
+// Like `trace_path_with`, also recording the path's vertices for debugging; see `pathdump`.
+pub fn trace_path_recorded(
+    ray: Ray,
+    world: &dyn Hittable,
+    lights: &[&dyn Light],
+    ray_t: Interval,
+    settings: &RenderSettings,
+    rng: &mut dyn rand::RngCore,
+) -> PathSample {
+    let mut integrator = Integrator::new(world, lights, settings, None);
+    integrator.record = true;
+    integrator.trace(ray, 0, ray_t, None, rng)
+}
+
+// The first hit along `r` within `ray_t` that stops it, passing through filters such as thin
+// glass on the way and tinting `throughput` by them; see `Material::filter`.
+pub(crate) fn hit_filtered(
+    world: &dyn Hittable,
+    r: &Ray,
+    ray_t: Interval,
+    throughput: &mut Color,
+    rng: &mut dyn rand::RngCore,
+) -> Option<HitRecord> {
+    let rec = world.hit(r, ray_t)?;
+    pass_filters(world, r, ray_t, rec, throughput, rng)
+}
+
+// `hit_filtered` once `rec`, the closest hit within `ray_t`, has been found.
+fn pass_filters(
+    world: &dyn Hittable,
+    r: &Ray,
+    mut ray_t: Interval,
+    mut rec: HitRecord,
+    throughput: &mut Color,
+    rng: &mut dyn rand::RngCore,
+) -> Option<HitRecord> {
+    loop {
+        match rec.material.get().filter(r, &rec, rng) {
+            Some(tint) => {
+                *throughput *= tint;
+                ray_t.min = rec.t;
+                rec = world.hit(r, ray_t)?;
+            }
+            None => return Some(rec),
+        }
+    }
+}
+
+pub fn lights(world: &dyn Hittable) -> Vec<&dyn Light> {
+    let mut lights = Vec::new();
+    world.lights(&mut lights);
+    lights
+}
+
+#[inline]
+fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other * other);
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        0.0
+    }
+}
+
+// Density of a guided bounce: the mixture of the guide and the material's own sampling.
+#[inline]
+fn mixture_pdf(guide: &DirectionalDistribution, direction: Vec3, scattering_pdf: f64) -> f64 {
+    GUIDE_FRACTION * guide.pdf(direction) + (1.0 - GUIDE_FRACTION) * scattering_pdf
+}
+
+// A diffuse bounce the guide learns from once the rest of the path is known.
+struct GuidedBounce {
+    point: Point3,
+    direction: Vec3,
+    pdf: f64,
+    // Path throughput after the bounce.
+    throughput: Color,
+    // Radiance the path had gathered before it.
+    color: Color,
+}
+
+// Everything a path needs besides its ray.
+struct Integrator<'a> {
+    world: &'a dyn Hittable,
+    lights: &'a [&'a dyn Light],
+    settings: &'a RenderSettings,
+    guide: Option<&'a PathGuide>,
+    background: &'a dyn Background,
+    // Fill in `PathSample::path`.
+    record: bool,
+}
+
+impl<'a> Integrator<'a> {
+    fn new(
+        world: &'a dyn Hittable,
+        lights: &'a [&'a dyn Light],
+        settings: &'a RenderSettings,
+        guide: Option<&'a PathGuide>,
+    ) -> Self {
+        Self {
+            world,
+            lights,
+            settings,
+            guide,
+            background: world.background().unwrap_or(&SKY),
+            record: false,
+        }
+    }
+
+    // Shadow rays from the diffuse hit `rec` towards randomly chosen lights. Each contribution
+    // is reported with its light group. `attenuation` is what `scatter` returned, for
+    // `Material::scattering`. `guide` is the learned distribution continuing rays are partly
+    // drawn from, if any. Without `mis`, shadow rays take all the credit, as when the path ends
+    // here.
+    fn direct_light(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color,
+        guide: Option<&DirectionalDistribution>,
+        mis: bool,
+        rng: &mut dyn rand::RngCore,
+    ) -> Vec<(Option<Arc<str>>, Color)> {
+        let (lights, settings) = (self.lights, self.settings);
+        let n = settings.shadow_samples as f64;
+        let pick = 1.0 / lights.len() as f64;
+        let mut contributions = Vec::new();
+        for _ in 0..settings.shadow_samples {
+            let light = lights[rng.random_range(0..lights.len())];
+            let Some(ls) = light.sample(rec.point, rng) else {
+                continue;
+            };
+            let shadow =
+                Ray::with_time(rec.point, ls.direction, ray_in.time()).with_kind(RayKind::Shadow);
+            let scattering_pdf = rec.material.get().scattering_pdf(ray_in, rec, &shadow);
+            if scattering_pdf <= 0.0 || ls.pdf <= 0.0 {
+                continue;
+            }
+            let visible = self
+                .world
+                .transmittance(&shadow, Interval::new(T_MIN, ls.distance - T_MIN));
+            if visible == BLACK {
+                continue;
+            }
+            let light_pdf = pick * ls.pdf;
+            let weight = if mis {
+                let bounce_pdf = guide.map_or(scattering_pdf, |g| {
+                    mixture_pdf(g, ls.direction, scattering_pdf)
+                });
+                power_heuristic(n * light_pdf, bounce_pdf)
+            } else {
+                1.0
+            };
+            let reflected = rec
+                .material
+                .get()
+                .scattering(ray_in, rec, attenuation, &shadow);
+            let c = visible * reflected * ls.radiance * (weight / (n * light_pdf));
+            contributions.push((light.light_group(), c));
+        }
+        contributions
+    }
+
+    fn trace(
+        &self,
+        ray: Ray,
+        depth: i32,
+        primary_range: Interval,
+        reservoir: Option<&Reservoir>,
+        rng: &mut dyn rand::RngCore,
+    ) -> PathSample {
+        self.trace_from(ray, depth, primary_range, reservoir, None, rng)
+    }
+
+    // Like `trace`, with the camera ray's closest hit already found if `first` is set, e.g. by
+    // `Hittable::hit_batch`.
+    fn trace_from(
+        &self,
+        mut ray: Ray,
+        depth: i32,
+        primary_range: Interval,
+        mut reservoir: Option<&Reservoir>,
+        mut first: Option<Option<HitRecord>>,
+        rng: &mut dyn rand::RngCore,
+    ) -> PathSample {
+        let (world, lights, settings) = (self.world, self.lights, self.settings);
+        let mut sample = PathSample {
+            path: self.record.then(|| RecordedPath {
+                origin: ray.origin(),
+                ..Default::default()
+            }),
+            ..PathSample::black()
+        };
+        let mut lobe = None;
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let camera_ray = ray;
+        let sample_lights = settings.shadow_samples > 0 && !lights.is_empty();
+        // Set after a bounce that also sent shadow rays: the density with which the continuing
+        // ray was chosen, to weigh a light it hits against those shadow rays.
+        let mut nee_pdf: Option<f64> = None;
+        let mut guided = Vec::new();
+
+        let limits = settings.bounces;
+        // Bounces taken so far of each `Lobe`.
+        let mut counts = [0u32; 3];
+        for bounce in depth..limits.total as i32 {
+            let ray_t = if bounce == depth {
+                primary_range
+            } else {
+                Interval::new(T_MIN, f64::INFINITY)
+            };
+            let found = match first.take() {
+                Some(hit) => {
+                    hit.and_then(|rec| pass_filters(world, &ray, ray_t, rec, &mut throughput, rng))
+                }
+                None => hit_filtered(world, &ray, ray_t, &mut throughput, rng),
+            };
+            let Some(mut rec) = found else {
+                if let Some(path) = &mut sample.path {
+                    path.escaped = Some(ray.direction());
+                }
+                let mut radiance = self.background.radiance(&ray);
+                // Shadow rays may also have found it, as for emitters below.
+                if let Some(scattering_pdf) = nee_pdf {
+                    let light_pdf = self.background.light().and_then(|l| {
+                        let (_, pdf) = l.pdf(ray.origin(), ray.direction())?;
+                        Some(pdf / lights.len() as f64)
+                    });
+                    if let Some(light_pdf) = light_pdf {
+                        let n = settings.shadow_samples as f64;
+                        radiance *= if settings.mis {
+                            power_heuristic(scattering_pdf, n * light_pdf)
+                        } else {
+                            0.0
+                        };
+                    }
+                }
+                let c = throughput * radiance;
+                sample.color += c;
+                sample.classes[Lobe::class(lobe, bounce - depth) as usize] += c;
+                sample.background += c;
+                break;
+            };
+
+            // Holdouts block the path; seen directly they also punch a hole in the alpha.
+            if rec.holdout {
+                if let Some(path) = &mut sample.path {
+                    path.vertices
+                        .push(vertex(&rec, None, throughput, BLACK, BLACK));
+                }
+                if bounce == depth {
+                    sample.alpha = 0.0;
+                    sample.primary = Some(rec);
+                }
+                break;
+            }
+
+            if let Some(angle) = settings.regularization {
+                if counts[Lobe::Diffuse as usize] > 0 {
+                    if let Some(material) = rec.material.regularized(angle) {
+                        rec.material = material;
+                    }
+                }
+            }
+
+            // Looked up once per bounce; hits carry only the id.
+            let material = rec.material.get();
+            let color_before = sample.color;
+            let mut emitted = material.emitted(&rec);
+            if let Some(scattering_pdf) = nee_pdf.filter(|_| emitted != BLACK) {
+                // Only lights shadow rays could have found share the credit; other emitters
+                // keep theirs. The light hit is the one whose intersection matches this hit.
+                let light_pdf = lights.iter().find_map(|l| {
+                    let (t, pdf) = l.pdf(ray.origin(), ray.direction())?;
+                    // The environment is infinitely far, so never what was hit.
+                    (t.is_finite() && (t - rec.t).abs() <= 1e-6 * t.max(1.0)).then_some(pdf)
+                });
+                if let Some(light_pdf) = light_pdf {
+                    let n = settings.shadow_samples as f64;
+                    let light_pdf = light_pdf / lights.len() as f64;
+                    emitted *= if settings.mis {
+                        power_heuristic(scattering_pdf, n * light_pdf)
+                    } else {
+                        0.0
+                    };
+                }
+            }
+            if emitted != BLACK {
+                let c = throughput * emitted;
+                sample.color += c;
+                sample.classes[Lobe::class(lobe, bounce - depth) as usize] += c;
+                sample.emission.push((material.light_group(), c));
+            }
+
+            let color_emitted = sample.color;
+            let mut scattered = material.scatter(&ray, &rec, rng);
+            // Light resampled for the camera ray's hit, if this is still that hit.
+            let resampled = reservoir.take().filter(|r| r.is_for(rec.point));
+
+            nee_pdf = None;
+            let mut bounce_pdf = None;
+            // Set when this bounce would go past a limit, so the path ends here.
+            let mut stop = false;
+            if let Some((attenuation, continued)) = &mut scattered {
+                let scattering_pdf = material.scattering_pdf(&ray, &rec, continued);
+                let kind = Lobe::of(&rec, continued, scattering_pdf);
+                if bounce == depth {
+                    lobe = Some(kind);
+                    if let Lobe::Diffuse = kind {
+                        sample.albedo = Some(*attenuation);
+                    }
+                }
+                counts[kind as usize] += 1;
+                stop =
+                    counts[kind as usize] > limits.limit(kind) || bounce + 1 >= limits.total as i32;
+                // Shadow rays add one more event: light reaching this vertex.
+                let class = Lobe::class(lobe, bounce - depth + 1) as usize;
+                if scattering_pdf > 0.0 {
+                    let guide = self.guide.and_then(|g| g.distribution(rec.point));
+                    if let Some(r) = resampled {
+                        if let Some((group, c)) = r.shade(world, lights, &ray, &rec, *attenuation) {
+                            let c = throughput * c;
+                            sample.color += c;
+                            sample.classes[class] += c;
+                            sample.emission.push((group, c));
+                        }
+                    } else if sample_lights {
+                        let mis = settings.mis && !stop;
+                        let direct = self.direct_light(&ray, &rec, *attenuation, guide, mis, rng);
+                        for (group, c) in direct {
+                            let c = throughput * c;
+                            sample.color += c;
+                            sample.classes[class] += c;
+                            sample.emission.push((group, c));
+                        }
+                    }
+
+                    let mut pdf = scattering_pdf;
+                    if let Some(guide) = guide {
+                        if rng.random::<f64>() < GUIDE_FRACTION {
+                            *continued = Ray::with_time(rec.point, guide.sample(rng), ray.time());
+                        }
+                        let scattering_pdf = material.scattering_pdf(&ray, &rec, continued);
+                        pdf = mixture_pdf(guide, continued.direction(), scattering_pdf);
+                        *attenuation =
+                            material.scattering(&ray, &rec, *attenuation, continued) / pdf;
+                    }
+                    bounce_pdf = Some(pdf);
+                    if resampled.is_some() {
+                        // The reservoir already accounts for every light, so one hit by
+                        // chance adds nothing.
+                        nee_pdf = Some(0.0);
+                    } else if sample_lights {
+                        nee_pdf = Some(pdf);
+                    }
+                    if self.guide.is_some() && !stop {
+                        guided.push(GuidedBounce {
+                            point: rec.point,
+                            direction: continued.direction(),
+                            pdf,
+                            throughput: throughput * *attenuation,
+                            color: sample.color,
+                        });
+                    }
+                }
+            }
+
+            if stop {
+                scattered = None;
+            }
+            if let Some(path) = &mut sample.path {
+                let emitted = color_emitted - color_before;
+                let direct = sample.color - color_emitted;
+                let mut v = vertex(&rec, material.to_desc(), throughput, emitted, direct);
+                if let Some((attenuation, continued)) = &scattered {
+                    v.scattered = Some(continued.direction());
+                    v.attenuation = Some(*attenuation);
+                    v.pdf = bounce_pdf;
+                }
+                path.vertices.push(v);
+            }
+
+            if bounce == depth {
+                sample.primary = Some(rec);
+            }
+
+            match scattered {
+                Some((attenuation, scattered)) if attenuation != BLACK => {
+                    throughput *= attenuation;
+                    ray = scattered;
+                }
+                _ => break,
+            }
+        }
+
+        if let Some(guide) = self.guide {
+            for b in &guided {
+                guide.learn(
+                    b.point,
+                    b.direction,
+                    b.pdf,
+                    b.throughput,
+                    sample.color - b.color,
+                );
+            }
+        }
+
+        if let Some(atmosphere) = &settings.atmosphere {
+            let t = match &sample.primary {
+                Some(rec) => Some(rec.t),
+                None => (!atmosphere.surfaces_only).then_some(f64::INFINITY),
+            };
+            if let Some(t) = t.filter(|_| sample.alpha > 0.0) {
+                fog(&mut sample, atmosphere, &camera_ray, t);
+            }
+        }
+
+        sample
+    }
+}
+
+// Fades everything seen along `ray` up to `t` towards the haze color.
+fn fog(sample: &mut PathSample, atmosphere: &Atmosphere, ray: &Ray, t: f64) {
+    let transmittance = atmosphere.transmittance(ray, t);
+    sample.color = transmittance * sample.color + (1.0 - transmittance) * atmosphere.color;
+    sample.background *= transmittance;
+    for (_, c) in &mut sample.emission {
+        *c *= transmittance;
+    }
+    for c in &mut sample.classes {
+        *c *= transmittance;
+    }
+}
+
+// Progressive renderer owning its film, for callers that interleave rendering with display.
+pub struct Renderer {
+    world: Arc<dyn Hittable>,
+    camera: Camera,
+    film: Film,
+    aovs: AovSet,
+    settings: RenderSettings,
+    // Built on the first guided pass and refined after every pass.
+    guide: Option<PathGuide>,
+    samples: u32,
+    // Samples per pixel carried over when the camera moves; see `with_reprojection`.
+    history: Option<f64>,
+    control: RenderControl,
+}
+
+impl Renderer {
+    pub fn new(world: Arc<dyn Hittable>, camera: Camera, width: u32, height: u32) -> Self {
+        let aovs = AovSet::new(&[], world.as_ref(), width, height);
+        Self {
+            world,
+            camera,
+            film: Film::new(width, height),
+            aovs,
+            settings: RenderSettings::default(),
+            guide: None,
+            samples: 0,
+            history: None,
+            control: RenderControl::new(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: RenderSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    // Makes `set_camera` keep what still lines up after the move, worth up to `history`
+    // samples per pixel, instead of starting over; see `reproject`. AOVs start over anyway.
+    pub fn with_reprojection(mut self, history: f64) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    // Shares `control` with other renderers, e.g. to stop them all at once.
+    pub fn with_control(mut self, control: RenderControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    pub fn with_aovs(mut self, aovs: &[Aov]) -> Self {
+        self.aovs = AovSet::new(
+            aovs,
+            self.world.as_ref(),
+            self.film.width(),
+            self.film.height(),
+        );
+        self
+    }
+
+    // Adds `samples` per pixel to the film and returns a copy of the result so far. If
+    // cancelled it returns `Error::Cancelled` instead, with the samples taken before then
+    // already in `film`; `reset` the control to render more.
+    pub fn render_pass(&mut self, samples: u32) -> Result<Film> {
+        let world = self.world.as_ref();
+        if self.settings.guiding && self.guide.is_none() {
+            self.guide = PathGuide::for_world(world);
+        }
+        let lights = lights(world);
+        let integrator = Integrator::new(world, &lights, &self.settings, self.guide.as_ref());
+        let rendered = render_tiles(
+            &integrator,
+            &self.camera,
+            &self.film,
+            &self.aovs,
+            (self.samples, samples),
+            &self.control,
+            &|_| {},
+        );
+        // Some of a cancelled pass's samples are in the film, so later passes must not take
+        // them again.
+        self.samples += samples;
+        rendered?;
+        if let Some(guide) = &mut self.guide {
+            guide.refine();
+        }
+        self.film.snapshot()
+    }
+
+    // A handle for cancelling, pausing and resuming passes from other threads while
+    // `render_pass` runs.
+    pub fn control(&self) -> RenderControl {
+        self.control.clone()
+    }
+
+    pub fn pause(&self) {
+        self.control.pause();
+    }
+
+    pub fn resume(&self) {
+        self.control.resume();
+    }
+
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+
+    // Samples per pixel passes have taken, counting cancelled passes in full though pixels
+    // they didn't reach hold fewer. The next pass starts its sample sequence here.
+    #[inline]
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    #[inline]
+    pub fn film(&self) -> &Film {
+        &self.film
+    }
+
+    #[inline]
+    pub fn aovs(&self) -> &AovSet {
+        &self.aovs
+    }
+
+    // Switches to a new camera, e.g. after user interaction, and starts accumulating afresh,
+    // from the reprojected film if `with_reprojection` was set.
+    pub fn set_camera(&mut self, camera: Camera) -> Result<()> {
+        let Some(history) = self.history else {
+            self.camera = camera;
+            return self.reset();
+        };
+        let world = self.world.as_ref();
+        let film = reproject(world, &self.camera, &camera, &self.film, history)?;
+        self.camera = camera;
+        self.reset()?;
+        self.film = film;
+        Ok(())
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        self.film.clear()?;
+        self.aovs = AovSet::new(
+            &self.aovs.aovs(),
+            self.world.as_ref(),
+            self.film.width(),
+            self.film.height(),
+        );
+        self.samples = 0;
+        Ok(())
+    }
+}
+
+// Reported after each tile has been merged into the film.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TileProgress {
+    pub done: u32,
+    pub total: u32,
+    // Pixel bounds (x0, y0, x1, y1) of the finished tile, exclusive at the far end.
+    pub bounds: (u32, u32, u32, u32),
+}
+
+// Renders `samples` per pixel of the camera's view into `film` and `aovs`, one scanline per
+// task. Samples add to whatever the film already holds, so repeated calls refine the image,
+// unless the settings have a seed: then each call takes the same samples, and `Renderer`
+// refines instead. `progress` is called from worker threads.
+pub fn render_image(
+    world: &dyn Hittable,
+    camera: &Camera,
+    film: &Film,
+    aovs: &AovSet,
+    samples: u32,
+    progress: &(dyn Fn(TileProgress) + Sync),
+) -> Result<()> {
+    let settings = RenderSettings::default();
+    render_image_with(world, camera, film, aovs, samples, &settings, progress)
+}
+
+// `render_image` with explicit sampling settings.
+pub fn render_image_with(
+    world: &dyn Hittable,
+    camera: &Camera,
+    film: &Film,
+    aovs: &AovSet,
+    samples: u32,
+    settings: &RenderSettings,
+    progress: &(dyn Fn(TileProgress) + Sync),
+) -> Result<()> {
+    let control = RenderControl::new();
+    render_image_controlled(
+        world, camera, film, aovs, samples, settings, &control, progress,
+    )
+}
+
+// `render_image_with` that `control` can pause or cancel, e.g. from `progress`. Cancelled, it
+// returns `Error::Cancelled`, with the samples taken before then in `film`.
+#[allow(clippy::too_many_arguments)]
+pub fn render_image_controlled(
+    world: &dyn Hittable,
+    camera: &Camera,
+    film: &Film,
+    aovs: &AovSet,
+    samples: u32,
+    settings: &RenderSettings,
+    control: &RenderControl,
+    progress: &(dyn Fn(TileProgress) + Sync),
+) -> Result<()> {
+    let lights = lights(world);
+    let guide = settings
+        .guiding
+        .then(|| PathGuide::for_world(world))
+        .flatten();
+    let Some(mut guide) = guide else {
+        let integrator = Integrator::new(world, &lights, settings, None);
+        return render_tiles(
+            &integrator,
+            camera,
+            film,
+            aovs,
+            (0, samples),
+            control,
+            progress,
+        );
+    };
+
+    // Learn while rendering: each pass is guided by what the ones before it found.
+    let passes = guided_passes(samples);
+    let rows = film.height();
+    let total = passes.len() as u32 * rows;
+    let mut taken = 0;
+    for (n, &pass) in passes.iter().enumerate() {
+        let integrator = Integrator::new(world, &lights, settings, Some(&guide));
+        let done = n as u32 * rows;
+        render_tiles(
+            &integrator,
+            camera,
+            film,
+            aovs,
+            (taken, pass),
+            control,
+            &|p| {
+                progress(TileProgress {
+                    done: done + p.done,
+                    total,
+                    ..p
+                })
+            },
+        )?;
+        guide.refine();
+        taken += pass;
+    }
+    debug!(regions = guide.region_count(), "path guide trained");
+    Ok(())
+}
+
+// Samples per guided pass: doubling from 1, with the remainder folded into the last pass so it
+// is the largest.
+fn guided_passes(samples: u32) -> Vec<u32> {
+    let mut passes = Vec::new();
+    let (mut left, mut next) = (samples, 1);
+    while left > 0 {
+        let pass = if left - next.min(left) < 2 * next {
+            left
+        } else {
+            next
+        };
+        passes.push(pass);
+        left -= pass;
+        next = next.saturating_mul(2);
+    }
+    passes
+}
+
+fn render_tiles(
+    integrator: &Integrator,
+    camera: &Camera,
+    film: &Film,
+    aovs: &AovSet,
+    // Samples per pixel taken before this call, for seeding, and to take now.
+    (taken, samples): (u32, u32),
+    control: &RenderControl,
+    progress: &(dyn Fn(TileProgress) + Sync),
+) -> Result<()> {
+    let (num_x, num_y) = (film.width(), film.height());
+    let (du, dv) = (1.0 / num_x as f64, 1.0 / num_y as f64);
+    let restir = (integrator.settings.restir).filter(|_| {
+        camera.is_thin_lens() && !camera.has_chromatic_aberration() && !integrator.lights.is_empty()
+    });
+    // Worlds that intersect rays in batches, e.g. on a GPU, get each row's camera rays together.
+    // Chromatic aberration traces each channel separately, so it goes ray by ray.
+    let batch = integrator.world.prefers_batches() && !camera.has_chromatic_aberration();
+    let full = Interval::new(T_MIN, f64::INFINITY);
+    let rows_done = AtomicU32::new(0);
+    let render_span = info_span!("render", width = num_x, height = num_y, spp = samples);
+    let _render = render_span.enter();
+
+    (0..num_y).into_par_iter().try_for_each(|j| -> Result<()> {
+        if !control.proceed() {
+            return Err(Error::Cancelled);
+        }
+        let row = num_y - 1 - j;
+        let mut rng = sampler(integrator.settings.seed, taken as u64, row as u64);
+        let _tile = info_span!(parent: &render_span, "tile", row).entered();
+        let tile_start = Instant::now();
+
+        let mut buffer = RowBuffer::new(film, aovs, (0, num_x), row);
+        let bounds = buffer.tile.bounds();
+        let clamp = integrator.settings.clamp;
+        // True when cancelled partway, after which the row keeps what it has.
+        let sampled = catch_unwind(AssertUnwindSafe(|| {
+            if let Some(restir) = &restir {
+                for _s in 0..samples {
+                    if !control.proceed() {
+                        return true;
+                    }
+                    // The whole row's camera rays first, so pixels can share light samples.
+                    let uvs: Vec<(f64, f64)> = (0..num_x)
+                        .map(|i| {
+                            let u = (i as f64 + rng.random::<f64>()) / num_x as f64;
+                            let v = (j as f64 + rng.random::<f64>()) / num_y as f64;
+                            (u, v)
+                        })
+                        .collect();
+                    // A thin lens never blocks a ray.
+                    let Some(rays) = uvs
+                        .iter()
+                        .map(|&(u, v)| camera.get_ray_differential(u, v, du, dv, &mut rng))
+                        .collect::<Option<Vec<Ray>>>()
+                    else {
+                        continue;
+                    };
+                    let primaries: Vec<_> = rays
+                        .iter()
+                        .map(|&r| {
+                            let (world, lights) = (integrator.world, integrator.lights);
+                            let ray_t = camera.clip_range(&r, full);
+                            restir::primary(world, lights, r, ray_t, restir, &mut rng)
+                        })
+                        .collect();
+                    let reservoirs =
+                        restir::spatial_reuse(&primaries, integrator.lights, restir, &mut rng);
+                    for (i, ((&(u, v), &r), reservoir)) in
+                        (0..num_x).zip(uvs.iter().zip(&rays).zip(&reservoirs))
+                    {
+                        buffer.at = Some(i);
+                        let ray_t = camera.clip_range(&r, full);
+                        let sample = integrator.trace(r, 0, ray_t, reservoir.as_ref(), &mut rng);
+                        let col = camera.vignetting(u, v) * sample.color;
+                        buffer.add(camera, i, &sample, col, clamp);
+                    }
+                }
+            } else if batch {
+                for _s in 0..samples {
+                    if !control.proceed() {
+                        return true;
+                    }
+                    // The whole row's camera rays at once, so their first hits are found in
+                    // one batch.
+                    let uvs: Vec<(f64, f64)> = (0..num_x)
+                        .map(|i| {
+                            let u = (i as f64 + rng.random::<f64>()) / num_x as f64;
+                            let v = (j as f64 + rng.random::<f64>()) / num_y as f64;
+                            (u, v)
+                        })
+                        .collect();
+                    let rays: Vec<Option<(Ray, Interval)>> = uvs
+                        .iter()
+                        .map(|&(u, v)| {
+                            let r = camera.get_ray_differential(u, v, du, dv, &mut rng)?;
+                            Some((r, camera.clip_range(&r, full)))
+                        })
+                        .collect();
+                    let batch: Vec<(Ray, Interval)> = rays.iter().flatten().copied().collect();
+                    let mut hits = integrator.world.hit_batch(&batch).into_iter();
+                    for (i, (&(u, v), ray)) in (0..num_x).zip(uvs.iter().zip(&rays)) {
+                        buffer.at = Some(i);
+                        let sample = match ray {
+                            Some((r, ray_t)) => {
+                                let first = hits.next().flatten();
+                                integrator.trace_from(*r, 0, *ray_t, None, Some(first), &mut rng)
+                            }
+                            None => PathSample::black(),
+                        };
+                        let col = camera.vignetting(u, v) * sample.color;
+                        buffer.add(camera, i, &sample, col, clamp);
+                    }
+                }
+            } else {
+                for i in 0..num_x {
+                    if !control.proceed() {
+                        return true;
+                    }
+                    buffer.at = Some(i);
+                    for _s in 0..samples {
+                        let u = (i as f64 + rng.random::<f64>()) / num_x as f64;
+                        let v = (j as f64 + rng.random::<f64>()) / num_y as f64;
+                        let (sample, col) =
+                            camera_sample(integrator, camera, u, v, (du, dv), &mut rng);
+                        buffer.add(camera, i, &sample, col, clamp);
+                    }
+                }
+            }
+            false
+        }));
+        let stopped = match sampled {
+            Ok(stopped) => stopped,
+            Err(panic) => {
+                buffer.fault(film, aovs, samples, panic.as_ref());
+                false
+            }
+        };
+        buffer.merge(film, aovs)?;
+        if stopped {
+            return Err(Error::Cancelled);
+        }
+
+        debug!(
+            elapsed_ms = tile_start.elapsed().as_millis() as u64,
+            "tile finished"
+        );
+        progress(TileProgress {
+            done: rows_done.fetch_add(1, Ordering::Relaxed) + 1,
+            total: num_y,
+            bounds,
+        });
+        Ok(())
+    })
+}
+
+// What rows whose sampling panicked are filled with.
+pub const FAULT_COLOR: Color = Color::new(1.0, 0.0, 1.0);
+
+// One row's share of the film and AOVs while it is sampled, merged in once it is done.
+struct RowBuffer<'a> {
+    row: u32,
+    tile: FilmTile,
+    aovs: AovTiles<'a>,
+    quarantine: Quarantine,
+    // The pixel being sampled, for reporting panics.
+    at: Option<u32>,
+}
+
+impl<'a> RowBuffer<'a> {
+    // For pixels `x0..x1` of `row`.
+    fn new(film: &Film, aovs: &'a AovSet, (x0, x1): (u32, u32), row: u32) -> Self {
+        Self {
+            row,
+            tile: film.tile(x0, row, x1, row + 1),
+            aovs: aovs.tile(x0, row, x1, row + 1),
+            quarantine: Quarantine::default(),
+            at: None,
+        }
+    }
+
+    // Adds a camera sample to the AOVs and its color to the film, clamped to `clamp`
+    // luminance if set, and to the `Unclamped` AOV as it was. Samples with NaN or infinite
+    // values, e.g. from a material dividing by zero, are quarantined instead, so they can't
+    // poison the pixel.
+    fn add(
+        &mut self,
+        camera: &Camera,
+        x: u32,
+        sample: &PathSample,
+        col: Color,
+        clamp: Option<f64>,
+    ) {
+        if !finite(col) || !finite(sample.color) || !sample.alpha.is_finite() {
+            self.quarantine.add((x, self.row), sample);
+            return;
+        }
+        self.aovs.add_sample(x, self.row, camera, sample);
+        self.aovs.add_unclamped(x, self.row, col);
+        let col = clamp_sample(col, clamp);
+        self.tile
+            .add_sample_alpha(x, self.row, col, sample.alpha, 1.0);
+    }
+
+    // After sampling panicked: discards the row's samples and fills it with `FAULT_COLOR`,
+    // worth `samples` each, so one broken material doesn't abort the whole render.
+    fn fault(&mut self, film: &Film, aovs: &'a AovSet, samples: u32, panic: &(dyn Any + Send)) {
+        let message = (panic.downcast_ref::<&str>().copied())
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        warn!(row = self.row, x = ?self.at, "sampling panicked, filling the row: {message}");
+        let (x0, _, x1, _) = self.tile.bounds();
+        self.tile = film.tile(x0, self.row, x1, self.row + 1);
+        self.aovs = aovs.tile(x0, self.row, x1, self.row + 1);
+        for x in x0..x1 {
+            self.tile
+                .add_sample_alpha(x, self.row, FAULT_COLOR, 1.0, samples as f64);
+        }
+    }
+
+    fn merge(self, film: &Film, aovs: &AovSet) -> Result<()> {
+        self.quarantine.report(self.row);
+        film.merge_tile(self.tile)?;
+        aovs.merge_tile(self.aovs)
+    }
+}
+
+fn finite(c: Color) -> bool {
+    c.r().is_finite() && c.g().is_finite() && c.b().is_finite()
+}
+
+// Non-finite samples dropped from one tile, reported together so a broken material warns once
+// per tile rather than per sample.
+#[derive(Default)]
+struct Quarantine {
+    samples: u32,
+    // The first one's pixel, and the object and material its camera ray hit.
+    pixel: Option<(u32, u32)>,
+    hit: Option<(u32, MaterialId)>,
+}
+
+impl Quarantine {
+    fn add(&mut self, pixel: (u32, u32), sample: &PathSample) {
+        self.samples += 1;
+        if self.pixel.is_none() {
+            self.pixel = Some(pixel);
+            self.hit = sample.primary.as_ref().map(|r| (r.object_id, r.material));
+        }
+    }
+
+    fn report(&self, row: u32) {
+        let Some((x, y)) = self.pixel else {
+            return;
+        };
+        let (object, material) = self.hit.unzip();
+        warn!(
+            row,
+            samples = self.samples,
+            x,
+            y,
+            object,
+            material = ?material.and_then(|m| m.get().to_desc()),
+            "dropped NaN or infinite samples"
+        );
+    }
+}
+
+// `col` scaled down to at most `max` luminance. Non-finite colors pass through for
+// `noisy_pixels` to find.
+pub fn clamp_sample(col: Color, max: Option<f64>) -> Color {
+    let Some(max) = max else {
+        return col;
+    };
+    let l = luminance(col);
+    if l > max && l.is_finite() {
+        col * (max.max(0.0) / l)
+    } else {
+        col
+    }
+}
+
+// One camera sample through film coordinates (u, v): its path, for AOVs, and the color it adds
+// to the film. `(du, dv)` is the size of a pixel in film coordinates.
+fn camera_sample(
+    integrator: &Integrator,
+    camera: &Camera,
+    u: f64,
+    v: f64,
+    (du, dv): (f64, f64),
+    rng: &mut dyn rand::RngCore,
+) -> (PathSample, Color) {
+    let full = Interval::new(T_MIN, f64::INFINITY);
+    let trace = |ray: Option<Ray>, rng: &mut dyn rand::RngCore| match ray {
+        Some(ray) => integrator.trace(ray, 0, camera.clip_range(&ray, full), None, rng),
+        None => PathSample::black(),
+    };
+    let (sample, col) = if camera.has_chromatic_aberration() {
+        let r = camera.get_ray_for_channel(u, v, 0, rng);
+        let g = camera.get_ray_for_channel(u, v, 1, rng);
+        let b = camera.get_ray_for_channel(u, v, 2, rng);
+        let sample = trace(g, rng);
+        let col = Color::new(
+            trace(r, rng).color.r(),
+            sample.color.g(),
+            trace(b, rng).color.b(),
+        );
+        (sample, col)
+    } else {
+        let sample = trace(camera.get_ray_differential(u, v, du, dv, rng), rng);
+        let col = sample.color;
+        (sample, col)
+    };
+    (sample, camera.vignetting(u, v) * col)
+}
+
+// Pixels worth rendering again: those a NaN or infinite sample poisoned and, if `aovs` has
+// `Aov::Variance` or `Aov::RelativeError`, those whose relative error is above
+// `max_relative_error`. Film coordinates, y down.
+pub fn noisy_pixels(
+    film: &Film,
+    aovs: &AovSet,
+    max_relative_error: f64,
+) -> Result<Vec<(u32, u32)>> {
+    let mut pixels = Vec::new();
+    for y in 0..film.height() {
+        for x in 0..film.width() {
+            let noisy = !film.pixel(x, y)?.is_finite()
+                || aovs
+                    .relative_error(x, y)?
+                    .is_some_and(|e| e > max_relative_error);
+            if noisy {
+                pixels.push((x, y));
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+// Renders `samples` more per pixel into just `pixels` and merges them into `film` and `aovs`,
+// after discarding whatever poisoned pixels had, rather than re-rendering the frame. Paths are
+// traced without guiding or ReSTIR, which need the whole image.
+pub fn repair_pixels(
+    world: &dyn Hittable,
+    camera: &Camera,
+    film: &Film,
+    aovs: &AovSet,
+    pixels: &[(u32, u32)],
+    samples: u32,
+    settings: &RenderSettings,
+) -> Result<()> {
+    let lights = lights(world);
+    let integrator = Integrator::new(world, &lights, settings, None);
+    let (num_x, num_y) = (film.width(), film.height());
+    let (du, dv) = (1.0 / num_x as f64, 1.0 / num_y as f64);
+    let _span = info_span!("repair", pixels = pixels.len(), spp = samples).entered();
+    pixels.par_iter().try_for_each(|&(x, y)| -> Result<()> {
+        if x >= num_x || y >= num_y {
+            return Err(Error::Scene(format!(
+                "pixel ({x}, {y}) is outside the {num_x}x{num_y} film"
+            )));
+        }
+        if !film.pixel(x, y)?.is_finite() {
+            film.clear_pixel(x, y)?;
+            aovs.clear_pixel(x, y)?;
+        }
+        // Apart from every pass of the render itself.
+        let index = y as u64 * num_x as u64 + x as u64;
+        let mut rng = sampler(settings.seed, u64::MAX, index);
+        let mut buffer = RowBuffer::new(film, aovs, (x, x + 1), y);
+        buffer.at = Some(x);
+        let j = num_y - 1 - y;
+        let sampled = catch_unwind(AssertUnwindSafe(|| {
+            for _ in 0..samples {
+                let u = (x as f64 + rng.random::<f64>()) / num_x as f64;
+                let v = (j as f64 + rng.random::<f64>()) / num_y as f64;
+                let (sample, col) = camera_sample(&integrator, camera, u, v, (du, dv), &mut rng);
+                buffer.add(camera, x, &sample, col, settings.clamp);
+            }
+        }));
+        if let Err(panic) = sampled {
+            buffer.fault(film, aovs, samples, panic.as_ref());
+        }
+        buffer.merge(film, aovs)
+    })
+}
+
+fn vertex(
+    rec: &HitRecord,
+    material: Option<MaterialDesc>,
+    throughput: Color,
+    emitted: Color,
+    direct: Color,
+) -> PathVertex {
+    PathVertex {
+        point: rec.point,
+        normal: rec.normal,
+        front_face: rec.front_face,
+        object_id: rec.object_id,
+        material,
+        throughput,
+        emitted,
+        direct,
+        scattered: None,
+        attenuation: None,
+        pdf: None,
+    }
+}
@@ -0,0 +1,154 @@
+// Exposure analysis of a finished render: luminance statistics, a histogram in stops and a
+// false-color view, to judge how much of the image clips or crushes before picking exposure.
+// Luminance is measured against display white, 1.0, where `film::to_rgba` clips.
+
+use crate::color::luminance;
+use crate::error::{Error, Result};
+use crate::film::{to_rgba, Film};
+use crate::vec3::Color;
+use image::{Rgb, RgbImage};
+use serde::Serialize;
+use std::path::Path;
+
+// The histogram spans [MIN_STOPS, MAX_STOPS) stops around display white, BINS_PER_STOP bins a
+// stop. Luminance outside the range lands in the end bins.
+pub const MIN_STOPS: i32 = -12;
+pub const MAX_STOPS: i32 = 4;
+pub const BINS_PER_STOP: usize = 4;
+
+// Below this the output quantizes to black; over 1.0 it clips.
+pub const CRUSHED: f64 = 1.0 / 256.0;
+pub const MID_GREY: f64 = 0.18;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ExposureStats {
+    pub pixels: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    // Geometric mean, over pixels brighter than `CRUSHED`: the image's key.
+    pub log_average: f64,
+    // Fractions of pixels at or over display white, and under `CRUSHED`.
+    pub clipped: f64,
+    pub crushed: f64,
+    // Stops of exposure that would bring `log_average` to `MID_GREY`.
+    pub exposure_to_mid_grey: f64,
+    pub histogram: Vec<u32>,
+}
+
+impl ExposureStats {
+    pub fn new(colors: &[Color]) -> Self {
+        let bins = (MAX_STOPS - MIN_STOPS) as usize * BINS_PER_STOP;
+        let mut stats = ExposureStats {
+            pixels: colors.len(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            histogram: vec![0; bins],
+            ..Default::default()
+        };
+        let (mut log_sum, mut lit) = (0.0, 0);
+        for &c in colors {
+            let l = luminance(c);
+            if !l.is_finite() {
+                continue;
+            }
+            stats.min = stats.min.min(l);
+            stats.max = stats.max.max(l);
+            stats.mean += l;
+            if l >= 1.0 {
+                stats.clipped += 1.0;
+            }
+            if l < CRUSHED {
+                stats.crushed += 1.0;
+            } else {
+                log_sum += l.log2();
+                lit += 1;
+            }
+            stats.histogram[bin(l)] += 1;
+        }
+        // Nothing finite to measure.
+        if stats.min > stats.max {
+            (stats.min, stats.max) = (0.0, 0.0);
+        }
+        if colors.is_empty() {
+            return stats;
+        }
+        let n = colors.len() as f64;
+        stats.mean /= n;
+        stats.clipped /= n;
+        stats.crushed /= n;
+        if lit > 0 {
+            stats.log_average = (log_sum / lit as f64).exp2();
+            stats.exposure_to_mid_grey = (MID_GREY / stats.log_average).log2();
+        }
+        stats
+    }
+
+    // Lowest luminance histogram bin `bin` counts.
+    pub fn bin_luminance(bin: usize) -> f64 {
+        (MIN_STOPS as f64 + bin as f64 / BINS_PER_STOP as f64).exp2()
+    }
+}
+
+fn bin(luminance: f64) -> usize {
+    let bins = (MAX_STOPS - MIN_STOPS) as usize * BINS_PER_STOP;
+    if luminance <= 0.0 {
+        return 0;
+    }
+    let stops = luminance.log2() - MIN_STOPS as f64;
+    ((stops * BINS_PER_STOP as f64).floor().max(0.0) as usize).min(bins - 1)
+}
+
+// Exposure zones as colors: red clips, yellow is within a stop of clipping, green is within a
+// third of a stop of mid grey, blue crushes to black. Everything else is the displayed
+// luminance in grey.
+pub fn false_color(luminance: f64) -> Rgb<u8> {
+    if luminance >= 1.0 {
+        Rgb([255, 0, 0])
+    } else if luminance >= 0.5 {
+        Rgb([255, 220, 0])
+    } else if (luminance / MID_GREY).log2().abs() <= 1.0 / 3.0 {
+        Rgb([0, 200, 0])
+    } else if luminance < CRUSHED {
+        Rgb([40, 0, 160])
+    } else {
+        let [v, ..] = to_rgba(Color::new(luminance, luminance, luminance), 1.0).0;
+        Rgb([v, v, v])
+    }
+}
+
+// Statistics of `film`'s luminance and a report image: the false-color view with the histogram
+// below it, each bar colored by its zone.
+pub fn exposure_report(film: &Film, splat_scale: f64) -> Result<(ExposureStats, RgbImage)> {
+    let colors = film.resolve(splat_scale)?;
+    let stats = ExposureStats::new(&colors);
+    let (width, height) = (film.width(), film.height());
+    let panel = (height / 4).max(32);
+    let mut img = RgbImage::new(width, height + panel);
+    for (i, &c) in colors.iter().enumerate() {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        img.put_pixel(x, y, false_color(luminance(c)));
+    }
+    let tallest = stats.histogram.iter().copied().max().unwrap_or(0).max(1);
+    for x in 0..width {
+        let bin = x as usize * stats.histogram.len() / width as usize;
+        let bar = (stats.histogram[bin] as u64 * panel as u64 / tallest as u64) as u32;
+        let color = false_color(ExposureStats::bin_luminance(bin));
+        for y in 0..bar {
+            img.put_pixel(x, height + panel - 1 - y, color);
+        }
+    }
+    Ok((stats, img))
+}
+
+// Writes `report.png` and `report.json`, the statistics, into `dir`.
+pub fn save_exposure_report(film: &Film, splat_scale: f64, dir: &Path) -> Result<ExposureStats> {
+    let (stats, img) = exposure_report(film, splat_scale)?;
+    let path = dir.join("report.png");
+    img.save(&path).map_err(Error::image(&path))?;
+    std::fs::write(
+        dir.join("report.json"),
+        serde_json::to_string_pretty(&stats)?,
+    )?;
+    Ok(stats)
+}
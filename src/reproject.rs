@@ -0,0 +1,92 @@
+// Carrying an interactive preview's samples over to a moved camera, so small moves refine the
+// image rather than starting over. Each pixel of the new view takes what the pixel seeing the
+// same surface point in the old view accumulated, found by tracing both views' pixel centers
+// and comparing depths. Pixels that see something new, or the background, start empty. Lens
+// distortion is ignored.
+
+use crate::camera::Camera;
+use crate::error::Result;
+use crate::film::{Film, Pixel};
+use crate::hittable::Hittable;
+use crate::interval::Interval;
+use crate::render::T_MIN;
+use crate::vec3::Point3;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use tracing::debug;
+
+// How far apart, relative to its depth, a point and the old view's hit through its pixel may
+// be and still count as the same surface.
+const DEPTH_TOLERANCE: f64 = 0.02;
+
+// `film`, rendered through `from`, rearranged for `to`. Carried pixels keep at most `history`
+// samples' worth of weight, so shading that changes with the view, like reflections, catches
+// up within a few passes.
+pub fn reproject(
+    world: &dyn Hittable,
+    from: &Camera,
+    to: &Camera,
+    film: &Film,
+    history: f64,
+) -> Result<Film> {
+    let (width, height) = (film.width(), film.height());
+    let carried: Vec<(u32, u32, Pixel)> = (0..height)
+        .into_par_iter()
+        .map(|y| -> Result<Vec<(u32, u32, Pixel)>> {
+            let mut row = Vec::new();
+            for x in 0..width {
+                let Some(p) = center_hit(world, to, x, y, width, height) else {
+                    continue;
+                };
+                let Some(seen) = from.project(p) else {
+                    continue;
+                };
+                let old_x = ((seen.s * width as f64) as u32).min(width - 1);
+                let old_y = height - 1 - ((seen.t * height as f64) as u32).min(height - 1);
+                let Some(q) = center_hit(world, from, old_x, old_y, width, height) else {
+                    continue;
+                };
+                let depth = from.depth(p);
+                if (from.depth(q) - depth).abs() > DEPTH_TOLERANCE * depth {
+                    continue;
+                }
+                let pixel = film.pixel(old_x, old_y)?;
+                if pixel.weight_sum > 0.0 && pixel.is_finite() {
+                    row.push((x, y, pixel));
+                }
+            }
+            Ok(row)
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut out = Film::new(width, height);
+    for &(x, y, pixel) in &carried {
+        let weight = pixel.weight_sum.min(history.max(0.0));
+        let color = pixel.color_sum / pixel.weight_sum;
+        let alpha = pixel.alpha_sum / pixel.weight_sum;
+        out.add_sample_alpha(x, y, color, alpha, weight)?;
+    }
+    debug!(pixels = carried.len(), "reprojected");
+    Ok(out)
+}
+
+// What pixel (x, y)'s center sees, with y running down from the top.
+fn center_hit(
+    world: &dyn Hittable,
+    camera: &Camera,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Option<Point3> {
+    let s = (x as f64 + 0.5) / width as f64;
+    let t = (height as f64 - y as f64 - 0.5) / height as f64;
+    // Only the lens and shutter are random, and the same for every pixel.
+    let ray = camera.get_ray(s, t, &mut StdRng::seed_from_u64(0))?;
+    let ray_t = camera.clip_range(&ray, Interval::new(T_MIN, f64::INFINITY));
+    world.hit(&ray, ray_t).map(|rec| rec.point)
+}
@@ -0,0 +1,325 @@
+// ReSTIR DI: direct lighting for scenes with many lights, by resampling many light samples down
+// to the one that gets a shadow ray. At each camera ray's first hit, `candidates` light samples
+// are drawn as usual and streamed through a weighted reservoir that keeps one of them with
+// probability proportional to the unshadowed light it brings. Each pixel then merges the
+// reservoirs of a few nearby pixels on similar surfaces, so it effectively chooses from many
+// times the candidates it drew. Neighbours are taken from the same row, which one thread
+// renders together. Merging doesn't check whether a neighbour's light is visible from here,
+// which slightly darkens penumbrae; bounces after the first sample lights as usual.
+
+use crate::color::luminance;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::light::Light;
+use crate::ray::{Ray, RayKind};
+use crate::render::T_MIN;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// Neighbours must face within this cosine of each other and lie at a depth within this
+// fraction of each other to share light samples.
+const NORMAL_COSINE: f64 = 0.9;
+const DEPTH_FRACTION: f64 = 0.1;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Restir {
+    // Light samples drawn at each primary hit.
+    pub candidates: u32,
+    // Nearby pixels whose reservoirs each pixel merges.
+    pub neighbors: u32,
+    // How far along the row, in pixels, neighbours are picked from.
+    pub radius: u32,
+}
+
+impl Default for Restir {
+    fn default() -> Self {
+        Self {
+            candidates: 32,
+            neighbors: 4,
+            radius: 16,
+        }
+    }
+}
+
+impl Restir {
+    pub fn with_candidates(mut self, candidates: u32) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    pub fn with_neighbors(mut self, neighbors: u32, radius: u32) -> Self {
+        self.neighbors = neighbors;
+        self.radius = radius;
+        self
+    }
+}
+
+// A point on a light, which any shading point can evaluate.
+#[derive(Copy, Clone)]
+struct Candidate {
+    light: usize,
+    position: Position,
+    radiance: Color,
+}
+
+#[derive(Copy, Clone)]
+enum Position {
+    At(Point3),
+    // Infinitely far away, like an environment map, so the same direction from anywhere.
+    Towards(Vec3),
+}
+
+#[derive(Clone)]
+pub(crate) struct Reservoir {
+    // Shading point the reservoir was built for.
+    point: Point3,
+    sample: Option<Candidate>,
+    weight_sum: f64,
+    count: u32,
+    // Target density of `sample` at `point`.
+    target: f64,
+}
+
+impl Reservoir {
+    fn new(point: Point3) -> Self {
+        Self {
+            point,
+            sample: None,
+            weight_sum: 0.0,
+            count: 0,
+            target: 0.0,
+        }
+    }
+
+    // Streams in `count` candidates summarized by `sample`, kept in proportion to `weight`.
+    fn update(
+        &mut self,
+        sample: Candidate,
+        weight: f64,
+        target: f64,
+        count: u32,
+        rng: &mut dyn rand::RngCore,
+    ) {
+        self.count += count;
+        if !(weight > 0.0 && weight.is_finite()) {
+            return;
+        }
+        self.weight_sum += weight;
+        if rng.random::<f64>() * self.weight_sum < weight {
+            self.sample = Some(sample);
+            self.target = target;
+        }
+    }
+
+    // Unbiased estimate of one over the density the kept sample was chosen with.
+    fn contribution_weight(&self) -> f64 {
+        if self.count == 0 || self.target <= 0.0 {
+            return 0.0;
+        }
+        self.weight_sum / (self.count as f64 * self.target)
+    }
+
+    #[inline]
+    pub(crate) fn is_for(&self, point: Point3) -> bool {
+        (self.point - point).length_squared() <= 1e-12
+    }
+
+    // The kept sample's light at the diffuse hit `rec` if it passes a shadow ray, with its
+    // light group. `attenuation` is as in `Integrator::direct_light`.
+    pub(crate) fn shade(
+        &self,
+        world: &dyn Hittable,
+        lights: &[&dyn Light],
+        ray_in: &Ray,
+        rec: &HitRecord,
+        attenuation: Color,
+    ) -> Option<(Option<Arc<str>>, Color)> {
+        let sample = self.sample?;
+        let weight = self.contribution_weight();
+        let reach = reach(lights, ray_in, rec, &sample)?;
+        if weight <= 0.0 {
+            return None;
+        }
+        let visible =
+            world.transmittance(&reach.shadow, Interval::new(T_MIN, reach.distance - T_MIN));
+        if visible == Color::default() {
+            return None;
+        }
+        let reflected = rec
+            .material
+            .get()
+            .scattering(ray_in, rec, attenuation, &reach.shadow);
+        let c = visible * reflected * sample.radiance * weight;
+        Some((lights[sample.light].light_group(), c))
+    }
+}
+
+// A camera ray's first hit and the light samples resampled for it.
+pub(crate) struct Primary {
+    ray: Ray,
+    rec: HitRecord,
+    reservoir: Reservoir,
+}
+
+// How a light sample reaches a shading point.
+struct Reach {
+    // Unshadowed light up to the constant albedo: the density reservoirs resample towards.
+    target: f64,
+    shadow: Ray,
+    distance: f64,
+    // Solid-angle density with which the light samples that point.
+    light_pdf: f64,
+}
+
+// How `sample` reaches `rec`; None where its light doesn't.
+fn reach(
+    lights: &[&dyn Light],
+    ray_in: &Ray,
+    rec: &HitRecord,
+    sample: &Candidate,
+) -> Option<Reach> {
+    let (direction, distance) = match sample.position {
+        Position::At(p) => {
+            let offset = p - rec.point;
+            let distance = offset.length();
+            if distance <= 0.0 {
+                return None;
+            }
+            (offset / distance, distance)
+        }
+        Position::Towards(d) => (d, f64::INFINITY),
+    };
+    let (t, light_pdf) = lights[sample.light].pdf(rec.point, direction)?;
+    let found = if distance.is_finite() {
+        (t - distance).abs() <= 1e-6 * distance.max(1.0)
+    } else {
+        t == distance
+    };
+    if !found || light_pdf <= 0.0 {
+        return None;
+    }
+    let shadow = Ray::with_time(rec.point, direction, ray_in.time()).with_kind(RayKind::Shadow);
+    let target =
+        luminance(sample.radiance) * rec.material.get().scattering_pdf(ray_in, rec, &shadow);
+    (target > 0.0).then_some(Reach {
+        target,
+        shadow,
+        distance,
+        light_pdf,
+    })
+}
+
+// The first hit of `ray` within `ray_t` with its initial candidates, unless it misses or
+// hits a holdout.
+pub(crate) fn primary(
+    world: &dyn Hittable,
+    lights: &[&dyn Light],
+    ray: Ray,
+    ray_t: Interval,
+    restir: &Restir,
+    rng: &mut dyn rand::RngCore,
+) -> Option<Primary> {
+    let rec = world.hit(&ray, ray_t).filter(|rec| !rec.holdout)?;
+    let mut reservoir = Reservoir::new(rec.point);
+    if !lights.is_empty() {
+        let pick = 1.0 / lights.len() as f64;
+        for _ in 0..restir.candidates {
+            let light = rng.random_range(0..lights.len());
+            let candidate = lights[light].sample(rec.point, rng).map(|ls| {
+                let sample = Candidate {
+                    light,
+                    position: if ls.distance.is_finite() {
+                        Position::At(rec.point + ls.distance * ls.direction)
+                    } else {
+                        Position::Towards(ls.direction)
+                    },
+                    radiance: ls.radiance,
+                };
+                (sample, pick * ls.pdf)
+            });
+            match candidate
+                .and_then(|(s, pdf)| Some((s, pdf, reach(lights, &ray, &rec, &s)?.target)))
+            {
+                Some((sample, pdf, target)) => {
+                    reservoir.update(sample, target / pdf, target, 1, rng);
+                }
+                None => reservoir.count += 1,
+            }
+        }
+    }
+    Some(Primary {
+        ray,
+        rec,
+        reservoir,
+    })
+}
+
+fn similar(a: &Primary, b: &Primary) -> bool {
+    Vec3::dot(a.rec.normal, b.rec.normal) >= NORMAL_COSINE
+        && (a.rec.t - b.rec.t).abs() <= DEPTH_FRACTION * a.rec.t
+}
+
+// Each pixel's reservoir merged with those of random similar neighbours along the row.
+pub(crate) fn spatial_reuse(
+    primaries: &[Option<Primary>],
+    lights: &[&dyn Light],
+    restir: &Restir,
+    rng: &mut dyn rand::RngCore,
+) -> Vec<Option<Reservoir>> {
+    let mut merged = Vec::with_capacity(primaries.len());
+    for (i, own) in primaries.iter().enumerate() {
+        let Some(own) = own else {
+            merged.push(None);
+            continue;
+        };
+        let mut sources = vec![own];
+        if restir.radius > 0 {
+            for _ in 0..restir.neighbors {
+                let offset = rng.random_range(1..=restir.radius as usize);
+                let j = if rng.random::<bool>() {
+                    i.checked_sub(offset)
+                } else {
+                    Some(i + offset)
+                };
+                if let Some(Some(other)) = j.and_then(|j| primaries.get(j)) {
+                    if similar(own, other) {
+                        sources.push(other);
+                    }
+                }
+            }
+        }
+
+        let mut reservoir = Reservoir::new(own.rec.point);
+        for source in &sources {
+            let r = &source.reservoir;
+            let shifted = r.sample.and_then(|s| {
+                let here = reach(lights, &own.ray, &own.rec, &s)?;
+                let there = reach(lights, &source.ray, &source.rec, &s)?;
+                Some((s, here, there))
+            });
+            match shifted {
+                Some((sample, here, there)) => {
+                    // Weights are per unit solid angle at the point they were drawn for; lights
+                    // sample uniformly by area, so the ratio of their densities converts them.
+                    let jacobian = there.light_pdf / here.light_pdf;
+                    let weight = here.target * r.contribution_weight() * r.count as f64 * jacobian;
+                    reservoir.update(sample, weight, here.target, r.count, rng);
+                }
+                None => reservoir.count += r.count,
+            }
+        }
+        // Only pixels that could have drawn the kept sample count towards its weight.
+        if let Some(sample) = reservoir.sample {
+            reservoir.count = sources
+                .iter()
+                .filter(|s| reach(lights, &s.ray, &s.rec, &sample).is_some())
+                .map(|s| s.reservoir.count)
+                .sum();
+        }
+        merged.push(Some(reservoir));
+    }
+    merged
+}
@@ -0,0 +1,188 @@
+// Object placement. Poisson-disk sampling keeps every pair of points at least `min_distance`
+// apart without the visible regularity of a grid; an optional density map then thins the
+// points, e.g. to keep clearings free or fade out towards the edges.
+
+use crate::mesh::Geometry;
+use crate::procgen::scatter_on_surface;
+use crate::vec3::{Point3, Vec3};
+use rand::Rng;
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+pub struct Scatter {
+    min_distance: f64,
+    attempts: u32,
+    density: Option<Box<dyn Fn(Point3) -> f64 + Send + Sync>>,
+}
+
+impl Scatter {
+    pub fn new(min_distance: f64) -> Self {
+        Self {
+            min_distance,
+            attempts: 30,
+            density: None,
+        }
+    }
+
+    // Candidates tried around each point before giving up on it. More fills gaps better.
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    // Probability in [0, 1] of keeping a point at the given position.
+    pub fn with_density(mut self, density: impl Fn(Point3) -> f64 + Send + Sync + 'static) -> Self {
+        self.density = Some(Box::new(density));
+        self
+    }
+
+    fn keep(&self, p: Point3, rng: &mut impl Rng) -> bool {
+        self.density
+            .as_ref()
+            .is_none_or(|d| rng.random::<f64>() < d(p).clamp(0.0, 1.0))
+    }
+
+    // Points on the horizontal rectangle spanned by `min` and `max` in x and z, at `min.y`.
+    // Bridson's algorithm: grow outward from a seed, trying candidates in the annulus between
+    // one and two times the minimum distance around each active point.
+    pub fn plane(&self, min: Point3, max: Point3, rng: &mut impl Rng) -> Vec<Point3> {
+        let r = self.min_distance;
+        let (w, d) = (max.x - min.x, max.z - min.z);
+        if r <= 0.0 || w <= 0.0 || d <= 0.0 {
+            return Vec::new();
+        }
+        // With cells of r / sqrt(2) each holds at most one point.
+        let cell = r / 2f64.sqrt();
+        let (nx, nz) = ((w / cell).ceil() as usize, (d / cell).ceil() as usize);
+        let mut grid: Vec<Option<usize>> = vec![None; nx * nz];
+        let at = |x: f64, z: f64| {
+            (
+                ((x / cell) as usize).min(nx - 1),
+                ((z / cell) as usize).min(nz - 1),
+            )
+        };
+
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        let mut active = Vec::new();
+        let seed = (rng.random::<f64>() * w, rng.random::<f64>() * d);
+        let (i, j) = at(seed.0, seed.1);
+        grid[j * nx + i] = Some(0);
+        points.push(seed);
+        active.push(0);
+
+        while !active.is_empty() {
+            let slot = rng.random_range(0..active.len());
+            let (px, pz) = points[active[slot]];
+            let found = (0..self.attempts).find_map(|_| {
+                let angle = TAU * rng.random::<f64>();
+                let radius = r * (1.0 + rng.random::<f64>());
+                let (x, z) = (px + radius * angle.cos(), pz + radius * angle.sin());
+                if !(0.0..w).contains(&x) || !(0.0..d).contains(&z) {
+                    return None;
+                }
+                let (i, j) = at(x, z);
+                let near = (j.saturating_sub(2)..(j + 3).min(nz))
+                    .flat_map(|jj| (i.saturating_sub(2)..(i + 3).min(nx)).map(move |ii| (ii, jj)))
+                    .filter_map(|(ii, jj)| grid[jj * nx + ii])
+                    .any(|k| {
+                        let (qx, qz) = points[k];
+                        (qx - x).powi(2) + (qz - z).powi(2) < r * r
+                    });
+                (!near).then_some((x, z, i, j))
+            });
+            match found {
+                Some((x, z, i, j)) => {
+                    grid[j * nx + i] = Some(points.len());
+                    active.push(points.len());
+                    points.push((x, z));
+                }
+                None => {
+                    active.swap_remove(slot);
+                }
+            }
+        }
+
+        // Thinning a Poisson-disk set keeps the minimum distance.
+        points
+            .into_iter()
+            .map(|(x, z)| Point3::new(min.x + x, min.y, min.z + z))
+            .filter(|&p| self.keep(p, rng))
+            .collect()
+    }
+
+    // Points with their unit normals on a mesh surface. Candidates spread uniformly by area are
+    // accepted in turn unless they fall within the minimum distance of one already taken.
+    pub fn surface(&self, geometry: &Geometry, rng: &mut impl Rng) -> Vec<(Point3, Vec3)> {
+        let r = self.min_distance;
+        if r <= 0.0 {
+            return Vec::new();
+        }
+        let (positions, indices) = geometry;
+        let area: f64 = indices
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|i| positions[i as usize]);
+                0.5 * Vec3::cross(b - a, c - a).length()
+            })
+            .sum();
+        // Roughly `attempts` candidates per disk of radius r / 2 that fits on the surface.
+        let candidates =
+            (self.attempts as f64 * area / (0.25 * std::f64::consts::PI * r * r)).min(1e7) as usize;
+
+        let key = |p: Point3| {
+            (
+                (p.x / r).floor() as i64,
+                (p.y / r).floor() as i64,
+                (p.z / r).floor() as i64,
+            )
+        };
+        let mut grid: HashMap<(i64, i64, i64), Vec<Point3>> = HashMap::new();
+        let mut points = Vec::new();
+        for (p, n) in scatter_on_surface(geometry, candidates, rng) {
+            let (x, y, z) = key(p);
+            let near = (-1..=1).any(|dx| {
+                (-1..=1).any(|dy| {
+                    (-1..=1).any(|dz| {
+                        grid.get(&(x + dx, y + dy, z + dz))
+                            .is_some_and(|cell| cell.iter().any(|q| (*q - p).length() < r))
+                    })
+                })
+            });
+            if !near {
+                grid.entry((x, y, z)).or_default().push(p);
+                points.push((p, n));
+            }
+        }
+        points.retain(|&(p, _)| self.keep(p, rng));
+        points
+    }
+}
+
+// One point per `cell` sized square over the same rectangle as `Scatter::plane`, displaced
+// from the square's centre by up to `jitter` cells. Cheaper than Poisson-disk sampling but
+// clumpier; zero jitter gives a regular grid.
+pub fn jittered_grid(
+    min: Point3,
+    max: Point3,
+    cell: f64,
+    jitter: f64,
+    rng: &mut impl Rng,
+) -> Vec<Point3> {
+    if cell <= 0.0 {
+        return Vec::new();
+    }
+    let nx = ((max.x - min.x) / cell).floor().max(0.0) as usize;
+    let nz = ((max.z - min.z) / cell).floor().max(0.0) as usize;
+    let mut offset = || jitter * cell * (rng.random::<f64>() - 0.5);
+    let mut points = Vec::with_capacity(nx * nz);
+    for i in 0..nx {
+        for j in 0..nz {
+            points.push(Point3::new(
+                min.x + (i as f64 + 0.5) * cell + offset(),
+                min.y,
+                min.z + (j as f64 + 0.5) * cell + offset(),
+            ));
+        }
+    }
+    points
+}
@@ -0,0 +1,978 @@
+use crate::aov;
+use crate::background::{Background, Constant, Gradient, Hdri, SunSky};
+use crate::camera::{Camera, LensEffects, Shutter};
+use crate::color;
+use crate::error::{Error, Result};
+use crate::exposure::{LightPower, PhysicalExposure};
+use crate::hittable::{
+    ClipPlane, Clipped, Hittable, HittableList, Holdout, Masked, MovingSphere, Primitive, Sphere,
+    Visibility,
+};
+use crate::light::{QuadLight, SpotLight};
+use crate::material::{
+    CarPaint, Cutout, Dielectric, DiffuseLight, Lambertian, Material, MaterialId, MaterialRef,
+    MeasuredMetal, Metal, Plastic, ThinDielectric, Velvet,
+};
+use crate::math::Quat;
+use crate::mesh::{self, LodView, Mesh};
+use crate::paged::{GeometryCache, PagedMesh};
+use crate::stats::short_type_name;
+use crate::texture::TextureCache;
+use crate::vec3::{Color, Point3, Vec3};
+use crate::volume::{Emission, Field, Volume};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Serializable scene description. Runtime types hold `Arc<dyn ...>` trait objects, so files
+// and network messages go through this IR and are built into a world on load.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneDesc {
+    pub camera: CameraDesc,
+    // Objects refer to materials by index, so shared materials stay shared.
+    pub materials: Vec<MaterialDesc>,
+    pub objects: Vec<ObjectDesc>,
+    // Absent means the default sky.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<BackgroundDesc>,
+    // How long one scene unit is in meters, for lights given in lumens or watts. Absent means
+    // 1; see `exposure`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meters_per_unit: Option<f64>,
+}
+
+// Thin-lens camera parameters. The aspect ratio comes from the output resolution.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraDesc {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    pub vfov: f64,
+    #[serde(default)]
+    pub aperture: f64,
+    pub focus_dist: f64,
+    #[serde(default)]
+    pub lens: LensEffects,
+    #[serde(default)]
+    pub shutter: Shutter,
+    // Near and far clipping distances; JSON has no infinity, so absent means unclipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clipping: Option<(f64, f64)>,
+    // Film point to focus on instead of `focus_dist`, (0.5, 0.5) being the center; see
+    // `Camera::with_autofocus`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autofocus: Option<(f64, f64)>,
+    // ISO, shutter time and f-number the render is developed at; absent leaves radiance as
+    // it is. See `PhysicalExposure`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exposure: Option<PhysicalExposure>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackgroundDesc {
+    Constant {
+        #[serde(deserialize_with = "color::deserialize")]
+        color: Color,
+    },
+    Gradient {
+        #[serde(deserialize_with = "color::deserialize")]
+        bottom: Color,
+        #[serde(deserialize_with = "color::deserialize")]
+        top: Color,
+    },
+    Hdri {
+        path: PathBuf,
+        #[serde(default = "one")]
+        intensity: f64,
+        #[serde(default)]
+        rotation: f64,
+    },
+    SunSky {
+        sun_direction: Vec3,
+        #[serde(default = "default_turbidity")]
+        turbidity: f64,
+        #[serde(default = "one")]
+        intensity: f64,
+        #[serde(default = "default_sun_intensity")]
+        sun_intensity: f64,
+        #[serde(default = "default_ground", deserialize_with = "color::deserialize")]
+        ground: Color,
+    },
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+fn default_turbidity() -> f64 {
+    SunSky::new(Vec3::new(0.0, 1.0, 0.0)).turbidity
+}
+
+fn default_sun_intensity() -> f64 {
+    SunSky::new(Vec3::new(0.0, 1.0, 0.0)).sun_intensity
+}
+
+fn default_spot_radius() -> f64 {
+    0.05
+}
+
+fn default_ground() -> Color {
+    SunSky::new(Vec3::new(0.0, 1.0, 0.0)).ground
+}
+
+fn default_plastic_ior() -> f64 {
+    1.5
+}
+
+fn default_flake_color() -> Color {
+    CarPaint::new(Color::default()).flake_color
+}
+
+fn default_flake_density() -> f64 {
+    CarPaint::new(Color::default()).flake_density
+}
+
+fn default_flake_size() -> f64 {
+    CarPaint::new(Color::default()).flake_size
+}
+
+fn default_flake_spread() -> f64 {
+    CarPaint::new(Color::default()).flake_spread
+}
+
+fn default_velvet_roughness() -> f64 {
+    0.3
+}
+
+fn white() -> Color {
+    Color::new(1.0, 1.0, 1.0)
+}
+
+fn is_white(c: &Color) -> bool {
+    *c == white()
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MaterialDesc {
+    Lambertian {
+        #[serde(deserialize_with = "color::deserialize")]
+        albedo: Color,
+    },
+    // With `measured`, reflects as that metal does, tinted by `albedo`.
+    Metal {
+        #[serde(default = "white", deserialize_with = "color::deserialize")]
+        albedo: Color,
+        fuzz: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        measured: Option<MeasuredMetal>,
+    },
+    Dielectric {
+        ior: f64,
+    },
+    // Glass with no thickness, for windows and bubbles, tinting what it lets through; see
+    // `ThinDielectric`.
+    ThinDielectric {
+        ior: f64,
+        #[serde(
+            default = "white",
+            deserialize_with = "color::deserialize",
+            skip_serializing_if = "is_white"
+        )]
+        tint: Color,
+    },
+    // A diffuse `albedo` under a clear coat of index `ior`, smooth or spread by `roughness`
+    // from 0 to 1; see `Plastic`.
+    Plastic {
+        #[serde(deserialize_with = "color::deserialize")]
+        albedo: Color,
+        #[serde(default = "default_plastic_ior")]
+        ior: f64,
+        #[serde(default)]
+        roughness: f64,
+    },
+    // Pigment `color` under a clearcoat with metal flakes in it; see `CarPaint`.
+    CarPaint {
+        #[serde(deserialize_with = "color::deserialize")]
+        color: Color,
+        #[serde(default = "default_plastic_ior")]
+        ior: f64,
+        #[serde(
+            default = "default_flake_color",
+            deserialize_with = "color::deserialize"
+        )]
+        flake_color: Color,
+        #[serde(default = "default_flake_density")]
+        flake_density: f64,
+        #[serde(default = "default_flake_size")]
+        flake_size: f64,
+        #[serde(default = "default_flake_spread")]
+        flake_spread: f64,
+    },
+    // Cloth: diffuse `albedo` with a `sheen` at grazing angles, broader as `roughness` goes
+    // from 0 to 1; see `Velvet`.
+    Velvet {
+        #[serde(deserialize_with = "color::deserialize")]
+        albedo: Color,
+        #[serde(
+            default = "white",
+            deserialize_with = "color::deserialize",
+            skip_serializing_if = "is_white"
+        )]
+        sheen: Color,
+        #[serde(default = "default_velvet_roughness")]
+        roughness: f64,
+    },
+    DiffuseLight {
+        #[serde(deserialize_with = "color::deserialize")]
+        emit: Color,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
+    },
+    // `material` with holes where the `opacity` image is dark, loaded into the shared
+    // `TextureCache`.
+    Cutout {
+        material: Box<MaterialDesc>,
+        opacity: PathBuf,
+    },
+    // Diffuse surface colored by a Rhai script, reloaded when it changes; see `script`. Needs
+    // the `script` feature.
+    Script {
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectDesc {
+    Sphere {
+        center: Point3,
+        radius: f64,
+        material: usize,
+    },
+    MovingSphere {
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: usize,
+    },
+    Holdout {
+        object: Box<ObjectDesc>,
+    },
+    // `object` seen only by some kinds of ray, e.g. a bounce card hidden from the camera.
+    Masked {
+        object: Box<ObjectDesc>,
+        #[serde(default)]
+        visibility: Visibility,
+    },
+    Clipped {
+        object: Box<ObjectDesc>,
+        planes: Vec<ClipPlane>,
+    },
+    List {
+        objects: Vec<ObjectDesc>,
+    },
+    // Rectangular area light; see `QuadLight`.
+    QuadLight {
+        corner: Point3,
+        u: Vec3,
+        v: Vec3,
+        #[serde(deserialize_with = "color::deserialize")]
+        emit: Color,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        two_sided: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
+        // Output in lumens or watts, making `emit` only the light's color; see `LightPower`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        power: Option<LightPower>,
+    },
+    // Disk-shaped spot light, optionally projecting the `gobo` image; see `SpotLight`. `angle`
+    // is the beam's half-angle in degrees and `up` the top of the gobo.
+    SpotLight {
+        position: Point3,
+        direction: Vec3,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        up: Option<Vec3>,
+        #[serde(default = "default_spot_radius")]
+        radius: f64,
+        angle: f64,
+        #[serde(default)]
+        softness: f64,
+        #[serde(deserialize_with = "color::deserialize")]
+        emit: Color,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gobo: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
+    },
+    // Participating medium filling `boundary`, or the density field's bounds if there is
+    // none; see `Volume`.
+    Volume {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        boundary: Option<Box<ObjectDesc>>,
+        density: Field,
+        #[serde(deserialize_with = "color::deserialize")]
+        albedo: Color,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        emission: Option<Emission>,
+    },
+    // Triangles given inline, or loaded from a Wavefront OBJ file when `obj` is set.
+    Mesh {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        positions: Vec<Point3>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        indices: Vec<[u32; 3]>,
+        // Texture coordinates per position, for inline triangles.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        uvs: Vec<[f64; 2]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        obj: Option<PathBuf>,
+        material: usize,
+        // Decimate to about this many triangles on load, e.g. to preview a heavy scan.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target_triangles: Option<usize>,
+        // Also reduce detail to what the camera can resolve; see `SceneDesc::build_lod`.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        lod: bool,
+    },
+    // Triangles streamed from a geometry file saved by `paged::write` (`rtt page`), loaded a
+    // chunk at a time into the shared `GeometryCache`.
+    PagedMesh {
+        path: PathBuf,
+        material: usize,
+    },
+}
+
+// Materials collected during export, each shared material saved once.
+#[derive(Default)]
+pub struct MaterialTable {
+    ids: Vec<MaterialId>,
+    descs: Vec<MaterialDesc>,
+}
+
+impl MaterialTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Index of `material` in the exported list, or None if it can't be saved.
+    pub fn index(&mut self, material: MaterialId) -> Option<usize> {
+        if let Some(i) = self.ids.iter().position(|&m| m == material) {
+            return Some(i);
+        }
+        self.descs.push(material.get().to_desc()?);
+        self.ids.push(material);
+        Some(self.descs.len() - 1)
+    }
+
+    pub fn into_descs(self) -> Vec<MaterialDesc> {
+        self.descs
+    }
+}
+
+impl SceneDesc {
+    // Inverse of `build`: describes a programmatically built world for saving.
+    pub fn from_world(world: &HittableList, camera: CameraDesc) -> Result<Self> {
+        let mut materials = MaterialTable::new();
+        let objects = world
+            .iter()
+            .map(|o| {
+                o.to_desc(&mut materials)
+                    .ok_or_else(|| Error::Scene(format!("{} can't be exported", o.type_name())))
+            })
+            .collect::<Result<_>>()?;
+        let background = match &world.background {
+            Some(b) => Some(b.to_desc().ok_or_else(|| {
+                Error::Scene(format!("{} can't be exported", short_type_name(&**b)))
+            })?),
+            None => None,
+        };
+        Ok(Self {
+            camera,
+            materials: materials.into_descs(),
+            objects,
+            background,
+            meters_per_unit: None,
+        })
+    }
+
+    // The objects with lights given in lumens or watts turned into radiance, at
+    // `meters_per_unit`. Borrowed when there are none.
+    pub fn objects_in_units(&self) -> Result<Cow<'_, [ObjectDesc]>> {
+        if !self.objects.iter().any(ObjectDesc::has_power) {
+            return Ok(Cow::Borrowed(&self.objects));
+        }
+        let meters = self.meters_per_unit.unwrap_or(1.0);
+        if !(meters > 0.0 && meters.is_finite()) {
+            return Err(Error::Scene(format!(
+                "meters_per_unit must be positive, got {meters}"
+            )));
+        }
+        let mut objects = self.objects.clone();
+        for o in &mut objects {
+            o.resolve_power(meters);
+        }
+        Ok(Cow::Owned(objects))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn build(&self, aspect_ratio: f64) -> Result<(HittableList, Camera)> {
+        self.build_with(aspect_ratio, None)
+    }
+
+    // Like `build`, also decimating meshes marked `lod` to what an `image_height` pixel tall
+    // render of them can resolve.
+    pub fn build_lod(
+        &self,
+        aspect_ratio: f64,
+        image_height: u32,
+    ) -> Result<(HittableList, Camera)> {
+        let view = LodView {
+            eye: self.camera.look_from,
+            vfov: self.camera.vfov,
+            image_height,
+        };
+        self.build_with(aspect_ratio, Some(&view))
+    }
+
+    fn build_with(
+        &self,
+        aspect_ratio: f64,
+        lod: Option<&LodView>,
+    ) -> Result<(HittableList, Camera)> {
+        let materials: Vec<MaterialRef> = self
+            .materials
+            .iter()
+            .map(|m| MaterialRef::new(m.build()))
+            .collect::<Result<_>>()?;
+        let mut world = HittableList::new();
+        for object in self.objects_in_units()?.iter() {
+            world.add(object.build_with(&materials, lod)?);
+        }
+        aov::check_light_groups(&world)?;
+        if let Some(background) = &self.background {
+            world.set_background(background.build()?);
+        }
+        let camera = self.camera.build_in(aspect_ratio, &world);
+        Ok((world, camera))
+    }
+
+    // Every file the scene refers to, e.g. to resolve them against another directory.
+    pub fn paths_mut(&mut self) -> Vec<&mut PathBuf> {
+        let mut out = Vec::new();
+        if let Some(BackgroundDesc::Hdri { path, .. }) = &mut self.background {
+            out.push(path);
+        }
+        for m in &mut self.materials {
+            m.collect_paths(&mut out);
+        }
+        for o in &mut self.objects {
+            o.collect_paths(&mut out);
+        }
+        out
+    }
+}
+
+impl BackgroundDesc {
+    pub fn build(&self) -> Result<Arc<dyn Background>> {
+        Ok(match self {
+            BackgroundDesc::Constant { color } => Arc::new(Constant::new(*color)),
+            BackgroundDesc::Gradient { bottom, top } => Arc::new(Gradient::new(*bottom, *top)),
+            BackgroundDesc::Hdri {
+                path,
+                intensity,
+                rotation,
+            } => Arc::new(
+                Hdri::load(path)?
+                    .with_intensity(*intensity)
+                    .with_rotation(*rotation),
+            ),
+            BackgroundDesc::SunSky {
+                sun_direction,
+                turbidity,
+                intensity,
+                sun_intensity,
+                ground,
+            } => {
+                if sun_direction.y <= 0.0 {
+                    return Err(Error::Scene(
+                        "sun_sky needs the sun above the horizon".to_string(),
+                    ));
+                }
+                let mut sky = SunSky::new(*sun_direction)
+                    .with_turbidity(*turbidity)
+                    .with_intensity(*intensity, *sun_intensity);
+                sky.ground = *ground;
+                Arc::new(sky)
+            }
+        })
+    }
+}
+
+// A pinhole one unit up the z axis looking back at the origin.
+impl Default for CameraDesc {
+    fn default() -> Self {
+        Self {
+            look_from: Point3::new(0.0, 0.0, 1.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 40.0,
+            aperture: 0.0,
+            focus_dist: 1.0,
+            lens: LensEffects::default(),
+            shutter: Shutter::default(),
+            clipping: None,
+            autofocus: None,
+            exposure: None,
+        }
+    }
+}
+
+impl CameraDesc {
+    // The same camera swung `angle` radians around the look-at point, about `vup`. Useful for
+    // turntables.
+    pub fn orbit(&self, angle: f64) -> CameraDesc {
+        let spin = Quat::from_axis_angle(Vec3::unit_vector(self.vup), angle);
+        CameraDesc {
+            look_from: self.look_at + spin.rotate(self.look_from - self.look_at),
+            ..self.clone()
+        }
+    }
+
+    pub fn build(&self, aspect_ratio: f64) -> Camera {
+        let camera = Camera::new(
+            self.look_from,
+            self.look_at,
+            self.vup,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+        )
+        .with_lens_effects(self.lens)
+        .with_shutter(self.shutter.clone());
+        match self.clipping {
+            Some((near, far)) => camera.with_clipping(near, far),
+            None => camera,
+        }
+    }
+
+    // Like `build`, also focusing on `world` if `autofocus` is set.
+    pub fn build_in(&self, aspect_ratio: f64, world: &dyn Hittable) -> Camera {
+        let camera = self.build(aspect_ratio);
+        match self.autofocus {
+            Some((s, t)) => camera.with_autofocus(world, s, t),
+            None => camera,
+        }
+    }
+}
+
+impl MaterialDesc {
+    fn collect_paths<'a>(&'a mut self, out: &mut Vec<&'a mut PathBuf>) {
+        match self {
+            MaterialDesc::Cutout { material, opacity } => {
+                out.push(opacity);
+                material.collect_paths(out);
+            }
+            MaterialDesc::Script { path } => out.push(path),
+            _ => {}
+        }
+    }
+
+    pub fn build(&self) -> Arc<dyn Material> {
+        match self {
+            MaterialDesc::Lambertian { albedo } => Arc::new(Lambertian::new(*albedo)),
+            MaterialDesc::Metal {
+                albedo,
+                fuzz,
+                measured,
+            } => {
+                let metal = Metal::new(*albedo, *fuzz);
+                Arc::new(match measured {
+                    Some(m) => metal.with_measured(*m),
+                    None => metal,
+                })
+            }
+            MaterialDesc::Dielectric { ior } => Arc::new(Dielectric::new(*ior)),
+            MaterialDesc::ThinDielectric { ior, tint } => {
+                Arc::new(ThinDielectric::new(*ior).with_tint(*tint))
+            }
+            MaterialDesc::Plastic {
+                albedo,
+                ior,
+                roughness,
+            } => Arc::new(Plastic::new(*albedo, *ior).with_roughness(*roughness)),
+            MaterialDesc::CarPaint {
+                color,
+                ior,
+                flake_color,
+                flake_density,
+                flake_size,
+                flake_spread,
+            } => Arc::new(
+                CarPaint::new(*color)
+                    .with_ior(*ior)
+                    .with_flakes(*flake_color, *flake_density)
+                    .with_flake_size(*flake_size)
+                    .with_flake_spread(*flake_spread),
+            ),
+            MaterialDesc::Velvet {
+                albedo,
+                sheen,
+                roughness,
+            } => Arc::new(Velvet::new(*albedo, *roughness).with_sheen(*sheen)),
+            MaterialDesc::DiffuseLight { emit, group } => {
+                let light = DiffuseLight::new(*emit);
+                match group {
+                    Some(g) => Arc::new(light.with_group(g)),
+                    None => Arc::new(light),
+                }
+            }
+            MaterialDesc::Cutout { material, opacity } => Arc::new(Cutout::new(
+                material.build(),
+                TextureCache::shared().texture(opacity),
+            )),
+            MaterialDesc::Script { path } => script_material(path),
+        }
+    }
+}
+
+#[cfg(feature = "script")]
+fn script_material(path: &Path) -> Arc<dyn Material> {
+    use crate::script::{Script, ScriptedMaterial};
+    Arc::new(ScriptedMaterial::new(Script::load(path)))
+}
+
+// Magenta, as scripts that fail to load render.
+#[cfg(not(feature = "script"))]
+fn script_material(path: &Path) -> Arc<dyn Material> {
+    tracing::warn!(
+        path = %path.display(),
+        "can't run material scripts: built without the `script` feature"
+    );
+    Arc::new(Lambertian::new(Color::new(1.0, 0.0, 1.0)))
+}
+
+impl ObjectDesc {
+    pub fn build(&self, materials: &[Arc<dyn Material>]) -> Result<Arc<dyn Hittable>> {
+        let materials: Vec<MaterialRef> = materials
+            .iter()
+            .cloned()
+            .map(MaterialRef::new)
+            .collect::<Result<_>>()?;
+        self.build_with(&materials, None).map(Primitive::into_arc)
+    }
+
+    // Indices of the materials the object and anything inside it use, in order.
+    pub fn material_indices(&self) -> Vec<usize> {
+        let mut out = Vec::new();
+        self.collect_material_indices(&mut out);
+        out
+    }
+
+    fn collect_material_indices(&self, out: &mut Vec<usize>) {
+        match self {
+            ObjectDesc::Sphere { material, .. }
+            | ObjectDesc::MovingSphere { material, .. }
+            | ObjectDesc::Mesh { material, .. }
+            | ObjectDesc::PagedMesh { material, .. } => out.push(*material),
+            ObjectDesc::Holdout { object }
+            | ObjectDesc::Masked { object, .. }
+            | ObjectDesc::Clipped { object, .. } => object.collect_material_indices(out),
+            ObjectDesc::List { objects } => {
+                for o in objects {
+                    o.collect_material_indices(out);
+                }
+            }
+            ObjectDesc::Volume { boundary, .. } => {
+                if let Some(b) = boundary {
+                    b.collect_material_indices(out);
+                }
+            }
+            ObjectDesc::QuadLight { .. } | ObjectDesc::SpotLight { .. } => {}
+        }
+    }
+
+    fn has_power(&self) -> bool {
+        match self {
+            ObjectDesc::QuadLight { power, .. } => power.is_some(),
+            ObjectDesc::Holdout { object }
+            | ObjectDesc::Masked { object, .. }
+            | ObjectDesc::Clipped { object, .. } => object.has_power(),
+            ObjectDesc::List { objects } => objects.iter().any(ObjectDesc::has_power),
+            ObjectDesc::Volume { boundary, .. } => boundary.as_ref().is_some_and(|b| b.has_power()),
+            ObjectDesc::Sphere { .. }
+            | ObjectDesc::MovingSphere { .. }
+            | ObjectDesc::Mesh { .. }
+            | ObjectDesc::PagedMesh { .. }
+            | ObjectDesc::SpotLight { .. } => false,
+        }
+    }
+
+    // Replaces light power with the radiance giving it, with areas `meters_per_unit` squared
+    // times their size in scene units.
+    fn resolve_power(&mut self, meters_per_unit: f64) {
+        match self {
+            ObjectDesc::QuadLight {
+                u,
+                v,
+                emit,
+                two_sided,
+                power,
+                ..
+            } => {
+                if let Some(p) = power.take() {
+                    let area = Vec3::cross(*u, *v).length() * meters_per_unit * meters_per_unit;
+                    *emit = p.radiance(*emit, area, *two_sided);
+                }
+            }
+            ObjectDesc::Holdout { object }
+            | ObjectDesc::Masked { object, .. }
+            | ObjectDesc::Clipped { object, .. } => object.resolve_power(meters_per_unit),
+            ObjectDesc::List { objects } => {
+                for o in objects {
+                    o.resolve_power(meters_per_unit);
+                }
+            }
+            ObjectDesc::Volume { boundary, .. } => {
+                if let Some(b) = boundary {
+                    b.resolve_power(meters_per_unit);
+                }
+            }
+            ObjectDesc::Sphere { .. }
+            | ObjectDesc::MovingSphere { .. }
+            | ObjectDesc::Mesh { .. }
+            | ObjectDesc::PagedMesh { .. }
+            | ObjectDesc::SpotLight { .. } => {}
+        }
+    }
+
+    fn collect_paths<'a>(&'a mut self, out: &mut Vec<&'a mut PathBuf>) {
+        match self {
+            ObjectDesc::Mesh {
+                obj: Some(path), ..
+            }
+            | ObjectDesc::PagedMesh { path, .. }
+            | ObjectDesc::SpotLight {
+                gobo: Some(path), ..
+            } => out.push(path),
+            ObjectDesc::Holdout { object }
+            | ObjectDesc::Masked { object, .. }
+            | ObjectDesc::Clipped { object, .. } => object.collect_paths(out),
+            ObjectDesc::List { objects } => {
+                for o in objects {
+                    o.collect_paths(out);
+                }
+            }
+            ObjectDesc::Volume {
+                boundary, density, ..
+            } => {
+                if let Field::Vdb { path, .. } = density {
+                    out.push(path);
+                }
+                if let Some(b) = boundary {
+                    b.collect_paths(out);
+                }
+            }
+            ObjectDesc::Sphere { .. }
+            | ObjectDesc::MovingSphere { .. }
+            | ObjectDesc::Mesh { .. }
+            | ObjectDesc::QuadLight { .. }
+            | ObjectDesc::SpotLight { .. } => {}
+        }
+    }
+
+    // Spheres come back by value, ready to store inline in a list.
+    pub(crate) fn build_with(
+        &self,
+        materials: &[MaterialRef],
+        lod: Option<&LodView>,
+    ) -> Result<Primitive> {
+        let material = |i: usize| {
+            materials.get(i).cloned().ok_or_else(|| {
+                Error::Scene(format!(
+                    "material index {i} out of range ({} materials)",
+                    materials.len()
+                ))
+            })
+        };
+        let object: Arc<dyn Hittable> = match self {
+            ObjectDesc::Sphere {
+                center,
+                radius,
+                material: m,
+            } => return Ok(Sphere::new(*center, *radius, material(*m)?).into()),
+            ObjectDesc::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                material: m,
+            } => {
+                return Ok(MovingSphere::new(
+                    *center0,
+                    *center1,
+                    *time0,
+                    *time1,
+                    *radius,
+                    material(*m)?,
+                )
+                .into())
+            }
+            ObjectDesc::Masked { object, visibility } => Arc::new(Masked::new(
+                object.build_with(materials, lod)?.into_arc(),
+                *visibility,
+            )),
+            ObjectDesc::Holdout { object } => {
+                Arc::new(Holdout::new(object.build_with(materials, lod)?.into_arc()))
+            }
+            ObjectDesc::Clipped { object, planes } => Arc::new(Clipped::new(
+                object.build_with(materials, lod)?.into_arc(),
+                planes.clone(),
+            )),
+            ObjectDesc::List { objects } => {
+                let mut list = HittableList::new();
+                for o in objects {
+                    list.add(o.build_with(materials, lod)?);
+                }
+                Arc::new(list)
+            }
+            ObjectDesc::QuadLight {
+                corner,
+                u,
+                v,
+                emit,
+                two_sided,
+                group,
+                ..
+            } => {
+                let light = QuadLight::new(*corner, *u, *v, *emit).with_two_sided(*two_sided);
+                match group {
+                    Some(g) => Arc::new(light.with_group(g)),
+                    None => Arc::new(light),
+                }
+            }
+            ObjectDesc::SpotLight {
+                position,
+                direction,
+                up,
+                radius,
+                angle,
+                softness,
+                emit,
+                gobo,
+                group,
+            } => {
+                if !(*angle > 0.0 && *angle < 90.0) {
+                    return Err(Error::Scene(format!(
+                        "spot light angle must be between 0 and 90 degrees, got {angle}"
+                    )));
+                }
+                if radius.is_nan() || *radius <= 0.0 || direction.length_squared() == 0.0 {
+                    return Err(Error::Scene(
+                        "spot lights need a positive radius and a direction".into(),
+                    ));
+                }
+                let mut light = SpotLight::new(*position, *direction, *radius, *angle, *emit)
+                    .with_softness(*softness);
+                if let Some(up) = up {
+                    light = light.with_up(*up);
+                }
+                if let Some(path) = gobo {
+                    light = light.with_gobo(TextureCache::shared().texture(path));
+                }
+                match group {
+                    Some(g) => Arc::new(light.with_group(g)),
+                    None => Arc::new(light),
+                }
+            }
+            ObjectDesc::Volume {
+                boundary,
+                density,
+                albedo,
+                emission,
+            } => {
+                let volume = match boundary {
+                    Some(b) => Volume::new(
+                        b.build_with(materials, lod)?.into_arc(),
+                        density.clone(),
+                        *albedo,
+                    )?,
+                    None => Volume::from_field(density.clone(), *albedo)?,
+                };
+                match emission {
+                    Some(e) => Arc::new(volume.with_emission(e.clone())?),
+                    None => Arc::new(volume),
+                }
+            }
+            ObjectDesc::Mesh {
+                positions,
+                indices,
+                uvs,
+                obj,
+                material: m,
+                target_triangles,
+                lod: use_lod,
+            } => {
+                let (positions, indices) = match obj {
+                    Some(path) => mesh::load_obj(path)?,
+                    None => (positions.clone(), indices.clone()),
+                };
+                let lod_target = lod
+                    .filter(|_| *use_lod)
+                    .zip(mesh::bounds(&positions))
+                    .map(|(view, bounds)| view.triangles(bounds));
+                let target = match (*target_triangles, lod_target) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                let (positions, indices, uvs) = match target {
+                    Some(target) if target < indices.len() => {
+                        let before = indices.len();
+                        let (positions, indices) = mesh::simplify(&positions, &indices, target);
+                        tracing::debug!(before, after = indices.len(), "decimated mesh");
+                        // Decimation moves vertices, so their texture coordinates no longer apply.
+                        (positions, indices, Vec::new())
+                    }
+                    _ if obj.is_some() => (positions, indices, Vec::new()),
+                    _ => (positions, indices, uvs.clone()),
+                };
+                let mut mesh = Mesh::new(positions, indices, material(*m)?)?;
+                if !uvs.is_empty() {
+                    mesh = mesh.with_uvs(uvs)?;
+                }
+                #[cfg(feature = "embree")]
+                let mesh = crate::embree::EmbreeMesh::new(mesh)?;
+                Arc::new(mesh)
+            }
+            ObjectDesc::PagedMesh { path, material: m } => Arc::new(PagedMesh::open(
+                path,
+                material(*m)?,
+                GeometryCache::shared(),
+            )?),
+        };
+        Ok(Primitive::Dyn(object))
+    }
+}
@@ -0,0 +1,242 @@
+// Materials written as small Rhai scripts, for trying out patterns without recompiling. A
+// script defines `albedo(p, n, uv)`, the diffuse color at a shading point as an `[r, g, b]`
+// array, and may define `emit(p, n, uv)` for light the surface gives off. `p` and `n` are
+// `[x, y, z]` arrays and `uv` is `[u, v]`:
+//
+//     fn albedo(p, n, uv) {
+//         let check = (floor(p[0] * 4.0) + floor(p[2] * 4.0)) % 2.0;
+//         if check == 0.0 { [0.8, 0.8, 0.8] } else { [0.1, 0.1, 0.1] }
+//     }
+//
+// Only the functions run per shading point; top-level statements are ignored. Scripts are
+// compiled once and shared by path, and `reload_changed` recompiles those whose files changed,
+// so every material using one follows the edit. A script that fails to load or run renders
+// magenta, so it stands out without aborting the render.
+
+use crate::error::{Error, Result};
+use crate::hittable::HitRecord;
+use crate::material::{Lambertian, Material};
+use crate::ray::Ray;
+use crate::scene::MaterialDesc;
+use crate::vec3::{Color, Vec3};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Scope, AST};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+use tracing::warn;
+
+// Steps a call may take before it is abandoned, so a runaway loop can't hang the render.
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub const ERROR_COLOR: Color = Color::new(1.0, 0.0, 1.0);
+
+pub struct Script {
+    path: PathBuf,
+    state: RwLock<ScriptState>,
+    // Set once a failure has been logged, so a broken script warns once rather than per hit.
+    warned: AtomicBool,
+}
+
+#[derive(Default)]
+struct ScriptState {
+    ast: Option<Arc<AST>>,
+    has_emit: bool,
+    modified: Option<SystemTime>,
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+    })
+}
+
+fn scripts() -> &'static Mutex<HashMap<PathBuf, Arc<Script>>> {
+    static SCRIPTS: OnceLock<Mutex<HashMap<PathBuf, Arc<Script>>>> = OnceLock::new();
+    SCRIPTS.get_or_init(Mutex::default)
+}
+
+impl Script {
+    // The shared script for `path`, compiled on first use. Failing to compile is logged and
+    // leaves the script rendering magenta until a reload succeeds.
+    pub fn load(path: &Path) -> Arc<Script> {
+        let mut scripts = scripts().lock().unwrap_or_else(|e| e.into_inner());
+        scripts
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let script = Arc::new(Script {
+                    path: path.to_path_buf(),
+                    state: RwLock::default(),
+                    warned: AtomicBool::new(false),
+                });
+                if let Err(e) = script.reload() {
+                    warn!("{e}");
+                }
+                script
+            })
+            .clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Recompiles the file. On failure the last version that compiled stays in use.
+    pub fn reload(&self) -> Result<()> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok();
+        let compiled = std::fs::read_to_string(&self.path)
+            .map_err(Error::from)
+            .and_then(|source| {
+                engine()
+                    .compile(source)
+                    .map_err(|e| Error::Scene(format!("{}: {e}", self.path.display())))
+            });
+        let mut state = self.state.write().map_err(|_| Error::Poisoned("script"))?;
+        state.modified = modified;
+        let ast = compiled?;
+        state.has_emit = ast.iter_functions().any(|f| f.name == "emit");
+        state.ast = Some(Arc::new(ast));
+        self.warned.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn changed(&self) -> bool {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok();
+        self.state
+            .read()
+            .map_or(true, |state| state.modified != modified)
+    }
+
+    // Whether the script defines `emit`.
+    pub fn emits(&self) -> bool {
+        self.state.read().is_ok_and(|state| state.has_emit)
+    }
+
+    // Calls `name(p, n, uv)` for the shading point `rec`.
+    pub fn call(&self, name: &str, rec: &HitRecord) -> Result<Color> {
+        let ast = self
+            .state
+            .read()
+            .map_err(|_| Error::Poisoned("script"))?
+            .ast
+            .clone()
+            .ok_or_else(|| Error::Scene(format!("{} didn't compile", self.path.display())))?;
+        let vector = |v: Vec3| -> Array { vec![v.x.into(), v.y.into(), v.z.into()] };
+        let uv: Array = vec![rec.u.into(), rec.v.into()];
+        let options = CallFnOptions::new().eval_ast(false);
+        let result: Array = engine()
+            .call_fn_with_options(
+                options,
+                &mut Scope::new(),
+                &ast,
+                name,
+                (vector(rec.point), vector(rec.normal), uv),
+            )
+            .map_err(|e| Error::Scene(format!("{}: {name}: {e}", self.path.display())))?;
+        match result
+            .iter()
+            .map(number)
+            .collect::<Option<Vec<f64>>>()
+            .as_deref()
+        {
+            Some(&[r, g, b]) => Ok(Color::new(r, g, b)),
+            _ => Err(Error::Scene(format!(
+                "{}: {name} must return [r, g, b], not {result:?}",
+                self.path.display()
+            ))),
+        }
+    }
+
+    // `call`, rendering failures magenta and logging the first.
+    fn color(&self, name: &str, rec: &HitRecord) -> Color {
+        self.call(name, rec).unwrap_or_else(|e| {
+            if !self.warned.swap(true, Ordering::Relaxed) {
+                warn!("{e}");
+            }
+            ERROR_COLOR
+        })
+    }
+}
+
+fn number(value: &Dynamic) -> Option<f64> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|i| i as f64))
+}
+
+// Recompiles every loaded script whose file changed since it was last read, returning how
+// many were. Scripts that no longer compile are logged and keep their last version.
+pub fn reload_changed() -> usize {
+    let scripts: Vec<Arc<Script>> = scripts()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    let mut reloaded = 0;
+    for script in scripts.iter().filter(|s| s.changed()) {
+        match script.reload() {
+            Ok(()) => reloaded += 1,
+            Err(e) => warn!("{e}"),
+        }
+    }
+    reloaded
+}
+
+// Files of every loaded script, e.g. to watch them for changes.
+pub fn loaded() -> Vec<PathBuf> {
+    scripts()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+// Lambertian surface whose color, and optionally emission, a script computes.
+pub struct ScriptedMaterial {
+    script: Arc<Script>,
+}
+
+impl ScriptedMaterial {
+    pub fn new(script: Arc<Script>) -> Self {
+        Self { script }
+    }
+}
+
+impl Material for ScriptedMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        Lambertian::new(self.script.color("albedo", rec)).scatter(ray_in, rec, rng)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        Lambertian::new(Color::default()).scattering_pdf(ray_in, rec, scattered)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        if !self.script.emits() {
+            return Color::default();
+        }
+        self.script.color("emit", rec)
+    }
+
+    fn to_desc(&self) -> Option<MaterialDesc> {
+        Some(MaterialDesc::Script {
+            path: self.script.path().to_path_buf(),
+        })
+    }
+}
@@ -0,0 +1,495 @@
+// Headless render service. Jobs render in the background; results can be fetched at any
+// time and show whatever has accumulated so far.
+//
+//   POST /jobs                  RenderRequest JSON -> {"id": n}
+//   GET  /jobs/<id>             JobStatus JSON
+//   GET  /jobs/<id>/image.png   beauty, 8-bit sRGB
+//   GET  /jobs/<id>/image.exr   beauty, linear float
+//   DELETE /jobs/<id>           cancels a rendering job, or forgets a finished one
+//
+// Requests too large for `ServeLimits` are refused with 413 before anything is allocated,
+// and with 503 while `max_jobs` are rendering. Scenes are built on the job's thread, so one
+// that fails to build fails its job. Finished jobs are forgotten after a while.
+
+use crate::aov::AovSet;
+use crate::control::RenderControl;
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::hittable::HittableList;
+use crate::memory::MemoryEstimate;
+use crate::render::{render_image_controlled, RenderSettings};
+use crate::scene::SceneDesc;
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RenderRequest {
+    pub scene: SceneDesc,
+    #[serde(default = "default_width")]
+    pub width: u32,
+    #[serde(default = "default_height")]
+    pub height: u32,
+    #[serde(default = "default_samples")]
+    pub samples: u32,
+    #[serde(default)]
+    pub settings: RenderSettings,
+}
+
+fn default_width() -> u32 {
+    640
+}
+
+fn default_height() -> u32 {
+    360
+}
+
+fn default_samples() -> u32 {
+    16
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Rendering,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobStatus {
+    pub id: usize,
+    pub state: JobState,
+    // Fraction of scanlines finished.
+    pub progress: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// What the service will take on and how long it remembers finished jobs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServeLimits {
+    // Largest width times height a job may ask for.
+    pub max_pixels: usize,
+    // Most samples per pixel a job may ask for.
+    pub max_samples: u32,
+    // Bytes a job may need by `MemoryEstimate`, if limited.
+    pub memory_budget: Option<usize>,
+    // Largest request body read.
+    pub max_request_bytes: usize,
+    // Jobs rendering at once; more are refused until one finishes.
+    pub max_jobs: usize,
+    // Finished jobs kept for fetching; beyond this the longest finished are dropped.
+    pub max_finished_jobs: usize,
+    // How long a finished job is kept.
+    pub job_ttl: Duration,
+    // Directory the files scenes refer to must be in, relative paths being taken from it.
+    // Without one, scenes referring to any file are refused.
+    pub scene_root: Option<PathBuf>,
+}
+
+impl Default for ServeLimits {
+    fn default() -> Self {
+        Self {
+            max_pixels: 4096 * 4096,
+            max_samples: 4096,
+            memory_budget: None,
+            max_request_bytes: 16 << 20,
+            max_jobs: 4,
+            max_finished_jobs: 64,
+            job_ttl: Duration::from_secs(3600),
+            scene_root: None,
+        }
+    }
+}
+
+struct Job {
+    status: JobStatus,
+    film: Arc<Film>,
+    control: RenderControl,
+    finished: Option<Instant>,
+}
+
+// A request the service won't take on, with the status to reply with.
+struct Refusal {
+    status: u16,
+    message: String,
+}
+
+impl Refusal {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for Refusal {
+    fn from(e: Error) -> Self {
+        match e {
+            // Too large to take on.
+            Error::Memory(_) => Refusal::new(413, e.to_string()),
+            Error::Poisoned(_) => Refusal::new(500, e.to_string()),
+            _ => Refusal::new(400, e.to_string()),
+        }
+    }
+}
+
+// Jobs by id. Ids are never reused, so a forgotten job's id stays unknown.
+#[derive(Default)]
+struct JobList {
+    jobs: BTreeMap<usize, Job>,
+    next_id: usize,
+}
+
+impl JobList {
+    // Drops finished jobs older than the TTL, then the longest finished over the limit.
+    fn evict(&mut self, limits: &ServeLimits, now: Instant) {
+        self.jobs
+            .retain(|_, job| job.finished.is_none_or(|t| now - t <= limits.job_ttl));
+        let mut finished: Vec<(Instant, usize)> = self
+            .jobs
+            .iter()
+            .filter_map(|(&id, job)| Some((job.finished?, id)))
+            .collect();
+        finished.sort_unstable();
+        let excess = finished.len().saturating_sub(limits.max_finished_jobs);
+        for (_, id) in &finished[..excess] {
+            self.jobs.remove(id);
+        }
+    }
+}
+
+type Jobs = Arc<Mutex<JobList>>;
+
+// The render service bound to an address, not yet serving.
+pub struct Service {
+    server: Server,
+    jobs: Jobs,
+    limits: ServeLimits,
+}
+
+impl Service {
+    pub fn bind(addr: &str, mut limits: ServeLimits) -> Result<Self> {
+        // Canonical, so resolved scene paths can be compared against it.
+        limits.scene_root = limits
+            .scene_root
+            .map(|root| root.canonicalize())
+            .transpose()?;
+        let server = Server::http(addr).map_err(|e| Error::Io(std::io::Error::other(e)))?;
+        Ok(Self {
+            server,
+            jobs: Arc::default(),
+            limits,
+        })
+    }
+
+    // Where the service listens; useful after binding port 0.
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    // Blocks serving requests.
+    pub fn run(&self) {
+        for request in self.server.incoming_requests() {
+            let method = request.method().clone();
+            let url = request.url().to_string();
+            if let Err(e) = handle(request, &self.jobs, &self.limits) {
+                warn!(%method, url, "request failed: {e}");
+            }
+        }
+    }
+}
+
+// Blocks serving requests on `addr`, e.g. "127.0.0.1:8080".
+pub fn serve(addr: &str, limits: ServeLimits) -> Result<()> {
+    let service = Service::bind(addr, limits)?;
+    info!(addr, "render service listening");
+    service.run();
+    Ok(())
+}
+
+fn handle(mut request: Request, jobs: &Jobs, limits: &ServeLimits) -> Result<()> {
+    lock(jobs)?.evict(limits, Instant::now());
+    let path: Vec<String> = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+
+    let response = match (request.method(), path.as_slice()) {
+        (Method::Post, ["jobs"]) => {
+            // One byte over the limit is enough to know the body is too large.
+            let mut body = String::new();
+            request
+                .as_reader()
+                .take(limits.max_request_bytes as u64 + 1)
+                .read_to_string(&mut body)?;
+            if body.len() > limits.max_request_bytes {
+                error(
+                    413,
+                    &format!(
+                        "the service reads requests of at most {} bytes",
+                        limits.max_request_bytes
+                    ),
+                )
+            } else {
+                match serde_json::from_str::<RenderRequest>(&body) {
+                    Ok(job) => match submit(job, jobs, limits) {
+                        Ok(id) => json(201, &serde_json::json!({ "id": id })),
+                        Err(refusal) => error(refusal.status, &refusal.message),
+                    },
+                    Err(e) => error(400, &format!("invalid render request: {e}")),
+                }
+            }
+        }
+        (Method::Get, ["jobs", id]) => match with_job(jobs, id, |job| job.status.clone())? {
+            Some(status) => json(200, &status),
+            None => error(404, "no such job"),
+        },
+        (Method::Delete, ["jobs", id]) => cancel(jobs, id)?,
+        (Method::Get, ["jobs", id, file]) => {
+            let format = match *file {
+                "image.png" => ImageFormat::Png,
+                "image.exr" => ImageFormat::OpenExr,
+                _ => return respond(request, error(404, "unknown file")),
+            };
+            match with_job(jobs, id, |job| Arc::clone(&job.film))? {
+                Some(film) => image(&film, format)?,
+                None => error(404, "no such job"),
+            }
+        }
+        _ => error(404, "not found"),
+    };
+    respond(request, response)
+}
+
+fn submit(
+    request: RenderRequest,
+    jobs: &Jobs,
+    limits: &ServeLimits,
+) -> std::result::Result<usize, Refusal> {
+    let RenderRequest {
+        mut scene,
+        width,
+        height,
+        samples,
+        settings,
+    } = request;
+    if width == 0 || height == 0 || samples == 0 {
+        return Err(Refusal::new(
+            400,
+            "width, height and samples must be positive",
+        ));
+    }
+    let pixels = width as usize * height as usize;
+    if pixels > limits.max_pixels {
+        return Err(Refusal::new(
+            413,
+            format!(
+                "{width}x{height} is {pixels} pixels; the service renders at most {}",
+                limits.max_pixels
+            ),
+        ));
+    }
+    if samples > limits.max_samples {
+        return Err(Refusal::new(
+            413,
+            format!(
+                "{samples} samples per pixel; the service takes at most {}",
+                limits.max_samples
+            ),
+        ));
+    }
+    // The film must fit now; the scene is checked once built.
+    if let Some(budget) = limits.memory_budget {
+        MemoryEstimate::new(&HittableList::new(), width, height, &[]).check(budget)?;
+    }
+    confine(&mut scene, limits.scene_root.as_deref())?;
+
+    let mut list = lock(jobs)?;
+    let rendering = list.jobs.values().filter(|j| j.finished.is_none()).count();
+    if rendering >= limits.max_jobs {
+        return Err(Refusal::new(
+            503,
+            format!("{rendering} jobs are rendering; try again once one finishes"),
+        ));
+    }
+    let film = Arc::new(Film::new(width, height));
+    let control = RenderControl::new();
+    let id = list.next_id;
+    list.next_id += 1;
+    list.jobs.insert(
+        id,
+        Job {
+            status: JobStatus {
+                id,
+                state: JobState::Rendering,
+                progress: 0.0,
+                error: None,
+            },
+            film: Arc::clone(&film),
+            control: control.clone(),
+            finished: None,
+        },
+    );
+    drop(list);
+    info!(id, width, height, samples, "job submitted");
+
+    let jobs = Arc::clone(jobs);
+    let budget = limits.memory_budget;
+    std::thread::spawn(move || {
+        let result = (|| {
+            let (world, camera) = scene.build_lod(width as f64 / height as f64, height)?;
+            if let Some(budget) = budget {
+                MemoryEstimate::new(&world, width, height, &[]).check(budget)?;
+            }
+            let aovs = AovSet::new(&[], &world, width, height);
+            render_image_controlled(
+                &world,
+                &camera,
+                &film,
+                &aovs,
+                samples,
+                &settings,
+                &control,
+                &|p| {
+                    if let Some(job) = jobs.lock().ok().as_mut().and_then(|l| l.jobs.get_mut(&id)) {
+                        job.status.progress = p.done as f64 / p.total as f64;
+                    }
+                },
+            )
+        })();
+        // Rendering jobs are never evicted; `max_jobs` bounds them instead.
+        if let Some(job) = jobs.lock().ok().as_mut().and_then(|l| l.jobs.get_mut(&id)) {
+            job.finished = Some(Instant::now());
+            let status = &mut job.status;
+            match result {
+                Ok(()) => status.state = JobState::Done,
+                Err(Error::Cancelled) => status.state = JobState::Cancelled,
+                Err(e) => {
+                    status.state = JobState::Failed;
+                    status.error = Some(e.to_string());
+                }
+            }
+            info!(id, state = ?status.state, "job finished");
+        }
+    });
+    Ok(id)
+}
+
+// Resolves the scene's files against `root`, refusing any outside it, or every file if there
+// is no root.
+fn confine(scene: &mut SceneDesc, root: Option<&Path>) -> std::result::Result<(), Refusal> {
+    let paths = scene.paths_mut();
+    let Some(first) = paths.first() else {
+        return Ok(());
+    };
+    let Some(root) = root else {
+        return Err(Refusal::new(
+            403,
+            format!(
+                "{}: the service has no scene root to read files from",
+                first.display()
+            ),
+        ));
+    };
+    for path in paths {
+        // Resolving symlinks and `..` first, so neither can lead out.
+        let resolved = root
+            .join(&*path)
+            .canonicalize()
+            .map_err(|e| Refusal::new(400, format!("{}: {e}", path.display())))?;
+        if !resolved.starts_with(root) {
+            return Err(Refusal::new(
+                403,
+                format!("{} is outside the scene root", path.display()),
+            ));
+        }
+        *path = resolved;
+    }
+    Ok(())
+}
+
+// Cancels a rendering job, which then finishes as cancelled, or forgets a finished one.
+fn cancel(jobs: &Jobs, id: &str) -> Result<HttpResponse> {
+    let mut list = lock(jobs)?;
+    let Some((id, job)) = id
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| Some((i, list.jobs.get(&i)?)))
+    else {
+        return Ok(error(404, "no such job"));
+    };
+    if job.finished.is_some() {
+        list.jobs.remove(&id);
+        return Ok(Response::from_data(Vec::new()).with_status_code(204));
+    }
+    job.control.cancel();
+    info!(id, "job cancelled");
+    Ok(json(202, &job.status))
+}
+
+fn lock(jobs: &Jobs) -> Result<std::sync::MutexGuard<'_, JobList>> {
+    jobs.lock().map_err(|_| Error::Poisoned("job list"))
+}
+
+fn with_job<T>(jobs: &Jobs, id: &str, f: impl FnOnce(&Job) -> T) -> Result<Option<T>> {
+    let list = lock(jobs)?;
+    Ok(id
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| list.jobs.get(&i))
+        .map(f))
+}
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+fn respond(request: Request, response: HttpResponse) -> Result<()> {
+    request.respond(response)?;
+    Ok(())
+}
+
+fn with_content_type(response: HttpResponse, content_type: &str) -> HttpResponse {
+    let header = Header::from_bytes("Content-Type", content_type).expect("static header is valid");
+    response.with_header(header)
+}
+
+fn json<T: Serialize>(status: u16, body: &T) -> HttpResponse {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    with_content_type(
+        Response::from_data(body).with_status_code(status),
+        "application/json",
+    )
+}
+
+fn error(status: u16, message: &str) -> HttpResponse {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+fn image(film: &Film, format: ImageFormat) -> Result<HttpResponse> {
+    let mut bytes = Cursor::new(Vec::new());
+    let written = match format {
+        ImageFormat::OpenExr => film.develop_linear(1.0)?.write_to(&mut bytes, format),
+        _ => film.develop(1.0)?.write_to(&mut bytes, format),
+    };
+    written.map_err(|e| Error::Io(std::io::Error::other(e)))?;
+    Ok(with_content_type(
+        Response::from_data(bytes.into_inner()),
+        format.to_mime_type(),
+    ))
+}
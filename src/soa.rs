@@ -0,0 +1,205 @@
+// Spheres stored as a structure of arrays, `LANES` to a block, so a ray is tested against a
+// whole block with the same arithmetic on every lane; the compiler turns each lane loop into
+// vector instructions. `HittableList` keeps one for its spheres once it holds enough of them.
+
+use crate::aabb::Aabb;
+use crate::hittable::{sphere_box, sphere_hits, sphere_record, HitRecord, Hittable, Sphere};
+use crate::interval::Interval;
+use crate::material::{MaterialId, MaterialRef};
+use crate::ray::Ray;
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::stats::SceneStats;
+use crate::vec3::Point3;
+
+pub const LANES: usize = 4;
+
+// Centres and squared radii of `LANES` spheres. Unused lanes have a negative squared radius,
+// which no ray can hit.
+#[derive(Clone, Copy)]
+struct Block {
+    x: [f64; LANES],
+    y: [f64; LANES],
+    z: [f64; LANES],
+    radius2: [f64; LANES],
+}
+
+const EMPTY_BLOCK: Block = Block {
+    x: [0.0; LANES],
+    y: [0.0; LANES],
+    z: [0.0; LANES],
+    radius2: [-1.0; LANES],
+};
+
+#[derive(Default)]
+pub struct SphereSoA {
+    blocks: Vec<Block>,
+    // Per sphere, only read once a sphere is known to be the closest hit.
+    centers: Vec<Point3>,
+    radii: Vec<f64>,
+    materials: Vec<MaterialRef>,
+    ids: Vec<u32>,
+}
+
+impl SphereSoA {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `id` is reported as the `object_id` of hits on this sphere.
+    pub fn push(&mut self, sphere: &Sphere, id: u32) {
+        let (block, lane) = (self.centers.len() / LANES, self.centers.len() % LANES);
+        if lane == 0 {
+            self.blocks.push(EMPTY_BLOCK);
+        }
+        let b = &mut self.blocks[block];
+        let c = sphere.center;
+        (b.x[lane], b.y[lane], b.z[lane]) = (c.x, c.y, c.z);
+        b.radius2[lane] = sphere.radius * sphere.radius;
+        self.centers.push(c);
+        self.radii.push(sphere.radius);
+        self.materials.push(sphere.material.clone());
+        self.ids.push(id);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.centers.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.centers.is_empty()
+    }
+
+    // Index and ray parameter of the closest sphere hit within `ray_t`. Discriminants are
+    // found a block at a time; roots only for the few lanes a ray actually crosses. Same
+    // arithmetic as `Sphere::hit`, so the two agree exactly.
+    fn closest(&self, r: &Ray, ray_t: Interval) -> Option<(usize, f64)> {
+        let a = r.direction().length_squared();
+        let mut best = ray_t.max;
+        let mut found = None;
+        for (i, b) in self.blocks.iter().enumerate() {
+            let Some((half_b, discriminant)) = b.crossings(r, a) else {
+                continue;
+            };
+            for l in 0..LANES {
+                if discriminant[l] <= 0.0 {
+                    continue;
+                }
+                let sqrtd = discriminant[l].sqrt();
+                let mut root = (-half_b[l] - sqrtd) / a;
+                if root <= ray_t.min || root >= best {
+                    root = (-half_b[l] + sqrtd) / a;
+                    if root <= ray_t.min || root >= best {
+                        continue;
+                    }
+                }
+                best = root;
+                found = Some(i * LANES + l);
+            }
+        }
+        found.map(|i| (i, best))
+    }
+
+    // Whether any sphere is hit within `ray_t`, stopping at the first.
+    fn any(&self, r: &Ray, ray_t: Interval) -> bool {
+        let a = r.direction().length_squared();
+        self.blocks.iter().any(|b| {
+            let Some((half_b, discriminant)) = b.crossings(r, a) else {
+                return false;
+            };
+            (0..LANES).any(|l| {
+                let sqrtd = discriminant[l].max(0.0).sqrt();
+                discriminant[l] > 0.0
+                    && (ray_t.surrounds((-half_b[l] - sqrtd) / a)
+                        || ray_t.surrounds((-half_b[l] + sqrtd) / a))
+            })
+        })
+    }
+}
+
+impl Block {
+    // Half the linear coefficient and the discriminant of each lane's quadratic, or None if
+    // the ray's line misses every sphere. `a` is the squared length of the ray's direction.
+    #[inline]
+    fn crossings(&self, r: &Ray, a: f64) -> Option<([f64; LANES], [f64; LANES])> {
+        let (o, d) = (r.origin(), r.direction());
+        let mut half_b = [0.0; LANES];
+        let mut discriminant = [0.0; LANES];
+        for l in 0..LANES {
+            let (ox, oy, oz) = (o.x - self.x[l], o.y - self.y[l], o.z - self.z[l]);
+            half_b[l] = ox * d.x + oy * d.y + oz * d.z;
+            let c = ox * ox + oy * oy + oz * oz - self.radius2[l];
+            discriminant[l] = half_b[l] * half_b[l] - a * c;
+        }
+        discriminant
+            .iter()
+            .any(|&x| x > 0.0)
+            .then_some((half_b, discriminant))
+    }
+}
+
+impl Hittable for SphereSoA {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let (i, t) = self.closest(r, ray_t)?;
+        let mut rec = sphere_record(self.centers[i], self.radii[i], self.materials[i].id(), r, t);
+        rec.object_id = self.ids[i];
+        Some(rec)
+    }
+
+    fn is_occluded(&self, r: &Ray, ray_t: Interval) -> bool {
+        self.any(r, ray_t)
+    }
+
+    fn hit_all_into(&self, r: &Ray, ray_t: Interval, out: &mut Vec<HitRecord>) {
+        for i in 0..self.len() {
+            let start = out.len();
+            sphere_hits(
+                self.centers[i],
+                self.radii[i],
+                self.materials[i].id(),
+                r,
+                ray_t,
+                out,
+            );
+            for rec in &mut out[start..] {
+                rec.object_id = self.ids[i];
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        (0..self.len())
+            .map(|i| sphere_box(self.centers[i], self.radii[i]))
+            .reduce(Aabb::surrounding_box)
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.extend(self.materials.iter().map(MaterialRef::id));
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        for _ in 0..self.len() {
+            stats.add_primitive("Sphere", 0);
+        }
+        stats.geometry_bytes += std::mem::size_of_val(self)
+            + self.blocks.capacity() * std::mem::size_of::<Block>()
+            + self.centers.capacity() * std::mem::size_of::<Point3>()
+            + self.radii.capacity() * std::mem::size_of::<f64>()
+            + self.materials.capacity() * std::mem::size_of::<MaterialRef>()
+            + self.ids.capacity() * std::mem::size_of::<u32>();
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        let objects = (0..self.len())
+            .map(|i| {
+                Some(ObjectDesc::Sphere {
+                    center: self.centers[i],
+                    radius: self.radii[i],
+                    material: materials.index(self.materials[i].id())?,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(ObjectDesc::List { objects })
+    }
+}
@@ -0,0 +1,146 @@
+use crate::aabb::Aabb;
+use crate::hittable::Hittable;
+use crate::texture::ImageTexture;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Shape of an acceleration structure, reported by whichever hittable owns it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    pub max_depth: usize,
+    // Expected cost of a random ray relative to testing one primitive.
+    pub sah_cost: f64,
+}
+
+impl BvhStats {
+    // Combines two acceleration structures, e.g. nested instances.
+    pub fn merge(self, other: BvhStats) -> BvhStats {
+        BvhStats {
+            nodes: self.nodes + other.nodes,
+            leaves: self.leaves + other.leaves,
+            max_depth: self.max_depth.max(other.max_depth),
+            sah_cost: self.sah_cost + other.sah_cost,
+        }
+    }
+}
+
+// Summary of what a scene is made of, gathered by walking the hittable tree.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SceneStats {
+    // Leaf primitives per type name.
+    pub primitives: BTreeMap<&'static str, usize>,
+    pub triangles: usize,
+    pub materials: usize,
+    // Decoded size of the images materials sample, as far as their caches keep them; see
+    // `ImageTexture::estimate_bytes`.
+    pub texture_bytes: usize,
+    pub geometry_bytes: usize,
+    pub material_bytes: usize,
+    pub bvh: Option<BvhStats>,
+    pub bounds: Option<Aabb>,
+}
+
+impl SceneStats {
+    pub fn new(world: &dyn Hittable) -> Self {
+        let mut stats = SceneStats::default();
+        world.stats(&mut stats);
+
+        let mut materials = Vec::new();
+        world.materials(&mut materials);
+        let mut seen = Vec::new();
+        let mut textures = Vec::new();
+        for m in materials {
+            if !seen.contains(&m) {
+                seen.push(m);
+                let m = m.get();
+                stats.material_bytes += std::mem::size_of_val(&*m);
+                m.textures(&mut textures);
+            }
+        }
+        stats.materials = seen.len();
+        stats.texture_bytes = ImageTexture::estimate_bytes(&textures);
+        stats.bounds = world.bounding_box(0.0, 1.0);
+        stats
+    }
+
+    pub fn add_primitive(&mut self, name: &'static str, bytes: usize) {
+        *self.primitives.entry(name).or_default() += 1;
+        self.geometry_bytes += bytes;
+    }
+
+    pub fn add_bvh(&mut self, bvh: BvhStats) {
+        self.bvh = Some(self.bvh.map_or(bvh, |b| b.merge(bvh)));
+    }
+
+    pub fn primitive_count(&self) -> usize {
+        self.primitives.values().sum()
+    }
+
+    // Rough heap footprint of the scene description, excluding allocator overhead.
+    pub fn memory_bytes(&self) -> usize {
+        self.geometry_bytes + self.material_bytes + self.texture_bytes
+    }
+}
+
+impl fmt::Display for SceneStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let by_type = self
+            .primitives
+            .iter()
+            .map(|(name, n)| format!("{name} {n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "  primitives: {} ({by_type})", self.primitive_count())?;
+        writeln!(f, "  triangles:  {}", self.triangles)?;
+        writeln!(f, "  materials:  {}", self.materials)?;
+        writeln!(f, "  textures:   {}", format_bytes(self.texture_bytes))?;
+        match &self.bvh {
+            Some(b) => writeln!(
+                f,
+                "  bvh:        {} nodes, {} leaves, depth {}, SAH cost {:.2}",
+                b.nodes, b.leaves, b.max_depth, b.sah_cost
+            )?,
+            None => writeln!(f, "  bvh:        none")?,
+        }
+        match &self.bounds {
+            Some(b) => writeln!(
+                f,
+                "  bounds:     ({:.3}, {:.3}, {:.3}) - ({:.3}, {:.3}, {:.3})",
+                b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z
+            )?,
+            None => writeln!(f, "  bounds:     unbounded")?,
+        }
+        write!(
+            f,
+            "  memory:     ~{} (geometry {}, materials {}, textures {})",
+            format_bytes(self.memory_bytes()),
+            format_bytes(self.geometry_bytes),
+            format_bytes(self.material_bytes),
+            format_bytes(self.texture_bytes)
+        )
+    }
+}
+
+// Last path segment of a type name, e.g. `rtt::hittable::Sphere` -> `Sphere`.
+pub fn short_type_name<T: ?Sized>(value: &T) -> &'static str {
+    let name = std::any::type_name_of_val(value);
+    let base = name.split('<').next().unwrap_or(name);
+    base.rsplit("::").next().unwrap_or(base)
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
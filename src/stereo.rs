@@ -0,0 +1,156 @@
+use crate::camera::{Camera, Shutter};
+use crate::ray::{Ray, RayKind};
+use crate::vec3::{Point3, Vec3};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StereoLayout {
+    // Left eye on the left half of the image.
+    #[default]
+    SideBySide,
+    // Left eye on the top half of the image.
+    TopBottom,
+}
+
+impl StereoLayout {
+    // Splits full-image screen coordinates into (is_left_eye, s, t) for one eye.
+    #[inline]
+    pub fn split(self, s: f64, t: f64) -> (bool, f64, f64) {
+        match self {
+            StereoLayout::SideBySide => {
+                if s < 0.5 {
+                    (true, 2.0 * s, t)
+                } else {
+                    (false, 2.0 * s - 1.0, t)
+                }
+            }
+            StereoLayout::TopBottom => {
+                if t >= 0.5 {
+                    (true, s, 2.0 * t - 1.0)
+                } else {
+                    (false, s, 2.0 * t)
+                }
+            }
+        }
+    }
+
+    // Aspect ratio of one eye given the aspect ratio of the full image.
+    #[inline]
+    pub fn eye_aspect_ratio(self, image_aspect_ratio: f64) -> f64 {
+        match self {
+            StereoLayout::SideBySide => 0.5 * image_aspect_ratio,
+            StereoLayout::TopBottom => 2.0 * image_aspect_ratio,
+        }
+    }
+}
+
+// A pair of parallel pinhole/thin-lens cameras separated by the interpupillary distance.
+pub struct StereoCamera {
+    left: Camera,
+    right: Camera,
+    layout: StereoLayout,
+}
+
+impl StereoCamera {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        vup: Vec3,
+        vertical_fov_degrees: f64,
+        image_aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+        ipd: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        let w = Vec3::unit_vector(look_from - look_at);
+        let u = Vec3::unit_vector(Vec3::cross(vup, w));
+        let half = 0.5 * ipd * u;
+        let aspect_ratio = layout.eye_aspect_ratio(image_aspect_ratio);
+
+        let eye = |offset: Vec3| {
+            Camera::new(
+                look_from + offset,
+                look_at + offset,
+                vup,
+                vertical_fov_degrees,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+            )
+        };
+
+        Self {
+            left: eye(-half),
+            right: eye(half),
+            layout,
+        }
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Option<Ray> {
+        let (is_left, s, t) = self.layout.split(s, t);
+        if is_left {
+            self.left.get_ray(s, t, rng)
+        } else {
+            self.right.get_ray(s, t, rng)
+        }
+    }
+}
+
+// Omnidirectional stereo: an equirectangular panorama per eye where every column is seen
+// from a viewpoint on a circle of diameter `ipd`.
+pub struct OdsCamera {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    ipd: f64,
+    layout: StereoLayout,
+    shutter: Shutter,
+}
+
+impl OdsCamera {
+    pub fn new(
+        look_from: Point3,
+        look_at: Point3,
+        vup: Vec3,
+        ipd: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        let w = Vec3::unit_vector(look_from - look_at);
+        let u = Vec3::unit_vector(Vec3::cross(vup, w));
+        let v = Vec3::cross(w, u);
+
+        Self {
+            origin: look_from,
+            u,
+            v,
+            w,
+            ipd,
+            layout,
+            shutter: Shutter::default(),
+        }
+    }
+
+    pub fn with_shutter(mut self, shutter: Shutter) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, rng: &mut dyn rand::RngCore) -> Ray {
+        let (is_left, s, t) = self.layout.split(s, t);
+
+        // Longitude 0 looks down -w; latitude +pi/2 is straight up.
+        let phi = (s - 0.5) * 2.0 * PI;
+        let theta = (t - 0.5) * PI;
+
+        let dir = theta.cos() * (phi.sin() * self.u - phi.cos() * self.w) + theta.sin() * self.v;
+        let side = if is_left { -0.5 } else { 0.5 };
+        let offset = side * self.ipd * (phi.cos() * self.u + phi.sin() * self.w);
+
+        Ray::with_time(self.origin + offset, dir, self.shutter.sample_time(t, rng))
+            .with_kind(RayKind::Camera)
+    }
+}
@@ -0,0 +1,125 @@
+// Streams a progressive render over a websocket so a browser can watch it converge. Each
+// client gets its own render, one client at a time. Protocol, integers little-endian:
+//
+//   text    {"width": w, "height": h, "passes": n}       once, on connect
+//   binary  pass u32, x0 u32, y0 u32, x1 u32, y1 u32,     per finished tile, developed from
+//           then (x1 - x0) * (y1 - y0) RGBA8 pixels        everything accumulated so far
+//   text    {"done": true}                                after the last pass
+//
+// See `web/stream.html` for a viewer.
+
+use crate::aov::AovSet;
+use crate::camera::Camera;
+use crate::control::RenderControl;
+use crate::error::{Error, Result};
+use crate::film::Film;
+use crate::hittable::Hittable;
+use crate::render::{render_image_controlled, RenderSettings, TileProgress};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use tracing::{info, warn};
+use tungstenite::{Message, WebSocket};
+
+pub const TILE_HEADER_BYTES: usize = 20;
+
+// Blocks accepting clients on `addr`, rendering `passes` passes of one sample per pixel each.
+pub fn stream(
+    addr: &str,
+    world: &dyn Hittable,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    passes: u32,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    info!(addr, "streaming renders to websocket clients");
+    stream_on(listener, world, camera, width, height, passes)
+}
+
+// `stream` to clients of an already bound `listener`, e.g. on a port the system picked.
+pub fn stream_on(
+    listener: TcpListener,
+    world: &dyn Hittable,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    passes: u32,
+) -> Result<()> {
+    for conn in listener.incoming() {
+        let conn = conn?;
+        let peer = conn.peer_addr().ok();
+        match tungstenite::accept(conn) {
+            Ok(ws) => {
+                info!(?peer, "client connected");
+                if let Err(e) = stream_to(ws, world, camera, width, height, passes) {
+                    warn!(?peer, "stream ended early: {e}");
+                }
+            }
+            Err(e) => warn!(?peer, "websocket handshake failed: {e}"),
+        }
+    }
+    Ok(())
+}
+
+fn stream_to(
+    mut ws: WebSocket<TcpStream>,
+    world: &dyn Hittable,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    passes: u32,
+) -> Result<()> {
+    let header = serde_json::json!({ "width": width, "height": height, "passes": passes });
+    ws.send(Message::text(header.to_string()))
+        .map_err(ws_error)?;
+
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(&[], world, width, height);
+    let ws = Mutex::new(ws);
+    let settings = RenderSettings::default();
+    // Cancelled once a tile can't be sent, so a client that left doesn't keep the render going.
+    let control = RenderControl::new();
+
+    for pass in 0..passes {
+        let progress = |p: TileProgress| {
+            if control.is_cancelled() {
+                return;
+            }
+            let sent = film
+                .develop_region(p.bounds, 1.0)
+                .map(|img| tile_message(pass, p.bounds, img.as_raw()))
+                .ok()
+                .and_then(|msg| ws.lock().ok()?.send(msg).ok());
+            if sent.is_none() {
+                control.cancel();
+            }
+        };
+        match render_image_controlled(
+            world, camera, &film, &aovs, 1, &settings, &control, &progress,
+        ) {
+            Err(Error::Cancelled) => {
+                return Err(Error::Io(std::io::ErrorKind::ConnectionAborted.into()))
+            }
+            rendered => rendered?,
+        }
+    }
+
+    let mut ws = ws.into_inner().map_err(|_| Error::Poisoned("websocket"))?;
+    ws.send(Message::text(r#"{"done":true}"#))
+        .map_err(ws_error)?;
+    ws.close(None).map_err(ws_error)?;
+    Ok(())
+}
+
+fn tile_message(pass: u32, (x0, y0, x1, y1): (u32, u32, u32, u32), rgba: &[u8]) -> Message {
+    let mut msg = Vec::with_capacity(TILE_HEADER_BYTES + rgba.len());
+    for v in [pass, x0, y0, x1, y1] {
+        msg.extend_from_slice(&v.to_le_bytes());
+    }
+    msg.extend_from_slice(rgba);
+    Message::binary(msg)
+}
+
+fn ws_error(e: tungstenite::Error) -> Error {
+    Error::Io(std::io::Error::other(e))
+}
@@ -0,0 +1,497 @@
+// Image textures are decoded on first use and kept in a shared cache. Identical paths share one
+// decoded image, and the least recently used images are dropped once the cache exceeds its
+// memory budget; they are decoded again if needed later. Decoded images can be kept at half
+// precision or block compressed to fit more of them in the budget. A file that fails to load is
+// warned about once and not tried again.
+
+use crate::color;
+use crate::error::{Error, Result};
+use crate::vec3::Color;
+use half::f16;
+use image::{DynamicImage, Rgb32FImage};
+use std::collections::hash_map::{self, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::{debug, warn};
+
+// Budget of the shared cache, in decoded bytes.
+pub const DEFAULT_BUDGET: usize = 1 << 30;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub loads: usize,
+    pub hits: usize,
+    pub evictions: usize,
+    // Decoded bytes currently resident.
+    pub bytes: usize,
+}
+
+// How decoded images are kept in memory.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextureFormat {
+    // 12 bytes a texel.
+    #[default]
+    F32,
+    // 6 bytes a texel, about three significant digits.
+    F16,
+    // 4x4 blocks of two half precision endpoints and a 2 bit index per texel, 1 byte a texel.
+    // Fine for albedo maps; smooth gradients and sharp multicolored edges band.
+    Block,
+}
+
+struct Entry {
+    image: Option<Arc<Texels>>,
+    // Why the file couldn't be loaded, if it couldn't.
+    failed: Option<String>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<PathBuf, Entry>,
+    clock: u64,
+    stats: CacheStats,
+    format: TextureFormat,
+}
+
+pub struct TextureCache {
+    budget: usize,
+    state: Mutex<CacheState>,
+}
+
+impl TextureCache {
+    // `budget` is in decoded bytes. A single image larger than the budget is still loaded, it
+    // just evicts everything else.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    // The cache scene files' textures load into.
+    pub fn shared() -> Arc<TextureCache> {
+        static SHARED: OnceLock<Arc<TextureCache>> = OnceLock::new();
+        Arc::clone(SHARED.get_or_init(|| Arc::new(TextureCache::new(DEFAULT_BUDGET))))
+    }
+
+    #[inline]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn format(&self) -> Result<TextureFormat> {
+        Ok(self.lock()?.format)
+    }
+
+    // Applies to images decoded from now on; resident ones keep their format.
+    pub fn set_format(&self, format: TextureFormat) -> Result<()> {
+        self.lock()?.format = format;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, CacheState>> {
+        self.state
+            .lock()
+            .map_err(|_| Error::Poisoned("texture cache"))
+    }
+
+    // Cache key for `path`: different spellings of the same file share an entry.
+    fn key(path: &Path) -> PathBuf {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    // Lazy handle; nothing is read from disk until the texture is first sampled.
+    pub fn texture(self: &Arc<Self>, path: impl AsRef<Path>) -> ImageTexture {
+        ImageTexture {
+            path: Self::key(path.as_ref()),
+            cache: Arc::clone(self),
+        }
+    }
+
+    // Decoded, linear image for `path`, loading it if it isn't resident.
+    pub fn get(&self, path: &Path) -> Result<Arc<Texels>> {
+        let key = Self::key(path);
+        let format = {
+            let mut state = self.lock()?;
+            state.clock += 1;
+            let now = state.clock;
+            if let Some(entry) = state.entries.get_mut(&key) {
+                entry.last_used = now;
+                if let Some(failed) = &entry.failed {
+                    return Err(Error::Scene(format!(
+                        "texture {} unavailable: {failed}",
+                        key.display()
+                    )));
+                }
+                if let Some(image) = entry.image.clone() {
+                    state.stats.hits += 1;
+                    return Ok(image);
+                }
+            }
+            state.format
+        };
+
+        // Decode without holding the lock so other textures stay available meanwhile.
+        let image = match load_linear(&key) {
+            Ok(image) => Arc::new(Texels::new(image, format)),
+            Err(e) => {
+                let mut state = self.lock()?;
+                let now = state.clock;
+                if let hash_map::Entry::Vacant(entry) = state.entries.entry(key.clone()) {
+                    warn!(path = %key.display(), "texture unavailable: {e}");
+                    entry.insert(Entry {
+                        image: None,
+                        failed: Some(e.to_string()),
+                        last_used: now,
+                    });
+                }
+                return Err(e);
+            }
+        };
+        let size = image.bytes();
+
+        let mut state = self.lock()?;
+        let now = state.clock;
+        // Another thread may have loaded it in the meantime; keep theirs.
+        if let Some(existing) = state.entries.get(&key).and_then(|e| e.image.clone()) {
+            return Ok(existing);
+        }
+        state.entries.insert(
+            key.clone(),
+            Entry {
+                image: Some(Arc::clone(&image)),
+                failed: None,
+                last_used: now,
+            },
+        );
+        state.stats.loads += 1;
+        state.stats.bytes += size;
+        debug!(path = %key.display(), bytes = size, "loaded texture");
+        self.evict(&mut state, &key);
+        Ok(image)
+    }
+
+    // Drops least recently used images, except `keep`, until within budget.
+    fn evict(&self, state: &mut CacheState, keep: &Path) {
+        while state.stats.bytes > self.budget {
+            let victim = state
+                .entries
+                .iter()
+                .filter(|(k, e)| e.image.is_some() && k.as_path() != keep)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            let Some(victim) = victim else { break };
+            if let Some(image) = state.entries.get_mut(&victim).and_then(|e| e.image.take()) {
+                state.stats.bytes -= image.bytes();
+                state.stats.evictions += 1;
+                debug!(path = %victim.display(), "evicted texture");
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        Ok(self.lock()?.stats)
+    }
+
+    // Number of distinct files referenced so far, resident or not.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.lock()?.entries.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    // Decoded bytes the images at `paths` take once loaded: their size if resident, else
+    // what their headers say they'll take in the current format. At most the budget stays
+    // resident, unless one image alone is larger. Unreadable files count nothing.
+    fn estimate_bytes(&self, paths: &[&Path]) -> usize {
+        let (resident, format) = match self.lock() {
+            Ok(state) => {
+                let resident: Vec<_> = paths
+                    .iter()
+                    .map(|&path| {
+                        let entry = state.entries.get(path)?;
+                        entry.image.as_ref().map(|image| image.bytes())
+                    })
+                    .collect();
+                (resident, state.format)
+            }
+            Err(_) => return 0,
+        };
+        let sizes = paths.iter().zip(resident).map(|(path, resident)| {
+            resident.unwrap_or_else(|| {
+                image::image_dimensions(path).map_or(0, |(width, height)| {
+                    Texels::bytes_for(width, height, format)
+                })
+            })
+        });
+        let (total, largest) = sizes.fold((0, 0), |(total, largest), bytes| {
+            (total + bytes, usize::max(largest, bytes))
+        });
+        total.min(self.budget.max(largest))
+    }
+}
+
+#[derive(Clone)]
+pub struct ImageTexture {
+    path: PathBuf,
+    cache: Arc<TextureCache>,
+}
+
+impl ImageTexture {
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Decoded bytes of the distinct images among `textures`, summed over the caches they
+    // load into; see `TextureCache::estimate_bytes`.
+    pub fn estimate_bytes(textures: &[ImageTexture]) -> usize {
+        let mut caches: Vec<(&Arc<TextureCache>, Vec<&Path>)> = Vec::new();
+        for texture in textures {
+            let i = match caches
+                .iter()
+                .position(|(cache, _)| Arc::ptr_eq(cache, &texture.cache))
+            {
+                Some(i) => i,
+                None => {
+                    caches.push((&texture.cache, Vec::new()));
+                    caches.len() - 1
+                }
+            };
+            let paths = &mut caches[i].1;
+            if !paths.contains(&texture.path()) {
+                paths.push(texture.path());
+            }
+        }
+        caches
+            .iter()
+            .map(|(cache, paths)| cache.estimate_bytes(paths))
+            .sum()
+    }
+
+    // Nearest texel at (u, v), v = 0 at the bottom. Textures that fail to load render cyan,
+    // so they stand out without aborting the render; the cache warns about them once.
+    pub fn value(&self, u: f64, v: f64) -> Color {
+        self.value_filtered(u, v, 0.0)
+    }
+
+    // Average over a `width` wide square of texture space centred on (u, v), e.g. from
+    // `Ray::footprint`, so minified textures don't alias. At most `MAX_TAPS` texels a side are
+    // read, spread evenly over the square; a width under one texel is a nearest lookup.
+    pub fn value_filtered(&self, u: f64, v: f64, width: f64) -> Color {
+        let Ok(image) = self.cache.get(&self.path) else {
+            return Color::new(0.0, 1.0, 1.0);
+        };
+        if image.width() == 0 || image.height() == 0 {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let (w, h) = (image.width() as f64, image.height() as f64);
+        let texel = |u: f64, v: f64| {
+            let x = ((u.clamp(0.0, 1.0) * w) as u32).min(image.width() - 1);
+            let y = (((1.0 - v.clamp(0.0, 1.0)) * h) as u32).min(image.height() - 1);
+            image.texel(x, y)
+        };
+        let width = if width.is_finite() {
+            width.min(1.0)
+        } else {
+            0.0
+        };
+        let taps = |size: f64| ((width * size).ceil() as usize).clamp(1, MAX_TAPS);
+        let (nx, ny) = (taps(w), taps(h));
+        if nx == 1 && ny == 1 {
+            return texel(u, v);
+        }
+        let mut sum = Color::default();
+        for i in 0..nx {
+            for j in 0..ny {
+                let su = u + width * ((i as f64 + 0.5) / nx as f64 - 0.5);
+                let sv = v + width * ((j as f64 + 0.5) / ny as f64 - 0.5);
+                sum += texel(su, sv);
+            }
+        }
+        sum / (nx * ny) as f64
+    }
+}
+
+const MAX_TAPS: usize = 16;
+
+// 8 and 16 bit images are sRGB encoded; float ones, like .hdr and .exr, are already linear.
+fn load_linear(path: &Path) -> Result<Rgb32FImage> {
+    let image = image::open(path).map_err(Error::image(path))?;
+    let encoded = !matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+    let mut image = image.to_rgb32f();
+    if encoded {
+        for px in image.pixels_mut() {
+            for c in px.0.iter_mut() {
+                *c = color::srgb_to_linear(*c as f64) as f32;
+            }
+        }
+    }
+    Ok(image)
+}
+
+// A decoded, linear image in one of the `TextureFormat`s.
+pub struct Texels {
+    width: u32,
+    height: u32,
+    storage: Storage,
+}
+
+enum Storage {
+    F32(Rgb32FImage),
+    F16(Vec<[f16; 3]>),
+    // Row major, `width.div_ceil(4)` blocks a row.
+    Block(Vec<Block>),
+}
+
+#[derive(Copy, Clone)]
+struct Block {
+    ends: [[f16; 3]; 2],
+    // Two bits a texel, row major within the block.
+    indices: u32,
+}
+
+impl Texels {
+    pub fn new(image: Rgb32FImage, format: TextureFormat) -> Self {
+        let (width, height) = image.dimensions();
+        let storage = match format {
+            TextureFormat::F32 => Storage::F32(image),
+            TextureFormat::F16 => {
+                Storage::F16(image.pixels().map(|px| px.0.map(f16::from_f32)).collect())
+            }
+            TextureFormat::Block => {
+                let mut blocks = Vec::new();
+                for by in 0..height.div_ceil(4) {
+                    for bx in 0..width.div_ceil(4) {
+                        // Edge blocks repeat the last row and column.
+                        let texels: [[f32; 3]; 16] = std::array::from_fn(|i| {
+                            let x = (bx * 4 + i as u32 % 4).min(width - 1);
+                            let y = (by * 4 + i as u32 / 4).min(height - 1);
+                            image.get_pixel(x, y).0
+                        });
+                        blocks.push(Block::encode(&texels));
+                    }
+                }
+                Storage::Block(blocks)
+            }
+        };
+        Self {
+            width,
+            height,
+            storage,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        match self.storage {
+            Storage::F32(_) => TextureFormat::F32,
+            Storage::F16(_) => TextureFormat::F16,
+            Storage::Block(_) => TextureFormat::Block,
+        }
+    }
+
+    // Texel (x, y), y = 0 at the top.
+    pub fn texel(&self, x: u32, y: u32) -> Color {
+        let [r, g, b] = match &self.storage {
+            Storage::F32(image) => image.get_pixel(x, y).0,
+            Storage::F16(texels) => texels[(y * self.width + x) as usize].map(f32::from),
+            Storage::Block(blocks) => {
+                let block = blocks[((y / 4) * self.width.div_ceil(4) + x / 4) as usize];
+                block.decode((y % 4 * 4 + x % 4) as usize)
+            }
+        };
+        Color::new(r as f64, g as f64, b as f64)
+    }
+
+    // What a `width` by `height` image takes in `format`, as `bytes` will count it.
+    pub fn bytes_for(width: u32, height: u32, format: TextureFormat) -> usize {
+        let texels = width as usize * height as usize;
+        match format {
+            TextureFormat::F32 => texels * size_of::<[f32; 3]>(),
+            TextureFormat::F16 => texels * size_of::<[f16; 3]>(),
+            TextureFormat::Block => {
+                width.div_ceil(4) as usize * height.div_ceil(4) as usize * size_of::<Block>()
+            }
+        }
+    }
+
+    // Bytes held in memory, as counted against the cache budget.
+    pub fn bytes(&self) -> usize {
+        match &self.storage {
+            Storage::F32(image) => std::mem::size_of_val(image.as_raw().as_slice()),
+            Storage::F16(texels) => std::mem::size_of_val(texels.as_slice()),
+            Storage::Block(blocks) => std::mem::size_of_val(blocks.as_slice()),
+        }
+    }
+}
+
+impl Block {
+    // Endpoints are the corners of the block's bounding box along the diagonal that follows
+    // how the channels vary together; each texel takes the nearest of four points between them.
+    fn encode(texels: &[[f32; 3]; 16]) -> Self {
+        let mut lo = [f32::INFINITY; 3];
+        let mut hi = [f32::NEG_INFINITY; 3];
+        let mut mean = [0.0; 3];
+        for t in texels {
+            for c in 0..3 {
+                lo[c] = lo[c].min(t[c]);
+                hi[c] = hi[c].max(t[c]);
+                mean[c] += t[c] / 16.0;
+            }
+        }
+        // Channels that fall as the widest one rises run the other way along the diagonal.
+        let widest = (0..3)
+            .max_by(|&a, &b| (hi[a] - lo[a]).total_cmp(&(hi[b] - lo[b])))
+            .unwrap_or(0);
+        for c in 0..3 {
+            let covariance: f32 = texels
+                .iter()
+                .map(|t| (t[widest] - mean[widest]) * (t[c] - mean[c]))
+                .sum();
+            if covariance < 0.0 {
+                std::mem::swap(&mut lo[c], &mut hi[c]);
+            }
+        }
+        let ends = [lo.map(f16::from_f32), hi.map(f16::from_f32)];
+        let (a, b) = (ends[0].map(f32::from), ends[1].map(f32::from));
+        let axis = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let length_squared: f32 = axis.iter().map(|d| d * d).sum();
+        let mut indices = 0;
+        for (i, t) in texels.iter().enumerate() {
+            let along = if length_squared > 0.0 {
+                (0..3).map(|c| (t[c] - a[c]) * axis[c]).sum::<f32>() / length_squared
+            } else {
+                0.0
+            };
+            let index = (along * 3.0).round().clamp(0.0, 3.0) as u32;
+            indices |= index << (2 * i);
+        }
+        Self { ends, indices }
+    }
+
+    fn decode(&self, i: usize) -> [f32; 3] {
+        let w = ((self.indices >> (2 * i)) & 3) as f32 / 3.0;
+        let (a, b) = (self.ends[0], self.ends[1]);
+        std::array::from_fn(|c| f32::from(a[c]) * (1.0 - w) + f32::from(b[c]) * w)
+    }
+}
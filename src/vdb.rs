@@ -0,0 +1,294 @@
+// Reads float grids from NanoVDB files (`.nvdb`), e.g. density and temperature from a Houdini or
+// Blender smoke simulation converted with `nanovdb_convert`. NanoVDB stores a grid as one
+// pointer-free buffer, so a voxel is found by following byte offsets through the root tiles
+// and the 32^3, 16^3 and 8^3 nodes. Grids are resampled into a dense `Field::Grid` over their
+// active voxels, with samples at voxel centers; the grid transform is assumed to be axis
+// aligned. Uncompressed and zip-compressed files are supported, Blosc is not.
+
+use crate::aabb::Aabb;
+use crate::error::{Error, Result};
+use crate::vec3::Point3;
+use crate::volume::Field;
+use std::io::Read;
+use std::path::Path;
+
+// "NanoVDB" followed by a format digit, little-endian.
+const MAGIC_PREFIX: u64 = 0x0042_4456_6f6e_614e;
+const MAGIC_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+const MAJOR_VERSION: u32 = 32;
+const GRID_TYPE_FLOAT: u32 = 1;
+const CODEC_NONE: u16 = 0;
+const CODEC_ZIP: u16 = 1;
+
+const FILE_HEADER_BYTES: usize = 16;
+const FILE_META_BYTES: usize = 176;
+const GRID_DATA_BYTES: usize = 672;
+const TREE_DATA_BYTES: usize = 64;
+const ROOT_DATA_BYTES: usize = 64;
+const ROOT_TILE_BYTES: usize = 32;
+const UPPER_TABLE_OFFSET: usize = 8256;
+const LOWER_TABLE_OFFSET: usize = 1088;
+const LEAF_VALUES_OFFSET: usize = 96;
+// Offset of the double precision index-to-world matrix and translation in the grid data.
+const MAP_MAT_OFFSET: usize = 384;
+const MAP_VEC_OFFSET: usize = 528;
+const GRID_TYPE_OFFSET: usize = 636;
+
+// Names of the grids in a NanoVDB file, in file order.
+pub fn grid_names(path: &Path) -> Result<Vec<String>> {
+    Ok(read_file(path)?.into_iter().map(|(name, _)| name).collect())
+}
+
+// The float grid called `name`, resampled over its active voxels.
+pub fn load_grid(path: &Path, name: &str) -> Result<Field> {
+    let grids = read_file(path)?;
+    let Some((_, buffer)) = grids.into_iter().find(|(n, _)| n == name) else {
+        return Err(Error::Scene(format!(
+            "{} has no grid named {name:?}",
+            path.display()
+        )));
+    };
+    dense_field(&buffer).map_err(|e| Error::Scene(format!("{}: {e}", path.display())))
+}
+
+// Each grid's name and uncompressed buffer.
+fn read_file(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let bytes = std::fs::read(path)?;
+    parse_file(&bytes).map_err(|e| Error::Scene(format!("{}: {e}", path.display())))
+}
+
+// A file is a sequence of segments: a header, then metadata and name for each of its grids,
+// then the grid buffers themselves.
+fn parse_file(bytes: &[u8]) -> std::result::Result<Vec<(String, Vec<u8>)>, String> {
+    let mut grids = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let header = Bytes(slice(bytes, pos, FILE_HEADER_BYTES)?);
+        if header.u64(0)? & MAGIC_MASK != MAGIC_PREFIX {
+            return Err("not a NanoVDB file".to_string());
+        }
+        check_version(header.u32(8)?)?;
+        let count = header.u16(12)? as usize;
+        let codec = header.u16(14)?;
+        pos += FILE_HEADER_BYTES;
+
+        let mut metas = Vec::with_capacity(count);
+        for _ in 0..count {
+            let meta = Bytes(slice(bytes, pos, FILE_META_BYTES)?);
+            let grid_size = meta.u64(0)? as usize;
+            let file_size = meta.u64(8)? as usize;
+            let name_size = meta.u32(136)? as usize;
+            pos += FILE_META_BYTES;
+            let name = slice(bytes, pos, name_size)?;
+            let name = name.split(|&b| b == 0).next().unwrap_or_default();
+            metas.push((
+                String::from_utf8_lossy(name).into_owned(),
+                grid_size,
+                file_size,
+            ));
+            pos += name_size;
+        }
+        for (name, grid_size, file_size) in metas {
+            let data = slice(bytes, pos, file_size)?;
+            pos += file_size;
+            let buffer = match codec {
+                CODEC_NONE => data.to_vec(),
+                CODEC_ZIP => {
+                    // A byte count, then a zlib stream.
+                    let compressed = slice(data, 8, file_size.saturating_sub(8))?;
+                    let mut buffer = Vec::with_capacity(grid_size);
+                    flate2::read::ZlibDecoder::new(compressed)
+                        .read_to_end(&mut buffer)
+                        .map_err(|e| format!("grid {name:?}: {e}"))?;
+                    buffer
+                }
+                other => return Err(format!("unsupported codec {other} (only none and zip)")),
+            };
+            if buffer.len() != grid_size {
+                return Err(format!("grid {name:?} is truncated"));
+            }
+            grids.push((name, buffer));
+        }
+    }
+    Ok(grids)
+}
+
+fn check_version(version: u32) -> std::result::Result<(), String> {
+    let major = version >> 21;
+    if major != MAJOR_VERSION {
+        return Err(format!(
+            "NanoVDB version {major}.{}, expected {MAJOR_VERSION}.x",
+            (version >> 10) & 0x7ff
+        ));
+    }
+    Ok(())
+}
+
+fn dense_field(buffer: &[u8]) -> std::result::Result<Field, String> {
+    let grid = FloatGrid::new(buffer)?;
+    let (min, max) = grid.index_bbox()?;
+    if (0..3).any(|a| max[a] < min[a]) {
+        return Err("grid has no active voxels".to_string());
+    }
+    let resolution = [0, 1, 2].map(|a| (max[a] - min[a]) as usize + 1);
+    let mut values = Vec::with_capacity(resolution.iter().product());
+    for k in min[2]..=max[2] {
+        for j in min[1]..=max[1] {
+            for i in min[0]..=max[0] {
+                values.push(grid.value([i, j, k])?);
+            }
+        }
+    }
+    let (a, b) = (grid.index_to_world(min)?, grid.index_to_world(max)?);
+    Ok(Field::Grid {
+        bounds: Aabb::from_points(a, b),
+        resolution,
+        values,
+    })
+}
+
+// Little-endian reads that fail on truncated or corrupt buffers instead of panicking.
+#[derive(Copy, Clone)]
+struct Bytes<'a>(&'a [u8]);
+
+impl Bytes<'_> {
+    fn array<const N: usize>(self, at: usize) -> std::result::Result<[u8; N], String> {
+        Ok(slice(self.0, at, N)?.try_into().expect("slice of N bytes"))
+    }
+
+    fn u16(self, at: usize) -> std::result::Result<u16, String> {
+        self.array(at).map(u16::from_le_bytes)
+    }
+
+    fn u32(self, at: usize) -> std::result::Result<u32, String> {
+        self.array(at).map(u32::from_le_bytes)
+    }
+
+    fn i32(self, at: usize) -> std::result::Result<i32, String> {
+        self.array(at).map(i32::from_le_bytes)
+    }
+
+    fn u64(self, at: usize) -> std::result::Result<u64, String> {
+        self.array(at).map(u64::from_le_bytes)
+    }
+
+    fn i64(self, at: usize) -> std::result::Result<i64, String> {
+        self.array(at).map(i64::from_le_bytes)
+    }
+
+    fn f32(self, at: usize) -> std::result::Result<f32, String> {
+        self.array(at).map(f32::from_le_bytes)
+    }
+
+    fn f64(self, at: usize) -> std::result::Result<f64, String> {
+        self.array(at).map(f64::from_le_bytes)
+    }
+
+    // Bit `n` of the mask starting at `at`.
+    fn bit(self, at: usize, n: usize) -> std::result::Result<bool, String> {
+        Ok(self.u64(at + 8 * (n >> 6))? & (1 << (n & 63)) != 0)
+    }
+}
+
+fn slice(bytes: &[u8], at: usize, len: usize) -> std::result::Result<&[u8], String> {
+    at.checked_add(len)
+        .and_then(|end| bytes.get(at..end))
+        .ok_or_else(|| "unexpected end of data".to_string())
+}
+
+struct FloatGrid<'a> {
+    data: Bytes<'a>,
+    root: usize,
+    tiles: usize,
+    background: f32,
+}
+
+impl<'a> FloatGrid<'a> {
+    fn new(buffer: &'a [u8]) -> std::result::Result<Self, String> {
+        let data = Bytes(buffer);
+        if data.u64(0)? & MAGIC_MASK != MAGIC_PREFIX {
+            return Err("grid buffer has no NanoVDB magic".to_string());
+        }
+        check_version(data.u32(16)?)?;
+        let grid_type = data.u32(GRID_TYPE_OFFSET)?;
+        if grid_type != GRID_TYPE_FLOAT {
+            return Err(format!("grid type {grid_type} is not float"));
+        }
+        // The tree data follows the grid data and holds node offsets relative to itself; the
+        // root's is the fourth.
+        let root = GRID_DATA_BYTES + data.u64(GRID_DATA_BYTES + 24)? as usize;
+        if root < GRID_DATA_BYTES + TREE_DATA_BYTES {
+            return Err("bad root offset".to_string());
+        }
+        Ok(Self {
+            data,
+            root,
+            tiles: data.u32(root + 24)? as usize,
+            background: data.f32(root + 28)?,
+        })
+    }
+
+    // Inclusive index-space bounds of the active voxels.
+    fn index_bbox(&self) -> std::result::Result<([i32; 3], [i32; 3]), String> {
+        let c = |i: usize| self.data.i32(self.root + 4 * i);
+        Ok(([c(0)?, c(1)?, c(2)?], [c(3)?, c(4)?, c(5)?]))
+    }
+
+    fn index_to_world(&self, ijk: [i32; 3]) -> std::result::Result<Point3, String> {
+        let m = |i: usize| self.data.f64(MAP_MAT_OFFSET + 8 * i);
+        let v = |i: usize| self.data.f64(MAP_VEC_OFFSET + 8 * i);
+        let [i, j, k] = ijk.map(f64::from);
+        let row = |r: usize| Ok::<_, String>(i * m(3 * r)? + j * m(3 * r + 1)? + k * m(3 * r + 2)?);
+        Ok(Point3::new(
+            row(0)? + v(0)?,
+            row(1)? + v(1)?,
+            row(2)? + v(2)?,
+        ))
+    }
+
+    fn value(&self, ijk: [i32; 3]) -> std::result::Result<f32, String> {
+        let [i, j, k] = ijk.map(|c| c as u32);
+        let key = (k >> 12) as u64 | ((j >> 12) as u64) << 21 | ((i >> 12) as u64) << 42;
+        let tile = (0..self.tiles)
+            .map(|n| self.root + ROOT_DATA_BYTES + n * ROOT_TILE_BYTES)
+            .find(|&t| self.data.u64(t).is_ok_and(|k| k == key));
+        let Some(tile) = tile else {
+            return Ok(self.background);
+        };
+        let child = self.data.i64(tile + 8)?;
+        if child == 0 {
+            return self.data.f32(tile + 20);
+        }
+
+        let upper = offset(self.root, child)?;
+        let n = (((i & 4095) >> 7) << 10 | ((j & 4095) >> 7) << 5 | (k & 4095) >> 7) as usize;
+        let Some(lower) = self.child(upper, 32 + 4096, UPPER_TABLE_OFFSET, n)? else {
+            return self.data.f32(upper + UPPER_TABLE_OFFSET + 8 * n);
+        };
+        let n = (((i & 127) >> 3) << 8 | ((j & 127) >> 3) << 4 | (k & 127) >> 3) as usize;
+        let Some(leaf) = self.child(lower, 32 + 512, LOWER_TABLE_OFFSET, n)? else {
+            return self.data.f32(lower + LOWER_TABLE_OFFSET + 8 * n);
+        };
+        let n = ((i & 7) << 6 | (j & 7) << 3 | (k & 7)) as usize;
+        self.data.f32(leaf + LEAF_VALUES_OFFSET + 4 * n)
+    }
+
+    // Address of child `n` of the internal node at `node`, if the child mask has it.
+    fn child(
+        &self,
+        node: usize,
+        child_mask: usize,
+        table: usize,
+        n: usize,
+    ) -> std::result::Result<Option<usize>, String> {
+        if !self.data.bit(node + child_mask, n)? {
+            return Ok(None);
+        }
+        offset(node, self.data.i64(node + table + 8 * n)?).map(Some)
+    }
+}
+
+fn offset(base: usize, delta: i64) -> std::result::Result<usize, String> {
+    base.checked_add_signed(delta as isize)
+        .ok_or_else(|| "bad node offset".to_string())
+}
@@ -0,0 +1,114 @@
+use crate::error::{Error, Result};
+use image::RgbaImage;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoSettings {
+    pub fps: f64,
+    // Target bitrate in ffmpeg syntax, e.g. "8M". None uses the codec's quality default.
+    pub bitrate: Option<String>,
+    pub ffmpeg: PathBuf,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            fps: 24.0,
+            bitrate: None,
+            ffmpeg: PathBuf::from("ffmpeg"),
+        }
+    }
+}
+
+// Encodes frames by piping raw RGBA to an ffmpeg process. The container and codec follow the
+// output extension: `.webm` uses VP9, anything else H.264.
+pub struct VideoEncoder {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    width: u32,
+    height: u32,
+    path: PathBuf,
+}
+
+impl VideoEncoder {
+    pub fn new(path: &Path, width: u32, height: u32, settings: &VideoSettings) -> Result<Self> {
+        let codec = match path.extension().and_then(|e| e.to_str()) {
+            Some("webm") => "libvpx-vp9",
+            _ => "libx264",
+        };
+
+        let mut cmd = Command::new(&settings.ffmpeg);
+        cmd.args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgba",
+        ])
+        .args(["-s", &format!("{width}x{height}")])
+        .args(["-r", &settings.fps.to_string()])
+        .args(["-i", "-", "-c:v", codec, "-pix_fmt", "yuv420p"])
+        // 4:2:0 chroma needs even dimensions.
+        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"]);
+        if let Some(bitrate) = &settings.bitrate {
+            cmd.args(["-b:v", bitrate]);
+        }
+        let mut child =
+            cmd.arg(path).stdin(Stdio::piped()).spawn().map_err(|e| {
+                Error::Encoder(format!("failed to start {:?}: {e}", settings.ffmpeg))
+            })?;
+
+        Ok(Self {
+            stdin: child.stdin.take(),
+            child,
+            width,
+            height,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn push_frame(&mut self, frame: &RgbaImage) -> Result<()> {
+        if frame.dimensions() != (self.width, self.height) {
+            return Err(Error::Encoder(format!(
+                "frame is {}x{}, video is {}x{}",
+                frame.width(),
+                frame.height(),
+                self.width,
+                self.height
+            )));
+        }
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| Error::Encoder("encoder input already closed".into()))?;
+        stdin.write_all(frame.as_raw())?;
+        Ok(())
+    }
+
+    // Closes the stream and waits for ffmpeg to finish writing the file.
+    pub fn finish(mut self) -> Result<()> {
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(Error::Encoder(format!(
+                "ffmpeg exited with {status} while writing {}",
+                self.path.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VideoEncoder {
+    // An encoder dropped without `finish` was abandoned; don't leave ffmpeg running.
+    fn drop(&mut self) {
+        if self.stdin.take().is_some() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
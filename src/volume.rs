@@ -0,0 +1,422 @@
+// Participating media: smoke, fire, clouds. A `Volume` fills a closed boundary with a density
+// field and is traced by delta tracking against the field's maximum, so a ray either passes
+// through or stops at a sampled collision. Collisions scatter isotropically with the medium's
+// albedo; the rest of the light is absorbed, and absorbing media can emit by temperature
+// through a blackbody ramp. Grids from simulations can be loaded from NanoVDB files with the
+// `vdb` feature.
+
+use crate::aabb::Aabb;
+use crate::error::{Error, Result};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::{random_in_unit_sphere, Material, MaterialId, MaterialRef};
+use crate::ray::Ray;
+use crate::scene::{MaterialTable, ObjectDesc};
+use crate::stats::SceneStats;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// Scalar field over space, e.g. extinction per unit length or temperature in Kelvin.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Field {
+    Constant {
+        value: f64,
+    },
+    // `value` at `center`, falling off linearly to zero at `radius`.
+    Radial {
+        center: Point3,
+        radius: f64,
+        value: f64,
+    },
+    // Samples at the corners of a regular lattice spanning `bounds`, x varying fastest, and
+    // trilinearly interpolated between them. Zero outside the bounds.
+    Grid {
+        bounds: Aabb,
+        resolution: [usize; 3],
+        values: Vec<f32>,
+    },
+    // Float grid named `grid` in a NanoVDB file, read into a `Grid` when the volume is built.
+    // Needs the `vdb` feature.
+    Vdb {
+        path: PathBuf,
+        grid: String,
+    },
+}
+
+impl Field {
+    pub fn value(&self, p: Point3) -> f64 {
+        match self {
+            Field::Constant { value } => *value,
+            Field::Radial {
+                center,
+                radius,
+                value,
+            } => value * (1.0 - (p - *center).length() / radius).max(0.0),
+            Field::Grid {
+                bounds,
+                resolution,
+                values,
+            } => grid_value(bounds, *resolution, values, p),
+            Field::Vdb { .. } => 0.0,
+        }
+    }
+
+    // Largest value anywhere in the field.
+    pub fn max_value(&self) -> f64 {
+        match self {
+            Field::Constant { value } | Field::Radial { value, .. } => value.max(0.0),
+            Field::Grid { values, .. } => values.iter().fold(0.0, |m, &v| m.max(v as f64)),
+            Field::Vdb { .. } => 0.0,
+        }
+    }
+
+    // Box outside which the field is zero, if there is one.
+    pub fn bounds(&self) -> Option<Aabb> {
+        match self {
+            Field::Radial { center, radius, .. } => {
+                let r = Vec3::new(radius.abs(), radius.abs(), radius.abs());
+                Some(Aabb::new(*center - r, *center + r))
+            }
+            Field::Grid { bounds, .. } => Some(*bounds),
+            Field::Constant { .. } | Field::Vdb { .. } => None,
+        }
+    }
+
+    // Reads file-backed fields and checks grids are consistent.
+    pub fn load(self) -> Result<Self> {
+        let field = match self {
+            Field::Vdb { path, grid } => load_vdb(&path, &grid)?,
+            field => field,
+        };
+        field.validate()?;
+        Ok(field)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Field::Grid {
+            resolution, values, ..
+        } = self
+        {
+            if resolution.contains(&0) {
+                return Err(Error::Scene(format!("empty grid of {resolution:?}")));
+            }
+            let expected = resolution.iter().product::<usize>();
+            if expected != values.len() {
+                return Err(Error::Scene(format!(
+                    "grid of {resolution:?} needs {expected} values, got {}",
+                    values.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn heap_bytes(&self) -> usize {
+        match self {
+            Field::Grid { values, .. } => values.capacity() * std::mem::size_of::<f32>(),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(feature = "vdb")]
+fn load_vdb(path: &std::path::Path, grid: &str) -> Result<Field> {
+    crate::vdb::load_grid(path, grid)
+}
+
+#[cfg(not(feature = "vdb"))]
+fn load_vdb(path: &std::path::Path, _grid: &str) -> Result<Field> {
+    Err(Error::Scene(format!(
+        "can't load {}: built without the `vdb` feature",
+        path.display()
+    )))
+}
+
+fn grid_value(bounds: &Aabb, resolution: [usize; 3], values: &[f32], p: Point3) -> f64 {
+    let extent = bounds.extent();
+    let local = p - bounds.min;
+    let mut cell = [0usize; 3];
+    let mut frac = [0.0; 3];
+    for (axis, (x, size)) in [
+        (local.x, extent.x),
+        (local.y, extent.y),
+        (local.z, extent.z),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if !(0.0..=size).contains(&x) {
+            return 0.0;
+        }
+        let last = resolution[axis].saturating_sub(1);
+        let g = if size > 0.0 {
+            x / size * last as f64
+        } else {
+            0.0
+        };
+        cell[axis] = (g as usize).min(last.saturating_sub(1));
+        frac[axis] = (g - cell[axis] as f64).min(1.0);
+    }
+
+    let [nx, ny, _] = resolution;
+    let at = |x: usize, y: usize, z: usize| {
+        let clamp = |i: usize, axis: usize| i.min(resolution[axis] - 1);
+        values[clamp(x, 0) + nx * (clamp(y, 1) + ny * clamp(z, 2))] as f64
+    };
+    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+    let [x, y, z] = cell;
+    let [fx, fy, fz] = frac;
+    let plane = |z: usize| {
+        lerp(
+            lerp(at(x, y, z), at(x + 1, y, z), fx),
+            lerp(at(x, y + 1, z), at(x + 1, y + 1, z), fx),
+            fy,
+        )
+    };
+    lerp(plane(z), plane(z + 1), fz)
+}
+
+pub use crate::color::blackbody;
+
+// Light given off by the absorbing part of a medium. Radiance at a point is `intensity` times
+// the blackbody color of its temperature, dimmed by the fourth power of the temperature
+// relative to the hottest point so cooler regions fade to dull red rather than glowing just
+// as brightly.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Emission {
+    pub temperature: Field,
+    pub intensity: f64,
+}
+
+impl Emission {
+    pub fn new(temperature: Field, intensity: f64) -> Self {
+        Self {
+            temperature,
+            intensity,
+        }
+    }
+
+    pub fn radiance(&self, p: Point3) -> Color {
+        let t = self.temperature.value(p);
+        let t_max = self.temperature.max_value();
+        if t <= 0.0 || t_max <= 0.0 {
+            return Color::default();
+        }
+        self.intensity * (t / t_max).powi(4) * blackbody(t)
+    }
+}
+
+enum Boundary {
+    // Closed, convex object; only its shape is used and its material is ignored.
+    Object(Arc<dyn Hittable>),
+    Box(Aabb),
+}
+
+impl Boundary {
+    // Ray parameters where the ray enters and leaves, either possibly behind the origin.
+    fn span(&self, r: &Ray) -> Option<(f64, f64)> {
+        match self {
+            Boundary::Object(object) => {
+                let hits = object.hit_all(r, Interval::UNIVERSE);
+                match (hits.first(), hits.last()) {
+                    (Some(enter), Some(exit)) if hits.len() > 1 => Some((enter.t, exit.t)),
+                    _ => None,
+                }
+            }
+            Boundary::Box(bbox) => {
+                let (o, d) = (r.origin(), r.direction());
+                let (mut t0, mut t1) = (f64::NEG_INFINITY, f64::INFINITY);
+                for (axis, (o, d)) in [(o.x, d.x), (o.y, d.y), (o.z, d.z)].into_iter().enumerate() {
+                    let (lo, hi) = bbox.axis(axis);
+                    let (a, b) = ((lo - o) / d, (hi - o) / d);
+                    // NaN from 0 / 0 on a slab face is dropped by `max`/`min`.
+                    t0 = t0.max(a.min(b));
+                    t1 = t1.min(a.max(b));
+                }
+                (t0 < t1).then_some((t0, t1))
+            }
+        }
+    }
+}
+
+pub struct Volume {
+    boundary: Boundary,
+    // Extinction per unit length.
+    pub density: Field,
+    majorant: f64,
+    medium: Arc<Medium>,
+    material: MaterialRef,
+}
+
+impl Volume {
+    // Medium filling `boundary`, which must be closed and convex.
+    pub fn new(boundary: Arc<dyn Hittable>, density: Field, albedo: Color) -> Result<Self> {
+        Self::with_boundary(Boundary::Object(boundary), density.load()?, albedo)
+    }
+
+    // Medium filling the bounds of its density field, e.g. a grid from a simulation.
+    pub fn from_field(density: Field, albedo: Color) -> Result<Self> {
+        let density = density.load()?;
+        let bounds = density
+            .bounds()
+            .ok_or_else(|| Error::Scene("unbounded volume needs a boundary".to_string()))?;
+        Self::with_boundary(Boundary::Box(bounds), density, albedo)
+    }
+
+    fn with_boundary(boundary: Boundary, density: Field, albedo: Color) -> Result<Self> {
+        let medium = Arc::new(Medium {
+            albedo,
+            emission: None,
+        });
+        Ok(Self {
+            boundary,
+            majorant: density.max_value(),
+            density,
+            material: MaterialRef::from(medium.clone()),
+            medium,
+        })
+    }
+
+    pub fn with_emission(mut self, emission: Emission) -> Result<Self> {
+        let emission = Emission {
+            temperature: emission.temperature.load()?,
+            ..emission
+        };
+        self.medium = Arc::new(Medium {
+            albedo: self.medium.albedo,
+            emission: Some(emission),
+        });
+        self.material = MaterialRef::from(self.medium.clone());
+        Ok(self)
+    }
+
+    // The object the medium fills, or None if it fills its density field's bounds.
+    pub fn boundary(&self) -> Option<&Arc<dyn Hittable>> {
+        match &self.boundary {
+            Boundary::Object(object) => Some(object),
+            Boundary::Box(_) => None,
+        }
+    }
+
+    #[inline]
+    pub fn albedo(&self) -> Color {
+        self.medium.albedo
+    }
+
+    #[inline]
+    pub fn emission(&self) -> Option<&Emission> {
+        self.medium.emission.as_ref()
+    }
+}
+
+impl Hittable for Volume {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let (enter, exit) = self.boundary.span(r)?;
+        let (t0, t1) = (enter.max(ray_t.min), exit.min(ray_t.max));
+        if t0 >= t1 {
+            return None;
+        }
+
+        // Delta tracking: step by the majorant's free-flight distance and accept a tentative
+        // collision with probability density / majorant.
+        let mut rng = rand::rng();
+        let speed = r.direction().length();
+        let mut t = t0;
+        loop {
+            t -= (1.0 - rng.random::<f64>()).ln() / (self.majorant * speed);
+            if t >= t1 {
+                return None;
+            }
+            let point = r.at(t);
+            if rng.random::<f64>() * self.majorant < self.density.value(point) {
+                return Some(HitRecord {
+                    t,
+                    point,
+                    normal: -Vec3::unit_vector(r.direction()),
+                    front_face: true,
+                    material: self.material.id(),
+                    object_id: 0,
+                    holdout: false,
+                    u: 0.0,
+                    v: 0.0,
+                });
+            }
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        match &self.boundary {
+            Boundary::Object(object) => object.bounding_box(time0, time1),
+            Boundary::Box(bbox) => Some(*bbox),
+        }
+    }
+
+    fn materials(&self, out: &mut Vec<MaterialId>) {
+        out.push(self.material.id());
+    }
+
+    fn stats(&self, stats: &mut SceneStats) {
+        let fields =
+            self.density.heap_bytes() + self.emission().map_or(0, |e| e.temperature.heap_bytes());
+        stats.add_primitive("Volume", std::mem::size_of_val(self) + fields);
+        if let Some(object) = self.boundary() {
+            object.stats(stats);
+        }
+    }
+
+    fn to_desc(&self, materials: &mut MaterialTable) -> Option<ObjectDesc> {
+        let boundary = match self.boundary() {
+            Some(object) => Some(Box::new(object.to_desc(materials)?)),
+            None => None,
+        };
+        Some(ObjectDesc::Volume {
+            boundary,
+            density: self.density.clone(),
+            albedo: self.albedo(),
+            emission: self.emission().cloned(),
+        })
+    }
+}
+
+// Phase function and emission at a collision inside a `Volume`.
+struct Medium {
+    albedo: Color,
+    emission: Option<Emission>,
+}
+
+impl Material for Medium {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        rec: &HitRecord,
+        rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        if self.albedo == Color::default() {
+            return None;
+        }
+        let direction = Vec3::unit_vector(random_in_unit_sphere(rng));
+        Some((
+            self.albedo,
+            Ray::with_time(rec.point, direction, ray_in.time()),
+        ))
+    }
+
+    // Only the absorbed fraction of collisions emits.
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        match &self.emission {
+            Some(e) => (Color::new(1.0, 1.0, 1.0) - self.albedo) * e.radiance(rec.point),
+            None => Color::default(),
+        }
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * std::f64::consts::PI)
+    }
+}
@@ -0,0 +1,104 @@
+// Waits for a scene file, or files it refers to, to change, for `rtt watch`: save the scene in
+// an editor and the render follows. Editors save in bursts of writes, or by writing a new file
+// and renaming it over the old one, so each file's directory is watched and quick successive
+// events count as one change.
+
+use crate::error::{Error, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+// How long the file must stay untouched before a change counts.
+pub const SETTLE: Duration = Duration::from_millis(100);
+
+pub struct SceneWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    // Names of the watched files, and the directories they are in. Events are matched by name
+    // alone, which is all editors reliably report.
+    files: Vec<OsString>,
+    dirs: Vec<PathBuf>,
+}
+
+impl SceneWatcher {
+    pub fn new(path: &Path) -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(tx).map_err(watch_error)?;
+        let mut watcher = Self {
+            watcher,
+            events,
+            files: Vec::new(),
+            dirs: Vec::new(),
+        };
+        watcher.add(path)?;
+        Ok(watcher)
+    }
+
+    // Also waits for `path` to change, e.g. a file the scene refers to.
+    pub fn add(&mut self, path: &Path) -> Result<()> {
+        let file = path
+            .file_name()
+            .ok_or_else(|| Error::Scene(format!("{} isn't a file", path.display())))?
+            .to_owned();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !self.dirs.iter().any(|d| d == dir) {
+            self.watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(watch_error)?;
+            self.dirs.push(dir.to_path_buf());
+        }
+        if !self.files.contains(&file) {
+            self.files.push(file);
+        }
+        Ok(())
+    }
+
+    // Blocks until a watched file has changed and settled.
+    pub fn wait(&self) -> Result<()> {
+        while !self.wait_timeout(Duration::from_secs(3600))? {}
+        Ok(())
+    }
+
+    // Like `wait`, giving up after `timeout`; whether a file changed.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(left) {
+                Ok(event) => {
+                    if self.concerns(event.map_err(watch_error)?) {
+                        self.settle();
+                        return Ok(true);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(false),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::Watch("watcher stopped".into()))
+                }
+            }
+        }
+    }
+
+    fn concerns(&self, event: Event) -> bool {
+        !matches!(event.kind, EventKind::Access(_))
+            && event
+                .paths
+                .iter()
+                .filter_map(|p| p.file_name())
+                .any(|name| self.files.iter().any(|f| f == name))
+    }
+
+    // Drains events until none arrive for `SETTLE`.
+    fn settle(&self) {
+        while self.events.recv_timeout(SETTLE).is_ok() {}
+    }
+}
+
+fn watch_error(e: notify::Error) -> Error {
+    Error::Watch(e.to_string())
+}
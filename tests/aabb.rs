@@ -0,0 +1,95 @@
+use rtt::aabb::Aabb;
+use rtt::interval::Interval;
+use rtt::ray::Ray;
+use rtt::vec3::{Point3, Vec3};
+
+const EVERYWHERE: Interval = Interval::new(0.0, f64::INFINITY);
+
+fn unit_box() -> Aabb {
+    Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+}
+
+fn entry(origin: Point3, direction: Vec3, ray_t: Interval) -> Option<f64> {
+    unit_box().entry(&Ray::new(origin, direction), ray_t)
+}
+
+#[test]
+fn axis_parallel_rays_hit_and_miss() {
+    for axis in 0..3 {
+        for sign in [1.0, -1.0] {
+            let mut d = [0.0; 3];
+            d[axis] = sign;
+            let direction = Vec3::new(d[0], d[1], d[2]);
+            let origin = Point3::default() - 5.0 * direction;
+            assert_eq!(entry(origin, direction, EVERYWHERE), Some(4.0));
+            // Beside the box, and on the plane of one of its faces.
+            let mut o = [origin.x, origin.y, origin.z];
+            o[(axis + 1) % 3] = 2.0;
+            let beside = Point3::new(o[0], o[1], o[2]);
+            assert_eq!(entry(beside, direction, EVERYWHERE), None, "{beside:?}");
+            o[(axis + 1) % 3] = 1.0;
+            let grazing = Point3::new(o[0], o[1], o[2]);
+            assert_eq!(entry(grazing, direction, EVERYWHERE), Some(4.0));
+        }
+    }
+    // Negative zero components are parallel too.
+    let direction = Vec3::new(-0.0, -0.0, -1.0);
+    assert_eq!(
+        entry(Point3::new(0.5, -0.5, 5.0), direction, EVERYWHERE),
+        Some(4.0)
+    );
+    assert_eq!(
+        entry(Point3::new(1.5, -0.5, 5.0), direction, EVERYWHERE),
+        None
+    );
+}
+
+#[test]
+fn rays_starting_inside_enter_at_once() {
+    let inside = Point3::new(0.2, -0.3, 0.9);
+    for direction in [
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        Vec3::new(-1.0, 2.0, 0.5),
+    ] {
+        assert_eq!(entry(inside, direction, EVERYWHERE), Some(0.0));
+        let later = Interval::new(0.01, f64::INFINITY);
+        assert_eq!(entry(inside, direction, later), Some(0.01));
+        // Until it has already left.
+        assert_eq!(
+            entry(inside, direction, Interval::new(10.0, f64::INFINITY)),
+            None
+        );
+    }
+}
+
+#[test]
+fn rays_only_hit_within_their_interval() {
+    let origin = Point3::new(0.0, 0.0, 5.0);
+    let towards = Vec3::new(0.0, 0.0, -1.0);
+    assert!(!unit_box().hit(&Ray::new(origin, -towards), EVERYWHERE));
+    assert_eq!(entry(origin, towards, Interval::new(0.0, 3.9)), None);
+    assert_eq!(entry(origin, towards, Interval::new(0.0, 4.0)), Some(4.0));
+    assert_eq!(entry(origin, towards, Interval::new(5.0, 6.0)), Some(5.0));
+}
+
+#[test]
+fn empty_boxes_hold_nothing() {
+    let empty = Aabb::EMPTY;
+    assert!(empty.is_empty());
+    assert_eq!(empty.surface_area(), 0.0);
+    for (origin, direction) in [
+        (Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)),
+        (Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+    ] {
+        assert!(!empty.hit(&Ray::new(origin, direction), EVERYWHERE));
+    }
+    // Any union or point replaces it.
+    assert_eq!(Aabb::surrounding_box(empty, unit_box()), unit_box());
+    assert_eq!(Aabb::surrounding_box(unit_box(), empty), unit_box());
+    let p = Point3::new(1.0, 2.0, 3.0);
+    assert_eq!(empty.expand(p), Aabb::new(p, p));
+    assert!(!empty.expand(p).is_empty());
+    let b = Aabb::from_points(Point3::new(1.0, -1.0, 1.0), Point3::new(-1.0, 1.0, -1.0));
+    assert_eq!(b, unit_box());
+}
@@ -0,0 +1,450 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aov::{
+    check_light_groups, light_group_names, Aov, AovSet, IdFilm, MaterialIds, PathClass,
+};
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::light::QuadLight;
+use rtt::material::{Dielectric, DiffuseLight, Lambertian, Material, MaterialRef, Metal};
+use rtt::mesh::Mesh;
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, trace_path, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Left half of the view is an evenly glowing wall, right half a grey floor lit by the sky.
+#[test]
+fn error_aovs_find_the_noisy_pixels() {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1000.5, 0.0, 0.0),
+        1000.0,
+        Arc::new(DiffuseLight::new(Color::new(0.8, 0.8, 0.8))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.5, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 2.0),
+        Point3::new(0.0, -0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        60.0,
+        1.0,
+        0.0,
+        2.0,
+    );
+    let (width, height) = (8, 8);
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(&[Aov::Variance, Aov::RelativeError], &world, width, height);
+    let settings = RenderSettings::default().with_shadow_samples(0);
+    render_image_with(&world, &camera, &film, &aovs, 64, &settings, &|_| {}).unwrap();
+
+    let variance = aovs.develop(Aov::Variance, &film).unwrap().unwrap();
+    let error = aovs.develop(Aov::RelativeError, &film).unwrap().unwrap();
+    assert!(aovs.develop(Aov::Depth, &film).unwrap().is_none());
+
+    // The emitter's pixels see the same radiance from every sample.
+    let wall = variance.get_pixel(0, height / 2);
+    assert_eq!(wall[3], 1.0);
+    assert!(wall[1] < 1e-12, "{wall:?}");
+    assert!(error.get_pixel(0, height / 2)[0] < 1e-6);
+
+    // The floor bounces towards the sky or the wall at random.
+    let floor = variance.get_pixel(width - 1, height - 1);
+    assert!(floor[1] > 1e-7, "{floor:?}");
+    let e = error.get_pixel(width - 1, height - 1);
+    assert!(e[0] > 1e-3 && e[0] < 0.5, "{e:?}");
+}
+
+// Ground with a mirror ball and a glass ball side by side under a lamp and the sky.
+fn balls() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.0, 0.5, 0.0),
+        0.5,
+        Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.0, 0.5, 0.0),
+        0.5,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.5, 3.0, -0.5),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(8.0, 8.0, 8.0),
+    )));
+    let camera = Camera::new(
+        Point3::new(0.0, 1.0, 4.0),
+        Point3::new(0.0, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        50.0,
+        1.0,
+        0.0,
+        4.0,
+    );
+    (world, camera)
+}
+
+#[test]
+fn path_classes_split_the_beauty() {
+    let (world, camera) = balls();
+    let mut rng = StdRng::seed_from_u64(7);
+    let class = |c: PathClass| c as usize;
+
+    // Straight up into the sky, and straight down onto the ground.
+    let sky = trace_path(
+        Ray::new(Point3::new(0.0, 1.0, 4.0), Vec3::new(0.0, 1.0, 0.0)),
+        &world,
+        &mut rng,
+    );
+    assert_eq!(sky.classes[class(PathClass::Emission)], sky.color);
+    let mut ground = [Color::default(); 6];
+    for _ in 0..200 {
+        let sample = trace_path(
+            Ray::new(Point3::new(0.0, 1.0, 2.0), Vec3::new(0.0, -1.0, 0.0)),
+            &world,
+            &mut rng,
+        );
+        for (sum, c) in ground.iter_mut().zip(sample.classes) {
+            *sum += c;
+        }
+    }
+    assert!(ground[class(PathClass::DirectDiffuse)].g() > 0.0);
+    assert!(ground[class(PathClass::IndirectDiffuse)].g() > 0.0);
+    for c in [
+        PathClass::Emission,
+        PathClass::DirectSpecular,
+        PathClass::IndirectSpecular,
+        PathClass::Transmission,
+    ] {
+        assert_eq!(ground[class(c)], Color::default(), "{c:?}");
+    }
+
+    // The mirror ball reflects the sky and the ground; the glass ball mostly refracts.
+    let mirror = trace_path(
+        Ray::new(Point3::new(-1.0, 0.5, 4.0), Vec3::new(0.0, 0.0, -1.0)),
+        &world,
+        &mut rng,
+    );
+    let specular = mirror.classes[class(PathClass::DirectSpecular)]
+        + mirror.classes[class(PathClass::IndirectSpecular)];
+    assert!((specular - mirror.color).length() < 1e-12);
+    let glass: f64 = (0..200)
+        .map(|_| {
+            let s = trace_path(
+                Ray::new(Point3::new(1.0, 0.5, 4.0), Vec3::new(0.0, 0.0, -1.0)),
+                &world,
+                &mut rng,
+            );
+            s.classes[class(PathClass::Transmission)].g() / s.color.g().max(1e-12)
+        })
+        .sum();
+    assert!(glass > 150.0, "{glass}");
+
+    // Over a whole render the class films add up to the beauty.
+    let (width, height) = (8, 8);
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(&[Aov::PathClasses], &world, width, height);
+    let settings = RenderSettings::default();
+    render_image_with(&world, &camera, &film, &aovs, 16, &settings, &|_| {}).unwrap();
+    let beauty = film.resolve(1.0).unwrap();
+    let classes: Vec<Vec<Color>> = aovs
+        .path_classes()
+        .map(|(_, f)| f.resolve(1.0).unwrap())
+        .collect();
+    assert_eq!(classes.len(), 6);
+    for (i, b) in beauty.iter().enumerate() {
+        let sum = classes.iter().fold(Color::default(), |acc, f| acc + f[i]);
+        assert!(
+            (sum - *b).length() <= 1e-9 * b.length().max(1.0),
+            "{sum:?} vs {b:?}"
+        );
+    }
+    assert!(aovs.develop(Aov::PathClasses, &film).unwrap().is_none());
+}
+
+#[test]
+fn denoise_aovs_remodulate_to_the_beauty() {
+    let (world, camera) = balls();
+    let (width, height) = (8, 8);
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(
+        &[Aov::Albedo, Aov::Diffuse, Aov::Specular],
+        &world,
+        width,
+        height,
+    );
+    let settings = RenderSettings::default();
+    render_image_with(&world, &camera, &film, &aovs, 16, &settings, &|_| {}).unwrap();
+    let beauty = film.resolve(1.0).unwrap();
+    let develop = |aov| aovs.develop(aov, &film).unwrap().unwrap();
+    let (albedo, diffuse, specular) = (
+        develop(Aov::Albedo),
+        develop(Aov::Diffuse),
+        develop(Aov::Specular),
+    );
+    let mut covered = 0;
+    for (i, b) in beauty.iter().enumerate() {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        let [ar, ag, ab, coverage] = albedo.get_pixel(x, y).0;
+        let [dr, dg, db, _] = diffuse.get_pixel(x, y).0;
+        let [sr, sg, sb, _] = specular.get_pixel(x, y).0;
+        // The ground is the only diffuse surface, so albedo is its color wherever it's seen.
+        if coverage > 0.0 {
+            covered += 1;
+            assert!((ag - 0.5).abs() < 1e-6, "{ag}");
+        }
+        let sum = Color::new(
+            (ar * dr * coverage + sr) as f64,
+            (ag * dg * coverage + sg) as f64,
+            (ab * db * coverage + sb) as f64,
+        );
+        assert!(
+            (sum - *b).length() <= 1e-4 * b.length().max(1.0),
+            "{sum:?} vs {b:?}"
+        );
+    }
+    assert!(covered > 0);
+}
+
+#[test]
+fn multilayer_exr_holds_every_layer() {
+    use exr::prelude::{read_all_flat_layers_from_file, FlatSamples};
+
+    let (world, camera) = balls();
+    let (width, height) = (6, 4);
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(
+        &[Aov::Depth, Aov::PathClasses, Aov::Albedo],
+        &world,
+        width,
+        height,
+    );
+    let settings = RenderSettings::default();
+    render_image_with(&world, &camera, &film, &aovs, 4, &settings, &|_| {}).unwrap();
+    let path = std::env::temp_dir().join(format!("rtt-aov-{}-layers.exr", std::process::id()));
+    aovs.save_multilayer(&film, 1.0, &path).unwrap();
+
+    let image = read_all_flat_layers_from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let [layer] = &image.layer_data[..] else {
+        panic!("{} parts", image.layer_data.len());
+    };
+    assert_eq!(layer.size.0, width as usize);
+    let channel = |name: &str| -> Vec<f32> {
+        let found = layer
+            .channel_data
+            .list
+            .iter()
+            .find(|c| c.name.to_string() == name)
+            .unwrap_or_else(|| panic!("no channel {name}"));
+        match &found.sample_data {
+            FlatSamples::F32(samples) => samples.clone(),
+            _ => panic!("{name} isn't f32"),
+        }
+    };
+    // The beauty is unprefixed, and the other layers are as `save` writes them.
+    let beauty = film.develop_linear(1.0).unwrap();
+    assert_eq!(
+        channel("G"),
+        beauty.pixels().map(|px| px.0[1]).collect::<Vec<_>>()
+    );
+    for (name, img) in aovs.layers(&film).unwrap() {
+        for (c, suffix) in ["R", "G", "B", "A"].into_iter().enumerate() {
+            let expected: Vec<f32> = img.pixels().map(|px| px.0[c]).collect();
+            assert_eq!(
+                channel(&format!("{name}.{suffix}")),
+                expected,
+                "{name}.{suffix}"
+            );
+        }
+    }
+    assert_eq!(
+        layer.channel_data.list.len(),
+        4 * (1 + 1 + PathClass::ALL.len() + 1)
+    );
+}
+
+// A wall 3 units straight ahead, over the left half of a 90 degree view.
+#[test]
+fn depth_and_position_find_the_wall() {
+    let mut world = HittableList::new();
+    let positions = vec![
+        Point3::new(-10.0, -10.0, -3.0),
+        Point3::new(0.0, -10.0, -3.0),
+        Point3::new(0.0, 10.0, -3.0),
+        Point3::new(-10.0, 10.0, -3.0),
+    ];
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Arc::new(
+        Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], grey).unwrap(),
+    ));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let size = 8;
+    let film = Film::new(size, size);
+    let aovs = AovSet::new(&[Aov::Depth, Aov::Position], &world, size, size);
+    let settings = RenderSettings::default().with_seed(4);
+    render_image_with(&world, &camera, &film, &aovs, 64, &settings, &|_| {}).unwrap();
+    let depth = aovs.develop(Aov::Depth, &film).unwrap().unwrap();
+    let position = aovs.develop(Aov::Position, &film).unwrap().unwrap();
+
+    // Film coordinates of pixel centers, 3 units out spanning -3 to 3.
+    let center = |i: u32| 3.0 * (2.0 * (i as f64 + 0.5) / size as f64 - 1.0);
+    for y in 0..size {
+        for x in 0..size {
+            let (d, p) = (depth.get_pixel(x, y).0, position.get_pixel(x, y).0);
+            if x < size / 2 {
+                assert!(d[..3].iter().all(|&d| (d - 3.0).abs() < 1e-5), "{d:?}");
+                assert_eq!((d[3], p[3]), (1.0, 1.0));
+                // The samples' mean lands near the pixel's center.
+                assert!((p[0] as f64 - center(x)).abs() < 0.1, "{x} {y} {p:?}");
+                assert!((p[1] as f64 + center(y)).abs() < 0.1, "{x} {y} {p:?}");
+                assert!((p[2] + 3.0).abs() < 1e-5, "{p:?}");
+            } else {
+                // Nothing there: no coverage and no value.
+                assert_eq!(d, [0.0; 4]);
+                assert_eq!(p, [0.0; 4]);
+            }
+        }
+    }
+}
+
+#[test]
+fn material_ids_follow_scene_order() {
+    let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.8, 0.1, 0.1)));
+    let blue: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.1, 0.1, 0.8)));
+    let mut world = HittableList::new();
+    for (x, material) in [(-2.0, &red), (0.0, &blue), (2.0, &red)] {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(x, 0.0, 0.0),
+            0.5,
+            material.clone(),
+        )));
+    }
+    let ids = MaterialIds::new(&world);
+    assert_eq!(ids.id(MaterialRef::new(red).unwrap().id()), 0);
+    assert_eq!(ids.id(MaterialRef::new(blue).unwrap().id()), 1);
+    let unseen = MaterialRef::new(Arc::new(Metal::new(Color::new(0.5, 0.5, 0.5), 0.0))).unwrap();
+    assert_eq!(ids.id(unseen.id()), u32::MAX);
+}
+
+#[test]
+fn id_coverage_is_ranked_by_weight() {
+    let mut total = Film::new(3, 1);
+    for _ in 0..4 {
+        total.add_sample(0, 0, Color::default(), 1.0).unwrap();
+    }
+    total.add_sample(1, 0, Color::default(), 2.0).unwrap();
+
+    let ids = IdFilm::new(3, 1);
+    let mut tile = ids.tile(0, 0, 3, 1);
+    tile.add_sample(0, 0, 9, 1.0);
+    tile.add_sample(0, 0, 3, 1.0);
+    tile.add_sample(1, 0, 5, 2.0);
+    // Never resolved by the beauty, so left empty.
+    tile.add_sample(2, 0, 4, 1.0);
+    ids.merge_tile(tile).unwrap();
+    let mut again = ids.tile(0, 0, 1, 1);
+    again.add_sample(0, 0, 3, 1.0);
+    again.add_sample(0, 0, 7, 1.0);
+    ids.merge_tile(again).unwrap();
+
+    let img = ids.develop(&total).unwrap();
+    // Most coverage first, ties to the lower ID; the third ID doesn't fit.
+    assert_eq!(img.get_pixel(0, 0).0, [3.0, 0.5, 7.0, 0.25]);
+    assert_eq!(img.get_pixel(1, 0).0, [5.0, 1.0, 0.0, 0.0]);
+    assert_eq!(img.get_pixel(2, 0).0, [0.0; 4]);
+}
+
+// Two walls meeting down the middle of the view; the middle column of pixels sees both.
+#[test]
+fn material_ids_split_pixels_on_an_edge() {
+    let wall = |x0: f64, x1: f64, material: Arc<dyn Material>| {
+        let positions = vec![
+            Point3::new(x0, -10.0, -3.0),
+            Point3::new(x1, -10.0, -3.0),
+            Point3::new(x1, 10.0, -3.0),
+            Point3::new(x0, 10.0, -3.0),
+        ];
+        Arc::new(Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], material).unwrap())
+    };
+    let mut world = HittableList::new();
+    world.add(wall(
+        -10.0,
+        0.0,
+        Arc::new(Lambertian::new(Color::new(0.8, 0.1, 0.1))),
+    ));
+    world.add(wall(
+        0.0,
+        10.0,
+        Arc::new(Lambertian::new(Color::new(0.1, 0.1, 0.8))),
+    ));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let size = 5;
+    let film = Film::new(size, size);
+    let aovs = AovSet::new(&[Aov::MaterialId], &world, size, size);
+    let settings = RenderSettings::default().with_seed(5);
+    render_image_with(&world, &camera, &film, &aovs, 256, &settings, &|_| {}).unwrap();
+    let ids = aovs.develop(Aov::MaterialId, &film).unwrap().unwrap();
+
+    for y in 0..size {
+        assert_eq!(ids.get_pixel(0, y).0, [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(ids.get_pixel(size - 1, y).0, [1.0, 1.0, 0.0, 0.0]);
+        let [id0, c0, id1, c1] = ids.get_pixel(size / 2, y).0;
+        let mut seen = [id0, id1];
+        seen.sort_by(f32::total_cmp);
+        assert_eq!(seen, [0.0, 1.0]);
+        assert!(c0 >= c1 && c1 > 0.3, "{c0} {c1}");
+        assert!((c0 + c1 - 1.0).abs() < 1e-5, "{c0} {c1}");
+    }
+}
+
+// An emitter grouped under a reserved name, as only code building its own world can make.
+#[test]
+fn reserved_light_groups_stay_apart() {
+    let lamp = |x: f64, light: DiffuseLight| {
+        Arc::new(Sphere::new(Point3::new(x, 0.0, 0.0), 0.5, Arc::new(light)))
+    };
+    let white = Color::new(1.0, 1.0, 1.0);
+    let mut world = HittableList::new();
+    world.add(lamp(-1.0, DiffuseLight::new(white)));
+    world.add(lamp(0.0, DiffuseLight::new(white).with_group("key")));
+    check_light_groups(&world).unwrap();
+    let names = light_group_names(&world);
+    assert_eq!(names, ["background", "emission", "key"].map(Arc::from));
+
+    world.add(lamp(1.0, DiffuseLight::new(white).with_group("emission")));
+    assert!(matches!(
+        check_light_groups(&world),
+        Err(rtt::Error::Scene(_))
+    ));
+    assert_eq!(light_group_names(&world), names);
+}
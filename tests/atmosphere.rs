@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::atmosphere::{Atmosphere, Haze};
+use rtt::background::{Background, SunSky};
+use rtt::hittable::{HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::material::DiffuseLight;
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+#[test]
+fn height_falloff_matches_numeric_integral() {
+    let fog = Atmosphere::new(0.3, Color::default()).with_height_falloff(0.5, 1.0);
+    let ray = Ray::new(Point3::new(0.0, 2.0, 0.0), Vec3::new(3.0, 1.0, -2.0));
+    let t = 4.0;
+    let steps = 100_000;
+    let ds = t * ray.direction().length() / steps as f64;
+    let numeric: f64 = (0..steps)
+        .map(|i| {
+            let y = ray.at((i as f64 + 0.5) / steps as f64 * t).y;
+            0.3 * (-0.5 * (y - 1.0)).exp() * ds
+        })
+        .sum();
+    assert!((fog.optical_depth(&ray, t) - numeric).abs() < 1e-6 * numeric);
+
+    // Level rays see constant density; rays climbing out of the fog see finite depth.
+    let level = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 2.0));
+    assert!((fog.optical_depth(&level, 5.0) - 0.3 * 10.0).abs() < 1e-12);
+    let up = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    assert!((fog.optical_depth(&up, f64::INFINITY) - 0.3 / 0.5).abs() < 1e-12);
+    let down = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert_eq!(fog.transmittance(&down, f64::INFINITY), 0.0);
+}
+
+#[test]
+fn camera_rays_fade_to_the_haze_color() {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -5.0),
+        1.0,
+        Arc::new(DiffuseLight::new(Color::new(2.0, 2.0, 2.0))),
+    )));
+    let haze = Color::new(0.5, 0.6, 0.7);
+    let settings = RenderSettings::default().with_atmosphere(Atmosphere::new(0.1, haze));
+    let lights = lights(&world);
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let mut rng = StdRng::seed_from_u64(1);
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let sample = trace_path_with(ray, &world, &lights, ray_t, &settings, &mut rng);
+    let t = (-0.1f64 * 4.0).exp();
+    let expected = t * Color::new(2.0, 2.0, 2.0) + (1.0 - t) * haze;
+    assert!(
+        (sample.color - expected).length() < 1e-9,
+        "{:?}",
+        sample.color
+    );
+    assert!((sample.emission[0].1.r() - 2.0 * t).abs() < 1e-9);
+
+    // Uniform fog hides the sky completely.
+    let sky = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let sample = trace_path_with(sky, &world, &lights, ray_t, &settings, &mut rng);
+    assert_eq!(sample.color, haze);
+    assert_eq!(sample.background, Color::default());
+}
+
+#[test]
+fn surfaces_only_fog_leaves_the_sky() {
+    let mut world = HittableList::new();
+    world.set_background(Arc::new(SunSky::from_angles(0.5, 0.0)));
+    let fog = Atmosphere::new(0.1, Color::new(0.5, 0.6, 0.7)).with_surfaces_only(true);
+    let settings = RenderSettings::default().with_atmosphere(fog);
+    let lights = lights(&world);
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let mut rng = StdRng::seed_from_u64(1);
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.3, 0.2, -1.0));
+    let sample = trace_path_with(ray, &world, &lights, ray_t, &settings, &mut rng);
+    let sky = world.background.as_ref().unwrap().radiance(&ray);
+    assert!((sample.color - sky).length() < 1e-9 * sky.length());
+}
+
+// Red over blue of the sun's disc, seen straight at it.
+fn sun_disc(sky: &SunSky) -> Color {
+    let ray = Ray::new(Point3::default(), sky.sun_direction);
+    sky.radiance(&ray)
+}
+
+#[test]
+fn turbidity_drives_sky_sun_and_haze_together() {
+    let sunset = SunSky::from_angles(4f64.to_radians(), 0.3);
+    let (clear, hazy) = (Haze::new(2.0), Haze::new(8.0));
+    assert!(hazy.aerosol_depth() > 3.0 * clear.aerosol_depth());
+
+    let (clear_sky, hazy_sky) = (clear.sky(sunset), hazy.sky(sunset));
+    assert_eq!(clear_sky.turbidity, 2.0);
+    assert_eq!(hazy_sky.turbidity, 8.0);
+    assert_eq!(hazy_sky.sun_direction, sunset.sun_direction);
+    // Through more haze the low sun is dimmer and redder.
+    let (c, h) = (sun_disc(&clear_sky), sun_disc(&hazy_sky));
+    assert!(h.g() < 0.2 * c.g(), "{c:?} vs {h:?}");
+    assert!(h.r() / h.b() > c.r() / c.b());
+    // A high sun dims much less than a setting one.
+    let noon = SunSky::from_angles(70f64.to_radians(), 0.3);
+    let high = hazy.sky(noon).sun_intensity / noon.sun_intensity;
+    let low = hazy_sky.sun_intensity / sunset.sun_intensity;
+    assert!(high > 0.4 && low < 0.01, "{high} {low}");
+
+    // Haze is thicker, and scene units convert it: kilometers see a thousand times more per
+    // unit than meters.
+    let (clear_air, hazy_air) = (
+        clear.atmosphere(&clear_sky, 1.0),
+        hazy.atmosphere(&hazy_sky, 1.0),
+    );
+    assert!(hazy_air.density > 2.0 * clear_air.density);
+    // Tens of kilometers of visibility on a clear day, by Koschmieder's 3.912 / extinction.
+    let visibility = 3.912 / clear_air.density;
+    assert!((20_000.0..60_000.0).contains(&visibility), "{visibility}");
+    let km = hazy.atmosphere(&hazy_sky, 1000.0);
+    assert!((km.density - 1000.0 * hazy_air.density).abs() < 1e-9 * km.density);
+    assert!((km.height_falloff - 1000.0 * hazy_air.height_falloff).abs() < 1e-12);
+    assert!(hazy_air.surfaces_only);
+    // The haze glows with the low sky: warm at sunset, bluer at noon.
+    let evening = hazy_air.color;
+    let midday = hazy.atmosphere(&hazy.sky(noon), 1.0).color;
+    assert!(
+        evening.r() / evening.b() > midday.r() / midday.b(),
+        "{evening:?} {midday:?}"
+    );
+}
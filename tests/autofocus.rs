@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use rtt::camera::Camera;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::scene::{CameraDesc, SceneDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A unit ball at the origin, its front 4 units from the camera.
+fn ball() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world
+}
+
+fn camera(focus_dist: f64) -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 5.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.5,
+        0.2,
+        focus_dist,
+    )
+}
+
+#[test]
+fn focuses_on_what_the_center_sees() {
+    let camera = camera(10.0).with_autofocus(&ball(), 0.5, 0.5);
+    assert!(
+        (camera.focus_dist() - 4.0).abs() < 0.01,
+        "{}",
+        camera.focus_dist()
+    );
+}
+
+#[test]
+fn nothing_to_focus_on_keeps_the_focus() {
+    let world = ball();
+    let camera = camera(10.0);
+    assert_eq!(camera.focus_at(&world, 0.02, 0.02), None);
+    let camera = camera.with_autofocus(&world, 0.02, 0.02);
+    assert!((camera.focus_dist() - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn refocusing_keeps_the_framing() {
+    let near = camera(10.0);
+    let far = camera(10.0).with_focus_dist(2.5);
+    assert!((far.focus_dist() - 2.5).abs() < 1e-9);
+    for p in [Point3::new(0.3, -0.2, 1.0), Point3::new(-1.0, 0.5, -2.0)] {
+        let (a, b) = (near.project(p).unwrap(), far.project(p).unwrap());
+        assert!(
+            (a.s - b.s).abs() < 1e-9 && (a.t - b.t).abs() < 1e-9,
+            "{p:?}"
+        );
+    }
+}
+
+#[test]
+fn scene_cameras_can_autofocus() {
+    let json = r#"{
+        "camera": {
+            "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vup": [0, 1, 0],
+            "vfov": 40, "aperture": 0.2, "focus_dist": 10, "autofocus": [0.5, 0.5]
+        },
+        "materials": [{"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}],
+        "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": 0}]
+    }"#;
+    let desc: SceneDesc = serde_json::from_str(json).unwrap();
+    assert_eq!(desc.camera.autofocus, Some((0.5, 0.5)));
+    let (_, camera) = desc.build(1.5).unwrap();
+    assert!((camera.focus_dist() - 4.0).abs() < 0.01);
+
+    let manual = CameraDesc {
+        autofocus: None,
+        exposure: None,
+        ..desc.camera
+    };
+    assert_eq!(manual.build_in(1.5, &ball()).focus_dist(), 10.0);
+}
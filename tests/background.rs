@@ -0,0 +1,277 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aov::AovSet;
+use rtt::background::{Background, Constant, FnBackground, Gradient, Hdri, SunSky, SKY};
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::material::Lambertian;
+use rtt::ray::Ray;
+use rtt::render::{lights, render_image_with, trace_path, trace_path_with, RenderSettings, T_MIN};
+use rtt::restir::Restir;
+use rtt::scene::{BackgroundDesc, CameraDesc, SceneDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+use rtt::Error;
+
+fn towards(direction: Vec3) -> Ray {
+    Ray::new(Point3::new(0.0, 0.0, 0.0), direction)
+}
+
+#[test]
+fn escaping_rays_see_the_world_background() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut world = HittableList::new();
+    let up = towards(Vec3::new(0.0, 1.0, 0.0));
+    assert_eq!(trace_path(up, &world, &mut rng).color, SKY.top);
+
+    world.set_background(Arc::new(Constant::new(Color::new(0.2, 0.3, 0.4))));
+    let sample = trace_path(up, &world, &mut rng);
+    assert_eq!(sample.color, Color::new(0.2, 0.3, 0.4));
+    assert_eq!(sample.background, sample.color);
+
+    world.set_background(Arc::new(FnBackground(|ray: &Ray| {
+        Color::new(ray.direction().y, 0.0, 0.0)
+    })));
+    let down = towards(Vec3::new(0.0, -0.5, 0.0));
+    assert_eq!(
+        trace_path(down, &world, &mut rng).color,
+        Color::new(-0.5, 0.0, 0.0)
+    );
+}
+
+#[test]
+fn gradient_blends_bottom_to_top() {
+    let g = Gradient::new(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+    assert_eq!(
+        g.radiance(&towards(Vec3::new(0.0, -2.0, 0.0))),
+        Color::new(1.0, 0.0, 0.0)
+    );
+    assert_eq!(
+        g.radiance(&towards(Vec3::new(3.0, 0.0, 0.0))),
+        Color::new(0.5, 0.0, 0.5)
+    );
+}
+
+#[test]
+fn sun_sky_is_bluest_overhead_and_brightest_at_the_sun() {
+    let sky = SunSky::from_angles(0.5, 0.0);
+    let sun = sky.sun_direction;
+    assert!((sun - Vec3::new(0.0, 0.5f64.sin(), -0.5f64.cos())).length() < 1e-12);
+
+    let zenith = sky.radiance(&towards(Vec3::new(0.0, 1.0, 0.0)));
+    let luminance = |c: Color| 0.2126 * c.r() + 0.7152 * c.g() + 0.0722 * c.b();
+    // The zenith has the requested luminance and is blue.
+    assert!((luminance(zenith) - 1.0).abs() < 0.05, "{zenith:?}");
+    assert!(zenith.b() > zenith.r());
+
+    // Sky near the sun is brighter than away from it; the disc itself outshines both.
+    let near = sky.radiance(&towards(sun + Vec3::new(0.0, 0.05, 0.0)));
+    let away = sky.radiance(&towards(Vec3::new(0.0, sun.y, -sun.z)));
+    assert!(luminance(near) > luminance(away));
+    let disc = sky.radiance(&towards(sun));
+    assert!(luminance(disc) > 100.0 * luminance(near));
+    // The low sun is reddened.
+    assert!(disc.r() > disc.b());
+    assert_eq!(
+        sky.radiance(&towards(Vec3::new(0.0, -1.0, 0.0))),
+        sky.ground
+    );
+}
+
+#[test]
+fn hdri_maps_directions_to_the_equirectangular_image() {
+    // Top half red, bottom half green; the left and right halves differ in blue.
+    let mut image = image::Rgb32FImage::new(4, 2);
+    for (x, y, px) in image.enumerate_pixels_mut() {
+        let b = if x < 2 { 0.0 } else { 1.0 };
+        *px = image::Rgb(if y == 0 { [2.0, 0.0, b] } else { [0.0, 2.0, b] });
+    }
+    let path = std::env::temp_dir().join(format!("rtt-hdri-{}.exr", std::process::id()));
+    image.save(&path).unwrap();
+
+    let hdri = Hdri::load(&path).unwrap().with_intensity(0.5);
+    let up = hdri.radiance(&towards(Vec3::new(0.0, 1.0, 0.0)));
+    assert_eq!((up.r(), up.g()), (1.0, 0.0));
+    let down = hdri.radiance(&towards(Vec3::new(0.0, -1.0, 0.0)));
+    assert_eq!((down.r(), down.g()), (0.0, 1.0));
+    // Looking down -z sees the center of the image, +x the right half.
+    let right = hdri.radiance(&towards(Vec3::new(1.0, 0.1, -0.1)));
+    let left = hdri.radiance(&towards(Vec3::new(-1.0, 0.1, -0.1)));
+    assert_eq!((left.b(), right.b()), (0.0, 0.5));
+    // Turned half a revolution, the halves swap.
+    let turned = Hdri::load(&path)
+        .unwrap()
+        .with_rotation(std::f64::consts::PI);
+    assert_eq!(
+        turned.radiance(&towards(Vec3::new(1.0, 0.1, -0.1))).b(),
+        0.0
+    );
+
+    let desc = BackgroundDesc::Hdri {
+        path: path.clone(),
+        intensity: 0.5,
+        rotation: 0.0,
+    };
+    assert_eq!(desc.build().unwrap().to_desc(), Some(desc));
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(Hdri::load(&path), Err(Error::Image { .. })));
+}
+
+#[test]
+fn closures_are_not_exported() {
+    let mut world = HittableList::new();
+    world.set_background(Arc::new(FnBackground(|_: &Ray| Color::default())));
+    let camera = CameraDesc {
+        look_from: Point3::new(0.0, 0.0, 1.0),
+        look_at: Point3::new(0.0, 0.0, 0.0),
+        ..Default::default()
+    };
+    assert!(matches!(
+        SceneDesc::from_world(&world, camera),
+        Err(Error::Scene(_))
+    ));
+    let below = BackgroundDesc::SunSky {
+        sun_direction: Vec3::new(0.0, -1.0, 0.0),
+        turbidity: 3.0,
+        intensity: 1.0,
+        sun_intensity: 1.0,
+        ground: Color::default(),
+    };
+    assert!(below.build().is_err());
+}
+
+// 64x32 map: a faint sky over black ground, with one pixel of sun at `SUN`.
+const SUN: (u32, u32) = (40, 6);
+
+fn sun_map(name: &str) -> std::path::PathBuf {
+    let mut image = image::Rgb32FImage::new(64, 32);
+    for (x, y, px) in image.enumerate_pixels_mut() {
+        let v = if (x, y) == SUN {
+            20000.0
+        } else if y < 16 {
+            0.2
+        } else {
+            0.0
+        };
+        *px = image::Rgb([v, v, v]);
+    }
+    let path = std::env::temp_dir().join(format!("rtt-{name}-{}.exr", std::process::id()));
+    image.save(&path).unwrap();
+    path
+}
+
+// Light reaching an upward-facing point from the upper half of `sun_map`, per texel:
+// radiance times the integral of cos(theta) over the texel's solid angle.
+fn irradiance() -> f64 {
+    let (w, h) = (64.0, 32.0);
+    (0..16u32)
+        .flat_map(|y| (0..64u32).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let l = if (x, y) == SUN { 20000.0 } else { 0.2 };
+            let (t0, t1) = (PI * y as f64 / h, PI * (y + 1) as f64 / h);
+            l * (2.0 * PI / w) * 0.5 * (t1.sin().powi(2) - t0.sin().powi(2))
+        })
+        .sum()
+}
+
+// Grey ground lit only by the map.
+fn sun_lit(path: &std::path::Path) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.set_background(Arc::new(Hdri::load(path).unwrap()));
+    world
+}
+
+#[test]
+fn hdri_lights_are_sampled_by_luminance() {
+    let path = sun_map("sun");
+    let world = sun_lit(&path);
+    let lights = lights(&world);
+    assert_eq!(lights.len(), 1);
+    let expected = 0.5 / PI * irradiance();
+
+    // A straight-down view of the ground, estimated with and without shadow rays.
+    let estimate = |settings: &RenderSettings, seed: u64| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let full = Interval::new(T_MIN, f64::INFINITY);
+        let n = 4000;
+        let values: Vec<f64> = (0..n)
+            .map(|_| {
+                trace_path_with(ray, &world, &lights, full, settings, &mut rng)
+                    .color
+                    .g()
+            })
+            .collect();
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+        (mean, variance)
+    };
+    let sampled = RenderSettings::default();
+    let (mean, variance) = estimate(&sampled, 1);
+    assert!(
+        (mean - expected).abs() < 0.02 * expected,
+        "{mean} vs {expected}"
+    );
+    let (_, plain_variance) = estimate(&sampled.with_shadow_samples(0), 1);
+    assert!(
+        variance < 0.01 * plain_variance,
+        "{variance} vs {plain_variance}"
+    );
+    // Weighted against each other, but not by MIS, the two strategies still agree.
+    let (unweighted, _) = estimate(&sampled.with_mis(false), 2);
+    assert!((unweighted - expected).abs() < 0.02 * expected);
+
+    // The light's density matches where it samples: mostly towards the sun texel.
+    let hdri = world.background().unwrap().light().unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    let toward_sun = (0..1000)
+        .filter(|_| {
+            let ls = hdri.sample(origin, &mut rng).unwrap();
+            let (t, pdf) = hdri.pdf(origin, ls.direction).unwrap();
+            assert!(t.is_infinite() && (pdf - ls.pdf).abs() <= 1e-9 * pdf);
+            ls.radiance.g() == 20000.0
+        })
+        .count();
+    assert!(toward_sun > 950, "{toward_sun}");
+    // Black ground is never sampled.
+    assert!(hdri.pdf(origin, Vec3::new(0.0, -1.0, 0.0)).is_none());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn resampled_direct_light_includes_the_environment() {
+    let path = sun_map("restir-sun");
+    let world = sun_lit(&path);
+    let camera = Camera::new(
+        Point3::new(0.0, 1.0, 0.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        20.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    let size = 8;
+    let settings = RenderSettings::default().with_restir(Restir::default());
+    let film = Film::new(size, size);
+    let aovs = AovSet::new(&[], &world, size, size);
+    render_image_with(&world, &camera, &film, &aovs, 64, &settings, &|_| {}).unwrap();
+    let image = film.resolve(1.0).unwrap();
+    let mean = image.iter().map(|c| c.g()).sum::<f64>() / image.len() as f64;
+    let expected = 0.5 / PI * irradiance();
+    assert!(
+        (mean - expected).abs() < 0.03 * expected,
+        "{mean} vs {expected}"
+    );
+    std::fs::remove_file(path).unwrap();
+}
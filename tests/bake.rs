@@ -0,0 +1,143 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rtt::background::Constant;
+use rtt::bake::{bake_curvature, bake_irradiance, bake_occlusion, pad};
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::mesh::Mesh;
+use rtt::procgen::uv_sphere;
+use rtt::render::RenderSettings;
+use rtt::vec3::{Color, Point3};
+use rtt::Error;
+
+const SIZE: u32 = 16;
+
+// A 2x2 floor facing up, its texture space spanning `u` across and all of v.
+fn floor(u: [f64; 2]) -> Mesh {
+    let positions = vec![
+        Point3::new(-1.0, 0.0, 1.0),
+        Point3::new(1.0, 0.0, 1.0),
+        Point3::new(1.0, 0.0, -1.0),
+        Point3::new(-1.0, 0.0, -1.0),
+    ];
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], grey)
+        .unwrap()
+        .with_uvs(vec![[u[0], 0.0], [u[1], 0.0], [u[1], 1.0], [u[0], 1.0]])
+        .unwrap()
+}
+
+fn sky() -> HittableList {
+    let mut world = HittableList::new();
+    world.set_background(Arc::new(Constant::new(Color::new(1.0, 1.0, 1.0))));
+    world
+}
+
+#[test]
+fn an_open_sky_gives_pi() {
+    let settings = RenderSettings::default();
+    let lightmap = bake_irradiance(&sky(), &floor([0.0, 1.0]), SIZE, SIZE, 8, &settings).unwrap();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let texel = lightmap.pixel(x, y).unwrap();
+            assert_eq!(texel.weight_sum, 1.0, "({x}, {y})");
+            let e = texel.resolve(1.0);
+            assert!(
+                (e - Color::new(PI, PI, PI)).length() < 1e-9,
+                "({x}, {y}): {e:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn occluders_darken_the_texels_under_them() {
+    let mut world = sky();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.5, 0.0),
+        0.4,
+        Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+    )));
+    let settings = RenderSettings::default().with_seed(7);
+    let lightmap = bake_irradiance(&world, &floor([0.0, 1.0]), SIZE, SIZE, 256, &settings).unwrap();
+    let center = lightmap.pixel(SIZE / 2, SIZE / 2).unwrap().resolve(1.0);
+    let corner = lightmap.pixel(0, 0).unwrap().resolve(1.0);
+    assert!(center.r() < 0.7 * corner.r(), "{center:?} vs {corner:?}");
+}
+
+#[test]
+fn only_charted_texels_are_baked_and_padding_grows_them() {
+    let settings = RenderSettings::default();
+    let lightmap = bake_irradiance(&sky(), &floor([0.0, 0.5]), SIZE, SIZE, 1, &settings).unwrap();
+    let baked = |film: &rtt::film::Film, x| film.pixel(x, SIZE / 2).unwrap().weight_sum > 0.0;
+    assert!(baked(&lightmap, SIZE / 2 - 1));
+    assert!(!baked(&lightmap, SIZE / 2));
+
+    let padded = pad(&lightmap, 2).unwrap();
+    assert!(baked(&padded, SIZE / 2 + 1));
+    assert!(!baked(&padded, SIZE / 2 + 2));
+    let grown = padded.pixel(SIZE / 2 + 1, SIZE / 2).unwrap().resolve(1.0);
+    assert!((grown.r() - PI).abs() < 1e-9);
+}
+
+#[test]
+fn meshes_need_texture_coordinates() {
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mesh = Mesh::new(floor([0.0, 1.0]).positions, vec![[0, 1, 2]], grey).unwrap();
+    let err = bake_irradiance(&sky(), &mesh, SIZE, SIZE, 1, &RenderSettings::default());
+    assert!(matches!(err, Err(Error::Scene(_))));
+}
+
+// A unit sphere, seen from above in texture space.
+fn ball(inside_out: bool) -> Mesh {
+    let (positions, mut indices) = uv_sphere(1.0, 24, 48);
+    if inside_out {
+        indices.iter_mut().for_each(|t| t.swap(1, 2));
+    }
+    let uvs = positions
+        .iter()
+        .map(|p| [0.5 + 0.5 * p.x, 0.5 - 0.5 * p.z])
+        .collect();
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    Mesh::new(positions, indices, grey)
+        .unwrap()
+        .with_uvs(uvs)
+        .unwrap()
+}
+
+#[test]
+fn occlusion_darkens_near_geometry_only() {
+    let floor = floor([0.0, 1.0]);
+    let open = bake_occlusion(&sky(), &floor, SIZE, SIZE, 16, 1.0, Some(1)).unwrap();
+    assert_eq!(
+        open.pixel(3, 5).unwrap().resolve(1.0),
+        Color::new(1.0, 1.0, 1.0)
+    );
+
+    let mut world = sky();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.5, 0.0),
+        0.4,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let near = bake_occlusion(&world, &floor, SIZE, SIZE, 256, 1.0, Some(1)).unwrap();
+    let center = near.pixel(SIZE / 2, SIZE / 2).unwrap().resolve(1.0).r();
+    let corner = near.pixel(0, 0).unwrap().resolve(1.0).r();
+    assert!(center < 0.5 && center < corner, "{center} vs {corner}");
+    // Out of reach.
+    let far = bake_occlusion(&world, &floor, SIZE, SIZE, 64, 0.05, Some(1)).unwrap();
+    assert_eq!(far.pixel(SIZE / 2, SIZE / 2).unwrap().resolve(1.0).r(), 1.0);
+}
+
+#[test]
+fn curvature_is_grey_when_flat_and_splits_convex_from_concave() {
+    let flat = bake_curvature(&floor([0.0, 1.0]), SIZE, SIZE, 1.0).unwrap();
+    assert_eq!(flat.pixel(4, 9).unwrap().resolve(1.0).r(), 0.5);
+    // A unit sphere has curvature 1: three quarters of the way to white at range 2.
+    for (inside_out, expected) in [(false, 0.75), (true, 0.25)] {
+        let map = bake_curvature(&ball(inside_out), SIZE, SIZE, 2.0).unwrap();
+        let grey = map.pixel(SIZE / 2, SIZE / 2).unwrap().resolve(1.0).r();
+        assert!((grey - expected).abs() < 0.01, "{grey} vs {expected}");
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HitRecord, Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::material::DiffuseLight;
+use rtt::mesh::Mesh;
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A mesh that asks for its rays in batches, as a GPU backend does, and counts them.
+struct Batching {
+    mesh: Mesh,
+    batches: AtomicU32,
+}
+
+impl Hittable for Batching {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        self.mesh.hit(r, ray_t)
+    }
+
+    fn hit_batch(&self, rays: &[(Ray, Interval)]) -> Vec<Option<HitRecord>> {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        rays.iter()
+            .map(|(r, ray_t)| self.mesh.hit(r, *ray_t))
+            .collect()
+    }
+
+    fn prefers_batches(&self) -> bool {
+        true
+    }
+}
+
+const WALL: Color = Color::new(0.2, 0.4, 0.6);
+const BALL: Color = Color::new(1.0, 0.5, 0.25);
+
+// A glowing wall 3 units ahead over the left half of the view.
+fn wall() -> Mesh {
+    let positions = vec![
+        Point3::new(-10.0, -10.0, -3.0),
+        Point3::new(0.0, -10.0, -3.0),
+        Point3::new(0.0, 10.0, -3.0),
+        Point3::new(-10.0, 10.0, -3.0),
+    ];
+    let glow = Arc::new(DiffuseLight::new(WALL));
+    Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], glow).unwrap()
+}
+
+// The wall and a glowing ball in front of its right edge, against a black sky, with the wall
+// batching if `batching` is set. Every camera ray sees exactly what it hits.
+fn world(batching: bool) -> (HittableList, Arc<Batching>) {
+    let batched = Arc::new(Batching {
+        mesh: wall(),
+        batches: AtomicU32::new(0),
+    });
+    let mut world = HittableList::new();
+    world.add(Sphere::new(
+        Point3::new(0.0, 0.0, -2.0),
+        1.0,
+        Arc::new(DiffuseLight::new(BALL)),
+    ));
+    if batching {
+        world.add(batched.clone());
+    } else {
+        world.add(Arc::new(wall()));
+    }
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    (world, batched)
+}
+
+#[test]
+fn batches_find_the_same_hits_as_single_rays() {
+    let (world, wall) = world(true);
+    assert!(world.prefers_batches());
+    let mut rng = StdRng::seed_from_u64(8);
+    let rays: Vec<(Ray, Interval)> = (0..500)
+        .map(|_| {
+            let target = Point3::new(
+                rng.random_range(-2.0..2.0),
+                rng.random_range(-2.0..2.0),
+                -3.0,
+            );
+            let far = rng.random_range(1.0..5.0);
+            let origin = Point3::new(0.0, 0.0, 0.0);
+            (Ray::new(origin, target - origin), Interval::new(1e-3, far))
+        })
+        .collect();
+    let hits = world.hit_batch(&rays);
+    assert_eq!(wall.batches.load(Ordering::Relaxed), 1);
+    let (mut walls, mut balls) = (0, 0);
+    for ((r, ray_t), hit) in rays.iter().zip(&hits) {
+        let expected = world.hit(r, *ray_t);
+        let key = |h: &HitRecord| (h.t, h.object_id);
+        assert_eq!(hit.as_ref().map(key), expected.as_ref().map(key));
+        match hit.as_ref().map(|h| h.object_id) {
+            Some(0) => balls += 1,
+            Some(_) => walls += 1,
+            None => {}
+        }
+    }
+    // Both kinds of object, nearer and further, were hit.
+    assert!(walls > 50 && balls > 10, "{walls} {balls}");
+}
+
+#[test]
+fn renders_find_camera_hits_a_row_at_a_time() {
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let (size, samples) = (8, 4);
+    let render = |batching: bool| {
+        let (world, wall) = world(batching);
+        let film = Film::new(size, size);
+        let aovs = AovSet::new(&[], &world, size, size);
+        let settings = RenderSettings::default().with_seed(2);
+        render_image_with(&world, &camera, &film, &aovs, samples, &settings, &|_| {}).unwrap();
+        (film, wall.batches.load(Ordering::Relaxed))
+    };
+    let (batched, batches) = render(true);
+    let (single, none) = render(false);
+    assert_eq!((batches, none), (size * samples, 0));
+
+    // Away from the edges every sample sees the same thing either way.
+    for (x, y, expected) in [
+        (0, 0, WALL),
+        (1, 6, WALL),
+        (4, 4, BALL),
+        (7, 7, Color::default()),
+    ] {
+        for film in [&batched, &single] {
+            let got = film.pixel(x, y).unwrap().resolve(1.0);
+            assert!((got - expected).length() < 1e-9, "{x} {y}: {got:?}");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use rtt::bloom::{Bloom, Glare};
+use rtt::film::Film;
+use rtt::vec3::Color;
+
+// A dark grey `size` x `size` film with one pixel of `highlight` in the middle.
+fn film(size: u32, highlight: Color) -> Film {
+    let mut film = Film::new(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            let c = if (x, y) == (size / 2, size / 2) {
+                highlight
+            } else {
+                Color::new(0.1, 0.1, 0.1)
+            };
+            film.add_sample(x, y, c, 1.0).unwrap();
+        }
+    }
+    film
+}
+
+fn sum(colors: &[Color]) -> Color {
+    colors.iter().fold(Color::default(), |a, &c| a + c)
+}
+
+#[test]
+fn light_under_the_threshold_is_untouched() {
+    let film = film(16, Color::new(0.9, 0.9, 0.9));
+    let bloomed = Bloom::new(1.0, 0.1, 1.0).apply(&film, 1.0).unwrap();
+    assert_eq!(film.resolve(1.0).unwrap(), bloomed.resolve(1.0).unwrap());
+}
+
+#[test]
+fn bloom_spreads_the_excess_symmetrically() {
+    let size = 65;
+    let film = film(size, Color::new(11.0, 11.0, 11.0));
+    let bloom = Bloom::new(1.0, 0.05, 0.5);
+    let before = film.resolve(1.0).unwrap();
+    let bloomed = bloom.apply(&film, 1.0).unwrap();
+    let after = bloomed.resolve(1.0).unwrap();
+    let glow: Vec<Color> = after.iter().zip(&before).map(|(a, b)| *a - *b).collect();
+
+    // Half of the 10 over the threshold, all of it on screen.
+    assert!((sum(&glow).g() - 5.0).abs() < 1e-6, "{:?}", sum(&glow));
+    let at = |x: u32, y: u32| glow[(y * size + x) as usize].g();
+    let c = size / 2;
+    assert!(at(c, c) > at(c + 2, c) && at(c + 2, c) > at(c + 6, c));
+    assert!((at(c + 3, c) - at(c - 3, c)).abs() < 1e-12);
+    assert!((at(c + 3, c) - at(c, c + 3)).abs() < 1e-12);
+    // Alpha and sample weights are the film's own.
+    let (a, b) = (film.pixel(0, 0).unwrap(), bloomed.pixel(0, 0).unwrap());
+    assert_eq!((a.weight_sum, a.alpha()), (b.weight_sum, b.alpha()));
+}
+
+#[test]
+fn glare_streaks_follow_the_aperture_with_longer_red() {
+    let size = 65;
+    let film = film(size, Color::new(11.0, 11.0, 11.0));
+    let glare = Glare {
+        streaks: 4,
+        length: 0.2,
+        intensity: 1.0,
+        rotation: 0.0,
+    };
+    let bloom = Bloom::new(1.0, 0.0, 0.0).with_glare(glare);
+    let before = film.resolve(1.0).unwrap();
+    let after = bloom.apply(&film, 1.0).unwrap().resolve(1.0).unwrap();
+    let glow = |x: u32, y: u32| {
+        let i = (y * size + x) as usize;
+        after[i] - before[i]
+    };
+    let c = size / 2;
+    // Four spikes along the axes, nothing on the diagonals.
+    for (x, y) in [(c + 5, c), (c - 5, c), (c, c + 5), (c, c - 5)] {
+        assert!(glow(x, y).g() > 0.0, "({x}, {y})");
+    }
+    assert_eq!(glow(c + 5, c + 5), Color::default());
+    // Red fades slower than blue.
+    let near = glow(c + 1, c);
+    let far = glow(c + 10, c);
+    assert!(far.r() / near.r() > far.b() / near.b());
+    // The streaks carry the intensity times the excess, split between the channels' falloffs.
+    let all: Vec<Color> = after.iter().zip(&before).map(|(a, b)| *a - *b).collect();
+    assert!((sum(&all).r() - 10.0).abs() < 1e-6, "{:?}", sum(&all));
+}
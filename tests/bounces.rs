@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aov::{Aov, AovSet, PathClass};
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::{Dielectric, Lambertian, Metal};
+use rtt::ray::Ray;
+use rtt::render::{
+    lights, render_image_with, trace_path_with, BounceLimits, RenderSettings, T_MIN,
+};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Pale ground and ball under a lamp in the dark.
+fn lamp_lit() -> HittableList {
+    let mut world = HittableList::new();
+    let grey = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        grey.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.5, 0.0), 0.5, grey)));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.5, 2.0, -0.5),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+fn mean(image: &[Color]) -> f64 {
+    image.iter().map(|c| c.g()).sum::<f64>() / image.len() as f64
+}
+
+#[test]
+fn no_diffuse_bounces_leaves_direct_light() {
+    let world = lamp_lit();
+    let camera = Camera::new(
+        Point3::new(0.0, 1.5, 3.0),
+        Point3::new(0.0, 0.3, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let size = 8;
+    let render = |settings: &RenderSettings| {
+        let film = Film::new(size, size);
+        let aovs = AovSet::new(&[Aov::PathClasses], &world, size, size);
+        render_image_with(&world, &camera, &film, &aovs, 256, settings, &|_| {}).unwrap();
+        let direct = aovs
+            .path_classes()
+            .find(|(c, _)| *c == PathClass::DirectDiffuse)
+            .map(|(_, f)| f.resolve(1.0).unwrap())
+            .unwrap();
+        (film.resolve(1.0).unwrap(), direct)
+    };
+    let (full, direct) = render(&RenderSettings::default());
+    let limits = BounceLimits::default().with_diffuse(0);
+    let (direct_only, _) = render(&RenderSettings::default().with_bounces(limits));
+    // Shadow rays alone find the same direct light as both strategies combined.
+    let (expected, got) = (mean(&direct), mean(&direct_only));
+    assert!(
+        (got - expected).abs() < 0.03 * expected,
+        "{got} vs {expected}"
+    );
+    assert!(mean(&full) > 1.05 * got);
+    // A single bounce in all is the same as no diffuse ones.
+    let (one, _) =
+        render(&RenderSettings::default().with_bounces(BounceLimits::default().with_total(1)));
+    assert!((mean(&one) - expected).abs() < 0.03 * expected);
+}
+
+#[test]
+fn glossy_and_transmission_limits_cut_paths() {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.0, 0.0, 0.0),
+        0.5,
+        Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.0, 0.0, 0.0),
+        0.5,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::new(1.0, 1.0, 1.0))));
+    let lights = lights(&world);
+    let full = Interval::new(T_MIN, f64::INFINITY);
+    let mut rng = StdRng::seed_from_u64(9);
+    let mut trace = |x: f64, limits: BounceLimits| {
+        let ray = Ray::new(Point3::new(x, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let settings = RenderSettings::default().with_bounces(limits);
+        (0..100)
+            .map(|_| {
+                trace_path_with(ray, &world, &lights, full, &settings, &mut rng)
+                    .color
+                    .g()
+            })
+            .sum::<f64>()
+            / 100.0
+    };
+
+    // The mirror shows the sky only if it may reflect once.
+    let limits = BounceLimits::default();
+    assert_eq!(trace(-1.0, limits.with_glossy(0)), 0.0);
+    assert!((trace(-1.0, limits.with_glossy(1)) - 0.9).abs() < 1e-9);
+    // Seeing through the glass ball takes two refractions; most of its light comes that way.
+    let one = trace(1.0, limits.with_transmission(1));
+    let two = trace(1.0, limits.with_transmission(2));
+    assert!(one < 0.1 && two > 0.8, "{one} vs {two}");
+}
+
+#[test]
+fn settings_default_missing_limits() {
+    let settings: RenderSettings = serde_json::from_str(r#"{"bounces": {"glossy": 4}}"#).unwrap();
+    assert_eq!(settings.bounces, BounceLimits::default().with_glossy(4));
+    assert_eq!(settings.bounces.total, 50);
+}
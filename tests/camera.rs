@@ -0,0 +1,234 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aabb::Aabb;
+use rtt::camera::{auto_frame, Camera, LensEffects, Shutter, ShutterCurve};
+use rtt::vec3::{Point3, Vec3};
+
+// A pinhole looking down -z from the origin, with `lens` effects.
+fn camera(lens: LensEffects) -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        60.0,
+        1.5,
+        0.0,
+        1.0,
+    )
+    .with_lens_effects(lens)
+}
+
+fn direction(camera: &Camera, s: f64, t: f64) -> Vec3 {
+    let mut rng = StdRng::seed_from_u64(1);
+    Vec3::unit_vector(camera.get_ray(s, t, &mut rng).unwrap().direction())
+}
+
+// Angle off the viewing axis, in radians.
+fn off_axis(direction: Vec3) -> f64 {
+    (-direction.z).acos()
+}
+
+// Film points on a grid covering the corners and the center.
+fn grid() -> impl Iterator<Item = (f64, f64)> {
+    (0..=8).flat_map(|i| (0..=8).map(move |j| (i as f64 / 8.0, j as f64 / 8.0)))
+}
+
+#[test]
+fn no_distortion_leaves_every_ray_alone() {
+    let ideal = camera(LensEffects::default());
+    // Vignetting weighs rays without moving them.
+    let undistorted = camera(LensEffects {
+        vignetting: 1.0,
+        ..Default::default()
+    });
+    for (s, t) in grid() {
+        let expected = direction(&ideal, s, t);
+        assert!((direction(&undistorted, s, t) - expected).length() < 1e-12);
+        let pinhole = Vec3::unit_vector(undistorted.pinhole_ray(s, t).direction());
+        assert!((pinhole - expected).length() < 1e-12);
+    }
+}
+
+#[test]
+fn distortion_bends_rays_more_away_from_the_center() {
+    let ideal = camera(LensEffects::default());
+    let lens = |distortion| {
+        camera(LensEffects {
+            distortion,
+            ..Default::default()
+        })
+    };
+    let (barrel, pincushion) = (lens(-0.2), lens(0.2));
+    // The center stays put.
+    for camera in [&barrel, &pincushion] {
+        let center = direction(camera, 0.5, 0.5);
+        assert!((center - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-12);
+    }
+    // Barrel distortion squeezes the edges in, so they see less; pincushion stretches them.
+    let mut last = 0.0;
+    for i in 1..=4 {
+        let s = 0.5 + i as f64 / 8.0;
+        let angle = off_axis(direction(&ideal, s, s));
+        assert!(off_axis(direction(&barrel, s, s)) < angle);
+        assert!(off_axis(direction(&pincushion, s, s)) > angle);
+        let squeezed = angle - off_axis(direction(&barrel, s, s));
+        assert!(squeezed > last, "{s}");
+        last = squeezed;
+    }
+}
+
+#[test]
+fn vignetting_falls_off_towards_the_corners() {
+    for strength in [0.25, 1.0] {
+        let camera = camera(LensEffects {
+            vignetting: strength,
+            ..Default::default()
+        });
+        assert_eq!(camera.vignetting(0.5, 0.5), 1.0);
+        // Falling steadily from the center out to each corner.
+        for (s, t) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            let mut last = 1.0;
+            for i in 1..=8 {
+                let f = i as f64 / 8.0;
+                let v = camera.vignetting(0.5 + f * (s - 0.5), 0.5 + f * (t - 0.5));
+                assert!(v < last && v > 0.0, "{strength} {s} {t} {f}");
+                last = v;
+            }
+            // The cos^4 law, blended in by the strength.
+            let cos = -direction(&camera, s, t).z;
+            let expected = 1.0 - strength * (1.0 - cos.powi(4));
+            assert!((last - expected).abs() < 1e-12);
+        }
+    }
+    // Without it, every point is weighed the same.
+    let flat = camera(LensEffects::default());
+    assert!(grid().all(|(s, t)| flat.vignetting(s, t) == 1.0));
+}
+
+fn shuttered(shutter: Shutter) -> Camera {
+    camera(LensEffects::default()).with_shutter(shutter)
+}
+
+// Times of `n` rays through film point (s, t).
+fn times(camera: &Camera, s: f64, t: f64, n: usize) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(2);
+    (0..n)
+        .map(|_| camera.get_ray(s, t, &mut rng).unwrap().time())
+        .collect()
+}
+
+// Share of `times` before `at`.
+fn before(times: &[f64], at: f64) -> f64 {
+    times.iter().filter(|&&t| t < at).count() as f64 / times.len() as f64
+}
+
+#[test]
+fn ray_times_follow_the_shutter_curve() {
+    const N: usize = 20_000;
+    let curve = |curve| {
+        shuttered(Shutter {
+            curve,
+            ..Shutter::new(1.0, 3.0)
+        })
+    };
+    // (curve, share of the exposure, share of rays expected before then)
+    let cases = [
+        (ShutterCurve::Box, 0.25, 0.25),
+        (ShutterCurve::Box, 0.5, 0.5),
+        // A triangle: density 4x up to the middle.
+        (ShutterCurve::Trapezoid { ramp: 0.5 }, 0.25, 0.125),
+        (ShutterCurve::Trapezoid { ramp: 0.5 }, 0.5, 0.5),
+        // Ramps over the first and last fifth, flat between: 0.1 of the area each.
+        (ShutterCurve::Trapezoid { ramp: 0.2 }, 0.2, 0.1 / 0.8),
+        (ShutterCurve::Tabulated(vec![1.0, 3.0]), 0.5, 0.25),
+        (ShutterCurve::Tabulated(vec![0.0, 1.0, 0.0]), 1.0 / 3.0, 0.0),
+    ];
+    for (shape, x, expected) in cases {
+        let times = times(&curve(shape.clone()), 0.5, 0.5, N);
+        assert!(times.iter().all(|t| (1.0..=3.0).contains(t)));
+        let share = before(&times, 1.0 + 2.0 * x);
+        assert!((share - expected).abs() < 0.015, "{shape:?} {x}: {share}");
+    }
+}
+
+#[test]
+fn rolling_shutters_read_rows_top_to_bottom() {
+    for exposure in [0.0, 0.05] {
+        let camera = shuttered(Shutter {
+            readout: 1.0,
+            ..Shutter::new(0.0, exposure)
+        });
+        // Rows from the top of the film down: every ray of a lower row comes later.
+        let mut last = f64::NEG_INFINITY;
+        for row in 0..=10 {
+            let t = 1.0 - row as f64 / 10.0;
+            let times = times(&camera, 0.5, t, 100);
+            let (first, latest) = times
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &t| {
+                    (lo.min(t), hi.max(t))
+                });
+            assert!(first > last, "{exposure} {row}");
+            assert!(first >= 1.0 - t && latest <= 1.0 - t + exposure);
+            last = latest;
+        }
+    }
+    // A global shutter exposes every row at once.
+    let global = shuttered(Shutter::new(0.0, 0.05));
+    assert_eq!(times(&global, 0.5, 1.0, 50), times(&global, 0.5, 0.0, 50));
+}
+
+#[test]
+fn auto_framing_keeps_every_corner_in_view() {
+    let boxes = [
+        Aabb::from_points(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)),
+        // Long and flat, off the origin.
+        Aabb::from_points(Point3::new(10.0, 0.0, -3.0), Point3::new(30.0, 0.5, 2.0)),
+        Aabb::from_points(Point3::new(-0.1, -5.0, 0.0), Point3::new(0.1, 5.0, 0.2)),
+    ];
+    let views = [
+        Vec3::new(0.0, 0.0, -1.0),
+        Vec3::new(1.0, -0.5, 0.3),
+        Vec3::new(-0.2, 0.9, 1.0),
+    ];
+    for bbox in &boxes {
+        let axes = [bbox.axis(0), bbox.axis(1), bbox.axis(2)];
+        let corners: Vec<Point3> = (0..8)
+            .map(|i| {
+                let [x, y, z] = [0, 1, 2].map(|a| {
+                    if i >> a & 1 == 0 {
+                        axes[a].0
+                    } else {
+                        axes[a].1
+                    }
+                });
+                Point3::new(x, y, z)
+            })
+            .collect();
+        for view in views {
+            for (vfov, aspect) in [(40.0, 1.0), (30.0, 2.0), (60.0, 0.5)] {
+                for margin in [1.0, 1.3] {
+                    let (look_from, look_at) = auto_frame(bbox, view, vfov, aspect, margin);
+                    assert!((look_at - bbox.centroid()).length() < 1e-12);
+                    let focus = (look_at - look_from).length();
+                    let camera = Camera::new(
+                        look_from,
+                        look_at,
+                        Vec3::new(0.0, 1.0, 0.0),
+                        vfov,
+                        aspect,
+                        0.0,
+                        focus,
+                    );
+                    for &corner in &corners {
+                        let sample = camera.project(corner);
+                        assert!(
+                            sample.is_some(),
+                            "{corner:?} from {view:?} at {vfov} {aspect} {margin}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
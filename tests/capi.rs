@@ -0,0 +1,221 @@
+#![cfg(feature = "capi")]
+
+use std::ffi::c_void;
+use std::ptr::{null, null_mut};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rtt::capi::*;
+
+const ORIGIN: [f64; 3] = [0.0, 0.0, 0.0];
+const UP: [f64; 3] = [0.0, 1.0, 0.0];
+const BACK: [f64; 3] = [0.0, 0.0, 3.0];
+
+fn material(kind: u32, param: f64) -> RttMaterial {
+    RttMaterial {
+        kind,
+        color: [0.8, 0.4, 0.2],
+        param,
+    }
+}
+
+unsafe fn camera(scene: *mut RttScene, look_from: [f64; 3], vup: [f64; 3], vfov: f64) -> i32 {
+    rtt_scene_set_camera(
+        scene,
+        look_from.as_ptr(),
+        ORIGIN.as_ptr(),
+        vup.as_ptr(),
+        vfov,
+        0.0,
+        3.0,
+    )
+}
+
+#[test]
+fn spheres_need_valid_pointers_and_values() {
+    unsafe {
+        let scene = rtt_scene_new();
+        let lambertian = material(RttMaterialKind::Lambertian as u32, 0.0);
+        let add = |radius, material: &RttMaterial| {
+            rtt_scene_add_sphere(scene, ORIGIN.as_ptr(), radius, material)
+        };
+        assert_eq!(add(1.0, &lambertian), RTT_OK);
+        assert_eq!(
+            add(1.0, &material(RttMaterialKind::Metal as u32, 0.3)),
+            RTT_OK
+        );
+        assert_eq!(
+            add(1.0, &material(RttMaterialKind::Dielectric as u32, 1.5)),
+            RTT_OK
+        );
+        assert_eq!(
+            add(1.0, &material(RttMaterialKind::DiffuseLight as u32, 0.0)),
+            RTT_OK
+        );
+
+        let null_scene = rtt_scene_add_sphere(null_mut(), ORIGIN.as_ptr(), 1.0, &lambertian);
+        assert_eq!(null_scene, RTT_ERR_NULL);
+        assert_eq!(
+            rtt_scene_add_sphere(scene, null(), 1.0, &lambertian),
+            RTT_ERR_NULL
+        );
+        assert_eq!(
+            rtt_scene_add_sphere(scene, ORIGIN.as_ptr(), 1.0, null()),
+            RTT_ERR_NULL
+        );
+
+        for radius in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(add(radius, &lambertian), RTT_ERR_INVALID, "{radius}");
+        }
+        let nan_center = [f64::NAN, 0.0, 0.0];
+        assert_eq!(
+            rtt_scene_add_sphere(scene, nan_center.as_ptr(), 1.0, &lambertian),
+            RTT_ERR_INVALID
+        );
+        let invalid = [
+            // Kinds C can store that the enum doesn't name.
+            material(4, 0.0),
+            material(u32::MAX, 0.0),
+            material(RttMaterialKind::Metal as u32, 1.5),
+            material(RttMaterialKind::Metal as u32, f64::NAN),
+            material(RttMaterialKind::Dielectric as u32, 0.0),
+            material(RttMaterialKind::Dielectric as u32, f64::INFINITY),
+            RttMaterial {
+                color: [-1.0, 0.0, 0.0],
+                ..lambertian
+            },
+            RttMaterial {
+                color: [f64::NAN, 0.0, 0.0],
+                ..lambertian
+            },
+        ];
+        for material in invalid {
+            assert_eq!(add(1.0, &material), RTT_ERR_INVALID, "{material:?}");
+        }
+        rtt_scene_free(scene);
+        rtt_scene_free(null_mut());
+    }
+}
+
+#[test]
+fn cameras_need_valid_pointers_and_values() {
+    unsafe {
+        let scene = rtt_scene_new();
+        assert_eq!(camera(scene, BACK, UP, 40.0), RTT_OK);
+        assert_eq!(camera(null_mut(), BACK, UP, 40.0), RTT_ERR_NULL);
+        let null_vup = rtt_scene_set_camera(
+            scene,
+            BACK.as_ptr(),
+            ORIGIN.as_ptr(),
+            null(),
+            40.0,
+            0.0,
+            3.0,
+        );
+        assert_eq!(null_vup, RTT_ERR_NULL);
+
+        for vfov in [0.0, -10.0, 180.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(camera(scene, BACK, UP, vfov), RTT_ERR_INVALID, "{vfov}");
+        }
+        // Looking at itself, or up along `vup`.
+        assert_eq!(camera(scene, ORIGIN, UP, 40.0), RTT_ERR_INVALID);
+        assert_eq!(camera(scene, [0.0, 3.0, 0.0], UP, 40.0), RTT_ERR_INVALID);
+        assert_eq!(
+            camera(scene, BACK, [0.0, f64::NAN, 0.0], 40.0),
+            RTT_ERR_INVALID
+        );
+
+        let set = |aperture, focus_dist| {
+            rtt_scene_set_camera(
+                scene,
+                BACK.as_ptr(),
+                ORIGIN.as_ptr(),
+                UP.as_ptr(),
+                40.0,
+                aperture,
+                focus_dist,
+            )
+        };
+        assert_eq!(set(0.1, 3.0), RTT_OK);
+        for (aperture, focus_dist) in [
+            (-0.1, 3.0),
+            (f64::NAN, 3.0),
+            (0.1, 0.0),
+            (0.1, f64::INFINITY),
+        ] {
+            let status = set(aperture, focus_dist);
+            assert_eq!(status, RTT_ERR_INVALID, "{aperture} {focus_dist}");
+        }
+        rtt_scene_free(scene);
+    }
+}
+
+extern "C" fn count_rows(done: u32, total: u32, user_data: *mut c_void) {
+    let rows = unsafe { &*(user_data as *const AtomicU32) };
+    assert!(done <= total);
+    rows.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn renders_into_the_callers_buffer() {
+    let (width, height) = (8u32, 4u32);
+    let mut out = vec![0u8; (width * height * 4) as usize];
+    let rows = AtomicU32::new(0);
+    unsafe {
+        let scene = rtt_scene_new();
+        // A glowing ball filling the view.
+        let light = RttMaterial {
+            kind: RttMaterialKind::DiffuseLight as u32,
+            color: [1.0, 1.0, 1.0],
+            param: 0.0,
+        };
+        assert_eq!(
+            rtt_scene_add_sphere(scene, ORIGIN.as_ptr(), 2.0, &light),
+            RTT_OK
+        );
+        assert_eq!(camera(scene, BACK, UP, 40.0), RTT_OK);
+
+        let render = |width, height, samples, out: *mut u8, out_len| {
+            rtt_render(
+                scene,
+                width,
+                height,
+                samples,
+                out,
+                out_len,
+                Some(count_rows),
+                &rows as *const AtomicU32 as *mut c_void,
+            )
+        };
+        let len = out.len();
+        assert_eq!(render(width, height, 2, out.as_mut_ptr(), len), RTT_OK);
+        assert!(rows.load(Ordering::Relaxed) > 0);
+        assert!(out.iter().all(|&b| b == 255), "{out:?}");
+
+        assert_eq!(render(width, height, 2, null_mut(), len), RTT_ERR_NULL);
+        let null_scene = rtt_render(
+            null(),
+            width,
+            height,
+            1,
+            out.as_mut_ptr(),
+            len,
+            None,
+            null_mut(),
+        );
+        assert_eq!(null_scene, RTT_ERR_NULL);
+        assert_eq!(render(0, height, 2, out.as_mut_ptr(), len), RTT_ERR_INVALID);
+        assert_eq!(
+            render(width, height, 0, out.as_mut_ptr(), len),
+            RTT_ERR_INVALID
+        );
+        assert_eq!(
+            render(width, height, 2, out.as_mut_ptr(), len - 1),
+            RTT_ERR_BUFFER
+        );
+        assert_eq!(
+            render(u32::MAX, u32::MAX, 1, out.as_mut_ptr(), len),
+            RTT_ERR_BUFFER
+        );
+        rtt_scene_free(scene);
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rtt::background::Constant;
+use rtt::hittable::{HitRecord, Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::CarPaint;
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings};
+use rtt::scene::MaterialDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+const BLUE: Color = Color::new(0.05, 0.1, 0.5);
+const UP: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+
+// A big floor of `paint` under a small lamp.
+fn floor(paint: CarPaint) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(paint),
+    )));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.25, 2.0, -0.25),
+        Vec3::new(0.5, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.5),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+// Hit on the floor at (x, 0, z), looked at from above at 45 degrees.
+fn hit(world: &HittableList, x: f64, z: f64) -> (Ray, HitRecord) {
+    let d = Vec3::new(1.0, -1.0, 0.0);
+    let ray = Ray::new(Point3::new(x - 1.0, 1.0, z), d);
+    let rec = world.hit(&ray, Interval::new(1e-3, f64::INFINITY)).unwrap();
+    (ray, rec)
+}
+
+#[test]
+fn flakes_are_fixed_per_cell() {
+    let paint = CarPaint::new(BLUE).with_flakes(Color::new(0.9, 0.9, 0.9), 0.4);
+    let size = paint.flake_size;
+    let (mut flakes, total) = (0, 4000);
+    for i in 0..total {
+        // Cell centres, and another point in each cell.
+        let centre = Point3::new((i % 64) as f64 + 0.5, 0.0, (i / 64) as f64 + 0.5) * size;
+        let nearby = centre + Vec3::new(0.3, 0.0, -0.2) * size;
+        let flake = paint.flake(centre, UP);
+        assert_eq!(flake, paint.flake(nearby, UP));
+        if let Some(m) = flake {
+            flakes += 1;
+            assert!((m.length() - 1.0).abs() < 1e-9);
+            assert!(Vec3::dot(m, UP) >= paint.flake_spread.cos() - 1e-9, "{m:?}");
+        }
+    }
+    let share = flakes as f64 / total as f64;
+    assert!((share - 0.4).abs() < 0.03, "{share}");
+    assert!(CarPaint::new(BLUE)
+        .with_flakes(BLUE, 0.0)
+        .flake(Point3::new(0.1, 0.0, 0.2), UP)
+        .is_none());
+}
+
+// Shadow rays weigh lights with `scattering` and `scattering_pdf`; their ratio must be what
+// `scatter` returns for the direction it picks.
+#[test]
+fn shadow_rays_agree_with_scatter_on_flakes_and_paint() {
+    let world = floor(CarPaint::new(BLUE).with_flakes(Color::new(0.9, 0.8, 0.5), 0.5));
+    let mut rng = StdRng::seed_from_u64(4);
+    let (mut glossy, mut diffuse) = (0, 0);
+    for _ in 0..2000 {
+        let (x, z) = (rng.random_range(-0.5..0.5), rng.random_range(-0.5..0.5));
+        let (ray, rec) = hit(&world, x, z);
+        let Some((attenuation, scattered)) = rec.material.get().scatter(&ray, &rec, &mut rng)
+        else {
+            continue;
+        };
+        let pdf = rec.material.get().scattering_pdf(&ray, &rec, &scattered);
+        if pdf <= 0.0 {
+            // The coat's mirror.
+            assert_eq!(attenuation, Color::new(1.0, 1.0, 1.0));
+            continue;
+        }
+        let value = rec
+            .material
+            .get()
+            .scattering(&ray, &rec, attenuation, &scattered);
+        assert!((value / pdf - attenuation).length() < 1e-9);
+        // Flakes glint in their own color; paint shows the pigment.
+        if attenuation.r() > attenuation.b() {
+            glossy += 1;
+        } else {
+            diffuse += 1;
+        }
+    }
+    assert!(glossy > 200 && diffuse > 200, "{glossy} {diffuse}");
+}
+
+// Mean light off the floor around the origin, seen at 45 degrees. Where it looks is drawn the
+// same way every time, so renders of one floor see the same flakes.
+fn floor_light(world: &HittableList, settings: RenderSettings, n: usize) -> Color {
+    let lights = lights(world);
+    let mut rng = StdRng::seed_from_u64(21);
+    let mut positions = StdRng::seed_from_u64(8);
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let mut sum = Color::default();
+    for _ in 0..n {
+        let x = positions.random_range(-0.3..0.3);
+        let z = positions.random_range(-0.3..0.3);
+        let ray = Ray::new(Point3::new(x - 1.0, 1.0, z), Vec3::new(1.0, -1.0, 0.0));
+        sum += trace_path_with(ray, world, &lights, ray_t, &settings, &mut rng).color;
+    }
+    sum / n as f64
+}
+
+#[test]
+fn flakes_sparkle_the_same_with_light_sampling() {
+    let settings = RenderSettings::default().with_seed(1);
+    let gold = Color::new(0.9, 0.7, 0.2);
+    let world = floor(
+        CarPaint::new(BLUE)
+            .with_flakes(gold, 0.5)
+            .with_flake_spread(0.8),
+    );
+    let n = 100_000;
+    let mis = floor_light(&world, settings, n);
+    let brute = floor_light(&world, settings.with_shadow_samples(0), n);
+    assert!(
+        (mis - brute).length() < 0.05 * brute.length(),
+        "{mis:?} vs brute force {brute:?}"
+    );
+    // The flakes catch the lamp and add their color to the blue.
+    let plain = floor_light(
+        &floor(CarPaint::new(BLUE).with_flakes(gold, 0.0)),
+        settings,
+        n,
+    );
+    assert!(mis.r() > 2.0 * plain.r(), "{mis:?} vs {plain:?}");
+}
+
+#[test]
+fn car_paint_round_trips_through_desc() {
+    let desc: MaterialDesc =
+        serde_json::from_str(r#"{"type": "car_paint", "color": [0.05, 0.1, 0.5]}"#).unwrap();
+    let defaults = CarPaint::new(BLUE);
+    assert_eq!(
+        desc,
+        MaterialDesc::CarPaint {
+            color: BLUE,
+            ior: 1.5,
+            flake_color: defaults.flake_color,
+            flake_density: defaults.flake_density,
+            flake_size: defaults.flake_size,
+            flake_spread: defaults.flake_spread,
+        }
+    );
+    assert_eq!(desc.build().to_desc(), Some(desc));
+
+    let json = r#"{"type": "car_paint", "color": [0.5, 0, 0], "flake_color": [0.9, 0.8, 0.5],
+        "flake_density": 0.6, "flake_size": 0.002, "flake_spread": 0.2, "ior": 1.6}"#;
+    let custom: MaterialDesc = serde_json::from_str(json).unwrap();
+    assert_eq!(custom.build().to_desc(), Some(custom));
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use rtt::aov::{Aov, AovSet};
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::color::luminance;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::render::{clamp_sample, render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A grey ball against a bright sky.
+fn bright_sky() -> HittableList {
+    let mut world = HittableList::new();
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5, grey)));
+    world.set_background(Arc::new(Constant::new(Color::new(8.0, 4.0, 2.0))));
+    world
+}
+
+fn render(settings: &RenderSettings) -> (Vec<Color>, Vec<Color>) {
+    let world = bright_sky();
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let size = 8;
+    let film = Film::new(size, size);
+    let aovs = AovSet::new(&[Aov::Unclamped], &world, size, size);
+    render_image_with(&world, &camera, &film, &aovs, 4, settings, &|_| {}).unwrap();
+    let unclamped = aovs.film(Aov::Unclamped).unwrap().resolve(1.0).unwrap();
+    (film.resolve(1.0).unwrap(), unclamped)
+}
+
+#[test]
+fn clamp_scales_bright_samples_keeping_hue() {
+    let col = Color::new(8.0, 4.0, 2.0);
+    assert_eq!(clamp_sample(col, None), col);
+    let clamped = clamp_sample(col, Some(1.0));
+    assert!((luminance(clamped) - 1.0).abs() < 1e-12);
+    assert!((clamped.r() / clamped.g() - 2.0).abs() < 1e-12);
+    let dim = Color::new(0.2, 0.1, 0.05);
+    assert_eq!(clamp_sample(dim, Some(1.0)), dim);
+    let nan = Color::new(f64::NAN, 0.0, 0.0);
+    assert!(clamp_sample(nan, Some(1.0)).r().is_nan());
+}
+
+#[test]
+fn unclamped_matches_the_beauty_without_a_clamp() {
+    let (beauty, unclamped) = render(&RenderSettings::default());
+    for (b, u) in beauty.iter().zip(&unclamped) {
+        assert!((*b - *u).length() < 1e-9, "{b:?} != {u:?}");
+    }
+}
+
+#[test]
+fn clamped_beauty_keeps_the_unclamped_image() {
+    let (beauty, unclamped) = render(&RenderSettings::default().with_clamp(1.0));
+    for c in &beauty {
+        assert!(luminance(*c) <= 1.0 + 1e-9, "{c:?}");
+    }
+    // The corners see only sky.
+    assert!((unclamped[0] - Color::new(8.0, 4.0, 2.0)).length() < 1e-9);
+    assert!((luminance(beauty[0]) - 1.0).abs() < 1e-9);
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::{Camera, LensEffects};
+use rtt::film::Film;
+use rtt::hittable::{ClipPlane, Clipped, Hittable, HittableList, Sphere};
+use rtt::material::DiffuseLight;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+const SIZE: u32 = 8;
+const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+const WALL: Color = Color::new(0.2, 0.2, 0.2);
+
+fn lamp(center: Point3, radius: f64, color: Color) -> Arc<dyn Hittable> {
+    Arc::new(Sphere::new(
+        center,
+        radius,
+        Arc::new(DiffuseLight::new(color)),
+    ))
+}
+
+// Center pixel of `ball`, in front of a glowing wall if `wall`, against a black sky. Everything
+// glows, so every channel of every ray sees exactly what it hits.
+fn render(ball: Arc<dyn Hittable>, wall: bool, camera: &Camera) -> Color {
+    let mut world = HittableList::new();
+    world.add(ball);
+    if wall {
+        world.add(lamp(Point3::new(0.0, 0.0, -100.0), 90.0, WALL));
+    }
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    let film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[], &world, SIZE, SIZE);
+    let settings = RenderSettings::default().with_seed(3);
+    render_image_with(&world, camera, &film, &aovs, 4, &settings, &|_| {}).unwrap();
+    film.pixel(SIZE / 2, SIZE / 2).unwrap().resolve(1.0)
+}
+
+fn ball(distance: f64) -> Arc<dyn Hittable> {
+    lamp(Point3::new(0.0, 0.0, 3.0 - distance), 0.5, WHITE)
+}
+
+// Looking down -z from z = 3, clipped to `near` and `far`, with and without chromatic
+// aberration.
+fn cameras(near: f64, far: f64) -> [Camera; 2] {
+    let camera = || {
+        Camera::new(
+            Point3::new(0.0, 0.0, 3.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            30.0,
+            1.0,
+            0.0,
+            3.0,
+        )
+        .with_clipping(near, far)
+    };
+    let aberrated = camera().with_lens_effects(LensEffects {
+        chromatic_aberration: 0.05,
+        ..Default::default()
+    });
+    [camera(), aberrated]
+}
+
+fn assert_close(got: Color, expected: Color) {
+    assert!((got - expected).length() < 1e-9, "{got:?} vs {expected:?}");
+}
+
+#[test]
+fn near_plane_hides_what_is_in_front_of_it() {
+    for camera in cameras(0.0, f64::INFINITY) {
+        assert_close(render(ball(3.0), true, &camera), WHITE);
+    }
+    // Every channel sees through to the wall, with no colored fringe.
+    for camera in cameras(4.0, f64::INFINITY) {
+        assert_close(render(ball(3.0), true, &camera), WALL);
+    }
+}
+
+#[test]
+fn far_plane_hides_what_lies_beyond_it() {
+    for camera in cameras(0.0, 5.0) {
+        assert_close(render(ball(3.0), false, &camera), WHITE);
+        assert_close(render(ball(6.0), false, &camera), Color::default());
+    }
+}
+
+#[test]
+fn clip_planes_cut_away_for_every_channel() {
+    for camera in cameras(0.0, f64::INFINITY) {
+        // Cuts away everything in front of z = -1, the whole ball.
+        let cut = ClipPlane::new(Point3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        let clipped = Arc::new(Clipped::new(ball(3.0), vec![cut]));
+        assert_close(render(clipped, true, &camera), WALL);
+        // Cutting away its front half shows the inside of its back.
+        let half = ClipPlane::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let halved = Arc::new(Clipped::new(ball(3.0), vec![half]));
+        assert_close(render(halved, true, &camera), WHITE);
+    }
+}
@@ -0,0 +1,209 @@
+use rtt::color::{
+    adapt, blackbody, cie_xyz, from_hex, from_kelvin, from_xyy, kelvin_xy_tinted, linear_to_srgb,
+    luminance, planck, spectrum_to_xyz, srgb_to_linear, to_hex, to_xyz, wavelength_to_rgb, BLACK,
+    VISIBLE, WHITE,
+};
+use rtt::material::{DiffuseLight, Material};
+use rtt::scene::MaterialDesc;
+use rtt::vec3::Color;
+
+#[test]
+fn hex_round_trips_through_srgb() {
+    assert_eq!(from_hex("#ffffff").unwrap(), WHITE);
+    assert_eq!(from_hex("000").unwrap(), BLACK);
+    assert_eq!(from_hex("#F80").unwrap(), from_hex("ff8800").unwrap());
+    // Mid grey in sRGB is about a fifth in linear terms.
+    let grey = from_hex("#808080").unwrap();
+    assert!((grey.x - 0.2158).abs() < 1e-3, "{grey:?}");
+    for hex in ["#000000", "#ff8800", "#1a2b3c", "#ffffff"] {
+        assert_eq!(to_hex(from_hex(hex).unwrap()), hex);
+    }
+    assert_eq!(to_hex(Color::new(2.0, -1.0, 0.0)), "#ff0000");
+    for bad in ["", "#12345", "#ggg", "#ffé", "1234567"] {
+        assert!(from_hex(bad).is_err(), "{bad:?}");
+    }
+
+    for i in 0..=20 {
+        let c = i as f64 / 20.0;
+        assert!((srgb_to_linear(linear_to_srgb(c)) - c).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn kelvin_runs_from_orange_to_blue() {
+    let candle = from_kelvin(1900.0);
+    let tungsten = from_kelvin(2700.0);
+    let daylight = from_kelvin(6500.0);
+    let sky = from_kelvin(12000.0);
+    // Brightest channel is always 1.
+    for c in [candle, tungsten, daylight, sky] {
+        assert!((c.x.max(c.y).max(c.z) - 1.0).abs() < 1e-12, "{c:?}");
+    }
+    assert!(candle.z < tungsten.z && tungsten.z < daylight.z);
+    assert!(tungsten.x == 1.0 && tungsten.x > tungsten.y && tungsten.y > tungsten.z);
+    assert!(sky.z == 1.0 && sky.x < sky.z);
+    // 6500 K sits just off the sRGB white point.
+    assert!(
+        daylight.x.min(daylight.y).min(daylight.z) > 0.9,
+        "{daylight:?}"
+    );
+    // Clamped to the fit's range.
+    assert_eq!(from_kelvin(100.0), from_kelvin(1667.0));
+    assert_eq!(from_kelvin(1e6), from_kelvin(25000.0));
+    assert!((luminance(WHITE) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn scene_files_take_any_color_form() {
+    let parse = |albedo: &str| {
+        let json = format!(r#"{{"type": "lambertian", "albedo": {albedo}}}"#);
+        match serde_json::from_str::<MaterialDesc>(&json) {
+            Ok(MaterialDesc::Lambertian { albedo }) => Ok(albedo),
+            Ok(other) => panic!("{other:?}"),
+            Err(e) => Err(e),
+        }
+    };
+    let c = Color::new(0.25, 0.5, 1.0);
+    assert_eq!(parse(r#"{"x": 0.25, "y": 0.5, "z": 1.0}"#).unwrap(), c);
+    assert_eq!(parse("[0.25, 0.5, 1.0]").unwrap(), c);
+    assert_eq!(
+        parse(r##""#ff8800""##).unwrap(),
+        from_hex("#ff8800").unwrap()
+    );
+    assert_eq!(
+        parse(r#"{"kelvin": 3000, "intensity": 4}"#).unwrap(),
+        4.0 * from_kelvin(3000.0)
+    );
+    assert_eq!(parse(r#"{"kelvin": 3000}"#).unwrap(), from_kelvin(3000.0));
+    assert_eq!(
+        parse(r#"{"blackbody": 1800, "intensity": 3}"#).unwrap(),
+        3.0 * blackbody(1800.0)
+    );
+    assert!(parse(r##""#ff88""##).is_err());
+    assert!(parse(r#"{"r": 1}"#).is_err());
+
+    // Saved colors stay in the component form.
+    let saved = serde_json::to_value(MaterialDesc::Lambertian { albedo: c }).unwrap();
+    assert_eq!(saved["albedo"]["y"], 0.5);
+}
+
+#[test]
+fn color_matching_functions_peak_where_tabulated() {
+    let peak = |channel: fn(rtt::vec3::Vec3) -> f64| {
+        (380..780)
+            .map(|nm| (nm, channel(cie_xyz(nm as f64))))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+    };
+    let (x_nm, x) = peak(|v| v.x);
+    let (y_nm, y) = peak(|v| v.y);
+    let (z_nm, z) = peak(|v| v.z);
+    assert!(
+        (595..=605).contains(&x_nm) && (x - 1.06).abs() < 0.02,
+        "{x_nm} {x}"
+    );
+    assert!(
+        (550..=560).contains(&y_nm) && (y - 1.0).abs() < 0.02,
+        "{y_nm} {y}"
+    );
+    assert!(
+        (440..=450).contains(&z_nm) && (z - 1.78).abs() < 0.03,
+        "{z_nm} {z}"
+    );
+
+    // The tables integrate to about 106.86 for each function.
+    let flat = spectrum_to_xyz(|_| 1.0);
+    for v in [flat.x, flat.y, flat.z] {
+        assert!((v - 106.86).abs() < 1.5, "{flat:?}");
+    }
+}
+
+#[test]
+fn single_wavelengths_average_to_the_spectrum() {
+    assert!(wavelength_to_rgb(650.0).x > wavelength_to_rgb(650.0).y);
+    let green = wavelength_to_rgb(530.0);
+    assert!(
+        green.y > green.x && green.y > green.z && green.x < 0.0,
+        "{green:?}"
+    );
+    assert!(wavelength_to_rgb(450.0).z > wavelength_to_rgb(450.0).y);
+    assert_eq!(wavelength_to_rgb(300.0), BLACK);
+
+    // Midpoint sums over the visible range, as a spectral render would average them.
+    let (lo, hi) = VISIBLE;
+    let n = 4000;
+    let average = |spectrum: &dyn Fn(f64) -> f64| {
+        (0..n).fold(rtt::vec3::Vec3::default(), |sum, i| {
+            let nm = lo + (i as f64 + 0.5) * (hi - lo) / n as f64;
+            sum + spectrum(nm) * wavelength_to_rgb(nm)
+        }) / n as f64
+    };
+    assert!((luminance(average(&|_| 1.0)) - 1.0).abs() < 0.01);
+    // Sunlight-like blackbody light comes out nearly white, candlelight orange.
+    let sun = average(&|nm| planck(nm, 6500.0));
+    let sun = sun / sun.x.max(sun.y).max(sun.z);
+    assert!(sun.x.min(sun.y).min(sun.z) > 0.85, "{sun:?}");
+    let candle = average(&|nm| planck(nm, 1900.0));
+    assert!(candle.x > candle.y && candle.y > candle.z, "{candle:?}");
+}
+
+#[test]
+fn blackbody_follows_plancks_law() {
+    // Wien's displacement law puts the 5800 K peak near 500 nm.
+    let peak = (300..1000)
+        .max_by(|&a, &b| planck(a as f64, 5800.0).total_cmp(&planck(b as f64, 5800.0)))
+        .unwrap();
+    assert!((495..=505).contains(&peak), "{peak}");
+    // Hotter is brighter at every wavelength.
+    assert!(planck(600.0, 3000.0) < planck(600.0, 3001.0));
+    assert_eq!(planck(600.0, 0.0), 0.0);
+
+    // The exact hue follows the same ramp as the Planckian locus fit.
+    for kelvin in [1900.0, 2700.0, 4000.0, 10000.0] {
+        let (exact, fit) = (blackbody(kelvin), from_kelvin(kelvin));
+        assert!(
+            (exact - fit).length() < 0.15,
+            "{kelvin}: {exact:?} vs {fit:?}"
+        );
+    }
+    let lamp = DiffuseLight::blackbody(2700.0, 5.0);
+    assert_eq!(lamp.emit, 5.0 * blackbody(2700.0));
+    assert!(lamp.to_desc().is_some());
+}
+
+#[test]
+fn bradford_adaptation_moves_whites_and_keeps_greys() {
+    let d65 = to_xyz(WHITE);
+    let warm = to_xyz(from_kelvin(3000.0));
+    // The warm light itself comes out white, at its own luminance.
+    let adapted = adapt(from_kelvin(3000.0), warm, d65);
+    let y = luminance(from_kelvin(3000.0));
+    assert!((adapted - y * WHITE).length() < 1e-3, "{adapted:?}");
+    // Adapting to the same white changes nothing, and back again undoes it.
+    let c = Color::new(0.2, 0.5, 0.1);
+    assert!((adapt(c, d65, d65) - c).length() < 1e-6);
+    assert!((adapt(adapt(c, warm, d65), d65, warm) - c).length() < 1e-6);
+}
+
+#[test]
+fn tint_moves_off_the_planckian_locus() {
+    for kelvin in [2700.0, 5000.0, 9000.0] {
+        let (x, y) = kelvin_xy_tinted(kelvin, 0.0);
+        let on_locus = from_kelvin(kelvin);
+        let c = from_xyy(x, y, 1.0);
+        assert!((c / c.x.max(c.y).max(c.z) - on_locus).length() < 1e-9);
+
+        let chroma = |(x, y): (f64, f64)| {
+            let c = from_xyy(x, y, 1.0);
+            c.y / (c.x + c.z)
+        };
+        let (green, magenta) = (
+            chroma(kelvin_xy_tinted(kelvin, 0.01)),
+            chroma(kelvin_xy_tinted(kelvin, -0.01)),
+        );
+        assert!(
+            green > chroma((x, y)) && magenta < chroma((x, y)),
+            "{kelvin}"
+        );
+    }
+}
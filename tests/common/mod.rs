@@ -0,0 +1,55 @@
+// Fixtures shared by the material tests.
+
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use rtt::background::Constant;
+use rtt::hittable::{HitRecord, Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::Material;
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A unit sphere of `material` at the origin, for `hit`.
+pub fn ball(material: impl Material + 'static) -> Sphere {
+    Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Arc::new(material))
+}
+
+// A ray arriving `theta` from the normal at the top of `ball`, and its hit. Hits refer to the
+// ball's material, so it must outlive them.
+pub fn hit(ball: &Sphere, theta: f64) -> (Ray, HitRecord) {
+    let d = Vec3::new(theta.sin(), -theta.cos(), 0.0);
+    let ray = Ray::new(Point3::new(0.0, 1.0, 0.0) - 3.0 * d, d);
+    let rec = ball.hit(&ray, Interval::new(1e-3, f64::INFINITY)).unwrap();
+    (ray, rec)
+}
+
+// Light off a floor of `material` from `lamp`, seen from above at an angle.
+pub fn lit_floor(
+    material: impl Material + 'static,
+    lamp: QuadLight,
+    settings: RenderSettings,
+    n: usize,
+) -> Color {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(material),
+    )));
+    world.add(Arc::new(lamp));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    let lights = lights(&world);
+    let mut rng = StdRng::seed_from_u64(21);
+    let ray = Ray::new(Point3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let mut sum = Color::default();
+    for _ in 0..n {
+        sum += trace_path_with(ray, &world, &lights, ray_t, &settings, &mut rng).color;
+    }
+    sum / n as f64
+}
@@ -0,0 +1,75 @@
+use image::{Rgb, Rgba, RgbaImage};
+use rtt::compare::{heat_color, heatmap, split, DiffStats};
+use rtt::vec3::Color;
+
+fn grey(v: f64) -> Color {
+    Color::new(v, v, v)
+}
+
+#[test]
+fn identical_images_have_no_difference() {
+    let a = vec![grey(0.2), grey(0.7), Color::new(0.1, 0.5, 0.9)];
+    let stats = DiffStats::new(&a, &a).unwrap();
+    assert_eq!(stats.mean_abs, 0.0);
+    assert_eq!(stats.rmse, 0.0);
+    assert_eq!(stats.max_abs, 0.0);
+    assert_eq!(stats.psnr, f64::INFINITY);
+}
+
+#[test]
+fn stats_measure_every_channel() {
+    let a = vec![grey(0.5), grey(0.5)];
+    let b = vec![grey(0.5), Color::new(0.5, 0.5, 0.8)];
+    let stats = DiffStats::new(&a, &b).unwrap();
+    assert!((stats.mean_abs - 0.3 / 6.0).abs() < 1e-12);
+    assert!((stats.rmse - (0.09f64 / 6.0).sqrt()).abs() < 1e-12);
+    assert!((stats.max_abs - 0.3).abs() < 1e-12);
+    assert!((stats.psnr - -10.0 * (0.09f64 / 6.0).log10()).abs() < 1e-9);
+}
+
+#[test]
+fn mismatched_sizes_are_errors() {
+    assert!(DiffStats::new(&[grey(0.0)], &[]).is_err());
+    assert!(heatmap(&[grey(0.0); 4], &[grey(0.0); 4], 3, 1, None).is_err());
+    let small = RgbaImage::new(4, 4);
+    assert!(split(&small, &RgbaImage::new(4, 5)).is_err());
+}
+
+#[test]
+fn split_shows_left_of_a_and_right_of_b() {
+    let a = RgbaImage::from_pixel(20, 4, Rgba([255, 0, 0, 255]));
+    let b = RgbaImage::from_pixel(20, 4, Rgba([0, 0, 255, 255]));
+    let img = split(&a, &b).unwrap();
+    assert_eq!(img.dimensions(), (20, 4));
+    assert_eq!(img.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+    assert_eq!(img.get_pixel(8, 3), &Rgba([255, 0, 0, 255]));
+    assert_eq!(img.get_pixel(9, 0), &Rgba([255, 255, 255, 255]));
+    assert_eq!(img.get_pixel(10, 0), &Rgba([255, 255, 255, 255]));
+    assert_eq!(img.get_pixel(11, 2), &Rgba([0, 0, 255, 255]));
+    assert_eq!(img.get_pixel(19, 3), &Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn heatmap_scales_differences() {
+    let a = vec![grey(0.5); 4];
+    let b = vec![grey(0.5), grey(0.6), grey(0.7), grey(0.5)];
+    let img = heatmap(&a, &b, 2, 2, Some(0.2)).unwrap();
+    assert_eq!(img.get_pixel(0, 0), &heat_color(0.0));
+    assert_eq!(img.get_pixel(0, 1), &heat_color(1.0));
+    // Halfway, up to rounding.
+    let (got, want) = (img.get_pixel(1, 0).0, heat_color(0.5).0);
+    assert!(got.iter().zip(want).all(|(&g, w)| g.abs_diff(w) <= 1));
+
+    // By default the largest differences reach full heat.
+    let img = heatmap(&a, &b, 2, 2, None).unwrap();
+    assert_eq!(img.get_pixel(0, 1), &heat_color(1.0));
+    assert_eq!(img.get_pixel(1, 1), &heat_color(0.0));
+}
+
+#[test]
+fn heat_runs_from_black_to_white() {
+    assert_eq!(heat_color(0.0), Rgb([0, 0, 0]));
+    assert_eq!(heat_color(1.0), Rgb([255, 255, 255]));
+    assert_eq!(heat_color(5.0), Rgb([255, 255, 255]));
+    assert_eq!(heat_color(-1.0), Rgb([0, 0, 0]));
+}
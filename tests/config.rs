@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use rtt::config::Config;
+use rtt::render::Preset;
+
+#[test]
+fn parses_every_setting() {
+    let config = Config::parse(
+        r#"
+        threads = 6
+        output_dir = "/tmp/renders"
+        format = "dng"
+        preset = "final"
+        memory_budget = 16384
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            threads: Some(6),
+            output_dir: Some(PathBuf::from("/tmp/renders")),
+            format: Some("dng".to_string()),
+            preset: Some(Preset::Final),
+            memory_budget: Some(16384),
+        }
+    );
+}
+
+#[test]
+fn missing_settings_stay_unset() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    let config = Config::parse("preset = \"draft\"").unwrap();
+    assert_eq!(config.preset, Some(Preset::Draft));
+    assert_eq!(config.threads, None);
+}
+
+#[test]
+fn bad_settings_are_errors() {
+    for text in [
+        "format = \"gif\"",
+        "preset = \"ultra\"",
+        "threads = 0",
+        "threads = \"many\"",
+        "thread = 4",
+        "threads = ",
+    ] {
+        assert!(Config::parse(text).is_err(), "{text:?} parsed");
+    }
+}
+
+#[test]
+fn loads_from_file_and_names_it_in_errors() {
+    let path = std::env::temp_dir().join(format!("rtt-config-{}.toml", std::process::id()));
+    std::fs::write(&path, "threads = 2\n").unwrap();
+    assert_eq!(Config::load(&path).unwrap().threads, Some(2));
+
+    std::fs::write(&path, "format = \"gif\"\n").unwrap();
+    let err = Config::load(&path).unwrap_err().to_string();
+    std::fs::remove_file(&path).unwrap();
+    assert!(err.contains(&path.display().to_string()), "{err}");
+}
+
+#[test]
+fn output_dir_without_tilde_is_kept() {
+    let config = Config {
+        output_dir: Some(PathBuf::from("renders/today")),
+        ..Config::default()
+    };
+    assert_eq!(config.output_dir(), Some(PathBuf::from("renders/today")));
+}
+
+#[test]
+fn user_config_lives_in_the_config_directory() {
+    if let Some(path) = Config::user_path() {
+        assert!(path.ends_with("rtt.toml"));
+    }
+}
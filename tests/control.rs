@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rtt::camera::Camera;
+use rtt::control::RenderControl;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::render::Renderer;
+use rtt::vec3::{Color, Point3, Vec3};
+use rtt::Error;
+
+fn renderer(size: u32) -> Renderer {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -1.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    Renderer::new(Arc::new(world), camera, size, size)
+}
+
+fn total_weight(r: &Renderer) -> f64 {
+    let film = r.film();
+    (0..film.height())
+        .flat_map(|y| (0..film.width()).map(move |x| (x, y)))
+        .map(|(x, y)| film.pixel(x, y).unwrap().weight_sum)
+        .sum()
+}
+
+#[test]
+fn cancelled_renderers_stop_at_once() {
+    let mut r = renderer(8);
+    r.cancel();
+    assert!(matches!(r.render_pass(4), Err(Error::Cancelled)));
+    // The pass's samples count as taken, even though none were.
+    assert_eq!(r.samples(), 4);
+    assert_eq!(total_weight(&r), 0.0);
+}
+
+#[test]
+fn cancelling_mid_pass_keeps_what_was_rendered() {
+    let mut r = renderer(64);
+    let control = r.control();
+    let start = Instant::now();
+    let render = thread::spawn(move || {
+        let result = r.render_pass(100_000);
+        (r, result)
+    });
+    thread::sleep(Duration::from_millis(200));
+    control.cancel();
+    let (r, result) = render.join().unwrap();
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert_eq!(r.samples(), 100_000);
+    let weight = total_weight(&r);
+    assert!(weight > 0.0 && weight < 100_000.0 * 64.0 * 64.0, "{weight}");
+}
+
+#[test]
+fn reset_renderers_carry_on_after_a_cancel() {
+    let mut r = renderer(64);
+    let control = r.control();
+    let render = thread::spawn(move || {
+        let result = r.render_pass(100_000).map(|_| ());
+        (r, result)
+    });
+    thread::sleep(Duration::from_millis(200));
+    control.cancel();
+    let (mut r, result) = render.join().unwrap();
+    assert!(matches!(result, Err(Error::Cancelled)));
+    let before = total_weight(&r);
+
+    control.reset();
+    assert!(!control.is_cancelled());
+    r.render_pass(2).unwrap();
+    assert_eq!(r.samples(), 100_002);
+    assert_eq!(total_weight(&r), before + 2.0 * 64.0 * 64.0);
+}
+
+#[test]
+fn passes_after_a_cancel_take_new_samples() {
+    let mut cancelled = renderer(8);
+    cancelled.cancel();
+    assert!(cancelled.render_pass(4).is_err());
+    cancelled.control().reset();
+    let after = cancelled.render_pass(1).unwrap().resolve(1.0).unwrap();
+    let fresh = renderer(8).render_pass(1).unwrap().resolve(1.0).unwrap();
+    assert_ne!(after, fresh);
+}
+
+#[test]
+fn paused_renders_wait_for_resume() {
+    let control = RenderControl::new();
+    let mut r = renderer(4).with_control(control.clone());
+    control.pause();
+    assert!(control.is_paused());
+    let render = thread::spawn(move || {
+        let result = r.render_pass(1).map(|_| ());
+        (r, result)
+    });
+    thread::sleep(Duration::from_millis(200));
+    assert!(!render.is_finished());
+    control.resume();
+    let (r, result) = render.join().unwrap();
+    result.unwrap();
+    assert_eq!(r.samples(), 1);
+    assert_eq!(total_weight(&r), 16.0);
+}
+
+#[test]
+fn cancelling_wakes_paused_renders() {
+    let mut r = renderer(4);
+    let control = r.control();
+    control.pause();
+    let render = thread::spawn(move || r.render_pass(1).map(|_| ()));
+    thread::sleep(Duration::from_millis(50));
+    control.cancel();
+    assert!(matches!(render.join().unwrap(), Err(Error::Cancelled)));
+    assert!(control.is_cancelled() && !control.is_paused());
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use image::{Rgb, RgbImage};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::hittable::{Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::material::{Cutout, Lambertian, Material};
+use rtt::mesh::Mesh;
+use rtt::ray::Ray;
+use rtt::scene::{CameraDesc, SceneDesc};
+use rtt::texture::TextureCache;
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Writes an opacity image whose pixel at `x` of 16 columns has gray level `level(x)`.
+fn write_mask(name: &str, level: impl Fn(u32) -> u8) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rtt-cutout-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    RgbImage::from_fn(16, 16, |x, _| Rgb([level(x); 3]))
+        .save(&path)
+        .unwrap();
+    path
+}
+
+fn grey() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+// A unit card in the z = 0 plane with texture coordinates running with x and y.
+fn card(material: Arc<dyn Material>) -> HittableList {
+    let positions = vec![
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(1.0, 1.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+    ];
+    let uvs = vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    let mesh = Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], material)
+        .unwrap()
+        .with_uvs(uvs)
+        .unwrap();
+    let mut world = HittableList::new();
+    world.add(Arc::new(mesh));
+    // A backdrop behind the card, so rays through its holes still hit something.
+    world.add(Sphere::new(Point3::new(0.5, 0.5, -101.0), 100.0, grey()));
+    world
+}
+
+fn toward_card(x: f64, y: f64) -> Ray {
+    Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0))
+}
+
+#[test]
+fn rays_pass_through_the_transparent_half() {
+    // Opaque on the left half of the texture, clear on the right.
+    let mask = write_mask("half.png", |x| if x < 8 { 255 } else { 0 });
+    let cutout = Arc::new(Cutout::new(grey(), TextureCache::shared().texture(&mask)));
+    let world = card(cutout);
+    let ray_t = Interval::new(1e-3, f64::INFINITY);
+
+    let rec = world.hit(&toward_card(0.25, 0.6), ray_t).unwrap();
+    assert!((rec.t - 1.0).abs() < 1e-9);
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.6).abs() < 1e-9);
+    assert!(world.is_occluded(&toward_card(0.25, 0.6), Interval::new(1e-3, 1.5)));
+
+    let rec = world.hit(&toward_card(0.75, 0.3), ray_t).unwrap();
+    // On the backdrop.
+    assert!(rec.t > 1.5);
+    assert!(!world.is_occluded(&toward_card(0.75, 0.3), Interval::new(1e-3, 1.5)));
+    assert_eq!(world.hit_all(&toward_card(0.75, 0.3), ray_t).len(), 2);
+    assert_eq!(world.hit_all(&toward_card(0.25, 0.3), ray_t).len(), 3);
+}
+
+#[test]
+fn partial_opacity_stops_that_share_of_rays() {
+    // 8 bit textures are sRGB encoded, so 188 reads back as about 0.5.
+    let mask = write_mask("grey.png", |_| 188);
+    let cutout = Arc::new(Cutout::new(grey(), TextureCache::shared().texture(&mask)));
+    let world = card(cutout);
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut stopped = 0;
+    let n = 4000;
+    for _ in 0..n {
+        let ray = toward_card(rng.random(), rng.random());
+        let hit = world
+            .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+            .is_some_and(|rec| rec.t < 1.5);
+        // Shadow rays see the same cutouts as closest hits.
+        assert_eq!(hit, world.is_occluded(&ray, Interval::new(1e-3, 1.5)));
+        stopped += hit as usize;
+    }
+    let share = stopped as f64 / n as f64;
+    assert!((share - 0.503).abs() < 0.03, "{share}");
+}
+
+#[test]
+fn cutouts_round_trip_through_scene_files() {
+    let mask = write_mask("scene.png", |x| if x < 8 { 255 } else { 0 });
+    let cutout = Arc::new(Cutout::new(grey(), TextureCache::shared().texture(&mask)));
+    let camera = CameraDesc {
+        look_from: Point3::new(0.5, 0.5, 3.0),
+        look_at: Point3::new(0.5, 0.5, 0.0),
+        ..Default::default()
+    };
+    let desc = SceneDesc::from_world(&card(cutout), camera).unwrap();
+    let json = desc.to_json().unwrap();
+    assert!(json.contains("cutout") && json.contains("uvs"));
+    let (world, _camera) = SceneDesc::from_json(&json).unwrap().build(1.0).unwrap();
+
+    let ray_t = Interval::new(1e-3, f64::INFINITY);
+    let opaque = world.hit(&toward_card(0.25, 0.5), ray_t).unwrap();
+    let clear = world.hit(&toward_card(0.75, 0.5), ray_t).unwrap();
+    assert!((opaque.t - 1.0).abs() < 1e-9 && clear.t > 1.5);
+}
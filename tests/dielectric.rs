@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rtt::background::Constant;
+use rtt::hittable::{HitRecord, Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::{fresnel_dielectric, Dielectric, Lambertian, Material, ThinDielectric};
+use rtt::mesh::Mesh;
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings};
+use rtt::scene::MaterialDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+const EPS: f64 = 1e-9;
+
+// Textbook s/p reflectances, written independently of `fresnel_dielectric`.
+fn analytic_fresnel(theta_i: f64, n1: f64, n2: f64) -> f64 {
+    let sin_t = n1 / n2 * theta_i.sin();
+    if sin_t >= 1.0 {
+        return 1.0;
+    }
+    let theta_t = sin_t.asin();
+    let (ci, ct) = (theta_i.cos(), theta_t.cos());
+    let rs = ((n1 * ci - n2 * ct) / (n1 * ci + n2 * ct)).powi(2);
+    let rp = ((n1 * ct - n2 * ci) / (n1 * ct + n2 * ci)).powi(2);
+    0.5 * (rs + rp)
+}
+
+fn glass_ball(ref_idx: f64) -> Sphere {
+    Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(ref_idx)),
+    )
+}
+
+// Hits refer to the ball's material, so it must outlive them.
+fn glass_hit(ball: &Sphere, ray: &Ray) -> HitRecord {
+    ball.hit(ray, Interval::new(0.001, f64::INFINITY))
+        .expect("ray should hit the sphere")
+}
+
+#[test]
+fn fresnel_normal_incidence() {
+    for n in [1.0_f64, 1.33, 1.5, 2.4] {
+        let expected = ((n - 1.0) / (n + 1.0)).powi(2);
+        assert!((fresnel_dielectric(1.0, n) - expected).abs() < EPS);
+        assert!((fresnel_dielectric(1.0, 1.0 / n) - expected).abs() < EPS);
+    }
+}
+
+#[test]
+fn fresnel_matches_analytic_over_angles() {
+    let mut rng = StdRng::seed_from_u64(125);
+    for _ in 0..10_000 {
+        let n = rng.random_range(1.0..3.0);
+        let theta = rng.random_range(0.0..std::f64::consts::FRAC_PI_2);
+
+        let entering = fresnel_dielectric(theta.cos(), n);
+        assert!((entering - analytic_fresnel(theta, 1.0, n)).abs() < 1e-9);
+
+        let exiting = fresnel_dielectric(theta.cos(), 1.0 / n);
+        assert!((exiting - analytic_fresnel(theta, n, 1.0)).abs() < 1e-9);
+        assert!((0.0..=1.0).contains(&exiting));
+    }
+}
+
+#[test]
+fn fresnel_vanishing_p_component_at_brewster() {
+    let n: f64 = 1.5;
+    let theta_b = n.atan();
+    let theta_t = std::f64::consts::FRAC_PI_2 - theta_b;
+    let rs = ((theta_b.cos() - n * theta_t.cos()) / (theta_b.cos() + n * theta_t.cos())).powi(2);
+    assert!((fresnel_dielectric(theta_b.cos(), n) - 0.5 * rs).abs() < EPS);
+}
+
+#[test]
+fn fresnel_reciprocal_across_interface() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..1_000 {
+        let n = rng.random_range(1.0..3.0);
+        let theta_i: f64 = rng.random_range(0.0..1.5);
+        let theta_t = (theta_i.sin() / n).asin();
+        let forward = fresnel_dielectric(theta_i.cos(), n);
+        let backward = fresnel_dielectric(theta_t.cos(), 1.0 / n);
+        assert!((forward - backward).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn fresnel_total_internal_reflection() {
+    let n: f64 = 1.5;
+    let critical = (1.0 / n).asin();
+    for k in 1..100 {
+        let theta = critical + (std::f64::consts::FRAC_PI_2 - critical) * k as f64 / 100.0;
+        assert_eq!(fresnel_dielectric(theta.cos(), 1.0 / n), 1.0);
+    }
+}
+
+#[test]
+fn exiting_rays_beyond_critical_angle_always_reflect() {
+    let n: f64 = 1.5;
+    let critical = (1.0 / n).asin();
+    let mut rng = StdRng::seed_from_u64(3);
+    let ball = glass_ball(n);
+
+    for _ in 0..1_000 {
+        // From inside the unit sphere, travelling +y, the exit angle against the normal is theta.
+        let theta: f64 = rng.random_range(critical + 0.01..1.5);
+        let ray = Ray::new(Point3::new(theta.sin(), 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let rec = glass_hit(&ball, &ray);
+        assert!(!rec.front_face);
+        assert!((-Vec3::dot(ray.direction(), rec.normal) - theta.cos()).abs() < 1e-9);
+
+        let (_, scattered) = rec.material.get().scatter(&ray, &rec, &mut rng).unwrap();
+        assert!(Vec3::dot(scattered.direction(), rec.normal) > 0.0);
+    }
+}
+
+#[test]
+fn scatter_obeys_reflection_and_snell() {
+    let n = 1.5;
+    let mut rng = StdRng::seed_from_u64(11);
+    let (mut reflected, mut refracted) = (0, 0);
+    let ball = glass_ball(n);
+
+    for _ in 0..5_000 {
+        let y = rng.random_range(-0.95..0.95);
+        let ray = Ray::new(Point3::new(-5.0, y, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let rec = glass_hit(&ball, &ray);
+        assert!(rec.front_face);
+
+        let d = Vec3::unit_vector(ray.direction());
+        let cos_i = -Vec3::dot(d, rec.normal);
+        let (attenuation, scattered) = rec.material.get().scatter(&ray, &rec, &mut rng).unwrap();
+        assert_eq!(attenuation, Vec3::new(1.0, 1.0, 1.0));
+
+        let out = Vec3::unit_vector(scattered.direction());
+        let cos_o = Vec3::dot(out, rec.normal);
+        if cos_o > 0.0 {
+            reflected += 1;
+            assert!((cos_o - cos_i).abs() < 1e-9);
+        } else {
+            refracted += 1;
+            let sin_i = (1.0 - cos_i * cos_i).sqrt();
+            let sin_t = (1.0 - cos_o * cos_o).sqrt();
+            assert!((sin_i - n * sin_t).abs() < 1e-9);
+        }
+    }
+
+    assert!(reflected > 0 && refracted > reflected);
+}
+
+// A grey floor lit from a panel two units up, under a window of `glass` halfway.
+fn window_scene(glass: Option<Arc<dyn Material>>) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    if let Some(glass) = glass {
+        let corners = [(-2.0, -2.0), (2.0, -2.0), (2.0, 2.0), (-2.0, 2.0)];
+        let positions = corners.map(|(x, z)| Point3::new(x, 1.0, z)).to_vec();
+        let pane = Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]], glass).unwrap();
+        world.add(Arc::new(pane));
+    }
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.25, 2.0, -0.25),
+        Vec3::new(0.5, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.5),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+// Mean light the floor under the window reflects straight up.
+fn floor_light(world: &HittableList, settings: RenderSettings, n: usize) -> Color {
+    let lights = lights(world);
+    let mut rng = StdRng::seed_from_u64(21);
+    let ray = Ray::new(Point3::new(0.0, 0.2, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let mut sum = Color::default();
+    for _ in 0..n {
+        sum += trace_path_with(ray, world, &lights, ray_t, &settings, &mut rng).color;
+    }
+    sum / n as f64
+}
+
+#[test]
+fn thin_glass_reflects_or_lets_light_through() {
+    let n = 1.5;
+    let tint = Color::new(0.9, 0.6, 0.3);
+    let pane = ThinDielectric::new(n).with_tint(tint);
+    // Both faces and the light bouncing between them.
+    let r = fresnel_dielectric(1.0, n);
+    let both = r + (1.0 - r) * (1.0 - r) * r / (1.0 - r * r);
+    assert!((pane.reflectance(1.0) - both).abs() < EPS);
+    assert!((pane.reflectance(-1.0) - both).abs() < EPS);
+    assert_eq!(pane.reflectance(0.0), 1.0);
+
+    let bubble = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Arc::new(pane));
+    let d = Vec3::new(1.0, 0.0, 0.0);
+    let ray = Ray::new(Point3::new(-5.0, 0.3, 0.0), d);
+    let rec = bubble
+        .hit(&ray, Interval::new(0.001, f64::INFINITY))
+        .unwrap();
+    let cos_i = -Vec3::dot(d, rec.normal);
+    let expected = 1.0 - ThinDielectric::new(n).reflectance(cos_i);
+    assert_eq!(
+        rec.material.get().transmittance(&ray, &rec),
+        expected * tint
+    );
+
+    // Hits that stop reflect about the normal; the rest go on, tinted.
+    let (attenuation, scattered) = rec
+        .material
+        .get()
+        .scatter(&ray, &rec, &mut StdRng::seed_from_u64(1))
+        .unwrap();
+    assert_eq!(attenuation, Vec3::new(1.0, 1.0, 1.0));
+    let cos_o = Vec3::dot(Vec3::unit_vector(scattered.direction()), rec.normal);
+    assert!((cos_o - cos_i).abs() < 1e-9);
+    let mut rng = StdRng::seed_from_u64(5);
+    let total = 20_000;
+    let mut through = 0;
+    for _ in 0..total {
+        if let Some(c) = rec.material.get().filter(&ray, &rec, &mut rng) {
+            assert_eq!(c, tint);
+            through += 1;
+        }
+    }
+    let measured = through as f64 / total as f64;
+    assert!(
+        (measured - expected).abs() < 0.01,
+        "{measured} vs {expected}"
+    );
+    // Other materials stop everything.
+    assert!(glass_hit(&glass_ball(n), &ray)
+        .material
+        .get()
+        .filter(&ray, &rec, &mut rng)
+        .is_none());
+}
+
+#[test]
+fn windows_of_thin_glass_let_light_through() {
+    let settings = RenderSettings::default().with_seed(1);
+    let n = 40_000;
+    let open = floor_light(&window_scene(None), settings, n).g();
+    let window = window_scene(Some(Arc::new(ThinDielectric::new(1.5))));
+    let clear = floor_light(&window, settings, n);
+    // Only the pane's reflection, about 8 percent, is lost on the way down; a little of the
+    // floor's own light comes back off it.
+    assert!(
+        clear.g() > 0.85 * open && clear.g() < open,
+        "{clear:?} vs {open}"
+    );
+    // Shadow rays see through it as bounces do.
+    let brute = floor_light(&window, settings.with_shadow_samples(0), n);
+    assert!(
+        (clear.g() - brute.g()).abs() < 0.05 * brute.g(),
+        "{clear:?} vs brute force {brute:?}"
+    );
+
+    // Tinted glazing colors the light through it, shadow rays included.
+    let tint = Color::new(0.9, 0.6, 0.3);
+    let tinted = window_scene(Some(Arc::new(ThinDielectric::new(1.5).with_tint(tint))));
+    let mis = floor_light(&tinted, settings, n);
+    let brute = floor_light(&tinted, settings.with_shadow_samples(0), n);
+    let channels = |c: Color| [c.r(), c.g(), c.b()];
+    for c in 0..3 {
+        let expected = channels(tint)[c] * channels(clear)[c];
+        assert!(
+            (channels(mis)[c] - expected).abs() < 0.05 * expected,
+            "{mis:?} vs {expected}"
+        );
+        assert!(
+            (channels(brute)[c] - expected).abs() < 0.05 * expected,
+            "{brute:?} vs {expected}"
+        );
+    }
+}
+
+#[test]
+fn thin_glass_round_trips_through_desc() {
+    let desc: MaterialDesc =
+        serde_json::from_str(r#"{"type": "thin_dielectric", "ior": 1.33}"#).unwrap();
+    let clear = MaterialDesc::ThinDielectric {
+        ior: 1.33,
+        tint: Color::new(1.0, 1.0, 1.0),
+    };
+    assert_eq!(desc, clear);
+    assert_eq!(desc.build().to_desc(), Some(desc));
+    assert!(!serde_json::to_string(&clear).unwrap().contains("tint"));
+
+    let json = r#"{"type": "thin_dielectric", "ior": 1.5, "tint": [0.5, 0.8, 0.6]}"#;
+    let tinted: MaterialDesc = serde_json::from_str(json).unwrap();
+    assert!(matches!(tinted, MaterialDesc::ThinDielectric { tint, .. } if tint.r() == 0.5));
+    assert_eq!(tinted.build().to_desc(), Some(tinted));
+}
@@ -0,0 +1,85 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::camera::Camera;
+use rtt::hittable::{Hittable, Sphere};
+use rtt::interval::Interval;
+use rtt::material::{Dielectric, Lambertian, Material, Metal};
+use rtt::ray::Ray;
+use rtt::vec3::{Color, Point3, Vec3};
+use std::sync::Arc;
+
+fn camera() -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        60.0,
+        1.0,
+        0.0,
+        1.0,
+    )
+}
+
+#[test]
+fn footprint_grows_with_distance() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let camera = camera();
+    let ray = camera
+        .get_ray_differential(0.5, 0.5, 1.0 / 100.0, 1.0 / 100.0, &mut rng)
+        .unwrap();
+    assert_eq!(
+        ray.direction(),
+        camera.get_ray(0.5, 0.5, &mut rng).unwrap().direction()
+    );
+    let normal = Vec3::new(0.0, 0.0, 1.0);
+    let near = ray.footprint(1.0, normal).unwrap();
+    let far = ray.footprint(10.0, normal).unwrap();
+    // A 60 degree view one unit away is about 1.15 wide, so a pixel is about 0.0115.
+    assert!(
+        (near - 2.0 * (30f64).to_radians().tan() / 100.0).abs() < 1e-3,
+        "{near}"
+    );
+    assert!((far / near - 10.0).abs() < 1e-6);
+    assert!(Ray::new(ray.origin(), ray.direction())
+        .footprint(1.0, normal)
+        .is_none());
+}
+
+#[test]
+fn specular_bounces_keep_differentials() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let ray = camera()
+        .get_ray_differential(0.5, 0.5, 0.01, 0.01, &mut rng)
+        .unwrap();
+    let sphere = Sphere::new(
+        Point3::new(0.0, 0.0, -3.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    );
+    let rec = sphere
+        .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+        .unwrap();
+    let width = ray.footprint(rec.t, rec.normal).unwrap();
+
+    let mirror = Metal::new(Color::new(0.9, 0.9, 0.9), 0.0);
+    let (_, reflected) = mirror.scatter(&ray, &rec, &mut rng).unwrap();
+    let diff = reflected.differential().unwrap();
+    // The neighbouring rays leave from one pixel's width away on the surface.
+    assert!(((diff.rx_origin - rec.point).length() - width).abs() < 1e-9);
+    // A convex mirror spreads them, so the footprint widens along the reflected ray.
+    assert!(reflected.footprint(1.0, reflected.direction()).unwrap() > width);
+
+    let glass = Dielectric::new(1.5);
+    for _ in 0..16 {
+        let (_, scattered) = glass.scatter(&ray, &rec, &mut rng).unwrap();
+        assert!(scattered.differential().is_some());
+    }
+
+    let diffuse = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+    let (_, bounced) = diffuse.scatter(&ray, &rec, &mut rng).unwrap();
+    assert!(bounced.differential().is_none());
+    let fuzzy = Metal::new(Color::new(0.9, 0.9, 0.9), 0.3);
+    if let Some((_, fuzzed)) = fuzzy.scatter(&ray, &rec, &mut rng) {
+        assert!(fuzzed.differential().is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use rtt::film::Film;
+use rtt::vec3::Color;
+use tiff::decoder::{Decoder, DecodingResult};
+
+// A 3 x 2 film with one sample per pixel, brighter than white on the right.
+fn film() -> Film {
+    let mut film = Film::new(3, 2);
+    for y in 0..2 {
+        for x in 0..3 {
+            let c = Color::new(x as f64, 0.25 * y as f64, 0.5);
+            film.add_sample(x, y, c, 1.0).unwrap();
+        }
+    }
+    film
+}
+
+fn scratch(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("rtt-dng-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn float_tiffs_keep_values_above_one() {
+    let film = film();
+    let path = scratch("linear.tif");
+    film.save(&path, 1.0).unwrap();
+    let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+    assert_eq!(decoder.dimensions().unwrap(), (3, 2));
+    let DecodingResult::F32(samples) = decoder.read_image().unwrap() else {
+        panic!("not float samples");
+    };
+    let expected: Vec<f32> = film.develop_linear(1.0).unwrap().into_raw();
+    assert_eq!(samples, expected);
+    assert_eq!(samples[4 * 2], 2.0);
+    std::fs::remove_file(path).unwrap();
+}
+
+// Tag -> (type, count, value bytes) of a little-endian TIFF's first directory. Written by
+// hand since `tiff`'s decoder rejects DNG's LinearRaw photometric.
+fn directory(bytes: &[u8]) -> HashMap<u16, (u16, usize, Vec<u8>)> {
+    assert_eq!(&bytes[..4], b"II*\0");
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    let ifd = u32_at(4) as usize;
+    let mut tags = HashMap::new();
+    for i in 0..u16_at(ifd) as usize {
+        let entry = ifd + 2 + 12 * i;
+        let (tag, kind, count) = (u16_at(entry), u16_at(entry + 2), u32_at(entry + 4) as usize);
+        let size = match kind {
+            1 | 2 | 7 => 1,
+            3 => 2,
+            4 | 9 | 11 => 4,
+            _ => 8,
+        } * count;
+        let at = if size <= 4 {
+            entry + 8
+        } else {
+            u32_at(entry + 8) as usize
+        };
+        tags.insert(tag, (kind, count, bytes[at..at + size].to_vec()));
+    }
+    tags
+}
+
+fn u32s(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn dngs_are_linear_raw_with_a_color_matrix() {
+    let film = film();
+    let path = scratch("linear.dng");
+    film.save(&path, 1.0).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let tags = directory(&bytes);
+    let tag = |t: u16| &tags.get(&t).unwrap_or_else(|| panic!("no tag {t}")).2;
+
+    // PhotometricInterpretation: LinearRaw.
+    assert_eq!(tag(262)[..2], 34892u16.to_le_bytes());
+    // DNGVersion 1.4, for float samples.
+    assert_eq!(tag(50706)[..], [1, 4, 0, 0]);
+    // ColorMatrix1: nine signed rationals, XYZ's Y mostly into green.
+    let (kind, count, matrix) = &tags[&50721];
+    assert_eq!((*kind, *count), (10, 9));
+    let matrix = u32s(matrix);
+    assert_eq!((matrix[8] as i32, matrix[9]), (18758, 10000));
+    // AsShotNeutral: white is equal RGB.
+    assert_eq!(u32s(tag(50728)), [1, 1, 1, 1, 1, 1]);
+
+    let expected: Vec<f32> = film
+        .develop_linear(1.0)
+        .unwrap()
+        .pixels()
+        .flat_map(|px| [px.0[0], px.0[1], px.0[2]])
+        .collect();
+    let (offsets, counts) = (u32s(tag(273)), u32s(tag(279)));
+    let samples: Vec<f32> = offsets
+        .iter()
+        .zip(&counts)
+        .flat_map(|(&at, &n)| bytes[at as usize..(at + n) as usize].chunks(4))
+        .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(samples, expected);
+}
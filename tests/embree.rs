@@ -0,0 +1,97 @@
+#![cfg(feature = "embree")]
+
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::embree::EmbreeMesh;
+use rtt::hittable::Hittable;
+use rtt::interval::Interval;
+use rtt::material::Lambertian;
+use rtt::mesh::Mesh;
+use rtt::ray::Ray;
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A unit cube centred on the origin.
+fn cube() -> Mesh {
+    let positions = (0..8)
+        .map(|i| {
+            Point3::new(
+                (i & 1) as f64 - 0.5,
+                ((i >> 1) & 1) as f64 - 0.5,
+                ((i >> 2) & 1) as f64 - 0.5,
+            )
+        })
+        .collect();
+    let indices = vec![
+        [0, 2, 1],
+        [1, 2, 3],
+        [4, 5, 6],
+        [5, 7, 6],
+        [0, 1, 4],
+        [1, 5, 4],
+        [2, 6, 3],
+        [3, 6, 7],
+        [0, 4, 2],
+        [2, 4, 6],
+        [1, 3, 5],
+        [3, 7, 5],
+    ];
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    Mesh::new(positions, indices, material).unwrap()
+}
+
+#[test]
+fn embree_hits_match_the_rust_mesh() {
+    let reference = cube();
+    let embree = EmbreeMesh::new(cube()).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut hits = 0;
+    for _ in 0..1000 {
+        let origin = Point3::new(
+            rng.random_range(-2.0..2.0),
+            rng.random_range(-2.0..2.0),
+            3.0,
+        );
+        let target = Point3::new(
+            rng.random_range(-0.7..0.7),
+            rng.random_range(-0.7..0.7),
+            0.0,
+        );
+        let ray = Ray::new(origin, target - origin);
+        let ray_t = Interval::new(1e-3, f64::INFINITY);
+        match (reference.hit(&ray, ray_t), embree.hit(&ray, ray_t)) {
+            (Some(a), Some(b)) => {
+                hits += 1;
+                assert!((a.t - b.t).abs() < 1e-9, "{} vs {}", a.t, b.t);
+                assert!((a.normal - b.normal).length() < 1e-9);
+                assert_eq!(a.front_face, b.front_face);
+            }
+            (None, None) => {}
+            // Rays grazing an edge may go either way in single precision.
+            (a, b) => assert!(
+                a.or(b).is_some_and(|h| {
+                    let p = h.point;
+                    [p.x, p.y, p.z].iter().filter(|c| c.abs() > 0.4999).count() >= 2
+                }),
+                "only one backend hit"
+            ),
+        }
+    }
+    assert!(hits > 100);
+    assert_eq!(
+        embree.bounding_box(0.0, 1.0),
+        reference.bounding_box(0.0, 1.0)
+    );
+}
+
+#[test]
+fn embree_mesh_without_triangles_hits_nothing() {
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mesh = Mesh::new(Vec::new(), Vec::new(), material).unwrap();
+    let embree = EmbreeMesh::new(mesh).unwrap();
+    let ray = Ray::new(Point3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(embree
+        .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+        .is_none());
+}
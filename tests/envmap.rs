@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use rtt::background::Constant;
+use rtt::envmap::{CubeFace, Cubemap};
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::DiffuseLight;
+use rtt::render::RenderSettings;
+use rtt::vec3::{Color, Point3};
+
+const SIZE: u32 = 16;
+
+// A glowing ball 5 units out along each axis, each its own color, against black.
+fn beacons() -> (HittableList, [(CubeFace, Color); 6]) {
+    let beacons = [
+        (CubeFace::PosX, Color::new(1.0, 0.0, 0.0)),
+        (CubeFace::NegX, Color::new(0.0, 1.0, 0.0)),
+        (CubeFace::PosY, Color::new(0.0, 0.0, 1.0)),
+        (CubeFace::NegY, Color::new(1.0, 1.0, 0.0)),
+        (CubeFace::PosZ, Color::new(0.0, 1.0, 1.0)),
+        (CubeFace::NegZ, Color::new(4.0, 0.0, 4.0)),
+    ];
+    let mut world = HittableList::new();
+    for (face, color) in beacons {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0) + 5.0 * face.basis().0,
+            1.0,
+            Arc::new(DiffuseLight::new(color)),
+        )));
+    }
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    (world, beacons)
+}
+
+fn cubemap(world: &HittableList) -> Cubemap {
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    Cubemap::render(world, origin, SIZE, 4, &RenderSettings::default()).unwrap()
+}
+
+fn color(film: &Film, x: u32, y: u32) -> Color {
+    film.pixel(x, y).unwrap().resolve(1.0)
+}
+
+fn assert_close(a: Color, b: Color) {
+    assert!((a - b).length() < 1e-6, "{a:?} vs {b:?}");
+}
+
+#[test]
+fn each_face_looks_along_its_axis() {
+    let (world, beacons) = beacons();
+    let cubemap = cubemap(&world);
+    for (face, expected) in beacons {
+        let film = cubemap.face(face);
+        assert_eq!((film.width(), film.height()), (SIZE, SIZE));
+        assert_close(color(film, SIZE / 2, SIZE / 2), expected);
+        assert_close(color(film, 0, 0), Color::default());
+    }
+}
+
+#[test]
+fn cross_folds_into_the_cube() {
+    let (world, beacons) = beacons();
+    let cross = cubemap(&world).cross().unwrap();
+    assert_eq!((cross.width(), cross.height()), (4 * SIZE, 3 * SIZE));
+    let mid = SIZE / 2;
+    let cells = [
+        (CubeFace::NegX, 0, 1),
+        (CubeFace::NegZ, 1, 1),
+        (CubeFace::PosX, 2, 1),
+        (CubeFace::PosZ, 3, 1),
+        (CubeFace::PosY, 1, 0),
+        (CubeFace::NegY, 1, 2),
+    ];
+    for (face, col, row) in cells {
+        let (_, expected) = beacons.iter().find(|(f, _)| *f == face).unwrap();
+        assert_close(color(&cross, col * SIZE + mid, row * SIZE + mid), *expected);
+    }
+    assert_eq!(cross.pixel(0, 0).unwrap().alpha(), 0.0);
+
+    // A ball on the edge between the top and the front shows on both sides of the seam.
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 5.0, -5.0),
+        1.0,
+        Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    let cross = cubemap(&world).cross().unwrap();
+    let x = SIZE + mid;
+    assert_close(color(&cross, x, SIZE - 1), Color::new(1.0, 1.0, 1.0));
+    assert_close(color(&cross, x, SIZE), Color::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn equirect_maps_like_hdri() {
+    let (world, beacons) = beacons();
+    let pano = cubemap(&world).equirect(4 * SIZE).unwrap();
+    let (w, h) = (pano.width(), pano.height());
+    assert_eq!((w, h), (4 * SIZE, 2 * SIZE));
+    let colors: Vec<Color> = beacons.iter().map(|&(_, c)| c).collect();
+    // The center looks down -z, a quarter turn right is +x.
+    assert_close(color(&pano, w / 2, h / 2), colors[5]);
+    assert_close(color(&pano, 3 * w / 4, h / 2), colors[0]);
+    assert_close(color(&pano, w / 4, h / 2), colors[1]);
+    assert_close(color(&pano, 0, h / 2), colors[4]);
+    assert_close(color(&pano, w / 2, 0), colors[2]);
+    assert_close(color(&pano, w / 2, h - 1), colors[3]);
+}
+
+#[test]
+fn float_formats_keep_the_full_range() {
+    let dir = std::env::temp_dir().join(format!("rtt-envmap-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut film = Film::new(2, 1);
+    film.add_sample(0, 0, Color::new(8.0, 0.5, 0.0), 1.0)
+        .unwrap();
+    film.add_sample(1, 0, Color::new(0.0, 0.0, 2.0), 1.0)
+        .unwrap();
+    for name in ["sky.exr", "sky.hdr"] {
+        let path = dir.join(name);
+        film.save(&path, 1.0).unwrap();
+        let img = image::open(&path).unwrap().to_rgb32f();
+        let [r, g, _] = img.get_pixel(0, 0).0;
+        assert!(
+            (r - 8.0).abs() < 0.1 && (g - 0.5).abs() < 0.01,
+            "{name}: {r} {g}"
+        );
+        assert!((img.get_pixel(1, 0).0[2] - 2.0).abs() < 0.02, "{name}");
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
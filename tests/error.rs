@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rtt::aov::{Aov, AovSet};
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::vec3::{Color, Point3};
+use rtt::Error;
+
+// Somewhere no file can be written: inside a directory that doesn't exist.
+fn unwritable(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("rtt-missing-{}", std::process::id()))
+        .join(name)
+}
+
+fn assert_names(result: rtt::Result<()>, path: &Path) {
+    let err = result.unwrap_err();
+    assert!(
+        err.to_string().contains(&path.display().to_string()),
+        "{err}"
+    );
+}
+
+#[test]
+fn film_writes_fail_with_the_path() {
+    let mut film = Film::new(4, 2);
+    film.add_sample(1, 1, Color::new(0.5, 0.5, 0.5), 1.0)
+        .unwrap();
+    for name in ["out.png", "out.exr", "out.hdr", "out.tif", "out.dng"] {
+        let path = unwritable(name);
+        let result = film.save(&path, 1.0);
+        // TIFF and DNG go through their own encoder.
+        if name.ends_with(".tif") || name.ends_with(".dng") {
+            assert!(matches!(result, Err(Error::Tiff { .. })), "{result:?}");
+        } else {
+            assert!(matches!(result, Err(Error::Image { .. })), "{result:?}");
+        }
+        assert_names(result, &path);
+    }
+}
+
+#[test]
+fn aov_writes_fail_with_the_path() {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let film = Film::new(4, 2);
+    let aovs = AovSet::new(&[Aov::Depth, Aov::MaterialId], &world, 4, 2);
+
+    let dir = unwritable("aovs");
+    let result = aovs.save(&film, &dir);
+    assert!(matches!(result, Err(Error::Image { .. })), "{result:?}");
+    assert_names(result, &dir);
+
+    let path = unwritable("layers.exr");
+    let result = aovs.save_multilayer(&film, 1.0, &path);
+    assert!(matches!(result, Err(Error::Exr { .. })), "{result:?}");
+    assert_names(result, &path);
+}
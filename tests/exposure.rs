@@ -0,0 +1,164 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use rtt::color::luminance;
+use rtt::exposure::{LightPower, PhysicalExposure, LUMENS_PER_WATT};
+use rtt::film::Film;
+use rtt::scene::{ObjectDesc, SceneDesc};
+use rtt::vec3::Color;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+// A 2x1 unit panel of `power` above the origin, facing down.
+fn panel_scene(power: &str, meters_per_unit: Option<f64>) -> SceneDesc {
+    let units = match meters_per_unit {
+        Some(m) => format!(r#", "meters_per_unit": {m}"#),
+        None => String::new(),
+    };
+    let json = format!(
+        r#"{{
+        "camera": {{
+            "look_from": [0, 0, 5], "look_at": [0, 0, 0], "vup": [0, 1, 0],
+            "vfov": 40, "focus_dist": 5
+        }},
+        "materials": [],
+        "objects": [{{
+            "type": "quad_light",
+            "corner": [-1, 2, -0.5], "u": [2, 0, 0], "v": [0, 0, 1],
+            "emit": [1, 0.5, 0.25], "power": {power}
+        }}]{units}
+    }}"#
+    );
+    SceneDesc::from_json(&json).unwrap()
+}
+
+fn emitted(desc: &SceneDesc) -> Color {
+    match &desc.objects_in_units().unwrap()[0] {
+        ObjectDesc::QuadLight { emit, power, .. } => {
+            assert!(power.is_none());
+            *emit
+        }
+        other => panic!("not a light: {other:?}"),
+    }
+}
+
+#[test]
+fn exposure_values_follow_the_exposure_triangle() {
+    let base = PhysicalExposure::new(100.0, 1.0, 1.0).unwrap();
+    assert!(close(base.ev100(), 0.0));
+    assert!(close(base.scale(), 1.0 / 1.2));
+    // Sunny 16: ISO 100, 1/100 s at f/16 is about EV 15.
+    let sunny = PhysicalExposure::new(100.0, 0.01, 16.0).unwrap();
+    assert!((sunny.ev100() - 14.64).abs() < 0.01, "{}", sunny.ev100());
+    // Each of a stop more ISO, twice the time or a stop wider lets in twice the light.
+    for brighter in [
+        PhysicalExposure::new(200.0, 0.01, 16.0).unwrap(),
+        PhysicalExposure::new(100.0, 0.02, 16.0).unwrap(),
+        PhysicalExposure::new(100.0, 0.01, 16.0 / 2f64.sqrt()).unwrap(),
+    ] {
+        assert!(close(brighter.ev100(), sunny.ev100() - 1.0));
+        assert!(close(brighter.scale(), 2.0 * sunny.scale()));
+    }
+}
+
+#[test]
+fn exposure_rejects_non_positive_settings() {
+    assert!(PhysicalExposure::new(0.0, 0.01, 8.0).is_err());
+    assert!(PhysicalExposure::new(100.0, -1.0, 8.0).is_err());
+    assert!(PhysicalExposure::new(100.0, 0.01, f64::NAN).is_err());
+    let bad = PhysicalExposure {
+        iso: 100.0,
+        shutter: 0.0,
+        f_number: 8.0,
+    };
+    assert!(bad.apply(&Film::new(1, 1), 1.0).is_err());
+}
+
+#[test]
+fn applying_exposure_scales_the_film() {
+    let mut film = Film::new(2, 1);
+    film.add_sample(0, 0, Color::new(120.0, 60.0, 12.0), 1.0)
+        .unwrap();
+    let exposure = PhysicalExposure::new(100.0, 1.0, 10.0).unwrap();
+    let exposed = exposure.apply(&film, 1.0).unwrap().resolve(1.0).unwrap();
+    let scale = exposure.scale();
+    assert!(close(exposed[0].r(), 120.0 * scale));
+    assert!(close(exposed[0].b(), 12.0 * scale));
+    assert_eq!(exposed[1], Color::default());
+    // Coverage is kept.
+    let pixel = exposure.apply(&film, 1.0).unwrap().pixel(1, 0).unwrap();
+    assert_eq!(pixel.alpha(), 0.0);
+}
+
+#[test]
+fn light_power_sets_luminance_by_area() {
+    let color = Color::new(1.0, 0.5, 0.25);
+    let l = LightPower::Lumens(800.0).radiance(color, 2.0, false);
+    assert!(close(luminance(l), 800.0 / (PI * 2.0)));
+    // The color's hue is kept.
+    assert!(close(l.g() / l.r(), 0.5));
+    // Two sides share the power.
+    let both = LightPower::Lumens(800.0).radiance(color, 2.0, true);
+    assert!(close(luminance(both), luminance(l) / 2.0));
+    let watts = LightPower::Watts(1.0).radiance(color, 2.0, false);
+    assert!(close(luminance(watts), LUMENS_PER_WATT / (PI * 2.0)));
+    // Black or empty lights give nothing.
+    assert_eq!(
+        LightPower::Lumens(800.0).radiance(Color::default(), 2.0, false),
+        Color::default()
+    );
+    assert_eq!(
+        LightPower::Lumens(800.0).radiance(color, 0.0, false),
+        Color::default()
+    );
+}
+
+#[test]
+fn scene_units_scale_light_area() {
+    let meters = emitted(&panel_scene(r#"{"lumens": 800}"#, None));
+    assert!(close(luminance(meters), 800.0 / (PI * 2.0)));
+    // The same panel in centimeters is 10^4 times smaller, so as much brighter.
+    let centimeters = emitted(&panel_scene(r#"{"lumens": 800}"#, Some(0.01)));
+    assert!(close(luminance(centimeters), 1e4 * luminance(meters)));
+    let watts = emitted(&panel_scene(r#"{"watts": 2}"#, None));
+    assert!(close(luminance(watts), 2.0 * LUMENS_PER_WATT / (PI * 2.0)));
+    assert!(panel_scene(r#"{"lumens": 800}"#, Some(0.0))
+        .objects_in_units()
+        .is_err());
+}
+
+#[test]
+fn scenes_without_power_are_left_alone() {
+    let mut desc = panel_scene("null", Some(0.01));
+    assert!(matches!(desc.objects_in_units().unwrap(), Cow::Borrowed(_)));
+    assert_eq!(emitted(&desc), Color::new(1.0, 0.5, 0.25));
+    // Power nested inside other objects is found too.
+    desc = panel_scene(r#"{"lumens": 800}"#, None);
+    let light = desc.objects.remove(0);
+    desc.objects.push(ObjectDesc::List {
+        objects: vec![light],
+    });
+    match &desc.objects_in_units().unwrap()[0] {
+        ObjectDesc::List { objects } => {
+            assert!(matches!(
+                objects[0],
+                ObjectDesc::QuadLight { power: None, .. }
+            ));
+        }
+        other => panic!("not a list: {other:?}"),
+    }
+}
+
+#[test]
+fn power_and_exposure_round_trip() {
+    let mut desc = panel_scene(r#"{"watts": 2}"#, Some(0.01));
+    desc.camera.exposure = Some(PhysicalExposure::new(400.0, 1.0 / 60.0, 2.8).unwrap());
+    let json = desc.to_json().unwrap();
+    assert!(json.contains("\"watts\": 2.0") && json.contains("\"f_number\": 2.8"));
+    assert_eq!(SceneDesc::from_json(&json).unwrap(), desc);
+    // Built scenes use the radiance.
+    let (world, _camera) = desc.build(1.0).unwrap();
+    assert_eq!(world.len(), 1);
+}
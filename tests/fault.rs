@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HitRecord, HittableList, Sphere};
+use rtt::material::Material;
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, RenderSettings, FAULT_COLOR};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Glows NaN.
+struct Broken;
+
+impl Material for Broken {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _rec: &HitRecord,
+        _rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new(f64::NAN, 0.0, 0.0)
+    }
+}
+
+struct Panicking;
+
+impl Material for Panicking {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _rec: &HitRecord,
+        _rng: &mut dyn rand::RngCore,
+    ) -> Option<(Vec3, Ray)> {
+        panic!("shader bug");
+    }
+}
+
+const SIZE: u32 = 8;
+const SKY: Color = Color::new(0.2, 0.3, 0.4);
+
+// `material` on a ball filling the middle of the frame, against a plain sky.
+fn render(material: Arc<dyn Material>) -> Film {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        0.5,
+        material,
+    )));
+    world.set_background(Arc::new(Constant::new(SKY)));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[], &world, SIZE, SIZE);
+    let settings = RenderSettings::default();
+    render_image_with(&world, &camera, &film, &aovs, 4, &settings, &|_| {}).unwrap();
+    film
+}
+
+#[test]
+fn nan_samples_are_dropped() {
+    let film = render(Arc::new(Broken));
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            assert!(film.pixel(x, y).unwrap().is_finite(), "({x}, {y})");
+        }
+    }
+    assert_eq!(film.pixel(4, 4).unwrap().weight_sum, 0.0);
+    assert_eq!(film.pixel(0, 0).unwrap().weight_sum, 4.0);
+    assert_eq!(film.pixel(0, 0).unwrap().resolve(1.0), SKY);
+}
+
+#[test]
+fn panicking_rows_get_the_fault_color() {
+    let film = render(Arc::new(Panicking));
+    // The ball's rows, all the way across.
+    for x in 0..SIZE {
+        let pixel = film.pixel(x, 4).unwrap();
+        assert_eq!(pixel.resolve(1.0), FAULT_COLOR);
+        assert_eq!(pixel.weight_sum, 4.0);
+    }
+    // Rows that only see sky render as usual.
+    assert_eq!(film.pixel(4, 0).unwrap().resolve(1.0), SKY);
+}
@@ -0,0 +1,109 @@
+use rtt::film::Film;
+use rtt::vec3::Color;
+use rtt::Error;
+
+const RED: Color = Color::new(1.0, 0.0, 0.0);
+const BLUE: Color = Color::new(0.0, 0.0, 1.0);
+
+#[test]
+fn samples_accumulate_as_weighted_means() {
+    let mut film = Film::new(3, 2);
+    film.add_sample(2, 1, RED, 1.0).unwrap();
+    film.add_sample(2, 1, BLUE, 3.0).unwrap();
+    film.add_sample_alpha(0, 0, RED, 0.0, 1.0).unwrap();
+    let pixel = film.pixel(2, 1).unwrap();
+    assert_eq!(pixel.weight_sum, 4.0);
+    assert_eq!(pixel.resolve(1.0), Color::new(0.25, 0.0, 0.75));
+    assert_eq!(pixel.alpha(), 1.0);
+    assert_eq!(film.pixel(0, 0).unwrap().alpha(), 0.0);
+    // Untouched pixels stay empty and transparent.
+    let empty = film.pixel(1, 0).unwrap();
+    assert_eq!(empty.weight_sum, 0.0);
+    assert_eq!(empty.resolve(1.0), Color::default());
+
+    let colors = film.resolve(1.0).unwrap();
+    assert_eq!(colors.len(), 6);
+    assert_eq!(colors[5], Color::new(0.25, 0.0, 0.75));
+}
+
+#[test]
+fn tiles_merge_into_their_own_pixels() {
+    let film = Film::new(5, 4);
+    // Clipped to the film.
+    let mut tile = film.tile(3, 2, 8, 8);
+    assert_eq!(tile.bounds(), (3, 2, 5, 4));
+    tile.add_sample(3, 2, RED, 1.0);
+    tile.add_sample(4, 3, BLUE, 2.0);
+    film.merge_tile(tile).unwrap();
+    // Merging twice adds the weights.
+    let mut again = film.tile(4, 3, 5, 4);
+    again.add_sample(4, 3, RED, 2.0);
+    film.merge_tile(again).unwrap();
+
+    assert_eq!(film.pixel(3, 2).unwrap().resolve(1.0), RED);
+    let corner = film.pixel(4, 3).unwrap();
+    assert_eq!(corner.weight_sum, 4.0);
+    assert_eq!(corner.resolve(1.0), Color::new(0.5, 0.0, 0.5));
+    for (x, y) in [(0, 0), (2, 2), (3, 3), (4, 2)] {
+        assert_eq!(film.pixel(x, y).unwrap().weight_sum, 0.0, "{x} {y}");
+    }
+}
+
+#[test]
+fn snapshots_are_independent_copies() {
+    let mut film = Film::new(2, 2);
+    film.add_sample(1, 0, RED, 1.0).unwrap();
+    let snapshot = film.snapshot().unwrap();
+    film.add_sample(1, 0, BLUE, 1.0).unwrap();
+    film.clear_pixel(0, 0).unwrap();
+
+    assert_eq!((snapshot.width(), snapshot.height()), (2, 2));
+    assert_eq!(snapshot.pixel(1, 0).unwrap().resolve(1.0), RED);
+    assert_eq!(
+        film.pixel(1, 0).unwrap().resolve(1.0),
+        Color::new(0.5, 0.0, 0.5)
+    );
+    film.clear().unwrap();
+    assert_eq!(film.pixel(1, 0).unwrap().weight_sum, 0.0);
+    assert_eq!(snapshot.pixel(1, 0).unwrap().weight_sum, 1.0);
+}
+
+// Rows start a whole width apart, however wide the film.
+#[test]
+fn wide_films_index_every_pixel() {
+    let (width, height) = (70_000, 3);
+    let mut film = Film::new(width, height);
+    film.add_sample(width - 1, height - 1, RED, 1.0).unwrap();
+    film.add_sample(0, 1, BLUE, 1.0).unwrap();
+    let colors = film.resolve(1.0).unwrap();
+    assert_eq!(colors.len(), width as usize * height as usize);
+    assert_eq!(colors[colors.len() - 1], RED);
+    assert_eq!(colors[width as usize], BLUE);
+}
+
+#[test]
+fn pixels_past_the_edge_are_errors() {
+    let mut film = Film::new(3, 2);
+    // Wrapping to the next row would land on (0, 1).
+    for (x, y) in [(3, 0), (0, 2), (u32::MAX, u32::MAX)] {
+        let err = film.add_sample(x, y, RED, 1.0).unwrap_err();
+        assert!(matches!(err, Error::OutOfBounds { .. }), "{err}");
+        assert!(film.add_sample_alpha(x, y, RED, 0.5, 1.0).is_err());
+        assert!(film.pixel(x, y).is_err());
+        assert!(film.clear_pixel(x, y).is_err());
+    }
+    assert_eq!(
+        film.pixel(3, 0).unwrap_err().to_string(),
+        "pixel (3, 0) is outside the 3x2 film"
+    );
+    assert!(film
+        .resolve(1.0)
+        .unwrap()
+        .iter()
+        .all(|&c| c == Color::default()));
+
+    // Tiles starting past the edge come out empty.
+    let tile = film.tile(5, 4, 8, 8);
+    assert_eq!(tile.bounds(), (3, 2, 3, 2));
+    film.merge_tile(tile).unwrap();
+}
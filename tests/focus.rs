@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use image::Rgb;
+use rtt::camera::Camera;
+use rtt::focus::{focus_view, FAR_COLOR, IN_FOCUS_COLOR, NEAR_COLOR};
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::vec3::{Color, Point3, Vec3};
+
+const SIZE: u32 = 64;
+
+fn camera(aperture: f64) -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        aperture,
+        5.0,
+    )
+}
+
+#[test]
+fn limits_bracket_the_focus_distance() {
+    let limits = camera(0.5).focus_limits(SIZE, 1.0);
+    assert!(limits.near < 5.0 && limits.far > 5.0, "{limits:?}");
+    assert!(limits.contains(5.0) && !limits.contains(1.0) && !limits.contains(50.0));
+    // Wider apertures and sharper tolerances narrow them.
+    let wide = camera(1.0).focus_limits(SIZE, 1.0);
+    assert!(wide.near > limits.near && wide.far < limits.far);
+    let strict = camera(0.5).focus_limits(SIZE, 0.5);
+    assert!(strict.near > limits.near && strict.far < limits.far);
+    // A pinhole keeps everything sharp.
+    let pinhole = camera(0.0).focus_limits(SIZE, 1.0);
+    assert_eq!(pinhole.near, 0.0);
+    assert_eq!(pinhole.far, f64::INFINITY);
+}
+
+#[test]
+fn view_colors_by_depth_of_field() {
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mut world = HittableList::new();
+    let balls = [
+        (Point3::new(-0.4, 0.0, -2.0), NEAR_COLOR),
+        (Point3::new(0.0, 0.0, -5.0), IN_FOCUS_COLOR),
+        (Point3::new(3.0, 0.0, -20.0), FAR_COLOR),
+    ];
+    for (center, _) in balls {
+        world.add(Arc::new(Sphere::new(
+            center,
+            0.05 * -center.z,
+            grey.clone(),
+        )));
+    }
+    let camera = camera(0.5);
+    let (img, coverage) = focus_view(&world, &camera, SIZE, SIZE, 1.0);
+    for (center, zone) in balls {
+        // The point of each ball facing the camera, shaded at full strength.
+        let front = center + Vec3::unit_vector(-center) * (0.05 * -center.z);
+        let seen = camera.project(front).unwrap();
+        let x = (seen.s * SIZE as f64) as u32;
+        let y = ((1.0 - seen.t) * SIZE as f64) as u32;
+        let Rgb(pixel) = *img.get_pixel(x, y);
+        let Rgb(expected) = zone;
+        for (p, e) in pixel.iter().zip(expected) {
+            assert!(p.abs_diff(e) <= 8, "{center:?}: {pixel:?} vs {expected:?}");
+        }
+    }
+    // The sky is infinitely far.
+    assert!(coverage.far > coverage.near && coverage.near > 0.0 && coverage.in_focus > 0.0);
+    assert!((coverage.near + coverage.in_focus + coverage.far - 1.0).abs() < 1e-9);
+}
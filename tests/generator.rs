@@ -0,0 +1,78 @@
+use rtt::generator::{MaterialWeights, SceneGenerator};
+use rtt::scene::{CameraDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use rtt::vec3::Point3;
+
+fn describe(generator: &SceneGenerator) -> SceneDesc {
+    let camera = CameraDesc {
+        look_from: Point3::new(13.0, 2.0, 3.0),
+        look_at: Point3::new(0.0, 0.0, 0.0),
+        vfov: 20.0,
+        focus_dist: 10.0,
+        ..Default::default()
+    };
+    SceneDesc::from_world(&generator.generate(), camera).unwrap()
+}
+
+fn spheres(desc: &SceneDesc) -> Vec<(Point3, f64, usize)> {
+    desc.objects
+        .iter()
+        .map(|o| match o {
+            ObjectDesc::Sphere {
+                center,
+                radius,
+                material,
+            } => (*center, *radius, *material),
+            other => panic!("unexpected object {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn same_seed_same_scene() {
+    let a = describe(&SceneGenerator::new(42));
+    assert_eq!(a, describe(&SceneGenerator::new(42)));
+    assert_ne!(a, describe(&SceneGenerator::new(43)));
+}
+
+#[test]
+fn count_and_sizes_are_honoured() {
+    for count in [0, 25, 3000] {
+        let generator = SceneGenerator::new(7)
+            .with_count(count)
+            .with_radius(0.1, 0.4);
+        let desc = describe(&generator);
+        // Ground plus three feature spheres.
+        assert_eq!(desc.objects.len(), count + 4);
+
+        let small = &spheres(&desc)[1..=count];
+        for &(center, radius, _) in small {
+            assert!((0.1..=0.4).contains(&radius));
+            assert_eq!(center.y, radius);
+        }
+        for (i, &(a, ra, _)) in small.iter().enumerate() {
+            for &(b, rb, _) in &small[i + 1..] {
+                assert!((a - b).length() >= ra + rb, "spheres overlap");
+            }
+        }
+    }
+}
+
+#[test]
+fn weights_pick_materials() {
+    let generator = SceneGenerator::new(1)
+        .with_count(100)
+        .with_feature_spheres(false)
+        .with_weights(MaterialWeights {
+            diffuse: 0.0,
+            metal: 2.0,
+            glass: 0.0,
+        });
+    let desc = describe(&generator);
+    assert_eq!(desc.objects.len(), 101);
+    for (_, _, material) in &spheres(&desc)[1..] {
+        assert!(matches!(
+            desc.materials[*material],
+            MaterialDesc::Metal { .. }
+        ));
+    }
+}
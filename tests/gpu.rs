@@ -0,0 +1,120 @@
+#![cfg(feature = "gpu")]
+
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::gpu::GpuScene;
+use rtt::hittable::{Hittable, HittableList};
+use rtt::interval::Interval;
+use rtt::material::{DiffuseLight, Lambertian};
+use rtt::mesh::Mesh;
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Two triangles making a unit square in the z = 0 plane, moved along x by `offset`.
+fn square(offset: f64) -> Mesh {
+    let positions = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+        .map(|(x, y)| Point3::new(x + offset, y, 0.0))
+        .to_vec();
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    Mesh::new(positions, vec![[0, 1, 2], [1, 3, 2]], material).unwrap()
+}
+
+#[test]
+fn gpu_hits_match_the_rust_meshes() {
+    // Machines without a ray tracing GPU have nothing to compare.
+    let scene = match GpuScene::new(vec![square(0.0), square(2.0)]) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("skipping: {e}");
+            return;
+        }
+    };
+    let mut rng = StdRng::seed_from_u64(5);
+    let rays: Vec<(Ray, Interval)> = (0..4096)
+        .map(|_| {
+            let origin = Point3::new(
+                rng.random_range(-1.0..4.0),
+                rng.random_range(-1.0..2.0),
+                2.0,
+            );
+            let target = Point3::new(
+                rng.random_range(-0.5..3.5),
+                rng.random_range(-0.5..1.5),
+                0.0,
+            );
+            (
+                Ray::new(origin, target - origin),
+                Interval::new(1e-3, f64::INFINITY),
+            )
+        })
+        .collect();
+    let hits = scene.intersect(&rays).unwrap();
+    assert_eq!(hits.len(), rays.len());
+    let mut matched = 0;
+    for ((ray, ray_t), hit) in rays.iter().zip(&hits) {
+        let expected = scene
+            .meshes()
+            .iter()
+            .filter_map(|m| m.hit(ray, *ray_t))
+            .min_by(|a, b| a.t.total_cmp(&b.t));
+        if let (Some(a), Some(b)) = (&expected, hit) {
+            assert!((a.t - b.t).abs() < 1e-9);
+            matched += 1;
+        }
+    }
+    // Only rays grazing an edge may disagree.
+    let expected = rays
+        .iter()
+        .filter(|(r, t)| scene.meshes().iter().any(|m| m.hit(r, *t).is_some()))
+        .count();
+    assert!(matched + 10 >= expected, "{matched} of {expected}");
+}
+
+// A glowing unit square seen straight on, filling the middle of the view.
+#[test]
+fn renders_find_camera_hits_on_the_gpu() {
+    let glow = Color::new(0.5, 0.7, 0.9);
+    let positions = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+        .map(|(x, y)| Point3::new(x, y, -3.0))
+        .to_vec();
+    let light = Arc::new(DiffuseLight::new(glow));
+    let mesh = Mesh::new(positions, vec![[0, 1, 2], [1, 3, 2]], light).unwrap();
+    let scene = match GpuScene::new(vec![mesh]) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("skipping: {e}");
+            return;
+        }
+    };
+    let mut world = HittableList::new();
+    world.add(Arc::new(scene));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    assert!(world.prefers_batches());
+
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let size = 8;
+    let film = Film::new(size, size);
+    let aovs = AovSet::new(&[], &world, size, size);
+    let settings = RenderSettings::default().with_seed(1);
+    render_image_with(&world, &camera, &film, &aovs, 4, &settings, &|_| {}).unwrap();
+    // The square covers the middle half of the view, and nothing else glows.
+    for (x, y, expected) in [(3, 3, glow), (4, 5, glow), (0, 0, Color::default())] {
+        let got = film.pixel(x, y).unwrap().resolve(1.0);
+        assert!((got - expected).length() < 1e-6, "{x} {y}: {got:?}");
+    }
+}
@@ -0,0 +1,178 @@
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aabb::Aabb;
+use rtt::aov::AovSet;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::guiding::PathGuide;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::material::{DiffuseLight, Lambertian};
+use rtt::ray::Ray;
+use rtt::render::{lights, render_image_with, trace_path_guided, trace_path_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn unit_box() -> Aabb {
+    Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+}
+
+#[test]
+fn learned_distribution_favors_bright_directions() {
+    let mut guide = PathGuide::new(unit_box());
+    let p = Point3::new(0.2, 0.1, -0.3);
+    assert!(guide.distribution(p).is_none());
+
+    let bright = Vec3::new(0.3, 0.9, 0.1);
+    let white = Color::new(1.0, 1.0, 1.0);
+    for _ in 0..100 {
+        guide.learn(p, bright, 1.0, white, white);
+        guide.learn(p, -bright, 1.0, white, Color::default());
+    }
+    guide.refine();
+    let d = guide.distribution(p).unwrap();
+    assert!(d.pdf(bright) > 10.0 / (4.0 * PI));
+    assert!(d.pdf(-bright) > 0.0);
+
+    // Sampling agrees with the density: E[1 / pdf] over its samples is the sphere's area, and
+    // it integrates to one over uniform directions.
+    let mut rng = StdRng::seed_from_u64(4);
+    let n = 20_000;
+    let area = (0..n).map(|_| 1.0 / d.pdf(d.sample(&mut rng))).sum::<f64>() / n as f64;
+    assert!((area - 4.0 * PI).abs() < 0.05 * 4.0 * PI, "{area}");
+    let integral = (0..n)
+        .map(|_| {
+            let v = rtt::material::random_in_unit_sphere(&mut rng);
+            4.0 * PI * d.pdf(v)
+        })
+        .sum::<f64>()
+        / n as f64;
+    assert!((integral - 1.0).abs() < 0.05, "{integral}");
+}
+
+#[test]
+fn busy_regions_split() {
+    let mut guide = PathGuide::new(unit_box());
+    let white = Color::new(1.0, 1.0, 1.0);
+    for i in 0..5000 {
+        let x = i as f64 / 5000.0 - 0.5;
+        guide.learn(
+            Point3::new(x, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            white,
+            white,
+        );
+    }
+    guide.refine();
+    assert_eq!(guide.region_count(), 2);
+    // Both halves keep what the parent learned.
+    for x in [-0.5, 0.5] {
+        assert!(guide.distribution(Point3::new(x, 0.0, 0.0)).is_some());
+    }
+    // Points outside the bounds are never guided.
+    assert!(guide.distribution(Point3::new(3.0, 0.0, 0.0)).is_none());
+}
+
+// Grey ground lit only by a small, bright emissive sphere that shadow rays don't know about.
+fn small_lamp() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.0, 2.0, 0.0),
+        0.25,
+        Arc::new(DiffuseLight::new(Color::new(50.0, 50.0, 50.0))),
+    )));
+    world
+}
+
+#[test]
+fn guided_paths_agree_and_converge_faster() {
+    let world = small_lamp();
+    let lights = lights(&world);
+    let settings = RenderSettings::default();
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let mut rng = StdRng::seed_from_u64(8);
+
+    // Only the lamp's light, not the sky's.
+    let lamp = |sample: rtt::render::PathSample| -> f64 {
+        sample.emission.iter().map(|(_, c)| c.r()).sum()
+    };
+    let stats = |values: &[f64]| {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        (mean, var)
+    };
+
+    let n = 100_000;
+    let plain: Vec<f64> = (0..n)
+        .map(|_| {
+            lamp(trace_path_with(
+                ray, &world, &lights, ray_t, &settings, &mut rng,
+            ))
+        })
+        .collect();
+
+    let mut guide = PathGuide::for_world(&world).unwrap();
+    for _ in 0..4 {
+        for _ in 0..20_000 {
+            trace_path_guided(ray, &world, &lights, &guide, ray_t, &settings, &mut rng);
+        }
+        guide.refine();
+    }
+    let guided: Vec<f64> = (0..n)
+        .map(|_| {
+            lamp(trace_path_guided(
+                ray, &world, &lights, &guide, ray_t, &settings, &mut rng,
+            ))
+        })
+        .collect();
+
+    // A sphere of radiance L subtending (r / d)^2 lights the ground with L pi (r / d)^2 cos.
+    let (d2, cos) = (5.0f64, 2.0 / 5.0f64.sqrt());
+    let exact = 0.5 / PI * 50.0 * PI * (0.25 * 0.25 / d2) * cos;
+    for (name, values) in [("plain", &plain), ("guided", &guided)] {
+        let (mean, var) = stats(values);
+        let error = (var / n as f64).sqrt();
+        assert!(
+            (mean - exact).abs() < 4.0 * error,
+            "{name}: {mean} vs {exact} +- {error}"
+        );
+    }
+    assert!(stats(&guided).1 < 0.5 * stats(&plain).1);
+}
+
+#[test]
+fn guided_render_reports_progress_over_all_passes() {
+    let world = small_lamp();
+    let camera = Camera::new(
+        Point3::new(0.0, 1.0, 4.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        4.0,
+    );
+    let film = Film::new(4, 4);
+    let aovs = AovSet::new(&[], &world, 4, 4);
+    let settings = RenderSettings::default().with_guiding(true);
+    let (done, total) = (AtomicU32::new(0), AtomicU32::new(0));
+    render_image_with(&world, &camera, &film, &aovs, 10, &settings, &|p| {
+        done.fetch_max(p.done, Ordering::Relaxed);
+        total.store(p.total, Ordering::Relaxed);
+    })
+    .unwrap();
+    // 10 samples go in passes of 1, 2 and 7.
+    assert_eq!(total.load(Ordering::Relaxed), 12);
+    assert_eq!(done.load(Ordering::Relaxed), 12);
+    assert_eq!(film.pixel(2, 2).unwrap().weight_sum, 10.0);
+}
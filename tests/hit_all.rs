@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use rtt::hittable::{ClipPlane, Clipped, Hittable, HittableList, Holdout, Sphere};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::Lambertian;
+use rtt::mesh::Mesh;
+use rtt::paged::{self, GeometryCache, PagedMesh};
+use rtt::procgen::uv_sphere;
+use rtt::ray::Ray;
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn material() -> Arc<Lambertian> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+fn ts(hits: &[rtt::hittable::HitRecord]) -> Vec<f64> {
+    hits.iter().map(|h| h.t).collect()
+}
+
+const ALL: Interval = Interval {
+    min: 1e-3,
+    max: f64::INFINITY,
+};
+
+#[test]
+fn spheres_report_both_crossings() {
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material());
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let hits = sphere.hit_all(&ray, ALL);
+    assert_eq!(ts(&hits), [4.0, 6.0]);
+    assert!(hits[0].front_face && !hits[1].front_face);
+    assert_eq!(ts(&sphere.hit_all(&ray, Interval::new(5.0, 10.0))), [6.0]);
+
+    let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    assert_eq!(ts(&sphere.hit_all(&inside, ALL)), [1.0]);
+}
+
+#[test]
+fn lists_merge_and_sort_their_objects_hits() {
+    let mut world = HittableList::new();
+    world.add(Sphere::new(Point3::new(0.0, 0.0, -3.0), 1.0, material()));
+    world.add(Arc::new(Holdout::new(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        material(),
+    )))));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-1.0, -1.0, 3.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+    )));
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let hits = world.hit_all(&ray, ALL);
+    assert_eq!(ts(&hits), [2.0, 4.0, 6.0, 7.0, 9.0]);
+    let ids: Vec<u32> = hits.iter().map(|h| h.object_id).collect();
+    assert_eq!(ids, [2, 1, 1, 0, 0]);
+    let holdouts: Vec<bool> = hits.iter().map(|h| h.holdout).collect();
+    assert_eq!(holdouts, [false, true, true, false, false]);
+    assert_eq!(hits[0].t, world.hit(&ray, ALL).unwrap().t);
+}
+
+#[test]
+fn clipped_hits_are_dropped() {
+    let sphere = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material()));
+    let clipped = Clipped::new(
+        sphere,
+        vec![ClipPlane::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        )],
+    );
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert_eq!(ts(&clipped.hit_all(&ray, ALL)), [6.0]);
+}
+
+#[test]
+fn meshes_report_every_triangle_crossed() {
+    let (positions, indices) = uv_sphere(1.0, 16, 32);
+    let mesh = Mesh::new(positions.clone(), indices.clone(), material()).unwrap();
+    let path = std::env::temp_dir().join(format!("rtt-hit-all-{}.geom", std::process::id()));
+    paged::write(&positions, &indices, &path, 64).unwrap();
+    let paged =
+        PagedMesh::open(&path, material(), Arc::new(GeometryCache::new(usize::MAX))).unwrap();
+
+    let ray = Ray::new(Point3::new(0.1, 0.2, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let hits = mesh.hit_all(&ray, ALL);
+    assert_eq!(hits.len(), 2);
+    assert!((hits[0].t - 4.0).abs() < 0.05 && (hits[1].t - 6.0).abs() < 0.05);
+    assert_eq!(hits[0].t, mesh.hit(&ray, ALL).unwrap().t);
+    assert_eq!(ts(&paged.hit_all(&ray, ALL)), ts(&hits));
+    std::fs::remove_file(path).unwrap();
+}
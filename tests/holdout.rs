@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{Hittable, HittableList, Holdout, Sphere};
+use rtt::interval::Interval;
+use rtt::material::DiffuseLight;
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+const SIZE: u32 = 8;
+const WALL: Color = Color::new(0.2, 0.2, 0.2);
+
+fn lamp(center: Point3, radius: f64, color: Color) -> Arc<dyn Hittable> {
+    Arc::new(Sphere::new(
+        center,
+        radius,
+        Arc::new(DiffuseLight::new(color)),
+    ))
+}
+
+// A glowing holdout ball filling the middle of the view, hiding a brighter ball straight
+// behind it, in front of a glowing wall.
+fn world() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Holdout::new(lamp(
+        Point3::new(0.0, 0.0, 0.0),
+        0.5,
+        Color::new(1.0, 1.0, 1.0),
+    ))));
+    world.add(lamp(
+        Point3::new(0.0, 0.0, -2.0),
+        0.5,
+        Color::new(4.0, 4.0, 4.0),
+    ));
+    world.add(lamp(Point3::new(0.0, 0.0, -100.0), 90.0, WALL));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+#[test]
+fn holdouts_are_transparent_and_black() {
+    let world = world();
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    let film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[], &world, SIZE, SIZE);
+    let settings = RenderSettings::default().with_seed(6);
+    render_image_with(&world, &camera, &film, &aovs, 16, &settings, &|_| {}).unwrap();
+
+    // Neither the holdout's glow nor the ball it hides gets through.
+    for (x, y) in [(3, 3), (4, 4)] {
+        let pixel = film.pixel(x, y).unwrap();
+        assert_eq!(pixel.alpha(), 0.0, "{x} {y}");
+        assert_eq!(pixel.resolve(1.0), Color::default(), "{x} {y}");
+    }
+    // The wall around it stays opaque.
+    for (x, y) in [(0, 0), (7, 0), (0, 7), (7, 7)] {
+        let pixel = film.pixel(x, y).unwrap();
+        assert_eq!(pixel.alpha(), 1.0, "{x} {y}");
+        assert!((pixel.resolve(1.0) - WALL).length() < 1e-9, "{x} {y}");
+    }
+}
+
+#[test]
+fn holdouts_block_shadow_rays() {
+    let world = world();
+    let through = Ray::new(Point3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(world.is_occluded(&through, Interval::new(0.001, 4.0)));
+    let beside = Ray::new(Point3::new(2.0, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(!world.is_occluded(&beside, Interval::new(0.001, 4.0)));
+}
@@ -0,0 +1,109 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rtt::hittable::{HitRecord, Hittable, Sphere};
+use rtt::interval::Interval;
+use rtt::material::Lambertian;
+use rtt::ray::Ray;
+use rtt::vec3::{Color, Point3, Vec3};
+
+const ALBEDO: Color = Color::new(0.2, 0.4, 0.6);
+const SAMPLES: usize = 200_000;
+
+// A ray hitting a unit sphere of Lambertian head on, where its normal is tilted off every axis.
+fn ball() -> Sphere {
+    Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(ALBEDO)),
+    )
+}
+
+// Hits refer to the ball's material, so it must outlive them.
+fn hit(sphere: &Sphere) -> (Ray, HitRecord) {
+    let n = Vec3::unit_vector(Vec3::new(1.0, 2.0, 3.0));
+    let ray = Ray::new(Point3::default() + 3.0 * n, -n);
+    let rec = sphere
+        .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+        .unwrap();
+    (ray, rec)
+}
+
+#[test]
+fn scatter_samples_the_pdf_it_reports() {
+    let sphere = ball();
+    let (ray, rec) = hit(&sphere);
+    let mut rng = StdRng::seed_from_u64(11);
+    // Cosine-weighted directions put b^2 - a^2 of their samples at cosines in [a, b].
+    let bins = 10;
+    let mut counts = vec![0usize; bins];
+    for _ in 0..SAMPLES {
+        let (attenuation, scattered) = rec.material.get().scatter(&ray, &rec, &mut rng).unwrap();
+        assert_eq!(attenuation, ALBEDO);
+        let cos = Vec3::dot(rec.normal, Vec3::unit_vector(scattered.direction()));
+        assert!(cos >= 0.0, "{cos}");
+        let pdf = rec.material.get().scattering_pdf(&ray, &rec, &scattered);
+        assert!((pdf - cos / PI).abs() < 1e-12, "{pdf} vs {cos}");
+        // Shadow rays weigh lights as `scatter` weighs its own directions.
+        let value = rec
+            .material
+            .get()
+            .scattering(&ray, &rec, attenuation, &scattered);
+        assert!((value - attenuation * pdf).length() < 1e-12);
+        counts[((cos * bins as f64) as usize).min(bins - 1)] += 1;
+    }
+    for (i, &count) in counts.iter().enumerate() {
+        let (a, b) = (i as f64 / bins as f64, (i + 1) as f64 / bins as f64);
+        let expected = b * b - a * a;
+        let measured = count as f64 / SAMPLES as f64;
+        assert!(
+            (measured - expected).abs() < 0.05 * expected + 1e-3,
+            "bin {i}: {measured} vs {expected}"
+        );
+    }
+}
+
+#[test]
+fn sampled_directions_integrate_to_cosine_over_pi() {
+    let sphere = ball();
+    let (ray, rec) = hit(&sphere);
+    let mut rng = StdRng::seed_from_u64(12);
+
+    // The pdf covers the sphere of directions once, and nothing below the surface.
+    let mut total = 0.0;
+    for _ in 0..SAMPLES {
+        let z: f64 = rng.random_range(-1.0..1.0);
+        let phi = rng.random_range(0.0..2.0 * PI);
+        let r = (1.0 - z * z).sqrt();
+        let d = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+        let pdf = rec
+            .material
+            .get()
+            .scattering_pdf(&ray, &rec, &Ray::new(rec.point, d));
+        if Vec3::dot(d, rec.normal) <= 0.0 {
+            assert_eq!(pdf, 0.0);
+        }
+        total += 4.0 * PI * pdf;
+    }
+    let total = total / SAMPLES as f64;
+    assert!((total - 1.0).abs() < 0.01, "{total}");
+
+    // Against cos / pi, the mean cosine is 2/3, and the directions show no preferred azimuth.
+    let tangent = Vec3::unit_vector(Vec3::cross(rec.normal, Vec3::new(0.0, 0.0, 1.0)));
+    let (mut cos_sum, mut ahead) = (0.0, 0);
+    for _ in 0..SAMPLES {
+        let (_, scattered) = rec.material.get().scatter(&ray, &rec, &mut rng).unwrap();
+        let d = Vec3::unit_vector(scattered.direction());
+        cos_sum += Vec3::dot(d, rec.normal);
+        if Vec3::dot(d, tangent) > 0.0 {
+            ahead += 1;
+        }
+    }
+    let mean_cos = cos_sum / SAMPLES as f64;
+    assert!((mean_cos - 2.0 / 3.0).abs() < 0.005, "{mean_cos}");
+    let ahead = ahead as f64 / SAMPLES as f64;
+    assert!((ahead - 0.5).abs() < 0.01, "{ahead}");
+}
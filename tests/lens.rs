@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::{Camera, Projection};
+use rtt::error::Result;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::lens::{double_gauss_50mm, LensElement, RealisticCamera};
+use rtt::material::DiffuseLight;
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Full-frame film diagonal, in millimetres.
+const FULL_FRAME: f64 = 43.27;
+const SAMPLES: usize = 4000;
+
+// A 50mm lens at the origin looking down -z, focused `focus` metres away.
+fn lens(elements: Vec<LensElement>, focus: f64) -> Result<RealisticCamera> {
+    RealisticCamera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        elements,
+        FULL_FRAME,
+        1.5,
+        focus,
+    )
+}
+
+// Of the rays film point (s, t) sends into the lens, those that make it out.
+fn rays(lens: &RealisticCamera, s: f64, t: f64) -> Vec<Ray> {
+    let mut rng = StdRng::seed_from_u64(1);
+    (0..SAMPLES)
+        .filter_map(|_| lens.get_ray(s, t, &mut rng))
+        .collect()
+}
+
+// How far from the axis rays from the center of the film stray at depth `z`.
+fn spread(rays: &[Ray], z: f64) -> f64 {
+    rays.iter()
+        .map(|r| {
+            let p = r.at((-z - r.origin().z) / r.direction().z);
+            (p.x * p.x + p.y * p.y).sqrt()
+        })
+        .fold(0.0, f64::max)
+}
+
+#[test]
+fn lens_tables_are_validated() {
+    assert!(lens(double_gauss_50mm(), 1.0).is_ok());
+    assert!(lens(Vec::new(), 1.0).is_err());
+    let broken = |i: usize, change: fn(&mut LensElement)| {
+        let mut elements = double_gauss_50mm();
+        change(&mut elements[i]);
+        elements
+    };
+    for elements in [
+        broken(0, |e| e.thickness = -1.0),
+        broken(0, |e| e.aperture_radius = 0.0),
+        broken(0, |e| e.curvature_radius = f64::NAN),
+        broken(0, |e| e.eta = 0.0),
+        // The stop, which has no glass.
+        broken(5, |e| e.eta = 1.5),
+    ] {
+        assert!(lens(elements, 1.0).is_err());
+    }
+    for focus in [0.0, -1.0, f64::INFINITY] {
+        assert!(lens(double_gauss_50mm(), focus).is_err(), "{focus}");
+    }
+}
+
+#[test]
+fn rays_leave_through_the_exit_pupil() {
+    let lens = lens(double_gauss_50mm(), 1.0).unwrap();
+    let front = double_gauss_50mm()[0].aperture_radius * 0.001;
+    let center = rays(&lens, 0.5, 0.5);
+    for r in &center {
+        let o = r.origin();
+        assert!((o.x * o.x + o.y * o.y).sqrt() <= front + 1e-9, "{r:?}");
+        assert!(r.direction().z < 0.0);
+    }
+    // The pupil narrows towards the corners, which the lens vignettes.
+    let corner = rays(&lens, 1.0, 1.0);
+    assert!(center.len() > SAMPLES / 2, "{}", center.len());
+    assert!(!corner.is_empty() && corner.len() < center.len() / 2);
+    // The image is upright: the top right corner looks up and to the right.
+    assert!(corner
+        .iter()
+        .all(|r| r.direction().x > 0.0 && r.direction().y > 0.0));
+
+    // Stopping down lets less through.
+    let stopped = lens.with_aperture_diameter(4.0);
+    assert!(rays(&stopped, 0.5, 0.5).len() < center.len() / 4);
+}
+
+#[test]
+fn rays_from_a_film_point_meet_at_the_focus_distance() {
+    for focus in [0.5, 1.0, 3.0] {
+        // Stopped down, so spherical aberration doesn't blur the focus.
+        let lens = lens(double_gauss_50mm(), focus).unwrap();
+        let rays = rays(&lens.with_aperture_diameter(4.0), 0.5, 0.5);
+        // The sharpest depth between half and twice the focus distance.
+        let sharpest = (0..=150)
+            .map(|i| focus * (0.5 + 0.01 * i as f64))
+            .min_by(|&a, &b| spread(&rays, a).total_cmp(&spread(&rays, b)))
+            .unwrap();
+        assert!((sharpest / focus - 1.0).abs() < 0.03, "{focus}: {sharpest}");
+        let sharp = spread(&rays, focus);
+        assert!(sharp < 1e-4 * focus, "{focus}: {sharp}");
+        for z in [0.5 * focus, 2.0 * focus] {
+            assert!(spread(&rays, z) > 10.0 * sharp, "{focus} {z}");
+        }
+    }
+}
+
+#[test]
+fn renders_through_the_lens() {
+    // A glowing ball above the axis, in front of a dimmer glowing wall.
+    let mut world = HittableList::new();
+    let lamp = |center, radius, color| {
+        Arc::new(Sphere::new(
+            center,
+            radius,
+            Arc::new(DiffuseLight::new(color)),
+        ))
+    };
+    world.add(lamp(
+        Point3::new(0.0, 0.5, -3.0),
+        0.3,
+        Color::new(1.0, 1.0, 1.0),
+    ));
+    world.add(lamp(
+        Point3::new(0.0, 0.0, -100.0),
+        90.0,
+        Color::new(0.2, 0.2, 0.2),
+    ));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    let lens = lens(double_gauss_50mm(), 3.0).unwrap();
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.5,
+        0.0,
+        3.0,
+    )
+    .with_projection(Projection::Realistic(Arc::new(lens)));
+
+    let (width, height) = (12, 8);
+    let film = Film::new(width, height);
+    let aovs = AovSet::new(&[], &world, width, height);
+    let settings = RenderSettings::default().with_seed(5);
+    render_image_with(&world, &camera, &film, &aovs, 256, &settings, &|_| {}).unwrap();
+    let at = |x, y| film.pixel(x, y).unwrap().resolve(1.0).g();
+
+    let mean = |pixels: &[(u32, u32)]| {
+        pixels.iter().map(|&(x, y)| at(x, y)).sum::<f64>() / pixels.len() as f64
+    };
+    // The ball lands in the top half, upright.
+    assert!(at(width / 2, 1) > 0.5, "{}", at(width / 2, 1));
+    // Blocked rays darken the wall, most of all in the corners, which stay opaque.
+    let middle: Vec<_> = (4..8).flat_map(|x| (4..8).map(move |y| (x, y))).collect();
+    let corners = [
+        (0, 0),
+        (width - 1, 0),
+        (0, height - 1),
+        (width - 1, height - 1),
+    ];
+    assert!(mean(&middle) < 0.2 && mean(&corners) < 0.6 * mean(&middle));
+    assert_eq!(film.pixel(0, height - 1).unwrap().alpha(), 1.0);
+}
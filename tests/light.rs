@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::hittable::{Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::{Light, QuadLight};
+use rtt::material::Lambertian;
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings};
+use rtt::scene::{MaterialTable, ObjectDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Unit quad light facing down, two units above a grey ground.
+fn studio() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.5, 2.0, -0.5),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    world
+}
+
+// Mean and variance of the light's contribution (red channel) looking straight down at the
+// ground, leaving out the sky's.
+fn estimate(world: &HittableList, settings: RenderSettings, n: usize) -> (f64, f64) {
+    let lights = lights(world);
+    let mut rng = StdRng::seed_from_u64(5);
+    let ray = Ray::new(Point3::new(0.3, 1.0, 0.1), Vec3::new(0.0, -1.0, 0.0));
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let (mut sum, mut sum_sq) = (0.0, 0.0);
+    for _ in 0..n {
+        let sample = trace_path_with(ray, world, &lights, ray_t, &settings, &mut rng);
+        let c: f64 = sample.emission.iter().map(|(_, c)| c.r()).sum();
+        sum += c;
+        sum_sq += c * c;
+    }
+    let mean = sum / n as f64;
+    (mean, sum_sq / n as f64 - mean * mean)
+}
+
+#[test]
+fn light_sampling_agrees_with_brute_force() {
+    let world = studio();
+    let n = 40_000;
+    let (brute, brute_var) = estimate(&world, RenderSettings::default().with_shadow_samples(0), n);
+    let (mis, mis_var) = estimate(&world, RenderSettings::default(), n);
+    let (nee, _) = estimate(&world, RenderSettings::default().with_mis(false), n);
+    let (many, many_var) = estimate(&world, RenderSettings::default().with_shadow_samples(4), n);
+
+    for (name, value) in [("mis", mis), ("nee", nee), ("4 shadow samples", many)] {
+        assert!(
+            (value - brute).abs() < 0.03 * brute,
+            "{name}: {value} vs brute force {brute}"
+        );
+    }
+    assert!(mis_var < brute_var, "{mis_var} >= {brute_var}");
+    assert!(many_var < mis_var, "{many_var} >= {mis_var}");
+}
+
+#[test]
+fn sample_density_matches_pdf() {
+    let light = QuadLight::new(
+        Point3::new(-1.0, 3.0, -0.5),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(1.0, 1.0, 1.0),
+    );
+    let mut rng = StdRng::seed_from_u64(9);
+    let origin = Point3::new(0.5, 0.0, 2.0);
+    for _ in 0..100 {
+        let ls = light.sample(origin, &mut rng).unwrap();
+        let (t, pdf) = light.pdf(origin, ls.direction).unwrap();
+        assert!((t - ls.distance).abs() < 1e-9);
+        assert!((pdf - ls.pdf).abs() < 1e-9 * pdf);
+    }
+    // Facing away: nothing to sample, and the back side doesn't emit.
+    let above = Point3::new(0.0, 5.0, 0.0);
+    assert!(light.sample(above, &mut rng).is_none());
+    assert!(light.pdf(above, Vec3::new(0.0, -1.0, 0.0)).is_none());
+    let r = Ray::new(above, Vec3::new(0.0, -1.0, 0.0));
+    let rec = light.hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap();
+    assert_eq!(rec.material.get().emitted(&rec), Color::default());
+
+    let two_sided = QuadLight::new(light.corner, light.u, light.v, Color::new(1.0, 1.0, 1.0))
+        .with_two_sided(true);
+    assert!(two_sided.sample(above, &mut rng).is_some());
+}
+
+#[test]
+fn quad_light_round_trips_through_desc() {
+    let light = QuadLight::new(
+        Point3::new(0.0, 1.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(2.0, 2.0, 2.0),
+    )
+    .with_group("key")
+    .with_two_sided(true);
+    let desc = light.to_desc(&mut MaterialTable::new()).unwrap();
+    assert!(
+        matches!(desc, ObjectDesc::QuadLight { two_sided: true, ref group, .. }
+        if group.as_deref() == Some("key"))
+    );
+    let built = desc.build(&[]).unwrap();
+    assert_eq!(built.to_desc(&mut MaterialTable::new()), Some(desc));
+    assert_eq!(lights(built.as_ref()).len(), 1);
+}
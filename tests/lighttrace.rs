@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::light::QuadLight;
+use rtt::lighttrace::render_light_paths;
+use rtt::material::Lambertian;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+const SIZE: u32 = 16;
+
+fn camera() -> Camera {
+    Camera::new(
+        Point3::new(0.0, 1.5, 0.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        90.0,
+        1.0,
+        0.0,
+        1.5,
+    )
+}
+
+// Grey ground under a small downward light, in the dark.
+fn lit_ground() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    ));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.2, 2.0, -0.2),
+        Vec3::new(0.4, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.4),
+        Color::new(10.0, 10.0, 10.0),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+fn mean(image: &[Color]) -> f64 {
+    image.iter().map(|c| c.g()).sum::<f64>() / image.len() as f64
+}
+
+#[test]
+fn light_tracing_matches_path_tracing() {
+    let world = lit_ground();
+    let settings = RenderSettings::default();
+
+    let film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[], &world, SIZE, SIZE);
+    render_image_with(&world, &camera(), &film, &aovs, 64, &settings, &|_| {}).unwrap();
+    let reference = mean(&film.resolve(1.0).unwrap());
+
+    let film = Film::new(SIZE, SIZE);
+    render_light_paths(&world, &camera(), &film, 64, &settings).unwrap();
+    let traced = film.resolve(1.0 / 64.0).unwrap();
+    assert!(traced.iter().all(|c| c.g() > 0.0));
+    let traced = mean(&traced);
+    assert!(
+        (traced / reference - 1.0).abs() < 0.05,
+        "{traced} vs {reference}"
+    );
+}
+
+#[test]
+fn projection_inverts_camera_rays() {
+    let camera = camera();
+    let mut rng = StdRng::seed_from_u64(3);
+    for &(s, t) in &[(0.5, 0.5), (0.1, 0.8), (0.9, 0.25)] {
+        let ray = camera.get_ray(s, t, &mut rng).unwrap();
+        let seen = camera.project(ray.at(2.0)).unwrap();
+        assert!((seen.s - s).abs() < 1e-9 && (seen.t - t).abs() < 1e-9);
+        assert!((seen.direction + Vec3::unit_vector(ray.direction())).length() < 1e-9);
+    }
+    // Behind the camera.
+    assert!(camera.project(Point3::new(0.0, 3.0, 0.0)).is_none());
+}
@@ -0,0 +1,68 @@
+use rtt::hittable::Hittable;
+use rtt::interval::Interval;
+use rtt::lookdev::{material_ball, TILES};
+use rtt::ray::Ray;
+use rtt::scene::{MaterialDesc, ObjectDesc, SceneDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn gold() -> MaterialDesc {
+    MaterialDesc::Metal {
+        albedo: Color::new(0.9, 0.6, 0.3),
+        fuzz: 0.2,
+        measured: None,
+    }
+}
+
+#[test]
+fn only_the_ball_material_changes() {
+    let a = material_ball(gold());
+    let b = material_ball(MaterialDesc::Dielectric { ior: 1.5 });
+    assert_eq!(a.materials[0], gold());
+    assert_eq!(a.camera, b.camera);
+    assert_eq!(a.objects, b.objects);
+    assert_eq!(a.materials[1..], b.materials[1..]);
+    assert_eq!(a.background, b.background);
+}
+
+#[test]
+fn floor_is_a_full_checkerboard() {
+    let scene = material_ball(gold());
+    let triangles: Vec<usize> = scene
+        .objects
+        .iter()
+        .filter_map(|o| match o {
+            ObjectDesc::Mesh { indices, .. } => Some(indices.len()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(triangles, vec![TILES * TILES, TILES * TILES]);
+}
+
+#[test]
+fn camera_sees_the_ball_and_neighbouring_squares_differ() {
+    let scene = material_ball(gold());
+    let (world, _) = scene.build(1.0).unwrap();
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let eye = scene.camera.look_from;
+    let to_ball = Ray::new(eye, Point3::new(0.0, 1.0, 0.0) - eye);
+    let hit = world.hit(&to_ball, ray_t).unwrap();
+    assert_eq!(hit.material.get().to_desc(), Some(gold()));
+
+    let floor_at = |x: f64, z: f64| {
+        let r = Ray::new(Point3::new(x, 3.0, z), Vec3::new(0.0, -1.0, 0.0));
+        world.hit(&r, ray_t).unwrap().material.get().to_desc()
+    };
+    let (a, b) = (floor_at(2.5, 2.5), floor_at(3.5, 2.5));
+    assert!(a.is_some() && b.is_some());
+    assert_ne!(a, b);
+    assert_eq!(a, floor_at(3.5, 3.5));
+}
+
+#[test]
+fn scene_saves_like_any_other() {
+    let scene = material_ball(gold());
+    assert_eq!(
+        SceneDesc::from_json(&scene.to_json().unwrap()).unwrap(),
+        scene
+    );
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use rtt::hittable::{Hittable, Sphere};
+use rtt::interval::Interval;
+use rtt::material::{Dielectric, Lambertian, Material, MaterialRef, MaterialRegistry};
+use rtt::ray::Ray;
+use rtt::scene::SceneDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+#[test]
+fn shared_materials_register_once() {
+    let grey: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let a = MaterialRef::new(Arc::clone(&grey)).unwrap();
+    let b = MaterialRef::new(Arc::clone(&grey)).unwrap();
+    assert_eq!(a.id(), b.id());
+
+    let other = MaterialRef::new(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))).unwrap();
+    assert_ne!(a.id(), other.id());
+    assert!(MaterialRegistry::global().len() >= 2);
+    // Ids look up what was registered.
+    assert_eq!(a.id().get().to_desc(), grey.to_desc());
+}
+
+#[test]
+fn many_registrations_stay_addressable() {
+    let materials: Vec<(MaterialRef, f64)> = (0..1000)
+        .map(|i| {
+            let ior = 1.0 + i as f64 / 1000.0;
+            (
+                MaterialRef::new(Arc::new(Dielectric::new(ior))).unwrap(),
+                ior,
+            )
+        })
+        .collect();
+    for (material, ior) in materials {
+        let desc = serde_json::to_value(material.id().get().to_desc().unwrap()).unwrap();
+        assert_eq!(desc["ior"], ior);
+    }
+}
+
+#[test]
+fn regularized_variants_are_built_once() {
+    let material = MaterialRef::new(Arc::new(Dielectric::new(1.5))).unwrap();
+    let glass = material.id();
+    let a = glass.regularized(0.2).unwrap();
+    let b = glass.regularized(0.2).unwrap();
+    assert_eq!(a, b);
+    assert_ne!(a, glass);
+    assert_ne!(glass.regularized(0.3).unwrap(), a);
+
+    let matte = MaterialRef::new(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))).unwrap();
+    assert!(matte.id().regularized(0.2).is_none());
+}
+
+#[test]
+fn hits_carry_the_primitive_material() {
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.2, 0.4, 0.6)));
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Arc::clone(&material));
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = sphere
+        .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+        .unwrap();
+    assert_eq!(rec.material, sphere.material.id());
+    assert_eq!(rec.material, MaterialRef::new(material).unwrap().id());
+}
+
+#[test]
+fn dropped_materials_free_their_slots() {
+    let material = MaterialRef::new(Arc::new(Dielectric::new(1.5))).unwrap();
+    let id = material.id();
+    drop(material);
+    assert!(MaterialRegistry::global().get(id).is_none());
+
+    // Other tests may hold a few thousand at once; without reuse this would leave 30000.
+    let desc: SceneDesc = serde_json::from_str(
+        r#"{
+            "camera": {"look_from": [0, 0, 1], "look_at": [0, 0, 0], "vup": [0, 1, 0],
+                       "vfov": 90, "aperture": 0, "focus_dist": 1},
+            "materials": [
+                {"type": "lambertian", "albedo": [0.5, 0.5, 0.5]},
+                {"type": "metal", "albedo": [0.8, 0.8, 0.8], "fuzz": 0.1},
+                {"type": "dielectric", "ior": 1.5}
+            ],
+            "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": 2}]
+        }"#,
+    )
+    .unwrap();
+    for _ in 0..10_000 {
+        desc.build(1.0).unwrap();
+    }
+    assert!(MaterialRegistry::global().len() < 10_000);
+}
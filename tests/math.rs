@@ -0,0 +1,201 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use proptest::prelude::*;
+
+use rtt::math::{Mat4, Onb, Quat};
+use rtt::vec3::Vec3;
+
+const EPS: f64 = 1e-9;
+
+fn vec3(range: f64) -> impl Strategy<Value = Vec3> {
+    (-range..range, -range..range, -range..range).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn axis() -> impl Strategy<Value = Vec3> {
+    vec3(1.0)
+        .prop_filter("near-zero axis", |v| v.length() > 1e-3)
+        .prop_map(Vec3::unit_vector)
+}
+
+fn quat() -> impl Strategy<Value = Quat> {
+    (axis(), -PI..PI).prop_map(|(a, t)| Quat::from_axis_angle(a, t))
+}
+
+// Non-degenerate scale so TRS matrices stay invertible.
+fn scale() -> impl Strategy<Value = Vec3> {
+    (0.1..10.0, 0.1..10.0, 0.1..10.0).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn trs() -> impl Strategy<Value = Mat4> {
+    (vec3(100.0), quat(), scale()).prop_map(|(t, r, s)| Mat4::from_trs(t, r, s))
+}
+
+fn close(a: Vec3, b: Vec3, tol: f64) -> bool {
+    (a - b).length() <= tol * (1.0 + a.length().max(b.length()))
+}
+
+fn mat_close(a: &Mat4, b: &Mat4, tol: f64) -> bool {
+    a.m.iter()
+        .flatten()
+        .zip(b.m.iter().flatten())
+        .all(|(x, y)| (x - y).abs() <= tol)
+}
+
+#[test]
+fn identity_is_neutral() {
+    let p = Vec3::new(1.0, -2.0, 3.0);
+    assert_eq!(Mat4::IDENTITY.transform_point(p), p);
+    assert_eq!(Mat4::IDENTITY.transform_vector(p), p);
+    assert_eq!(Mat4::IDENTITY.inverse(), Some(Mat4::IDENTITY));
+    assert_eq!(Quat::IDENTITY.rotate(p), p);
+    assert_eq!(Mat4::default(), Mat4::IDENTITY);
+}
+
+#[test]
+fn translation_moves_points_not_vectors() {
+    let t = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+    let v = Vec3::new(4.0, 5.0, 6.0);
+    assert_eq!(t.transform_point(v), Vec3::new(5.0, 7.0, 9.0));
+    assert_eq!(t.transform_vector(v), v);
+}
+
+#[test]
+fn axis_rotations_are_right_handed() {
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    let z = Vec3::new(0.0, 0.0, 1.0);
+    assert!(close(
+        Mat4::rotation_z(FRAC_PI_2).transform_vector(x),
+        y,
+        EPS
+    ));
+    assert!(close(
+        Mat4::rotation_x(FRAC_PI_2).transform_vector(y),
+        z,
+        EPS
+    ));
+    assert!(close(
+        Mat4::rotation_y(FRAC_PI_2).transform_vector(z),
+        x,
+        EPS
+    ));
+}
+
+#[test]
+fn singular_matrix_has_no_inverse() {
+    let m = Mat4::scale(Vec3::new(1.0, 0.0, 1.0));
+    assert_eq!(m.determinant(), 0.0);
+    assert!(m.inverse().is_none());
+}
+
+#[test]
+fn non_uniform_scale_normal_stays_perpendicular() {
+    let m = Mat4::scale(Vec3::new(4.0, 1.0, 1.0));
+    // Surface tangent along (1, 1, 0) with normal (1, -1, 0).
+    let tangent = m.transform_vector(Vec3::new(1.0, 1.0, 0.0));
+    let normal = m
+        .normal_matrix()
+        .unwrap()
+        .transform_normal(Vec3::new(1.0, -1.0, 0.0));
+    assert!(Vec3::dot(tangent, normal).abs() < EPS);
+    // Naively transforming the normal as a vector would not be perpendicular.
+    let naive = m.transform_vector(Vec3::new(1.0, -1.0, 0.0));
+    assert!(Vec3::dot(tangent, naive).abs() > 1.0);
+}
+
+proptest! {
+    #[test]
+    fn inverse_round_trips(m in trs(), p in vec3(100.0)) {
+        let inv = m.inverse().unwrap();
+        prop_assert!(mat_close(&(m * inv), &Mat4::IDENTITY, 1e-9));
+        prop_assert!(mat_close(&(inv * m), &Mat4::IDENTITY, 1e-9));
+        prop_assert!(close(inv.transform_point(m.transform_point(p)), p, 1e-9));
+    }
+
+    #[test]
+    fn transpose_is_involutive(m in trs()) {
+        prop_assert_eq!(m.transpose().transpose(), m);
+    }
+
+    #[test]
+    fn determinant_is_product_of_scales(r in quat(), s in scale(), t in vec3(10.0)) {
+        let m = Mat4::from_trs(t, r, s);
+        let expected = s.x * s.y * s.z;
+        prop_assert!((m.determinant() - expected).abs() <= 1e-9 * expected.max(1.0));
+    }
+
+    #[test]
+    fn multiplication_is_associative(a in trs(), b in trs(), c in trs()) {
+        let lhs = (a * b) * c;
+        let rhs = a * (b * c);
+        let scale = lhs.m.iter().flatten().fold(1.0f64, |acc, v| acc.max(v.abs()));
+        prop_assert!(mat_close(&lhs, &rhs, 1e-9 * scale));
+    }
+
+    #[test]
+    fn composition_matches_sequential_application(a in trs(), b in trs(), p in vec3(10.0)) {
+        let sequential = a.transform_point(b.transform_point(p));
+        prop_assert!(close((a * b).transform_point(p), sequential, 1e-9));
+    }
+
+    #[test]
+    fn normal_matrix_preserves_perpendicularity(m in trs(), n in axis(), v in axis()) {
+        // Any tangent perpendicular to n stays perpendicular to the transformed normal.
+        let tangent = Vec3::cross(n, v);
+        prop_assume!(tangent.length() > 1e-3);
+        let t = m.transform_vector(tangent);
+        let n = m.normal_matrix().unwrap().transform_normal(n);
+        prop_assert!(Vec3::dot(t, n).abs() <= 1e-9 * t.length() * n.length());
+    }
+
+    #[test]
+    fn quat_rotation_matches_matrix(q in quat(), v in vec3(10.0)) {
+        prop_assert!(close(q.rotate(v), q.to_mat4().transform_vector(v), 1e-9));
+    }
+
+    #[test]
+    fn quat_rotation_preserves_length_and_angles(q in quat(), u in vec3(10.0), v in vec3(10.0)) {
+        let (ru, rv) = (q.rotate(u), q.rotate(v));
+        prop_assert!((ru.length() - u.length()).abs() <= 1e-9 * (1.0 + u.length()));
+        let scale = 1.0 + u.length() * v.length();
+        prop_assert!((Vec3::dot(ru, rv) - Vec3::dot(u, v)).abs() <= 1e-9 * scale);
+    }
+
+    #[test]
+    fn quat_product_composes_rotations(a in quat(), b in quat(), v in vec3(10.0)) {
+        prop_assert!(close((a * b).rotate(v), a.rotate(b.rotate(v)), 1e-9));
+    }
+
+    #[test]
+    fn quat_inverse_undoes_rotation(q in quat(), v in vec3(10.0)) {
+        prop_assert!(close(q.inverse().rotate(q.rotate(v)), v, 1e-9));
+        prop_assert!(close((q * q.inverse()).vector(), Vec3::default(), 1e-9));
+    }
+
+    #[test]
+    fn quat_rotates_by_axis_angle(a in axis(), t in -PI..PI) {
+        let q = Quat::from_axis_angle(a, t);
+        // The axis is fixed, and a perpendicular vector turns by exactly t.
+        prop_assert!(close(q.rotate(a), a, 1e-9));
+        let perp = Onb::from_w(a).u;
+        let cos = Vec3::dot(q.rotate(perp), perp);
+        prop_assert!((cos - t.cos()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn slerp_hits_endpoints_and_stays_unit(a in quat(), b in quat(), t in 0.0..1.0f64) {
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        prop_assert!(close(Quat::slerp(a, b, 0.0).rotate(v), a.rotate(v), 1e-6));
+        prop_assert!(close(Quat::slerp(a, b, 1.0).rotate(v), b.rotate(v), 1e-6));
+        prop_assert!((Quat::slerp(a, b, t).length() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn onb_round_trips(n in axis(), v in vec3(10.0)) {
+        let uvw = Onb::from_w(n);
+        prop_assert!(close(uvw.to_world(uvw.to_local(v)), v, 1e-9));
+        prop_assert!(close(uvw.w, n, 1e-12));
+        prop_assert!(Vec3::dot(uvw.u, uvw.v).abs() < 1e-9);
+        prop_assert!(close(Vec3::cross(uvw.u, uvw.v), uvw.w, 1e-9));
+    }
+}
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use image::{Rgb, RgbImage};
+use rtt::aov::Aov;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::{Cutout, Lambertian, Material};
+use rtt::memory::MemoryEstimate;
+use rtt::stats::SceneStats;
+use rtt::texture::{TextureCache, TextureFormat};
+use rtt::vec3::{Color, Point3};
+use rtt::Error;
+
+fn world() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -1.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world
+}
+
+#[test]
+fn films_scale_with_the_resolution() {
+    let world = world();
+    let small = MemoryEstimate::new(&world, 100, 100, &[]);
+    let large = MemoryEstimate::new(&world, 200, 200, &[]);
+    assert!(small.film_bytes > 0);
+    assert_eq!(large.film_bytes, 4 * small.film_bytes);
+    assert_eq!(small.aov_bytes, 0);
+    assert_eq!(small.scene_bytes, large.scene_bytes);
+    assert!(small.scene_bytes > 0);
+}
+
+#[test]
+fn every_aov_adds_to_the_estimate() {
+    let world = world();
+    let depth = MemoryEstimate::new(&world, 64, 64, &[Aov::Depth]);
+    let more = MemoryEstimate::new(&world, 64, 64, &[Aov::Depth, Aov::Position, Aov::Albedo]);
+    assert!(depth.aov_bytes > 0);
+    assert_eq!(more.aov_bytes, 3 * depth.aov_bytes);
+    // One film per path class.
+    let classes = MemoryEstimate::new(&world, 64, 64, &[Aov::PathClasses]);
+    assert!(classes.aov_bytes > 4 * depth.aov_bytes);
+    assert_eq!(
+        more.total(),
+        more.film_bytes + more.aov_bytes + more.scene_bytes + more.bvh_bytes
+    );
+}
+
+#[test]
+fn renders_over_the_budget_are_refused() {
+    let estimate = MemoryEstimate::new(&world(), 1000, 1000, &[Aov::Depth]);
+    estimate.check(estimate.total()).unwrap();
+    let err = estimate.check(estimate.total() - 1).unwrap_err();
+    assert!(matches!(err, Error::Memory(_)));
+    assert!(err.to_string().contains("films"), "{err}");
+}
+
+// Writes a `width` x `height` PNG into this test run's scratch directory.
+fn write_png(name: &str, width: u32, height: u32) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rtt-memory-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    RgbImage::from_pixel(width, height, Rgb([200, 200, 200]))
+        .save(&path)
+        .unwrap();
+    path
+}
+
+// Balls cut out by each of `masks`.
+fn textured(masks: &[Arc<dyn Material>]) -> HittableList {
+    let mut world = world();
+    for mask in masks {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -3.0),
+            0.5,
+            Arc::clone(mask),
+        )));
+    }
+    world
+}
+
+#[test]
+fn textures_count_before_they_are_loaded() {
+    let (wide, tall) = (write_png("wide.png", 64, 32), write_png("tall.png", 8, 128));
+    let plain = MemoryEstimate::new(&world(), 16, 16, &[]);
+    let cache = Arc::new(TextureCache::unbounded());
+    let cutout = |path: &PathBuf| -> Arc<dyn Material> {
+        let base = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Arc::new(Cutout::new(base, cache.texture(path)))
+    };
+    // A second material on the same image doesn't count it twice.
+    let texels = 64 * 32 + 8 * 128;
+    let textures = |world: &HittableList| SceneStats::new(world).texture_bytes;
+    let world = textured(&[cutout(&wide), cutout(&tall), cutout(&wide)]);
+    let estimate = MemoryEstimate::new(&world, 16, 16, &[]);
+    assert_eq!(cache.stats().unwrap().loads, 0);
+    assert_eq!(textures(&world), texels * 12);
+    assert!(estimate.scene_bytes > plain.scene_bytes + texels * 12);
+
+    // Sized as the cache will keep them, and the same once loaded.
+    cache.set_format(TextureFormat::F16).unwrap();
+    assert_eq!(textures(&world), texels * 6);
+    cache.get(&wide).unwrap();
+    assert_eq!(cache.stats().unwrap().loads, 1);
+    assert_eq!(textures(&world), texels * 6);
+
+    // The budget bounds what stays resident, but not below the largest image.
+    let small = Arc::new(TextureCache::new(1000));
+    let mask = |path: &PathBuf| -> Arc<dyn Material> {
+        let base = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Arc::new(Cutout::new(base, small.texture(path)))
+    };
+    assert_eq!(
+        textures(&textured(&[mask(&wide), mask(&tall)])),
+        64 * 32 * 12
+    );
+    // Files that can't be read take nothing.
+    let missing = cutout(&wide.with_file_name("missing.png"));
+    assert_eq!(textures(&textured(&[missing])), 0);
+}
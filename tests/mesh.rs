@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::hittable::{Hittable, HittableList};
+use rtt::interval::Interval;
+use rtt::material::Lambertian;
+use rtt::mesh::{self, LodView, Mesh};
+use rtt::procgen::uv_sphere;
+use rtt::ray::Ray;
+use rtt::scene::{CameraDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use rtt::stats::SceneStats;
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn material() -> Arc<Lambertian> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+#[test]
+fn simplify_reaches_target_and_keeps_shape() {
+    let (positions, indices) = uv_sphere(1.0, 32, 64);
+    assert_eq!(indices.len(), 3968);
+    let (p, i) = mesh::simplify(&positions, &indices, 400);
+    assert!(i.len() <= 400 && i.len() > 300, "{} triangles", i.len());
+    assert!(p.len() < positions.len());
+    for v in &p {
+        assert!(
+            (v.length() - 1.0).abs() < 0.1,
+            "vertex {v:?} left the sphere"
+        );
+    }
+    for tri in &i {
+        assert!(tri.iter().all(|&k| (k as usize) < p.len()));
+        assert!(tri[0] != tri[1] && tri[1] != tri[2] && tri[0] != tri[2]);
+    }
+}
+
+#[test]
+fn simplify_below_target_is_a_copy() {
+    let (positions, indices) = uv_sphere(1.0, 4, 8);
+    let (p, i) = mesh::simplify(&positions, &indices, 1000);
+    assert_eq!((p, i), (positions, indices));
+}
+
+#[test]
+fn mesh_hits_nearest_triangle() {
+    let (positions, indices) = uv_sphere(1.0, 16, 32);
+    let mesh = Mesh::new(positions, indices, material()).unwrap();
+    let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = mesh.hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap();
+    assert!((rec.t - 4.0).abs() < 0.02, "t = {}", rec.t);
+    assert!(rec.front_face);
+    assert!(rec.normal.z > 0.99);
+
+    let miss = Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(mesh
+        .hit(&miss, Interval::new(0.001, f64::INFINITY))
+        .is_none());
+
+    let mut stats = SceneStats::default();
+    mesh.stats(&mut stats);
+    assert_eq!(stats.triangles, 960);
+}
+
+// The BVH finds what testing every triangle finds.
+#[test]
+fn bvh_agrees_with_every_triangle() {
+    let (positions, indices) = uv_sphere(1.0, 16, 32);
+    let mesh = Mesh::new(positions.clone(), indices.clone(), material()).unwrap();
+    let mut each = HittableList::new();
+    for &tri in &indices {
+        let single = Mesh::new(positions.clone(), vec![tri], material()).unwrap();
+        each.add(Arc::new(single));
+    }
+    let mut rng = StdRng::seed_from_u64(5);
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let mut hits = 0;
+    for _ in 0..500 {
+        let mut point = || {
+            Point3::new(rng.random(), rng.random(), rng.random()) * 4.0 - Vec3::new(2.0, 2.0, 2.0)
+        };
+        let origin = point();
+        let r = Ray::new(origin, point() - origin);
+        let got = mesh.hit(&r, ray_t).map(|rec| (rec.t, rec.u, rec.v));
+        let expected = each.hit(&r, ray_t).map(|rec| (rec.t, rec.u, rec.v));
+        assert_eq!(got, expected);
+        assert_eq!(mesh.is_occluded(&r, ray_t), expected.is_some());
+        let mut all: Vec<f64> = mesh.hit_all(&r, ray_t).iter().map(|h| h.t).collect();
+        all.sort_by(f64::total_cmp);
+        let mut every: Vec<f64> = each.hit_all(&r, ray_t).iter().map(|h| h.t).collect();
+        every.sort_by(f64::total_cmp);
+        assert_eq!(all, every);
+        hits += got.is_some() as usize;
+    }
+    assert!(hits > 100, "{hits}");
+}
+
+#[test]
+fn out_of_range_index_is_an_error() {
+    let positions = vec![Point3::new(0.0, 0.0, 0.0); 3];
+    assert!(Mesh::new(positions, vec![[0, 1, 3]], material()).is_err());
+}
+
+#[test]
+fn obj_faces_are_triangulated() {
+    let path = std::env::temp_dir().join(format!("rtt-mesh-{}.obj", std::process::id()));
+    std::fs::write(
+        &path,
+        "# quad\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1 -1//1\n",
+    )
+    .unwrap();
+    let (positions, indices) = mesh::load_obj(&path).unwrap();
+    assert_eq!(positions.len(), 4);
+    assert_eq!(indices, vec![[0, 1, 2], [0, 2, 3]]);
+
+    std::fs::write(&path, "v 0 0 0\nf 1 2\n").unwrap();
+    assert!(mesh::load_obj(&path).is_err());
+}
+
+#[test]
+fn lod_follows_projected_size() {
+    let (positions, _) = uv_sphere(1.0, 8, 16);
+    let bounds = mesh::bounds(&positions).unwrap();
+    let view = |z: f64| LodView {
+        eye: Point3::new(0.0, 0.0, z),
+        vfov: 40.0,
+        image_height: 1080,
+    };
+    assert!(view(100.0).triangles(bounds) < view(10.0).triangles(bounds));
+    assert_eq!(view(1.0).triangles(bounds), usize::MAX);
+    assert_eq!(view(1e6).triangles(bounds), 1);
+}
+
+#[test]
+fn scene_meshes_decimate_on_load() {
+    let (positions, indices) = uv_sphere(1.0, 32, 64);
+    let scene = |target_triangles, lod| SceneDesc {
+        camera: CameraDesc {
+            look_from: Point3::new(0.0, 0.0, 400.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            focus_dist: 400.0,
+            ..Default::default()
+        },
+        materials: vec![MaterialDesc::Lambertian {
+            albedo: Color::new(0.5, 0.5, 0.5),
+        }],
+        objects: vec![ObjectDesc::Mesh {
+            positions: positions.clone(),
+            indices: indices.clone(),
+            uvs: Vec::new(),
+            obj: None,
+            material: 0,
+            target_triangles,
+            lod,
+        }],
+        background: None,
+        meters_per_unit: None,
+    };
+    let triangles = |desc: &SceneDesc, height: Option<u32>| {
+        let (world, _) = match height {
+            Some(h) => desc.build_lod(1.0, h).unwrap(),
+            None => desc.build(1.0).unwrap(),
+        };
+        SceneStats::new(&world).triangles
+    };
+
+    assert_eq!(triangles(&scene(None, false), Some(100)), 3968);
+    assert!(triangles(&scene(Some(1000), false), None) <= 1000);
+    // Lod only applies when building for a resolution.
+    assert_eq!(triangles(&scene(None, true), None), 3968);
+    assert!(triangles(&scene(None, true), Some(100)) < 100);
+
+    // The farther the mesh, the fewer triangles it keeps.
+    let mut far = scene(None, true);
+    far.camera.look_from = Point3::new(0.0, 0.0, 1000.0);
+    let mut near = scene(None, true);
+    near.camera.look_from = Point3::new(0.0, 0.0, 150.0);
+    let (far, near) = (triangles(&far, Some(1080)), triangles(&near, Some(1080)));
+    assert!(0 < far && far < near && near < 3968, "{far} vs {near}");
+
+    let json = scene(Some(10), true).to_json().unwrap();
+    let object = &SceneDesc::from_json(&json).unwrap().objects[0];
+    assert!(matches!(
+        object,
+        ObjectDesc::Mesh {
+            obj: None,
+            target_triangles: Some(10),
+            lod: true,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::hittable::{Hittable, Sphere};
+use rtt::interval::Interval;
+use rtt::material::{fresnel_conductor, Material, MeasuredMetal, Metal};
+use rtt::polarization::Mueller;
+use rtt::ray::Ray;
+use rtt::scene::MaterialDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+const METALS: [MeasuredMetal; 4] = [
+    MeasuredMetal::Gold,
+    MeasuredMetal::Silver,
+    MeasuredMetal::Copper,
+    MeasuredMetal::Aluminum,
+];
+
+#[test]
+fn conductor_fresnel_matches_the_complex_equations() {
+    for metal in METALS {
+        let (n, k) = metal.ior();
+        for (n, k) in [(n.r(), k.r()), (n.g(), k.g()), (n.b(), k.b())] {
+            let head_on = ((n - 1.0) * (n - 1.0) + k * k) / ((n + 1.0) * (n + 1.0) + k * k);
+            assert!((fresnel_conductor(1.0, n, k) - head_on).abs() < 1e-9);
+            for i in 0..=20 {
+                let cos_i = i as f64 / 20.0;
+                let expected = Mueller::conductor_reflection(cos_i, n, k).0[0][0];
+                let r = fresnel_conductor(cos_i, n, k);
+                assert!((r - expected).abs() < 1e-9, "{metal:?} {cos_i}: {r}");
+            }
+            assert!((fresnel_conductor(0.0, n, k) - 1.0).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn measured_metals_have_their_colour_head_on_and_whiten_at_grazing() {
+    let gold = MeasuredMetal::Gold.reflectance(1.0);
+    assert!(gold.r() > gold.g() && gold.g() > gold.b(), "{gold:?}");
+    let copper = MeasuredMetal::Copper.reflectance(1.0);
+    assert!(copper.r() > copper.b(), "{copper:?}");
+    let silver = MeasuredMetal::Silver.reflectance(1.0);
+    assert!(silver.b() > 0.85, "{silver:?}");
+
+    let spread = |c: Color| c.r().max(c.g()).max(c.b()) - c.r().min(c.g()).min(c.b());
+    for metal in [MeasuredMetal::Gold, MeasuredMetal::Copper] {
+        assert!(spread(metal.reflectance(0.02)) < 0.5 * spread(metal.reflectance(1.0)));
+    }
+    for metal in METALS {
+        let grazing = metal.reflectance(0.02);
+        assert!(grazing.b() > metal.reflectance(1.0).b(), "{metal:?}");
+    }
+}
+
+#[test]
+fn measured_metal_mirrors_tint_by_angle() {
+    let gold: Arc<dyn Material> =
+        Arc::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0).with_measured(MeasuredMetal::Gold));
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, gold);
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut reflect = |y: f64| {
+        let ray = Ray::new(Point3::new(-5.0, y, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let rec = sphere
+            .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+            .unwrap();
+        let cos_i = -Vec3::dot(ray.direction(), rec.normal);
+        let (attenuation, _) = rec.material.get().scatter(&ray, &rec, &mut rng).unwrap();
+        (attenuation, cos_i)
+    };
+    for y in [0.0, 0.5, 0.99] {
+        let (attenuation, cos_i) = reflect(y);
+        let expected = MeasuredMetal::Gold.reflectance(cos_i);
+        assert!((attenuation - expected).length() < 1e-9, "{y}");
+    }
+    // The albedo tints it.
+    let tinted = Metal::new(Color::new(0.5, 1.0, 1.0), 0.0).with_measured(MeasuredMetal::Gold);
+    let desc = tinted.to_desc().unwrap();
+    assert!(matches!(
+        desc,
+        MaterialDesc::Metal {
+            measured: Some(MeasuredMetal::Gold),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn measured_metals_read_by_name() {
+    let desc: MaterialDesc =
+        serde_json::from_str(r#"{"type": "metal", "measured": "aluminium", "fuzz": 0.1}"#).unwrap();
+    let MaterialDesc::Metal {
+        albedo, measured, ..
+    } = &desc
+    else {
+        panic!("{desc:?}");
+    };
+    assert_eq!(*measured, Some(MeasuredMetal::Aluminum));
+    assert_eq!(*albedo, Color::new(1.0, 1.0, 1.0));
+    let json = serde_json::to_string(&desc).unwrap();
+    assert!(json.contains(r#""measured":"aluminum""#), "{json}");
+    let back: MaterialDesc = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, desc);
+
+    // Artist metals keep writing the old form.
+    let plain = Metal::new(Color::new(0.9, 0.8, 0.5), 0.0)
+        .to_desc()
+        .unwrap();
+    assert!(!serde_json::to_string(&plain).unwrap().contains("measured"));
+}
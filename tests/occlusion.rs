@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::generator::SceneGenerator;
+use rtt::hittable::{Hittable, HittableList, MovingSphere};
+use rtt::interval::Interval;
+use rtt::material::Lambertian;
+use rtt::mesh::Mesh;
+use rtt::paged::{self, GeometryCache, PagedMesh};
+use rtt::procgen::uv_sphere;
+use rtt::ray::Ray;
+use rtt::vec3::{Color, Point3};
+
+// Shadow-like rays between random points, so many stop short of what lies behind them.
+fn agrees_with_hit(object: &dyn Hittable, extent: f64, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut point = || {
+        Point3::new(
+            rng.random_range(-extent..extent),
+            rng.random_range(0.0..extent / 4.0),
+            rng.random_range(-extent..extent),
+        )
+    };
+    let (mut occluded, mut clear) = (0, 0);
+    for _ in 0..2000 {
+        let (from, to) = (point(), point());
+        let ray = Ray::new(from, to - from);
+        let ray_t = Interval::new(1e-3, 1.0 - 1e-3);
+        let expected = object.hit(&ray, ray_t).is_some();
+        assert_eq!(object.is_occluded(&ray, ray_t), expected);
+        if expected {
+            occluded += 1;
+        } else {
+            clear += 1;
+        }
+    }
+    assert!(
+        occluded > 100 && clear > 100,
+        "{occluded} occluded, {clear} clear"
+    );
+}
+
+#[test]
+fn sphere_lists_agree_with_closest_hits() {
+    agrees_with_hit(&SceneGenerator::new(3).generate(), 11.0, 1);
+
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mut few = HittableList::new();
+    for i in 0..4 {
+        let x = 3.0 * i as f64 - 4.5;
+        few.add(MovingSphere::new(
+            Point3::new(x, 0.5, 0.0),
+            Point3::new(x, 1.5, 0.0),
+            0.0,
+            1.0,
+            1.0,
+            material.clone(),
+        ));
+    }
+    agrees_with_hit(&few, 6.0, 2);
+}
+
+#[test]
+fn meshes_agree_with_closest_hits() {
+    let (positions, indices) = uv_sphere(2.0, 16, 32);
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mesh = Mesh::new(positions.clone(), indices.clone(), material.clone()).unwrap();
+    agrees_with_hit(&mesh, 4.0, 3);
+
+    let path = std::env::temp_dir().join(format!("rtt-occlusion-{}.geom", std::process::id()));
+    paged::write(&positions, &indices, &path, 64).unwrap();
+    let paged = PagedMesh::open(&path, material, Arc::new(GeometryCache::new(usize::MAX))).unwrap();
+    agrees_with_hit(&paged, 4.0, 3);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::error::Error;
+use rtt::hittable::{Hittable, HittableList};
+use rtt::interval::Interval;
+use rtt::material::{Lambertian, Material};
+use rtt::mesh::Mesh;
+use rtt::paged::{self, GeometryCache, PagedMesh};
+use rtt::ray::Ray;
+use rtt::scene::{CameraDesc, ObjectDesc, SceneDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn material() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+// A bumpy `n` x `n` quad grid over [-1, 1] in x and z, two triangles per quad.
+fn terrain(n: u32) -> (Vec<Point3>, Vec<[u32; 3]>) {
+    let mut positions = Vec::new();
+    for i in 0..=n {
+        for j in 0..=n {
+            let (x, z) = (
+                2.0 * i as f64 / n as f64 - 1.0,
+                2.0 * j as f64 / n as f64 - 1.0,
+            );
+            positions.push(Point3::new(x, 0.2 * (3.0 * x).sin() * (2.0 * z).cos(), z));
+        }
+    }
+    let mut indices = Vec::new();
+    for i in 0..n {
+        for j in 0..n {
+            let v = i * (n + 1) + j;
+            indices.push([v, v + 1, v + n + 1]);
+            indices.push([v + 1, v + n + 2, v + n + 1]);
+        }
+    }
+    (positions, indices)
+}
+
+// Saves the terrain to a per-test scratch file.
+fn write_terrain(test: &str, n: u32, chunk_triangles: usize) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rtt-paged-{}-{test}.geom", std::process::id()));
+    let (positions, indices) = terrain(n);
+    paged::write(&positions, &indices, &path, chunk_triangles).unwrap();
+    path
+}
+
+fn random_ray(rng: &mut StdRng) -> Ray {
+    let origin = Point3::new(
+        rng.random_range(-2.0..2.0),
+        rng.random_range(1.0..2.0),
+        rng.random_range(-2.0..2.0),
+    );
+    let target = Point3::new(
+        rng.random_range(-1.2..1.2),
+        rng.random_range(-0.3..0.3),
+        rng.random_range(-1.2..1.2),
+    );
+    Ray::new(origin, target - origin)
+}
+
+#[test]
+fn paged_hits_match_the_in_memory_mesh() {
+    let path = write_terrain("match", 24, 64);
+    let cache = Arc::new(GeometryCache::new(usize::MAX));
+    let paged = PagedMesh::open(&path, material(), cache).unwrap();
+    let (positions, indices) = terrain(24);
+    let mesh = Mesh::new(positions, indices, material()).unwrap();
+    assert_eq!(paged.triangle_count(), 24 * 24 * 2);
+    assert!(paged.chunk_count() >= 24 * 24 * 2 / 64);
+
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut hits = 0;
+    for _ in 0..500 {
+        let ray = random_ray(&mut rng);
+        let ray_t = Interval::new(1e-3, f64::INFINITY);
+        match (mesh.hit(&ray, ray_t), paged.hit(&ray, ray_t)) {
+            (Some(a), Some(b)) => {
+                hits += 1;
+                assert!((a.t - b.t).abs() < 1e-9, "{} vs {}", a.t, b.t);
+                assert!((a.normal - b.normal).length() < 1e-9);
+                assert_eq!(a.front_face, b.front_face);
+            }
+            (None, None) => {}
+            _ => panic!("only one mesh was hit"),
+        }
+    }
+    assert!(hits > 200);
+
+    let (a, b) = (
+        mesh.bounding_box(0.0, 1.0).unwrap(),
+        paged.bounding_box(0.0, 1.0).unwrap(),
+    );
+    // Chunk bounds are padded slightly, never shrunk.
+    for axis in 0..3 {
+        let ((a0, a1), (b0, b1)) = (a.axis(axis), b.axis(axis));
+        assert!(b0 <= a0 && b1 >= a1);
+        assert!(a0 - b0 < 1e-3 && b1 - a1 < 1e-3);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn resident_geometry_stays_within_budget() {
+    let path = write_terrain("budget", 24, 64);
+    let reference =
+        PagedMesh::open(&path, material(), Arc::new(GeometryCache::new(usize::MAX))).unwrap();
+    let budget = 16 * 1024;
+    let cache = Arc::new(GeometryCache::new(budget));
+    let paged = PagedMesh::open(&path, material(), Arc::clone(&cache)).unwrap();
+
+    let mut rng = StdRng::seed_from_u64(9);
+    for _ in 0..500 {
+        let ray = random_ray(&mut rng);
+        let ray_t = Interval::new(1e-3, f64::INFINITY);
+        let (a, b) = (reference.hit(&ray, ray_t), paged.hit(&ray, ray_t));
+        assert_eq!(a.map(|h| h.t), b.map(|h| h.t));
+        assert!(cache.stats().unwrap().bytes <= budget);
+    }
+    let stats = cache.stats().unwrap();
+    assert!(stats.evictions > 0);
+    assert!(stats.loads > paged.chunk_count());
+    assert!(stats.hits > 0);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rays_missing_the_bounds_load_nothing() {
+    let path = write_terrain("lazy", 8, 16);
+    let cache = Arc::new(GeometryCache::new(usize::MAX));
+    let paged = PagedMesh::open(&path, material(), Arc::clone(&cache)).unwrap();
+    assert_eq!(cache.stats().unwrap().loads, 0);
+
+    let ray = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert!(paged
+        .hit(&ray, Interval::new(1e-3, f64::INFINITY))
+        .is_none());
+    assert_eq!(cache.stats().unwrap().loads, 0);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn paged_mesh_round_trips_through_scene_files() {
+    let path = write_terrain("scene", 8, 16);
+    let mut world = HittableList::new();
+    world.add(Arc::new(
+        PagedMesh::open(&path, material(), GeometryCache::shared()).unwrap(),
+    ));
+    let camera = CameraDesc {
+        look_from: Point3::new(0.0, 3.0, 3.0),
+        look_at: Point3::new(0.0, 0.0, 0.0),
+        ..Default::default()
+    };
+    let desc = SceneDesc::from_world(&world, camera).unwrap();
+    assert!(matches!(
+        &desc.objects[..],
+        [ObjectDesc::PagedMesh { material: 0, .. }]
+    ));
+    let desc = SceneDesc::from_json(&desc.to_json().unwrap()).unwrap();
+    let (built, _camera) = desc.build(1.0).unwrap();
+
+    let ray = Ray::new(Point3::new(0.1, 2.0, 0.2), Vec3::new(0.0, -1.0, 0.0));
+    let ray_t = Interval::new(1e-3, f64::INFINITY);
+    let (a, b) = (
+        world.hit(&ray, ray_t).unwrap(),
+        built.hit(&ray, ray_t).unwrap(),
+    );
+    assert!((a.t - b.t).abs() < 1e-12);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn other_files_are_rejected() {
+    let path = std::env::temp_dir().join(format!("rtt-paged-{}-bogus.geom", std::process::id()));
+    std::fs::write(&path, b"not a paged mesh at all").unwrap();
+    assert!(matches!(
+        PagedMesh::open(&path, material(), GeometryCache::shared()),
+        Err(Error::Scene(_))
+    ));
+    std::fs::remove_file(path).unwrap();
+}
+
+// Triangles scattered through [-1, 1]^3, some flattened into axis planes and some sharing
+// vertices, so sibling bounds overlap and many are degenerate along an axis.
+fn soup(rng: &mut StdRng, count: u32) -> (Vec<Point3>, Vec<[u32; 3]>) {
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut indices = Vec::new();
+    for _ in 0..count {
+        let center = Point3::new(
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+        );
+        let size = rng.random_range(0.05..0.8);
+        let flat = rng.random_range(0..4);
+        let base = positions.len() as u32;
+        for _ in 0..3 {
+            let mut offset = Vec3::new(
+                rng.random_range(-size..size),
+                rng.random_range(-size..size),
+                rng.random_range(-size..size),
+            );
+            match flat {
+                0 => offset.x = 0.0,
+                1 => offset.y = 0.0,
+                2 => offset.z = 0.0,
+                _ => {}
+            }
+            positions.push(center + offset);
+        }
+        if base >= 3 && rng.random_range(0..4) == 0 {
+            indices.push([base - 1, base, base + 1]);
+        } else {
+            indices.push([base, base + 1, base + 2]);
+        }
+    }
+    (positions, indices)
+}
+
+// Rays from inside and around the soup, some along the axes so direction components are zero.
+fn probe(rng: &mut StdRng) -> Ray {
+    let origin = Point3::new(
+        rng.random_range(-1.5..1.5),
+        rng.random_range(-1.5..1.5),
+        rng.random_range(-1.5..1.5),
+    );
+    let mut direction = Vec3::new(
+        rng.random_range(-1.0..1.0),
+        rng.random_range(-1.0..1.0),
+        rng.random_range(-1.0..1.0),
+    );
+    match rng.random_range(0..6) {
+        0 => direction.x = 0.0,
+        1 => (direction.x, direction.y) = (0.0, 0.0),
+        2 => (direction.y, direction.z) = (0.0, 0.0),
+        _ => {}
+    }
+    if direction.length_squared() == 0.0 {
+        direction.y = 1.0;
+    }
+    Ray::new(origin, direction)
+}
+
+#[test]
+fn bvh_agrees_with_brute_force_on_random_soups() {
+    let path = std::env::temp_dir().join(format!("rtt-paged-{}-soup.geom", std::process::id()));
+    let mut rng = StdRng::seed_from_u64(17);
+    let mut hits = 0;
+    for _ in 0..40 {
+        let count = rng.random_range(1..300);
+        let (positions, indices) = soup(&mut rng, count);
+        let chunk_triangles = rng.random_range(1..64);
+        paged::write(&positions, &indices, &path, chunk_triangles).unwrap();
+        let paged =
+            PagedMesh::open(&path, material(), Arc::new(GeometryCache::new(usize::MAX))).unwrap();
+        // One mesh per triangle, searched one after another.
+        let mut brute = HittableList::new();
+        for tri in &indices {
+            let corners = tri.map(|i| positions[i as usize]);
+            brute.add(Arc::new(
+                Mesh::new(corners.to_vec(), vec![[0, 1, 2]], material()).unwrap(),
+            ));
+        }
+
+        for _ in 0..200 {
+            let ray = probe(&mut rng);
+            let t_max = if rng.random_range(0..3) == 0 {
+                rng.random_range(0.1..2.0)
+            } else {
+                f64::INFINITY
+            };
+            let ray_t = Interval::new(1e-3, t_max);
+            match (brute.hit(&ray, ray_t), paged.hit(&ray, ray_t)) {
+                (Some(a), Some(b)) => {
+                    hits += 1;
+                    assert!((a.t - b.t).abs() < 1e-9, "{} vs {}", a.t, b.t);
+                }
+                (None, None) => {}
+                (a, b) => panic!(
+                    "brute force hit at {:?}, BVH at {:?}",
+                    a.map(|h| h.t),
+                    b.map(|h| h.t)
+                ),
+            }
+            assert_eq!(
+                brute.is_occluded(&ray, ray_t),
+                paged.is_occluded(&ray, ray_t)
+            );
+            assert_eq!(
+                brute.hit_all(&ray, ray_t).len(),
+                paged.hit_all(&ray, ray_t).len()
+            );
+        }
+    }
+    assert!(hits > 1000);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::camera::Camera;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::light::QuadLight;
+use rtt::material::Lambertian;
+use rtt::pathdump::{record_pixel, save, to_obj};
+use rtt::render::RenderSettings;
+use rtt::scene::MaterialDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn lit_ground() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.5, 2.0, -0.5),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    let camera = Camera::new(
+        Point3::new(0.0, 1.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    (world, camera)
+}
+
+#[test]
+fn records_every_vertex_of_the_paths() {
+    let (world, camera) = lit_ground();
+    let mut rng = StdRng::seed_from_u64(3);
+    let settings = RenderSettings::default();
+    let paths = record_pixel(&world, &camera, (8, 8), (4, 5), 20, &settings, &mut rng).unwrap();
+    assert_eq!(paths.len(), 20);
+    for (i, path) in paths.iter().enumerate() {
+        assert_eq!((path.pixel, path.sample), ([4, 5], i as u32));
+        assert_eq!(path.origin, Point3::new(0.0, 1.0, 3.0));
+        let first = &path.vertices[0];
+        assert!(first.point.y.abs() < 1e-2, "{:?}", first.point);
+        assert!(matches!(
+            first.material,
+            Some(MaterialDesc::Lambertian { .. })
+        ));
+        assert!(first.pdf.is_some_and(|pdf| pdf > 0.0));
+        assert_eq!(first.throughput, Color::new(1.0, 1.0, 1.0));
+        // Everything a vertex gathered ends up in the path's color, along with the sky.
+        let gathered: f64 = path
+            .vertices
+            .iter()
+            .map(|v| (v.emitted + v.direct).g())
+            .sum();
+        assert!(gathered <= path.color.g() + 1e-9);
+        // Paths end by escaping or being absorbed, never both.
+        let last = path.vertices.last().unwrap();
+        assert_eq!(path.escaped.is_some(), last.scattered.is_some());
+    }
+    // The light sits right above, so shadow rays from the ground find it.
+    assert!(paths.iter().any(|p| p.vertices[0].direct.g() > 0.0));
+
+    assert!(record_pixel(&world, &camera, (8, 8), (8, 0), 1, &settings, &mut rng).is_err());
+}
+
+#[test]
+fn saves_json_and_obj() {
+    let (world, camera) = lit_ground();
+    let mut rng = StdRng::seed_from_u64(5);
+    let settings = RenderSettings::default();
+    let paths = record_pixel(&world, &camera, (8, 8), (2, 6), 3, &settings, &mut rng).unwrap();
+
+    let obj = to_obj(&paths);
+    let vertices = obj.lines().filter(|l| l.starts_with("v ")).count();
+    let expected: usize = paths
+        .iter()
+        .map(|p| 1 + p.vertices.len() + p.escaped.is_some() as usize)
+        .sum();
+    assert_eq!(vertices, expected);
+    assert_eq!(obj.lines().filter(|l| l.starts_with("l ")).count(), 3);
+    assert!(obj.starts_with("o path_2_6_0\nv 0 1 3\n"));
+
+    let dir = std::env::temp_dir();
+    let json = dir.join(format!("rtt-paths-{}.json", std::process::id()));
+    save(&paths, &json).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&std::fs::read(&json).unwrap()).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 3);
+    assert!(value[0]["vertices"][0]["material"]["type"] == "lambertian");
+    std::fs::remove_file(json).unwrap();
+}
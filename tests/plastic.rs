@@ -0,0 +1,155 @@
+mod common;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rtt::hittable::HitRecord;
+use rtt::light::QuadLight;
+use rtt::material::{fresnel_dielectric, Plastic};
+use rtt::ray::Ray;
+use rtt::render::RenderSettings;
+use rtt::scene::MaterialDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+use common::{ball, hit, lit_floor};
+
+const RED: Color = Color::new(0.8, 0.2, 0.1);
+
+// Mean attenuation of `scatter`, and BRDF times cosine integrated over the hemisphere by
+// uniform sampling after the bounces shadow rays would follow, those not off the coat's
+// mirror, plus the chance the mirror took the ray.
+fn albedo(ray: &Ray, rec: &HitRecord, n: usize) -> (Color, Color, f64) {
+    let mut rng = StdRng::seed_from_u64(9);
+    let (mut sampled, mut integrated, mut mirrored) = (Color::default(), Color::default(), 0);
+    let mirror = ray.direction() - 2.0 * Vec3::dot(ray.direction(), rec.normal) * rec.normal;
+    for _ in 0..n {
+        let (attenuation, scattered) = rec.material.get().scatter(ray, rec, &mut rng).unwrap();
+        sampled += attenuation;
+        if (Vec3::unit_vector(scattered.direction()) - mirror).length() < 1e-9 {
+            mirrored += 1;
+            continue;
+        }
+        let z: f64 = rng.random();
+        let phi = 2.0 * std::f64::consts::PI * rng.random::<f64>();
+        let r = (1.0 - z * z).sqrt();
+        let up = Vec3::new(r * phi.cos(), z, r * phi.sin());
+        let towards = Ray::new(rec.point, up);
+        integrated += rec
+            .material
+            .get()
+            .scattering(ray, rec, attenuation, &towards)
+            * (2.0 * std::f64::consts::PI);
+    }
+    (
+        sampled / n as f64,
+        integrated / (n - mirrored) as f64,
+        mirrored as f64 / n as f64,
+    )
+}
+
+#[test]
+fn white_plastic_reflects_everything() {
+    for material in [
+        Plastic::new(Color::new(1.0, 1.0, 1.0), 1.5),
+        Plastic::new(Color::new(1.0, 1.0, 1.0), 1.5).with_roughness(0.4),
+    ] {
+        for theta in [0.0, 0.8, 1.4] {
+            let sphere = ball(material);
+            let (ray, rec) = hit(&sphere, theta);
+            let mut rng = StdRng::seed_from_u64(3);
+            for _ in 0..1000 {
+                let (attenuation, _) = rec.material.get().scatter(&ray, &rec, &mut rng).unwrap();
+                assert!((attenuation - Color::new(1.0, 1.0, 1.0)).length() < 1e-9);
+            }
+        }
+    }
+}
+
+#[test]
+fn coat_reflects_by_fresnel() {
+    for theta in [0.0, 1.0, 1.4] {
+        let sphere = ball(Plastic::new(RED, 1.5));
+        let (ray, rec) = hit(&sphere, theta);
+        let (sampled, _, mirrored) = albedo(&ray, &rec, 40_000);
+        let f = fresnel_dielectric(theta.cos(), 1.5);
+        assert!((mirrored - f).abs() < 0.01, "{theta}: {mirrored} vs {f}");
+        // What the coat doesn't reflect reaches the base, whose color shows through it.
+        assert!(sampled.r() > f && sampled.r() < 0.8 + f, "{sampled:?}");
+        assert!(
+            sampled.r() > 3.0 * sampled.b() || theta > 1.3,
+            "{sampled:?}"
+        );
+    }
+}
+
+#[test]
+fn shadow_rays_see_what_scatter_samples() {
+    for roughness in [0.0, 0.3] {
+        for theta in [0.3, 1.1] {
+            let material = Plastic::new(RED, 1.5).with_roughness(roughness);
+            let sphere = ball(material);
+            let (ray, rec) = hit(&sphere, theta);
+            let (sampled, integrated, mirrored) = albedo(&ray, &rec, 200_000);
+            // A smooth coat's mirror has no density; shadow rays see the diffuse lobe, given
+            // that it was picked.
+            let expected = if roughness == 0.0 {
+                (sampled - Color::new(mirrored, mirrored, mirrored)) / (1.0 - mirrored)
+            } else {
+                sampled
+            };
+            assert!(
+                (integrated - expected).length() < 0.02 * expected.length(),
+                "{roughness} {theta}: {integrated:?} vs {expected:?}"
+            );
+        }
+    }
+}
+
+// A small lamp overhead, lighting a floor seen from above at an angle.
+fn lamp() -> QuadLight {
+    QuadLight::new(
+        Point3::new(-0.25, 2.0, -0.25),
+        Vec3::new(0.5, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.5),
+        Color::new(4.0, 4.0, 4.0),
+    )
+}
+
+#[test]
+fn light_sampling_matches_brute_force() {
+    let settings = RenderSettings::default().with_seed(1);
+    for roughness in [0.0, 0.5] {
+        let material = Plastic::new(RED, 1.5).with_roughness(roughness);
+        let mis = lit_floor(material, lamp(), settings, 20_000);
+        let brute = lit_floor(material, lamp(), settings.with_shadow_samples(0), 200_000);
+        assert!(mis.r() > 3.0 * mis.b(), "{mis:?}");
+        assert!(
+            (mis - brute).length() < 0.05 * brute.length(),
+            "{roughness}: {mis:?} vs brute force {brute:?}"
+        );
+    }
+}
+
+#[test]
+fn plastic_round_trips_through_desc() {
+    let desc: MaterialDesc =
+        serde_json::from_str(r#"{"type": "plastic", "albedo": [0.8, 0.2, 0.1]}"#).unwrap();
+    assert_eq!(
+        desc,
+        MaterialDesc::Plastic {
+            albedo: RED,
+            ior: 1.5,
+            roughness: 0.0,
+        }
+    );
+    assert_eq!(desc.build().to_desc(), Some(desc.clone()));
+
+    let rough: MaterialDesc = serde_json::from_str(
+        r#"{"type": "plastic", "albedo": [0.8, 0.2, 0.1], "ior": 1.4, "roughness": 0.3}"#,
+    )
+    .unwrap();
+    assert_eq!(rough.build().to_desc(), Some(rough));
+    // Regularizing spreads the coat but exports what was asked for.
+    let regularized = desc.build().regularized(0.2).unwrap();
+    assert_eq!(regularized.to_desc(), Some(desc));
+}
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use rtt::aov::AovSet;
+use rtt::background::FnBackground;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::{fresnel_dielectric, Dielectric, Lambertian, Material, Metal};
+use rtt::polarization::{degree_of_polarization, render_polarized, Mueller, StokesImage};
+use rtt::ray::Ray;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+const UNPOLARIZED: [f64; 4] = [1.0, 0.0, 0.0, 0.0];
+
+// A flat floor of `material` under a sky bright above the horizon and black below it.
+fn floor(material: Arc<dyn Material>) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        material,
+    )));
+    world.set_background(Arc::new(FnBackground(|ray: &Ray| {
+        if ray.direction().y > 0.0 {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            Color::default()
+        }
+    })));
+    world
+}
+
+// Looking at the floor's origin `degrees` from straight down.
+fn camera(degrees: f64) -> Camera {
+    let a = degrees.to_radians();
+    Camera::new(
+        Point3::new(-a.sin(), a.cos(), 0.0) * 3.0,
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        2.0,
+        1.0,
+        0.0,
+        3.0,
+    )
+}
+
+fn render(world: &HittableList, camera: &Camera, samples: u32) -> StokesImage {
+    let settings = RenderSettings::default().with_seed(5);
+    render_polarized(world, camera, 4, 4, samples, &settings).unwrap()
+}
+
+#[test]
+fn brewster_reflection_is_fully_polarized() {
+    let eta: f64 = 1.5;
+    let cos_brewster = eta.atan().cos();
+    let s = Mueller::dielectric_reflection(cos_brewster, eta).apply(UNPOLARIZED);
+    assert!((degree_of_polarization(s) - 1.0).abs() < 1e-9, "{s:?}");
+    // Perpendicular to the plane of incidence.
+    assert!(s[1] > 0.0);
+    // Head on, nothing is preferred.
+    let head_on = Mueller::dielectric_reflection(1.0, eta).apply(UNPOLARIZED);
+    assert!(degree_of_polarization(head_on) < 1e-9);
+}
+
+#[test]
+fn fresnel_matrices_agree_with_reflectance() {
+    for eta in [1.33, 1.5, 1.0 / 1.5] {
+        for i in 0..=20 {
+            let cos_i = i as f64 / 20.0;
+            let r = Mueller::dielectric_reflection(cos_i, eta).0[0][0];
+            let t = Mueller::dielectric_transmission(cos_i, eta).0[0][0];
+            let expected = fresnel_dielectric(cos_i, eta);
+            assert!(
+                (r - expected).abs() < 1e-9,
+                "{eta} {cos_i}: {r} vs {expected}"
+            );
+            assert!((r + t - 1.0).abs() < 1e-9, "{eta} {cos_i}: {r} + {t}");
+        }
+    }
+    // Gold-like n and k head on.
+    let (n, k) = (0.47, 2.4);
+    let r = Mueller::conductor_reflection(1.0, n, k).0[0][0];
+    let expected = ((n - 1.0) * (n - 1.0) + k * k) / ((n + 1.0) * (n + 1.0) + k * k);
+    assert!((r - expected).abs() < 1e-9);
+    // Metals turn linear into elliptical polarization at an angle.
+    let diagonal = [1.0, 0.0, 1.0, 0.0];
+    let s = Mueller::conductor_reflection(0.5, n, k).apply(diagonal);
+    assert!(s[3].abs() > 0.1, "{s:?}");
+}
+
+#[test]
+fn rotation_turns_the_frame() {
+    let horizontal = [1.0, 1.0, 0.0, 0.0];
+    let quarter = Mueller::rotator(std::f64::consts::FRAC_PI_2).apply(horizontal);
+    assert!((quarter[1] + 1.0).abs() < 1e-9, "{quarter:?}");
+    let eighth = Mueller::rotator(std::f64::consts::FRAC_PI_4).apply(horizontal);
+    assert!(eighth[1].abs() < 1e-9 && (eighth[2].abs() - 1.0).abs() < 1e-9);
+    let back = Mueller::rotator(-0.3) * Mueller::rotator(0.3);
+    assert_eq!(
+        back.apply(horizontal).map(|v| (v * 1e9).round()),
+        [1e9, 1e9, 0.0, 0.0]
+    );
+}
+
+#[test]
+fn glass_glare_is_polarized_and_rough_floors_are_not() {
+    let glass = render(
+        &floor(Arc::new(Dielectric::new(1.5))),
+        &camera(1.5f64.atan().to_degrees()),
+        64,
+    );
+    let dop = glass.degree_of_polarization(2, 2);
+    assert!(dop > 0.8, "{dop}");
+    // Polarized along the floor, the image's horizontal.
+    let [_, green, _] = glass.stokes(2, 2);
+    assert!(
+        green[1] > 0.0 && green[1].abs() > 10.0 * green[2].abs(),
+        "{green:?}"
+    );
+    let view = glass.degree_view();
+    assert_ne!(view.get_pixel(2, 2).0, [0, 0, 0]);
+
+    let matte = render(
+        &floor(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))),
+        &camera(50.0),
+        16,
+    );
+    for y in 0..4 {
+        for x in 0..4 {
+            assert_eq!(matte.degree_of_polarization(x, y), 0.0);
+        }
+    }
+    // Metal glare is polarized less than glass.
+    let metal = render(
+        &floor(Arc::new(Metal::new(Color::new(0.9, 0.8, 0.5), 0.0))),
+        &camera(60.0),
+        16,
+    );
+    let metal_dop = metal.degree_of_polarization(2, 2);
+    assert!(metal_dop > 0.0 && metal_dop < dop, "{metal_dop}");
+}
+
+// One bounce off rough metal: polarization could only change the intensity from the second on.
+#[test]
+fn intensity_matches_an_ordinary_render() {
+    let world = floor(Arc::new(Metal::new(Color::new(0.9, 0.8, 0.5), 0.4)));
+    let camera = camera(75.0);
+    let samples = 1024;
+    let polarized = render(&world, &camera, samples).intensity().unwrap();
+    let film = Film::new(4, 4);
+    let aovs = AovSet::new(&[], &world, 4, 4);
+    let settings = RenderSettings::default().with_seed(7);
+    render_image_with(&world, &camera, &film, &aovs, samples, &settings, &|_| {}).unwrap();
+    let mean = |film: &Film| {
+        let image = film.resolve(1.0).unwrap();
+        image.iter().map(|c| c.g()).sum::<f64>() / image.len() as f64
+    };
+    let (a, b) = (mean(&polarized), mean(&film));
+    assert!(a > 0.1 && a < 0.75, "{a}");
+    assert!((a - b).abs() < 0.02 * b, "{a} vs {b}");
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use rtt::color::{from_kelvin, luminance};
+use rtt::film::Film;
+use rtt::post::{Grain, Look, Lut, Vignette, WhiteBalance};
+use rtt::vec3::Color;
+
+fn flat(width: u32, height: u32, c: Color) -> Film {
+    let mut film = Film::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            film.add_sample(x, y, c, 1.0).unwrap();
+        }
+    }
+    film
+}
+
+fn grey(l: f64) -> Color {
+    Color::new(l, l, l)
+}
+
+// A `size`^3 `.cube` file mapping each display value through `f`.
+fn cube(size: usize, f: impl Fn(Color) -> Color) -> String {
+    let mut text = format!("TITLE \"test\"\n# comment\nLUT_3D_SIZE {size}\n");
+    let n = (size - 1) as f64;
+    for b in 0..size {
+        for g in 0..size {
+            for r in 0..size {
+                let c = f(Color::new(r as f64 / n, g as f64 / n, b as f64 / n));
+                text += &format!("{} {} {}\n", c.r(), c.g(), c.b());
+            }
+        }
+    }
+    text
+}
+
+#[test]
+fn vignettes_darken_the_corners_only() {
+    let film = flat(33, 21, grey(0.5));
+    let look = Look {
+        vignette: Some(Vignette::new(0.4)),
+        ..Default::default()
+    };
+    let out = look.apply(&film, 1.0, 0).unwrap().resolve(1.0).unwrap();
+    let at = |x: u32, y: u32| out[(y * 33 + x) as usize].g();
+    assert!((at(16, 10) - 0.5).abs() < 1e-3);
+    // The corner pixel's centre is a little inside the half diagonal, so it loses a little
+    // under the full strength.
+    assert!(at(0, 0) > 0.5 * 0.6 && at(0, 0) < 0.5 * 0.7, "{}", at(0, 0));
+    assert!((at(32, 20) - at(0, 0)).abs() < 1e-12);
+    assert!(at(0, 10) < at(16, 10) && at(0, 10) > at(0, 0));
+}
+
+#[test]
+fn cube_luts_map_display_values() {
+    let identity = Lut::parse(&cube(5, |c| c)).unwrap();
+    let c = Color::new(0.1, 0.55, 0.93);
+    assert!((identity.lookup(c) - c).length() < 1e-12);
+
+    let invert = Lut::parse(&cube(3, |c| Color::new(1.0, 1.0, 1.0) - c)).unwrap();
+    let film = flat(2, 2, grey(0.25));
+    let look = Look {
+        lut: Some(Arc::new(invert)),
+        ..Default::default()
+    };
+    let out = look.apply(&film, 1.0, 0).unwrap().resolve(1.0).unwrap();
+    // Display value sqrt(0.25) = 0.5 maps to 0.5, back to linear 0.25; 0.0 goes to white.
+    assert!((out[0].g() - 0.25).abs() < 1e-12);
+    let black = flat(1, 1, grey(0.0));
+    let out = look.apply(&black, 1.0, 0).unwrap().resolve(1.0).unwrap();
+    assert!((out[0].g() - 1.0).abs() < 1e-12);
+
+    // Domains rescale the input.
+    let text = cube(2, |c| c).replace("LUT_3D_SIZE 2", "LUT_3D_SIZE 2\nDOMAIN_MAX 2 2 2");
+    let wide = Lut::parse(&text).unwrap();
+    assert!((wide.lookup(grey(1.0)).g() - 0.5).abs() < 1e-12);
+
+    assert!(Lut::parse("0 0 0\n1 1 1\n").is_err());
+    assert!(Lut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+    assert!(Lut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+}
+
+#[test]
+fn grain_is_stable_per_seed_and_frame() {
+    let film = flat(64, 64, grey(0.25));
+    let look = |seed| Look {
+        grain: Some(Grain::new(0.05, 2.0, seed)),
+        ..Default::default()
+    };
+    let render = |seed, frame| {
+        look(seed)
+            .apply(&film, 1.0, frame)
+            .unwrap()
+            .resolve(1.0)
+            .unwrap()
+    };
+    let a = render(1, 0);
+    assert_eq!(a, render(1, 0));
+    assert_ne!(a, render(1, 1));
+    assert_ne!(a, render(2, 0));
+
+    // Zero mean in display space, and monochrome.
+    let display: Vec<f64> = a.iter().map(|c| c.g().sqrt() - 0.5).collect();
+    let mean = display.iter().sum::<f64>() / display.len() as f64;
+    let spread = (display.iter().map(|d| d * d).sum::<f64>() / display.len() as f64).sqrt();
+    assert!(mean.abs() < 0.01, "{mean}");
+    assert!(spread > 0.01 && spread < 0.06, "{spread}");
+    assert!(a.iter().all(|c| c.r() == c.g() && c.g() == c.b()));
+
+    // Black has no midtones to grain.
+    let black = flat(8, 8, grey(0.0));
+    let out = look(1).apply(&black, 1.0, 0).unwrap().resolve(1.0).unwrap();
+    assert!(out.iter().all(|c| *c == grey(0.0)));
+}
+
+#[test]
+fn white_balance_neutralizes_the_chosen_light() {
+    // A grey card under a tungsten lamp.
+    let lamp = 0.3 * from_kelvin(3200.0);
+    let film = flat(4, 4, lamp);
+    for balance in [
+        WhiteBalance::from_temperature(3200.0, 0.0),
+        WhiteBalance::new(lamp),
+    ] {
+        let look = Look {
+            white_balance: Some(balance),
+            ..Default::default()
+        };
+        let out = look.apply(&film, 1.0, 0).unwrap().resolve(1.0).unwrap();
+        let c = out[5];
+        assert!(
+            (c.r() - c.g()).abs() < 2e-3 && (c.b() - c.g()).abs() < 2e-3,
+            "{c:?}"
+        );
+        assert!((luminance(c) - luminance(lamp)).abs() < 1e-3);
+    }
+    // Greener light gets pulled towards magenta.
+    let tinted = WhiteBalance::from_temperature(3200.0, 0.01).apply(grey(0.5));
+    let plain = WhiteBalance::from_temperature(3200.0, 0.0).apply(grey(0.5));
+    assert!(tinted.g() < plain.g());
+}
@@ -0,0 +1,42 @@
+use rtt::render::{Preset, RenderSettings};
+
+#[test]
+fn presets_are_found_by_name() {
+    for preset in Preset::ALL {
+        assert_eq!(Preset::from_name(preset.name()), Some(preset));
+    }
+    assert_eq!(Preset::from_name("ultra"), None);
+}
+
+#[test]
+fn presets_get_more_thorough() {
+    for pair in Preset::ALL.windows(2) {
+        let (fast, slow) = (pair[0], pair[1]);
+        assert!(fast.samples() < slow.samples());
+        let (a, b) = (fast.settings(), slow.settings());
+        assert!(a.bounces.total <= b.bounces.total);
+        assert!(a.bounces.diffuse <= b.bounces.diffuse);
+        assert!(a.shadow_samples <= b.shadow_samples);
+        // Less blurring of caustics.
+        assert!(b.regularization.unwrap_or(0.0) <= a.regularization.unwrap_or(0.0));
+    }
+}
+
+#[test]
+fn final_keeps_default_bounces_and_repairs() {
+    let settings = Preset::Final.settings();
+    assert_eq!(settings.bounces, RenderSettings::default().bounces);
+    assert_eq!(settings.regularization, None);
+    assert!(Preset::Final.repair().is_some());
+    assert_eq!(Preset::Draft.repair(), None);
+}
+
+#[test]
+fn presets_serialize_by_name() {
+    assert_eq!(
+        serde_json::to_string(&Preset::Preview).unwrap(),
+        "\"preview\""
+    );
+    let preset: Preset = serde_json::from_str("\"final\"").unwrap();
+    assert_eq!(preset, Preset::Final);
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::math::Mat4;
+use rtt::mesh::Geometry;
+use rtt::procgen::{self, cuboid, cylinder, icosphere, plane_grid, torus, uv_sphere};
+use rtt::vec3::{Point3, Vec3};
+
+// Enclosed volume; positive when triangles are wound outward.
+fn signed_volume((positions, indices): &Geometry) -> f64 {
+    indices
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.map(|i| positions[i as usize]);
+            Vec3::dot(a, Vec3::cross(b, c)) / 6.0
+        })
+        .sum()
+}
+
+// Every edge is shared by exactly two triangles, traversed in opposite directions.
+fn is_watertight((_, indices): &Geometry) -> bool {
+    let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+    for t in indices {
+        for k in 0..3 {
+            let (a, b) = (t[k], t[(k + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_default() += if a < b { 1 } else { -1 };
+        }
+    }
+    edges.values().all(|&n| n == 0)
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance * expected.abs(),
+        "{actual} is not within {tolerance} of {expected}"
+    );
+}
+
+#[test]
+fn closed_shapes_are_watertight_and_outward() {
+    let shapes: [(&str, Geometry, f64); 6] = [
+        ("uv_sphere", uv_sphere(2.0, 48, 96), 4.0 / 3.0 * PI * 8.0),
+        ("icosphere", icosphere(2.0, 4), 4.0 / 3.0 * PI * 8.0),
+        (
+            "cuboid",
+            cuboid(Point3::new(-1.0, 0.0, 2.0), Point3::new(2.0, 1.0, 4.0)),
+            6.0,
+        ),
+        ("torus", torus(2.0, 0.5, 96, 48), 2.0 * PI * PI * 2.0 * 0.25),
+        ("cylinder", cylinder(1.0, 3.0, 128, true), PI * 3.0),
+        ("octagon", cylinder(1.0, 1.0, 8, true), 2.0 * 2f64.sqrt()),
+    ];
+    for (name, geometry, volume) in &shapes {
+        // The cuboid's faces don't share vertices, so only its volume is checked.
+        if *name != "cuboid" {
+            assert!(is_watertight(geometry), "{name} has open edges");
+        }
+        assert_close(signed_volume(geometry), *volume, 0.01);
+    }
+}
+
+#[test]
+fn triangle_counts_match_parameters() {
+    assert_eq!(uv_sphere(1.0, 8, 16).1.len(), 2 * 7 * 16);
+    assert_eq!(icosphere(1.0, 2).1.len(), 20 * 16);
+    assert_eq!(icosphere(1.0, 2).0.len(), 162);
+    assert_eq!(plane_grid(1.0, 1.0, 4, 3).1.len(), 24);
+    assert_eq!(
+        cuboid(Point3::default(), Point3::new(1.0, 1.0, 1.0))
+            .1
+            .len(),
+        12
+    );
+    assert_eq!(torus(1.0, 0.2, 12, 6).1.len(), 144);
+    assert_eq!(cylinder(1.0, 1.0, 10, false).1.len(), 20);
+    assert!(!is_watertight(&cylinder(1.0, 1.0, 10, false)));
+}
+
+#[test]
+fn plane_grid_faces_up() {
+    let (positions, indices) = plane_grid(4.0, 2.0, 8, 4);
+    for t in &indices {
+        let [a, b, c] = t.map(|i| positions[i as usize]);
+        let n = Vec3::unit_vector(Vec3::cross(b - a, c - a));
+        assert!((n.y - 1.0).abs() < 1e-12);
+    }
+    let xs = positions.iter().map(|p| p.x);
+    assert_eq!(xs.clone().fold(f64::INFINITY, f64::min), -2.0);
+    assert_eq!(xs.fold(f64::NEG_INFINITY, f64::max), 2.0);
+}
+
+#[test]
+fn mirroring_transform_keeps_outward_winding() {
+    let mut sphere = icosphere(1.0, 2);
+    let volume = signed_volume(&sphere);
+    procgen::transform(&mut sphere, &Mat4::scale(Vec3::new(-2.0, 1.0, 1.0)));
+    assert_close(signed_volume(&sphere), 2.0 * volume, 1e-9);
+
+    let mut moved = icosphere(1.0, 1);
+    procgen::transform(&mut moved, &Mat4::translation(Vec3::new(0.0, 5.0, 0.0)));
+    assert!(moved.0.iter().all(|p| (p.y - 5.0).abs() <= 1.0 + 1e-9));
+}
+
+#[test]
+fn scattered_points_lie_on_the_surface() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let sphere = icosphere(3.0, 3);
+    let points = procgen::scatter_on_surface(&sphere, 2000, &mut rng);
+    assert_eq!(points.len(), 2000);
+    let mut upper = 0;
+    for (p, n) in &points {
+        assert!(p.length() > 2.9 && p.length() <= 3.0 + 1e-9);
+        assert!(Vec3::dot(*n, Vec3::unit_vector(*p)) > 0.95);
+        upper += (p.y > 0.0) as usize;
+    }
+    // Uniform by area, so about half land on each hemisphere.
+    assert!(
+        (800..1200).contains(&upper),
+        "{upper} points in the upper half"
+    );
+
+    let degenerate: Geometry = (vec![Point3::default(); 3], vec![[0, 1, 2]]);
+    assert!(procgen::scatter_on_surface(&degenerate, 10, &mut rng).is_empty());
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::background::Constant;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::{Lambertian, Metal};
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings, T_MIN};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A floor lit only by a small lamp facing away from it, through a mirror ceiling: a caustic
+// that only bounces off the floor can find.
+fn mirrored_lamp() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 1002.0, 0.0),
+        1000.0,
+        Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0)),
+    )));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.1, 1.0, 0.1),
+        Vec3::new(0.2, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -0.2),
+        Color::new(100.0, 100.0, 100.0),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+// Mean and variance of the radiance leaving a spot on the floor.
+fn estimate(settings: &RenderSettings, samples: u32) -> (f64, f64) {
+    let world = mirrored_lamp();
+    let lights = lights(&world);
+    let mut rng = StdRng::seed_from_u64(7);
+    let ray = Ray::new(Point3::new(0.5, 0.5, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let values: Vec<f64> = (0..samples)
+        .map(|_| {
+            let range = Interval::new(T_MIN, f64::INFINITY);
+            trace_path_with(ray, &world, &lights, range, settings, &mut rng)
+                .color
+                .g()
+        })
+        .collect();
+    let mean = values.iter().sum::<f64>() / samples as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / samples as f64;
+    (mean, variance)
+}
+
+#[test]
+fn regularization_tames_caustic_fireflies() {
+    let (reference, noisy) = estimate(&RenderSettings::default(), 100_000);
+    let regularized = RenderSettings::default().with_regularization(0.3);
+    let (mean, variance) = estimate(&regularized, 20_000);
+    assert!(reference > 0.0);
+    assert!(variance < 0.25 * noisy, "{variance} vs {noisy}");
+    // Blurring the lamp's reflection only slightly changes how much light arrives.
+    assert!(
+        (mean - reference).abs() < 0.2 * reference,
+        "{mean} vs {reference}"
+    );
+}
+
+#[test]
+fn regularization_is_off_by_default() {
+    let settings: RenderSettings = serde_json::from_str("{}").unwrap();
+    assert_eq!(settings.regularization, None);
+    let json = serde_json::to_string(&RenderSettings::default().with_regularization(0.1)).unwrap();
+    let settings: RenderSettings = serde_json::from_str(&json).unwrap();
+    assert_eq!(settings.regularization, Some(0.1));
+}
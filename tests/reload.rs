@@ -0,0 +1,130 @@
+use rtt::hittable::Hittable;
+use rtt::interval::Interval;
+use rtt::ray::Ray;
+use rtt::reload::{ReloadStats, SceneCache};
+use rtt::scene::{BackgroundDesc, CameraDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn sphere(x: f64, material: usize) -> ObjectDesc {
+    ObjectDesc::Sphere {
+        center: Point3::new(x, 0.0, 0.0),
+        radius: 0.5,
+        material,
+    }
+}
+
+fn scene() -> SceneDesc {
+    SceneDesc {
+        camera: CameraDesc {
+            look_from: Point3::new(0.0, 0.0, 5.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            focus_dist: 5.0,
+            ..Default::default()
+        },
+        materials: vec![
+            MaterialDesc::Lambertian {
+                albedo: Color::new(0.8, 0.1, 0.1),
+            },
+            MaterialDesc::Metal {
+                albedo: Color::new(0.9, 0.9, 0.9),
+                fuzz: 0.1,
+                measured: None,
+            },
+        ],
+        objects: vec![
+            sphere(-2.0, 0),
+            sphere(0.0, 1),
+            ObjectDesc::Holdout {
+                object: Box::new(sphere(2.0, 1)),
+            },
+        ],
+        background: Some(BackgroundDesc::Constant {
+            color: Color::new(0.2, 0.3, 0.4),
+        }),
+        meters_per_unit: None,
+    }
+}
+
+#[test]
+fn unchanged_scene_reuses_everything() {
+    let mut cache = SceneCache::new();
+    cache.build(&scene(), 1.0).unwrap();
+    assert_eq!(cache.stats().objects_built, 3);
+
+    cache.build(&scene(), 1.0).unwrap();
+    assert_eq!(
+        cache.stats(),
+        ReloadStats {
+            objects_reused: 3,
+            objects_built: 0,
+            materials_reused: 2,
+            materials_built: 0,
+            background_reused: true,
+        }
+    );
+}
+
+#[test]
+fn changing_a_material_rebuilds_only_its_objects() {
+    let mut cache = SceneCache::new();
+    cache.build(&scene(), 1.0).unwrap();
+    let mut edited = scene();
+    edited.materials[1] = MaterialDesc::Metal {
+        albedo: Color::new(0.9, 0.7, 0.3),
+        fuzz: 0.1,
+        measured: None,
+    };
+    cache.build(&edited, 1.0).unwrap();
+    let stats = cache.stats();
+    assert_eq!((stats.objects_reused, stats.objects_built), (1, 2));
+    assert_eq!((stats.materials_reused, stats.materials_built), (1, 1));
+}
+
+#[test]
+fn moved_and_added_objects() {
+    let mut cache = SceneCache::new();
+    cache.build(&scene(), 1.0).unwrap();
+    let mut edited = scene();
+    edited.objects.reverse();
+    edited.objects.push(sphere(4.0, 0));
+    edited.objects[0] = sphere(-3.0, 0);
+    cache.build(&edited, 1.0).unwrap();
+    let stats = cache.stats();
+    assert_eq!((stats.objects_reused, stats.objects_built), (2, 2));
+}
+
+#[test]
+fn failed_build_keeps_the_cache() {
+    let mut cache = SceneCache::new();
+    cache.build(&scene(), 1.0).unwrap();
+    let mut broken = scene();
+    broken.objects.push(sphere(4.0, 7));
+    assert!(cache.build(&broken, 1.0).is_err());
+
+    cache.build(&scene(), 1.0).unwrap();
+    assert_eq!(cache.stats().objects_reused, 3);
+}
+
+#[test]
+fn reloaded_world_matches_a_fresh_build() {
+    let mut cache = SceneCache::new();
+    cache.build(&scene(), 1.0).unwrap();
+    let mut edited = scene();
+    edited.materials[0] = MaterialDesc::Lambertian {
+        albedo: Color::new(0.1, 0.8, 0.1),
+    };
+    edited.objects.swap(0, 1);
+    let (reloaded, _) = cache.build(&edited, 1.0).unwrap();
+    let (fresh, _) = edited.build(1.0).unwrap();
+
+    for x in [-2.0, 0.0, 2.0, 4.0] {
+        let r = Ray::new(Point3::new(x, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let (a, b) = (reloaded.hit(&r, ray_t), fresh.hit(&r, ray_t));
+        assert_eq!(a.is_some(), b.is_some(), "x = {x}");
+        if let (Some(a), Some(b)) = (a, b) {
+            assert_eq!(a.t, b.t);
+            assert_eq!(a.material.get().to_desc(), b.material.get().to_desc());
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use rtt::camera::Camera;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::render::Renderer;
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn renderer() -> Renderer {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -1.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    Renderer::new(Arc::new(world), camera, 8, 8)
+}
+
+#[test]
+fn passes_accumulate_samples() {
+    let mut r = renderer();
+    let first = r.render_pass(2).unwrap();
+    let second = r.render_pass(3).unwrap();
+    assert_eq!(r.samples(), 5);
+    for (x, y) in [(0, 0), (4, 4), (7, 3)] {
+        assert_eq!(first.pixel(x, y).unwrap().weight_sum, 2.0);
+        assert_eq!(second.pixel(x, y).unwrap().weight_sum, 5.0);
+    }
+    // Snapshots are independent of later passes.
+    r.render_pass(1).unwrap();
+    assert_eq!(second.pixel(0, 0).unwrap().weight_sum, 5.0);
+    assert_eq!(second.develop(1.0).unwrap().dimensions(), (8, 8));
+}
+
+#[test]
+fn reset_starts_over() {
+    let mut r = renderer();
+    r.render_pass(4).unwrap();
+    r.reset().unwrap();
+    assert_eq!(r.samples(), 0);
+    assert_eq!(r.film().pixel(4, 4).unwrap().weight_sum, 0.0);
+    let snapshot = r.render_pass(1).unwrap();
+    assert_eq!(snapshot.pixel(4, 4).unwrap().weight_sum, 1.0);
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use rtt::aov::{Aov, AovSet};
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::{DiffuseLight, Lambertian};
+use rtt::render::{noisy_pixels, render_image_with, repair_pixels, RenderSettings};
+use rtt::vec3::{Color, Point3, Vec3};
+
+const SIZE: u32 = 8;
+
+// Grey ball under a small lamp against the sky: the ball's pixels are far noisier than the
+// sky's, which every sample sees the same.
+fn scene() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 3.0, 0.0),
+        0.1,
+        Arc::new(DiffuseLight::new(Color::new(100.0, 100.0, 100.0))),
+    )));
+    let camera = Camera::new(
+        Point3::new(0.0, 0.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30.0,
+        1.0,
+        0.0,
+        3.0,
+    );
+    (world, camera)
+}
+
+#[test]
+fn repairs_only_noisy_pixels() {
+    let (world, camera) = scene();
+    let settings = RenderSettings::default();
+    let film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[Aov::RelativeError], &world, SIZE, SIZE);
+    render_image_with(&world, &camera, &film, &aovs, 16, &settings, &|_| {}).unwrap();
+
+    let noisy = noisy_pixels(&film, &aovs, 0.01).unwrap();
+    let center = (SIZE / 2, SIZE / 2);
+    assert!(noisy.contains(&center));
+    assert!(!noisy.contains(&(0, 0)));
+    let before = aovs.relative_error(center.0, center.1).unwrap().unwrap();
+
+    repair_pixels(&world, &camera, &film, &aovs, &noisy, 240, &settings).unwrap();
+    assert_eq!(film.pixel(center.0, center.1).unwrap().weight_sum, 256.0);
+    assert_eq!(film.pixel(0, 0).unwrap().weight_sum, 16.0);
+    // Sixteen times the samples, so about a quarter of the error.
+    let after = aovs.relative_error(center.0, center.1).unwrap().unwrap();
+    assert!(after < 0.5 * before, "{after} vs {before}");
+}
+
+#[test]
+fn discards_poisoned_pixels() {
+    let (world, camera) = scene();
+    let settings = RenderSettings::default();
+    let mut film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[], &world, SIZE, SIZE);
+    render_image_with(&world, &camera, &film, &aovs, 4, &settings, &|_| {}).unwrap();
+    let nan = Color::new(f64::NAN, 0.0, 0.0);
+    film.add_sample_alpha(3, 5, nan, 1.0, 1.0).unwrap();
+
+    // Without an error AOV only the poisoned pixel is picked, whatever the threshold.
+    let noisy = noisy_pixels(&film, &aovs, 0.0).unwrap();
+    assert_eq!(noisy, [(3, 5)]);
+    repair_pixels(&world, &camera, &film, &aovs, &noisy, 8, &settings).unwrap();
+    let pixel = film.pixel(3, 5).unwrap();
+    assert!(pixel.is_finite());
+    assert_eq!(pixel.weight_sum, 8.0);
+
+    assert!(repair_pixels(&world, &camera, &film, &aovs, &[(SIZE, 0)], 1, &settings).is_err());
+}
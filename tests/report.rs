@@ -0,0 +1,67 @@
+use rtt::film::Film;
+use rtt::report::{exposure_report, false_color, ExposureStats, BINS_PER_STOP, MIN_STOPS};
+use rtt::vec3::Color;
+
+fn grey(l: f64) -> Color {
+    Color::new(l, l, l)
+}
+
+#[test]
+fn statistics_measure_against_display_white() {
+    let colors = [grey(0.0), grey(0.045), grey(0.18), grey(0.72), grey(2.88)];
+    let stats = ExposureStats::new(&colors);
+    assert_eq!(stats.pixels, 5);
+    assert!((stats.min - 0.0).abs() < 1e-12);
+    assert!((stats.max - 2.88).abs() < 1e-9);
+    assert!((stats.mean - 3.825 / 5.0).abs() < 1e-9);
+    assert!((stats.clipped - 0.2).abs() < 1e-12);
+    assert!((stats.crushed - 0.2).abs() < 1e-12);
+    // The lit pixels are -2, 0, 2 and 4 stops from 0.18, so one stop above it on average.
+    assert!(
+        (stats.log_average - 0.36).abs() < 1e-9,
+        "{}",
+        stats.log_average
+    );
+    assert!((stats.exposure_to_mid_grey + 1.0).abs() < 1e-9);
+
+    assert_eq!(stats.histogram.iter().sum::<u32>(), 5);
+    // Black lands in the first bin.
+    assert_eq!(stats.histogram[0], 1);
+    let bin = ((0.18f64.log2() - MIN_STOPS as f64) * BINS_PER_STOP as f64) as usize;
+    assert_eq!(stats.histogram[bin], 1);
+    assert!(ExposureStats::bin_luminance(bin) <= 0.18);
+    assert!(ExposureStats::bin_luminance(bin + 1) > 0.18);
+}
+
+#[test]
+fn false_color_marks_the_zones() {
+    assert_eq!(false_color(1.5).0, [255, 0, 0]);
+    assert_eq!(false_color(0.7).0, [255, 220, 0]);
+    assert_eq!(false_color(0.19).0, [0, 200, 0]);
+    assert_eq!(false_color(0.001).0, [40, 0, 160]);
+    let [r, g, b] = false_color(0.04).0;
+    assert!(r == g && g == b && r > 0);
+}
+
+#[test]
+fn report_images_stack_the_histogram_under_the_view() {
+    let mut film = Film::new(64, 16);
+    for y in 0..16 {
+        for x in 0..64 {
+            let l = if x < 32 { 0.18 } else { 2.0 };
+            film.add_sample(x, y, grey(l), 1.0).unwrap();
+        }
+    }
+    let (stats, img) = exposure_report(&film, 1.0).unwrap();
+    assert_eq!(img.dimensions(), (64, 16 + 32));
+    assert_eq!(img.get_pixel(0, 0).0, [0, 200, 0]);
+    assert_eq!(img.get_pixel(63, 15).0, [255, 0, 0]);
+    assert!((stats.clipped - 0.5).abs() < 1e-12);
+    // Two equal bars reach the top of the panel; the bottom row under empty bins stays black.
+    let tall: Vec<u32> = (0..64)
+        .filter(|&x| img.get_pixel(x, 16).0 != [0, 0, 0])
+        .collect();
+    assert!(!tall.is_empty());
+    assert!(tall.iter().all(|&x| img.get_pixel(x, 47).0 != [0, 0, 0]));
+    assert_eq!(img.get_pixel(0, 47).0, [0, 0, 0]);
+}
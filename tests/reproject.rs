@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::render::Renderer;
+use rtt::reproject::reproject;
+use rtt::vec3::{Color, Point3, Vec3};
+
+// A wall, nearly flat, 10 units in front of the origin, with a ball in front of its left side.
+fn wall() -> HittableList {
+    let mut world = HittableList::new();
+    let grey = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -1010.0),
+        1000.0,
+        grey.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-2.5, 0.0, -5.0),
+        1.0,
+        grey,
+    )));
+    world
+}
+
+// 8x8 pixels 2.5 units wide at the wall.
+fn camera_at(x: f64) -> Camera {
+    Camera::new(
+        Point3::new(x, 0.0, 0.0),
+        Point3::new(x, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        90.0,
+        1.0,
+        0.0,
+        10.0,
+    )
+}
+
+// Each pixel's color records where it is.
+fn marked_film() -> Film {
+    let mut film = Film::new(8, 8);
+    for y in 0..8 {
+        for x in 0..8 {
+            let color = Color::new(x as f64, y as f64, 0.0);
+            film.add_sample_alpha(x, y, color, 1.0, 16.0).unwrap();
+        }
+    }
+    film
+}
+
+#[test]
+fn a_still_camera_keeps_everything_down_to_the_history() {
+    let world = wall();
+    let film = marked_film();
+    let out = reproject(&world, &camera_at(0.0), &camera_at(0.0), &film, 4.0).unwrap();
+    for y in 0..8 {
+        for x in 0..8 {
+            let pixel = out.pixel(x, y).unwrap();
+            assert_eq!(pixel.weight_sum, 4.0);
+            assert_eq!(pixel.resolve(1.0), film.pixel(x, y).unwrap().resolve(1.0));
+        }
+    }
+}
+
+#[test]
+fn pixels_follow_the_surface_they_saw() {
+    let world = wall();
+    let film = marked_film();
+    // One pixel's width to the right at the wall.
+    let out = reproject(&world, &camera_at(0.0), &camera_at(2.5), &film, 4.0).unwrap();
+    for y in 2..6 {
+        for x in 3..7 {
+            let carried = out.pixel(x, y).unwrap().resolve(1.0);
+            assert_eq!(
+                carried,
+                Color::new(x as f64 + 1.0, y as f64, 0.0),
+                "({x}, {y})"
+            );
+        }
+        // Newly in view.
+        assert_eq!(out.pixel(7, y).unwrap().weight_sum, 0.0);
+    }
+    // Wall the ball hid before the move, seen past its right edge now.
+    let revealed = (0..8)
+        .flat_map(|x| (0..8).map(move |y| (x, y)))
+        .filter(|&(x, y)| x < 7 && out.pixel(x, y).unwrap().weight_sum == 0.0)
+        .count();
+    assert!(revealed > 0);
+}
+
+#[test]
+fn renderer_keeps_samples_across_camera_moves() {
+    let world = Arc::new(wall());
+    let mut r = Renderer::new(world.clone(), camera_at(0.0), 8, 8).with_reprojection(2.0);
+    r.render_pass(8).unwrap();
+    r.set_camera(camera_at(0.0)).unwrap();
+    assert_eq!(r.samples(), 0);
+    assert_eq!(r.film().pixel(4, 4).unwrap().weight_sum, 2.0);
+    let snapshot = r.render_pass(1).unwrap();
+    assert_eq!(snapshot.pixel(4, 4).unwrap().weight_sum, 3.0);
+
+    let mut plain = Renderer::new(world, camera_at(0.0), 8, 8);
+    plain.render_pass(8).unwrap();
+    plain.set_camera(camera_at(0.0)).unwrap();
+    assert_eq!(plain.film().pixel(4, 4).unwrap().weight_sum, 0.0);
+}
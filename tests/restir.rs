@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use rtt::aov::AovSet;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::light::QuadLight;
+use rtt::material::Lambertian;
+use rtt::render::{render_image_with, RenderSettings};
+use rtt::restir::Restir;
+use rtt::vec3::{Color, Point3, Vec3};
+
+const SIZE: u32 = 16;
+
+// Grey ground under a grid of 64 small downward lights of very different strengths.
+fn many_lights() -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    for k in 0..64 {
+        let (x, z) = ((k % 8) as f64 - 3.5, (k / 8) as f64 - 3.5);
+        let strength = 4.0 * ((k * 37) % 11) as f64;
+        world.add(Arc::new(QuadLight::new(
+            Point3::new(x - 0.1, 2.0, z - 0.1),
+            Vec3::new(0.2, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.2),
+            Color::new(strength, strength, strength),
+        )));
+    }
+    world
+}
+
+fn render(world: &HittableList, samples: u32, settings: &RenderSettings) -> Vec<Color> {
+    let camera = Camera::new(
+        Point3::new(0.0, 1.5, 0.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        90.0,
+        1.0,
+        0.0,
+        1.5,
+    );
+    let film = Film::new(SIZE, SIZE);
+    let aovs = AovSet::new(&[], world, SIZE, SIZE);
+    render_image_with(world, &camera, &film, &aovs, samples, settings, &|_| {}).unwrap();
+    film.resolve(1.0).unwrap()
+}
+
+fn mean(image: &[Color]) -> f64 {
+    image.iter().map(|c| c.g()).sum::<f64>() / image.len() as f64
+}
+
+fn squared_error(image: &[Color], reference: &[Color]) -> f64 {
+    image
+        .iter()
+        .zip(reference)
+        .map(|(a, b)| (a.g() - b.g()).powi(2))
+        .sum::<f64>()
+        / image.len() as f64
+}
+
+#[test]
+fn resampling_agrees_and_is_less_noisy() {
+    let world = many_lights();
+    let plain = RenderSettings::default();
+    let restir = plain.with_restir(Restir::default());
+    let reference = render(&world, 1024, &plain);
+
+    let (mut plain_error, mut restir_error) = (0.0, 0.0);
+    let mut restir_mean = 0.0;
+    let runs = 8;
+    for _ in 0..runs {
+        plain_error += squared_error(&render(&world, 4, &plain), &reference);
+        let image = render(&world, 4, &restir);
+        restir_error += squared_error(&image, &reference);
+        restir_mean += mean(&image) / runs as f64;
+    }
+    let expected = mean(&reference);
+    assert!(
+        (restir_mean - expected).abs() < 0.03 * expected,
+        "{restir_mean} vs {expected}"
+    );
+    assert!(
+        restir_error < 0.5 * plain_error,
+        "{restir_error} vs {plain_error}"
+    );
+}
+
+#[test]
+fn settings_default_missing_fields() {
+    let settings: RenderSettings =
+        serde_json::from_str(r#"{"restir": {"candidates": 8}}"#).unwrap();
+    assert_eq!(settings.restir, Some(Restir::default().with_candidates(8)));
+    let json = serde_json::to_string(&RenderSettings::default()).unwrap();
+    assert!(!json.contains("restir"));
+}
@@ -0,0 +1,73 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::procgen::icosphere;
+use rtt::scatter::{jittered_grid, Scatter};
+use rtt::vec3::{Point3, Vec3};
+
+fn min_spacing(points: &[Point3]) -> f64 {
+    let mut min = f64::INFINITY;
+    for (i, p) in points.iter().enumerate() {
+        for q in &points[i + 1..] {
+            min = min.min((*p - *q).length());
+        }
+    }
+    min
+}
+
+#[test]
+fn plane_points_keep_their_distance_and_fill_the_area() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let (min, max) = (Point3::new(-5.0, 1.0, 0.0), Point3::new(5.0, 1.0, 10.0));
+    let points = Scatter::new(0.5).plane(min, max, &mut rng);
+    assert!(min_spacing(&points) >= 0.5);
+    for p in &points {
+        assert!(p.x >= min.x && p.x < max.x && p.z >= min.z && p.z < max.z);
+        assert_eq!(p.y, 1.0);
+    }
+    // Maximal Poisson-disk sets cover roughly 0.6-0.7 of the hexagonal packing density.
+    let packed = 100.0 / (0.5f64.powi(2) * 3f64.sqrt() / 2.0);
+    let fill = points.len() as f64 / packed;
+    assert!((0.5..0.9).contains(&fill), "fill {fill}");
+}
+
+#[test]
+fn density_map_thins_points() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let (min, max) = (Point3::new(0.0, 0.0, 0.0), Point3::new(10.0, 0.0, 10.0));
+    let all = Scatter::new(0.4).plane(min, max, &mut rng).len();
+    let points = Scatter::new(0.4)
+        .with_density(|p| if p.x < 5.0 { 0.0 } else { 0.5 })
+        .plane(min, max, &mut rng);
+    assert!(points.iter().all(|p| p.x >= 5.0));
+    let expected = all as f64 / 4.0;
+    assert!((points.len() as f64 - expected).abs() < 0.25 * expected);
+}
+
+#[test]
+fn surface_points_lie_on_the_mesh() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let sphere = icosphere(2.0, 3);
+    let samples = Scatter::new(0.3).surface(&sphere, &mut rng);
+    let points: Vec<Point3> = samples.iter().map(|(p, _)| *p).collect();
+    assert!(points.len() > 200, "{} points", points.len());
+    assert!(min_spacing(&points) >= 0.3);
+    for (p, n) in &samples {
+        assert!(p.length() > 1.95 && p.length() <= 2.0 + 1e-9);
+        assert!(Vec3::dot(*n, Vec3::unit_vector(*p)) > 0.95);
+    }
+}
+
+#[test]
+fn jittered_grid_has_one_point_per_cell() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let (min, max) = (Point3::new(-2.0, 0.0, -1.0), Point3::new(2.0, 0.0, 1.0));
+    let points = jittered_grid(min, max, 0.5, 0.9, &mut rng);
+    assert_eq!(points.len(), 8 * 4);
+    for (k, p) in points.iter().enumerate() {
+        let (i, j) = ((k / 4) as f64, (k % 4) as f64);
+        assert!((p.x - (min.x + (i + 0.5) * 0.5)).abs() <= 0.45 * 0.5);
+        assert!((p.z - (min.z + (j + 0.5) * 0.5)).abs() <= 0.45 * 0.5);
+    }
+    let regular = jittered_grid(min, max, 1.0, 0.0, &mut rng);
+    assert_eq!(regular[0], Point3::new(-1.5, 0.0, -0.5));
+}
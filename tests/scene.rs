@@ -0,0 +1,158 @@
+use rtt::camera::{LensEffects, Shutter, ShutterCurve};
+use rtt::hittable::{ClipPlane, Hittable, Primitive};
+use rtt::interval::Interval;
+use rtt::ray::Ray;
+use rtt::scene::{BackgroundDesc, CameraDesc, MaterialDesc, ObjectDesc, SceneDesc};
+use rtt::stats::SceneStats;
+use rtt::vec3::{Color, Point3, Vec3};
+use rtt::Error;
+
+fn scene() -> SceneDesc {
+    SceneDesc {
+        camera: CameraDesc {
+            look_from: Point3::new(0.0, 0.0, 5.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            focus_dist: 5.0,
+            lens: LensEffects {
+                distortion: -0.1,
+                ..Default::default()
+            },
+            shutter: Shutter {
+                curve: ShutterCurve::Tabulated(vec![1.0, 2.0]),
+                ..Shutter::new(0.0, 1.0)
+            },
+            clipping: Some((0.5, 100.0)),
+            ..Default::default()
+        },
+        materials: vec![
+            MaterialDesc::Lambertian {
+                albedo: Color::new(0.5, 0.5, 0.5),
+            },
+            MaterialDesc::DiffuseLight {
+                emit: Color::new(4.0, 4.0, 4.0),
+                group: Some("key".to_string()),
+            },
+        ],
+        objects: vec![
+            ObjectDesc::Sphere {
+                center: Point3::new(0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: 0,
+            },
+            ObjectDesc::Clipped {
+                object: Box::new(ObjectDesc::Holdout {
+                    object: Box::new(ObjectDesc::MovingSphere {
+                        center0: Point3::new(3.0, 0.0, 0.0),
+                        center1: Point3::new(3.0, 1.0, 0.0),
+                        time0: 0.0,
+                        time1: 1.0,
+                        radius: 0.5,
+                        material: 1,
+                    }),
+                }),
+                planes: vec![ClipPlane::new(
+                    Point3::new(3.0, 0.0, 0.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                )],
+            },
+        ],
+        background: Some(BackgroundDesc::SunSky {
+            sun_direction: Vec3::new(0.6, 0.8, 0.0),
+            turbidity: 4.0,
+            intensity: 0.5,
+            sun_intensity: 800.0,
+            ground: Color::new(0.1, 0.1, 0.1),
+        }),
+        meters_per_unit: None,
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    let desc = scene();
+    let json = desc.to_json().unwrap();
+    assert_eq!(SceneDesc::from_json(&json).unwrap(), desc);
+}
+
+#[test]
+fn built_world_matches_description() {
+    let (world, _camera) = scene().build(1.0).unwrap();
+    assert_eq!(world.len(), 2);
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = world
+        .hit(&ray, Interval::new(0.001, f64::INFINITY))
+        .unwrap();
+    assert!((rec.t - 4.0).abs() < 1e-9);
+    assert!(!rec.holdout);
+
+    let ray = Ray::new(Point3::new(3.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = world
+        .hit(&ray, Interval::new(0.001, f64::INFINITY))
+        .unwrap();
+    assert!(rec.holdout);
+    assert_eq!(rec.material.get().light_group().as_deref(), Some("key"));
+}
+
+#[test]
+fn spheres_are_stored_inline() {
+    let (world, _camera) = scene().build(1.0).unwrap();
+    assert!(matches!(*world.get(0).unwrap(), Primitive::Sphere(_)));
+    assert!(matches!(*world.get(1).unwrap(), Primitive::Dyn(_)));
+    let stats = SceneStats::new(&world);
+    assert_eq!(stats.primitives["Sphere"], 1);
+    assert_eq!(stats.primitives["MovingSphere"], 1);
+}
+
+#[test]
+fn dangling_material_index_is_an_error() {
+    let mut desc = scene();
+    desc.objects.push(ObjectDesc::Sphere {
+        center: Point3::new(0.0, 0.0, 0.0),
+        radius: 1.0,
+        material: 7,
+    });
+    assert!(matches!(desc.build(1.0), Err(Error::Scene(_))));
+}
+
+#[test]
+fn reserved_light_group_names_are_an_error() {
+    for name in ["background", "emission"] {
+        let mut desc = scene();
+        desc.materials[1] = MaterialDesc::DiffuseLight {
+            emit: Color::new(4.0, 4.0, 4.0),
+            group: Some(name.to_string()),
+        };
+        assert!(matches!(desc.build(1.0), Err(Error::Scene(_))), "{name}");
+    }
+}
+
+#[test]
+fn malformed_json_is_an_error() {
+    assert!(matches!(
+        SceneDesc::from_json("{\"camera\": 3}"),
+        Err(Error::Json(_))
+    ));
+}
+
+#[test]
+fn exported_world_round_trips() {
+    let desc = scene();
+    let (world, _camera) = desc.build(1.0).unwrap();
+    let exported = SceneDesc::from_world(&world, desc.camera.clone()).unwrap();
+    assert_eq!(exported, desc);
+}
+
+#[test]
+fn export_shares_materials() {
+    let mut desc = scene();
+    desc.objects.push(ObjectDesc::Sphere {
+        center: Point3::new(0.0, -101.0, 0.0),
+        radius: 100.0,
+        material: 0,
+    });
+    let (world, _camera) = desc.build(1.0).unwrap();
+    let exported = SceneDesc::from_world(&world, desc.camera.clone()).unwrap();
+    assert_eq!(exported.materials.len(), 2);
+    assert_eq!(exported, desc);
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "script")]
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::hittable::{HitRecord, Hittable, Sphere};
+use rtt::interval::Interval;
+use rtt::material::Material;
+use rtt::ray::Ray;
+use rtt::scene::MaterialDesc;
+use rtt::script::{reload_changed, Script, ScriptedMaterial, ERROR_COLOR};
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn script_file(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rtt-script-{name}-{}.rhai", std::process::id()));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+// Hit on top of a unit sphere at the origin, looking down.
+fn top_hit(material: Arc<dyn Material>) -> HitRecord {
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material);
+    let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    sphere.hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap()
+}
+
+fn albedo(material: &dyn Material, rec: &HitRecord) -> Color {
+    let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let mut rng = StdRng::seed_from_u64(1);
+    material.scatter(&r, rec, &mut rng).unwrap().0
+}
+
+#[test]
+fn script_colors_the_surface_from_the_shading_point() {
+    let path = script_file("color", "fn albedo(p, n, uv) { [p[1] * 0.5, n[1], uv[1]] }");
+    let material: Arc<dyn Material> = Arc::new(ScriptedMaterial::new(Script::load(&path)));
+    let rec = top_hit(material.clone());
+    let c = albedo(&*material, &rec);
+    assert!((c.r() - 0.5).abs() < 1e-9, "{c:?}");
+    assert!((c.g() - 1.0).abs() < 1e-9, "{c:?}");
+    assert!((c.b() - rec.v).abs() < 1e-9, "{c:?}");
+    assert_eq!(material.emitted(&rec), Color::default());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn emit_is_optional_and_integers_count() {
+    let path = script_file(
+        "emit",
+        "fn albedo(p, n, uv) { [0, 0, 0] }\nfn emit(p, n, uv) { [2, 1.5, 1] }",
+    );
+    let material: Arc<dyn Material> = Arc::new(ScriptedMaterial::new(Script::load(&path)));
+    let rec = top_hit(material.clone());
+    assert_eq!(material.emitted(&rec), Color::new(2.0, 1.5, 1.0));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn broken_scripts_render_magenta() {
+    for (name, source) in [
+        ("syntax", "fn albedo(p, n, uv) { [1, 2 "),
+        ("missing", "fn color(p) { [1, 1, 1] }"),
+        ("shape", "fn albedo(p, n, uv) { [1, 1] }"),
+        ("runaway", "fn albedo(p, n, uv) { loop {} }"),
+    ] {
+        let path = script_file(name, source);
+        let material: Arc<dyn Material> = Arc::new(ScriptedMaterial::new(Script::load(&path)));
+        let rec = top_hit(material.clone());
+        assert_eq!(albedo(&*material, &rec), ERROR_COLOR, "{name}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn edits_are_picked_up_by_reload() {
+    let path = script_file("reload", "fn albedo(p, n, uv) { [1, 0, 0] }");
+    let script = Script::load(&path);
+    let material: Arc<dyn Material> = Arc::new(ScriptedMaterial::new(script.clone()));
+    let rec = top_hit(material.clone());
+    assert_eq!(albedo(&*material, &rec), Color::new(1.0, 0.0, 0.0));
+
+    std::fs::write(&path, "fn albedo(p, n, uv) { [0, 0, 1] }").unwrap();
+    script.reload().unwrap();
+    assert_eq!(albedo(&*material, &rec), Color::new(0.0, 0.0, 1.0));
+
+    // A broken edit keeps the last version that compiled.
+    std::fs::write(&path, "fn albedo(p, n, uv) {").unwrap();
+    assert!(script.reload().is_err());
+    assert_eq!(albedo(&*material, &rec), Color::new(0.0, 0.0, 1.0));
+    // Already read, so nothing changed since.
+    assert_eq!(reload_changed(), 0);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn scripts_are_shared_and_saved_by_path() {
+    let path = script_file("shared", "fn albedo(p, n, uv) { [0.5, 0.5, 0.5] }");
+    assert!(Arc::ptr_eq(&Script::load(&path), &Script::load(&path)));
+    let material = MaterialDesc::Script { path: path.clone() }.build();
+    assert_eq!(
+        material.to_desc(),
+        Some(MaterialDesc::Script { path: path.clone() })
+    );
+    std::fs::remove_file(&path).unwrap();
+}
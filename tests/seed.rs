@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use rtt::aov::AovSet;
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::film::Film;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::light::QuadLight;
+use rtt::material::Lambertian;
+use rtt::render::{render_image_with, RenderSettings, Renderer, SeedMode};
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Pale ground and ball under a lamp in the dark.
+fn lamp_lit() -> HittableList {
+    let mut world = HittableList::new();
+    let grey = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        grey.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.5, 0.0), 0.5, grey)));
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-0.5, 2.0, -0.5),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+    world
+}
+
+fn camera() -> Camera {
+    Camera::new(
+        Point3::new(0.0, 1.5, 3.0),
+        Point3::new(0.0, 0.3, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        3.0,
+    )
+}
+
+fn render(settings: &RenderSettings) -> Vec<Color> {
+    let world = lamp_lit();
+    let film = Film::new(8, 8);
+    let aovs = AovSet::new(&[], &world, 8, 8);
+    render_image_with(&world, &camera(), &film, &aovs, 4, settings, &|_| {}).unwrap();
+    film.resolve(1.0).unwrap()
+}
+
+#[test]
+fn the_same_seed_gives_the_same_noise() {
+    let settings = RenderSettings::default().with_seed(7);
+    assert_eq!(render(&settings), render(&settings));
+    assert_ne!(render(&settings), render(&settings.with_seed(8)));
+    assert_eq!(
+        render(&settings.with_guiding(true)),
+        render(&settings.with_guiding(true))
+    );
+}
+
+#[test]
+fn seeded_passes_take_new_samples() {
+    let world = Arc::new(lamp_lit());
+    let pass = |renderer: &mut Renderer| renderer.render_pass(2).unwrap().resolve(1.0).unwrap();
+    let settings = RenderSettings::default().with_seed(3);
+    let mut a = Renderer::new(world.clone(), camera(), 8, 8).with_settings(settings);
+    let mut b = Renderer::new(world, camera(), 8, 8).with_settings(settings);
+    let first = pass(&mut a);
+    let second = pass(&mut a);
+    // Repeating the first pass's samples would leave the average unchanged.
+    assert_ne!(first, second);
+    assert_eq!(first, pass(&mut b));
+    assert_eq!(second, pass(&mut b));
+}
+
+#[test]
+fn seed_modes() {
+    assert_eq!(SeedMode::Fixed.seed(5, 0), SeedMode::Fixed.seed(5, 9));
+    let frames: Vec<u64> = (0..4).map(|f| SeedMode::Decorrelated.seed(5, f)).collect();
+    for (i, a) in frames.iter().enumerate() {
+        assert!(frames[i + 1..].iter().all(|b| a != b));
+    }
+    for mode in SeedMode::ALL {
+        assert_eq!(SeedMode::from_name(mode.name()), Some(mode));
+    }
+    assert_eq!(SeedMode::from_name("random"), None);
+}
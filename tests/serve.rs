@@ -0,0 +1,277 @@
+#![cfg(feature = "serve")]
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use rtt::serve::{ServeLimits, Service};
+
+// A lit ball, small enough to render in moments.
+const SCENE: &str = r#"{
+    "camera": {"look_from": [0, 0, 3], "look_at": [0, 0, 0], "vup": [0, 1, 0], "vfov": 30,
+        "focus_dist": 3},
+    "materials": [{"type": "lambertian", "albedo": [0.5, 0.5, 0.5]}],
+    "objects": [{"type": "sphere", "center": [0, 0, 0], "radius": 0.5, "material": 0}]
+}"#;
+
+fn start(limits: ServeLimits) -> SocketAddr {
+    let service = Service::bind("127.0.0.1:0", limits).unwrap();
+    let addr = service.addr().unwrap();
+    std::thread::spawn(move || service.run());
+    addr
+}
+
+struct Reply {
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> Reply {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\
+         Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).unwrap();
+    let split = reply.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8_lossy(&reply[..split]).to_string();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    let content_type = head
+        .lines()
+        .find_map(|l| l.strip_prefix("Content-Type: "))
+        .unwrap_or("")
+        .to_string();
+    Reply {
+        status,
+        content_type,
+        body: reply[split + 4..].to_vec(),
+    }
+}
+
+fn submit(addr: SocketAddr, width: u32, height: u32, samples: u32) -> Reply {
+    let body = format!(
+        r#"{{"scene": {SCENE}, "width": {width}, "height": {height}, "samples": {samples}}}"#
+    );
+    request(addr, "POST", "/jobs", &body)
+}
+
+// The job's status once it stops rendering.
+fn wait(addr: SocketAddr, id: u64) -> serde_json::Value {
+    let start = Instant::now();
+    loop {
+        let status = request(addr, "GET", &format!("/jobs/{id}"), "").json();
+        if status["state"] != "rendering" {
+            return status;
+        }
+        assert!(start.elapsed() < Duration::from_secs(30), "{status}");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn jobs_render_and_serve_their_images() {
+    let addr = start(ServeLimits::default());
+    let reply = submit(addr, 16, 8, 2);
+    assert_eq!(reply.status, 201);
+    let id = reply.json()["id"].as_u64().unwrap();
+
+    let status = wait(addr, id);
+    assert_eq!(status["state"], "done");
+    assert_eq!(status["progress"], 1.0);
+    assert_eq!(status["id"], id);
+
+    let png = request(addr, "GET", &format!("/jobs/{id}/image.png"), "");
+    assert_eq!((png.status, png.content_type.as_str()), (200, "image/png"));
+    let image = image::load_from_memory(&png.body).unwrap();
+    assert_eq!((image.width(), image.height()), (16, 8));
+    let exr = request(addr, "GET", &format!("/jobs/{id}/image.exr"), "");
+    assert_eq!(exr.status, 200);
+    assert!(exr.body.starts_with(&[0x76, 0x2f, 0x31, 0x01]));
+
+    for path in [
+        format!("/jobs/{id}/image.gif"),
+        "/jobs/99".to_string(),
+        "/jobs/99/image.png".to_string(),
+        "/jobs/x".to_string(),
+        "/elsewhere".to_string(),
+    ] {
+        assert_eq!(request(addr, "GET", &path, "").status, 404, "{path}");
+    }
+}
+
+#[test]
+fn bad_requests_are_refused() {
+    let addr = start(ServeLimits::default());
+    assert_eq!(submit(addr, 16, 8, 0).status, 400);
+    assert_eq!(submit(addr, 0, 8, 1).status, 400);
+    let garbled = request(addr, "POST", "/jobs", "{\"scene\": 1}");
+    assert_eq!(garbled.status, 400);
+    assert!(garbled.json()["error"]
+        .as_str()
+        .unwrap()
+        .contains("invalid"));
+    // Nothing was queued.
+    assert_eq!(request(addr, "GET", "/jobs/0", "").status, 404);
+}
+
+#[test]
+fn oversized_jobs_are_refused_before_allocating() {
+    let addr = start(ServeLimits::default());
+    let reply = submit(addr, 100_000, 100_000, 1);
+    assert_eq!(reply.status, 413);
+    assert!(reply.json()["error"].as_str().unwrap().contains("pixels"));
+
+    let addr = start(ServeLimits {
+        memory_budget: Some(1 << 20),
+        ..Default::default()
+    });
+    assert_eq!(submit(addr, 1024, 1024, 1).status, 413);
+    assert_eq!(submit(addr, 16, 8, 1).status, 201);
+}
+
+#[test]
+fn finished_jobs_are_forgotten() {
+    let addr = start(ServeLimits {
+        max_finished_jobs: 1,
+        ..Default::default()
+    });
+    let first = submit(addr, 8, 8, 1).json()["id"].as_u64().unwrap();
+    wait(addr, first);
+    let second = submit(addr, 8, 8, 1).json()["id"].as_u64().unwrap();
+    assert_ne!(first, second);
+    wait(addr, second);
+    // Only the most recently finished is kept.
+    assert_eq!(
+        request(addr, "GET", &format!("/jobs/{first}"), "").status,
+        404
+    );
+    assert_eq!(
+        request(addr, "GET", &format!("/jobs/{second}"), "").status,
+        200
+    );
+
+    let addr = start(ServeLimits {
+        job_ttl: Duration::ZERO,
+        ..Default::default()
+    });
+    let id = submit(addr, 8, 8, 1).json()["id"].as_u64().unwrap();
+    let start = Instant::now();
+    while request(addr, "GET", &format!("/jobs/{id}"), "").status == 200 {
+        assert!(start.elapsed() < Duration::from_secs(30));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn requests_over_the_limits_are_refused() {
+    let addr = start(ServeLimits {
+        max_samples: 8,
+        ..Default::default()
+    });
+    let reply = submit(addr, 16, 8, 9);
+    assert_eq!(reply.status, 413);
+    assert!(reply.json()["error"].as_str().unwrap().contains("samples"));
+    assert_eq!(submit(addr, 16, 8, 8).status, 201);
+
+    let addr = start(ServeLimits {
+        max_request_bytes: 64,
+        ..Default::default()
+    });
+    let reply = submit(addr, 16, 8, 1);
+    assert_eq!(reply.status, 413);
+    assert!(reply.json()["error"].as_str().unwrap().contains("64 bytes"));
+}
+
+#[test]
+fn rendering_jobs_can_be_cancelled() {
+    let addr = start(ServeLimits {
+        max_jobs: 1,
+        ..Default::default()
+    });
+    let id = submit(addr, 256, 256, 4096).json()["id"].as_u64().unwrap();
+    // The one job slot is taken.
+    assert_eq!(submit(addr, 8, 8, 1).status, 503);
+
+    let reply = request(addr, "DELETE", &format!("/jobs/{id}"), "");
+    assert_eq!(reply.status, 202);
+    assert_eq!(wait(addr, id)["state"], "cancelled");
+    assert_eq!(submit(addr, 8, 8, 1).status, 201);
+
+    // Finished jobs are forgotten instead.
+    assert_eq!(
+        request(addr, "DELETE", &format!("/jobs/{id}"), "").status,
+        204
+    );
+    assert_eq!(request(addr, "GET", &format!("/jobs/{id}"), "").status, 404);
+    assert_eq!(request(addr, "DELETE", "/jobs/99", "").status, 404);
+}
+
+#[test]
+fn scenes_build_on_the_job_thread() {
+    let addr = start(ServeLimits::default());
+    let dangling = SCENE.replace(r#""material": 0"#, r#""material": 3"#);
+    let body = format!(r#"{{"scene": {dangling}, "width": 8, "height": 8, "samples": 1}}"#);
+    let reply = request(addr, "POST", "/jobs", &body);
+    assert_eq!(reply.status, 201);
+    let status = wait(addr, reply.json()["id"].as_u64().unwrap());
+    assert_eq!(status["state"], "failed");
+    assert!(status["error"].as_str().unwrap().contains("material index"));
+}
+
+#[test]
+fn scene_files_stay_under_the_scene_root() {
+    let root = std::env::temp_dir().join(format!("rtt-serve-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("inside")).unwrap();
+    let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+    std::fs::write(root.join("inside/tri.obj"), obj).unwrap();
+    std::fs::write(root.join("outside.obj"), obj).unwrap();
+    let with_mesh = |path: &str| {
+        let scene = SCENE.replace(
+            r#"{"type": "sphere""#,
+            &format!(r#"{{"type": "mesh", "obj": "{path}", "material": 0}}, {{"type": "sphere""#),
+        );
+        format!(r#"{{"scene": {scene}, "width": 8, "height": 8, "samples": 1}}"#)
+    };
+
+    // Without a root, no file can be read.
+    let addr = start(ServeLimits::default());
+    assert_eq!(
+        request(addr, "POST", "/jobs", &with_mesh("tri.obj")).status,
+        403
+    );
+
+    let addr = start(ServeLimits {
+        scene_root: Some(root.join("inside")),
+        ..Default::default()
+    });
+    let reply = request(addr, "POST", "/jobs", &with_mesh("tri.obj"));
+    assert_eq!(reply.status, 201);
+    assert_eq!(
+        wait(addr, reply.json()["id"].as_u64().unwrap())["state"],
+        "done"
+    );
+    for escape in [
+        "../outside.obj",
+        &root.join("outside.obj").display().to_string(),
+    ] {
+        let reply = request(addr, "POST", "/jobs", &with_mesh(escape));
+        assert_eq!(reply.status, 403, "{escape}");
+        assert!(reply.json()["error"].as_str().unwrap().contains("outside"));
+    }
+    assert_eq!(
+        request(addr, "POST", "/jobs", &with_mesh("missing.obj")).status,
+        400
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+}
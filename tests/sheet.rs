@@ -0,0 +1,81 @@
+use image::{Rgba, RgbaImage};
+use rtt::sheet::{draw_text, text_height, text_width, ContactSheet};
+
+fn thumb(width: u32, height: u32, value: u8) -> RgbaImage {
+    RgbaImage::from_pixel(width, height, Rgba([value, value, value, 255]))
+}
+
+#[test]
+fn cells_fill_rows_of_columns() {
+    let sheet = ContactSheet::new(3);
+    let cells: Vec<(String, RgbaImage)> = (0..5)
+        .map(|i| (format!("cell {i}"), thumb(100, 50, 200)))
+        .collect();
+    let img = sheet.compose(&cells);
+    let label = text_height(1) + 2;
+    assert_eq!(img.width(), 3 * (100 + 8) + 8);
+    assert_eq!(img.height(), 2 * (50 + label + 8) + 8);
+
+    // The fifth cell is in the second row's middle; the sixth is empty.
+    let row2 = 8 + 50 + label + 8;
+    assert_eq!(
+        img.get_pixel(8 + 108 + 50, row2 + 25),
+        &Rgba([200, 200, 200, 255])
+    );
+    assert_eq!(img.get_pixel(8 + 216 + 50, row2 + 25), &sheet.background);
+}
+
+#[test]
+fn fewer_cells_than_columns_make_one_narrow_row() {
+    let img = ContactSheet::new(8).compose(&[("a".into(), thumb(40, 30, 0))]);
+    assert_eq!(
+        (img.width(), img.height()),
+        (40 + 16, 30 + text_height(1) + 2 + 16)
+    );
+}
+
+#[test]
+fn smaller_thumbnails_are_centred() {
+    let cells = vec![
+        ("big".to_string(), thumb(60, 40, 255)),
+        ("small".to_string(), thumb(20, 10, 100)),
+    ];
+    let img = ContactSheet::new(2).compose(&cells);
+    let (x, y) = (8 + 68, 8);
+    assert_eq!(img.get_pixel(x + 30, y + 20), &Rgba([100, 100, 100, 255]));
+    assert_eq!(
+        img.get_pixel(x + 5, y + 5),
+        &ContactSheet::new(2).background
+    );
+}
+
+#[test]
+fn labels_are_drawn_under_their_thumbnail() {
+    let sheet = ContactSheet::new(1);
+    let img = sheet.compose(&[("OK 90°".into(), thumb(100, 50, 0))]);
+    let label_rows = 8 + 50..img.height() - 8;
+    let lit = label_rows
+        .flat_map(|y| (0..img.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| img.get_pixel(x, y) == &sheet.label_color)
+        .count();
+    assert!(lit > 20, "{lit} label pixels");
+    // Nothing is drawn over the thumbnail.
+    assert!((8..58).all(|y| img.get_pixel(58, y) == &Rgba([0, 0, 0, 255])));
+}
+
+#[test]
+fn text_measures_and_clips() {
+    assert_eq!(text_width("", 1), 0);
+    assert_eq!(text_width("A", 1), 5);
+    assert_eq!(text_width("AB", 2), 22);
+
+    let mut img = RgbaImage::new(8, 8);
+    let white = Rgba([255, 255, 255, 255]);
+    // Runs off the right edge without panicking.
+    draw_text(&mut img, 2, 0, "HHH", 1, white);
+    // H's left column, and lowercase drawn as capitals.
+    assert_eq!(img.get_pixel(2, 3), &white);
+    let mut lower = RgbaImage::new(8, 8);
+    draw_text(&mut lower, 2, 0, "hhh", 1, white);
+    assert_eq!(img, lower);
+}
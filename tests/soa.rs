@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rtt::generator::SceneGenerator;
+use rtt::hittable::{Hittable, HittableList, Primitive, Sphere, SOA_MIN_SPHERES};
+use rtt::interval::Interval;
+use rtt::light::QuadLight;
+use rtt::material::{Lambertian, Material};
+use rtt::ray::Ray;
+use rtt::soa::SphereSoA;
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn random_ray(rng: &mut StdRng) -> Ray {
+    let origin = Point3::new(
+        rng.random_range(-20.0..20.0),
+        rng.random_range(0.5..4.0),
+        rng.random_range(-20.0..20.0),
+    );
+    let target = Point3::new(
+        rng.random_range(-10.0..10.0),
+        0.0,
+        rng.random_range(-10.0..10.0),
+    );
+    Ray::new(origin, target - origin)
+}
+
+#[test]
+fn batched_hits_match_sphere_by_sphere() {
+    let world = SceneGenerator::new(7).generate();
+    let mut soa = SphereSoA::new();
+    for (i, obj) in world.iter().enumerate() {
+        let Primitive::Sphere(s) = obj else {
+            panic!("random scenes are all spheres");
+        };
+        soa.push(s, i as u32);
+    }
+    assert_eq!(soa.len(), world.len());
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let ray_t = Interval::new(1e-3, f64::INFINITY);
+    for _ in 0..2000 {
+        let ray = random_ray(&mut rng);
+        let expected = world
+            .iter()
+            .enumerate()
+            .filter_map(|(i, o)| o.hit(&ray, ray_t).map(|h| (i as u32, h)))
+            .min_by(|a, b| a.1.t.total_cmp(&b.1.t));
+        let batched = soa.hit(&ray, ray_t);
+        match (expected, batched) {
+            (Some((id, a)), Some(b)) => {
+                assert_eq!(b.object_id, id);
+                assert_eq!(a.t, b.t);
+                assert_eq!(a.normal, b.normal);
+                assert_eq!(a.front_face, b.front_face);
+                assert_eq!(a.material, b.material);
+            }
+            (None, None) => {}
+            _ => panic!("only one of them hit"),
+        }
+        let (all, batched) = (world.hit_all(&ray, ray_t), soa.hit_all(&ray, ray_t));
+        let key = |h: &rtt::hittable::HitRecord| (h.t, h.object_id);
+        assert_eq!(
+            all.iter().map(key).collect::<Vec<_>>(),
+            batched.iter().map(key).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(soa.bounding_box(0.0, 1.0), world.bounding_box(0.0, 1.0));
+}
+
+// A row of small spheres along x with an emitter in front of the middle ones.
+fn row(n: usize) -> HittableList {
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mut world = HittableList::new();
+    for i in 0..n {
+        let center = Point3::new(i as f64, 0.0, 0.0);
+        world.add(Sphere::new(center, 0.4, Arc::clone(&material)));
+    }
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(1.5, -1.0, 1.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+    )));
+    world
+}
+
+#[test]
+fn lists_mixing_spheres_and_other_objects() {
+    for n in [SOA_MIN_SPHERES - 1, SOA_MIN_SPHERES + 3] {
+        let world = row(n);
+        let light_id = n as u32;
+        let ray_t = Interval::new(1e-3, f64::INFINITY);
+
+        // The light sits in front of spheres 1 to 3.
+        let ray = Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = world.hit(&ray, ray_t).unwrap();
+        assert_eq!((rec.object_id, rec.t), (light_id, 4.0));
+
+        let ray = Ray::new(Point3::new(6.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = world.hit(&ray, ray_t).unwrap();
+        assert_eq!(rec.object_id, 6);
+        assert!((rec.t - 4.6).abs() < 1e-12);
+
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(world.hit(&ray, ray_t).unwrap().object_id, 0);
+    }
+}
+
+#[test]
+fn editing_the_list_after_tracing() {
+    let mut world = row(SOA_MIN_SPHERES);
+    let ray_t = Interval::new(1e-3, f64::INFINITY);
+    let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(world.hit(&ray, ray_t).unwrap().object_id, 0);
+
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Sphere::new(
+        Point3::new(-2.0, 0.0, 0.0),
+        0.4,
+        material.clone(),
+    ));
+    let added = world.len() as u32 - 1;
+    assert_eq!(world.hit(&ray, ray_t).unwrap().object_id, added);
+
+    world.push(Sphere::new(Point3::new(-3.0, 0.0, 0.0), 0.4, material).into());
+    let pushed = world.len() as u32 - 1;
+    assert_eq!(world.hit(&ray, ray_t).unwrap().object_id, pushed);
+
+    // Edits that keep the length start the batch over too.
+    let Some(Primitive::Sphere(s)) = world.get_mut(pushed as usize) else {
+        panic!("pushed a sphere");
+    };
+    s.center = Point3::new(0.0, 5.0, 0.0);
+    assert_eq!(world.hit(&ray, ray_t).unwrap().object_id, added);
+    for o in world.iter_mut() {
+        if let Primitive::Sphere(s) = o {
+            s.center += Vec3::new(0.0, 10.0, 0.0);
+        }
+    }
+    assert!(world.hit(&ray, ray_t).is_none());
+}
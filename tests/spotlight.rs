@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use image::{Rgb, RgbImage};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::hittable::{Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::light::{Light, SpotLight};
+use rtt::material::Lambertian;
+use rtt::ray::Ray;
+use rtt::render::{lights, trace_path_with, RenderSettings};
+use rtt::scene::{MaterialTable, ObjectDesc};
+use rtt::texture::TextureCache;
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Writes a gobo dark on its left half and white on its right.
+fn write_gobo() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rtt-spotlight-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("half.png");
+    RgbImage::from_fn(16, 16, |x, _| Rgb([if x < 8 { 0 } else { 255 }; 3]))
+        .save(&path)
+        .unwrap();
+    path
+}
+
+// Pointing straight down from two units up, with the gobo's right towards +x.
+fn spot(angle: f64) -> SpotLight {
+    SpotLight::new(
+        Point3::new(0.0, 2.0, 0.0),
+        Vec3::new(0.0, -1.0, 0.0),
+        0.2,
+        angle,
+        Color::new(4.0, 4.0, 4.0),
+    )
+    .with_up(Vec3::new(0.0, 0.0, -1.0))
+}
+
+// Leaving the light at `degrees` off its axis towards +x.
+fn off_axis(degrees: f64) -> Vec3 {
+    let a = degrees.to_radians();
+    Vec3::new(a.sin(), -a.cos(), 0.0)
+}
+
+fn stage(light: SpotLight) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(light));
+    world
+}
+
+// Mean light reflected by the ground at x, seen from straight above.
+fn lit(world: &HittableList, x: f64, settings: RenderSettings, n: usize) -> f64 {
+    let lights = lights(world);
+    let mut rng = StdRng::seed_from_u64(3);
+    let ray = Ray::new(Point3::new(x, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+    let sum: f64 = (0..n)
+        .map(|_| {
+            let sample = trace_path_with(ray, world, &lights, ray_t, &settings, &mut rng);
+            sample.emission.iter().map(|(_, c)| c.r()).sum::<f64>()
+        })
+        .sum();
+    sum / n as f64
+}
+
+#[test]
+fn beam_is_a_cone_with_a_soft_edge() {
+    let hard = spot(30.0);
+    assert_eq!(hard.radiance(off_axis(0.0)), Color::new(4.0, 4.0, 4.0));
+    assert_eq!(hard.radiance(off_axis(29.0)), Color::new(4.0, 4.0, 4.0));
+    assert_eq!(hard.radiance(off_axis(31.0)), Color::default());
+    assert_eq!(hard.radiance(Vec3::new(0.0, 1.0, 0.0)), Color::default());
+
+    let soft = spot(30.0).with_softness(0.5);
+    assert_eq!(soft.radiance(off_axis(10.0)), Color::new(4.0, 4.0, 4.0));
+    let fading: Vec<f64> = [16.0, 22.5, 29.0]
+        .iter()
+        .map(|&a| soft.radiance(off_axis(a)).r())
+        .collect();
+    assert!(fading[0] < 4.0 && fading[0] > fading[1] && fading[1] > fading[2]);
+    assert!((fading[1] - 2.0).abs() < 1e-9, "{fading:?}");
+    assert!(fading[2] > 0.0);
+}
+
+#[test]
+fn gobo_patterns_the_beam() {
+    let gobo = TextureCache::shared().texture(write_gobo());
+    let light = spot(30.0).with_gobo(gobo);
+    assert_eq!(light.radiance(off_axis(15.0)), Color::new(4.0, 4.0, 4.0));
+    assert_eq!(light.radiance(off_axis(-15.0)), Color::default());
+
+    // On the ground, +x is lit and -x in the gobo's shadow.
+    let world = stage(light);
+    let settings = RenderSettings::default();
+    let bright = lit(&world, 0.6, settings, 200);
+    let dark = lit(&world, -0.6, settings, 200);
+    let outside = lit(&world, 3.0, settings, 200);
+    assert!(bright > 0.01, "{bright}");
+    assert_eq!(dark, 0.0);
+    assert_eq!(outside, 0.0);
+}
+
+#[test]
+fn light_sampling_agrees_with_brute_force() {
+    let world = stage(spot(40.0).with_softness(0.3));
+    let n = 20_000;
+    let brute = lit(
+        &world,
+        0.5,
+        RenderSettings::default().with_shadow_samples(0),
+        n,
+    );
+    let mis = lit(&world, 0.5, RenderSettings::default(), n);
+    assert!(
+        (mis - brute).abs() < 0.05 * brute,
+        "mis {mis} vs brute force {brute}"
+    );
+}
+
+#[test]
+fn sample_density_matches_pdf() {
+    let light = spot(45.0);
+    let mut rng = StdRng::seed_from_u64(9);
+    let origin = Point3::new(0.5, 0.0, 0.3);
+    for _ in 0..100 {
+        let ls = light.sample(origin, &mut rng).unwrap();
+        let (t, pdf) = light.pdf(origin, ls.direction).unwrap();
+        assert!((t - ls.distance).abs() < 1e-9);
+        assert!((pdf - ls.pdf).abs() < 1e-9 * pdf);
+    }
+    // Behind the light, nothing.
+    let above = Point3::new(0.0, 5.0, 0.0);
+    assert!(light.sample(above, &mut rng).is_none());
+    let r = Ray::new(above, Vec3::new(0.0, -1.0, 0.0));
+    let rec = light.hit(&r, Interval::new(0.001, f64::INFINITY)).unwrap();
+    assert_eq!(rec.material.get().emitted(&rec), Color::default());
+    // Light tracing starts inside the cone.
+    for _ in 0..100 {
+        let emission = light.sample_emission(&mut rng).unwrap();
+        let d = Vec3::unit_vector(emission.ray.direction());
+        assert!(Vec3::dot(d, light.direction()) >= 45f64.to_radians().cos() - 1e-9);
+    }
+}
+
+#[test]
+fn spot_light_round_trips_through_desc() {
+    let gobo = write_gobo();
+    let light = spot(25.0)
+        .with_softness(0.4)
+        .with_gobo(TextureCache::shared().texture(&gobo))
+        .with_group("stage");
+    let desc = light.to_desc(&mut MaterialTable::new()).unwrap();
+    assert!(matches!(
+        desc,
+        ObjectDesc::SpotLight { softness, ref gobo, ref group, .. }
+        if softness == 0.4 && gobo.is_some() && group.as_deref() == Some("stage")
+    ));
+    let built = desc.build(&[]).unwrap();
+    assert_eq!(built.to_desc(&mut MaterialTable::new()), Some(desc));
+    assert_eq!(lights(built.as_ref()).len(), 1);
+
+    let json = r#"{"type": "spot_light", "position": [0, 3, 0], "direction": [0, -1, 0],
+        "angle": 95, "emit": [1, 1, 1]}"#;
+    let wide: ObjectDesc = serde_json::from_str(json).unwrap();
+    assert!(wide.build(&[]).is_err());
+    let json = json.replace("95", "20");
+    let ok: ObjectDesc = serde_json::from_str(&json).unwrap();
+    assert!(matches!(ok, ObjectDesc::SpotLight { radius, .. } if radius > 0.0));
+    assert!(ok.build(&[]).is_ok());
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use rtt::hittable::{HittableList, Holdout, MovingSphere, Sphere};
+use rtt::light::QuadLight;
+use rtt::material::{Lambertian, Material};
+use rtt::mesh::Mesh;
+use rtt::stats::SceneStats;
+use rtt::vec3::{Color, Point3, Vec3};
+
+// Three spheres (one held out), a moving sphere, a quad and a tetrahedron, and a light, with
+// the grey material shared between them.
+fn world() -> HittableList {
+    let grey: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.8, 0.1, 0.1)));
+    let mut world = HittableList::new();
+    world.add(Sphere::new(Point3::new(-2.0, 0.0, 0.0), 0.5, grey.clone()));
+    world.add(Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.5, grey.clone()));
+    world.add(Arc::new(Holdout::new(Arc::new(Sphere::new(
+        Point3::new(2.0, 0.0, 0.0),
+        0.5,
+        red.clone(),
+    )))));
+    world.add(MovingSphere::new(
+        Point3::new(0.0, 2.0, 0.0),
+        Point3::new(0.0, 3.0, 0.0),
+        0.0,
+        1.0,
+        0.5,
+        red,
+    ));
+
+    let quad = vec![
+        Point3::new(-5.0, -1.0, -5.0),
+        Point3::new(5.0, -1.0, -5.0),
+        Point3::new(5.0, -1.0, 5.0),
+        Point3::new(-5.0, -1.0, 5.0),
+    ];
+    let floor = Mesh::new(quad, vec![[0, 1, 2], [0, 2, 3]], grey.clone()).unwrap();
+    world.add(Arc::new(floor));
+    let corners = vec![
+        Point3::new(0.0, 0.0, -3.0),
+        Point3::new(1.0, 0.0, -3.0),
+        Point3::new(0.0, 1.0, -3.0),
+        Point3::new(0.0, 0.0, -2.0),
+    ];
+    let faces = vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+    world.add(Arc::new(Mesh::new(corners, faces, grey).unwrap()));
+
+    world.add(Arc::new(QuadLight::new(
+        Point3::new(-1.0, 4.0, -1.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 2.0),
+        Color::new(4.0, 4.0, 4.0),
+    )));
+    world
+}
+
+#[test]
+fn counts_primitives_triangles_and_materials() {
+    let stats = SceneStats::new(&world());
+    let primitives: Vec<(&str, usize)> = stats.primitives.clone().into_iter().collect();
+    assert_eq!(
+        primitives,
+        [
+            ("Mesh", 2),
+            ("MovingSphere", 1),
+            ("QuadLight", 1),
+            ("Sphere", 3)
+        ]
+    );
+    assert_eq!(stats.primitive_count(), 7);
+    assert_eq!(stats.triangles, 6);
+    // Grey, red and the light's own.
+    assert_eq!(stats.materials, 3);
+    assert_eq!(stats.texture_bytes, 0);
+
+    let bounds = stats.bounds.unwrap();
+    assert!(bounds.min.x <= -5.0 && bounds.max.x >= 5.0, "{bounds:?}");
+    assert!(bounds.min.y <= -1.0 && bounds.max.y >= 4.0, "{bounds:?}");
+    assert!(bounds.min.z <= -5.0 && bounds.max.z >= 5.0, "{bounds:?}");
+}
+
+#[test]
+fn empty_scenes_have_nothing_to_count() {
+    let stats = SceneStats::new(&HittableList::new());
+    assert_eq!(stats.primitive_count(), 0);
+    assert_eq!((stats.triangles, stats.materials), (0, 0));
+    assert!(stats.bvh.is_none());
+}
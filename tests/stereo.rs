@@ -0,0 +1,132 @@
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::camera::{Camera, Projection, Shutter};
+use rtt::stereo::{OdsCamera, StereoCamera, StereoLayout};
+use rtt::vec3::{Point3, Vec3};
+
+const IPD: f64 = 0.064;
+const EYE: Point3 = Point3::new(0.0, 1.0, 0.0);
+
+fn assert_close(got: Vec3, expected: Vec3) {
+    assert!((got - expected).length() < 1e-9, "{got:?} vs {expected:?}");
+}
+
+// Looking down -z, so the eyes sit along x.
+fn stereo(layout: StereoLayout) -> StereoCamera {
+    StereoCamera::new(
+        EYE,
+        Point3::new(0.0, 1.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        2.0,
+        0.0,
+        5.0,
+        IPD,
+        layout,
+    )
+}
+
+fn ods() -> OdsCamera {
+    OdsCamera::new(
+        EYE,
+        Point3::new(0.0, 1.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        IPD,
+        StereoLayout::TopBottom,
+    )
+}
+
+#[test]
+fn eyes_sit_an_ipd_apart() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let left_eye = EYE - Vec3::new(0.5 * IPD, 0.0, 0.0);
+    let right_eye = EYE + Vec3::new(0.5 * IPD, 0.0, 0.0);
+    let sides = stereo(StereoLayout::SideBySide);
+    let stacked = stereo(StereoLayout::TopBottom);
+    // The same point of each eye's view, through either layout.
+    for (camera, left, right) in [
+        (&sides, (0.3, 0.4), (0.8, 0.4)),
+        (&stacked, (0.6, 0.7), (0.6, 0.2)),
+    ] {
+        let left = camera.get_ray(left.0, left.1, &mut rng).unwrap();
+        let right = camera.get_ray(right.0, right.1, &mut rng).unwrap();
+        assert_close(left.origin(), left_eye);
+        assert_close(right.origin(), right_eye);
+        // Parallel eyes see the same directions.
+        assert_close(
+            Vec3::unit_vector(left.direction()),
+            Vec3::unit_vector(right.direction()),
+        );
+    }
+    // Each eye's half of the film looks straight ahead at its center.
+    let center = sides.get_ray(0.25, 0.5, &mut rng).unwrap();
+    assert_close(
+        Vec3::unit_vector(center.direction()),
+        Vec3::new(0.0, 0.0, -1.0),
+    );
+}
+
+#[test]
+fn ods_rays_cover_the_sphere_from_a_circle() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let ods = ods();
+    // Left eye on top: (s, t) within the top half, then the direction it sees.
+    for (s, t, direction) in [
+        (0.5, 0.75, Vec3::new(0.0, 0.0, -1.0)),
+        (0.75, 0.75, Vec3::new(1.0, 0.0, 0.0)),
+        (0.25, 0.75, Vec3::new(-1.0, 0.0, 0.0)),
+        (0.0, 0.75, Vec3::new(0.0, 0.0, 1.0)),
+        (0.5, 0.875, Vec3::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2)),
+        (0.5, 0.625, Vec3::new(0.0, -FRAC_1_SQRT_2, -FRAC_1_SQRT_2)),
+    ] {
+        let left = ods.get_ray(s, t, &mut rng);
+        let right = ods.get_ray(s, t - 0.5, &mut rng);
+        for ray in [left, right] {
+            assert_close(ray.direction(), direction);
+            // Each eye looks out tangent to the viewing circle.
+            let offset = ray.origin() - EYE;
+            assert!((offset.length() - 0.5 * IPD).abs() < 1e-9, "{s} {t}");
+            assert!(Vec3::dot(offset, direction).abs() < 1e-9, "{s} {t}");
+        }
+        // On opposite sides of the circle, the left eye to the left when looking ahead.
+        assert_close(left.origin() - EYE, EYE - right.origin());
+        if direction.y == 0.0 {
+            let to_right = Vec3::cross(direction, Vec3::new(0.0, 1.0, 0.0));
+            assert!(Vec3::dot(left.origin() - EYE, to_right) < 0.0, "{s} {t}");
+        }
+    }
+}
+
+#[test]
+fn cameras_take_stereo_projections() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let camera = || {
+        Camera::new(
+            EYE,
+            Point3::new(0.0, 1.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            2.0,
+            0.0,
+            5.0,
+        )
+        .with_shutter(Shutter::new(1.0, 2.0))
+    };
+    let sides = camera().with_projection(Projection::Stereo(Arc::new(stereo(
+        StereoLayout::SideBySide,
+    ))));
+    let ray = sides.get_ray(0.2, 0.5, &mut rng).unwrap();
+    assert_close(ray.origin(), EYE - Vec3::new(0.5 * IPD, 0.0, 0.0));
+    // The camera's shutter times every projection's rays.
+    assert!((1.0..2.0).contains(&ray.time()));
+
+    let panorama = camera().with_projection(Projection::Ods(Arc::new(ods())));
+    let back = panorama.get_ray(0.0, 0.25, &mut rng).unwrap();
+    assert_close(back.direction(), Vec3::new(0.0, 0.0, 1.0));
+    // Light tracing only knows how to reach a thin lens.
+    assert!(panorama.project(Point3::new(0.0, 1.0, -3.0)).is_none());
+    assert!(camera().project(Point3::new(0.0, 1.0, -3.0)).is_some());
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "stream")]
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rtt::background::Constant;
+use rtt::camera::Camera;
+use rtt::hittable::{HittableList, Sphere};
+use rtt::material::Lambertian;
+use rtt::stream::{stream_on, TILE_HEADER_BYTES};
+use rtt::vec3::{Color, Point3, Vec3};
+use tungstenite::{Message, WebSocket};
+
+// Serves a lit ball on a port the system picks.
+fn start(width: u32, height: u32, passes: u32) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+        world.set_background(Arc::new(Constant::new(Color::new(0.7, 0.8, 1.0))));
+        let camera = Camera::new(
+            Point3::new(0.0, 0.0, 3.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            30.0,
+            width as f64 / height as f64,
+            0.0,
+            3.0,
+        );
+        stream_on(listener, &world, &camera, width, height, passes)
+    });
+    addr
+}
+
+fn connect(addr: SocketAddr) -> WebSocket<TcpStream> {
+    let stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(30)))
+        .unwrap();
+    let (ws, _) = tungstenite::client(format!("ws://{addr}/"), stream).unwrap();
+    ws
+}
+
+fn json(message: Message) -> serde_json::Value {
+    serde_json::from_str(message.to_text().unwrap()).unwrap()
+}
+
+// (pass, bounds) of a tile update, checking its pixels fill its bounds.
+fn tile(message: &[u8]) -> (u32, [u32; 4]) {
+    let field = |i: usize| {
+        let bytes = message[4 * i..4 * i + 4].try_into().unwrap();
+        u32::from_le_bytes(bytes)
+    };
+    let [x0, y0, x1, y1] = [field(1), field(2), field(3), field(4)];
+    let pixels = ((x1 - x0) * (y1 - y0)) as usize;
+    assert_eq!(message.len(), TILE_HEADER_BYTES + 4 * pixels);
+    (field(0), [x0, y0, x1, y1])
+}
+
+#[test]
+fn clients_watch_every_tile_of_every_pass() {
+    let (width, height, passes) = (16, 8, 3);
+    let addr = start(width, height, passes);
+    let mut ws = connect(addr);
+    let header = json(ws.read().unwrap());
+    assert_eq!(header["width"], width);
+    assert_eq!(header["height"], height);
+    assert_eq!(header["passes"], passes);
+
+    let mut tiles = Vec::new();
+    loop {
+        match ws.read().unwrap() {
+            Message::Binary(message) => tiles.push(tile(&message)),
+            message => {
+                assert_eq!(json(message)["done"], true);
+                break;
+            }
+        }
+    }
+    // One tile per row, all inside the image.
+    assert_eq!(tiles.len(), (passes * height) as usize);
+    for (pass, [x0, y0, x1, y1]) in tiles {
+        assert!(pass < passes);
+        assert!(x0 < x1 && x1 <= width && y0 < y1 && y1 <= height);
+    }
+}
+
+#[test]
+fn leaving_stops_the_render_for_the_next_client() {
+    // Far more passes than could finish while the test runs.
+    let addr = start(32, 16, u32::MAX);
+    let mut first = connect(addr);
+    first.read().unwrap();
+    assert!(matches!(first.read().unwrap(), Message::Binary(_)));
+    drop(first);
+
+    // Clients are served one at a time, so the next is only greeted once the first render
+    // has stopped.
+    let mut next = connect(addr);
+    let header = json(next.read().unwrap());
+    assert_eq!(header["width"], 32);
+    assert!(matches!(next.read().unwrap(), Message::Binary(_)));
+}
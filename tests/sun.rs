@@ -0,0 +1,88 @@
+use rtt::background::SunSky;
+use rtt::sun::{SolarPosition, SunTime};
+use rtt::vec3::Vec3;
+
+fn degrees(p: SolarPosition) -> (f64, f64) {
+    (p.elevation.to_degrees(), p.azimuth.to_degrees())
+}
+
+#[test]
+fn matches_reference_position() {
+    // The worked example of Reda and Andreas' solar position algorithm: Golden, Colorado on
+    // 17 October 2003 at 12:30:30 MST, zenith 50.11 and azimuth 194.34 degrees.
+    let time = SunTime::parse("2003-10-17T12:30:30-07:00").unwrap();
+    let (elevation, azimuth) = degrees(SolarPosition::at(&time, 39.742476, -105.1786));
+    assert!((elevation - (90.0 - 50.111)).abs() < 0.05, "{elevation}");
+    assert!((azimuth - 194.340).abs() < 0.05, "{azimuth}");
+}
+
+#[test]
+fn sun_follows_the_day_and_the_seasons() {
+    let at = |s: &str, lat: f64| degrees(SolarPosition::at(&SunTime::parse(s).unwrap(), lat, 0.0));
+    // Midsummer noon in Greenwich: due south at 90 - 51.5 + 23.4 degrees.
+    let (elevation, azimuth) = at("2024-06-21T12:00Z", 51.48);
+    assert!((elevation - 61.96).abs() < 0.2, "{elevation}");
+    assert!((azimuth - 180.0).abs() < 1.0, "{azimuth}");
+    // Midwinter noon is lower by twice the tilt.
+    let (winter, _) = at("2024-12-21T12:00Z", 51.48);
+    assert!((elevation - winter - 46.9).abs() < 0.3, "{winter}");
+    // Mornings are in the east, afternoons in the west, and midnight is below the horizon.
+    let (_, morning) = at("2024-06-21T08:00Z", 51.48);
+    let (_, evening) = at("2024-06-21T16:00Z", 51.48);
+    assert!(morning > 45.0 && morning < 135.0, "{morning}");
+    assert!(evening > 225.0 && evening < 315.0, "{evening}");
+    assert!(at("2024-06-21T00:00Z", 51.48).0 < 0.0);
+    // South of the equator the noon sun is in the north.
+    let (_, southern) = at("2024-06-21T12:00Z", -33.9);
+    assert!(!(90.0..270.0).contains(&southern), "{southern}");
+}
+
+#[test]
+fn time_zones_name_the_same_instant() {
+    let utc = SunTime::parse("2026-03-01T10:15Z").unwrap();
+    let cet = SunTime::parse("2026-03-01T11:15+01:00").unwrap();
+    let est = SunTime::parse("2026-03-01 05:15:00-0500").unwrap();
+    assert!((utc.julian_day() - cet.julian_day()).abs() < 1e-9);
+    assert!((utc.julian_day() - est.julian_day()).abs() < 1e-9);
+    // J2000 is noon UTC on 1 January 2000.
+    let j2000 = SunTime::parse("2000-01-01T12:00").unwrap();
+    assert_eq!(j2000.julian_day(), 2451545.0);
+}
+
+#[test]
+fn rejects_impossible_dates() {
+    for s in [
+        "2026-02-29T12:00",
+        "2026-13-01T12:00",
+        "2026-04-31T12:00",
+        "2026-06-21T24:00",
+        "2026-06-21",
+        "2026-06-21T12:00+99:00",
+        "noon",
+    ] {
+        assert!(SunTime::parse(s).is_err(), "{s}");
+    }
+    assert!(SunTime::parse("2024-02-29T12:00").is_ok());
+}
+
+#[test]
+fn sky_turns_with_north() {
+    let time = SunTime::parse("2024-06-21T08:00Z").unwrap();
+    let sun = SolarPosition::at(&time, 51.48, 0.0);
+    // North along -z puts the morning sun towards +x.
+    let sky = sun.sky(0.0).unwrap();
+    let expected = SunSky::from_angles(sun.elevation, sun.azimuth);
+    assert_eq!(sky, expected);
+    let d = sky.sun_direction;
+    assert!(d.x > 0.5 && d.y > 0.0, "{d:?}");
+    // With north along +x, east is +z.
+    let turned = sun.sky(90f64.to_radians()).unwrap().sun_direction;
+    assert!(
+        turned.z > 0.5 && (turned.y - d.y).abs() < 1e-9,
+        "{turned:?}"
+    );
+    assert!(Vec3::dot(turned, d).abs() < 0.9);
+
+    let night = SolarPosition::at(&SunTime::parse("2024-06-21T00:00Z").unwrap(), 51.48, 0.0);
+    assert!(night.sky(0.0).is_err());
+}
@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use image::{Rgb, Rgb32FImage, RgbImage};
+use rtt::color::srgb_to_linear;
+use rtt::texture::{TextureCache, TextureFormat};
+use rtt::vec3::Color;
+
+// Writes a solid `size` x `size` PNG into a per-test scratch directory.
+fn write_png(test: &str, name: &str, size: u32, color: [u8; 3]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rtt-texture-{}-{test}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    RgbImage::from_pixel(size, size, Rgb(color))
+        .save(&path)
+        .unwrap();
+    path
+}
+
+// Decoded size of a `size` x `size` texture.
+fn bytes(size: u32) -> usize {
+    (size * size * 3) as usize * std::mem::size_of::<f32>()
+}
+
+#[test]
+fn loads_lazily_and_shares_paths() {
+    let path = write_png("lazy", "red.png", 4, [255, 0, 0]);
+    let cache = Arc::new(TextureCache::unbounded());
+    let a = cache.texture(&path);
+    let b = cache.texture(path.parent().unwrap().join(".").join("red.png"));
+    assert_eq!(a.path(), b.path());
+    assert_eq!(cache.stats().unwrap().loads, 0);
+
+    assert_eq!(a.value(0.5, 0.5).r(), 1.0);
+    assert_eq!(b.value(0.1, 0.9).g(), 0.0);
+    let stats = cache.stats().unwrap();
+    assert_eq!((stats.loads, stats.hits), (1, 1));
+    assert_eq!(stats.bytes, bytes(4));
+    assert_eq!(cache.len().unwrap(), 1);
+}
+
+#[test]
+fn evicts_least_recently_used_over_budget() {
+    let a = write_png("lru", "a.png", 8, [255, 255, 255]);
+    let b = write_png("lru", "b.png", 8, [255, 255, 255]);
+    let c = write_png("lru", "c.png", 8, [255, 255, 255]);
+    let cache = TextureCache::new(2 * bytes(8));
+
+    cache.get(&a).unwrap();
+    cache.get(&b).unwrap();
+    cache.get(&a).unwrap();
+    // Over budget: b is the least recently used.
+    cache.get(&c).unwrap();
+    let stats = cache.stats().unwrap();
+    assert_eq!((stats.loads, stats.evictions), (3, 1));
+    assert_eq!(stats.bytes, 2 * bytes(8));
+
+    cache.get(&a).unwrap();
+    assert_eq!(cache.stats().unwrap().loads, 3);
+    cache.get(&b).unwrap();
+    assert_eq!(cache.stats().unwrap().loads, 4);
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let cache = Arc::new(TextureCache::unbounded());
+    let missing = std::env::temp_dir().join("rtt-texture-missing.png");
+    assert!(cache.get(&missing).is_err());
+    assert_eq!(cache.texture(&missing).value(0.5, 0.5).g(), 1.0);
+}
+
+#[test]
+fn wide_footprints_average_texels() {
+    let dir = std::env::temp_dir().join(format!("rtt-texture-{}-filter", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("checker.png");
+    RgbImage::from_fn(64, 64, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgb([255, 255, 255])
+        } else {
+            Rgb([0, 0, 0])
+        }
+    })
+    .save(&path)
+    .unwrap();
+    let texture = Arc::new(TextureCache::unbounded()).texture(&path);
+
+    // Under a texel wide: the nearest texel, black or white.
+    let sharp = texture.value_filtered(0.5, 0.5, 0.1 / 64.0).r();
+    assert!(sharp == 0.0 || sharp == 1.0);
+    assert_eq!(sharp, texture.value(0.5, 0.5).r());
+    // Many texels wide: half white.
+    let blurred = texture.value_filtered(0.5, 0.5, 0.25).r();
+    assert!((blurred - 0.5).abs() < 0.05, "{blurred}");
+}
+
+#[test]
+fn smaller_formats_stay_close_to_full_precision() {
+    let dir = std::env::temp_dir().join(format!("rtt-texture-{}-formats", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("blocks.png");
+    // Two colors in every 4x4 block, different from block to block, which blocks keep exactly
+    // up to half precision.
+    RgbImage::from_fn(30, 30, |x, y| {
+        let (bx, by) = ((x / 4 * 30) as u8, (y / 4 * 30) as u8);
+        if (x + y) % 3 == 0 {
+            Rgb([bx, 255 - by, 40])
+        } else {
+            Rgb([200, bx, by])
+        }
+    })
+    .save(&path)
+    .unwrap();
+    let reference = Arc::new(TextureCache::unbounded()).texture(&path);
+    // Blocks are whole, so the 30 texel edges pad to 32.
+    for (format, expected, tolerance) in [
+        (TextureFormat::F16, bytes(30) / 2, 1e-3),
+        (TextureFormat::Block, 8 * 8 * 16, 2e-3),
+    ] {
+        let cache = Arc::new(TextureCache::unbounded());
+        cache.set_format(format).unwrap();
+        let texels = cache.get(&path).unwrap();
+        assert_eq!(texels.format(), format);
+        assert_eq!((texels.width(), texels.height()), (30, 30));
+        assert_eq!(cache.stats().unwrap().bytes, expected);
+
+        let texture = cache.texture(&path);
+        for i in 0..30 {
+            for j in 0..30 {
+                let (u, v) = ((i as f64 + 0.5) / 30.0, (j as f64 + 0.5) / 30.0);
+                let (a, b) = (reference.value(u, v), texture.value(u, v));
+                assert!(
+                    (a - b).length() < tolerance,
+                    "{format:?} at ({i}, {j}): {a:?} vs {b:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn failed_loads_are_remembered() {
+    let dir = std::env::temp_dir().join(format!("rtt-texture-{}-failed", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("late.png");
+    let _ = std::fs::remove_file(&path);
+    let cache = Arc::new(TextureCache::unbounded());
+    assert!(cache.get(&path).is_err());
+    assert_eq!(cache.len().unwrap(), 1);
+    // Not tried again, even once the file turns up.
+    RgbImage::from_pixel(2, 2, Rgb([255, 0, 0]))
+        .save(&path)
+        .unwrap();
+    assert!(cache.get(&path).is_err());
+    assert_eq!(
+        cache.texture(&path).value(0.5, 0.5),
+        Color::new(0.0, 1.0, 1.0)
+    );
+    let stats = cache.stats().unwrap();
+    assert_eq!((stats.loads, stats.bytes), (0, 0));
+    // A fresh cache sees it.
+    assert_eq!(
+        TextureCache::unbounded()
+            .get(&path)
+            .unwrap()
+            .texel(0, 0)
+            .r(),
+        1.0
+    );
+}
+
+#[test]
+fn only_integer_images_are_srgb_decoded() {
+    let grey = write_png("srgb", "grey.png", 2, [128, 128, 128]);
+    let wide = grey.with_file_name("grey16.png");
+    image::ImageBuffer::<Rgb<u16>, _>::from_pixel(2, 2, Rgb([32896, 32896, 32896]))
+        .save(&wide)
+        .unwrap();
+    let float = grey.with_file_name("half.exr");
+    Rgb32FImage::from_pixel(2, 2, Rgb([0.5, 2.0, 0.25]))
+        .save(&float)
+        .unwrap();
+
+    let cache = TextureCache::unbounded();
+    let expected = srgb_to_linear(128.0 / 255.0);
+    for path in [&grey, &wide] {
+        let texel = cache.get(path).unwrap().texel(1, 1);
+        assert!((texel.g() - expected).abs() < 1e-6, "{path:?}: {texel:?}");
+    }
+    let texel = cache.get(&float).unwrap().texel(0, 1);
+    assert_eq!(texel, Color::new(0.5, 2.0, 0.25));
+}
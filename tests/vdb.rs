@@ -0,0 +1,173 @@
+#![cfg(feature = "vdb")]
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use rtt::vdb::{grid_names, load_grid};
+use rtt::vec3::{Color, Point3};
+use rtt::volume::{Field, Volume};
+
+const VERSION: u32 = (32 << 21) | (6 << 10);
+
+fn put(buf: &mut [u8], at: usize, bytes: &[u8]) {
+    buf[at..at + bytes.len()].copy_from_slice(bytes);
+}
+
+// Float grid with voxel size 0.5 and origin (1, 0, 0), holding `value([i, j, k])` for the
+// active voxels 0..4 on each axis, all in one leaf under one root tile.
+fn grid_buffer(value: impl Fn([i32; 3]) -> f32) -> Vec<u8> {
+    let (tree, root, upper) = (672, 736, 832);
+    let lower = upper + 8256 + 32768 * 8;
+    let leaf = lower + 1088 + 4096 * 8;
+    let size = leaf + 2144;
+    let mut buf = vec![0u8; size];
+
+    put(&mut buf, 0, b"NanoVDB0");
+    put(&mut buf, 16, &VERSION.to_le_bytes());
+    put(&mut buf, 32, &(size as u64).to_le_bytes());
+    for (i, m) in [0.5, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.5]
+        .iter()
+        .enumerate()
+    {
+        put(&mut buf, 384 + 8 * i, &f64::to_le_bytes(*m));
+    }
+    put(&mut buf, 528, &1.0f64.to_le_bytes());
+    put(&mut buf, 636, &1u32.to_le_bytes());
+
+    for (i, node) in [leaf, lower, upper, root].iter().enumerate() {
+        put(
+            &mut buf,
+            tree + 8 * i,
+            &((node - tree) as u64).to_le_bytes(),
+        );
+    }
+
+    for (i, c) in [0i32, 0, 0, 3, 3, 3].iter().enumerate() {
+        put(&mut buf, root + 4 * i, &c.to_le_bytes());
+    }
+    put(&mut buf, root + 24, &1u32.to_le_bytes());
+    let tile = root + 64;
+    put(&mut buf, tile + 8, &((upper - root) as i64).to_le_bytes());
+
+    put(&mut buf, upper + 32 + 4096, &1u64.to_le_bytes());
+    put(
+        &mut buf,
+        upper + 8256,
+        &((lower - upper) as i64).to_le_bytes(),
+    );
+    put(&mut buf, lower + 32 + 512, &1u64.to_le_bytes());
+    put(
+        &mut buf,
+        lower + 1088,
+        &((leaf - lower) as i64).to_le_bytes(),
+    );
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                let n = (i << 6 | j << 3 | k) as usize;
+                put(&mut buf, leaf + 96 + 4 * n, &value([i, j, k]).to_le_bytes());
+            }
+        }
+    }
+    buf
+}
+
+fn write_file(test: &str, grids: &[(&str, Vec<u8>)], zip: bool) -> PathBuf {
+    let mut file = Vec::new();
+    file.extend_from_slice(b"NanoVDB2");
+    file.extend_from_slice(&VERSION.to_le_bytes());
+    file.extend_from_slice(&(grids.len() as u16).to_le_bytes());
+    file.extend_from_slice(&(zip as u16).to_le_bytes());
+    let payloads: Vec<Vec<u8>> = grids
+        .iter()
+        .map(|(_, buf)| {
+            if !zip {
+                return buf.clone();
+            }
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+            encoder.write_all(buf).unwrap();
+            let compressed = encoder.finish().unwrap();
+            let mut payload = (compressed.len() as u64).to_le_bytes().to_vec();
+            payload.extend(compressed);
+            payload
+        })
+        .collect();
+    for ((name, buf), payload) in grids.iter().zip(&payloads) {
+        let mut meta = [0u8; 176];
+        put(&mut meta, 0, &(buf.len() as u64).to_le_bytes());
+        put(&mut meta, 8, &(payload.len() as u64).to_le_bytes());
+        put(&mut meta, 136, &(name.len() as u32 + 1).to_le_bytes());
+        file.extend_from_slice(&meta);
+        file.extend_from_slice(name.as_bytes());
+        file.push(0);
+    }
+    for payload in payloads {
+        file.extend(payload);
+    }
+    let path = std::env::temp_dir().join(format!("rtt-vdb-{}-{test}.nvdb", std::process::id()));
+    std::fs::write(&path, file).unwrap();
+    path
+}
+
+fn ramp([i, j, k]: [i32; 3]) -> f32 {
+    (i + 10 * j + 100 * k) as f32
+}
+
+#[test]
+fn loads_float_grids_by_name() {
+    for zip in [false, true] {
+        let path = write_file(
+            &format!("names-{zip}"),
+            &[
+                ("density", grid_buffer(ramp)),
+                ("temperature", grid_buffer(|_| 1200.0)),
+            ],
+            zip,
+        );
+        assert_eq!(grid_names(&path).unwrap(), ["density", "temperature"]);
+
+        let density = load_grid(&path, "density").unwrap();
+        let Field::Grid {
+            bounds, resolution, ..
+        } = &density
+        else {
+            panic!("{density:?}");
+        };
+        assert_eq!(*resolution, [4, 4, 4]);
+        assert_eq!(bounds.min, Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(bounds.max, Point3::new(2.5, 1.5, 1.5));
+        // Voxel (1, 2, 3) sits at index * 0.5 + (1, 0, 0).
+        assert_eq!(density.value(Point3::new(1.5, 1.0, 1.5)), 321.0);
+        assert!((density.value(Point3::new(1.25, 0.0, 0.0)) - 0.5).abs() < 1e-9);
+
+        let temperature = load_grid(&path, "temperature").unwrap();
+        assert_eq!(temperature.max_value(), 1200.0);
+        assert!(load_grid(&path, "velocity").is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn scene_fields_load_from_file() {
+    let path = write_file("scene", &[("density", grid_buffer(ramp))], false);
+    let field = Field::Vdb {
+        path: path.clone(),
+        grid: "density".to_string(),
+    };
+    let volume = Volume::from_field(field, Color::new(0.8, 0.8, 0.8)).unwrap();
+    assert!(volume.boundary().is_none());
+    assert!(matches!(volume.density, Field::Grid { .. }));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_corrupt_files() {
+    let path = write_file("corrupt", &[("density", grid_buffer(ramp))], false);
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+    assert!(grid_names(&path).is_err());
+    std::fs::write(&path, b"not a grid").unwrap();
+    assert!(load_grid(&path, "density").is_err());
+    std::fs::remove_file(path).unwrap();
+}
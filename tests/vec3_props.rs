@@ -0,0 +1,176 @@
+use proptest::prelude::*;
+
+use rtt::material::{reflect, refract};
+use rtt::ray::Ray;
+use rtt::vec3::Vec3;
+
+const EPS: f64 = 1e-9;
+
+fn component() -> impl Strategy<Value = f64> {
+    -1.0e3..1.0e3
+}
+
+fn vec3() -> impl Strategy<Value = Vec3> {
+    (component(), component(), component()).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+// Vectors far enough from zero to normalize without blowing up relative error.
+fn nonzero_vec3() -> impl Strategy<Value = Vec3> {
+    vec3().prop_filter("near-zero vector", |v| v.length() > 1e-3)
+}
+
+fn unit_vec3() -> impl Strategy<Value = Vec3> {
+    nonzero_vec3().prop_map(Vec3::unit_vector)
+}
+
+fn approx_eq(a: f64, b: f64, scale: f64) -> bool {
+    (a - b).abs() <= EPS * scale.max(1.0)
+}
+
+fn approx_eq_vec(a: Vec3, b: Vec3, scale: f64) -> bool {
+    approx_eq(a.x, b.x, scale) && approx_eq(a.y, b.y, scale) && approx_eq(a.z, b.z, scale)
+}
+
+proptest! {
+    #[test]
+    fn add_sub_round_trip(u in vec3(), v in vec3()) {
+        prop_assert!(approx_eq_vec(u + v - v, u, u.length() + v.length()));
+    }
+
+    #[test]
+    fn negation_is_involutive(u in vec3()) {
+        prop_assert_eq!(-(-u), u);
+        prop_assert_eq!(u + (-u), Vec3::default());
+    }
+
+    #[test]
+    fn dot_is_symmetric(u in vec3(), v in vec3()) {
+        prop_assert_eq!(Vec3::dot(u, v), Vec3::dot(v, u));
+    }
+
+    #[test]
+    fn dot_with_self_is_length_squared(u in vec3()) {
+        prop_assert!(approx_eq(Vec3::dot(u, u), u.length_squared(), u.length_squared()));
+    }
+
+    #[test]
+    fn cross_is_orthogonal(u in vec3(), v in vec3()) {
+        let c = Vec3::cross(u, v);
+        let scale = c.length() * (u.length() + v.length());
+        prop_assert!(approx_eq(Vec3::dot(c, u), 0.0, scale));
+        prop_assert!(approx_eq(Vec3::dot(c, v), 0.0, scale));
+    }
+
+    #[test]
+    fn cross_is_anticommutative(u in vec3(), v in vec3()) {
+        prop_assert_eq!(Vec3::cross(u, v), -Vec3::cross(v, u));
+    }
+
+    #[test]
+    fn cross_magnitude_is_lagrange_identity(u in vec3(), v in vec3()) {
+        let lhs = Vec3::cross(u, v).length_squared();
+        let rhs = u.length_squared() * v.length_squared() - Vec3::dot(u, v).powi(2);
+        prop_assert!(approx_eq(lhs, rhs, u.length_squared() * v.length_squared()));
+    }
+
+    #[test]
+    fn unit_vector_has_unit_length(u in nonzero_vec3()) {
+        let n = Vec3::unit_vector(u);
+        prop_assert!(approx_eq(n.length(), 1.0, 1.0));
+        // Same direction as the input.
+        prop_assert!(Vec3::dot(n, u) > 0.0);
+    }
+
+    #[test]
+    fn scalar_mul_div_round_trip(u in vec3(), t in 1.0e-3..1.0e3f64) {
+        prop_assert!(approx_eq_vec(u * t / t, u, u.length()));
+        prop_assert_eq!(t * u, u * t);
+    }
+
+    #[test]
+    fn assign_ops_match_binary_ops(u in vec3(), v in vec3(), t in 1.0e-3..1.0e3f64) {
+        let mut w = u;
+        w += v;
+        prop_assert_eq!(w, u + v);
+        w = u;
+        w -= v;
+        prop_assert_eq!(w, u - v);
+        w = u;
+        w *= v;
+        prop_assert_eq!(w, u * v);
+        w = u;
+        w *= t;
+        prop_assert_eq!(w, u * t);
+        w = u;
+        w /= t;
+        prop_assert_eq!(w, u / t);
+    }
+
+    #[test]
+    fn reflect_preserves_length_and_flips_normal_component(v in vec3(), n in unit_vec3()) {
+        let r = reflect(v, n);
+        let scale = v.length_squared();
+        prop_assert!(approx_eq(r.length_squared(), v.length_squared(), scale));
+        prop_assert!(approx_eq(Vec3::dot(r, n), -Vec3::dot(v, n), v.length()));
+        // Tangential component is unchanged.
+        let tangent = |w: Vec3| w - Vec3::dot(w, n) * n;
+        prop_assert!(approx_eq_vec(tangent(r), tangent(v), v.length()));
+    }
+
+    #[test]
+    fn reflect_is_involutive(v in vec3(), n in unit_vec3()) {
+        prop_assert!(approx_eq_vec(reflect(reflect(v, n), n), v, v.length()));
+    }
+
+    #[test]
+    fn refract_obeys_snell(v in unit_vec3(), n in unit_vec3(), eta in 0.3..3.0f64) {
+        // refract() expects the normal to face against the incoming direction.
+        let n = if Vec3::dot(v, n) > 0.0 { -n } else { n };
+        let cos_i = -Vec3::dot(v, n);
+        prop_assume!(cos_i > 1e-6);
+
+        let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+        match refract(v, n, eta) {
+            Some(t) => {
+                prop_assert!(sin2_t < 1.0);
+                prop_assert!(approx_eq(t.length(), 1.0, 1e3));
+                // Transmitted ray continues through the surface.
+                prop_assert!(Vec3::dot(t, n) < 0.0);
+                let sin_i = (1.0 - cos_i * cos_i).sqrt();
+                let sin_t = (1.0 - Vec3::dot(t, n).powi(2)).max(0.0).sqrt();
+                prop_assert!((eta * sin_i - sin_t).abs() < 1e-6);
+            }
+            None => prop_assert!(sin2_t >= 1.0 - 1e-12),
+        }
+    }
+
+    #[test]
+    fn refract_with_unit_eta_is_identity(v in unit_vec3(), n in unit_vec3()) {
+        let n = if Vec3::dot(v, n) > 0.0 { -n } else { n };
+        prop_assume!(-Vec3::dot(v, n) > 1e-6);
+        let t = refract(v, n, 1.0).unwrap();
+        prop_assert!(approx_eq_vec(t, v, 1e3));
+    }
+
+    #[test]
+    fn ray_at_is_affine(o in vec3(), d in vec3(), t in -1.0e3..1.0e3f64, s in -1.0e3..1.0e3f64) {
+        let r = Ray::new(o, d);
+        prop_assert_eq!(r.at(0.0), o);
+        let scale = o.length() + d.length() * (t.abs() + s.abs());
+        prop_assert!(approx_eq_vec(r.at(t) - r.at(s), (t - s) * d, scale));
+    }
+
+    #[test]
+    fn ray_preserves_time(o in vec3(), d in vec3(), time in 0.0..1.0f64) {
+        let r = Ray::with_time(o, d, time);
+        prop_assert_eq!(r.origin(), o);
+        prop_assert_eq!(r.direction(), d);
+        prop_assert_eq!(r.time(), time);
+    }
+
+    #[test]
+    fn conversions_agree(x in component(), y in component(), z in component()) {
+        prop_assert_eq!(Vec3::from((x, y, z)), Vec3::new(x, y, z));
+        prop_assert_eq!(Vec3::from([x, y, z]), Vec3::new(x, y, z));
+    }
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use rtt::hittable::HitRecord;
+use rtt::light::QuadLight;
+use rtt::material::Velvet;
+use rtt::ray::Ray;
+use rtt::render::RenderSettings;
+use rtt::scene::MaterialDesc;
+use rtt::vec3::{Color, Point3, Vec3};
+
+use common::{ball, hit, lit_floor};
+
+const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+
+// Mean attenuation of `scatter`: how much of the light arriving along `ray` it reflects.
+fn albedo(ray: &Ray, rec: &HitRecord, n: usize) -> Color {
+    let mut rng = StdRng::seed_from_u64(9);
+    let mut sum = Color::default();
+    for _ in 0..n {
+        let (attenuation, scattered) = rec.material.get().scatter(ray, rec, &mut rng).unwrap();
+        // Shadow rays weigh lights as `scatter` weighs its own directions.
+        let pdf = rec.material.get().scattering_pdf(ray, rec, &scattered);
+        let value = rec
+            .material
+            .get()
+            .scattering(ray, rec, attenuation, &scattered);
+        assert!((value / pdf - attenuation).length() < 1e-9);
+        sum += attenuation;
+    }
+    sum / n as f64
+}
+
+#[test]
+fn sheen_albedo_matches_scatter() {
+    for roughness in [0.1, 0.5, 1.0] {
+        let sheen = Velvet::new(Color::default(), roughness);
+        for theta in [0.0, 0.7, 1.2, 1.5] {
+            let sphere = ball(sheen.clone());
+            let (ray, rec) = hit(&sphere, theta);
+            let measured = albedo(&ray, &rec, 100_000).g();
+            let expected = sheen.sheen_albedo(theta.cos());
+            assert!(
+                (measured - expected).abs() < 0.02 * expected.max(0.1),
+                "{roughness} {theta}: {measured} vs {expected}"
+            );
+            assert!(expected > 0.0 && expected < 1.0, "{expected}");
+        }
+    }
+}
+
+#[test]
+fn velvet_never_reflects_more_than_arrives() {
+    for roughness in [0.1, 0.4, 1.0] {
+        let velvet = Velvet::new(WHITE, roughness);
+        for theta in [0.0, 0.8, 1.3, 1.55] {
+            let sphere = ball(velvet.clone());
+            let (ray, rec) = hit(&sphere, theta);
+            let reflected = albedo(&ray, &rec, 100_000);
+            assert!(
+                (reflected.g() - 1.0).abs() < 0.02,
+                "{roughness} {theta}: {reflected:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn sheen_shines_at_grazing_angles() {
+    let sheen = Velvet::new(Color::default(), 0.3);
+    assert!(sheen.sheen_albedo(0.1) > 2.0 * sheen.sheen_albedo(1.0));
+    // Seen head on, it brightens towards lights low on the horizon.
+    let sphere = ball(sheen.clone());
+    let (ray, rec) = hit(&sphere, 0.0);
+    let towards = |theta: f64| {
+        let l = Ray::new(rec.point, Vec3::new(theta.sin(), theta.cos(), 0.0));
+        rec.material
+            .get()
+            .scattering(&ray, &rec, Color::default(), &l)
+            / theta.cos()
+    };
+    assert!(towards(1.4).g() > 3.0 * towards(0.2).g());
+    // Reciprocal: swapping the ways in and out changes nothing.
+    let sphere = ball(sheen);
+    let (grazing, rec2) = hit(&sphere, 1.2);
+    let back = Ray::new(rec2.point, -ray.direction());
+    let out = Ray::new(rec.point, -grazing.direction());
+    let a = rec2
+        .material
+        .get()
+        .scattering(&grazing, &rec2, Color::default(), &back);
+    let b = rec
+        .material
+        .get()
+        .scattering(&ray, &rec, Color::default(), &out);
+    assert!((a - b / 1.2f64.cos()).length() < 1e-9);
+}
+
+// A small lamp low down across a floor seen from above at an angle.
+fn lamp() -> QuadLight {
+    QuadLight::new(
+        Point3::new(1.0, 0.5, -0.25),
+        Vec3::new(0.0, 0.0, 0.5),
+        Vec3::new(0.0, 0.5, 0.0),
+        Color::new(4.0, 4.0, 4.0),
+    )
+}
+
+#[test]
+fn light_sampling_matches_brute_force() {
+    let settings = RenderSettings::default().with_seed(1);
+    let velvet = Velvet::new(Color::new(0.3, 0.05, 0.1), 0.3).with_sheen(Color::new(1.0, 0.8, 0.9));
+    let mis = lit_floor(velvet.clone(), lamp(), settings, 20_000);
+    let brute = lit_floor(velvet, lamp(), settings.with_shadow_samples(0), 200_000);
+    assert!(
+        (mis - brute).length() < 0.05 * brute.length(),
+        "{mis:?} vs brute force {brute:?}"
+    );
+}
+
+#[test]
+fn velvet_round_trips_through_desc() {
+    let desc: MaterialDesc =
+        serde_json::from_str(r#"{"type": "velvet", "albedo": [0.3, 0.05, 0.1]}"#).unwrap();
+    assert_eq!(
+        desc,
+        MaterialDesc::Velvet {
+            albedo: Color::new(0.3, 0.05, 0.1),
+            sheen: WHITE,
+            roughness: 0.3,
+        }
+    );
+    assert_eq!(desc.build().to_desc(), Some(desc.clone()));
+    assert!(!serde_json::to_string(&desc).unwrap().contains("sheen"));
+
+    let json = r#"{"type": "velvet", "albedo": [0.1, 0.1, 0.4], "sheen": [0.5, 0.5, 1],
+        "roughness": 0.6}"#;
+    let custom: MaterialDesc = serde_json::from_str(json).unwrap();
+    assert_eq!(custom.build().to_desc(), Some(custom));
+}
@@ -0,0 +1,138 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use image::RgbaImage;
+use rtt::video::{VideoEncoder, VideoSettings};
+use rtt::Error;
+
+// Tests swap `PATH`, which every spawn reads.
+static PATH: Mutex<()> = Mutex::new(());
+
+// A directory holding a fake `ffmpeg` that saves its arguments, one a line, and what it was
+// piped next to itself, then exits with `status`.
+fn fake_ffmpeg(test: &str, status: i32) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rtt-video-{}-{test}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("ffmpeg");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nprintf '%s\\n' \"$@\" > \"$(dirname \"$0\")/args\"\n\
+             cat > \"$(dirname \"$0\")/input\"\nexit {status}\n"
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    dir
+}
+
+// Encodes two 3x2 frames into `dir`, with the `ffmpeg` found on `PATH` after `dir`.
+fn encode(dir: &Path, output: &str, settings: &VideoSettings) -> Result<(), Error> {
+    let _path = PATH.lock().unwrap_or_else(|e| e.into_inner());
+    let old = std::env::var_os("PATH").unwrap_or_default();
+    let mut paths = vec![dir.to_path_buf()];
+    paths.extend(std::env::split_paths(&old));
+    std::env::set_var("PATH", std::env::join_paths(paths).unwrap());
+    let encoded = (|| {
+        let mut encoder = VideoEncoder::new(&dir.join(output), 3, 2, settings)?;
+        let frame = RgbaImage::new(3, 2);
+        encoder.push_frame(&frame)?;
+        encoder.push_frame(&frame)?;
+        encoder.finish()
+    })();
+    std::env::set_var("PATH", old);
+    encoded
+}
+
+fn args(dir: &Path) -> Vec<String> {
+    let args = std::fs::read_to_string(dir.join("args")).unwrap();
+    args.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn arguments_follow_the_output_and_settings() {
+    let dir = fake_ffmpeg("args", 0);
+    let settings = VideoSettings {
+        fps: 30.0,
+        bitrate: Some("8M".to_string()),
+        ..Default::default()
+    };
+    encode(&dir, "turntable.mp4", &settings).unwrap();
+    let output = dir.join("turntable.mp4").display().to_string();
+    let expected = [
+        "-y",
+        "-loglevel",
+        "error",
+        "-f",
+        "rawvideo",
+        "-pix_fmt",
+        "rgba",
+        "-s",
+        "3x2",
+        "-r",
+        "30",
+        "-i",
+        "-",
+        "-c:v",
+        "libx264",
+        "-pix_fmt",
+        "yuv420p",
+        "-vf",
+        "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+        "-b:v",
+        "8M",
+        &output,
+    ];
+    assert_eq!(args(&dir), expected);
+    // Both frames went down the pipe as raw RGBA.
+    let input = std::fs::read(dir.join("input")).unwrap();
+    assert_eq!(input.len(), 2 * 3 * 2 * 4);
+
+    encode(&dir, "turntable.webm", &VideoSettings::default()).unwrap();
+    let args = args(&dir);
+    assert!(
+        args.windows(2).any(|w| w == ["-c:v", "libvpx-vp9"]),
+        "{args:?}"
+    );
+    assert!(args.windows(2).any(|w| w == ["-r", "24"]), "{args:?}");
+    assert!(!args.iter().any(|a| a == "-b:v"), "{args:?}");
+}
+
+#[test]
+fn missing_ffmpeg_is_an_error() {
+    let settings = VideoSettings {
+        ffmpeg: PathBuf::from("rtt-no-such-ffmpeg"),
+        ..Default::default()
+    };
+    let dir = std::env::temp_dir();
+    let err = encode(&dir, "missing.mp4", &settings).unwrap_err();
+    assert!(matches!(err, Error::Encoder(_)), "{err}");
+    assert!(err.to_string().contains("rtt-no-such-ffmpeg"), "{err}");
+}
+
+#[test]
+fn failing_ffmpeg_is_an_error() {
+    let dir = fake_ffmpeg("failing", 3);
+    let err = encode(&dir, "failing.mp4", &VideoSettings::default()).unwrap_err();
+    assert!(matches!(err, Error::Encoder(_)), "{err}");
+    assert!(err.to_string().contains("failing.mp4"), "{err}");
+
+    // Frames of the wrong size are refused before reaching ffmpeg.
+    let dir = fake_ffmpeg("size", 0);
+    let _path = PATH.lock().unwrap_or_else(|e| e.into_inner());
+    let mut encoder = VideoEncoder::new(&dir.join("size.mp4"), 3, 2, &fake(&dir)).unwrap();
+    let err = encoder.push_frame(&RgbaImage::new(2, 3)).unwrap_err();
+    assert!(matches!(err, Error::Encoder(_)), "{err}");
+    encoder.finish().unwrap();
+}
+
+// Settings naming `dir`'s fake ffmpeg directly, without searching `PATH`.
+fn fake(dir: &Path) -> VideoSettings {
+    VideoSettings {
+        ffmpeg: dir.join("ffmpeg"),
+        ..Default::default()
+    }
+}
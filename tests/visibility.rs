@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::background::Constant;
+use rtt::hittable::{Hittable, HittableList, Masked, Sphere, Visibility};
+use rtt::interval::Interval;
+use rtt::material::{DiffuseLight, Lambertian};
+use rtt::ray::{Ray, RayKind};
+use rtt::render::trace_path;
+use rtt::scene::{CameraDesc, ObjectDesc, SceneDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+
+fn grey_ball(center: Point3, radius: f64) -> Arc<dyn Hittable> {
+    Arc::new(Sphere::new(
+        center,
+        radius,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    ))
+}
+
+// A ball at the origin seen only by `visibility`, in front of a backdrop at z = -10.
+fn masked(visibility: Visibility) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Masked::new(
+        grey_ball(Point3::new(0.0, 0.0, 0.0), 1.0),
+        visibility,
+    )));
+    world.add(grey_ball(Point3::new(0.0, 0.0, -110.0), 100.0));
+    world
+}
+
+fn toward_ball(kind: RayKind) -> Ray {
+    Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)).with_kind(kind)
+}
+
+#[test]
+fn each_kind_of_ray_sees_what_it_may() {
+    let ray_t = Interval::new(1e-3, f64::INFINITY);
+    let kinds = [RayKind::Camera, RayKind::Shadow, RayKind::Indirect];
+    let cases = [
+        (Visibility::default(), [true, true, true]),
+        (
+            Visibility::default().with_camera(false),
+            [false, true, true],
+        ),
+        (
+            Visibility::default().with_shadow(false),
+            [true, false, true],
+        ),
+        (
+            Visibility::default().with_indirect(false),
+            [true, true, false],
+        ),
+    ];
+    for (visibility, seen) in cases {
+        let world = masked(visibility);
+        for (kind, seen) in kinds.into_iter().zip(seen) {
+            let ray = toward_ball(kind);
+            let t = world.hit(&ray, ray_t).unwrap().t;
+            assert_eq!(t < 5.0, seen, "{visibility:?} {kind:?}");
+            assert_eq!(world.is_occluded(&ray, Interval::new(1e-3, 5.0)), seen);
+            assert_eq!(world.hit_all(&ray, ray_t).len(), if seen { 4 } else { 2 });
+        }
+    }
+    // Plain rays are bounces.
+    assert_eq!(Ray::default().kind(), RayKind::Indirect);
+}
+
+#[test]
+fn hidden_cards_light_without_being_seen() {
+    // A bright card hidden from the camera, beside a grey ball it lights.
+    let mut world = HittableList::new();
+    world.add(Arc::new(Masked::new(
+        Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0))),
+        )),
+        Visibility::default().with_camera(false),
+    )));
+    world.add(grey_ball(Point3::new(2.5, 0.0, 0.0), 1.0));
+    world.set_background(Arc::new(Constant::new(Color::default())));
+
+    let mut rng = StdRng::seed_from_u64(6);
+    let at_card = toward_ball(RayKind::Camera);
+    let sample = trace_path(at_card, &world, &mut rng);
+    assert_eq!(sample.color, Color::default());
+    assert!(sample.primary.is_none());
+
+    // The side of the ball facing the card is lit by it.
+    let at_ball =
+        Ray::new(Point3::new(1.8, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)).with_kind(RayKind::Camera);
+    let lit = (0..64)
+        .map(|_| trace_path(at_ball, &world, &mut rng).color.g())
+        .sum::<f64>();
+    assert!(lit > 0.0);
+}
+
+#[test]
+fn visibility_round_trips_through_scene_files() {
+    let world = masked(Visibility::default().with_shadow(false));
+    let camera = CameraDesc {
+        look_from: Point3::new(0.0, 0.0, 5.0),
+        look_at: Point3::new(0.0, 0.0, 0.0),
+        ..Default::default()
+    };
+    let desc = SceneDesc::from_world(&world, camera).unwrap();
+    let desc = SceneDesc::from_json(&desc.to_json().unwrap()).unwrap();
+    assert!(matches!(
+        &desc.objects[0],
+        ObjectDesc::Masked {
+            visibility: Visibility {
+                camera: true,
+                shadow: false,
+                indirect: true,
+            },
+            ..
+        }
+    ));
+    let (built, _camera) = desc.build(1.0).unwrap();
+    let ray = toward_ball(RayKind::Shadow);
+    assert!(!built.is_occluded(&ray, Interval::new(1e-3, 5.0)));
+    assert!(built.is_occluded(&toward_ball(RayKind::Indirect), Interval::new(1e-3, 5.0)));
+}
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rtt::aabb::Aabb;
+use rtt::hittable::{Hittable, HittableList, Sphere};
+use rtt::interval::Interval;
+use rtt::material::Lambertian;
+use rtt::ray::Ray;
+use rtt::render::trace_path;
+use rtt::scene::{MaterialTable, ObjectDesc};
+use rtt::vec3::{Color, Point3, Vec3};
+use rtt::volume::{blackbody, Emission, Field, Volume};
+
+fn unit_sphere() -> Arc<dyn Hittable> {
+    Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    ))
+}
+
+#[test]
+fn blackbody_ramp_runs_from_red_to_blue() {
+    let white = blackbody(6500.0);
+    for c in [white.r(), white.g(), white.b()] {
+        assert!((c - 1.0).abs() < 1e-9, "{white:?}");
+    }
+    let ember = blackbody(1500.0);
+    assert_eq!(ember.r(), 1.0);
+    assert!(ember.r() > ember.g() && ember.g() > ember.b(), "{ember:?}");
+    let hot = blackbody(15000.0);
+    assert_eq!(hot.b(), 1.0);
+    assert!(hot.r() < hot.g(), "{hot:?}");
+    assert_eq!(blackbody(0.0), Color::default());
+}
+
+#[test]
+fn grid_interpolates_between_samples() {
+    let grid = Field::Grid {
+        bounds: Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0)),
+        resolution: [3, 2, 2],
+        values: (0..12).map(|i| i as f32).collect(),
+    };
+    assert_eq!(grid.value(Point3::new(0.0, 0.0, 0.0)), 0.0);
+    assert_eq!(grid.value(Point3::new(2.0, 1.0, 1.0)), 11.0);
+    assert_eq!(grid.value(Point3::new(1.0, 0.0, 0.0)), 1.0);
+    assert!((grid.value(Point3::new(0.5, 0.5, 0.5)) - 5.0).abs() < 1e-12);
+    assert_eq!(grid.value(Point3::new(2.5, 0.5, 0.5)), 0.0);
+    assert_eq!(grid.max_value(), 11.0);
+
+    let short = Field::Grid {
+        bounds: Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+        resolution: [2, 2, 2],
+        values: vec![1.0; 7],
+    };
+    assert!(Volume::new(unit_sphere(), short, Color::default()).is_err());
+}
+
+// A purely absorbing, uniformly hot medium seen through its center glows with the emitted
+// radiance times its opacity, 1 - exp(-density * 2).
+#[test]
+fn emission_matches_absorption() {
+    let density = 0.7;
+    let volume = Volume::new(
+        unit_sphere(),
+        Field::Constant { value: density },
+        Color::default(),
+    )
+    .unwrap()
+    .with_emission(Emission::new(Field::Constant { value: 1500.0 }, 3.0))
+    .unwrap();
+    let mut world = HittableList::new();
+    world.add(Arc::new(volume));
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let n = 40_000;
+    let mut sum = 0.0;
+    for _ in 0..n {
+        let sample = trace_path(ray, &world, &mut rng);
+        sum += sample.emission.iter().map(|(_, c)| c.r()).sum::<f64>();
+    }
+    let expected = 3.0 * (1.0 - (-density * 2.0f64).exp());
+    let mean = sum / n as f64;
+    assert!(
+        (mean - expected).abs() < 0.02 * expected,
+        "{mean} vs {expected}"
+    );
+}
+
+#[test]
+fn empty_regions_are_transparent() {
+    let volume = Volume::new(
+        unit_sphere(),
+        Field::Radial {
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: 0.5,
+            value: 100.0,
+        },
+        Color::new(0.9, 0.9, 0.9),
+    )
+    .unwrap();
+    let t = Interval::new(0.001, f64::INFINITY);
+    let grazing = Ray::new(Point3::new(0.0, 0.8, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let center = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    for _ in 0..100 {
+        assert!(volume.hit(&grazing, t).is_none());
+        let rec = volume.hit(&center, t).unwrap();
+        assert!(rec.point.z.abs() < 0.5);
+    }
+}
+
+#[test]
+fn field_bounds_stand_in_for_a_boundary() {
+    let field = Field::Radial {
+        center: Point3::new(2.0, 0.0, 0.0),
+        radius: 1.0,
+        value: 50.0,
+    };
+    let volume = Volume::from_field(field, Color::new(0.5, 0.5, 0.5)).unwrap();
+    assert!(volume.boundary().is_none());
+    let bbox = volume.bounding_box(0.0, 1.0).unwrap();
+    assert_eq!(bbox.min, Point3::new(1.0, -1.0, -1.0));
+
+    let t = Interval::new(0.001, f64::INFINITY);
+    let ray = Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(volume.hit(&ray, t).is_some());
+    let miss = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(volume.hit(&miss, t).is_none());
+
+    let desc = volume.to_desc(&mut MaterialTable::new()).unwrap();
+    assert!(matches!(desc, ObjectDesc::Volume { boundary: None, .. }));
+    assert!(desc.build(&[]).is_ok());
+
+    let unbounded = Field::Constant { value: 1.0 };
+    assert!(Volume::from_field(unbounded, Color::default()).is_err());
+}
+
+#[test]
+fn volume_round_trips_through_desc() {
+    let volume = Volume::new(
+        unit_sphere(),
+        Field::Constant { value: 2.0 },
+        Color::new(0.2, 0.2, 0.2),
+    )
+    .unwrap()
+    .with_emission(Emission::new(
+        Field::Radial {
+            center: Point3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            value: 2000.0,
+        },
+        5.0,
+    ))
+    .unwrap();
+    let mut materials = MaterialTable::new();
+    let desc = volume.to_desc(&mut materials).unwrap();
+    assert!(matches!(
+        desc,
+        ObjectDesc::Volume {
+            emission: Some(_),
+            ..
+        }
+    ));
+    let json = serde_json::to_string(&desc).unwrap();
+    assert_eq!(serde_json::from_str::<ObjectDesc>(&json).unwrap(), desc);
+
+    let built = desc
+        .build(&[Arc::new(Lambertian::new(Color::default()))])
+        .unwrap();
+    assert_eq!(built.to_desc(&mut MaterialTable::new()), Some(desc));
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "watch")]
+
+use std::time::Duration;
+
+use rtt::watch::SceneWatcher;
+
+fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rtt-watch-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn saving_the_scene_is_seen() {
+    let dir = scratch_dir("save");
+    let path = dir.join("scene.json");
+    std::fs::write(&path, "{}").unwrap();
+    let watcher = SceneWatcher::new(&path).unwrap();
+    assert!(!watcher.wait_timeout(Duration::from_millis(200)).unwrap());
+
+    std::fs::write(&path, "{\"objects\": []}").unwrap();
+    assert!(watcher.wait_timeout(Duration::from_secs(5)).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn replacing_the_scene_by_rename_is_seen() {
+    let dir = scratch_dir("rename");
+    let path = dir.join("scene.json");
+    std::fs::write(&path, "{}").unwrap();
+    let watcher = SceneWatcher::new(&path).unwrap();
+
+    let temp = dir.join("scene.json.tmp");
+    std::fs::write(&temp, "{\"objects\": []}").unwrap();
+    std::fs::rename(&temp, &path).unwrap();
+    assert!(watcher.wait_timeout(Duration::from_secs(5)).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn other_files_are_ignored() {
+    let dir = scratch_dir("other");
+    let path = dir.join("scene.json");
+    std::fs::write(&path, "{}").unwrap();
+    let watcher = SceneWatcher::new(&path).unwrap();
+
+    std::fs::write(dir.join("notes.txt"), "unrelated").unwrap();
+    assert!(!watcher.wait_timeout(Duration::from_millis(500)).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}